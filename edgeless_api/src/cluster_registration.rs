@@ -0,0 +1,65 @@
+// SPDX-FileCopyrightText: © 2024 Technical University of Munich, Chair of Connected Mobility
+// SPDX-License-Identifier: MIT
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClusterAnnouncement {
+    pub cluster_id: uuid::Uuid,
+    // Name of the cluster, which may be used instead of the identifier
+    // to refer to it.
+    pub domain_id: String,
+    pub controller_url: String,
+    pub link_providers: Vec<crate::node_registration::LinkProviderSpecification>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum UpdateClusterRequest {
+    Announcement(ClusterAnnouncement),
+
+    // 0: cluster_id (cannot be empty)
+    Deregistration(uuid::Uuid),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum UpdateClusterResponse {
+    ResponseError(crate::common::ResponseError),
+    Accepted,
+}
+
+#[async_trait::async_trait]
+pub trait ClusterRegistrationAPI: ClusterRegistrationAPIClone + Sync + Send {
+    async fn update_cluster(&mut self, request: UpdateClusterRequest) -> anyhow::Result<UpdateClusterResponse>;
+    /// Rejected if the cluster sending the heartbeat is not known, in which
+    /// case it must be announced again.
+    async fn heartbeat(&mut self, cluster_id: uuid::Uuid) -> anyhow::Result<UpdateClusterResponse>;
+}
+
+impl std::fmt::Display for ClusterAnnouncement {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "cluster_id {}, domain_id {}, controller_url {}, link types [{}]",
+            self.cluster_id,
+            self.domain_id,
+            self.controller_url,
+            self.link_providers.iter().map(|p| p.class.0.clone()).collect::<Vec<String>>().join(","),
+        )
+    }
+}
+
+// https://stackoverflow.com/a/30353928
+pub trait ClusterRegistrationAPIClone {
+    fn clone_box(&self) -> Box<dyn ClusterRegistrationAPI>;
+}
+impl<T> ClusterRegistrationAPIClone for T
+where
+    T: 'static + ClusterRegistrationAPI + Clone,
+{
+    fn clone_box(&self) -> Box<dyn ClusterRegistrationAPI> {
+        Box::new(self.clone())
+    }
+}
+impl Clone for Box<dyn ClusterRegistrationAPI> {
+    fn clone(&self) -> Box<dyn ClusterRegistrationAPI> {
+        self.clone_box()
+    }
+}
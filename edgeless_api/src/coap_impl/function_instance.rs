@@ -15,4 +15,7 @@ impl crate::function_instance::FunctionInstanceAPI<edgeless_api_core::instance_i
     async fn patch(&mut self, _update: crate::common::PatchRequest) -> anyhow::Result<()> {
         todo!()
     }
+    async fn get_state(&mut self, _id: edgeless_api_core::instance_id::InstanceId) -> anyhow::Result<Option<String>> {
+        todo!()
+    }
 }
@@ -37,13 +37,13 @@ impl CoapInvocationServer {
                             stream_id: invocation_event.stream_id,
                             data: match invocation_event.data {
                                 edgeless_api_core::invocation::EventData::Cast(val) => {
-                                    crate::invocation::EventData::Cast(String::from_utf8(val.to_vec()).unwrap())
+                                    crate::invocation::EventData::Cast(val.to_vec())
                                 }
                                 edgeless_api_core::invocation::EventData::Call(val) => {
-                                    crate::invocation::EventData::Call(String::from_utf8(val.to_vec()).unwrap())
+                                    crate::invocation::EventData::Call(val.to_vec())
                                 }
                                 edgeless_api_core::invocation::EventData::CallRet(val) => {
-                                    crate::invocation::EventData::CallRet(String::from_utf8(val.to_vec()).unwrap())
+                                    crate::invocation::EventData::CallRet(val.to_vec())
                                 }
                                 edgeless_api_core::invocation::EventData::CallNoRet => crate::invocation::EventData::CallNoRet,
                                 edgeless_api_core::invocation::EventData::Err => crate::invocation::EventData::Err,
@@ -93,9 +93,9 @@ impl crate::invocation::InvocationAPI for super::CoapClient {
             target_port: edgeless_api_core::port::Port(heapless::String::from_str(&event.target_port.0).unwrap()),
             stream_id: event.stream_id,
             data: match &event.data {
-                crate::invocation::EventData::Cast(val) => edgeless_api_core::invocation::EventData::Cast(val.as_bytes()),
-                crate::invocation::EventData::Call(val) => edgeless_api_core::invocation::EventData::Call(val.as_bytes()),
-                crate::invocation::EventData::CallRet(val) => edgeless_api_core::invocation::EventData::CallRet(val.as_bytes()),
+                crate::invocation::EventData::Cast(val) => edgeless_api_core::invocation::EventData::Cast(val.as_slice()),
+                crate::invocation::EventData::Call(val) => edgeless_api_core::invocation::EventData::Call(val.as_slice()),
+                crate::invocation::EventData::CallRet(val) => edgeless_api_core::invocation::EventData::CallRet(val.as_slice()),
                 crate::invocation::EventData::CallNoRet => edgeless_api_core::invocation::EventData::CallNoRet,
                 crate::invocation::EventData::Err => edgeless_api_core::invocation::EventData::Err,
             },
@@ -7,7 +7,8 @@ use crate::node_management::HealthStatus;
 impl crate::node_management::NodeManagementAPI for super::CoapClient {
     async fn update_peers(&mut self, request: crate::node_management::UpdatePeersRequest) -> anyhow::Result<()> {
         match request {
-            crate::node_management::UpdatePeersRequest::Add(id, url) => {
+            // Embedded peers do not support QUIC.
+            crate::node_management::UpdatePeersRequest::Add(id, url, _) => {
                 let (_, ip, port) = crate::util::parse_http_host(&url).unwrap();
                 let ip: std::net::Ipv4Addr = ip.parse().unwrap();
                 let ip_bytes: [u8; 4] = ip.octets();
@@ -67,6 +67,7 @@ impl CoapOrchestrationServer {
                 .collect(),
             crate::node_registration::NodeCapabilities::empty(),
             Vec::new(),
+            None,
         );
 
         let ret = match key_entry {
@@ -14,7 +14,7 @@ pub enum StartComponentResponse<InstanceIdType> {
     InstanceId(InstanceIdType),
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub enum Output {
     Single(edgeless_api_core::instance_id::InstanceId, crate::function_instance::PortId),
     Any(Vec<(edgeless_api_core::instance_id::InstanceId, crate::function_instance::PortId)>),
@@ -22,7 +22,7 @@ pub enum Output {
     Link(crate::link::LinkInstanceId),
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub enum Input {
     Link(crate::link::LinkInstanceId),
 }
@@ -3,4 +3,6 @@
 pub trait ControllerAPI: Sync {
     fn workflow_instance_api(&mut self) -> Box<dyn crate::workflow_instance::WorkflowInstanceAPI>;
     fn node_registration_api(&mut self) -> Box<dyn crate::node_registration::NodeRegistrationAPI>;
+    fn cluster_registration_api(&mut self) -> Box<dyn crate::cluster_registration::ClusterRegistrationAPI>;
+    fn node_maintenance_api(&mut self) -> Box<dyn crate::node_maintenance::NodeMaintenanceAPI>;
 }
@@ -15,6 +15,9 @@ pub enum StatePolicy {
 pub struct StateSpecification {
     pub state_id: uuid::Uuid,
     pub state_policy: StatePolicy,
+    /// Serialized state the instance starts with, overriding the one stored
+    /// under `state_id`. Used when an instance is migrated between nodes.
+    pub initial_state: Option<String>,
 }
 
 impl Default for StateSpecification {
@@ -22,6 +25,7 @@ impl Default for StateSpecification {
         Self {
             state_id: uuid::Uuid::nil(),
             state_policy: StatePolicy::NodeLocal,
+            initial_state: None,
         }
     }
 }
@@ -104,6 +108,9 @@ pub trait FunctionInstanceAPI<FunctionIdType: Clone>: FunctionInstanceAPIClone<F
     async fn start(&mut self, spawn_request: SpawnFunctionRequest) -> anyhow::Result<crate::common::StartComponentResponse<FunctionIdType>>;
     async fn stop(&mut self, id: FunctionIdType) -> anyhow::Result<()>;
     async fn patch(&mut self, update: crate::common::PatchRequest) -> anyhow::Result<()>;
+    /// Get the state last synced by the instance, None if it has never
+    /// synced its state.
+    async fn get_state(&mut self, id: FunctionIdType) -> anyhow::Result<Option<String>>;
 }
 
 // https://stackoverflow.com/a/30353928
@@ -0,0 +1,243 @@
+// SPDX-FileCopyrightText: © 2024 Technical University of Munich, Chair of Connected Mobility
+// SPDX-License-Identifier: MIT
+use std::str::FromStr;
+
+#[derive(Clone)]
+pub struct ClusterRegistrationClient {
+    client: crate::grpc_impl::api::cluster_registration_client::ClusterRegistrationClient<tonic::transport::Channel>,
+}
+
+pub struct ClusterRegistrationAPIService {
+    pub cluster_registration_api: tokio::sync::Mutex<Box<dyn crate::cluster_registration::ClusterRegistrationAPI>>,
+}
+
+impl ClusterRegistrationClient {
+    pub async fn new(server_addr: &str, retry_interval: Option<u64>) -> anyhow::Result<Self> {
+        loop {
+            match crate::grpc_impl::api::cluster_registration_client::ClusterRegistrationClient::connect(server_addr.to_string()).await {
+                Ok(client) => {
+                    let client = client.max_decoding_message_size(usize::MAX);
+                    return Ok(Self { client });
+                }
+                Err(err) => match retry_interval {
+                    Some(val) => tokio::time::sleep(tokio::time::Duration::from_secs(val)).await,
+                    None => {
+                        return Err(anyhow::anyhow!("Error when connecting to {}: {}", server_addr, err));
+                    }
+                },
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::cluster_registration::ClusterRegistrationAPI for ClusterRegistrationClient {
+    async fn update_cluster(
+        &mut self,
+        request: crate::cluster_registration::UpdateClusterRequest,
+    ) -> anyhow::Result<crate::cluster_registration::UpdateClusterResponse> {
+        match self
+            .client
+            .update_cluster(tonic::Request::new(serialize_update_cluster_request(&request)))
+            .await
+        {
+            Ok(res) => parse_update_cluster_response(&res.into_inner()),
+            Err(err) => Err(anyhow::anyhow!("Communication error while updating a cluster: {}", err)),
+        }
+    }
+
+    async fn heartbeat(&mut self, cluster_id: uuid::Uuid) -> anyhow::Result<crate::cluster_registration::UpdateClusterResponse> {
+        match self
+            .client
+            .heartbeat(tonic::Request::new(crate::grpc_impl::api::ClusterHeartbeat {
+                cluster_id: cluster_id.to_string(),
+            }))
+            .await
+        {
+            Ok(res) => parse_update_cluster_response(&res.into_inner()),
+            Err(err) => Err(anyhow::anyhow!("Communication error while sending a heartbeat: {}", err)),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::grpc_impl::api::cluster_registration_server::ClusterRegistration for ClusterRegistrationAPIService {
+    async fn update_cluster(
+        &self,
+        request: tonic::Request<crate::grpc_impl::api::UpdateClusterRequest>,
+    ) -> Result<tonic::Response<crate::grpc_impl::api::UpdateClusterResponse>, tonic::Status> {
+        let parsed_request = match parse_update_cluster_request(&request.into_inner()) {
+            Ok(parsed_request) => parsed_request,
+            Err(err) => {
+                log::error!("Parse UpdateClusterRequest Failed: {}", err);
+                return Err(tonic::Status::invalid_argument(format!(
+                    "Error when parsing an UpdateClusterRequest message: {}",
+                    err
+                )));
+            }
+        };
+        match self.cluster_registration_api.lock().await.update_cluster(parsed_request).await {
+            Ok(res) => Ok(tonic::Response::new(serialize_update_cluster_response(&res))),
+            Err(err) => Err(tonic::Status::internal(format!("Error when updating a cluster: {}", err))),
+        }
+    }
+
+    async fn heartbeat(
+        &self,
+        request: tonic::Request<crate::grpc_impl::api::ClusterHeartbeat>,
+    ) -> Result<tonic::Response<crate::grpc_impl::api::UpdateClusterResponse>, tonic::Status> {
+        let cluster_id = match uuid::Uuid::from_str(request.into_inner().cluster_id.as_str()) {
+            Ok(cluster_id) => cluster_id,
+            Err(err) => {
+                return Err(tonic::Status::invalid_argument(format!(
+                    "Ill-formed cluster_id field in ClusterHeartbeat message: {}",
+                    err
+                )));
+            }
+        };
+        match self.cluster_registration_api.lock().await.heartbeat(cluster_id).await {
+            Ok(res) => Ok(tonic::Response::new(serialize_update_cluster_response(&res))),
+            Err(err) => Err(tonic::Status::internal(format!("Error when handling a heartbeat: {}", err))),
+        }
+    }
+}
+
+fn parse_update_cluster_request(
+    api_instance: &crate::grpc_impl::api::UpdateClusterRequest,
+) -> anyhow::Result<crate::cluster_registration::UpdateClusterRequest> {
+    let cluster_id = match uuid::Uuid::from_str(api_instance.cluster_id.as_str()) {
+        Ok(cluster_id) => cluster_id,
+        Err(err) => return Err(anyhow::anyhow!("Ill-formed cluster_id field in UpdateClusterRequest message: {}", err)),
+    };
+    match api_instance.request_type {
+        x if x == crate::grpc_impl::api::UpdateClusterRequestType::Announce as i32 => {
+            let controller_url = match api_instance.controller_url.as_ref() {
+                Some(controller_url) if !controller_url.is_empty() => controller_url.clone(),
+                _ => {
+                    return Err(anyhow::anyhow!(
+                        "Ill-formed UpdateClusterRequest message: controller URL not present in announcement"
+                    ))
+                }
+            };
+            let mut link_providers = vec![];
+            for link_provider in &api_instance.link_providers {
+                match crate::node_registration::LinkProviderSpecification::try_from(link_provider.clone()) {
+                    Ok(val) => link_providers.push(val),
+                    Err(err) => {
+                        return Err(anyhow::anyhow!("Ill-formed link provider in UpdateClusterRequest message: {}", err));
+                    }
+                }
+            }
+            Ok(crate::cluster_registration::UpdateClusterRequest::Announcement(
+                crate::cluster_registration::ClusterAnnouncement {
+                    cluster_id,
+                    domain_id: api_instance.domain_id.clone().unwrap_or_default(),
+                    controller_url,
+                    link_providers,
+                },
+            ))
+        }
+        x if x == crate::grpc_impl::api::UpdateClusterRequestType::Leave as i32 => {
+            Ok(crate::cluster_registration::UpdateClusterRequest::Deregistration(cluster_id))
+        }
+        x => Err(anyhow::anyhow!("Ill-formed UpdateClusterRequest message: unknown type {}", x)),
+    }
+}
+
+fn serialize_update_cluster_request(req: &crate::cluster_registration::UpdateClusterRequest) -> crate::grpc_impl::api::UpdateClusterRequest {
+    match req {
+        crate::cluster_registration::UpdateClusterRequest::Announcement(announcement) => crate::grpc_impl::api::UpdateClusterRequest {
+            request_type: crate::grpc_impl::api::UpdateClusterRequestType::Announce as i32,
+            cluster_id: announcement.cluster_id.to_string(),
+            domain_id: Some(announcement.domain_id.clone()),
+            controller_url: Some(announcement.controller_url.clone()),
+            link_providers: announcement.link_providers.iter().map(|p| p.clone().into()).collect(),
+        },
+        crate::cluster_registration::UpdateClusterRequest::Deregistration(cluster_id) => crate::grpc_impl::api::UpdateClusterRequest {
+            request_type: crate::grpc_impl::api::UpdateClusterRequestType::Leave as i32,
+            cluster_id: cluster_id.to_string(),
+            domain_id: None,
+            controller_url: None,
+            link_providers: Vec::new(),
+        },
+    }
+}
+
+fn serialize_update_cluster_response(req: &crate::cluster_registration::UpdateClusterResponse) -> crate::grpc_impl::api::UpdateClusterResponse {
+    match req {
+        crate::cluster_registration::UpdateClusterResponse::ResponseError(err) => crate::grpc_impl::api::UpdateClusterResponse {
+            response_error: Some(crate::grpc_impl::api::ResponseError {
+                summary: err.summary.clone(),
+                detail: err.detail.clone(),
+            }),
+        },
+        crate::cluster_registration::UpdateClusterResponse::Accepted => crate::grpc_impl::api::UpdateClusterResponse { response_error: None },
+    }
+}
+
+fn parse_update_cluster_response(
+    api_instance: &crate::grpc_impl::api::UpdateClusterResponse,
+) -> anyhow::Result<crate::cluster_registration::UpdateClusterResponse> {
+    match api_instance.response_error.as_ref() {
+        Some(err) => Ok(crate::cluster_registration::UpdateClusterResponse::ResponseError(
+            crate::common::ResponseError {
+                summary: err.summary.clone(),
+                detail: err.detail.clone(),
+            },
+        )),
+        None => Ok(crate::cluster_registration::UpdateClusterResponse::Accepted),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cluster_registration::ClusterAnnouncement;
+    use crate::cluster_registration::UpdateClusterRequest;
+    use crate::cluster_registration::UpdateClusterResponse;
+
+    #[test]
+    fn serialize_deserialize_update_cluster_request() {
+        let messages = vec![
+            UpdateClusterRequest::Announcement(ClusterAnnouncement {
+                cluster_id: uuid::Uuid::new_v4(),
+                domain_id: "domain-1".to_string(),
+                controller_url: "http://127.0.0.1:7001".to_string(),
+                link_providers: vec![crate::node_registration::LinkProviderSpecification {
+                    provider_id: crate::link::LinkProviderId(uuid::Uuid::new_v4()),
+                    class: crate::link::LinkType("MULTICAST".to_string()),
+                }],
+            }),
+            UpdateClusterRequest::Announcement(ClusterAnnouncement {
+                cluster_id: uuid::Uuid::new_v4(),
+                domain_id: "".to_string(),
+                controller_url: "http://127.0.0.1:7001".to_string(),
+                link_providers: vec![],
+            }),
+            UpdateClusterRequest::Deregistration(uuid::Uuid::new_v4()),
+        ];
+        for msg in messages {
+            match parse_update_cluster_request(&serialize_update_cluster_request(&msg)) {
+                Ok(val) => assert_eq!(msg, val),
+                Err(err) => panic!("{}", err),
+            }
+        }
+    }
+
+    #[test]
+    fn serialize_deserialize_update_cluster_response() {
+        let messages = vec![
+            UpdateClusterResponse::ResponseError(crate::common::ResponseError {
+                summary: "error summary".to_string(),
+                detail: Some("error details".to_string()),
+            }),
+            UpdateClusterResponse::Accepted,
+        ];
+        for msg in messages {
+            match parse_update_cluster_response(&serialize_update_cluster_response(&msg)) {
+                Ok(val) => assert_eq!(msg, val),
+                Err(err) => panic!("{}", err),
+            }
+        }
+    }
+}
@@ -4,6 +4,8 @@
 pub struct ControllerAPIClient {
     workflow_instance_client: Box<dyn crate::workflow_instance::WorkflowInstanceAPI>,
     node_registration_client: Box<dyn crate::node_registration::NodeRegistrationAPI>,
+    cluster_registration_client: Box<dyn crate::cluster_registration::ClusterRegistrationAPI>,
+    node_maintenance_client: Box<dyn crate::node_maintenance::NodeMaintenanceAPI>,
 }
 
 impl ControllerAPIClient {
@@ -15,6 +17,16 @@ impl ControllerAPIClient {
                     .await
                     .unwrap(),
             ),
+            cluster_registration_client: Box::new(
+                crate::grpc_impl::cluster_registration::ClusterRegistrationClient::new(api_addr, Some(5))
+                    .await
+                    .unwrap(),
+            ),
+            node_maintenance_client: Box::new(
+                crate::grpc_impl::node_maintenance::NodeMaintenanceClient::new(api_addr, Some(5))
+                    .await
+                    .unwrap(),
+            ),
         }
     }
 }
@@ -27,6 +39,14 @@ impl crate::controller::ControllerAPI for ControllerAPIClient {
     fn node_registration_api(&mut self) -> Box<dyn crate::node_registration::NodeRegistrationAPI> {
         self.node_registration_client.clone()
     }
+
+    fn cluster_registration_api(&mut self) -> Box<dyn crate::cluster_registration::ClusterRegistrationAPI> {
+        self.cluster_registration_client.clone()
+    }
+
+    fn node_maintenance_api(&mut self) -> Box<dyn crate::node_maintenance::NodeMaintenanceAPI> {
+        self.node_maintenance_client.clone()
+    }
 }
 
 pub struct WorkflowInstanceAPIServer {}
@@ -40,6 +60,12 @@ impl WorkflowInstanceAPIServer {
         let node_registration_api = crate::grpc_impl::node_registration::NodeRegistrationAPIService {
             node_registration_api: tokio::sync::Mutex::new(controller_api.node_registration_api()),
         };
+        let cluster_registration_api = crate::grpc_impl::cluster_registration::ClusterRegistrationAPIService {
+            cluster_registration_api: tokio::sync::Mutex::new(controller_api.cluster_registration_api()),
+        };
+        let node_maintenance_api = crate::grpc_impl::node_maintenance::NodeMaintenanceAPIService {
+            node_maintenance_api: tokio::sync::Mutex::new(controller_api.node_maintenance_api()),
+        };
         Box::pin(async move {
             let workflow_api = workflow_api;
             if let Ok((_proto, host, port)) = crate::util::parse_http_host(&controller_url) {
@@ -55,6 +81,14 @@ impl WorkflowInstanceAPIServer {
                             crate::grpc_impl::api::node_registration_server::NodeRegistrationServer::new(node_registration_api)
                                 .max_decoding_message_size(usize::MAX),
                         )
+                        .add_service(
+                            crate::grpc_impl::api::cluster_registration_server::ClusterRegistrationServer::new(cluster_registration_api)
+                                .max_decoding_message_size(usize::MAX),
+                        )
+                        .add_service(
+                            crate::grpc_impl::api::node_maintenance_server::NodeMaintenanceServer::new(node_maintenance_api)
+                                .max_decoding_message_size(usize::MAX),
+                        )
                         .serve(host)
                         .await
                     {
@@ -105,6 +105,7 @@ impl FunctonInstanceConverters {
                 2 => crate::function_instance::StatePolicy::Global,
                 _ => crate::function_instance::StatePolicy::Transient,
             },
+            initial_state: api_spec.initial_state.clone(),
         })
     }
 
@@ -200,6 +201,7 @@ impl FunctonInstanceConverters {
                 crate::function_instance::StatePolicy::Global => crate::grpc_impl::api::StatePolicy::Global as i32,
                 crate::function_instance::StatePolicy::NodeLocal => crate::grpc_impl::api::StatePolicy::NodeLocal as i32,
             },
+            initial_state: crate_spec.initial_state.clone(),
         }
     }
 
@@ -294,6 +296,20 @@ where
             )),
         }
     }
+
+    async fn get_state(&mut self, id: FunctionIdType) -> anyhow::Result<Option<String>> {
+        match self
+            .client
+            .get_state(tonic::Request::new(super::common::SerializeableId::serialize(&id)))
+            .await
+        {
+            Ok(res) => Ok(res.into_inner().serialized_state),
+            Err(err) => Err(anyhow::anyhow!(
+                "Communication error while getting the state of a function instance: {}",
+                err
+            )),
+        }
+    }
 }
 pub struct FunctionInstanceAPIServer<FunctionIdType> {
     pub root_api: tokio::sync::Mutex<Box<dyn crate::function_instance::FunctionInstanceAPI<FunctionIdType>>>,
@@ -372,6 +388,29 @@ where
             ))),
         }
     }
+
+    async fn get_state(
+        &self,
+        request: tonic::Request<super::api::InstanceIdVariant>,
+    ) -> Result<tonic::Response<crate::grpc_impl::api::InstanceState>, tonic::Status> {
+        let function_id = match crate::grpc_impl::common::ParseableId::<FunctionIdType>::parse(&request.into_inner()) {
+            Ok(function_id) => function_id,
+            Err(err) => {
+                log::error!("Error when getting the state of a function instance: {}", err);
+                return Err(tonic::Status::invalid_argument(format!(
+                    "Error when getting the state of a function instance: {}",
+                    err
+                )));
+            }
+        };
+        match self.root_api.lock().await.get_state(function_id).await {
+            Ok(serialized_state) => Ok(tonic::Response::new(crate::grpc_impl::api::InstanceState { serialized_state })),
+            Err(err) => Err(tonic::Status::internal(format!(
+                "Error when getting the state of a function instance: {}",
+                err
+            ))),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -452,6 +491,7 @@ mod tests {
             state_specification: StateSpecification {
                 state_id: uuid::Uuid::new_v4(),
                 state_policy: StatePolicy::NodeLocal,
+                initial_state: Some("{\"count\":42}".to_string()),
             },
         }];
         for msg in messages {
@@ -226,6 +226,7 @@ pub fn parse_output_event_data(api_instance: &crate::grpc_impl::api::OutputEvent
         },
         alias: api_instance.alias.clone(),
         msg: api_instance.msg.clone(),
+        partition_key: api_instance.partition_key.clone(),
     })
 }
 
@@ -308,6 +309,7 @@ fn serialize_output_event_data(event: &crate::guest_api_host::OutputEventData) -
         originator: Some(crate::grpc_impl::common::CommonConverters::serialize_instance_id(&event.originator)),
         alias: event.alias.clone(),
         msg: event.msg.clone(),
+        partition_key: event.partition_key.clone(),
     }
 }
 
@@ -368,11 +370,19 @@ mod test {
                 originator: edgeless_api_core::instance_id::InstanceId::new(uuid::Uuid::new_v4()),
                 alias: "".to_string(),
                 msg: vec![],
+                partition_key: None,
             },
             OutputEventData {
                 originator: edgeless_api_core::instance_id::InstanceId::new(uuid::Uuid::new_v4()),
                 alias: "my-fun".to_string(),
                 msg: vec![0, 42, 0, 42, 99],
+                partition_key: None,
+            },
+            OutputEventData {
+                originator: edgeless_api_core::instance_id::InstanceId::new(uuid::Uuid::new_v4()),
+                alias: "cart".to_string(),
+                msg: vec![1, 2, 3],
+                partition_key: Some(b"user-42".to_vec()),
             },
         ];
         for msg in messages {
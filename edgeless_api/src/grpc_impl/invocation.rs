@@ -3,15 +3,24 @@
 // SPDX-License-Identifier: MIT
 use super::common::CommonConverters;
 
-struct InvocationConverters {}
+pub(crate) struct InvocationConverters {}
 
 const TYPE_CALL: i32 = crate::grpc_impl::api::EventType::Call as i32;
 const TYPE_CAST: i32 = crate::grpc_impl::api::EventType::Cast as i32;
 const TYPE_CALL_RET: i32 = crate::grpc_impl::api::EventType::CallRet as i32;
 const TYPE_CALL_NO_RET: i32 = crate::grpc_impl::api::EventType::CallNoRet as i32;
 
+/// Maximum number of events sent together on an invocation stream.
+const MAX_BATCH_SIZE: usize = 256;
+
+/// Event waiting to be sent on an invocation stream, with the channel to report the outcome of handling it.
+type QueuedEvent = (
+    crate::grpc_impl::api::Event,
+    tokio::sync::oneshot::Sender<anyhow::Result<crate::invocation::LinkProcessingResult>>,
+);
+
 impl InvocationConverters {
-    fn parse_api_event(api_event: &crate::grpc_impl::api::Event) -> anyhow::Result<crate::invocation::Event> {
+    pub(crate) fn parse_api_event(api_event: &crate::grpc_impl::api::Event) -> anyhow::Result<crate::invocation::Event> {
         Ok(crate::invocation::Event {
             target: CommonConverters::parse_instance_id(api_event.target.as_ref().unwrap())?,
             source: CommonConverters::parse_instance_id(api_event.source.as_ref().unwrap())?,
@@ -24,15 +33,15 @@ impl InvocationConverters {
 
     fn parse_api_event_data(api_event_data: &crate::grpc_impl::api::EventData) -> anyhow::Result<crate::invocation::EventData> {
         match api_event_data.event_type {
-            TYPE_CALL => Ok(crate::invocation::EventData::Call(api_event_data.payload.to_string())),
-            TYPE_CAST => Ok(crate::invocation::EventData::Cast(api_event_data.payload.to_string())),
-            TYPE_CALL_RET => Ok(crate::invocation::EventData::CallRet(api_event_data.payload.to_string())),
+            TYPE_CALL => Ok(crate::invocation::EventData::Call(api_event_data.payload.clone())),
+            TYPE_CAST => Ok(crate::invocation::EventData::Cast(api_event_data.payload.clone())),
+            TYPE_CALL_RET => Ok(crate::invocation::EventData::CallRet(api_event_data.payload.clone())),
             TYPE_CALL_NO_RET => Ok(crate::invocation::EventData::CallNoRet),
             _ => Ok(crate::invocation::EventData::Err),
         }
     }
 
-    fn encode_crate_event(crate_event: &crate::invocation::Event) -> crate::grpc_impl::api::Event {
+    pub(crate) fn encode_crate_event(crate_event: &crate::invocation::Event) -> crate::grpc_impl::api::Event {
         crate::grpc_impl::api::Event {
             target: Some(CommonConverters::serialize_instance_id(&crate_event.target)),
             source: Some(CommonConverters::serialize_instance_id(&crate_event.source)),
@@ -44,18 +53,18 @@ impl InvocationConverters {
     }
 
     fn encode_crate_event_data(crate_event: &crate::invocation::EventData) -> crate::grpc_impl::api::EventData {
-        let mut payload_buffer = "".to_string();
+        let mut payload_buffer = vec![];
         let event = match crate_event {
             crate::invocation::EventData::Call(payload) => {
-                payload_buffer = payload.to_string();
+                payload_buffer = payload.clone();
                 crate::grpc_impl::api::EventType::Call
             }
             crate::invocation::EventData::Cast(payload) => {
-                payload_buffer = payload.to_string();
+                payload_buffer = payload.clone();
                 crate::grpc_impl::api::EventType::Cast
             }
             crate::invocation::EventData::CallRet(payload) => {
-                payload_buffer = payload.to_string();
+                payload_buffer = payload.clone();
                 crate::grpc_impl::api::EventType::CallRet
             }
             crate::invocation::EventData::CallNoRet => crate::grpc_impl::api::EventType::CallNoRet,
@@ -88,8 +97,13 @@ impl TryInto<opentelemetry::trace::SpanContext> for &crate::grpc_impl::api::Span
     }
 }
 
+/// Client of the InvocationAPI of a peer. If the peer supports it, the events
+/// are sent in batches on a long-lived stream, without waiting for the previous
+/// ones to be handled; otherwise, each event is sent with its own request.
 pub struct InvocationAPIClient {
     client: crate::grpc_impl::api::function_invocation_client::FunctionInvocationClient<tonic::transport::Channel>,
+    stream: Option<tokio::sync::mpsc::UnboundedSender<QueuedEvent>>,
+    stream_supported: bool,
 }
 
 impl InvocationAPIClient {
@@ -98,7 +112,13 @@ impl InvocationAPIClient {
             match crate::grpc_impl::api::function_invocation_client::FunctionInvocationClient::connect(server_addr.to_string()).await {
                 Ok(client) => {
                     let client = client.max_decoding_message_size(usize::MAX);
-                    return Self { client };
+                    let mut client = Self {
+                        client,
+                        stream: None,
+                        stream_supported: true,
+                    };
+                    client.open_stream().await;
+                    return client;
                 }
                 Err(_) => {
                     log::debug!("Waiting for InvocationAPI");
@@ -107,22 +127,120 @@ impl InvocationAPIClient {
             }
         }
     }
+
+    async fn open_stream(&mut self) {
+        let (batch_sender, batch_receiver) = futures::channel::mpsc::unbounded();
+        match self.client.handle_stream(tonic::Request::new(batch_receiver)).await {
+            Ok(results) => {
+                let (event_sender, event_receiver) = tokio::sync::mpsc::unbounded_channel();
+                tokio::spawn(Self::run_stream(event_receiver, batch_sender, results.into_inner()));
+                self.stream = Some(event_sender);
+            }
+            Err(status) if status.code() == tonic::Code::Unimplemented => {
+                log::info!("InvocationAPI server without streams, sending one event per request");
+                self.stream_supported = false;
+                self.stream = None;
+            }
+            Err(status) => {
+                log::warn!("Could not open the invocation stream: {}", status);
+                self.stream = None;
+            }
+        }
+    }
+
+    /// Send the queued events in batches and report the outcomes received.
+    async fn run_stream(
+        mut events: tokio::sync::mpsc::UnboundedReceiver<QueuedEvent>,
+        batches: futures::channel::mpsc::UnboundedSender<crate::grpc_impl::api::EventBatch>,
+        mut results: tonic::Streaming<crate::grpc_impl::api::EventBatchResult>,
+    ) {
+        let mut next_batch_id = 0;
+        let mut pending = std::collections::HashMap::new();
+        loop {
+            tokio::select! {
+                event = events.recv() => {
+                    let Some(event) = event else {
+                        break;
+                    };
+                    // Send along all the events queued in the meantime.
+                    let mut batch = vec![event];
+                    while batch.len() < MAX_BATCH_SIZE {
+                        match events.try_recv() {
+                            Ok(event) => batch.push(event),
+                            Err(_) => break,
+                        }
+                    }
+                    let (batch, outcomes): (Vec<_>, Vec<_>) = batch.into_iter().unzip();
+                    let batch = crate::grpc_impl::api::EventBatch {
+                        batch_id: next_batch_id,
+                        events: batch,
+                    };
+                    pending.insert(next_batch_id, outcomes);
+                    next_batch_id += 1;
+                    if batches.unbounded_send(batch).is_err() {
+                        break;
+                    }
+                }
+                result = results.message() => {
+                    match result {
+                        Ok(Some(result)) => {
+                            for (i, outcome) in pending.remove(&result.batch_id).unwrap_or_default().into_iter().enumerate() {
+                                let _ = outcome.send(if result.handled.get(i).copied().unwrap_or(false) {
+                                    Ok(crate::invocation::LinkProcessingResult::PROCESSED)
+                                } else {
+                                    Err(anyhow::anyhow!("Remote Event Request Failed"))
+                                });
+                            }
+                        }
+                        Ok(None) => break,
+                        Err(status) => {
+                            log::warn!("Invocation stream failure: {}", status);
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+
+        // The events not handled yet are reported as failed: the client opens a new stream for the next ones.
+        events.close();
+        let queued = std::iter::from_fn(|| events.try_recv().ok()).map(|(_, outcome)| outcome);
+        for outcome in pending.into_values().flatten().chain(queued) {
+            let _ = outcome.send(Err(anyhow::anyhow!("Invocation Stream Closed")));
+        }
+    }
 }
 
 #[async_trait::async_trait]
 impl crate::invocation::InvocationAPI for InvocationAPIClient {
     async fn handle(&mut self, event: crate::invocation::Event) -> anyhow::Result<crate::invocation::LinkProcessingResult> {
+        self.handle_pipelined(event).await.await
+    }
+
+    async fn handle_pipelined(&mut self, event: crate::invocation::Event) -> crate::invocation::PendingResult {
         let serialized_event = InvocationConverters::encode_crate_event(&event);
-        let res = self.client.handle(tonic::Request::new(serialized_event)).await;
-        match res {
+        if self.stream_supported && self.stream.as_ref().map_or(true, |stream| stream.is_closed()) {
+            self.open_stream().await;
+        }
+
+        if let Some(stream) = &self.stream {
+            let (outcome_sender, outcome_receiver) = tokio::sync::oneshot::channel();
+            if stream.send((serialized_event, outcome_sender)).is_err() {
+                return Box::pin(async { Err(anyhow::anyhow!("Invocation Stream Closed")) });
+            }
+            return Box::pin(async move { outcome_receiver.await.unwrap_or_else(|_| Err(anyhow::anyhow!("Invocation Stream Closed"))) });
+        }
+
+        let res = match self.client.handle(tonic::Request::new(serialized_event)).await {
             Ok(_) => Ok(crate::invocation::LinkProcessingResult::PROCESSED),
             Err(_) => Err(anyhow::anyhow!("Remote Event Request Failed")),
-        }
+        };
+        Box::pin(async move { res })
     }
 }
 
 pub struct InvocationAPIServerHandler {
-    pub root_api: tokio::sync::Mutex<Box<dyn crate::invocation::InvocationAPI>>,
+    pub root_api: std::sync::Arc<tokio::sync::Mutex<Box<dyn crate::invocation::InvocationAPI>>>,
 }
 
 #[async_trait::async_trait]
@@ -143,6 +261,52 @@ impl crate::grpc_impl::api::function_invocation_server::FunctionInvocation for I
             Err(_) => Err(tonic::Status::internal("Server Error")),
         }
     }
+
+    type HandleStreamStream =
+        std::pin::Pin<Box<dyn futures::Stream<Item = Result<crate::grpc_impl::api::EventBatchResult, tonic::Status>> + Send>>;
+
+    async fn handle_stream(
+        &self,
+        request: tonic::Request<tonic::Streaming<crate::grpc_impl::api::EventBatch>>,
+    ) -> Result<tonic::Response<Self::HandleStreamStream>, tonic::Status> {
+        let mut batches = request.into_inner();
+        let root_api = self.root_api.clone();
+        let (result_sender, result_receiver) = futures::channel::mpsc::unbounded();
+        tokio::spawn(async move {
+            loop {
+                let batch = match batches.message().await {
+                    Ok(Some(batch)) => batch,
+                    Ok(None) => break,
+                    Err(status) => {
+                        log::warn!("Invocation stream failure: {}", status);
+                        break;
+                    }
+                };
+
+                let mut handled = Vec::with_capacity(batch.events.len());
+                let mut root_api = root_api.lock().await;
+                for event in &batch.events {
+                    handled.push(match InvocationConverters::parse_api_event(event) {
+                        Ok(event) => root_api.handle(event).await.is_ok(),
+                        Err(err) => {
+                            log::error!("Parse Request Failed: {}", err);
+                            false
+                        }
+                    });
+                }
+                drop(root_api);
+
+                let result = crate::grpc_impl::api::EventBatchResult {
+                    batch_id: batch.batch_id,
+                    handled,
+                };
+                if result_sender.unbounded_send(Ok(result)).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(tonic::Response::new(Box::pin(result_receiver)))
+    }
 }
 
 pub struct InvocationAPIServer {}
@@ -151,7 +315,7 @@ impl InvocationAPIServer {
     pub fn run(data_plane: Box<dyn crate::invocation::InvocationAPI>, invocation_url: String) -> futures::future::BoxFuture<'static, ()> {
         let data_plane = data_plane;
         let function_api = crate::grpc_impl::invocation::InvocationAPIServerHandler {
-            root_api: tokio::sync::Mutex::new(data_plane),
+            root_api: std::sync::Arc::new(tokio::sync::Mutex::new(data_plane)),
         };
         Box::pin(async move {
             let function_api = function_api;
@@ -180,3 +344,82 @@ impl InvocationAPIServer {
         })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::invocation::EventData;
+
+    #[test]
+    fn serialize_deserialize_event_data() {
+        let messages = vec![
+            EventData::Call("{\"key\":\"value\"}".as_bytes().to_vec()),
+            // Not valid UTF-8.
+            EventData::Cast(vec![0xa1, 0x00, 0xff, 0xfe]),
+            EventData::CallRet(vec![]),
+            EventData::CallNoRet,
+            EventData::Err,
+        ];
+        for msg in messages {
+            match InvocationConverters::parse_api_event_data(&InvocationConverters::encode_crate_event_data(&msg)) {
+                Ok(val) => assert_eq!(msg, val),
+                Err(err) => panic!("{}", err),
+            }
+        }
+    }
+
+    struct MockInvocationAPI {
+        events: futures::channel::mpsc::UnboundedSender<crate::invocation::Event>,
+    }
+
+    #[async_trait::async_trait]
+    impl crate::invocation::InvocationAPI for MockInvocationAPI {
+        async fn handle(&mut self, event: crate::invocation::Event) -> anyhow::Result<crate::invocation::LinkProcessingResult> {
+            self.events.unbounded_send(event)?;
+            Ok(crate::invocation::LinkProcessingResult::FINAL)
+        }
+    }
+
+    #[tokio::test]
+    async fn stream_events_in_order() {
+        use crate::invocation::InvocationAPI;
+        use futures::StreamExt;
+
+        let (sender, mut receiver) = futures::channel::mpsc::unbounded();
+        let _server = tokio::spawn(InvocationAPIServer::run(
+            Box::new(MockInvocationAPI { events: sender }),
+            "http://127.0.0.1:7102".to_string(),
+        ));
+        let mut client = InvocationAPIClient::new("http://127.0.0.1:7102").await;
+        assert!(client.stream.is_some());
+
+        let event = |stream_id| crate::invocation::Event {
+            target: crate::function_instance::InstanceId::new(uuid::Uuid::new_v4()),
+            source: crate::function_instance::InstanceId::new(uuid::Uuid::new_v4()),
+            target_port: crate::function_instance::PortId("test".to_string()),
+            stream_id,
+            data: EventData::Cast(vec![0xa1, 0x00, 0xff, 0xfe]),
+            context: opentelemetry::trace::SpanContext::empty_context(),
+        };
+
+        // The events are sent before any of them is handled.
+        let mut pending = vec![];
+        for stream_id in 0..1000 {
+            pending.push(client.handle_pipelined(event(stream_id)).await);
+        }
+        for res in pending {
+            assert_eq!(res.await.unwrap(), crate::invocation::LinkProcessingResult::PROCESSED);
+        }
+        for stream_id in 0..1000 {
+            let received = receiver.next().await.unwrap();
+            assert_eq!(received.stream_id, stream_id);
+            assert_eq!(received.data, EventData::Cast(vec![0xa1, 0x00, 0xff, 0xfe]));
+        }
+
+        assert_eq!(
+            client.handle(event(1000)).await.unwrap(),
+            crate::invocation::LinkProcessingResult::PROCESSED
+        );
+        assert_eq!(receiver.next().await.unwrap().stream_id, 1000);
+    }
+}
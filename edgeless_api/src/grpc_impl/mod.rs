@@ -33,6 +33,10 @@ pub mod node_management;
 
 pub mod node_registration;
 
+pub mod cluster_registration;
+
+pub mod node_maintenance;
+
 pub mod link;
 
 pub mod proxy_instance;
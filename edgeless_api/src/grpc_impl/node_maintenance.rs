@@ -0,0 +1,169 @@
+// SPDX-FileCopyrightText: © 2024 Technical University of Munich, Chair of Connected Mobility
+// SPDX-License-Identifier: MIT
+use std::str::FromStr;
+
+#[derive(Clone)]
+pub struct NodeMaintenanceClient {
+    client: crate::grpc_impl::api::node_maintenance_client::NodeMaintenanceClient<tonic::transport::Channel>,
+}
+
+pub struct NodeMaintenanceAPIService {
+    pub node_maintenance_api: tokio::sync::Mutex<Box<dyn crate::node_maintenance::NodeMaintenanceAPI>>,
+}
+
+impl NodeMaintenanceClient {
+    pub async fn new(server_addr: &str, retry_interval: Option<u64>) -> anyhow::Result<Self> {
+        loop {
+            match crate::grpc_impl::api::node_maintenance_client::NodeMaintenanceClient::connect(server_addr.to_string()).await {
+                Ok(client) => {
+                    let client = client.max_decoding_message_size(usize::MAX);
+                    return Ok(Self { client });
+                }
+                Err(err) => match retry_interval {
+                    Some(val) => tokio::time::sleep(tokio::time::Duration::from_secs(val)).await,
+                    None => {
+                        return Err(anyhow::anyhow!("Error when connecting to {}: {}", server_addr, err));
+                    }
+                },
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::node_maintenance::NodeMaintenanceAPI for NodeMaintenanceClient {
+    async fn update_node_maintenance(
+        &mut self,
+        request: crate::node_maintenance::NodeMaintenanceRequest,
+    ) -> anyhow::Result<crate::node_maintenance::NodeMaintenanceResponse> {
+        match self
+            .client
+            .update_node_maintenance(tonic::Request::new(serialize_node_maintenance_request(&request)))
+            .await
+        {
+            Ok(res) => parse_node_maintenance_response(&res.into_inner()),
+            Err(err) => Err(anyhow::anyhow!("Communication error while updating the maintenance of a node: {}", err)),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::grpc_impl::api::node_maintenance_server::NodeMaintenance for NodeMaintenanceAPIService {
+    async fn update_node_maintenance(
+        &self,
+        request: tonic::Request<crate::grpc_impl::api::NodeMaintenanceRequest>,
+    ) -> Result<tonic::Response<crate::grpc_impl::api::NodeMaintenanceResponse>, tonic::Status> {
+        let parsed_request = match parse_node_maintenance_request(&request.into_inner()) {
+            Ok(parsed_request) => parsed_request,
+            Err(err) => {
+                log::error!("Parse NodeMaintenanceRequest Failed: {}", err);
+                return Err(tonic::Status::invalid_argument(format!(
+                    "Error when parsing a NodeMaintenanceRequest message: {}",
+                    err
+                )));
+            }
+        };
+        match self.node_maintenance_api.lock().await.update_node_maintenance(parsed_request).await {
+            Ok(res) => Ok(tonic::Response::new(serialize_node_maintenance_response(&res))),
+            Err(err) => Err(tonic::Status::internal(format!("Error when updating the maintenance of a node: {}", err))),
+        }
+    }
+}
+
+fn parse_node_maintenance_request(
+    api_instance: &crate::grpc_impl::api::NodeMaintenanceRequest,
+) -> anyhow::Result<crate::node_maintenance::NodeMaintenanceRequest> {
+    let node_id = match uuid::Uuid::from_str(api_instance.node_id.as_str()) {
+        Ok(node_id) => node_id,
+        Err(err) => return Err(anyhow::anyhow!("Ill-formed node_id field in NodeMaintenanceRequest message: {}", err)),
+    };
+    match api_instance.request_type {
+        x if x == crate::grpc_impl::api::NodeMaintenanceRequestType::Cordon as i32 => {
+            Ok(crate::node_maintenance::NodeMaintenanceRequest::Cordon(node_id))
+        }
+        x if x == crate::grpc_impl::api::NodeMaintenanceRequestType::Uncordon as i32 => {
+            Ok(crate::node_maintenance::NodeMaintenanceRequest::Uncordon(node_id))
+        }
+        x if x == crate::grpc_impl::api::NodeMaintenanceRequestType::Drain as i32 => {
+            Ok(crate::node_maintenance::NodeMaintenanceRequest::Drain(node_id))
+        }
+        x => Err(anyhow::anyhow!("Ill-formed NodeMaintenanceRequest message: unknown type {}", x)),
+    }
+}
+
+fn serialize_node_maintenance_request(req: &crate::node_maintenance::NodeMaintenanceRequest) -> crate::grpc_impl::api::NodeMaintenanceRequest {
+    let (request_type, node_id) = match req {
+        crate::node_maintenance::NodeMaintenanceRequest::Cordon(node_id) => (crate::grpc_impl::api::NodeMaintenanceRequestType::Cordon, node_id),
+        crate::node_maintenance::NodeMaintenanceRequest::Uncordon(node_id) => (crate::grpc_impl::api::NodeMaintenanceRequestType::Uncordon, node_id),
+        crate::node_maintenance::NodeMaintenanceRequest::Drain(node_id) => (crate::grpc_impl::api::NodeMaintenanceRequestType::Drain, node_id),
+    };
+    crate::grpc_impl::api::NodeMaintenanceRequest {
+        request_type: request_type as i32,
+        node_id: node_id.to_string(),
+    }
+}
+
+fn serialize_node_maintenance_response(req: &crate::node_maintenance::NodeMaintenanceResponse) -> crate::grpc_impl::api::NodeMaintenanceResponse {
+    match req {
+        crate::node_maintenance::NodeMaintenanceResponse::ResponseError(err) => crate::grpc_impl::api::NodeMaintenanceResponse {
+            response_error: Some(crate::grpc_impl::api::ResponseError {
+                summary: err.summary.clone(),
+                detail: err.detail.clone(),
+            }),
+        },
+        crate::node_maintenance::NodeMaintenanceResponse::Accepted => crate::grpc_impl::api::NodeMaintenanceResponse { response_error: None },
+    }
+}
+
+fn parse_node_maintenance_response(
+    api_instance: &crate::grpc_impl::api::NodeMaintenanceResponse,
+) -> anyhow::Result<crate::node_maintenance::NodeMaintenanceResponse> {
+    match api_instance.response_error.as_ref() {
+        Some(err) => Ok(crate::node_maintenance::NodeMaintenanceResponse::ResponseError(
+            crate::common::ResponseError {
+                summary: err.summary.clone(),
+                detail: err.detail.clone(),
+            },
+        )),
+        None => Ok(crate::node_maintenance::NodeMaintenanceResponse::Accepted),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::node_maintenance::NodeMaintenanceRequest;
+    use crate::node_maintenance::NodeMaintenanceResponse;
+
+    #[test]
+    fn serialize_deserialize_node_maintenance_request() {
+        let messages = vec![
+            NodeMaintenanceRequest::Cordon(uuid::Uuid::new_v4()),
+            NodeMaintenanceRequest::Uncordon(uuid::Uuid::new_v4()),
+            NodeMaintenanceRequest::Drain(uuid::Uuid::new_v4()),
+        ];
+        for msg in messages {
+            match parse_node_maintenance_request(&serialize_node_maintenance_request(&msg)) {
+                Ok(val) => assert_eq!(msg, val),
+                Err(err) => panic!("{}", err),
+            }
+        }
+    }
+
+    #[test]
+    fn serialize_deserialize_node_maintenance_response() {
+        let messages = vec![
+            NodeMaintenanceResponse::ResponseError(crate::common::ResponseError {
+                summary: "error summary".to_string(),
+                detail: Some("error details".to_string()),
+            }),
+            NodeMaintenanceResponse::Accepted,
+        ];
+        for msg in messages {
+            match parse_node_maintenance_response(&serialize_node_maintenance_response(&msg)) {
+                Ok(val) => assert_eq!(msg, val),
+                Err(err) => panic!("{}", err),
+            }
+        }
+    }
+}
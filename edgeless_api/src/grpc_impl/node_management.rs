@@ -86,7 +86,11 @@ pub fn parse_update_peers_request(
             if let (Some(node_id), Some(invocation_url)) = (&api_instance.node_id, &api_instance.invocation_url) {
                 let node_id = uuid::Uuid::from_str(node_id.as_str());
                 match node_id {
-                    Ok(node_id) => Ok(crate::node_management::UpdatePeersRequest::Add(node_id, invocation_url.clone())),
+                    Ok(node_id) => Ok(crate::node_management::UpdatePeersRequest::Add(
+                        node_id,
+                        invocation_url.clone(),
+                        api_instance.invocation_url_quic.clone(),
+                    )),
                     Err(_) => Err(anyhow::anyhow!("Ill-formed UpdatePeersRequest: invalid UUID as node_id")),
                 }
             } else {
@@ -124,25 +128,49 @@ pub fn parse_health_status(api_instance: &crate::grpc_impl::api::HealthStatus) -
         proc_cpu_usage: api_instance.proc_cpu_usage,
         proc_memory: api_instance.proc_memory,
         proc_vmemory: api_instance.proc_vmemory,
+        instance_statistics: api_instance
+            .instance_statistics
+            .iter()
+            .map(parse_instance_statistics)
+            .collect::<anyhow::Result<Vec<_>>>()?,
+    })
+}
+
+fn parse_instance_statistics(api_instance: &crate::grpc_impl::api::InstanceStatistics) -> anyhow::Result<crate::node_management::InstanceStatistics> {
+    Ok(crate::node_management::InstanceStatistics {
+        function_id: uuid::Uuid::from_str(api_instance.function_id.as_str())
+            .map_err(|_| anyhow::anyhow!("Ill-formed InstanceStatistics message: invalid UUID as function_id"))?,
+        invocations: api_instance.invocations,
+        total_duration_us: api_instance.total_duration_us,
+        invocations_by_port: api_instance
+            .invocations_by_port
+            .iter()
+            .map(|(port, invocations)| (crate::function_instance::PortId(port.clone()), *invocations))
+            .collect(),
+        queue_depth: api_instance.queue_depth,
+        dropped_events: api_instance.dropped_events,
     })
 }
 
 fn serialize_update_peers_request(req: &crate::node_management::UpdatePeersRequest) -> crate::grpc_impl::api::UpdatePeersRequest {
     match req {
-        crate::node_management::UpdatePeersRequest::Add(node_id, invocation_url) => crate::grpc_impl::api::UpdatePeersRequest {
+        crate::node_management::UpdatePeersRequest::Add(node_id, invocation_url, invocation_url_quic) => crate::grpc_impl::api::UpdatePeersRequest {
             request_type: crate::grpc_impl::api::UpdatePeersRequestType::Add as i32,
             node_id: Some(node_id.to_string()),
             invocation_url: Some(invocation_url.clone()),
+            invocation_url_quic: invocation_url_quic.clone(),
         },
         crate::node_management::UpdatePeersRequest::Del(node_id) => crate::grpc_impl::api::UpdatePeersRequest {
             request_type: crate::grpc_impl::api::UpdatePeersRequestType::Del as i32,
             node_id: Some(node_id.to_string()),
             invocation_url: None,
+            invocation_url_quic: None,
         },
         crate::node_management::UpdatePeersRequest::Clear => crate::grpc_impl::api::UpdatePeersRequest {
             request_type: crate::grpc_impl::api::UpdatePeersRequestType::Clear as i32,
             node_id: None,
             invocation_url: None,
+            invocation_url_quic: None,
         },
     }
 }
@@ -158,6 +186,22 @@ fn serialize_health_status(req: &crate::node_management::HealthStatus) -> crate:
         proc_cpu_usage: req.proc_cpu_usage,
         proc_memory: req.proc_memory,
         proc_vmemory: req.proc_vmemory,
+        instance_statistics: req.instance_statistics.iter().map(serialize_instance_statistics).collect(),
+    }
+}
+
+fn serialize_instance_statistics(req: &crate::node_management::InstanceStatistics) -> crate::grpc_impl::api::InstanceStatistics {
+    crate::grpc_impl::api::InstanceStatistics {
+        function_id: req.function_id.to_string(),
+        invocations: req.invocations,
+        total_duration_us: req.total_duration_us,
+        invocations_by_port: req
+            .invocations_by_port
+            .iter()
+            .map(|(port, invocations)| (port.0.clone(), *invocations))
+            .collect(),
+        queue_depth: req.queue_depth,
+        dropped_events: req.dropped_events,
     }
 }
 
@@ -165,12 +209,18 @@ fn serialize_health_status(req: &crate::node_management::HealthStatus) -> crate:
 mod test {
     use super::*;
     use crate::node_management::HealthStatus;
+    use crate::node_management::InstanceStatistics;
     use crate::node_management::UpdatePeersRequest;
 
     #[test]
     fn serialize_deserialize_update_peers_request() {
         let messages = vec![
-            UpdatePeersRequest::Add(uuid::Uuid::new_v4(), "http://127.0.0.10001".to_string()),
+            UpdatePeersRequest::Add(uuid::Uuid::new_v4(), "http://127.0.0.10001".to_string(), None),
+            UpdatePeersRequest::Add(
+                uuid::Uuid::new_v4(),
+                "http://127.0.0.10001".to_string(),
+                Some("quic://127.0.0.1:10002".to_string()),
+            ),
             UpdatePeersRequest::Del(uuid::Uuid::new_v4()),
             UpdatePeersRequest::Clear,
         ];
@@ -197,6 +247,39 @@ mod test {
                 proc_cpu_usage: 7,
                 proc_memory: 8,
                 proc_vmemory: 9,
+                instance_statistics: vec![],
+            },
+            HealthStatus {
+                cpu_usage: 1,
+                cpu_load: 2,
+                mem_free: 3,
+                mem_used: 4,
+                mem_total: 5,
+                mem_available: 6,
+                proc_cpu_usage: 7,
+                proc_memory: 8,
+                proc_vmemory: 9,
+                instance_statistics: vec![
+                    InstanceStatistics {
+                        function_id: uuid::Uuid::new_v4(),
+                        invocations: 0,
+                        total_duration_us: 0,
+                        invocations_by_port: std::collections::HashMap::new(),
+                        queue_depth: 0,
+                        dropped_events: 0,
+                    },
+                    InstanceStatistics {
+                        function_id: uuid::Uuid::new_v4(),
+                        invocations: 42,
+                        total_duration_us: 123456,
+                        invocations_by_port: std::collections::HashMap::from([
+                            (crate::function_instance::PortId("in".to_string()), 40),
+                            (crate::function_instance::PortId("other".to_string()), 2),
+                        ]),
+                        queue_depth: 17,
+                        dropped_events: 3,
+                    },
+                ],
             },
         ];
         for msg in messages {
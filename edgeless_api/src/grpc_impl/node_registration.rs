@@ -130,6 +130,7 @@ fn parse_update_node_request(api_instance: &crate::grpc_impl::api::UpdateNodeReq
                         .iter()
                         .map(|p| crate::node_registration::LinkProviderSpecification::try_from(p.clone()).unwrap())
                         .collect(),
+                    api_instance.invocation_url_quic.clone(),
                 ))
             } else {
                 Err(anyhow::anyhow!(
@@ -179,6 +180,7 @@ fn serialize_update_node_request(req: &crate::node_registration::UpdateNodeReque
             resource_providers,
             capabilities,
             link_providers,
+            invocation_url_quic,
         ) => crate::grpc_impl::api::UpdateNodeRequest {
             request_type: crate::grpc_impl::api::UpdateNodeRequestType::Register as i32,
             node_id: node_id.to_string(),
@@ -187,6 +189,7 @@ fn serialize_update_node_request(req: &crate::node_registration::UpdateNodeReque
             resource_providers: resource_providers.iter().map(serialize_resource_provider_specification).collect(),
             capabilities: Some(serialize_node_capabilities(capabilities)),
             link_providers: link_providers.iter().map(|p| p.clone().into()).collect(),
+            invocation_url_quic: invocation_url_quic.clone(),
         },
         crate::node_registration::UpdateNodeRequest::Deregistration(node_id) => crate::grpc_impl::api::UpdateNodeRequest {
             request_type: crate::grpc_impl::api::UpdateNodeRequestType::Deregister as i32,
@@ -196,6 +199,7 @@ fn serialize_update_node_request(req: &crate::node_registration::UpdateNodeReque
             resource_providers: vec![],
             capabilities: None,
             link_providers: Vec::new(),
+            invocation_url_quic: None,
         },
     }
 }
@@ -282,6 +286,7 @@ mod test {
                     runtimes: vec!["RUST_WASM".to_string()],
                 },
                 vec![],
+                Some("quic://127.0.0.1:10002".to_string()),
             ),
             UpdateNodeRequest::Registration(
                 uuid::Uuid::new_v4(),
@@ -290,6 +295,7 @@ mod test {
                 vec![],
                 NodeCapabilities::empty(),
                 vec![],
+                None,
             ),
             UpdateNodeRequest::Deregistration(uuid::Uuid::new_v4()),
         ];
@@ -79,8 +79,77 @@ impl WorkflowInstanceConverters {
                 })
                 .collect(),
             annotations: api_request.annotations.clone(),
-            workflow_egress_proxies: Vec::new(),
-            workflow_ingress_proxies: Vec::new(),
+            workflow_egress_proxies: api_request
+                .workflow_egress_proxies
+                .iter()
+                .map(WorkflowInstanceConverters::parse_workflow_egress_proxy)
+                .collect::<anyhow::Result<Vec<_>>>()?,
+            workflow_ingress_proxies: api_request
+                .workflow_ingress_proxies
+                .iter()
+                .map(WorkflowInstanceConverters::parse_workflow_ingress_proxy)
+                .collect::<anyhow::Result<Vec<_>>>()?,
+        })
+    }
+
+    pub fn parse_workflow_ingress_proxy(
+        api_proxy: &crate::grpc_impl::api::WorkflowIngressProxy,
+    ) -> anyhow::Result<crate::workflow_instance::WorkflowIngressProxy> {
+        Ok(crate::workflow_instance::WorkflowIngressProxy {
+            id: api_proxy.id.clone(),
+            inner_output: Self::parse_port_mapping(match api_proxy.inner_output.as_ref() {
+                Some(val) => val,
+                None => return Err(anyhow::anyhow!("Missing Ingress Proxy Inner Output")),
+            }),
+            external_input: match api_proxy.external_input.as_ref() {
+                Some(val) => Some(CommonConverters::parse_input(val)?),
+                None => None,
+            },
+        })
+    }
+
+    pub fn parse_workflow_egress_proxy(
+        api_proxy: &crate::grpc_impl::api::WorkflowEgressProxy,
+    ) -> anyhow::Result<crate::workflow_instance::WorkflowEgressProxy> {
+        Ok(crate::workflow_instance::WorkflowEgressProxy {
+            id: api_proxy.id.clone(),
+            inner_input: Self::parse_port_mapping(match api_proxy.inner_input.as_ref() {
+                Some(val) => val,
+                None => return Err(anyhow::anyhow!("Missing Egress Proxy Inner Input")),
+            }),
+            external_output: CommonConverters::parse_output(match api_proxy.external_output.as_ref() {
+                Some(val) => val,
+                None => return Err(anyhow::anyhow!("Missing Egress Proxy External Output")),
+            })?,
+        })
+    }
+
+    pub fn parse_migrate_function_request(
+        api_request: &crate::grpc_impl::api::MigrateFunctionRequest,
+    ) -> anyhow::Result<crate::workflow_instance::MigrateFunctionRequest> {
+        Ok(crate::workflow_instance::MigrateFunctionRequest {
+            workflow_id: WorkflowInstanceConverters::parse_workflow_id(match api_request.workflow_id.as_ref() {
+                Some(val) => val,
+                None => return Err(anyhow::anyhow!("WorkflowId Missing")),
+            })?,
+            function_name: api_request.function_name.clone(),
+            source_node_id: uuid::Uuid::parse_str(&api_request.source_node_id)?,
+            target_node_id: uuid::Uuid::parse_str(&api_request.target_node_id)?,
+        })
+    }
+
+    pub fn parse_workflow_update_request(
+        api_request: &crate::grpc_impl::api::UpdateWorkflowRequest,
+    ) -> anyhow::Result<crate::workflow_instance::UpdateWorkflowRequest> {
+        Ok(crate::workflow_instance::UpdateWorkflowRequest {
+            workflow_id: WorkflowInstanceConverters::parse_workflow_id(match api_request.workflow_id.as_ref() {
+                Some(val) => val,
+                None => return Err(anyhow::anyhow!("WorkflowId Missing")),
+            })?,
+            workflow: WorkflowInstanceConverters::parse_workflow_spawn_request(match api_request.workflow.as_ref() {
+                Some(val) => val,
+                None => return Err(anyhow::anyhow!("Workflow Missing")),
+            })?,
         })
     }
 
@@ -223,6 +292,56 @@ impl WorkflowInstanceConverters {
             workflow_functions: crate_request.workflow_functions.iter().map(Self::serialize_workflow_function).collect(),
             workflow_resources: crate_request.workflow_resources.iter().map(Self::serialize_workflow_resource).collect(),
             annotations: crate_request.annotations.clone(),
+            workflow_ingress_proxies: crate_request
+                .workflow_ingress_proxies
+                .iter()
+                .map(Self::serialize_workflow_ingress_proxy)
+                .collect(),
+            workflow_egress_proxies: crate_request
+                .workflow_egress_proxies
+                .iter()
+                .map(Self::serialize_workflow_egress_proxy)
+                .collect(),
+        }
+    }
+
+    pub fn serialize_workflow_ingress_proxy(
+        crate_proxy: &crate::workflow_instance::WorkflowIngressProxy,
+    ) -> crate::grpc_impl::api::WorkflowIngressProxy {
+        crate::grpc_impl::api::WorkflowIngressProxy {
+            id: crate_proxy.id.clone(),
+            inner_output: Some(Self::serialize_port_mapping(&crate_proxy.inner_output)),
+            external_input: crate_proxy.external_input.as_ref().map(CommonConverters::serialize_input),
+        }
+    }
+
+    pub fn serialize_workflow_egress_proxy(
+        crate_proxy: &crate::workflow_instance::WorkflowEgressProxy,
+    ) -> crate::grpc_impl::api::WorkflowEgressProxy {
+        crate::grpc_impl::api::WorkflowEgressProxy {
+            id: crate_proxy.id.clone(),
+            inner_input: Some(Self::serialize_port_mapping(&crate_proxy.inner_input)),
+            external_output: Some(CommonConverters::serialize_output(&crate_proxy.external_output)),
+        }
+    }
+
+    pub fn serialize_migrate_function_request(
+        crate_request: &crate::workflow_instance::MigrateFunctionRequest,
+    ) -> crate::grpc_impl::api::MigrateFunctionRequest {
+        crate::grpc_impl::api::MigrateFunctionRequest {
+            workflow_id: Some(Self::serialize_workflow_id(&crate_request.workflow_id)),
+            function_name: crate_request.function_name.clone(),
+            source_node_id: crate_request.source_node_id.to_string(),
+            target_node_id: crate_request.target_node_id.to_string(),
+        }
+    }
+
+    pub fn serialize_workflow_update_request(
+        crate_request: &crate::workflow_instance::UpdateWorkflowRequest,
+    ) -> crate::grpc_impl::api::UpdateWorkflowRequest {
+        crate::grpc_impl::api::UpdateWorkflowRequest {
+            workflow_id: Some(Self::serialize_workflow_id(&crate_request.workflow_id)),
+            workflow: Some(Self::serialize_workflow_spawn_request(&crate_request.workflow)),
         }
     }
 
@@ -381,6 +500,38 @@ impl crate::workflow_instance::WorkflowInstanceAPI for WorkflowInstanceAPIClient
             Err(err) => Err(anyhow::anyhow!("Communication error while listing workflows: {}", err.to_string())),
         }
     }
+
+    async fn update(
+        &mut self,
+        request: crate::workflow_instance::UpdateWorkflowRequest,
+    ) -> anyhow::Result<crate::workflow_instance::SpawnWorkflowResponse> {
+        let ret = self
+            .client
+            .update(tonic::Request::new(
+                crate::grpc_impl::workflow_instance::WorkflowInstanceConverters::serialize_workflow_update_request(&request),
+            ))
+            .await;
+        match ret {
+            Ok(ret) => crate::grpc_impl::workflow_instance::WorkflowInstanceConverters::parse_workflow_spawn_response(&ret.into_inner()),
+            Err(err) => Err(anyhow::anyhow!("Communication error while updating a workflow: {}", err)),
+        }
+    }
+
+    async fn migrate(
+        &mut self,
+        request: crate::workflow_instance::MigrateFunctionRequest,
+    ) -> anyhow::Result<crate::workflow_instance::SpawnWorkflowResponse> {
+        let ret = self
+            .client
+            .migrate(tonic::Request::new(
+                crate::grpc_impl::workflow_instance::WorkflowInstanceConverters::serialize_migrate_function_request(&request),
+            ))
+            .await;
+        match ret {
+            Ok(ret) => crate::grpc_impl::workflow_instance::WorkflowInstanceConverters::parse_workflow_spawn_response(&ret.into_inner()),
+            Err(err) => Err(anyhow::anyhow!("Communication error while migrating a function: {}", err)),
+        }
+    }
 }
 
 pub struct WorkflowInstanceAPIServer {
@@ -467,6 +618,68 @@ impl crate::grpc_impl::api::workflow_instance_server::WorkflowInstance for Workf
             ))),
         }
     }
+
+    async fn update(
+        &self,
+        request: tonic::Request<crate::grpc_impl::api::UpdateWorkflowRequest>,
+    ) -> Result<tonic::Response<crate::grpc_impl::api::SpawnWorkflowResponse>, tonic::Status> {
+        let req = match crate::grpc_impl::workflow_instance::WorkflowInstanceConverters::parse_workflow_update_request(&request.into_inner()) {
+            Ok(val) => val,
+            Err(err) => {
+                return Ok(tonic::Response::new(crate::grpc_impl::api::SpawnWorkflowResponse {
+                    response_error: Some(crate::grpc_impl::api::ResponseError {
+                        summary: "Invalid request".to_string(),
+                        detail: Some(err.to_string()),
+                    }),
+                    workflow_status: None,
+                }))
+            }
+        };
+        let ret = self.root_api.lock().await.update(req).await;
+        match ret {
+            Ok(response) => Ok(tonic::Response::new(
+                crate::grpc_impl::workflow_instance::WorkflowInstanceConverters::serialize_workflow_spawn_response(&response),
+            )),
+            Err(err) => Ok(tonic::Response::new(crate::grpc_impl::api::SpawnWorkflowResponse {
+                response_error: Some(crate::grpc_impl::api::ResponseError {
+                    summary: "Request rejected".to_string(),
+                    detail: Some(err.to_string()),
+                }),
+                workflow_status: None,
+            })),
+        }
+    }
+
+    async fn migrate(
+        &self,
+        request: tonic::Request<crate::grpc_impl::api::MigrateFunctionRequest>,
+    ) -> Result<tonic::Response<crate::grpc_impl::api::SpawnWorkflowResponse>, tonic::Status> {
+        let req = match crate::grpc_impl::workflow_instance::WorkflowInstanceConverters::parse_migrate_function_request(&request.into_inner()) {
+            Ok(val) => val,
+            Err(err) => {
+                return Ok(tonic::Response::new(crate::grpc_impl::api::SpawnWorkflowResponse {
+                    response_error: Some(crate::grpc_impl::api::ResponseError {
+                        summary: "Invalid request".to_string(),
+                        detail: Some(err.to_string()),
+                    }),
+                    workflow_status: None,
+                }))
+            }
+        };
+        let ret = self.root_api.lock().await.migrate(req).await;
+        match ret {
+            Ok(response) => Ok(tonic::Response::new(
+                crate::grpc_impl::workflow_instance::WorkflowInstanceConverters::serialize_workflow_spawn_response(&response),
+            )),
+            Err(err) => Ok(tonic::Response::new(crate::grpc_impl::api::SpawnWorkflowResponse {
+                response_error: Some(crate::grpc_impl::api::ResponseError {
+                    summary: "Request rejected".to_string(),
+                    detail: Some(err.to_string()),
+                }),
+                workflow_status: None,
+            })),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -475,8 +688,10 @@ mod tests {
 
     use super::*;
     use crate::function_instance::FunctionClassSpecification;
+    use crate::workflow_instance::MigrateFunctionRequest;
     use crate::workflow_instance::SpawnWorkflowRequest;
     use crate::workflow_instance::SpawnWorkflowResponse;
+    use crate::workflow_instance::UpdateWorkflowRequest;
     use crate::workflow_instance::WorkflowFunction;
     use crate::workflow_instance::WorkflowFunctionMapping;
     use crate::workflow_instance::WorkflowId;
@@ -673,8 +888,35 @@ mod tests {
                 input_mapping: HashMap::new(),
                 configurations: HashMap::from([("conf1".to_string(), "val1".to_string()), ("conf2".to_string(), "val2".to_string())]),
             }],
-            workflow_ingress_proxies: vec![],
-            workflow_egress_proxies: vec![],
+            workflow_ingress_proxies: vec![
+                crate::workflow_instance::WorkflowIngressProxy {
+                    id: "f1.in1".to_string(),
+                    inner_output: crate::workflow_instance::PortMapping::DirectTarget(
+                        "f1".to_string(),
+                        crate::function_instance::PortId("in1".to_string()),
+                    ),
+                    external_input: Some(crate::common::Input::Link(crate::link::LinkInstanceId(uuid::Uuid::new_v4()))),
+                },
+                crate::workflow_instance::WorkflowIngressProxy {
+                    id: "f1.in2".to_string(),
+                    inner_output: crate::workflow_instance::PortMapping::DirectTarget(
+                        "f1".to_string(),
+                        crate::function_instance::PortId("in2".to_string()),
+                    ),
+                    external_input: None,
+                },
+            ],
+            workflow_egress_proxies: vec![crate::workflow_instance::WorkflowEgressProxy {
+                id: "f1.out1".to_string(),
+                inner_input: crate::workflow_instance::PortMapping::AnyOfTargets(vec![(
+                    "f1".to_string(),
+                    crate::function_instance::PortId("out1".to_string()),
+                )]),
+                external_output: crate::common::Output::Single(
+                    crate::function_instance::InstanceId::new(uuid::Uuid::new_v4()),
+                    crate::function_instance::PortId("in5".to_string()),
+                ),
+            }],
         }];
 
         for msg in messages {
@@ -685,6 +927,57 @@ mod tests {
         }
     }
 
+    #[test]
+    fn serialize_deserialize_workflow_update_request() {
+        let messages = vec![UpdateWorkflowRequest {
+            workflow_id: WorkflowId {
+                workflow_id: uuid::Uuid::new_v4(),
+            },
+            workflow: SpawnWorkflowRequest {
+                workflow_functions: vec![],
+                workflow_resources: vec![WorkflowResource {
+                    name: "res1".to_string(),
+                    class_type: "my_res_class_type".to_string(),
+                    output_mapping: HashMap::from([(
+                        crate::function_instance::PortId("out1".to_string()),
+                        crate::workflow_instance::PortMapping::Topic("topic1".to_string()),
+                    )]),
+                    input_mapping: HashMap::new(),
+                    configurations: HashMap::from([("conf1".to_string(), "val1".to_string())]),
+                }],
+                workflow_ingress_proxies: vec![],
+                workflow_egress_proxies: vec![],
+                annotations: HashMap::from([("ann1".to_string(), "val1".to_string())]),
+            },
+        }];
+
+        for msg in messages {
+            match WorkflowInstanceConverters::parse_workflow_update_request(&WorkflowInstanceConverters::serialize_workflow_update_request(&msg)) {
+                Ok(val) => assert_eq!(msg, val),
+                Err(err) => panic!("{}", err),
+            }
+        }
+    }
+
+    #[test]
+    fn serialize_deserialize_migrate_function_request() {
+        let messages = vec![MigrateFunctionRequest {
+            workflow_id: WorkflowId {
+                workflow_id: uuid::Uuid::new_v4(),
+            },
+            function_name: "counter".to_string(),
+            source_node_id: uuid::Uuid::new_v4(),
+            target_node_id: uuid::Uuid::new_v4(),
+        }];
+
+        for msg in messages {
+            match WorkflowInstanceConverters::parse_migrate_function_request(&WorkflowInstanceConverters::serialize_migrate_function_request(&msg)) {
+                Ok(val) => assert_eq!(msg, val),
+                Err(err) => panic!("{}", err),
+            }
+        }
+    }
+
     #[test]
     fn serialize_deserialize_workflow_function_mapping() {
         let messages = vec![WorkflowFunctionMapping {
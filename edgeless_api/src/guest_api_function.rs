@@ -24,6 +24,7 @@ pub enum CallReturn {
     NoRet,
     Reply(Vec<u8>),
     Err,
+    Timeout,
 }
 
 #[async_trait::async_trait]
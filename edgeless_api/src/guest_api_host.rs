@@ -6,6 +6,9 @@ pub struct OutputEventData {
     pub originator: edgeless_api_core::instance_id::InstanceId,
     pub alias: String,
     pub msg: Vec<u8>,
+    /// Picks the recipient if the alias is mapped to multiple instances
+    /// load-balanced with consistent hashing.
+    pub partition_key: Option<Vec<u8>>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
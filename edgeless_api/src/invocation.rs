@@ -2,11 +2,11 @@
 // SPDX-FileCopyrightText: © 2023 Claudio Cicconetti <c.cicconetti@iit.cnr.it>
 // SPDX-License-Identifier: MIT
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum EventData {
-    Call(String),
-    Cast(String),
-    CallRet(String),
+    Call(Vec<u8>),
+    Cast(Vec<u8>),
+    CallRet(Vec<u8>),
     CallNoRet,
     Err,
 }
@@ -15,8 +15,8 @@ impl std::fmt::Display for EventData {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
             EventData::Call(data) => write!(f, "Call (size {} bytes)", data.len()),
-            EventData::Cast(data) => write!(f, "Call (size {} bytes)", data.len()),
-            EventData::CallRet(data) => write!(f, "Call (size {} bytes)", data.len()),
+            EventData::Cast(data) => write!(f, "Cast (size {} bytes)", data.len()),
+            EventData::CallRet(data) => write!(f, "CallRet (size {} bytes)", data.len()),
             EventData::CallNoRet => write!(f, "CallNoRet"),
             EventData::Err => write!(f, "Err"),
         }
@@ -48,9 +48,22 @@ pub enum LinkProcessingResult {
     FINAL,
     PROCESSED,
     PASSED,
+    /// The event is for this link, but it could not be delivered.
+    ERROR,
 }
 
+/// Outcome of an event handed over to an `InvocationAPI`, available once the event has been handled.
+pub type PendingResult = std::pin::Pin<Box<dyn std::future::Future<Output = anyhow::Result<LinkProcessingResult>> + Send>>;
+
 #[async_trait::async_trait]
 pub trait InvocationAPI: Sync + Send {
     async fn handle(&mut self, event: Event) -> anyhow::Result<LinkProcessingResult>;
+
+    /// Hand over an event, returning as soon as the next one can be handed over.
+    /// The events handed over are handled in order. By default, the event is
+    /// handled before returning.
+    async fn handle_pipelined(&mut self, event: Event) -> PendingResult {
+        let res = self.handle(event).await;
+        Box::pin(async move { res })
+    }
 }
@@ -4,6 +4,9 @@
 #[cfg(feature = "grpc_impl")]
 pub mod grpc_impl;
 
+#[cfg(feature = "quic_impl")]
+pub mod quic_impl;
+
 pub mod container_function;
 
 pub mod container_runtime;
@@ -36,6 +39,10 @@ pub mod node_management;
 
 pub mod node_registration;
 
+pub mod cluster_registration;
+
+pub mod node_maintenance;
+
 pub mod link;
 
 pub mod proxy_instance;
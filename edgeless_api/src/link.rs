@@ -11,7 +11,7 @@ pub enum LinkDirection {
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub struct LinkProviderId(pub uuid::Uuid);
 
-#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[derive(Debug, Clone, Hash, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct LinkInstanceId(pub uuid::Uuid);
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
@@ -63,6 +63,10 @@ pub trait LinkController: Send + Sync {
     fn config_for(&self, link: LinkInstanceId, node: crate::function_instance::NodeId) -> Option<Vec<u8>>;
     fn remove_link(&mut self, id: LinkInstanceId);
     async fn instantiate_control_plane(&mut self, link: LinkInstanceId);
+    /// Called when a node joins the cluster, for the links that need to know
+    /// how to reach the nodes.
+    fn add_node(&mut self, _node: crate::function_instance::NodeId, _invocation_url: &str) {}
+    fn remove_node(&mut self, _node: crate::function_instance::NodeId) {}
 }
 
 #[async_trait::async_trait]
@@ -0,0 +1,51 @@
+// SPDX-FileCopyrightText: © 2024 Technical University of Munich, Chair of Connected Mobility
+// SPDX-License-Identifier: MIT
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum NodeMaintenanceRequest {
+    // No new instances are placed on the node.
+    Cordon(crate::function_instance::NodeId),
+    // New instances can be placed on the node again.
+    Uncordon(crate::function_instance::NodeId),
+    // The node is cordoned and its instances are placed on other nodes.
+    Drain(crate::function_instance::NodeId),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum NodeMaintenanceResponse {
+    ResponseError(crate::common::ResponseError),
+    Accepted,
+}
+
+#[async_trait::async_trait]
+pub trait NodeMaintenanceAPI: NodeMaintenanceAPIClone + Sync + Send {
+    async fn update_node_maintenance(&mut self, request: NodeMaintenanceRequest) -> anyhow::Result<NodeMaintenanceResponse>;
+}
+
+impl std::fmt::Display for NodeMaintenanceRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            NodeMaintenanceRequest::Cordon(node_id) => write!(f, "cordon node {}", node_id),
+            NodeMaintenanceRequest::Uncordon(node_id) => write!(f, "uncordon node {}", node_id),
+            NodeMaintenanceRequest::Drain(node_id) => write!(f, "drain node {}", node_id),
+        }
+    }
+}
+
+// https://stackoverflow.com/a/30353928
+pub trait NodeMaintenanceAPIClone {
+    fn clone_box(&self) -> Box<dyn NodeMaintenanceAPI>;
+}
+impl<T> NodeMaintenanceAPIClone for T
+where
+    T: 'static + NodeMaintenanceAPI + Clone,
+{
+    fn clone_box(&self) -> Box<dyn NodeMaintenanceAPI> {
+        Box::new(self.clone())
+    }
+}
+impl Clone for Box<dyn NodeMaintenanceAPI> {
+    fn clone(&self) -> Box<dyn NodeMaintenanceAPI> {
+        self.clone_box()
+    }
+}
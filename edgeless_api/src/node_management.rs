@@ -4,8 +4,8 @@
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum UpdatePeersRequest {
-    Add(uuid::Uuid, String), // node_id, invocation_url
-    Del(uuid::Uuid),         // node_id
+    Add(uuid::Uuid, String, Option<String>), // node_id, invocation_url, QUIC invocation_url
+    Del(uuid::Uuid),                         // node_id
     Clear,
 }
 
@@ -20,6 +20,23 @@ pub struct HealthStatus {
     pub proc_cpu_usage: i32,
     pub proc_memory: i32,
     pub proc_vmemory: i32,
+    #[serde(default)]
+    pub instance_statistics: Vec<InstanceStatistics>,
+}
+
+/// Cumulative runtime statistics of a function instance, since it was started.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct InstanceStatistics {
+    pub function_id: crate::function_instance::ComponentId,
+    pub invocations: u64,
+    pub total_duration_us: u64,
+    pub invocations_by_port: std::collections::HashMap<crate::function_instance::PortId, u64>,
+    /// Number of events waiting in the input queue of the instance.
+    #[serde(default)]
+    pub queue_depth: u64,
+    /// Number of events discarded or rejected because the input queue was full.
+    #[serde(default)]
+    pub dropped_events: u64,
 }
 
 impl std::fmt::Display for HealthStatus {
@@ -52,6 +69,7 @@ impl HealthStatus {
             proc_cpu_usage: 0,
             proc_memory: 0,
             proc_vmemory: 0,
+            instance_statistics: vec![],
         }
     }
 
@@ -66,6 +84,7 @@ impl HealthStatus {
             proc_cpu_usage: -1,
             proc_memory: -1,
             proc_vmemory: -1,
+            instance_statistics: vec![],
         }
     }
 }
@@ -104,6 +104,8 @@ pub enum UpdateNodeRequest {
     // 2: invocation_url (cannot be empty)
     // 3: resource provider specifications (can be empty)
     // 4: node capabilities
+    // 5: link provider specifications (can be empty)
+    // 6: QUIC invocation_url (optional)
     Registration(
         uuid::Uuid,
         String,
@@ -111,6 +113,7 @@ pub enum UpdateNodeRequest {
         Vec<ResourceProviderSpecification>,
         NodeCapabilities,
         Vec<LinkProviderSpecification>,
+        Option<String>,
     ),
 
     // 0: node_id (cannot be empty)
@@ -0,0 +1,385 @@
+// SPDX-FileCopyrightText: © 2024 Technical University of Munich, Chair of Connected Mobility
+// SPDX-License-Identifier: MIT
+
+use crate::grpc_impl::invocation::InvocationConverters;
+
+/// Time allowed to establish a connection with a QUIC invocation server.
+const CONNECT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Largest event accepted from a stream, as for the TCP links.
+const MAX_FRAME_SIZE: usize = 16 * 1024 * 1024;
+
+/// Event waiting to be sent on a QUIC stream, already framed, with the channel to report the outcome of handling it.
+type QueuedEvent = (
+    Vec<u8>,
+    tokio::sync::oneshot::Sender<anyhow::Result<crate::invocation::LinkProcessingResult>>,
+);
+
+/// QUIC endpoint shared by the clients of a node. It keeps the session
+/// tickets of the servers, so that the clients can resume their sessions.
+#[derive(Clone)]
+pub struct QuicClientEndpoint {
+    endpoint: quinn::Endpoint,
+}
+
+impl QuicClientEndpoint {
+    pub fn new() -> anyhow::Result<Self> {
+        let mut endpoint = quinn::Endpoint::client(std::net::SocketAddr::from(([0, 0, 0, 0], 0)))?;
+        endpoint.set_default_client_config(super::client_config()?);
+        Ok(Self { endpoint })
+    }
+}
+
+/// Client of the QUIC InvocationAPI of a peer. The events for each target
+/// instance are sent in order on their own stream, so that the events for an
+/// instance are not held back by those lost or delayed for another one.
+pub struct QuicInvocationAPIClient {
+    endpoint: quinn::Endpoint,
+    server_addr: std::net::SocketAddr,
+    connection: Option<quinn::Connection>,
+    streams: std::collections::HashMap<crate::function_instance::ComponentId, tokio::sync::mpsc::UnboundedSender<QueuedEvent>>,
+}
+
+impl QuicInvocationAPIClient {
+    /// Create a client of the server at the given URL, e.g., `quic://127.0.0.1:7004`.
+    /// Fail if the server cannot be reached.
+    pub async fn new(endpoint: QuicClientEndpoint, server_url: &str) -> anyhow::Result<Self> {
+        let (_, host, port) = crate::util::parse_http_host(server_url)?;
+        let server_addr = tokio::net::lookup_host((host.as_str(), port))
+            .await?
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("Unknown host: {}", host))?;
+        let mut client = Self {
+            endpoint: endpoint.endpoint,
+            server_addr,
+            connection: None,
+            streams: std::collections::HashMap::new(),
+        };
+        tokio::time::timeout(CONNECT_TIMEOUT, client.connection()).await??;
+        Ok(client)
+    }
+
+    async fn connection(&mut self) -> anyhow::Result<quinn::Connection> {
+        if let Some(connection) = &self.connection {
+            if connection.close_reason().is_none() {
+                return Ok(connection.clone());
+            }
+        }
+
+        self.streams.clear();
+        // The events are not sent as 0-RTT data, which an attacker could replay.
+        let connection = self.endpoint.connect(self.server_addr, super::SERVER_NAME)?.await?;
+        self.connection = Some(connection.clone());
+        Ok(connection)
+    }
+
+    async fn stream(&mut self, target: crate::function_instance::ComponentId) -> anyhow::Result<tokio::sync::mpsc::UnboundedSender<QueuedEvent>> {
+        if let Some(stream) = self.streams.get(&target) {
+            if !stream.is_closed() {
+                return Ok(stream.clone());
+            }
+        }
+
+        let (send, recv) = self.connection().await?.open_bi().await?;
+        let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+        tokio::spawn(run_stream(receiver, send, recv));
+        self.streams.retain(|_, stream| !stream.is_closed());
+        self.streams.insert(target, sender.clone());
+        Ok(sender)
+    }
+}
+
+/// Write the queued events on a stream and report the outcomes received, in the same order.
+async fn run_stream(mut events: tokio::sync::mpsc::UnboundedReceiver<QueuedEvent>, mut send: quinn::SendStream, mut recv: quinn::RecvStream) {
+    let (outcome_sender, mut outcome_receiver) = tokio::sync::mpsc::unbounded_channel();
+    let writer = async move {
+        while let Some((frame, outcome)) = events.recv().await {
+            // The server may answer as soon as the event is written.
+            if outcome_sender.send(outcome).is_err() {
+                break;
+            }
+            if let Err(err) = send.write_all(&frame).await {
+                log::warn!("QUIC stream failure: {}", err);
+                break;
+            }
+        }
+        let _ = send.finish();
+    };
+    let reader = async move {
+        while let Some(outcome) = outcome_receiver.recv().await {
+            let mut handled = [0_u8; 1];
+            if let Err(err) = recv.read_exact(&mut handled).await {
+                log::warn!("QUIC stream failure: {}", err);
+                break;
+            }
+            let _ = outcome.send(match handled[0] {
+                1 => Ok(crate::invocation::LinkProcessingResult::PROCESSED),
+                _ => Err(anyhow::anyhow!("Remote Event Request Failed")),
+            });
+        }
+    };
+
+    // The events whose outcome is not known yet are reported as failed when the stream closes.
+    tokio::select! {
+        _ = writer => {}
+        _ = reader => {}
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::invocation::InvocationAPI for QuicInvocationAPIClient {
+    async fn handle(&mut self, event: crate::invocation::Event) -> anyhow::Result<crate::invocation::LinkProcessingResult> {
+        self.handle_pipelined(event).await.await
+    }
+
+    async fn handle_pipelined(&mut self, event: crate::invocation::Event) -> crate::invocation::PendingResult {
+        let frame = encode_frame(&event);
+        let stream = match self.stream(event.target.function_id).await {
+            Ok(stream) => stream,
+            Err(err) => return Box::pin(async move { Err(err) }),
+        };
+
+        let (outcome_sender, outcome_receiver) = tokio::sync::oneshot::channel();
+        if stream.send((frame, outcome_sender)).is_err() {
+            return Box::pin(async { Err(anyhow::anyhow!("QUIC Stream Closed")) });
+        }
+        Box::pin(async move { outcome_receiver.await.unwrap_or_else(|_| Err(anyhow::anyhow!("QUIC Stream Closed"))) })
+    }
+}
+
+pub struct QuicInvocationAPIServer {}
+
+impl QuicInvocationAPIServer {
+    pub fn run(data_plane: Box<dyn crate::invocation::InvocationAPI>, invocation_url: String) -> futures::future::BoxFuture<'static, ()> {
+        let root_api = std::sync::Arc::new(tokio::sync::Mutex::new(data_plane));
+        Box::pin(async move {
+            let endpoint = match Self::endpoint(&invocation_url).await {
+                Ok(endpoint) => endpoint,
+                Err(err) => {
+                    log::error!("QUIC Server Failure at {}: {}", invocation_url, err);
+                    return;
+                }
+            };
+
+            log::info!("Start InvocationAPI QUIC Server at {}", invocation_url);
+            while let Some(incoming) = endpoint.accept().await {
+                tokio::spawn(Self::handle_connection(incoming, root_api.clone()));
+            }
+            log::info!("Stop Invocation QUIC Server");
+        })
+    }
+
+    async fn endpoint(invocation_url: &str) -> anyhow::Result<quinn::Endpoint> {
+        let (_, host, port) = crate::util::parse_http_host(invocation_url)?;
+        let addr = tokio::net::lookup_host((host.as_str(), port))
+            .await?
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("Unknown host: {}", host))?;
+        Ok(quinn::Endpoint::server(super::server_config()?, addr)?)
+    }
+
+    async fn handle_connection(incoming: quinn::Incoming, root_api: std::sync::Arc<tokio::sync::Mutex<Box<dyn crate::invocation::InvocationAPI>>>) {
+        let connecting = match incoming.accept() {
+            Ok(connecting) => connecting,
+            Err(err) => {
+                log::warn!("QUIC connection refused: {}", err);
+                return;
+            }
+        };
+        let connection = match connecting.await {
+            Ok(connection) => connection,
+            Err(err) => {
+                log::warn!("QUIC handshake failure: {}", err);
+                return;
+            }
+        };
+
+        loop {
+            match connection.accept_bi().await {
+                Ok((send, recv)) => {
+                    tokio::spawn(Self::handle_stream(send, recv, root_api.clone()));
+                }
+                Err(err) => {
+                    log::debug!("QUIC connection closed: {}", err);
+                    break;
+                }
+            }
+        }
+    }
+
+    async fn handle_stream(
+        mut send: quinn::SendStream,
+        mut recv: quinn::RecvStream,
+        root_api: std::sync::Arc<tokio::sync::Mutex<Box<dyn crate::invocation::InvocationAPI>>>,
+    ) {
+        loop {
+            let frame = match read_frame(&mut recv).await {
+                Ok(Some(frame)) => frame,
+                Ok(None) => break,
+                Err(err) => {
+                    log::warn!("QUIC stream failure: {}", err);
+                    break;
+                }
+            };
+
+            let handled = match decode_frame(&frame) {
+                Ok(event) => root_api.lock().await.handle(event).await.is_ok(),
+                Err(err) => {
+                    log::error!("Parse Request Failed: {}", err);
+                    false
+                }
+            };
+            if let Err(err) = send.write_all(&[handled as u8]).await {
+                log::warn!("QUIC stream failure: {}", err);
+                break;
+            }
+        }
+        let _ = send.finish();
+    }
+}
+
+/// Encode an event as its length (4 bytes, big endian) followed by the protobuf message also used by gRPC.
+fn encode_frame(event: &crate::invocation::Event) -> Vec<u8> {
+    let event = prost::Message::encode_to_vec(&InvocationConverters::encode_crate_event(event));
+    let mut frame = (event.len() as u32).to_be_bytes().to_vec();
+    frame.extend(event);
+    frame
+}
+
+fn decode_frame(frame: &[u8]) -> anyhow::Result<crate::invocation::Event> {
+    let event = <crate::grpc_impl::api::Event as prost::Message>::decode(frame)?;
+    InvocationConverters::parse_api_event(&event)
+}
+
+/// Read the next event from a stream, without its length, or None if the stream is finished.
+/// Fail if the event is longer than `MAX_FRAME_SIZE`.
+async fn read_frame(recv: &mut quinn::RecvStream) -> anyhow::Result<Option<Vec<u8>>> {
+    let mut len = [0_u8; 4];
+    match recv.read_exact(&mut len).await {
+        Ok(()) => {}
+        Err(quinn::ReadExactError::FinishedEarly(0)) => return Ok(None),
+        Err(err) => return Err(err.into()),
+    }
+    let len = u32::from_be_bytes(len) as usize;
+    if len > MAX_FRAME_SIZE {
+        anyhow::bail!("Event too large: {} bytes", len);
+    }
+    let mut frame = vec![0_u8; len];
+    recv.read_exact(&mut frame).await?;
+    Ok(Some(frame))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::invocation::{EventData, InvocationAPI};
+
+    struct MockInvocationAPI {
+        events: futures::channel::mpsc::UnboundedSender<crate::invocation::Event>,
+    }
+
+    #[async_trait::async_trait]
+    impl crate::invocation::InvocationAPI for MockInvocationAPI {
+        async fn handle(&mut self, event: crate::invocation::Event) -> anyhow::Result<crate::invocation::LinkProcessingResult> {
+            self.events.unbounded_send(event)?;
+            Ok(crate::invocation::LinkProcessingResult::FINAL)
+        }
+    }
+
+    #[test]
+    fn encode_decode_frame() {
+        let event = crate::invocation::Event {
+            target: crate::function_instance::InstanceId::new(uuid::Uuid::new_v4()),
+            source: crate::function_instance::InstanceId::new(uuid::Uuid::new_v4()),
+            target_port: crate::function_instance::PortId("test".to_string()),
+            stream_id: 42,
+            data: EventData::Call(vec![0xa1, 0x00, 0xff, 0xfe]),
+            context: opentelemetry::trace::SpanContext::empty_context(),
+        };
+        let frame = encode_frame(&event);
+        assert_eq!(u32::from_be_bytes(frame[0..4].try_into().unwrap()) as usize, frame.len() - 4);
+
+        let decoded = decode_frame(&frame[4..]).unwrap();
+        assert_eq!(decoded.target, event.target);
+        assert_eq!(decoded.source, event.source);
+        assert_eq!(decoded.target_port, event.target_port);
+        assert_eq!(decoded.stream_id, event.stream_id);
+        assert_eq!(decoded.data, event.data);
+    }
+
+    #[tokio::test]
+    async fn events_in_order_by_target() {
+        use futures::StreamExt;
+
+        let (sender, mut receiver) = futures::channel::mpsc::unbounded();
+        let _server = tokio::spawn(QuicInvocationAPIServer::run(
+            Box::new(MockInvocationAPI { events: sender }),
+            "quic://127.0.0.1:7104".to_string(),
+        ));
+
+        let endpoint = QuicClientEndpoint::new().unwrap();
+        let mut client = loop {
+            match QuicInvocationAPIClient::new(endpoint.clone(), "quic://127.0.0.1:7104").await {
+                Ok(client) => break client,
+                Err(_) => tokio::time::sleep(std::time::Duration::from_millis(100)).await,
+            }
+        };
+
+        let targets = [
+            crate::function_instance::InstanceId::new(uuid::Uuid::new_v4()),
+            crate::function_instance::InstanceId::new(uuid::Uuid::new_v4()),
+        ];
+        let event = |target, stream_id| crate::invocation::Event {
+            target,
+            source: crate::function_instance::InstanceId::new(uuid::Uuid::new_v4()),
+            target_port: crate::function_instance::PortId("test".to_string()),
+            stream_id,
+            data: EventData::Cast(b"test".to_vec()),
+            context: opentelemetry::trace::SpanContext::empty_context(),
+        };
+
+        let mut pending = vec![];
+        for stream_id in 0..100 {
+            for target in targets {
+                pending.push(client.handle_pipelined(event(target, stream_id)).await);
+            }
+        }
+        for res in pending {
+            assert_eq!(res.await.unwrap(), crate::invocation::LinkProcessingResult::PROCESSED);
+        }
+
+        // The streams of different targets are independent, but each one is in order.
+        let mut next_stream_id = std::collections::HashMap::new();
+        for _ in 0..200 {
+            let received = receiver.next().await.unwrap();
+            let expected = next_stream_id.entry(received.target).or_insert(0);
+            assert_eq!(received.stream_id, *expected);
+            *expected += 1;
+        }
+        assert_eq!(next_stream_id.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn oversized_frame_rejected() {
+        let (sender, _receiver) = futures::channel::mpsc::unbounded();
+        let _server = tokio::spawn(QuicInvocationAPIServer::run(
+            Box::new(MockInvocationAPI { events: sender }),
+            "quic://127.0.0.1:7105".to_string(),
+        ));
+
+        let mut client = loop {
+            match QuicInvocationAPIClient::new(QuicClientEndpoint::new().unwrap(), "quic://127.0.0.1:7105").await {
+                Ok(client) => break client,
+                Err(_) => tokio::time::sleep(std::time::Duration::from_millis(100)).await,
+            }
+        };
+
+        // Only the length is sent: the server gives up on the stream without waiting for the event.
+        let (mut send, mut recv) = client.connection().await.unwrap().open_bi().await.unwrap();
+        send.write_all(&((MAX_FRAME_SIZE + 1) as u32).to_be_bytes()).await.unwrap();
+        let reply = tokio::time::timeout(std::time::Duration::from_secs(5), recv.read_to_end(1))
+            .await
+            .unwrap();
+        assert!(reply.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,86 @@
+// SPDX-FileCopyrightText: © 2024 Technical University of Munich, Chair of Connected Mobility
+// SPDX-License-Identifier: MIT
+
+pub mod invocation;
+
+/// Application protocol negotiated by the QUIC peers.
+const ALPN: &[u8] = b"edgeless-invocation";
+
+/// Name of the server in the self-signed certificates.
+const SERVER_NAME: &str = "edgeless";
+
+/// Configuration of a QUIC server with a self-signed certificate.
+/// The peers are not authenticated, as with the gRPC invocation server.
+fn server_config() -> anyhow::Result<quinn::ServerConfig> {
+    let certified_key = rcgen::generate_simple_self_signed(vec![SERVER_NAME.to_string()])?;
+    let cert = certified_key.cert.der().clone();
+    let key = rustls::pki_types::PrivatePkcs8KeyDer::from(certified_key.key_pair.serialize_der());
+
+    let mut crypto = rustls::ServerConfig::builder_with_provider(std::sync::Arc::new(rustls::crypto::ring::default_provider()))
+        .with_protocol_versions(&[&rustls::version::TLS13])?
+        .with_no_client_auth()
+        .with_single_cert(vec![cert], key.into())?;
+    crypto.alpn_protocols = vec![ALPN.to_vec()];
+    // No 0-RTT data is accepted, since the events could be replayed.
+    crypto.max_early_data_size = 0;
+
+    let mut config = quinn::ServerConfig::with_crypto(std::sync::Arc::new(quinn::crypto::rustls::QuicServerConfig::try_from(crypto)?));
+    // Keep the connections of the clients whose address changes, e.g., mobile nodes.
+    config.migration(true);
+    Ok(config)
+}
+
+/// Configuration of a QUIC client that accepts any server certificate.
+fn client_config() -> anyhow::Result<quinn::ClientConfig> {
+    let provider = std::sync::Arc::new(rustls::crypto::ring::default_provider());
+    let mut crypto = rustls::ClientConfig::builder_with_provider(provider.clone())
+        .with_protocol_versions(&[&rustls::version::TLS13])?
+        .dangerous()
+        .with_custom_certificate_verifier(std::sync::Arc::new(SkipServerVerification(provider)))
+        .with_no_client_auth();
+    crypto.alpn_protocols = vec![ALPN.to_vec()];
+
+    Ok(quinn::ClientConfig::new(std::sync::Arc::new(
+        quinn::crypto::rustls::QuicClientConfig::try_from(crypto)?,
+    )))
+}
+
+/// Certificate verifier accepting the self-signed certificates of the servers,
+/// while still checking that the handshake is signed by their key.
+#[derive(Debug)]
+struct SkipServerVerification(std::sync::Arc<rustls::crypto::CryptoProvider>);
+
+impl rustls::client::danger::ServerCertVerifier for SkipServerVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &rustls::pki_types::CertificateDer<'_>,
+        _intermediates: &[rustls::pki_types::CertificateDer<'_>],
+        _server_name: &rustls::pki_types::ServerName<'_>,
+        _ocsp: &[u8],
+        _now: rustls::pki_types::UnixTime,
+    ) -> Result<rustls::client::danger::ServerCertVerified, rustls::Error> {
+        Ok(rustls::client::danger::ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &rustls::pki_types::CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(message, cert, dss, &self.0.signature_verification_algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &rustls::pki_types::CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(message, cert, dss, &self.0.signature_verification_algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<rustls::SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
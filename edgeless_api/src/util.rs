@@ -6,6 +6,7 @@ pub enum Proto {
     HTTP,
     HTTPS,
     COAP,
+    QUIC,
 }
 
 pub fn parse_http_host(raw: &str) -> anyhow::Result<(Proto, String, u16)> {
@@ -17,6 +18,7 @@ pub fn parse_http_host(raw: &str) -> anyhow::Result<(Proto, String, u16)> {
                 "http" => Proto::HTTP,
                 "https" => Proto::HTTPS,
                 "coap" => Proto::COAP,
+                "quic" => Proto::QUIC,
                 _ => {
                     return Err(anyhow::anyhow!("Host Parse Error"));
                 }
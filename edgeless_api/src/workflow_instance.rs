@@ -47,7 +47,7 @@ pub struct WorkflowInstance {
     pub node_mapping: Vec<WorkflowFunctionMapping>,
 }
 
-#[derive(Clone, Debug, serde::Deserialize, PartialEq)]
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
 pub struct WorkflowResource {
     pub name: String,
     pub class_type: String,
@@ -56,7 +56,7 @@ pub struct WorkflowResource {
     pub configurations: std::collections::HashMap<String, String>,
 }
 
-#[derive(Clone, Debug, serde::Deserialize, PartialEq)]
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
 pub enum PortMapping {
     DirectTarget(String, super::function_instance::PortId),
     AnyOfTargets(Vec<(String, super::function_instance::PortId)>),
@@ -64,7 +64,7 @@ pub enum PortMapping {
     Topic(String),
 }
 
-#[derive(Clone, Debug, serde::Deserialize, PartialEq)]
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
 pub struct WorkflowFunction {
     pub name: String,
     pub function_class_specification: crate::function_instance::FunctionClassSpecification,
@@ -73,29 +73,46 @@ pub struct WorkflowFunction {
     pub annotations: std::collections::HashMap<String, String>,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct WorkflowIngressProxy {
     pub id: String,
     pub inner_output: PortMapping,
-    pub external_input: crate::common::Input,
+    pub external_input: Option<crate::common::Input>,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct WorkflowEgressProxy {
     pub id: String,
     pub inner_input: PortMapping,
     pub external_output: crate::common::Output,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct SpawnWorkflowRequest {
     pub workflow_functions: Vec<WorkflowFunction>,
     pub workflow_resources: Vec<WorkflowResource>,
+    #[serde(default)]
     pub workflow_ingress_proxies: Vec<WorkflowIngressProxy>,
+    #[serde(default)]
     pub workflow_egress_proxies: Vec<WorkflowEgressProxy>,
     pub annotations: std::collections::HashMap<String, String>,
 }
 
+#[derive(Clone, Debug, PartialEq)]
+pub struct UpdateWorkflowRequest {
+    pub workflow_id: WorkflowId,
+    pub workflow: SpawnWorkflowRequest,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MigrateFunctionRequest {
+    pub workflow_id: WorkflowId,
+    pub function_name: String,
+    /// Node currently hosting the instance to be moved.
+    pub source_node_id: super::function_instance::NodeId,
+    pub target_node_id: super::function_instance::NodeId,
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum SpawnWorkflowResponse {
     ResponseError(ResponseError),
@@ -108,6 +125,8 @@ pub trait WorkflowInstanceAPI: WorkflowInstanceAPIClone + Send + Sync {
     async fn stop(&mut self, id: WorkflowId) -> anyhow::Result<()>;
     async fn patch(&mut self, update: super::common::PatchRequest) -> anyhow::Result<()>;
     async fn list(&mut self, id: WorkflowId) -> anyhow::Result<Vec<WorkflowInstance>>;
+    async fn update(&mut self, request: UpdateWorkflowRequest) -> anyhow::Result<SpawnWorkflowResponse>;
+    async fn migrate(&mut self, request: MigrateFunctionRequest) -> anyhow::Result<SpawnWorkflowResponse>;
 }
 
 // https://stackoverflow.com/a/30353928
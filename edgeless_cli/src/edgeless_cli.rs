@@ -19,6 +19,20 @@ enum WorkflowCommands {
     Start { spec_file: String },
     Stop { id: String },
     List {},
+    Update { id: String, spec_file: String },
+    Migrate {
+        id: String,
+        function_name: String,
+        source_node_id: String,
+        target_node_id: String,
+    },
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum NodeCommands {
+    Cordon { node_id: String },
+    Uncordon { node_id: String },
+    Drain { node_id: String },
 }
 
 #[derive(Debug, clap::Subcommand)]
@@ -60,6 +74,10 @@ enum Commands {
         #[command(subcommand)]
         workflow_command: WorkflowCommands,
     },
+    Node {
+        #[command(subcommand)]
+        node_command: NodeCommands,
+    },
     Function {
         #[command(subcommand)]
         function_command: FunctionCommands,
@@ -134,182 +152,7 @@ async fn main() -> anyhow::Result<()> {
                     WorkflowCommands::Start { spec_file } => {
                         log::debug!("Start Workflow");
 
-                        let p = std::path::PathBuf::from(spec_file.clone());
-                        let workflow: edgeless_config::workflow::EdgelessWorkflow = if p.extension().unwrap() == "json" {
-                            serde_json::from_str(&std::fs::read_to_string(spec_file.clone()).unwrap()).unwrap()
-                        } else {
-                            match edgeless_config::load(p).unwrap() {
-                                edgeless_config::LoadResult::Workflow(wf) => wf,
-                                _ => {
-                                    panic!("Can't Spawn Function as Workflow");
-                                }
-                            }
-                        };
-                        let res = con_wf_client
-                            .start(edgeless_api::workflow_instance::SpawnWorkflowRequest {
-                                workflow_functions: workflow
-                                    .actors
-                                    .into_iter()
-                                    .map(|func_spec| {
-                                        log::info!("{:?}", func_spec.klass.code.clone());
-                                        let function_class_code = match func_spec.klass.code_type.as_str() {
-                                            "RUST_WASM" => std::fs::read(func_spec.klass.code.unwrap().path).unwrap(),
-                                            "RUST" => std::fs::read(func_spec.klass.code.unwrap().path).unwrap(),
-                                            "CONTAINER" => func_spec.klass.code.unwrap().path.as_bytes().to_vec(),
-                                            _ => panic!("unknown function class type: {}", func_spec.klass.id),
-                                        };
-
-                                        edgeless_api::workflow_instance::WorkflowFunction {
-                                            name: func_spec.id,
-                                            function_class_specification: edgeless_api::function_instance::FunctionClassSpecification {
-                                                function_class_id: func_spec.klass.id,
-                                                function_class_type: func_spec.klass.code_type,
-                                                function_class_version: func_spec.klass.version,
-                                                function_class_code,
-                                                function_class_outputs: func_spec
-                                                    .klass
-                                                    .outputs
-                                                    .iter()
-                                                    .map(|(port_id, port_spec)| {
-                                                        (
-                                                            edgeless_api::function_instance::PortId(port_id.clone()),
-                                                            edgeless_api::function_instance::Port {
-                                                                id: edgeless_api::function_instance::PortId(port_id.clone()),
-                                                                method: match port_spec.method {
-                                                                    edgeless_config::port_class::Method::Call => {
-                                                                        edgeless_api::function_instance::PortMethod::Call
-                                                                    }
-                                                                    edgeless_config::port_class::Method::Cast => {
-                                                                        edgeless_api::function_instance::PortMethod::Cast
-                                                                    }
-                                                                },
-                                                                data_type: edgeless_api::function_instance::PortDataType(port_spec.data_type.clone()),
-                                                                return_data_type: port_spec
-                                                                    .return_data_type
-                                                                    .clone()
-                                                                    .map(edgeless_api::function_instance::PortDataType),
-                                                            },
-                                                        )
-                                                    })
-                                                    .collect(),
-                                                function_class_inputs: func_spec
-                                                    .klass
-                                                    .inputs
-                                                    .iter()
-                                                    .map(|(port_id, port_spec)| {
-                                                        (
-                                                            edgeless_api::function_instance::PortId(port_id.clone()),
-                                                            edgeless_api::function_instance::Port {
-                                                                id: edgeless_api::function_instance::PortId(port_id.clone()),
-                                                                method: match port_spec.method {
-                                                                    edgeless_config::port_class::Method::Call => {
-                                                                        edgeless_api::function_instance::PortMethod::Call
-                                                                    }
-                                                                    edgeless_config::port_class::Method::Cast => {
-                                                                        edgeless_api::function_instance::PortMethod::Cast
-                                                                    }
-                                                                },
-                                                                data_type: edgeless_api::function_instance::PortDataType(port_spec.data_type.clone()),
-                                                                return_data_type: port_spec
-                                                                    .return_data_type
-                                                                    .clone()
-                                                                    .map(edgeless_api::function_instance::PortDataType),
-                                                            },
-                                                        )
-                                                    })
-                                                    .collect(),
-                                                function_class_inner_structure: func_spec
-                                                    .klass
-                                                    .inner_structure
-                                                    .iter()
-                                                    .map(|mapping| {
-                                                        (
-                                                            match &mapping.source {
-                                                                edgeless_config::inner_structure::MappingNode::Port(port_id) => {
-                                                                    edgeless_api::function_instance::MappingNode::Port(
-                                                                        edgeless_api::function_instance::PortId(port_id.clone()),
-                                                                    )
-                                                                }
-                                                                edgeless_config::inner_structure::MappingNode::SideEffect => {
-                                                                    edgeless_api::function_instance::MappingNode::SideEffect
-                                                                }
-                                                            },
-                                                            mapping
-                                                                .dests
-                                                                .iter()
-                                                                .map(|dest| match dest {
-                                                                    edgeless_config::inner_structure::MappingNode::Port(port_id) => {
-                                                                        edgeless_api::function_instance::MappingNode::Port(
-                                                                            edgeless_api::function_instance::PortId(port_id.clone()),
-                                                                        )
-                                                                    }
-                                                                    edgeless_config::inner_structure::MappingNode::SideEffect => {
-                                                                        edgeless_api::function_instance::MappingNode::SideEffect
-                                                                    }
-                                                                })
-                                                                .collect(),
-                                                        )
-                                                    })
-                                                    .collect(),
-                                            },
-                                            output_mapping: func_spec
-                                                .outputs
-                                                .iter()
-                                                .map(|(port_id, mapping)| {
-                                                    (
-                                                        edgeless_api::function_instance::PortId(port_id.clone()),
-                                                        parse_port_mapping(&mapping.mapping),
-                                                    )
-                                                })
-                                                .collect(),
-                                            input_mapping: func_spec
-                                                .inputs
-                                                .iter()
-                                                .map(|(port_id, mapping)| {
-                                                    (
-                                                        edgeless_api::function_instance::PortId(port_id.clone()),
-                                                        parse_port_mapping(&mapping.mapping),
-                                                    )
-                                                })
-                                                .collect(),
-                                            annotations: func_spec.annotations,
-                                        }
-                                    })
-                                    .collect(),
-                                workflow_resources: workflow
-                                    .resources
-                                    .into_iter()
-                                    .map(|res_spec| edgeless_api::workflow_instance::WorkflowResource {
-                                        name: res_spec.id,
-                                        class_type: res_spec.klass.id,
-                                        output_mapping: res_spec
-                                            .outputs
-                                            .iter()
-                                            .map(|(port_id, mapping)| {
-                                                (
-                                                    edgeless_api::function_instance::PortId(port_id.clone()),
-                                                    parse_port_mapping(&mapping.mapping),
-                                                )
-                                            })
-                                            .collect(),
-                                        input_mapping: res_spec
-                                            .inputs
-                                            .iter()
-                                            .map(|(port_id, mapping)| {
-                                                (
-                                                    edgeless_api::function_instance::PortId(port_id.clone()),
-                                                    parse_port_mapping(&mapping.mapping),
-                                                )
-                                            })
-                                            .collect(),
-                                        configurations: res_spec.configurations,
-                                    })
-                                    .collect(),
-                                workflow_egress_proxies: Vec::new(),
-                                workflow_ingress_proxies: Vec::new(),
-                                annotations: workflow.annotations.clone(),
-                            })
-                            .await;
+                        let res = con_wf_client.start(load_workflow(&spec_file)).await;
                         match res {
                             Ok(response) => {
                                 match &response {
@@ -346,6 +189,83 @@ async fn main() -> anyhow::Result<()> {
                         }
                         Err(err) => println!("{}", err),
                     },
+                    WorkflowCommands::Update { id, spec_file } => {
+                        let parsed_id = uuid::Uuid::parse_str(&id)?;
+                        let res = con_wf_client
+                            .update(edgeless_api::workflow_instance::UpdateWorkflowRequest {
+                                workflow_id: edgeless_api::workflow_instance::WorkflowId { workflow_id: parsed_id },
+                                workflow: load_workflow(&spec_file),
+                            })
+                            .await;
+                        match res {
+                            Ok(response) => {
+                                match &response {
+                                    SpawnWorkflowResponse::ResponseError(err) => {
+                                        println!("{:?}", err);
+                                    }
+                                    SpawnWorkflowResponse::WorkflowInstance(_) => {
+                                        println!("Workflow Updated");
+                                    }
+                                }
+                                log::info!("{:?}", response)
+                            }
+                            Err(err) => println!("{}", err),
+                        }
+                    }
+                    WorkflowCommands::Migrate {
+                        id,
+                        function_name,
+                        source_node_id,
+                        target_node_id,
+                    } => {
+                        let res = con_wf_client
+                            .migrate(edgeless_api::workflow_instance::MigrateFunctionRequest {
+                                workflow_id: edgeless_api::workflow_instance::WorkflowId {
+                                    workflow_id: uuid::Uuid::parse_str(&id)?,
+                                },
+                                function_name,
+                                source_node_id: uuid::Uuid::parse_str(&source_node_id)?,
+                                target_node_id: uuid::Uuid::parse_str(&target_node_id)?,
+                            })
+                            .await;
+                        match res {
+                            Ok(response) => {
+                                match &response {
+                                    SpawnWorkflowResponse::ResponseError(err) => {
+                                        println!("{:?}", err);
+                                    }
+                                    SpawnWorkflowResponse::WorkflowInstance(_) => {
+                                        println!("Function Migrated");
+                                    }
+                                }
+                                log::info!("{:?}", response)
+                            }
+                            Err(err) => println!("{}", err),
+                        }
+                    }
+                }
+            }
+            Commands::Node { node_command } => {
+                if std::fs::metadata(&args.config_file).is_err() {
+                    return Err(anyhow::anyhow!(
+                        "configuration file does not exist or cannot be accessed: {}",
+                        &args.config_file
+                    ));
+                }
+                let conf: CLiConfig = toml::from_str(&std::fs::read_to_string(args.config_file).unwrap()).unwrap();
+                let mut con_client = edgeless_api::grpc_impl::controller::ControllerAPIClient::new(&conf.controller_url).await;
+                let mut con_node_client = con_client.node_maintenance_api();
+                let request = match node_command {
+                    NodeCommands::Cordon { node_id } => edgeless_api::node_maintenance::NodeMaintenanceRequest::Cordon(uuid::Uuid::parse_str(&node_id)?),
+                    NodeCommands::Uncordon { node_id } => {
+                        edgeless_api::node_maintenance::NodeMaintenanceRequest::Uncordon(uuid::Uuid::parse_str(&node_id)?)
+                    }
+                    NodeCommands::Drain { node_id } => edgeless_api::node_maintenance::NodeMaintenanceRequest::Drain(uuid::Uuid::parse_str(&node_id)?),
+                };
+                match con_node_client.update_node_maintenance(request).await {
+                    Ok(edgeless_api::node_maintenance::NodeMaintenanceResponse::Accepted) => println!("Done"),
+                    Ok(edgeless_api::node_maintenance::NodeMaintenanceResponse::ResponseError(err)) => println!("{:?}", err),
+                    Err(err) => println!("{}", err),
                 }
             }
             Commands::Function { function_command } => match function_command {
@@ -428,7 +348,7 @@ async fn main() -> anyhow::Result<()> {
                         source: edgeless_api::function_instance::InstanceId::none(),
                         stream_id: 0,
                         data: match event_type.as_str() {
-                            "cast" => edgeless_api::invocation::EventData::Cast(payload),
+                            "cast" => edgeless_api::invocation::EventData::Cast(payload.into_bytes()),
                             _ => return Err(anyhow::anyhow!("invalid event type: {}", event_type)),
                         },
                         target_port: edgeless_api::function_instance::PortId(target_port),
@@ -602,6 +522,184 @@ async fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
+fn load_workflow(spec_file: &str) -> edgeless_api::workflow_instance::SpawnWorkflowRequest {
+    let p = std::path::PathBuf::from(spec_file);
+    let workflow: edgeless_config::workflow::EdgelessWorkflow = if p.extension().unwrap() == "json" {
+        serde_json::from_str(&std::fs::read_to_string(spec_file).unwrap()).unwrap()
+    } else {
+        match edgeless_config::load(p).unwrap() {
+            edgeless_config::LoadResult::Workflow(wf) => wf,
+            _ => {
+                panic!("Can't Spawn Function as Workflow");
+            }
+        }
+    };
+    edgeless_api::workflow_instance::SpawnWorkflowRequest {
+        workflow_functions: workflow
+            .actors
+            .into_iter()
+            .map(|func_spec| {
+                log::info!("{:?}", func_spec.klass.code.clone());
+                let function_class_code = match func_spec.klass.code_type.as_str() {
+                    "RUST_WASM" => std::fs::read(func_spec.klass.code.unwrap().path).unwrap(),
+                    "RUST" => std::fs::read(func_spec.klass.code.unwrap().path).unwrap(),
+                    "CONTAINER" => func_spec.klass.code.unwrap().path.as_bytes().to_vec(),
+                    "NATIVE_PROCESS" => func_spec.klass.code.unwrap().path.as_bytes().to_vec(),
+                    _ => panic!("unknown function class type: {}", func_spec.klass.id),
+                };
+
+                edgeless_api::workflow_instance::WorkflowFunction {
+                    name: func_spec.id,
+                    function_class_specification: edgeless_api::function_instance::FunctionClassSpecification {
+                        function_class_id: func_spec.klass.id,
+                        function_class_type: func_spec.klass.code_type,
+                        function_class_version: func_spec.klass.version,
+                        function_class_code,
+                        function_class_outputs: func_spec
+                            .klass
+                            .outputs
+                            .iter()
+                            .map(|(port_id, port_spec)| {
+                                (
+                                    edgeless_api::function_instance::PortId(port_id.clone()),
+                                    edgeless_api::function_instance::Port {
+                                        id: edgeless_api::function_instance::PortId(port_id.clone()),
+                                        method: match port_spec.method {
+                                            edgeless_config::port_class::Method::Call => {
+                                                edgeless_api::function_instance::PortMethod::Call
+                                            }
+                                            edgeless_config::port_class::Method::Cast => {
+                                                edgeless_api::function_instance::PortMethod::Cast
+                                            }
+                                        },
+                                        data_type: edgeless_api::function_instance::PortDataType(port_spec.data_type.clone()),
+                                        return_data_type: port_spec
+                                            .return_data_type
+                                            .clone()
+                                            .map(edgeless_api::function_instance::PortDataType),
+                                    },
+                                )
+                            })
+                            .collect(),
+                        function_class_inputs: func_spec
+                            .klass
+                            .inputs
+                            .iter()
+                            .map(|(port_id, port_spec)| {
+                                (
+                                    edgeless_api::function_instance::PortId(port_id.clone()),
+                                    edgeless_api::function_instance::Port {
+                                        id: edgeless_api::function_instance::PortId(port_id.clone()),
+                                        method: match port_spec.method {
+                                            edgeless_config::port_class::Method::Call => {
+                                                edgeless_api::function_instance::PortMethod::Call
+                                            }
+                                            edgeless_config::port_class::Method::Cast => {
+                                                edgeless_api::function_instance::PortMethod::Cast
+                                            }
+                                        },
+                                        data_type: edgeless_api::function_instance::PortDataType(port_spec.data_type.clone()),
+                                        return_data_type: port_spec
+                                            .return_data_type
+                                            .clone()
+                                            .map(edgeless_api::function_instance::PortDataType),
+                                    },
+                                )
+                            })
+                            .collect(),
+                        function_class_inner_structure: func_spec
+                            .klass
+                            .inner_structure
+                            .iter()
+                            .map(|mapping| {
+                                (
+                                    match &mapping.source {
+                                        edgeless_config::inner_structure::MappingNode::Port(port_id) => {
+                                            edgeless_api::function_instance::MappingNode::Port(
+                                                edgeless_api::function_instance::PortId(port_id.clone()),
+                                            )
+                                        }
+                                        edgeless_config::inner_structure::MappingNode::SideEffect => {
+                                            edgeless_api::function_instance::MappingNode::SideEffect
+                                        }
+                                    },
+                                    mapping
+                                        .dests
+                                        .iter()
+                                        .map(|dest| match dest {
+                                            edgeless_config::inner_structure::MappingNode::Port(port_id) => {
+                                                edgeless_api::function_instance::MappingNode::Port(
+                                                    edgeless_api::function_instance::PortId(port_id.clone()),
+                                                )
+                                            }
+                                            edgeless_config::inner_structure::MappingNode::SideEffect => {
+                                                edgeless_api::function_instance::MappingNode::SideEffect
+                                            }
+                                        })
+                                        .collect(),
+                                )
+                            })
+                            .collect(),
+                    },
+                    output_mapping: func_spec
+                        .outputs
+                        .iter()
+                        .map(|(port_id, mapping)| {
+                            (
+                                edgeless_api::function_instance::PortId(port_id.clone()),
+                                parse_port_mapping(&mapping.mapping),
+                            )
+                        })
+                        .collect(),
+                    input_mapping: func_spec
+                        .inputs
+                        .iter()
+                        .map(|(port_id, mapping)| {
+                            (
+                                edgeless_api::function_instance::PortId(port_id.clone()),
+                                parse_port_mapping(&mapping.mapping),
+                            )
+                        })
+                        .collect(),
+                    annotations: func_spec.annotations,
+                }
+            })
+            .collect(),
+        workflow_resources: workflow
+            .resources
+            .into_iter()
+            .map(|res_spec| edgeless_api::workflow_instance::WorkflowResource {
+                name: res_spec.id,
+                class_type: res_spec.klass.id,
+                output_mapping: res_spec
+                    .outputs
+                    .iter()
+                    .map(|(port_id, mapping)| {
+                        (
+                            edgeless_api::function_instance::PortId(port_id.clone()),
+                            parse_port_mapping(&mapping.mapping),
+                        )
+                    })
+                    .collect(),
+                input_mapping: res_spec
+                    .inputs
+                    .iter()
+                    .map(|(port_id, mapping)| {
+                        (
+                            edgeless_api::function_instance::PortId(port_id.clone()),
+                            parse_port_mapping(&mapping.mapping),
+                        )
+                    })
+                    .collect(),
+                configurations: res_spec.configurations,
+            })
+            .collect(),
+        workflow_egress_proxies: Vec::new(),
+        workflow_ingress_proxies: Vec::new(),
+        annotations: workflow.annotations.clone(),
+    }
+}
+
 fn parse_port_mapping(mapping: &edgeless_config::port::Mapping) -> edgeless_api::workflow_instance::PortMapping {
     match mapping {
         edgeless_config::port::Mapping::Direct(direct_target) => edgeless_api::workflow_instance::PortMapping::DirectTarget(
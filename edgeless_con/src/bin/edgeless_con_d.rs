@@ -24,7 +24,9 @@ fn main() -> anyhow::Result<()> {
     let async_runtime = tokio::runtime::Builder::new_multi_thread().worker_threads(8).enable_all().build()?;
     let mut async_tasks = vec![];
 
-    async_tasks.push(async_runtime.spawn(edgeless_con::edgeless_con_main(conf.clone())));
+    async_tasks.push(async_runtime.spawn(edgeless_con::edgeless_con_main_until(conf.clone(), async {
+        let _ = tokio::signal::ctrl_c().await;
+    })));
 
     async_runtime.block_on(async { futures::future::join_all(async_tasks).await });
 
@@ -4,6 +4,7 @@
 // SPDX-License-Identifier: MIT
 
 pub mod client;
+pub mod federation;
 pub mod server;
 // TODO Split and fix
 // #[cfg(test)]
@@ -26,11 +27,51 @@ pub(crate) enum ControllerRequest {
         tokio::sync::oneshot::Sender<anyhow::Result<Vec<edgeless_api::workflow_instance::WorkflowInstance>>>,
     ),
     PATCH(edgeless_api::common::PatchRequest),
+    UPDATE(
+        edgeless_api::workflow_instance::UpdateWorkflowRequest,
+        // Reply Channel
+        tokio::sync::oneshot::Sender<anyhow::Result<edgeless_api::workflow_instance::SpawnWorkflowResponse>>,
+    ),
+    MIGRATE(
+        edgeless_api::workflow_instance::MigrateFunctionRequest,
+        // Reply Channel
+        tokio::sync::oneshot::Sender<anyhow::Result<edgeless_api::workflow_instance::SpawnWorkflowResponse>>,
+    ),
+    /// Sent by the task draining a function instance being migrated, with
+    /// the state of the instance after the drain.
+    MIGRATIONDRAINED(
+        edgeless_api::workflow_instance::MigrateFunctionRequest,
+        edgeless_api::function_instance::InstanceId,
+        Result<Option<String>, String>,
+        // Reply Channel
+        tokio::sync::oneshot::Sender<anyhow::Result<edgeless_api::workflow_instance::SpawnWorkflowResponse>>,
+    ),
     UPDATENODE(
         edgeless_api::node_registration::UpdateNodeRequest,
         // Reply Channel
         tokio::sync::oneshot::Sender<anyhow::Result<edgeless_api::node_registration::UpdateNodeResponse>>,
     ),
+    UPDATECLUSTER(
+        edgeless_api::cluster_registration::UpdateClusterRequest,
+        // Reply Channel
+        tokio::sync::oneshot::Sender<anyhow::Result<edgeless_api::cluster_registration::UpdateClusterResponse>>,
+    ),
+    /// Sent by the task connecting to the controller of a new peer cluster,
+    /// with the client if the connection succeeded.
+    CLUSTERCONNECTED(
+        edgeless_api::cluster_registration::ClusterAnnouncement,
+        Option<Box<dyn edgeless_api::controller::ControllerAPI + Send>>,
+    ),
+    CLUSTERHEARTBEAT(
+        uuid::Uuid,
+        // Reply Channel
+        tokio::sync::oneshot::Sender<anyhow::Result<edgeless_api::cluster_registration::UpdateClusterResponse>>,
+    ),
+    NODEMAINTENANCE(
+        edgeless_api::node_maintenance::NodeMaintenanceRequest,
+        // Reply Channel
+        tokio::sync::oneshot::Sender<anyhow::Result<edgeless_api::node_maintenance::NodeMaintenanceResponse>>,
+    ),
 }
 
 #[derive(Clone)]
@@ -41,31 +82,45 @@ enum ComponentType {
 }
 
 impl Controller {
+    /// Creates the controller, whose task runs until `shutdown` completes,
+    /// after which this cluster leaves its peer clusters.
     pub async fn new_from_config(
         controller_settings: crate::EdgelessConSettings,
+        shutdown: impl futures::Future<Output = ()> + Send + 'static,
     ) -> (Self, std::pin::Pin<Box<dyn futures::Future<Output = ()> + Send>>) {
-        // Connect to all orchestrators.
-        // let mut orc_clients = std::collections::HashMap::<String, Box<dyn edgeless_api::orc::OrchestratorAPI>>::new();
-        // for orc in &controller_settings.orchestrators {
-        //     match edgeless_api::grpc_impl::orc::OrchestratorAPIClient::new(&orc.orchestrator_url, Some(1)).await {
-        //         Ok(val) => {
-        //             orc_clients.insert(orc.domain_id.to_string(), Box::new(val));
-        //         }
-        //         Err(err) => {
-        //             log::error!("Could not connect to e-ORC {}: {}", &orc.orchestrator_url, err);
-        //         }
-        //     }
-        // }
+        let store = crate::persistence::new_store(&controller_settings.persistence)
+            .unwrap_or_else(|err| panic!("could not open the controller's persistent store: {}", err));
 
-        Self::new()
+        Self::new(controller_settings, store, shutdown)
     }
 
-    fn new() -> (Self, std::pin::Pin<Box<dyn futures::Future<Output = ()> + Send>>) {
+    fn new(
+        controller_settings: crate::EdgelessConSettings,
+        store: Box<dyn crate::persistence::ControllerStore>,
+        shutdown: impl futures::Future<Output = ()> + Send + 'static,
+    ) -> (Self, std::pin::Pin<Box<dyn futures::Future<Output = ()> + Send>>) {
         let (sender, receiver) = futures::channel::mpsc::unbounded();
+        let request_sender = sender.clone();
 
         let main_task = Box::pin(async move {
-            let mut controller_task = server::ControllerTask::new(uuid::Uuid::new_v4(), receiver);
-            controller_task.run().await;
+            let mut controller_task = server::ControllerTask::new(
+                uuid::Uuid::new_v4(),
+                receiver,
+                request_sender,
+                controller_settings.orchestration_strategy.clone(),
+                store,
+            );
+            controller_task.recover().await;
+            // Peer clusters are contacted by a separate task, so that two
+            // controllers never wait for each other in their main loops.
+            let mut federation_task = controller_task.federation_task(&controller_settings);
+            tokio::select! {
+                _ = futures::future::join(controller_task.main_loop(), federation_task.run()) => {}
+                _ = shutdown => {
+                    log::info!("shutting down the controller");
+                    federation_task.deregister().await;
+                }
+            }
         });
 
         (Controller { sender }, main_task)
@@ -8,6 +8,8 @@ use futures::SinkExt;
 pub struct ControllerClient {
     workflow_instance_client: Box<dyn edgeless_api::workflow_instance::WorkflowInstanceAPI>,
     node_registration_client: Box<dyn edgeless_api::node_registration::NodeRegistrationAPI>,
+    cluster_registration_client: Box<dyn edgeless_api::cluster_registration::ClusterRegistrationAPI>,
+    node_maintenance_client: Box<dyn edgeless_api::node_maintenance::NodeMaintenanceAPI>,
 }
 
 impl ControllerClient {
@@ -15,6 +17,8 @@ impl ControllerClient {
         Box::new(ControllerClient {
             workflow_instance_client: Box::new(ControllerWorkflowInstanceClient { sender: sender.clone() }),
             node_registration_client: Box::new(ControllerNodeRegistrationClient { sender: sender.clone() }),
+            cluster_registration_client: Box::new(ControllerClusterRegistrationClient { sender: sender.clone() }),
+            node_maintenance_client: Box::new(ControllerNodeMaintenanceClient { sender: sender.clone() }),
         })
     }
 }
@@ -27,6 +31,14 @@ impl edgeless_api::controller::ControllerAPI for ControllerClient {
     fn node_registration_api(&mut self) -> Box<dyn edgeless_api::node_registration::NodeRegistrationAPI> {
         self.node_registration_client.clone()
     }
+
+    fn cluster_registration_api(&mut self) -> Box<dyn edgeless_api::cluster_registration::ClusterRegistrationAPI> {
+        self.cluster_registration_client.clone()
+    }
+
+    fn node_maintenance_api(&mut self) -> Box<dyn edgeless_api::node_maintenance::NodeMaintenanceAPI> {
+        self.node_maintenance_client.clone()
+    }
 }
 
 #[derive(Clone)]
@@ -39,6 +51,16 @@ pub struct ControllerNodeRegistrationClient {
     sender: futures::channel::mpsc::UnboundedSender<super::ControllerRequest>,
 }
 
+#[derive(Clone)]
+pub struct ControllerClusterRegistrationClient {
+    sender: futures::channel::mpsc::UnboundedSender<super::ControllerRequest>,
+}
+
+#[derive(Clone)]
+pub struct ControllerNodeMaintenanceClient {
+    sender: futures::channel::mpsc::UnboundedSender<super::ControllerRequest>,
+}
+
 #[async_trait::async_trait]
 impl edgeless_api::workflow_instance::WorkflowInstanceAPI for ControllerWorkflowInstanceClient {
     async fn start(
@@ -81,7 +103,42 @@ impl edgeless_api::workflow_instance::WorkflowInstanceAPI for ControllerWorkflow
     }
 
     async fn patch(&mut self, update: edgeless_api::common::PatchRequest) -> anyhow::Result<()> {
-        Ok(())
+        match self.sender.send(super::ControllerRequest::PATCH(update)).await {
+            Ok(_) => Ok(()),
+            Err(_) => Err(anyhow::anyhow!("Controller Channel Error")),
+        }
+    }
+
+    async fn update(
+        &mut self,
+        request: edgeless_api::workflow_instance::UpdateWorkflowRequest,
+    ) -> anyhow::Result<edgeless_api::workflow_instance::SpawnWorkflowResponse> {
+        let (reply_sender, reply_receiver) =
+            tokio::sync::oneshot::channel::<anyhow::Result<edgeless_api::workflow_instance::SpawnWorkflowResponse>>();
+        match self.sender.send(super::ControllerRequest::UPDATE(request, reply_sender)).await {
+            Ok(_) => {}
+            Err(_) => return Err(anyhow::anyhow!("Controller Channel Error")),
+        }
+        match reply_receiver.await {
+            Ok(ret) => ret,
+            Err(_) => Err(anyhow::anyhow!("Controller Channel Error")),
+        }
+    }
+
+    async fn migrate(
+        &mut self,
+        request: edgeless_api::workflow_instance::MigrateFunctionRequest,
+    ) -> anyhow::Result<edgeless_api::workflow_instance::SpawnWorkflowResponse> {
+        let (reply_sender, reply_receiver) =
+            tokio::sync::oneshot::channel::<anyhow::Result<edgeless_api::workflow_instance::SpawnWorkflowResponse>>();
+        match self.sender.send(super::ControllerRequest::MIGRATE(request, reply_sender)).await {
+            Ok(_) => {}
+            Err(_) => return Err(anyhow::anyhow!("Controller Channel Error")),
+        }
+        match reply_receiver.await {
+            Ok(ret) => ret,
+            Err(_) => Err(anyhow::anyhow!("Controller Channel Error")),
+        }
     }
 }
 
@@ -105,3 +162,63 @@ impl edgeless_api::node_registration::NodeRegistrationAPI for ControllerNodeRegi
         todo!()
     }
 }
+
+#[async_trait::async_trait]
+impl edgeless_api::cluster_registration::ClusterRegistrationAPI for ControllerClusterRegistrationClient {
+    async fn update_cluster(
+        &mut self,
+        request: edgeless_api::cluster_registration::UpdateClusterRequest,
+    ) -> anyhow::Result<edgeless_api::cluster_registration::UpdateClusterResponse> {
+        log::debug!("ClusterRegistrationAPI::update_cluster() {:?}", request);
+        let (reply_sender, reply_receiver) =
+            tokio::sync::oneshot::channel::<anyhow::Result<edgeless_api::cluster_registration::UpdateClusterResponse>>();
+        if let Err(err) = self.sender.send(super::ControllerRequest::UPDATECLUSTER(request, reply_sender)).await {
+            return Err(anyhow::anyhow!("Controller channel error when updating a cluster: {}", err));
+        }
+        match reply_receiver.await {
+            Ok(res) => res,
+            Err(err) => Err(anyhow::anyhow!("Controller channel error when updating a cluster: {}", err)),
+        }
+    }
+
+    async fn heartbeat(&mut self, cluster_id: uuid::Uuid) -> anyhow::Result<edgeless_api::cluster_registration::UpdateClusterResponse> {
+        let (reply_sender, reply_receiver) =
+            tokio::sync::oneshot::channel::<anyhow::Result<edgeless_api::cluster_registration::UpdateClusterResponse>>();
+        if let Err(err) = self
+            .sender
+            .send(super::ControllerRequest::CLUSTERHEARTBEAT(cluster_id, reply_sender))
+            .await
+        {
+            return Err(anyhow::anyhow!("Controller channel error when handling a heartbeat: {}", err));
+        }
+        match reply_receiver.await {
+            Ok(res) => res,
+            Err(err) => Err(anyhow::anyhow!("Controller channel error when handling a heartbeat: {}", err)),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl edgeless_api::node_maintenance::NodeMaintenanceAPI for ControllerNodeMaintenanceClient {
+    async fn update_node_maintenance(
+        &mut self,
+        request: edgeless_api::node_maintenance::NodeMaintenanceRequest,
+    ) -> anyhow::Result<edgeless_api::node_maintenance::NodeMaintenanceResponse> {
+        log::debug!("NodeMaintenanceAPI::update_node_maintenance() {:?}", request);
+        let (reply_sender, reply_receiver) =
+            tokio::sync::oneshot::channel::<anyhow::Result<edgeless_api::node_maintenance::NodeMaintenanceResponse>>();
+        if let Err(err) = self.sender.send(super::ControllerRequest::NODEMAINTENANCE(request, reply_sender)).await {
+            return Err(anyhow::anyhow!(
+                "Controller channel error when updating the maintenance of a node: {}",
+                err
+            ));
+        }
+        match reply_receiver.await {
+            Ok(res) => res,
+            Err(err) => Err(anyhow::anyhow!(
+                "Controller channel error when updating the maintenance of a node: {}",
+                err
+            )),
+        }
+    }
+}
@@ -0,0 +1,161 @@
+// SPDX-FileCopyrightText: © 2024 Technical University of Munich, Chair of Connected Mobility
+// SPDX-License-Identifier: MIT
+
+/// Task announcing this cluster to the controllers of the peer clusters
+/// in the configuration, then sending them periodic heartbeats.
+/// A peer is announced again whenever it does not know this cluster
+/// anymore, e.g., because it has been restarted.
+pub struct FederationTask {
+    cluster_id: uuid::Uuid,
+    domain_id: String,
+    controller_url: String,
+    nodes: std::sync::Arc<tokio::sync::Mutex<std::collections::HashMap<edgeless_api::function_instance::NodeId, super::server::WorkerNode>>>,
+    peers: Vec<Peer>,
+}
+
+struct Peer {
+    domain_id: String,
+    controller_url: String,
+    client: Option<Box<dyn edgeless_api::cluster_registration::ClusterRegistrationAPI>>,
+    announced: bool,
+}
+
+impl FederationTask {
+    pub fn new(
+        cluster_id: uuid::Uuid,
+        settings: &crate::EdgelessConSettings,
+        nodes: std::sync::Arc<tokio::sync::Mutex<std::collections::HashMap<edgeless_api::function_instance::NodeId, super::server::WorkerNode>>>,
+    ) -> Self {
+        Self {
+            cluster_id,
+            domain_id: settings.domain_id.clone(),
+            controller_url: settings.controller_url.clone(),
+            nodes,
+            peers: settings
+                .orchestrators
+                .iter()
+                .map(|peer| Peer {
+                    domain_id: peer.domain_id.clone(),
+                    controller_url: peer.orchestrator_url.clone(),
+                    client: None,
+                    announced: false,
+                })
+                .collect(),
+        }
+    }
+
+    pub async fn run(&mut self) {
+        if self.peers.is_empty() {
+            return;
+        }
+
+        let mut heartbeat_interval = tokio::time::interval(tokio::time::Duration::from_secs(2));
+        loop {
+            heartbeat_interval.tick().await;
+            let announcement = self.announcement().await;
+            for peer in &mut self.peers {
+                peer.refresh(&announcement).await;
+            }
+        }
+    }
+
+    /// Tells the peer clusters this cluster has been announced to that it
+    /// is leaving, so that they place its subflows elsewhere right away.
+    pub async fn deregister(&mut self) {
+        for peer in &mut self.peers {
+            peer.deregister(self.cluster_id).await;
+        }
+    }
+
+    async fn announcement(&self) -> edgeless_api::cluster_registration::ClusterAnnouncement {
+        // One provider for each link type supported by the nodes.
+        let mut link_types = std::collections::HashMap::new();
+        for node in self.nodes.lock().await.values() {
+            for (class, provider_id) in &node.supported_link_types {
+                link_types.entry(class.clone()).or_insert(provider_id.clone());
+            }
+        }
+
+        edgeless_api::cluster_registration::ClusterAnnouncement {
+            cluster_id: self.cluster_id,
+            domain_id: self.domain_id.clone(),
+            controller_url: self.controller_url.clone(),
+            link_providers: link_types
+                .into_iter()
+                .map(|(class, provider_id)| edgeless_api::node_registration::LinkProviderSpecification { provider_id, class })
+                .collect(),
+        }
+    }
+}
+
+impl Peer {
+    async fn deregister(&mut self, cluster_id: uuid::Uuid) {
+        let client = match self.client.as_mut() {
+            Some(client) if self.announced => client,
+            _ => return,
+        };
+        let res = tokio::time::timeout(
+            tokio::time::Duration::from_secs(2),
+            client.update_cluster(edgeless_api::cluster_registration::UpdateClusterRequest::Deregistration(cluster_id)),
+        )
+        .await;
+        match res {
+            Ok(Ok(edgeless_api::cluster_registration::UpdateClusterResponse::Accepted)) => {
+                log::info!("deregistered from peer cluster {}", self.domain_id);
+            }
+            Ok(Ok(edgeless_api::cluster_registration::UpdateClusterResponse::ResponseError(err))) => {
+                log::warn!("deregistration rejected by peer cluster {}: {}", self.domain_id, err);
+            }
+            Ok(Err(err)) => log::warn!("could not deregister from peer cluster {}: {}", self.domain_id, err),
+            Err(_) => log::warn!("could not deregister from peer cluster {}: timeout", self.domain_id),
+        }
+        self.announced = false;
+    }
+
+    async fn refresh(&mut self, announcement: &edgeless_api::cluster_registration::ClusterAnnouncement) {
+        if self.client.is_none() {
+            match edgeless_api::grpc_impl::cluster_registration::ClusterRegistrationClient::new(&self.controller_url, None).await {
+                Ok(client) => self.client = Some(Box::new(client)),
+                Err(err) => {
+                    log::debug!("peer cluster {} not reachable: {}", self.domain_id, err);
+                    return;
+                }
+            }
+        }
+        let client = match self.client.as_mut() {
+            Some(client) => client,
+            None => return,
+        };
+
+        let res = if self.announced {
+            client.heartbeat(announcement.cluster_id).await
+        } else {
+            client
+                .update_cluster(edgeless_api::cluster_registration::UpdateClusterRequest::Announcement(
+                    announcement.clone(),
+                ))
+                .await
+        };
+        match res {
+            Ok(edgeless_api::cluster_registration::UpdateClusterResponse::Accepted) => {
+                if !self.announced {
+                    log::info!("announced to peer cluster {} at {}", self.domain_id, self.controller_url);
+                    self.announced = true;
+                }
+            }
+            Ok(edgeless_api::cluster_registration::UpdateClusterResponse::ResponseError(err)) => {
+                if self.announced {
+                    log::info!("peer cluster {} does not know this cluster anymore: {}", self.domain_id, err);
+                } else {
+                    log::warn!("announcement rejected by peer cluster {}: {}", self.domain_id, err);
+                }
+                self.announced = false;
+            }
+            Err(err) => {
+                log::warn!("lost connection to peer cluster {}: {}", self.domain_id, err);
+                self.client = None;
+                self.announced = false;
+            }
+        }
+    }
+}
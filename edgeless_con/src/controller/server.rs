@@ -11,19 +11,45 @@ use futures::StreamExt;
 
 use crate::ir::RequiredChange;
 
+/// Time without heartbeats after which a peer cluster is considered gone.
+const PEER_CLUSTER_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Time given to the connection to the controller of a new peer cluster.
+const PEER_CLUSTER_CONNECTION_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Maximum time waited for a function instance to process the events in its
+/// input queue, when it is migrated.
+const FUNCTION_DRAIN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Period with which the input queue of a function instance being drained
+/// is checked.
+const FUNCTION_DRAIN_CHECK_PERIOD: std::time::Duration = std::time::Duration::from_millis(100);
+
 pub struct ControllerTask {
     request_receiver: futures::channel::mpsc::UnboundedReceiver<super::ControllerRequest>,
+    // Used by the tasks spawned by the controller to report back.
+    request_sender: futures::channel::mpsc::UnboundedSender<super::ControllerRequest>,
     cluster_id: edgeless_api::function_instance::NodeId,
     nodes: std::sync::Arc<tokio::sync::Mutex<std::collections::HashMap<edgeless_api::function_instance::NodeId, WorkerNode>>>,
     peer_clusters: std::sync::Arc<tokio::sync::Mutex<std::collections::HashMap<edgeless_api::function_instance::NodeId, PeerCluster>>>,
+    // Peer clusters announced whose controller is being connected to.
+    connecting_clusters: std::collections::HashSet<edgeless_api::function_instance::NodeId>,
     link_controllers:
         std::sync::Arc<tokio::sync::Mutex<std::collections::HashMap<edgeless_api::link::LinkType, Box<dyn edgeless_api::link::LinkController>>>>,
     active_workflows: std::collections::HashMap<edgeless_api::workflow_instance::WorkflowId, super::super::ir::managed_worflow::ManagedWorkflow>,
+    // Workflow spawned on the peer cluster for each subflow instance.
+    subflow_workflows: std::collections::HashMap<edgeless_api::function_instance::InstanceId, edgeless_api::workflow_instance::WorkflowId>,
     orchestration_logic: std::sync::Arc<tokio::sync::Mutex<crate::orchestration_logic::OrchestrationLogic>>,
+    store: Box<dyn crate::persistence::ControllerStore>,
+    // True until the workflows restored at start-up have been reconciled
+    // with the function instances reported by the nodes.
+    recovering: bool,
 }
 pub struct WorkerNode {
     pub agent_url: String,
     pub invocation_url: String,
+    /// URL of the QUIC invocation server of the node, if any.
+    pub invocation_url_quic: Option<String>,
     pub api: Box<dyn edgeless_api::agent::AgentAPI + Send>,
     pub resource_providers: std::collections::HashMap<String, ResourceProvider>,
     pub capabilities: edgeless_api::node_registration::NodeCapabilities,
@@ -32,12 +58,16 @@ pub struct WorkerNode {
     pub supported_link_types: std::collections::HashMap<edgeless_api::link::LinkType, edgeless_api::link::LinkProviderId>,
     // This should probably be based on link types and is a placeholder
     pub is_proxy: bool,
+    /// True if no new instances can be placed on the node.
+    pub cordoned: bool,
 }
 
 pub struct PeerCluster {
+    pub domain_id: String,
     pub controller_url: String,
     pub api: Box<dyn edgeless_api::controller::ControllerAPI + Send>,
     pub supported_link_types: std::collections::HashMap<edgeless_api::link::LinkType, edgeless_api::link::LinkProviderId>,
+    pub last_heartbeat: std::time::Instant,
 }
 
 #[derive(serde::Serialize)]
@@ -46,33 +76,301 @@ pub struct ResourceProvider {
     pub outputs: Vec<String>,
 }
 
+#[cfg(test)]
+impl WorkerNode {
+    /// Node with the given capabilities whose agent cannot be reached,
+    /// which is enough for placement decisions.
+    pub fn new_for_test(capabilities: edgeless_api::node_registration::NodeCapabilities) -> Self {
+        Self {
+            agent_url: String::new(),
+            invocation_url: String::new(),
+            invocation_url_quic: None,
+            api: Box::new(UnreachableAgent {}),
+            resource_providers: std::collections::HashMap::new(),
+            weight: 1.0,
+            capabilities,
+            health_status: edgeless_api::node_management::HealthStatus::empty(),
+            supported_link_types: std::collections::HashMap::new(),
+            is_proxy: false,
+            cordoned: false,
+        }
+    }
+}
+
+#[cfg(test)]
+impl PeerCluster {
+    /// Peer cluster whose controller cannot be reached, which is enough
+    /// for placement decisions.
+    pub fn new_for_test(domain_id: &str) -> Self {
+        Self {
+            domain_id: domain_id.to_string(),
+            controller_url: String::new(),
+            api: Box::new(UnreachableController {}),
+            supported_link_types: std::collections::HashMap::new(),
+            last_heartbeat: std::time::Instant::now(),
+        }
+    }
+}
+
+#[cfg(test)]
+struct UnreachableController {}
+
+#[cfg(test)]
+impl edgeless_api::controller::ControllerAPI for UnreachableController {
+    fn workflow_instance_api(&mut self) -> Box<dyn edgeless_api::workflow_instance::WorkflowInstanceAPI> {
+        unimplemented!("the controller of a test peer cluster cannot be reached")
+    }
+
+    fn node_registration_api(&mut self) -> Box<dyn edgeless_api::node_registration::NodeRegistrationAPI> {
+        unimplemented!("the controller of a test peer cluster cannot be reached")
+    }
+
+    fn cluster_registration_api(&mut self) -> Box<dyn edgeless_api::cluster_registration::ClusterRegistrationAPI> {
+        unimplemented!("the controller of a test peer cluster cannot be reached")
+    }
+
+    fn node_maintenance_api(&mut self) -> Box<dyn edgeless_api::node_maintenance::NodeMaintenanceAPI> {
+        unimplemented!("the controller of a test peer cluster cannot be reached")
+    }
+}
+
+#[cfg(test)]
+struct UnreachableAgent {}
+
+#[cfg(test)]
+impl edgeless_api::agent::AgentAPI for UnreachableAgent {
+    fn function_instance_api(
+        &mut self,
+    ) -> Box<dyn edgeless_api::function_instance::FunctionInstanceAPI<edgeless_api::function_instance::InstanceId>> {
+        unimplemented!("the agent of a test node cannot be reached")
+    }
+
+    fn node_management_api(&mut self) -> Box<dyn edgeless_api::node_management::NodeManagementAPI> {
+        unimplemented!("the agent of a test node cannot be reached")
+    }
+
+    fn resource_configuration_api(
+        &mut self,
+    ) -> Box<dyn edgeless_api::resource_configuration::ResourceConfigurationAPI<edgeless_api::function_instance::InstanceId>> {
+        unimplemented!("the agent of a test node cannot be reached")
+    }
+
+    fn link_instance_api(&mut self) -> Box<dyn edgeless_api::link::LinkInstanceAPI> {
+        unimplemented!("the agent of a test node cannot be reached")
+    }
+
+    fn proxy_instance_api(&mut self) -> Box<dyn edgeless_api::proxy_instance::ProxyInstanceAPI> {
+        unimplemented!("the agent of a test node cannot be reached")
+    }
+}
+
 impl ControllerTask {
     pub fn new(
         cluster_id: edgeless_api::function_instance::NodeId,
         request_receiver: futures::channel::mpsc::UnboundedReceiver<super::ControllerRequest>,
+        request_sender: futures::channel::mpsc::UnboundedSender<super::ControllerRequest>,
+        orchestration_strategy: crate::orchestration_utils::OrchestrationStrategy,
+        store: Box<dyn crate::persistence::ControllerStore>,
     ) -> Self {
         Self {
             request_receiver,
+            request_sender,
             nodes: std::sync::Arc::new(tokio::sync::Mutex::new(std::collections::HashMap::new())),
             cluster_id,
             peer_clusters: std::sync::Arc::new(tokio::sync::Mutex::new(std::collections::HashMap::new())),
-            link_controllers: std::sync::Arc::new(tokio::sync::Mutex::new(std::collections::HashMap::from([(
-                edgeless_api::link::LinkType("MULTICAST".to_string()),
-                Box::new(edgeless_link_multicast::controller::MulticastController::new()) as Box<dyn edgeless_api::link::LinkController>,
-            )]))),
+            connecting_clusters: std::collections::HashSet::new(),
+            link_controllers: std::sync::Arc::new(tokio::sync::Mutex::new(std::collections::HashMap::from([
+                (
+                    edgeless_api::link::LinkType(edgeless_link_multicast::common::LINK_TYPE.to_string()),
+                    Box::new(edgeless_link_multicast::controller::MulticastController::new()) as Box<dyn edgeless_api::link::LinkController>,
+                ),
+                (
+                    edgeless_api::link::LinkType(edgeless_link_tcp::common::LINK_TYPE.to_string()),
+                    Box::new(edgeless_link_tcp::controller::TcpController::new()) as Box<dyn edgeless_api::link::LinkController>,
+                ),
+                (
+                    edgeless_api::link::LinkType(edgeless_link_memory::common::LINK_TYPE.to_string()),
+                    Box::new(edgeless_link_memory::controller::MemoryController::new()) as Box<dyn edgeless_api::link::LinkController>,
+                ),
+            ]))),
             active_workflows: std::collections::HashMap::new(),
+            subflow_workflows: std::collections::HashMap::new(),
             orchestration_logic: std::sync::Arc::new(tokio::sync::Mutex::new(crate::orchestration_logic::OrchestrationLogic::new(
-                crate::orchestration_utils::OrchestrationStrategy::Random,
+                orchestration_strategy,
             ))),
+            store,
+            recovering: false,
+        }
+    }
+
+    /// Task that announces this cluster to the peer clusters configured.
+    /// It must be created after the recovery, which may change the
+    /// identifier of the cluster.
+    pub fn federation_task(&self, settings: &crate::EdgelessConSettings) -> super::federation::FederationTask {
+        super::federation::FederationTask::new(self.cluster_id, settings, self.nodes.clone())
+    }
+
+    /// Restores the identifier of the cluster, the nodes and the workflows
+    /// saved before the last restart.
+    /// The nodes that cannot be reached are discarded, while the workflows
+    /// are reconciled with the nodes at the first health check.
+    pub async fn recover(&mut self) {
+        let snapshot = match self.store.load() {
+            Ok(snapshot) => snapshot,
+            Err(err) => {
+                log::error!("could not load the controller state: {}", err);
+                return;
+            }
+        };
+        // The peer clusters know this cluster by its identifier.
+        match snapshot.cluster_id {
+            Some(cluster_id) => self.cluster_id = cluster_id,
+            None => {
+                if let Err(err) = self.store.put_cluster_id(&self.cluster_id) {
+                    log::error!("could not save the identifier of the cluster: {}", err);
+                }
+            }
+        }
+        log::info!("cluster identifier: {}", self.cluster_id);
+        if snapshot.nodes.is_empty() && snapshot.workflows.is_empty() {
+            return;
+        }
+        log::info!("recovering {} nodes and {} workflows", snapshot.nodes.len(), snapshot.workflows.len());
+
+        for (node_id, node) in snapshot.nodes {
+            let registration = self.process_node_registration(
+                node_id,
+                node.agent_url.clone(),
+                node.invocation_url.clone(),
+                node.resource_providers(),
+                node.capabilities.clone(),
+                node.link_providers(),
+                node.invocation_url_quic.clone(),
+            );
+            match tokio::time::timeout(tokio::time::Duration::from_secs(5), registration).await {
+                Ok(Ok(edgeless_api::node_registration::UpdateNodeResponse::Accepted)) => {
+                    if node.cordoned {
+                        self.set_node_cordoned(&node_id, true).await;
+                    }
+                }
+                _ => {
+                    log::warn!("could not recover node {} at {}", node_id, node.agent_url);
+                    self.nodes.lock().await.remove(&node_id);
+                    self.remove_node_from_links(node_id).await;
+                    if let Err(err) = self.store.remove_node(&node_id) {
+                        log::error!("could not save the removal of node {}: {}", node_id, err);
+                    }
+                }
+            }
+        }
+
+        for (workflow_id, record) in snapshot.workflows {
+            let wf_id = edgeless_api::workflow_instance::WorkflowId { workflow_id };
+            let mut wf = super::super::ir::managed_worflow::ManagedWorkflow::new(
+                record.spawn_request.clone(),
+                wf_id.clone(),
+                self.orchestration_logic.clone(),
+                self.nodes.clone(),
+                self.peer_clusters.clone(),
+                self.link_controllers.clone(),
+            );
+            tokio::task::block_in_place(|| wf.restore(&record));
+            for (subflow_id, workflow_id) in &record.subflow_workflows {
+                self.subflow_workflows
+                    .insert(*subflow_id, edgeless_api::workflow_instance::WorkflowId { workflow_id: *workflow_id });
+            }
+            self.active_workflows.insert(wf_id, wf);
         }
+        self.recovering = true;
     }
 
-    pub async fn run(&mut self) {
-        self.main_loop().await;
+    /// Stops the function instances not belonging to any workflow and makes
+    /// the restored workflows consistent with the nodes.
+    async fn reconcile(&mut self) {
+        let available_nodes: std::collections::HashSet<_> = self.nodes.lock().await.keys().cloned().collect();
+        let running_functions: std::collections::HashSet<_> = self
+            .nodes
+            .lock()
+            .await
+            .iter()
+            .flat_map(|(node_id, client_desc)| {
+                client_desc
+                    .health_status
+                    .instance_statistics
+                    .iter()
+                    .map(move |instance_statistics| edgeless_api::function_instance::InstanceId {
+                        node_id: *node_id,
+                        function_id: instance_statistics.function_id,
+                    })
+            })
+            .collect();
+
+        let mut known_functions = std::collections::HashSet::new();
+        for wf_id in self
+            .active_workflows
+            .keys()
+            .cloned()
+            .collect::<Vec<edgeless_api::workflow_instance::WorkflowId>>()
+        {
+            if let Some(wf) = self.active_workflows.get_mut(&wf_id) {
+                let required_changes = tokio::task::block_in_place(|| wf.reconcile(&available_nodes, &running_functions));
+                known_functions.extend(
+                    wf.wf
+                        .functions
+                        .values()
+                        .flat_map(|f| f.borrow().instances.iter().map(|i| i.borrow().id).collect::<Vec<_>>()),
+                );
+                if let Err(errs) = self.materialize(wf_id, required_changes).await {
+                    log::error!("Failures Handling Reconciliation: {}", errs.join(";"));
+                }
+            }
+        }
+
+        for orphan in running_functions.difference(&known_functions) {
+            log::info!("stopping orphaned function instance {}", orphan);
+            if let Some(mut client) = self.fn_client(&orphan.node_id).await {
+                if let Err(err) = client.stop(*orphan).await {
+                    log::error!("could not stop orphaned function instance {}: {}", orphan, err);
+                }
+            }
+        }
+    }
+
+    fn persist_workflow(&mut self, wf_id: &edgeless_api::workflow_instance::WorkflowId) {
+        let res = match self.active_workflows.get(wf_id) {
+            Some(wf) => {
+                let mut record = wf.record();
+                record.subflow_workflows = record
+                    .subflows
+                    .iter()
+                    .filter_map(|instance| {
+                        let subflow_id = edgeless_api::function_instance::InstanceId {
+                            node_id: instance.node_id,
+                            function_id: instance.function_id,
+                        };
+                        self.subflow_workflows
+                            .get(&subflow_id)
+                            .map(|workflow_id| (subflow_id, workflow_id.workflow_id))
+                    })
+                    .collect();
+                self.store.put_workflow(record)
+            }
+            None => self.store.remove_workflow(&wf_id.workflow_id),
+        };
+        if let Err(err) = res {
+            log::error!("could not save the state of workflow {}: {}", wf_id.to_string(), err);
+        }
+    }
+
+    fn persist_node_removal(&mut self, node_id: &edgeless_api::function_instance::NodeId) {
+        if let Err(err) = self.store.remove_node(node_id) {
+            log::error!("could not save the removal of node {}: {}", node_id, err);
+        }
     }
 
-    async fn main_loop(&mut self) {
+    pub async fn main_loop(&mut self) {
         let mut check_interval = tokio::time::interval(tokio::time::Duration::from_secs(2));
+        let mut autoscaling_interval = tokio::time::interval(tokio::time::Duration::from_secs(5));
         loop {
             tokio::select! {
                 req = self.request_receiver.next() => {
@@ -100,9 +398,21 @@ impl ControllerTask {
                                     }
                                 }
                             }
+                            super::ControllerRequest::MIGRATE(migrate_function_request, reply_sender) => {
+                                self.migrate_function(migrate_function_request, reply_sender).await;
+                            }
+                            super::ControllerRequest::MIGRATIONDRAINED(migrate_function_request, instance_id, state, reply_sender) => {
+                                let reply = self.complete_migration(migrate_function_request, instance_id, state).await;
+                                match reply_sender.send(reply) {
+                                    Ok(_) => {}
+                                    Err(err) => {
+                                        log::error!("Unhandled: {:?}", err);
+                                    }
+                                }
+                            }
                             super::ControllerRequest::UPDATENODE(update, reply_sender) => {
                                 let reply = match update {
-                                    edgeless_api::node_registration::UpdateNodeRequest::Registration(node_id, agent_url, invocation_url, resource_providers, capabilities, link_providers) => self.process_node_registration(node_id, agent_url, invocation_url, resource_providers, capabilities, link_providers).await,
+                                    edgeless_api::node_registration::UpdateNodeRequest::Registration(node_id, agent_url, invocation_url, resource_providers, capabilities, link_providers, invocation_url_quic) => self.process_node_registration(node_id, agent_url, invocation_url, resource_providers, capabilities, link_providers, invocation_url_quic).await,
                                     edgeless_api::node_registration::UpdateNodeRequest::Deregistration(node_id) => self.process_node_del(node_id).await,
                                 };
                                 match reply_sender.send(reply) {
@@ -112,15 +422,60 @@ impl ControllerTask {
                                     }
                                 }
                             },
+                            super::ControllerRequest::NODEMAINTENANCE(request, reply_sender) => {
+                                let reply = self.process_node_maintenance(request).await;
+                                match reply_sender.send(reply) {
+                                    Ok(_) => {}
+                                    Err(err) => {
+                                        log::error!("Unhandled: {:?}", err);
+                                    }
+                                }
+                            }
+                            super::ControllerRequest::UPDATECLUSTER(update, reply_sender) => {
+                                let reply = match update {
+                                    edgeless_api::cluster_registration::UpdateClusterRequest::Announcement(announcement) => self.process_cluster_announcement(announcement).await,
+                                    edgeless_api::cluster_registration::UpdateClusterRequest::Deregistration(cluster_id) => self.process_cluster_del(cluster_id).await,
+                                };
+                                match reply_sender.send(reply) {
+                                    Ok(_) => {}
+                                    Err(err) => {
+                                        log::error!("Unhandled: {:?}", err);
+                                    }
+                                }
+                            }
+                            super::ControllerRequest::CLUSTERCONNECTED(announcement, api) => {
+                                self.process_cluster_connected(announcement, api).await;
+                            }
+                            super::ControllerRequest::CLUSTERHEARTBEAT(cluster_id, reply_sender) => {
+                                let reply = self.process_cluster_heartbeat(cluster_id).await;
+                                match reply_sender.send(reply) {
+                                    Ok(_) => {}
+                                    Err(err) => {
+                                        log::error!("Unhandled: {:?}", err);
+                                    }
+                                }
+                            }
                             super::ControllerRequest::PATCH(update) => {
                                 let _res = self.patch_workflow(&update).await;
                             }
+                            super::ControllerRequest::UPDATE(update_workflow_request, reply_sender) => {
+                                let reply = self.update_workflow(update_workflow_request).await;
+                                match reply_sender.send(reply) {
+                                    Ok(_) => {}
+                                    Err(err) => {
+                                        log::error!("Unhandled: {:?}", err);
+                                    }
+                                }
+                            }
                         }
                     }
                 },
                 _ = check_interval.tick() => {
                     self.periodic_health_check().await;
                 }
+                _ = autoscaling_interval.tick() => {
+                    self.periodic_autoscaling().await;
+                }
 
             }
         }
@@ -130,6 +485,18 @@ impl ControllerTask {
         &mut self,
         spawn_workflow_request: edgeless_api::workflow_instance::SpawnWorkflowRequest,
     ) -> anyhow::Result<edgeless_api::workflow_instance::SpawnWorkflowResponse> {
+        if let Err(err) = super::super::ir::transformations::workflow_spitter::WorkflowSplitter::check_clusters(
+            &spawn_workflow_request,
+            &*self.peer_clusters.lock().await,
+        ) {
+            return Ok(edgeless_api::workflow_instance::SpawnWorkflowResponse::ResponseError(
+                edgeless_api::common::ResponseError {
+                    summary: "Workflow creation failed".to_string(),
+                    detail: Some(err.to_string()),
+                },
+            ));
+        }
+
         // Assign a new identifier to the newly-created workflow.
         let wf_id = edgeless_api::workflow_instance::WorkflowId {
             workflow_id: uuid::Uuid::new_v4(),
@@ -145,25 +512,7 @@ impl ControllerTask {
         );
         let required_changes = tokio::task::block_in_place(|| wf.initial_spawn());
 
-        let desc = edgeless_api::workflow_instance::WorkflowInstance {
-            workflow_id: wf_id.clone(),
-            node_mapping: wf
-                .wf
-                .components()
-                .iter()
-                .filter_map(|(id, a)| {
-                    let instances: Vec<_> = a.borrow_mut().instance_ids().iter().map(|i| i.node_id.to_string()).collect();
-                    if !instances.is_empty() {
-                        Some(edgeless_api::workflow_instance::WorkflowFunctionMapping {
-                            name: id.to_string(),
-                            node_ids: instances,
-                        })
-                    } else {
-                        None
-                    }
-                })
-                .collect(),
-        };
+        let desc = Self::describe_workflow(&wf_id, &wf);
 
         self.active_workflows.insert(wf_id.clone(), wf);
 
@@ -238,6 +587,248 @@ impl ControllerTask {
         Ok(ret)
     }
 
+    /// Replaces the description of an active workflow, keeping in execution
+    /// the components that have not changed.
+    /// Unlike a failed start, a failed update does not tear down the workflow.
+    async fn update_workflow(
+        &mut self,
+        update_workflow_request: edgeless_api::workflow_instance::UpdateWorkflowRequest,
+    ) -> anyhow::Result<edgeless_api::workflow_instance::SpawnWorkflowResponse> {
+        let wf_id = update_workflow_request.workflow_id;
+        if let Err(err) = super::super::ir::transformations::workflow_spitter::WorkflowSplitter::check_clusters(
+            &update_workflow_request.workflow,
+            &*self.peer_clusters.lock().await,
+        ) {
+            return Ok(edgeless_api::workflow_instance::SpawnWorkflowResponse::ResponseError(
+                edgeless_api::common::ResponseError {
+                    summary: "Workflow update failed".to_string(),
+                    detail: Some(err.to_string()),
+                },
+            ));
+        }
+        let wf = match self.active_workflows.get_mut(&wf_id) {
+            Some(wf) => wf,
+            None => {
+                return Ok(edgeless_api::workflow_instance::SpawnWorkflowResponse::ResponseError(
+                    edgeless_api::common::ResponseError {
+                        summary: "Workflow update failed".to_string(),
+                        detail: Some(format!("unknown workflow: {}", wf_id.to_string())),
+                    },
+                ))
+            }
+        };
+        let required_changes = tokio::task::block_in_place(|| wf.update(update_workflow_request.workflow));
+
+        let desc = Self::describe_workflow(&wf_id, wf);
+
+        match self.materialize(wf_id, required_changes).await {
+            Ok(_) => Ok(edgeless_api::workflow_instance::SpawnWorkflowResponse::WorkflowInstance(desc)),
+            Err(err) => Ok(edgeless_api::workflow_instance::SpawnWorkflowResponse::ResponseError(
+                edgeless_api::common::ResponseError {
+                    summary: "Workflow update failed".to_string(),
+                    detail: Some(err.join(";")),
+                },
+            )),
+        }
+    }
+
+    /// Moves a function instance of an active workflow to another node,
+    /// handing off the state synced by the instance.
+    /// Once the request is validated, the instance is drained by a separate
+    /// task, which reports back with a MIGRATIONDRAINED request carrying the
+    /// state of the instance, so that the main loop is not blocked.
+    /// The other components keep sending events to the instance until they
+    /// are patched by complete_migration(), hence the state handed off does
+    /// not reflect the events that the instance processes in the meantime.
+    async fn migrate_function(
+        &mut self,
+        request: edgeless_api::workflow_instance::MigrateFunctionRequest,
+        reply_sender: tokio::sync::oneshot::Sender<anyhow::Result<edgeless_api::workflow_instance::SpawnWorkflowResponse>>,
+    ) {
+        let instance_id = match self.migrated_instance(&request).await {
+            Ok(instance_id) => instance_id,
+            Err(detail) => {
+                if let Err(err) = reply_sender.send(Self::migration_rejected(detail)) {
+                    log::error!("Unhandled: {:?}", err);
+                }
+                return;
+            }
+        };
+
+        let clients = match self.nodes.lock().await.get_mut(&instance_id.node_id) {
+            Some(node) => Some((node.api.node_management_api(), node.api.function_instance_api())),
+            None => None,
+        };
+        let (node_management_client, mut fn_client) = match clients {
+            Some(clients) => clients,
+            None => {
+                if let Err(err) = reply_sender.send(Self::migration_rejected(format!("no function client for node: {}", instance_id.node_id))) {
+                    log::error!("Unhandled: {:?}", err);
+                }
+                return;
+            }
+        };
+
+        log::info!(
+            "migrating {} of workflow {} from node {} to node {}",
+            request.function_name,
+            request.workflow_id.to_string(),
+            request.source_node_id,
+            request.target_node_id
+        );
+        let request_sender = self.request_sender.clone();
+        tokio::spawn(async move {
+            // The state is fetched after the events already queued have
+            // been processed, so that the new instance starts from the
+            // state they produced, but not from that of the events still
+            // to be received before the patches.
+            if !Self::wait_for_empty_queue(node_management_client, instance_id).await {
+                log::warn!("the input queue of {} is not empty after {:?}", instance_id, FUNCTION_DRAIN_TIMEOUT);
+            }
+            let state = fn_client
+                .get_state(instance_id)
+                .await
+                .map_err(|err| format!("could not get the state of {}: {}", instance_id, err));
+            let _ = request_sender.unbounded_send(super::ControllerRequest::MIGRATIONDRAINED(request, instance_id, state, reply_sender));
+        });
+    }
+
+    /// Starts the new instance of a function being migrated with the state
+    /// of the old one, then patches the other components to use it, and
+    /// finally drains and stops the old instance.
+    async fn complete_migration(
+        &mut self,
+        request: edgeless_api::workflow_instance::MigrateFunctionRequest,
+        instance_id: edgeless_api::function_instance::InstanceId,
+        state: Result<Option<String>, String>,
+    ) -> anyhow::Result<edgeless_api::workflow_instance::SpawnWorkflowResponse> {
+        let state = match state {
+            Ok(state) => state,
+            Err(detail) => return Self::migration_rejected(detail),
+        };
+        // The workflow may have changed while the instance was drained.
+        match self.migrated_instance(&request).await {
+            Ok(current_id) if current_id == instance_id => {}
+            Ok(_) | Err(_) => return Self::migration_rejected(format!("{} has changed while being drained", instance_id)),
+        }
+
+        let wf_id = request.workflow_id;
+        let wf = match self.active_workflows.get_mut(&wf_id) {
+            Some(wf) => wf,
+            None => return Self::migration_rejected(format!("unknown workflow: {}", wf_id.to_string())),
+        };
+        let required_changes = tokio::task::block_in_place(|| wf.migrate(&request.function_name, instance_id, request.target_node_id, state));
+
+        let desc = Self::describe_workflow(&wf_id, wf);
+
+        match self.materialize(wf_id, required_changes).await {
+            Ok(_) => Ok(edgeless_api::workflow_instance::SpawnWorkflowResponse::WorkflowInstance(desc)),
+            Err(err) => Self::migration_rejected(err.join(";")),
+        }
+    }
+
+    /// Instance to be migrated, if the request can be satisfied.
+    async fn migrated_instance(
+        &mut self,
+        request: &edgeless_api::workflow_instance::MigrateFunctionRequest,
+    ) -> Result<edgeless_api::function_instance::InstanceId, String> {
+        let wf_id = &request.workflow_id;
+        let (instance_id, runtime, annotations) = match self.active_workflows.get(wf_id) {
+            Some(wf) => match wf.wf.functions.get(&request.function_name) {
+                Some(function) => {
+                    let function = function.borrow();
+                    match function.instances.iter().find(|i| i.borrow().id.node_id == request.source_node_id) {
+                        Some(instance) => (instance.borrow().id, function.image.format.clone(), function.annotations.clone()),
+                        None => return Err(format!("no instance of {} on node {}", request.function_name, request.source_node_id)),
+                    }
+                }
+                None => return Err(format!("unknown function: {}", request.function_name)),
+            },
+            None => return Err(format!("unknown workflow: {}", wf_id.to_string())),
+        };
+
+        let feasible = match self.nodes.lock().await.get(&request.target_node_id) {
+            Some(node) if node.cordoned => return Err(format!("node {} is cordoned", request.target_node_id)),
+            Some(node) => crate::orchestration_logic::OrchestrationLogic::is_node_feasible(
+                &runtime,
+                &crate::orchestration_utils::DeploymentRequirements::from_annotations(&annotations),
+                &request.target_node_id,
+                &node.capabilities,
+                &node.resource_providers,
+            ),
+            None => return Err(format!("unknown node: {}", request.target_node_id)),
+        };
+        if !feasible {
+            return Err(format!(
+                "node {} does not satisfy the requirements of {}",
+                request.target_node_id, request.function_name
+            ));
+        }
+        Ok(instance_id)
+    }
+
+    fn migration_rejected(detail: String) -> anyhow::Result<edgeless_api::workflow_instance::SpawnWorkflowResponse> {
+        Ok(edgeless_api::workflow_instance::SpawnWorkflowResponse::ResponseError(
+            edgeless_api::common::ResponseError {
+                summary: "Function migration failed".to_string(),
+                detail: Some(detail),
+            },
+        ))
+    }
+
+    /// Waits until the node reports that the input queue of a function
+    /// instance is empty, or that the instance is not running anymore.
+    /// Returns false if this does not happen within FUNCTION_DRAIN_TIMEOUT.
+    async fn wait_for_empty_queue(
+        mut node_management_client: Box<dyn edgeless_api::node_management::NodeManagementAPI>,
+        instance_id: edgeless_api::function_instance::InstanceId,
+    ) -> bool {
+        let drained = async {
+            loop {
+                match node_management_client.keep_alive().await {
+                    Ok(health_status) => {
+                        match health_status
+                            .instance_statistics
+                            .iter()
+                            .find(|statistics| statistics.function_id == instance_id.function_id)
+                        {
+                            Some(statistics) if statistics.queue_depth > 0 => {}
+                            _ => return,
+                        }
+                    }
+                    Err(err) => log::debug!("could not check the input queue of {}: {}", instance_id, err),
+                }
+                tokio::time::sleep(FUNCTION_DRAIN_CHECK_PERIOD).await;
+            }
+        };
+        tokio::time::timeout(FUNCTION_DRAIN_TIMEOUT, drained).await.is_ok()
+    }
+
+    fn describe_workflow(
+        wf_id: &edgeless_api::workflow_instance::WorkflowId,
+        wf: &super::super::ir::managed_worflow::ManagedWorkflow,
+    ) -> edgeless_api::workflow_instance::WorkflowInstance {
+        edgeless_api::workflow_instance::WorkflowInstance {
+            workflow_id: wf_id.clone(),
+            node_mapping: wf
+                .wf
+                .components()
+                .iter()
+                .filter_map(|(id, a)| {
+                    let instances: Vec<_> = a.borrow_mut().instance_ids().iter().map(|i| i.node_id.to_string()).collect();
+                    if !instances.is_empty() {
+                        Some(edgeless_api::workflow_instance::WorkflowFunctionMapping {
+                            name: id.to_string(),
+                            node_ids: instances,
+                        })
+                    } else {
+                        None
+                    }
+                })
+                .collect(),
+        }
+    }
+
     async fn patch_workflow(&mut self, req: &edgeless_api::common::PatchRequest) -> anyhow::Result<()> {
         let id = edgeless_api::workflow_instance::WorkflowId {
             workflow_id: req.function_id.function_id,
@@ -259,9 +850,10 @@ impl ControllerTask {
         resource_providers: Vec<edgeless_api::node_registration::ResourceProviderSpecification>,
         capabilities: edgeless_api::node_registration::NodeCapabilities,
         link_providers: Vec<edgeless_api::node_registration::LinkProviderSpecification>,
+        invocation_url_quic: Option<String>,
     ) -> anyhow::Result<edgeless_api::node_registration::UpdateNodeResponse> {
         if let Some(node) = self.nodes.lock().await.get(&node_id) {
-            if node.agent_url == agent_url && node.invocation_url == invocation_url {
+            if node.agent_url == agent_url && node.invocation_url == invocation_url && node.invocation_url_quic == invocation_url_quic {
                 return Ok(edgeless_api::node_registration::UpdateNodeResponse::Accepted);
             } else {
                 return Ok(edgeless_api::node_registration::UpdateNodeResponse::ResponseError(ResponseError {
@@ -279,11 +871,29 @@ impl ControllerTask {
             node_weight = 1.0;
         };
 
+        let record = crate::persistence::NodeRecord {
+            node_id,
+            agent_url: agent_url.clone(),
+            invocation_url: invocation_url.clone(),
+            invocation_url_quic: invocation_url_quic.clone(),
+            resource_providers: resource_providers
+                .iter()
+                .map(|r| (r.provider_id.clone(), r.class_type.clone(), r.outputs.clone()))
+                .collect(),
+            capabilities: capabilities.clone(),
+            link_providers: link_providers.iter().map(|p| (p.provider_id.0, p.class.0.clone())).collect(),
+            cordoned: false,
+        };
+        if let Err(err) = self.store.put_node(record) {
+            log::error!("could not save the registration of node {}: {}", node_id, err);
+        }
+
         self.nodes.lock().await.insert(
             node_id,
             WorkerNode {
                 agent_url,
                 invocation_url: invocation_url.clone(),
+                invocation_url_quic: invocation_url_quic.clone(),
                 api,
                 resource_providers: resource_providers
                     .into_iter()
@@ -302,11 +912,20 @@ impl ControllerTask {
                 weight: node_weight,
                 supported_link_types: link_providers.into_iter().map(|p| (p.class, p.provider_id)).collect(),
                 is_proxy: true,
+                cordoned: false,
             },
         );
 
-        self.send_peer_updates(vec![edgeless_api::node_management::UpdatePeersRequest::Add(node_id, invocation_url)])
-            .await;
+        for lc in self.link_controllers.lock().await.values_mut() {
+            lc.add_node(node_id, &invocation_url);
+        }
+
+        self.send_peer_updates(vec![edgeless_api::node_management::UpdatePeersRequest::Add(
+            node_id,
+            invocation_url,
+            invocation_url_quic,
+        )])
+        .await;
 
         // Send information about all nodes to the new node.
         let updates: Vec<_> = self
@@ -319,6 +938,7 @@ impl ControllerTask {
                     Some(edgeless_api::node_management::UpdatePeersRequest::Add(
                         *n_id,
                         n_spec.invocation_url.clone(),
+                        n_spec.invocation_url_quic.clone(),
                     ))
                 } else {
                     None
@@ -343,6 +963,8 @@ impl ControllerTask {
     ) -> anyhow::Result<edgeless_api::node_registration::UpdateNodeResponse> {
         let old_value = self.nodes.lock().await.remove(&node_id);
         if old_value.is_some() {
+            self.remove_node_from_links(node_id).await;
+            self.persist_node_removal(&node_id);
             self.handle_node_removal(&std::collections::HashSet::from_iter(vec![node_id].into_iter()))
                 .await;
             self.send_peer_updates(vec![edgeless_api::node_management::UpdatePeersRequest::Del(node_id)])
@@ -353,6 +975,221 @@ impl ControllerTask {
         }
     }
 
+    /// Removes a node that left the cluster from the link controllers.
+    async fn remove_node_from_links(&mut self, node_id: edgeless_api::function_instance::NodeId) {
+        for lc in self.link_controllers.lock().await.values_mut() {
+            lc.remove_node(node_id);
+        }
+    }
+
+    async fn process_node_maintenance(
+        &mut self,
+        request: edgeless_api::node_maintenance::NodeMaintenanceRequest,
+    ) -> anyhow::Result<edgeless_api::node_maintenance::NodeMaintenanceResponse> {
+        log::info!("node maintenance: {}", request);
+        let node_id = match request {
+            edgeless_api::node_maintenance::NodeMaintenanceRequest::Cordon(node_id)
+            | edgeless_api::node_maintenance::NodeMaintenanceRequest::Uncordon(node_id)
+            | edgeless_api::node_maintenance::NodeMaintenanceRequest::Drain(node_id) => node_id,
+        };
+        let cordoned = !matches!(request, edgeless_api::node_maintenance::NodeMaintenanceRequest::Uncordon(_));
+        if !self.set_node_cordoned(&node_id, cordoned).await {
+            return Ok(edgeless_api::node_maintenance::NodeMaintenanceResponse::ResponseError(ResponseError {
+                summary: "Unknown node.".to_string(),
+                detail: Some(node_id.to_string()),
+            }));
+        }
+
+        if let edgeless_api::node_maintenance::NodeMaintenanceRequest::Drain(node_id) = request {
+            let drained_nodes = std::collections::HashSet::from([node_id]);
+            let mut errors = vec![];
+            for wf_id in self
+                .active_workflows
+                .keys()
+                .cloned()
+                .collect::<Vec<edgeless_api::workflow_instance::WorkflowId>>()
+            {
+                if let Some(wf) = self.active_workflows.get_mut(&wf_id) {
+                    let required_changes = tokio::task::block_in_place(|| wf.node_drain(&drained_nodes));
+                    if let Err(mut errs) = self.materialize(wf_id, required_changes).await {
+                        errors.append(&mut errs);
+                    }
+                }
+            }
+            if !errors.is_empty() {
+                return Ok(edgeless_api::node_maintenance::NodeMaintenanceResponse::ResponseError(ResponseError {
+                    summary: "Failures while draining the node.".to_string(),
+                    detail: Some(errors.join(";")),
+                }));
+            }
+        }
+
+        Ok(edgeless_api::node_maintenance::NodeMaintenanceResponse::Accepted)
+    }
+
+    /// Marks a node as (un)schedulable and saves its registration.
+    /// Returns false if the node is not known.
+    async fn set_node_cordoned(&mut self, node_id: &edgeless_api::function_instance::NodeId, cordoned: bool) -> bool {
+        let record = match self.nodes.lock().await.get_mut(node_id) {
+            Some(node) => {
+                node.cordoned = cordoned;
+                crate::persistence::NodeRecord {
+                    node_id: *node_id,
+                    agent_url: node.agent_url.clone(),
+                    invocation_url: node.invocation_url.clone(),
+                    invocation_url_quic: node.invocation_url_quic.clone(),
+                    resource_providers: node
+                        .resource_providers
+                        .iter()
+                        .map(|(provider_id, r)| (provider_id.clone(), r.class_type.clone(), r.outputs.clone()))
+                        .collect(),
+                    capabilities: node.capabilities.clone(),
+                    link_providers: node.supported_link_types.iter().map(|(class, p)| (p.0, class.0.clone())).collect(),
+                    cordoned,
+                }
+            }
+            None => return false,
+        };
+        if let Err(err) = self.store.put_node(record) {
+            log::error!("could not save the registration of node {}: {}", node_id, err);
+        }
+        true
+    }
+
+    async fn process_cluster_announcement(
+        &mut self,
+        announcement: edgeless_api::cluster_registration::ClusterAnnouncement,
+    ) -> anyhow::Result<edgeless_api::cluster_registration::UpdateClusterResponse> {
+        log::debug!("announcement of peer cluster: {}", announcement);
+        if announcement.cluster_id == self.cluster_id {
+            return Ok(edgeless_api::cluster_registration::UpdateClusterResponse::ResponseError(ResponseError {
+                summary: "A cluster cannot be a peer of itself.".to_string(),
+                detail: None,
+            }));
+        }
+
+        if let Some(cluster) = self.peer_clusters.lock().await.get_mut(&announcement.cluster_id) {
+            if cluster.controller_url == announcement.controller_url {
+                cluster.domain_id = announcement.domain_id;
+                cluster.supported_link_types = announcement.link_providers.into_iter().map(|p| (p.class, p.provider_id)).collect();
+                cluster.last_heartbeat = std::time::Instant::now();
+                return Ok(edgeless_api::cluster_registration::UpdateClusterResponse::Accepted);
+            } else {
+                return Ok(edgeless_api::cluster_registration::UpdateClusterResponse::ResponseError(ResponseError {
+                    summary: "Duplicate ClusterId with different URL.".to_string(),
+                    detail: None,
+                }));
+            }
+        }
+
+        // The controller of the peer cluster may be slow to answer or even
+        // wait for this controller, hence it is connected to by a separate
+        // task, which reports back with a CLUSTERCONNECTED request.
+        // In the meantime, the heartbeats of the peer cluster are rejected,
+        // thus it announces itself again if the connection fails.
+        if self.connecting_clusters.insert(announcement.cluster_id) {
+            let request_sender = self.request_sender.clone();
+            tokio::spawn(async move {
+                let controller_url = announcement.controller_url.clone();
+                // The client panics if it cannot connect to the controller.
+                let mut connection =
+                    tokio::spawn(async move { edgeless_api::grpc_impl::controller::ControllerAPIClient::new(&controller_url).await });
+                let api = match tokio::time::timeout(PEER_CLUSTER_CONNECTION_TIMEOUT, &mut connection).await {
+                    Ok(Ok(api)) => Some(Box::new(api) as Box<dyn edgeless_api::controller::ControllerAPI + Send>),
+                    Ok(Err(_)) => None,
+                    Err(_) => {
+                        connection.abort();
+                        None
+                    }
+                };
+                let _ = request_sender.unbounded_send(super::ControllerRequest::CLUSTERCONNECTED(announcement, api));
+            });
+        }
+
+        Ok(edgeless_api::cluster_registration::UpdateClusterResponse::Accepted)
+    }
+
+    /// Adds a peer cluster once its controller has been connected to, or
+    /// forgets it if the connection failed.
+    async fn process_cluster_connected(
+        &mut self,
+        announcement: edgeless_api::cluster_registration::ClusterAnnouncement,
+        api: Option<Box<dyn edgeless_api::controller::ControllerAPI + Send>>,
+    ) {
+        self.connecting_clusters.remove(&announcement.cluster_id);
+        let api = match api {
+            Some(api) => api,
+            None => {
+                log::warn!(
+                    "could not connect to the controller of peer cluster {} at {}",
+                    announcement.cluster_id,
+                    announcement.controller_url
+                );
+                return;
+            }
+        };
+
+        log::info!(
+            "new peer cluster {} ({}) at {}",
+            announcement.cluster_id,
+            announcement.domain_id,
+            announcement.controller_url
+        );
+        self.peer_clusters.lock().await.insert(
+            announcement.cluster_id,
+            PeerCluster {
+                domain_id: announcement.domain_id,
+                controller_url: announcement.controller_url,
+                api,
+                supported_link_types: announcement.link_providers.into_iter().map(|p| (p.class, p.provider_id)).collect(),
+                last_heartbeat: std::time::Instant::now(),
+            },
+        );
+
+        // Subflows waiting for this cluster can be placed now.
+        for wf_id in self
+            .active_workflows
+            .keys()
+            .cloned()
+            .collect::<Vec<edgeless_api::workflow_instance::WorkflowId>>()
+        {
+            if let Some(wf) = self.active_workflows.get_mut(&wf_id) {
+                let required_changes = tokio::task::block_in_place(|| wf.place_pending_subflows());
+                if let Err(errs) = self.materialize(wf_id, required_changes).await {
+                    log::error!("Failures Handling New Peer Cluster: {}", errs.join(";"));
+                }
+            }
+        }
+    }
+
+    async fn process_cluster_del(
+        &mut self,
+        cluster_id: edgeless_api::function_instance::NodeId,
+    ) -> anyhow::Result<edgeless_api::cluster_registration::UpdateClusterResponse> {
+        let old_value = self.peer_clusters.lock().await.remove(&cluster_id);
+        if old_value.is_some() {
+            log::info!("peer cluster {} deregistered", cluster_id);
+            self.handle_peer_cluster_removal(cluster_id).await;
+        }
+        Ok(edgeless_api::cluster_registration::UpdateClusterResponse::Accepted)
+    }
+
+    async fn process_cluster_heartbeat(
+        &mut self,
+        cluster_id: edgeless_api::function_instance::NodeId,
+    ) -> anyhow::Result<edgeless_api::cluster_registration::UpdateClusterResponse> {
+        match self.peer_clusters.lock().await.get_mut(&cluster_id) {
+            Some(cluster) => {
+                cluster.last_heartbeat = std::time::Instant::now();
+                Ok(edgeless_api::cluster_registration::UpdateClusterResponse::Accepted)
+            }
+            None => Ok(edgeless_api::cluster_registration::UpdateClusterResponse::ResponseError(ResponseError {
+                summary: "Unknown peer cluster.".to_string(),
+                detail: None,
+            })),
+        }
+    }
+
     async fn materialize(
         &mut self,
         wf_id: edgeless_api::workflow_instance::WorkflowId,
@@ -370,9 +1207,19 @@ impl ControllerTask {
                     output_mapping,
                     function_name,
                     annotations,
+                    initial_state,
                 } => {
-                    self.start_workflow_function_on_node(&wf_id, function_name, function_id, image, input_mapping, output_mapping, annotations)
-                        .await
+                    self.start_workflow_function_on_node(
+                        &wf_id,
+                        function_name,
+                        function_id,
+                        image,
+                        input_mapping,
+                        output_mapping,
+                        annotations,
+                        initial_state,
+                    )
+                    .await
                 }
                 RequiredChange::StartResource {
                     resource_id,
@@ -448,9 +1295,21 @@ impl ControllerTask {
                     self.start_proxy_on_node(proxy_id, internal_inputs, internal_outputs, external_inputs, external_outputs)
                         .await
                 }
+                RequiredChange::DrainFunction { function_id, function_name } => self.drain_function(function_id, function_name).await,
+                RequiredChange::StopFunction { function_id, function_name } => {
+                    self.stop_workflow_function_on_node(&wf_id, function_name, function_id).await
+                }
+                RequiredChange::StopResource { resource_id, resource_name } => {
+                    self.stop_workflow_resource_on_node(&wf_id, resource_name, resource_id).await
+                }
+                RequiredChange::RemoveLinkControlPlane { link_id, class } => self.remove_link_control_plane(link_id, class).await,
+                RequiredChange::StopSubflow { subflow_id } => self.stop_subflow_on_cluster(subflow_id).await,
+                RequiredChange::StopProxy { proxy_id } => self.stop_proxy_on_node(proxy_id).await,
             });
         }
 
+        self.persist_workflow(&wf_id);
+
         let mut error_msg = Vec::new();
         for res in results {
             if let Err(msg) = res {
@@ -465,6 +1324,27 @@ impl ControllerTask {
         }
     }
 
+    /// Stops a function instance once it has processed the events in its
+    /// input queue, which is waited for by a separate task.
+    async fn drain_function(&mut self, function_id: edgeless_api::function_instance::InstanceId, function_name: String) -> Result<(), String> {
+        log::info!("draining function {} with fid {}", function_name, function_id);
+        let clients = match self.nodes.lock().await.get_mut(&function_id.node_id) {
+            Some(node) => Some((node.api.node_management_api(), node.api.function_instance_api())),
+            None => None,
+        };
+        let (node_management_client, mut fn_client) = clients.ok_or(format!("No function client for node: {}", function_id.node_id))?;
+        tokio::spawn(async move {
+            if !Self::wait_for_empty_queue(node_management_client, function_id).await {
+                log::warn!("stopping {} with events still in its input queue", function_id);
+            }
+            match fn_client.stop(function_id).await {
+                Ok(_) => log::info!("function {} with fid {} drained and stopped", function_name, function_id),
+                Err(err) => log::warn!("could not stop the drained function {} with fid {}: {}", function_name, function_id, err),
+            }
+        });
+        Ok(())
+    }
+
     async fn start_workflow_function_on_node(
         &mut self,
         wf_id: &edgeless_api::workflow_instance::WorkflowId,
@@ -474,6 +1354,7 @@ impl ControllerTask {
         input_mapping: std::collections::HashMap<edgeless_api::function_instance::PortId, super::super::ir::PhysicalInput>,
         output_mapping: std::collections::HashMap<edgeless_api::function_instance::PortId, super::super::ir::PhysicalOutput>,
         annotations: std::collections::HashMap<String, String>,
+        initial_state: Option<String>,
     ) -> Result<(), String> {
         // [TODO] Issue#95
         // The state_specification configuration should be
@@ -504,6 +1385,7 @@ impl ControllerTask {
                 state_specification: edgeless_api::function_instance::StateSpecification {
                     state_id: uuid::Uuid::new_v4(),
                     state_policy: edgeless_api::function_instance::StatePolicy::NodeLocal,
+                    initial_state,
                 },
                 input_mapping: input_mapping.clone(),
                 output_mapping: output_mapping.clone(),
@@ -565,11 +1447,30 @@ impl ControllerTask {
         subflow_id: edgeless_api::function_instance::InstanceId,
         spawn_req: edgeless_api::workflow_instance::SpawnWorkflowRequest,
     ) -> Result<(), String> {
-        if let Some(cluster) = self.peer_clusters.lock().await.get_mut(&subflow_id.node_id) {
-            cluster.api.workflow_instance_api().start(spawn_req).await.map_err(|e| e.to_string())?;
-            Ok(())
-        } else {
-            Err("Failed to start subflow".to_string())
+        match self
+            .workflow_client(&subflow_id.node_id)
+            .await
+            .ok_or(format!("No workflow client for cluster {}", subflow_id.node_id))?
+            .start(spawn_req)
+            .await
+        {
+            Ok(response) => match response {
+                edgeless_api::workflow_instance::SpawnWorkflowResponse::ResponseError(error) => {
+                    log::warn!("subflow start rejected: {}", error);
+                    Err(format!("subflow start rejected: {} ", error))
+                }
+                edgeless_api::workflow_instance::SpawnWorkflowResponse::WorkflowInstance(instance) => {
+                    log::info!(
+                        "subflow {} started on cluster {} as workflow {}",
+                        subflow_id,
+                        subflow_id.node_id,
+                        instance.workflow_id.to_string()
+                    );
+                    self.subflow_workflows.insert(subflow_id, instance.workflow_id);
+                    Ok(())
+                }
+            },
+            Err(err) => Err(format!("failed interaction when starting subflow: {}", err)),
         }
     }
 
@@ -599,6 +1500,78 @@ impl ControllerTask {
         }
     }
 
+    async fn stop_workflow_function_on_node(
+        &mut self,
+        wf_id: &edgeless_api::workflow_instance::WorkflowId,
+        f_name: String,
+        function_id: edgeless_api::function_instance::InstanceId,
+    ) -> Result<(), String> {
+        match self
+            .fn_client(&function_id.node_id)
+            .await
+            .ok_or(format!("No function client for node: {}", &function_id.node_id))?
+            .stop(function_id)
+            .await
+        {
+            Ok(_) => {
+                log::info!("workflow {} function {} stopped with fid {}", wf_id.to_string(), &f_name, &function_id);
+                Ok(())
+            }
+            Err(err) => Err(format!("failed interaction when stopping function {}: {}", f_name, err)),
+        }
+    }
+
+    async fn stop_workflow_resource_on_node(
+        &mut self,
+        wf_id: &edgeless_api::workflow_instance::WorkflowId,
+        r_name: String,
+        resource_id: edgeless_api::function_instance::InstanceId,
+    ) -> Result<(), String> {
+        match self
+            .resource_client(&resource_id.node_id)
+            .await
+            .ok_or(format!("No resource client for node: {}", &resource_id.node_id))?
+            .stop(resource_id)
+            .await
+        {
+            Ok(_) => {
+                log::info!("workflow {} resource {} stopped with fid {}", wf_id.to_string(), &r_name, &resource_id);
+                Ok(())
+            }
+            Err(err) => Err(format!("failed interaction when stopping resource {}: {}", r_name, err)),
+        }
+    }
+
+    async fn stop_subflow_on_cluster(&mut self, subflow_id: edgeless_api::function_instance::InstanceId) -> Result<(), String> {
+        let workflow_id = self
+            .subflow_workflows
+            .remove(&subflow_id)
+            .ok_or(format!("No workflow started for subflow {}", subflow_id))?;
+        match self
+            .workflow_client(&subflow_id.node_id)
+            .await
+            .ok_or(format!("No workflow client for cluster {}", subflow_id.node_id))?
+            .stop(workflow_id)
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(err) => Err(format!("failed stopping subflow: {}", err)),
+        }
+    }
+
+    async fn stop_proxy_on_node(&mut self, proxy_id: edgeless_api::function_instance::InstanceId) -> Result<(), String> {
+        match self
+            .proxy_client(&proxy_id.node_id)
+            .await
+            .ok_or(format!("No proxy client for node {}", proxy_id.node_id))?
+            .stop(proxy_id)
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(err) => Err(format!("failed stopping proxy: {}", err)),
+        }
+    }
+
     async fn periodic_health_check(&mut self) {
         // First check if there are nodes that must be disconnected
         // because they failed to reply to a keep-alive.
@@ -609,6 +1582,8 @@ impl ControllerTask {
             log::info!("disconnected node not replying to keep-alive: {}", &node_id);
             let val = self.nodes.lock().await.remove(node_id);
             assert!(val.is_some());
+            self.remove_node_from_links(*node_id).await;
+            self.persist_node_removal(node_id);
         }
 
         // Update the peers of (still alive) nodes by
@@ -630,6 +1605,76 @@ impl ControllerTask {
         }
 
         self.handle_node_removal(&to_be_disconnected).await;
+
+        // Then, disconnect the peer clusters that stopped sending heartbeats.
+        let dead_clusters: Vec<_> = self
+            .peer_clusters
+            .lock()
+            .await
+            .iter()
+            .filter_map(|(cluster_id, cluster)| {
+                if cluster.last_heartbeat.elapsed() > PEER_CLUSTER_TIMEOUT {
+                    Some(*cluster_id)
+                } else {
+                    None
+                }
+            })
+            .collect();
+        for cluster_id in dead_clusters {
+            log::info!("disconnected peer cluster not sending heartbeats: {}", cluster_id);
+            self.peer_clusters.lock().await.remove(&cluster_id);
+            self.handle_peer_cluster_removal(cluster_id).await;
+        }
+
+        // Then, pass the runtime statistics of the function instances,
+        // as received with the keep-alive responses, to the workflows.
+        self.update_statistics().await;
+
+        // Finally, if the controller has just been restarted, use the function
+        // instances reported by the nodes to reconcile the restored workflows.
+        if self.recovering {
+            self.recovering = false;
+            self.reconcile().await;
+        }
+    }
+
+    async fn update_statistics(&mut self) {
+        let statistics: std::collections::HashMap<_, _> = self
+            .nodes
+            .lock()
+            .await
+            .iter()
+            .flat_map(|(node_id, client_desc)| {
+                client_desc.health_status.instance_statistics.iter().map(move |instance_statistics| {
+                    (
+                        edgeless_api::function_instance::InstanceId {
+                            node_id: *node_id,
+                            function_id: instance_statistics.function_id,
+                        },
+                        instance_statistics.clone(),
+                    )
+                })
+            })
+            .collect();
+        for wf in self.active_workflows.values_mut() {
+            wf.update_statistics(&statistics);
+        }
+    }
+
+    async fn periodic_autoscaling(&mut self) {
+        for wf_id in self
+            .active_workflows
+            .keys()
+            .cloned()
+            .collect::<Vec<edgeless_api::workflow_instance::WorkflowId>>()
+        {
+            if let Some(wf) = self.active_workflows.get_mut(&wf_id) {
+                let required_changes = tokio::task::block_in_place(|| wf.autoscale());
+                if let Err(errs) = self.materialize(wf_id, required_changes).await {
+                    log::error!("Failures Handling Autoscaling: {}", errs.join(";"));
+                }
+            }
+        }
     }
 
     async fn find_dead_nodes(&mut self) -> std::collections::HashSet<edgeless_api::function_instance::NodeId> {
@@ -647,6 +1692,24 @@ impl ControllerTask {
         dead_nodes
     }
 
+    async fn handle_peer_cluster_removal(&mut self, removed_cluster_id: edgeless_api::function_instance::NodeId) {
+        // The workflows spawned on the cluster are gone with it.
+        self.subflow_workflows.retain(|subflow_id, _| subflow_id.node_id != removed_cluster_id);
+        for wf_id in self
+            .active_workflows
+            .keys()
+            .cloned()
+            .collect::<Vec<edgeless_api::workflow_instance::WorkflowId>>()
+        {
+            if let Some(wf) = self.active_workflows.get_mut(&wf_id) {
+                let required_changes = tokio::task::block_in_place(|| wf.peer_cluster_removal(removed_cluster_id));
+                if let Err(errs) = self.materialize(wf_id, required_changes).await {
+                    log::error!("Failures Handling Peer Cluster Removal: {}", errs.join(";"));
+                }
+            }
+        }
+    }
+
     async fn handle_node_removal(&mut self, removed_nodes: &std::collections::HashSet<edgeless_api::function_instance::NodeId>) {
         for wf_id in self
             .active_workflows
@@ -713,6 +1776,15 @@ impl ControllerTask {
                 }
             }
             super::ComponentType::SubFlow => {
+                // The workflow on the peer cluster is patched as a whole.
+                let workflow_id = self
+                    .subflow_workflows
+                    .get(&origin_id)
+                    .ok_or(format!("No workflow started for subflow {}", origin_id))?;
+                let origin_id = edgeless_api::function_instance::InstanceId {
+                    node_id: origin_id.node_id,
+                    function_id: workflow_id.workflow_id,
+                };
                 match self
                     .workflow_client(&origin_id.node_id)
                     .await
@@ -768,6 +1840,19 @@ impl ControllerTask {
         Ok(())
     }
 
+    async fn remove_link_control_plane(
+        &mut self,
+        link_id: edgeless_api::link::LinkInstanceId,
+        class: edgeless_api::link::LinkType,
+    ) -> Result<(), String> {
+        if let Some(lc) = self.link_controllers.lock().await.get_mut(&class) {
+            lc.remove_link(link_id);
+            Ok(())
+        } else {
+            Err(format!("No link controller for class {}", class.0))
+        }
+    }
+
     async fn create_link_on_node(
         &mut self,
         link_id: edgeless_api::link::LinkInstanceId,
@@ -837,7 +1922,7 @@ impl ControllerTask {
         for (n_id, n_spec) in self.nodes.lock().await.iter_mut() {
             for update in &updates {
                 let is_self = match update {
-                    edgeless_api::node_management::UpdatePeersRequest::Add(id, _) => id == n_id,
+                    edgeless_api::node_management::UpdatePeersRequest::Add(id, _, _) => id == n_id,
                     edgeless_api::node_management::UpdatePeersRequest::Del(id) => id == n_id,
                     edgeless_api::node_management::UpdatePeersRequest::Clear => false,
                 };
@@ -860,3 +1945,78 @@ impl ControllerTask {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    fn controller_task(node_ids: &[edgeless_api::function_instance::NodeId]) -> super::ControllerTask {
+        let (request_sender, request_receiver) = futures::channel::mpsc::unbounded();
+        let task = super::ControllerTask::new(
+            uuid::Uuid::new_v4(),
+            request_receiver,
+            request_sender,
+            crate::orchestration_utils::OrchestrationStrategy::RoundRobin,
+            Box::new(crate::persistence::NoStore {}),
+        );
+        for node_id in node_ids {
+            task.nodes.blocking_lock().insert(
+                *node_id,
+                super::WorkerNode::new_for_test(edgeless_api::node_registration::NodeCapabilities::minimum()),
+            );
+        }
+        task
+    }
+
+    #[test]
+    fn cordoned_node_is_not_a_migration_target() {
+        let node_1 = uuid::Uuid::new_v4();
+        let node_2 = uuid::Uuid::new_v4();
+        let mut task = controller_task(&[node_1, node_2]);
+        task.nodes.blocking_lock().get_mut(&node_2).unwrap().cordoned = true;
+
+        let workflow_id = edgeless_api::workflow_instance::WorkflowId {
+            workflow_id: uuid::Uuid::new_v4(),
+        };
+        let mut wf = crate::ir::managed_worflow::ManagedWorkflow::new(
+            edgeless_api::workflow_instance::SpawnWorkflowRequest {
+                workflow_functions: vec![edgeless_api::workflow_instance::WorkflowFunction {
+                    name: "f1".to_string(),
+                    function_class_specification: edgeless_api::function_instance::FunctionClassSpecification {
+                        function_class_id: "f1".to_string(),
+                        function_class_type: "RUST_WASM".to_string(),
+                        function_class_version: "0.1".to_string(),
+                        ..Default::default()
+                    },
+                    output_mapping: std::collections::HashMap::new(),
+                    input_mapping: std::collections::HashMap::new(),
+                    annotations: std::collections::HashMap::new(),
+                }],
+                workflow_resources: vec![],
+                workflow_ingress_proxies: vec![],
+                workflow_egress_proxies: vec![],
+                annotations: std::collections::HashMap::new(),
+            },
+            workflow_id.clone(),
+            task.orchestration_logic.clone(),
+            task.nodes.clone(),
+            task.peer_clusters.clone(),
+            task.link_controllers.clone(),
+        );
+        wf.initial_spawn();
+        let instance_id = wf.wf.functions["f1"].borrow().instances[0].borrow().id;
+        assert_eq!(instance_id.node_id, node_1);
+        task.active_workflows.insert(workflow_id.clone(), wf);
+
+        let request = edgeless_api::workflow_instance::MigrateFunctionRequest {
+            workflow_id,
+            function_name: "f1".to_string(),
+            source_node_id: node_1,
+            target_node_id: node_2,
+        };
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        let err = runtime.block_on(task.migrated_instance(&request)).unwrap_err();
+        assert!(err.contains("cordoned"), "{}", err);
+
+        task.nodes.blocking_lock().get_mut(&node_2).unwrap().cordoned = false;
+        assert_eq!(runtime.block_on(task.migrated_instance(&request)), Ok(instance_id));
+    }
+}
@@ -99,6 +99,10 @@ impl edgeless_api::function_instance::FunctionInstanceAPI<edgeless_api::function
         self.sender.send(MockFunctionInstanceEvent::Patch(request)).await.unwrap();
         Ok(())
     }
+
+    async fn get_state(&mut self, _id: edgeless_api::function_instance::InstanceId) -> anyhow::Result<Option<String>> {
+        Ok(None)
+    }
 }
 #[async_trait::async_trait]
 impl edgeless_api::resource_configuration::ResourceConfigurationAPI<edgeless_api::function_instance::InstanceId> for MockResourceConfigurationAPI {
@@ -10,6 +10,12 @@ pub struct LogicalActor {
     pub logical_ports: super::LogicalPorts,
 
     pub instances: Vec<std::cell::RefCell<PhysicalActor>>,
+
+    /// Number of instances set by the autoscaler, overriding the one
+    /// derived from the annotations.
+    pub desired_instances: Option<usize>,
+    /// Last time the number of instances was changed by the autoscaler.
+    pub last_scaling: Option<std::time::Instant>,
 }
 
 impl super::LogicalComponent for LogicalActor {
@@ -43,7 +49,10 @@ pub struct PhysicalActor {
     pub(crate) id: edgeless_api::function_instance::InstanceId,
     pub(crate) image: Option<ActorImage>,
     pub(crate) desired_mapping: super::PhysicalPorts,
-    pub(crate) materialized: Option<super::PhysicalPorts>,
+    pub(crate) materialized: Option<MaterializedActor>,
+    /// State handed off to the instance when it is started, if it replaces
+    /// an instance migrated from another node.
+    pub(crate) initial_state: Option<String>,
 }
 
 impl super::PhysicalComponent for PhysicalActor {
@@ -51,8 +60,24 @@ impl super::PhysicalComponent for PhysicalActor {
         &mut self.desired_mapping
     }
 
-    fn materialized_state(&mut self) -> &mut dyn super::MaterializedComponent {
-        todo!()
+    fn materialized_state(&mut self) -> Option<&mut dyn super::MaterializedComponent> {
+        self.materialized.as_mut().map(|m| m as &mut dyn super::MaterializedComponent)
+    }
+}
+
+/// State of a function instance that has been started on a node.
+pub struct MaterializedActor {
+    pub(crate) ports: super::PhysicalPorts,
+    pub(crate) statistics: super::statistics::RuntimeStatistics,
+}
+
+impl super::MaterializedComponent for MaterializedActor {
+    fn materialized_ports(&mut self) -> &mut super::PhysicalPorts {
+        &mut self.ports
+    }
+
+    fn runtime_statistics(&mut self) -> &mut dyn super::ComponentStatistics {
+        &mut self.statistics
     }
 }
 
@@ -106,6 +131,8 @@ impl From<edgeless_api::workflow_instance::WorkflowFunction> for LogicalActor {
                 code: function_req.function_class_specification.function_class_code,
             },
             instances: Vec::new(),
+            desired_instances: None,
+            last_scaling: None,
             annotations: function_req.annotations,
             logical_ports: super::LogicalPorts {
                 logical_input_mapping: function_req
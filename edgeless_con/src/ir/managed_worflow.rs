@@ -34,6 +34,264 @@ impl ManagedWorkflow {
         self.materialize()
     }
 
+    /// Rebuilds the workflow from the placement of its instances, as saved
+    /// before a restart of the controller.
+    /// The instances that were materialized are assumed to be still running
+    /// with the port mapping derived from the placement, until reconciled.
+    pub fn restore(&mut self, record: &crate::persistence::WorkflowRecord) {
+        for instance in &record.functions {
+            if let Some(function) = self.wf.functions.get(&instance.component) {
+                function.borrow_mut().instances.push(std::cell::RefCell::new(super::actor::PhysicalActor {
+                    id: edgeless_api::function_instance::InstanceId {
+                        node_id: instance.node_id,
+                        function_id: instance.function_id,
+                    },
+                    image: None,
+                    desired_mapping: super::PhysicalPorts::default(),
+                    materialized: None,
+                    initial_state: None,
+                }));
+            }
+        }
+        for instance in &record.resources {
+            if let Some(resource) = self.wf.resources.get(&instance.component) {
+                resource
+                    .borrow_mut()
+                    .instances
+                    .push(std::cell::RefCell::new(super::resource::PhysicalResource {
+                        id: edgeless_api::function_instance::InstanceId {
+                            node_id: instance.node_id,
+                            function_id: instance.function_id,
+                        },
+                        desired_mapping: super::PhysicalPorts::default(),
+                        materialized: None,
+                    }));
+            }
+        }
+        for function in self.wf.functions.values() {
+            let mut function = function.borrow_mut();
+            let target_instances = crate::orchestration_utils::DeploymentRequirements::from_annotations(&function.annotations).target_instances();
+            if function.instances.len() > target_instances {
+                function.desired_instances = Some(function.instances.len());
+            }
+        }
+
+        // The subflows only exist once the workflow has been split.
+        self.pipeline.apply_logical(&mut self.wf);
+        for instance in &record.subflows {
+            if let Some(subflow) = self.wf.subflows.get(&instance.component) {
+                subflow
+                    .borrow_mut()
+                    .instances
+                    .push(std::cell::RefCell::new(super::subflow::PhysicalSubFlow {
+                        id: edgeless_api::function_instance::InstanceId {
+                            node_id: instance.node_id,
+                            function_id: instance.function_id,
+                        },
+                        desired_mapping: super::PhysicalPorts::default(),
+                        materialized: None,
+                    }));
+            }
+        }
+
+        // The links are not used anymore by the instances, whose mapping is
+        // computed again, but they must be removed when the workflow stops.
+        for link in &record.links {
+            let link_id = edgeless_api::link::LinkInstanceId(link.link_id);
+            self.wf.links.insert(
+                link_id.clone(),
+                super::link::WorkflowLink {
+                    id: link_id,
+                    class: edgeless_api::link::LinkType(link.class.clone()),
+                    materialized: link.materialized,
+                    nodes: link
+                        .nodes
+                        .iter()
+                        .map(|(node_id, provider_id, config, materialized)| {
+                            (*node_id, edgeless_api::link::LinkProviderId(*provider_id), config.clone(), *materialized)
+                        })
+                        .collect(),
+                },
+            );
+        }
+
+        self.pipeline.apply_all(&mut self.wf);
+
+        let materialized: std::collections::HashSet<_> = record
+            .functions
+            .iter()
+            .chain(record.resources.iter())
+            .filter(|instance| instance.materialized)
+            .map(|instance| instance.function_id)
+            .collect();
+        for function in self.wf.functions.values() {
+            let function = function.borrow_mut();
+            let policy = crate::orchestration_utils::AutoscalingPolicy::from_annotations(&function.annotations);
+            for i in &function.instances {
+                let mut current = i.borrow_mut();
+                if materialized.contains(&current.id.function_id) {
+                    current.materialized = Some(super::actor::MaterializedActor {
+                        ports: super::PhysicalPorts {
+                            physical_input_mapping: current.desired_mapping.physical_input_mapping.clone(),
+                            physical_output_mapping: current.desired_mapping.physical_output_mapping.clone(),
+                        },
+                        statistics: super::statistics::RuntimeStatistics::new(policy.soft_limit_ms, policy.hard_limit_ms),
+                    });
+                }
+            }
+        }
+        for resource in self.wf.resources.values() {
+            for i in &resource.borrow_mut().instances {
+                let mut current = i.borrow_mut();
+                if materialized.contains(&current.id.function_id) {
+                    current.materialized = Some(super::PhysicalPorts {
+                        physical_input_mapping: current.desired_mapping.physical_input_mapping.clone(),
+                        physical_output_mapping: current.desired_mapping.physical_output_mapping.clone(),
+                    });
+                }
+            }
+        }
+        for subflow in self.wf.subflows.values() {
+            for i in &subflow.borrow_mut().instances {
+                let mut current = i.borrow_mut();
+                if record
+                    .subflows
+                    .iter()
+                    .any(|instance| instance.materialized && instance.function_id == current.id.function_id)
+                {
+                    current.materialized = Some(super::PhysicalPorts {
+                        physical_input_mapping: current.desired_mapping.physical_input_mapping.clone(),
+                        physical_output_mapping: current.desired_mapping.physical_output_mapping.clone(),
+                    });
+                }
+            }
+        }
+    }
+
+    /// Reconciles the restored workflow with the actual state of the nodes:
+    /// the instances on nodes that are not available anymore are placed
+    /// again and the function instances that are not running are restarted.
+    pub fn reconcile(
+        &mut self,
+        available_nodes: &std::collections::HashSet<edgeless_api::function_instance::NodeId>,
+        running_functions: &std::collections::HashSet<edgeless_api::function_instance::InstanceId>,
+    ) -> Vec<super::RequiredChange> {
+        let missing_nodes: std::collections::HashSet<_> = self
+            .wf
+            .components()
+            .into_iter()
+            .flat_map(|(_, c)| c.borrow_mut().instance_ids())
+            .map(|id| id.node_id)
+            .filter(|node_id| !available_nodes.contains(node_id))
+            .collect();
+        let mut changed = self.remove_nodes(&missing_nodes);
+
+        for function in self.wf.functions.values() {
+            for i in &function.borrow().instances {
+                let mut current = i.borrow_mut();
+                if current.materialized.is_some() && !running_functions.contains(&current.id) {
+                    current.materialized = None;
+                    changed = true;
+                }
+            }
+        }
+
+        if changed {
+            self.pipeline.apply_all(&mut self.wf);
+            self.materialize()
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Placement and materialization state of the workflow, to be saved.
+    /// The workflows spawned for the subflows are not known here.
+    pub fn record(&self) -> crate::persistence::WorkflowRecord {
+        crate::persistence::WorkflowRecord {
+            workflow_id: self.wf.id.workflow_id,
+            spawn_request: self.wf.original_request.clone(),
+            functions: self
+                .wf
+                .functions
+                .iter()
+                .flat_map(|(f_name, function)| {
+                    function
+                        .borrow()
+                        .instances
+                        .iter()
+                        .map(|i| {
+                            let current = i.borrow();
+                            crate::persistence::InstanceRecord {
+                                component: f_name.clone(),
+                                node_id: current.id.node_id,
+                                function_id: current.id.function_id,
+                                materialized: current.materialized.is_some(),
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .collect(),
+            resources: self
+                .wf
+                .resources
+                .iter()
+                .flat_map(|(r_name, resource)| {
+                    resource
+                        .borrow()
+                        .instances
+                        .iter()
+                        .map(|i| {
+                            let current = i.borrow();
+                            crate::persistence::InstanceRecord {
+                                component: r_name.clone(),
+                                node_id: current.id.node_id,
+                                function_id: current.id.function_id,
+                                materialized: current.materialized.is_some(),
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .collect(),
+            subflows: self
+                .wf
+                .subflows
+                .iter()
+                .flat_map(|(s_name, subflow)| {
+                    subflow
+                        .borrow()
+                        .instances
+                        .iter()
+                        .map(|i| {
+                            let current = i.borrow();
+                            crate::persistence::InstanceRecord {
+                                component: s_name.clone(),
+                                node_id: current.id.node_id,
+                                function_id: current.id.function_id,
+                                materialized: current.materialized.is_some(),
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .collect(),
+            subflow_workflows: Vec::new(),
+            links: self
+                .wf
+                .links
+                .values()
+                .map(|link| crate::persistence::LinkRecord {
+                    link_id: link.id.0,
+                    class: link.class.0.clone(),
+                    materialized: link.materialized,
+                    nodes: link
+                        .nodes
+                        .iter()
+                        .map(|(node_id, provider_id, config, materialized)| (*node_id, provider_id.0, config.clone(), *materialized))
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+
     pub fn node_removal(
         &mut self,
         removed_node_ids: &std::collections::HashSet<edgeless_api::function_instance::NodeId>,
@@ -46,6 +304,50 @@ impl ManagedWorkflow {
         }
     }
 
+    /// Places elsewhere the function and resource instances on nodes that
+    /// are taken out of service while still running.
+    /// Unlike node_removal(), the old instances are stopped, which happens
+    /// after the new ones have been started and the other components have
+    /// been patched to use them, and so are the links that are not used
+    /// anymore.
+    pub fn node_drain(
+        &mut self,
+        drained_node_ids: &std::collections::HashSet<edgeless_api::function_instance::NodeId>,
+    ) -> Vec<super::RequiredChange> {
+        let mut stopped = Vec::new();
+        for (f_name, function) in &self.wf.functions {
+            for i in &function.borrow().instances {
+                let current = i.borrow();
+                if drained_node_ids.contains(&current.id.node_id) && current.materialized.is_some() {
+                    stopped.push(super::RequiredChange::StopFunction {
+                        function_id: current.id,
+                        function_name: f_name.clone(),
+                    });
+                }
+            }
+        }
+        for (r_name, resource) in &self.wf.resources {
+            for i in &resource.borrow().instances {
+                let current = i.borrow();
+                if drained_node_ids.contains(&current.id.node_id) && current.materialized.is_some() {
+                    stopped.push(super::RequiredChange::StopResource {
+                        resource_id: current.id,
+                        resource_name: r_name.clone(),
+                    });
+                }
+            }
+        }
+
+        if !self.remove_nodes(drained_node_ids) {
+            return Vec::new();
+        }
+        self.pipeline.apply_all(&mut self.wf);
+        let mut changes = self.materialize_patches_last();
+        changes.append(&mut stopped);
+        changes.append(&mut self.remove_unused_links());
+        changes
+    }
+
     pub fn patch_external_links(&mut self, update: edgeless_api::common::PatchRequest) -> Vec<super::RequiredChange> {
         {
             let mut prx = self.wf.proxy.borrow_mut();
@@ -56,13 +358,475 @@ impl ManagedWorkflow {
         self.materialize()
     }
 
-    pub fn peer_cluster_removal(&self, removed_cluster_ids: edgeless_api::function_instance::NodeId) -> Vec<super::RequiredChange> {
-        Vec::new()
+    /// Replaces the description of the workflow with a new one.
+    /// The instances of the functions and resources whose specification is
+    /// unchanged are kept and only patched if their mapping changes, whereas
+    /// the other ones are replaced by new instances.
+    /// The same holds for the subflows, whose workflows on the peer
+    /// clusters are kept if their functions and ports are unchanged.
+    /// The new instances are started first, then the existing ones are
+    /// patched to use them, and finally the instances and links not needed
+    /// anymore are removed, so that events are not dropped in between.
+    pub fn update(&mut self, request: edgeless_api::workflow_instance::SpawnWorkflowRequest) -> Vec<super::RequiredChange> {
+        let mut updated = super::workflow::ActiveWorkflow::new(request, self.wf.id.clone());
+        let mut stopped = Vec::new();
+
+        for (f_name, function) in self.wf.functions.drain() {
+            let mut function = function.into_inner();
+            let old_spec = self.wf.original_request.workflow_functions.iter().find(|f| f.name == f_name);
+            let new_spec = updated.original_request.workflow_functions.iter().find(|f| f.name == f_name);
+            let unchanged = match (old_spec, new_spec) {
+                (Some(old_spec), Some(new_spec)) => {
+                    old_spec.function_class_specification == new_spec.function_class_specification && old_spec.annotations == new_spec.annotations
+                }
+                _ => false,
+            };
+            match updated.functions.get(&f_name) {
+                Some(new_function) if unchanged => {
+                    // The mapping is computed again from scratch.
+                    for i in &function.instances {
+                        i.borrow_mut().desired_mapping = super::PhysicalPorts::default();
+                    }
+                    let mut new_function = new_function.borrow_mut();
+                    new_function.instances = std::mem::take(&mut function.instances);
+                    new_function.desired_instances = function.desired_instances;
+                    new_function.last_scaling = function.last_scaling;
+                }
+                _ => {
+                    for i in function.instances.drain(..) {
+                        let current = i.into_inner();
+                        if current.materialized.is_some() {
+                            stopped.push(super::RequiredChange::StopFunction {
+                                function_id: current.id,
+                                function_name: f_name.clone(),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        for (r_name, resource) in self.wf.resources.drain() {
+            let mut resource = resource.into_inner();
+            match updated.resources.get(&r_name) {
+                Some(new_resource)
+                    if new_resource.borrow().class == resource.class && new_resource.borrow().configurations == resource.configurations =>
+                {
+                    for i in &resource.instances {
+                        i.borrow_mut().desired_mapping = super::PhysicalPorts::default();
+                    }
+                    new_resource.borrow_mut().instances = std::mem::take(&mut resource.instances);
+                }
+                _ => {
+                    for i in resource.instances.drain(..) {
+                        let current = i.into_inner();
+                        if current.materialized.is_some() {
+                            stopped.push(super::RequiredChange::StopResource {
+                                resource_id: current.id,
+                                resource_name: r_name.clone(),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        // The workflows spawned on the peer clusters are kept only if the
+        // subflow split from the new description is the same.
+        self.pipeline.apply_logical(&mut updated);
+        for (s_name, subflow) in self.wf.subflows.drain() {
+            let mut subflow = subflow.into_inner();
+            match updated.subflows.get(&s_name) {
+                Some(new_subflow) if Self::same_subflow(&subflow, &new_subflow.borrow()) => {
+                    for i in &subflow.instances {
+                        i.borrow_mut().desired_mapping = super::PhysicalPorts::default();
+                    }
+                    new_subflow.borrow_mut().instances = std::mem::take(&mut subflow.instances);
+                }
+                _ => {
+                    for i in subflow.instances.drain(..) {
+                        let current = i.into_inner();
+                        if current.materialized.is_some() {
+                            stopped.push(super::RequiredChange::StopSubflow { subflow_id: current.id });
+                        }
+                    }
+                }
+            }
+        }
+
+        // Proxies and links are not part of the description.
+        updated.links = std::mem::take(&mut self.wf.links);
+        std::mem::swap(&mut updated.proxy, &mut self.wf.proxy);
+        self.wf = updated;
+
+        self.pipeline.apply_all(&mut self.wf);
+        let mut changes = self.materialize_patches_last();
+        changes.append(&mut stopped);
+        changes.append(&mut self.remove_unused_links());
+        changes
+    }
+
+    fn same_subflow(old: &super::subflow::LogicalSubFlow, new: &super::subflow::LogicalSubFlow) -> bool {
+        old.functions.len() == new.functions.len()
+            && old
+                .functions
+                .iter()
+                .all(|(f_name, function)| new.functions.get(f_name).is_some_and(|new_function| new_function.spec == function.spec))
+            && old.internal_ports == new.internal_ports
+    }
+
+    /// Removes the links that are not used by any instance anymore, which
+    /// must happen after the instances using them have been patched or
+    /// stopped.
+    fn remove_unused_links(&mut self) -> Vec<super::RequiredChange> {
+        let mut used = std::collections::HashSet::new();
+        for (_, component) in self.wf.components() {
+            let mut component = component.borrow_mut();
+            for i in component.instances() {
+                let mut current = i.borrow_mut();
+                let ports = current.physical_ports();
+                used.extend(ports.physical_output_mapping.values().filter_map(|output| match output {
+                    super::PhysicalOutput::Link(link_id) => Some(link_id.clone()),
+                    _ => None,
+                }));
+                used.extend(ports.physical_input_mapping.values().map(|input| match input {
+                    super::PhysicalInput::Link(link_id) => link_id.clone(),
+                }));
+            }
+        }
+
+        let unused: Vec<_> = self.wf.links.keys().filter(|link_id| !used.contains(*link_id)).cloned().collect();
+        let mut changes = Vec::new();
+        for link_id in unused {
+            if let Some(link) = self.wf.links.remove(&link_id) {
+                changes.append(&mut Self::link_removal(link_id, link));
+            }
+        }
+        changes
+    }
+
+    fn link_removal(link_id: edgeless_api::link::LinkInstanceId, link: super::link::WorkflowLink) -> Vec<super::RequiredChange> {
+        let mut changes = Vec::new();
+        for (node, _, _, node_materialized) in &link.nodes {
+            if *node_materialized {
+                changes.push(super::RequiredChange::RemoveLinkFromNode {
+                    link_id: link_id.clone(),
+                    node_id: *node,
+                });
+            }
+        }
+        if link.materialized {
+            changes.push(super::RequiredChange::RemoveLinkControlPlane { link_id, class: link.class });
+        }
+        changes
+    }
+
+    /// Records the runtime statistics reported by the nodes for the
+    /// materialized function instances of the workflow.
+    /// Instances that discarded events because their input queue was full
+    /// since the previous report are logged as saturated, which autoscale()
+    /// acts upon.
+    pub fn update_statistics(
+        &mut self,
+        statistics: &std::collections::HashMap<edgeless_api::function_instance::InstanceId, edgeless_api::node_management::InstanceStatistics>,
+    ) {
+        for (f_name, function) in &self.wf.functions {
+            for i in &function.borrow().instances {
+                let mut current = i.borrow_mut();
+                let current = &mut *current;
+                if let (Some(materialized), Some(instance_statistics)) = (&mut current.materialized, statistics.get(&current.id)) {
+                    let dropped_before = super::ComponentStatistics::dropped_events(&materialized.statistics, None);
+                    materialized.statistics.record(instance_statistics);
+                    let dropped = super::ComponentStatistics::dropped_events(&materialized.statistics, None).saturating_sub(dropped_before);
+                    if dropped > 0 {
+                        log::warn!(
+                            "instance {:?} of function {} is saturated: {} events queued, {} dropped since the last report",
+                            current.id,
+                            f_name,
+                            super::ComponentStatistics::queue_depth(&materialized.statistics),
+                            dropped
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// Adds or removes one instance of the functions whose runtime statistics
+    /// cross the thresholds of their autoscaling policy.
+    /// The first decision for a function is taken after a full observation
+    /// window, and so is every decision after a scaling action, unless the
+    /// hard limit is exceeded or an instance is saturated, i.e., it discarded
+    /// events because its input queue was full, since the last scaling action.
+    /// A function is not scaled in while events are queued at its instances.
+    pub fn autoscale(&mut self) -> Vec<super::RequiredChange> {
+        let now = std::time::Instant::now();
+        let mut stopped = Vec::new();
+        let mut changed = false;
+
+        for (f_name, function) in &self.wf.functions {
+            let mut function = function.borrow_mut();
+            let policy = crate::orchestration_utils::AutoscalingPolicy::from_annotations(&function.annotations);
+            if !policy.is_enabled() || function.instances.is_empty() {
+                continue;
+            }
+            let last_scaling = match function.last_scaling {
+                Some(last_scaling) => last_scaling,
+                None => {
+                    function.last_scaling = Some(now);
+                    continue;
+                }
+            };
+            let since_last_scaling = now.duration_since(last_scaling);
+            let cooling_down = since_last_scaling < policy.window;
+
+            let mut invocation_rates = Vec::new();
+            let mut soft_limit_score = 0.0_f64;
+            let mut hard_limit_score = 0.0_f64;
+            let mut dropped_events = 0;
+            let mut queued_events = 0;
+            let mut port_invocations = std::collections::HashMap::<edgeless_api::function_instance::PortId, u64>::new();
+            for i in &function.instances {
+                let mut current = i.borrow_mut();
+                if let Some(state) = super::PhysicalComponent::materialized_state(&mut *current) {
+                    let recent = Some(std::cmp::min(since_last_scaling, policy.window));
+                    dropped_events += state.runtime_statistics().dropped_events(recent);
+                    queued_events += state.runtime_statistics().queue_depth();
+                    let metric = state.runtime_statistics().processing_duration();
+                    soft_limit_score = soft_limit_score.max(metric.soft_limit_score(Some(policy.window)));
+                    hard_limit_score = hard_limit_score.max(metric.hard_limit_score(recent));
+                    let invocations = metric.invocations(Some(policy.window));
+                    for (port, port_count) in metric.invocations_by_port(Some(policy.window)) {
+                        *port_invocations.entry(port).or_default() += port_count;
+                    }
+                    let observed = current
+                        .materialized
+                        .as_ref()
+                        .map(|m| m.statistics.observed(Some(policy.window)))
+                        .unwrap_or_default();
+                    if !observed.is_zero() {
+                        invocation_rates.push(invocations as f64 / observed.as_secs_f64());
+                    }
+                }
+            }
+            if invocation_rates.is_empty() {
+                continue;
+            }
+            let invocation_rate = invocation_rates.iter().sum::<f64>() / invocation_rates.len() as f64;
+
+            let requirements = crate::orchestration_utils::DeploymentRequirements::from_annotations(&function.annotations);
+            let num_instances = function.instances.len();
+            let can_scale_out = requirements.max_instances == 0 || num_instances < requirements.max_instances;
+            let can_scale_in = num_instances > requirements.target_instances();
+            let overloaded = policy.scale_out_rate.is_some_and(|threshold| invocation_rate > threshold) || soft_limit_score > 1.0;
+            let saturated = dropped_events > 0;
+            let underloaded = policy.scale_in_rate.is_some_and(|threshold| invocation_rate < threshold)
+                && soft_limit_score <= 1.0
+                && !saturated
+                && queued_events == 0;
+
+            if can_scale_out && (hard_limit_score > 1.0 || saturated || (!cooling_down && overloaded)) {
+                let busiest_input = port_invocations
+                    .into_iter()
+                    .max_by_key(|(_, port_count)| *port_count)
+                    .map(|(port, _)| port.0)
+                    .unwrap_or_default();
+                log::info!(
+                    "scaling out {} in workflow {} to {} instances, invocation rate {:.2}/s (busiest input: {}), soft limit score {:.2}, hard limit score {:.2}, queued events {}, dropped events {}",
+                    f_name,
+                    self.wf.id.workflow_id,
+                    num_instances + 1,
+                    invocation_rate,
+                    busiest_input,
+                    soft_limit_score,
+                    hard_limit_score,
+                    queued_events,
+                    dropped_events
+                );
+                function.desired_instances = Some(num_instances + 1);
+                function.last_scaling = Some(now);
+                changed = true;
+            } else if can_scale_in && !cooling_down && underloaded {
+                log::info!(
+                    "scaling in {} in workflow {} to {} instances, invocation rate {:.2}/s",
+                    f_name,
+                    self.wf.id.workflow_id,
+                    num_instances - 1,
+                    invocation_rate
+                );
+                function.desired_instances = Some(num_instances - 1);
+                function.last_scaling = Some(now);
+                if let Some(removed) = function.instances.pop() {
+                    let removed = removed.into_inner();
+                    if removed.materialized.is_some() {
+                        stopped.push(super::RequiredChange::StopFunction {
+                            function_id: removed.id,
+                            function_name: f_name.clone(),
+                        });
+                    }
+                }
+                changed = true;
+            }
+        }
+
+        if !changed {
+            return Vec::new();
+        }
+
+        // The instances removed are stopped only after the other components
+        // have been patched not to use them anymore.
+        self.pipeline.apply_all(&mut self.wf);
+        let mut changes = self.materialize();
+        changes.append(&mut stopped);
+        changes
+    }
+
+    /// Moves an instance of a function to another node.
+    /// The new instance is started with the given state, i.e., the one
+    /// synced by the instance moved after processing the events in its
+    /// queue, then the other components are patched to use it, and finally
+    /// the instance moved is drained and stopped, and the links that it
+    /// used alone are removed.
+    /// The state synced by the instance moved while processing the events
+    /// received before the patches is not handed off.
+    /// Returns an empty set of changes if the instance does not exist.
+    pub fn migrate(
+        &mut self,
+        function_name: &str,
+        instance_id: edgeless_api::function_instance::InstanceId,
+        target_node_id: edgeless_api::function_instance::NodeId,
+        state: Option<String>,
+    ) -> Vec<super::RequiredChange> {
+        let migrated = match self.wf.functions.get(function_name) {
+            Some(function) => {
+                let mut function = function.borrow_mut();
+                let pos = match function.instances.iter().position(|i| i.borrow().id == instance_id) {
+                    Some(pos) => pos,
+                    None => return Vec::new(),
+                };
+                let migrated = function.instances.remove(pos).into_inner();
+                function.instances.insert(
+                    pos,
+                    std::cell::RefCell::new(super::actor::PhysicalActor {
+                        id: edgeless_api::function_instance::InstanceId::new(target_node_id),
+                        image: migrated.image.clone(),
+                        desired_mapping: super::PhysicalPorts::default(),
+                        materialized: None,
+                        initial_state: state,
+                    }),
+                );
+                migrated
+            }
+            None => return Vec::new(),
+        };
+
+        self.pipeline.apply_all(&mut self.wf);
+        let mut changes = self.materialize_patches_last();
+        if migrated.materialized.is_some() {
+            changes.push(super::RequiredChange::DrainFunction {
+                function_id: migrated.id,
+                function_name: function_name.to_string(),
+            });
+        }
+        changes.append(&mut self.remove_unused_links());
+        changes
+    }
+
+    /// Places again the subflows that were spawned on a peer cluster that
+    /// is gone, if another peer cluster can host them.
+    pub fn peer_cluster_removal(&mut self, removed_cluster_id: edgeless_api::function_instance::NodeId) -> Vec<super::RequiredChange> {
+        let mut changed = false;
+        for subflow in self.wf.subflows.values() {
+            let mut subflow = subflow.borrow_mut();
+            let before = subflow.instances.len();
+            subflow.instances.retain(|i| i.borrow().id.node_id != removed_cluster_id);
+            changed = changed || subflow.instances.len() != before;
+        }
+        if changed {
+            self.pipeline.apply_all(&mut self.wf);
+            self.materialize()
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Places the subflows that have not found a peer cluster yet.
+    pub fn place_pending_subflows(&mut self) -> Vec<super::RequiredChange> {
+        if self.wf.subflows.values().any(|subflow| subflow.borrow().instances.is_empty()) {
+            self.pipeline.apply_all(&mut self.wf);
+            self.materialize()
+        } else {
+            Vec::new()
+        }
     }
 
+    /// Tears down all materialized components of the workflow.
+    /// Components are stopped before the links they use are removed.
     pub fn stop(&mut self) -> Vec<super::RequiredChange> {
-        // TODO
-        Vec::new()
+        let mut changes = Vec::new();
+
+        for (f_name, function) in &self.wf.functions {
+            for i in function.borrow_mut().instances.drain(..) {
+                let current = i.into_inner();
+                if current.materialized.is_some() {
+                    changes.push(super::RequiredChange::StopFunction {
+                        function_id: current.id,
+                        function_name: f_name.clone(),
+                    });
+                }
+            }
+        }
+
+        for (r_name, resource) in &self.wf.resources {
+            for i in resource.borrow_mut().instances.drain(..) {
+                let current = i.into_inner();
+                if current.materialized.is_some() {
+                    changes.push(super::RequiredChange::StopResource {
+                        resource_id: current.id,
+                        resource_name: r_name.clone(),
+                    });
+                }
+            }
+        }
+
+        for subflow in self.wf.subflows.values() {
+            for i in subflow.borrow_mut().instances.drain(..) {
+                let current = i.into_inner();
+                if current.materialized.is_some() {
+                    changes.push(super::RequiredChange::StopSubflow { subflow_id: current.id });
+                }
+            }
+        }
+
+        for i in self.wf.proxy.borrow_mut().instances.drain(..) {
+            let current = i.into_inner();
+            if current.materialized.is_some() {
+                changes.push(super::RequiredChange::StopProxy { proxy_id: current.id });
+            }
+        }
+
+        for (link_id, link) in self.wf.links.drain() {
+            changes.append(&mut Self::link_removal(link_id, link));
+        }
+
+        changes
+    }
+
+    /// Same as materialize(), but the components are patched only after the
+    /// new instances have been started, so that no events are sent to
+    /// instances that do not exist yet.
+    fn materialize_patches_last(&mut self) -> Vec<super::RequiredChange> {
+        let (mut changes, mut patches): (Vec<_>, Vec<_>) = self.materialize().into_iter().partition(|change| {
+            !matches!(
+                change,
+                super::RequiredChange::PatchFunction { .. }
+                    | super::RequiredChange::PatchResource { .. }
+                    | super::RequiredChange::PatchSubflow { .. }
+                    | super::RequiredChange::PatchProxy { .. }
+            )
+        });
+        changes.append(&mut patches);
+        changes
     }
 
     fn materialize(&mut self) -> Vec<super::RequiredChange> {
@@ -74,27 +838,31 @@ impl ManagedWorkflow {
                     link_id: link_id.clone(),
                     class: link.class.clone(),
                 });
+                link.materialized = true;
             }
 
-            for (node, link_provider_id, node_config, node_materialized) in &link.nodes {
-                if !node_materialized {
+            for (node, link_provider_id, node_config, node_materialized) in &mut link.nodes {
+                if !*node_materialized {
                     changes.push(super::RequiredChange::CreateLinkOnNode {
                         node_id: *node,
                         provider_id: link_provider_id.clone(),
                         link_id: link_id.clone(),
                         config: node_config.clone(),
                     });
+                    *node_materialized = true;
                 }
             }
         }
 
         for (f_name, function) in &self.wf.functions {
             let function = function.borrow_mut();
+            let policy = crate::orchestration_utils::AutoscalingPolicy::from_annotations(&function.annotations);
             for i in function.instances.iter() {
                 let mut current = i.borrow_mut();
-                if let Some(materialized) = &current.materialized {
-                    if materialized.physical_input_mapping != current.desired_mapping.physical_input_mapping
-                        || materialized.physical_output_mapping != current.desired_mapping.physical_output_mapping
+                let current = &mut *current;
+                if let Some(materialized) = &mut current.materialized {
+                    if materialized.ports.physical_input_mapping != current.desired_mapping.physical_input_mapping
+                        || materialized.ports.physical_output_mapping != current.desired_mapping.physical_output_mapping
                     {
                         changes.push(super::RequiredChange::PatchFunction {
                             function_id: current.id,
@@ -102,6 +870,10 @@ impl ManagedWorkflow {
                             input_mapping: current.desired_mapping.physical_input_mapping.clone(),
                             output_mapping: current.desired_mapping.physical_output_mapping.clone(),
                         });
+                        materialized.ports = super::PhysicalPorts {
+                            physical_input_mapping: current.desired_mapping.physical_input_mapping.clone(),
+                            physical_output_mapping: current.desired_mapping.physical_output_mapping.clone(),
+                        };
                     }
                 } else {
                     changes.push(super::RequiredChange::StartFunction {
@@ -115,10 +887,14 @@ impl ManagedWorkflow {
                         input_mapping: current.desired_mapping.physical_input_mapping.clone(),
                         output_mapping: current.desired_mapping.physical_output_mapping.clone(),
                         annotations: function.annotations.clone(),
+                        initial_state: current.initial_state.take(),
                     });
-                    current.materialized = Some(super::PhysicalPorts {
-                        physical_input_mapping: current.desired_mapping.physical_input_mapping.clone(),
-                        physical_output_mapping: current.desired_mapping.physical_output_mapping.clone(),
+                    current.materialized = Some(super::actor::MaterializedActor {
+                        ports: super::PhysicalPorts {
+                            physical_input_mapping: current.desired_mapping.physical_input_mapping.clone(),
+                            physical_output_mapping: current.desired_mapping.physical_output_mapping.clone(),
+                        },
+                        statistics: super::statistics::RuntimeStatistics::new(policy.soft_limit_ms, policy.hard_limit_ms),
                     });
                 }
             }
@@ -138,6 +914,10 @@ impl ManagedWorkflow {
                             input_mapping: current.desired_mapping.physical_input_mapping.clone(),
                             output_mapping: current.desired_mapping.physical_output_mapping.clone(),
                         });
+                        current.materialized = Some(super::PhysicalPorts {
+                            physical_input_mapping: current.desired_mapping.physical_input_mapping.clone(),
+                            physical_output_mapping: current.desired_mapping.physical_output_mapping.clone(),
+                        });
                     }
                 } else {
                     changes.push(super::RequiredChange::StartResource {
@@ -159,7 +939,7 @@ impl ManagedWorkflow {
         for (_s_name, subflow) in &mut self.wf.subflows {
             let subflow = subflow.borrow_mut();
             for i in &subflow.instances {
-                let current = i.borrow_mut();
+                let mut current = i.borrow_mut();
                 if let Some(materialized) = &current.materialized {
                     if materialized.physical_input_mapping != current.desired_mapping.physical_input_mapping
                         || materialized.physical_output_mapping != current.desired_mapping.physical_output_mapping
@@ -169,39 +949,50 @@ impl ManagedWorkflow {
                             input_mapping: current.desired_mapping.physical_input_mapping.clone(),
                             output_mapping: current.desired_mapping.physical_output_mapping.clone(),
                         });
+                        current.materialized = Some(super::PhysicalPorts {
+                            physical_input_mapping: current.desired_mapping.physical_input_mapping.clone(),
+                            physical_output_mapping: current.desired_mapping.physical_output_mapping.clone(),
+                        });
                     }
                 } else {
                     changes.push(super::RequiredChange::CreateSubflow {
                         subflow_id: current.id,
                         spawn_req: edgeless_api::workflow_instance::SpawnWorkflowRequest {
-                            workflow_functions: Vec::new(),
+                            workflow_functions: subflow.functions.values().map(|f| f.spec.clone()).collect(),
                             workflow_resources: Vec::new(),
-                            workflow_ingress_proxies: current
-                                .desired_mapping
-                                .physical_input_mapping
+                            workflow_ingress_proxies: subflow
+                                .internal_ports
+                                .internal_input_mapping
                                 .iter()
-                                .map(|(id, physical_port)| edgeless_api::workflow_instance::WorkflowIngressProxy {
+                                .map(|(id, inner_output)| edgeless_api::workflow_instance::WorkflowIngressProxy {
                                     id: id.0.clone(),
-                                    inner_output: subflow.logical_ports.logical_output_mapping.get(id).unwrap().clone(),
-                                    external_input: physical_port.clone(),
+                                    inner_output: inner_output.clone(),
+                                    external_input: current.desired_mapping.physical_input_mapping.get(id).cloned(),
                                 })
                                 .collect(),
-                            workflow_egress_proxies: current
-                                .desired_mapping
-                                .physical_output_mapping
+                            workflow_egress_proxies: subflow
+                                .internal_ports
+                                .internal_output_mapping
                                 .iter()
-                                .map(|(id, physical_port)| edgeless_api::workflow_instance::WorkflowEgressProxy {
-                                    id: id.0.clone(),
-                                    inner_input: match subflow.logical_ports.logical_input_mapping.get(id).unwrap().clone() {
-                                        super::LogicalInput::Direct(vec) => edgeless_api::workflow_instance::PortMapping::AnyOfTargets(vec),
-                                        super::LogicalInput::Topic(topic) => edgeless_api::workflow_instance::PortMapping::Topic(topic),
-                                    },
-                                    external_output: physical_port.clone(),
+                                .filter_map(|(id, inner_input)| {
+                                    let external_output = current.desired_mapping.physical_output_mapping.get(id)?;
+                                    Some(edgeless_api::workflow_instance::WorkflowEgressProxy {
+                                        id: id.0.clone(),
+                                        inner_input: match inner_input.clone() {
+                                            super::LogicalInput::Direct(vec) => edgeless_api::workflow_instance::PortMapping::AnyOfTargets(vec),
+                                            super::LogicalInput::Topic(topic) => edgeless_api::workflow_instance::PortMapping::Topic(topic),
+                                        },
+                                        external_output: external_output.clone(),
+                                    })
                                 })
                                 .collect(),
                             annotations: std::collections::HashMap::new(),
                         },
                     });
+                    current.materialized = Some(super::PhysicalPorts {
+                        physical_input_mapping: current.desired_mapping.physical_input_mapping.clone(),
+                        physical_output_mapping: current.desired_mapping.physical_output_mapping.clone(),
+                    });
                 }
             }
         }
@@ -209,7 +1000,7 @@ impl ManagedWorkflow {
         {
             let prx = self.wf.proxy.borrow_mut();
             for i in &prx.instances {
-                let current = i.borrow_mut();
+                let mut current = i.borrow_mut();
                 if let Some(materialized) = &current.materialized {
                     if materialized.physical_input_mapping != current.desired_mapping.physical_input_mapping
                         || materialized.physical_output_mapping != current.desired_mapping.physical_output_mapping
@@ -220,16 +1011,24 @@ impl ManagedWorkflow {
                             internal_outputs: current.desired_mapping.physical_output_mapping.clone(),
                             external_inputs: prx.external_ports.external_input_mapping.clone(),
                             external_outputs: prx.external_ports.external_output_mapping.clone(),
-                        })
-                    } else {
-                        changes.push(super::RequiredChange::CrateProxy {
-                            proxy_id: current.id,
-                            internal_inputs: current.desired_mapping.physical_input_mapping.clone(),
-                            internal_outputs: current.desired_mapping.physical_output_mapping.clone(),
-                            external_inputs: prx.external_ports.external_input_mapping.clone(),
-                            external_outputs: prx.external_ports.external_output_mapping.clone(),
+                        });
+                        current.materialized = Some(super::PhysicalPorts {
+                            physical_input_mapping: current.desired_mapping.physical_input_mapping.clone(),
+                            physical_output_mapping: current.desired_mapping.physical_output_mapping.clone(),
                         });
                     }
+                } else {
+                    changes.push(super::RequiredChange::CrateProxy {
+                        proxy_id: current.id,
+                        internal_inputs: current.desired_mapping.physical_input_mapping.clone(),
+                        internal_outputs: current.desired_mapping.physical_output_mapping.clone(),
+                        external_inputs: prx.external_ports.external_input_mapping.clone(),
+                        external_outputs: prx.external_ports.external_output_mapping.clone(),
+                    });
+                    current.materialized = Some(super::PhysicalPorts {
+                        physical_input_mapping: current.desired_mapping.physical_input_mapping.clone(),
+                        physical_output_mapping: current.desired_mapping.physical_output_mapping.clone(),
+                    });
                 }
             }
         }
@@ -262,3 +1061,861 @@ impl ManagedWorkflow {
         changed
     }
 }
+
+#[cfg(test)]
+mod test {
+    fn function(name: &str) -> edgeless_api::workflow_instance::WorkflowFunction {
+        edgeless_api::workflow_instance::WorkflowFunction {
+            name: name.to_string(),
+            function_class_specification: edgeless_api::function_instance::FunctionClassSpecification {
+                function_class_id: name.to_string(),
+                function_class_type: "RUST_WASM".to_string(),
+                function_class_version: "0.1".to_string(),
+                ..Default::default()
+            },
+            output_mapping: std::collections::HashMap::new(),
+            input_mapping: std::collections::HashMap::new(),
+            annotations: std::collections::HashMap::new(),
+        }
+    }
+
+    fn with_annotations(
+        mut function: edgeless_api::workflow_instance::WorkflowFunction,
+        annotations: &[(&str, &str)],
+    ) -> edgeless_api::workflow_instance::WorkflowFunction {
+        function.annotations = annotations.iter().map(|(key, val)| (key.to_string(), val.to_string())).collect();
+        function
+    }
+
+    fn resource(name: &str) -> edgeless_api::workflow_instance::WorkflowResource {
+        edgeless_api::workflow_instance::WorkflowResource {
+            name: name.to_string(),
+            class_type: "file-log".to_string(),
+            output_mapping: std::collections::HashMap::new(),
+            input_mapping: std::collections::HashMap::new(),
+            configurations: std::collections::HashMap::new(),
+        }
+    }
+
+    fn request(
+        workflow_functions: Vec<edgeless_api::workflow_instance::WorkflowFunction>,
+        workflow_resources: Vec<edgeless_api::workflow_instance::WorkflowResource>,
+    ) -> edgeless_api::workflow_instance::SpawnWorkflowRequest {
+        edgeless_api::workflow_instance::SpawnWorkflowRequest {
+            workflow_functions,
+            workflow_resources,
+            workflow_ingress_proxies: vec![],
+            workflow_egress_proxies: vec![],
+            annotations: std::collections::HashMap::new(),
+        }
+    }
+
+    /// Function with the given outputs and inputs, each of them being
+    /// used by the function.
+    fn with_ports(
+        mut function: edgeless_api::workflow_instance::WorkflowFunction,
+        outputs: &[(&str, edgeless_api::workflow_instance::PortMapping)],
+        inputs: &[&str],
+    ) -> edgeless_api::workflow_instance::WorkflowFunction {
+        let port = |id: &str| edgeless_api::function_instance::Port {
+            id: edgeless_api::function_instance::PortId(id.to_string()),
+            method: edgeless_api::function_instance::PortMethod::Cast,
+            data_type: edgeless_api::function_instance::PortDataType("d1".to_string()),
+            return_data_type: None,
+        };
+        let class = &mut function.function_class_specification;
+        for (id, mapping) in outputs {
+            let port_id = edgeless_api::function_instance::PortId(id.to_string());
+            function.output_mapping.insert(port_id.clone(), mapping.clone());
+            class.function_class_outputs.insert(port_id.clone(), port(id));
+            class
+                .function_class_inner_structure
+                .entry(edgeless_api::function_instance::MappingNode::SideEffect)
+                .or_default()
+                .push(edgeless_api::function_instance::MappingNode::Port(port_id));
+        }
+        for id in inputs {
+            let port_id = edgeless_api::function_instance::PortId(id.to_string());
+            class.function_class_inputs.insert(port_id.clone(), port(id));
+            class.function_class_inner_structure.insert(
+                edgeless_api::function_instance::MappingNode::Port(port_id),
+                vec![edgeless_api::function_instance::MappingNode::SideEffect],
+            );
+        }
+        function
+    }
+
+    fn target(name: &str, port: &str) -> (String, edgeless_api::function_instance::PortId) {
+        (name.to_string(), edgeless_api::function_instance::PortId(port.to_string()))
+    }
+
+    /// Workflow placed with the round-robin strategy on the given nodes.
+    fn managed_workflow(
+        request: edgeless_api::workflow_instance::SpawnWorkflowRequest,
+        node_ids: &[edgeless_api::function_instance::NodeId],
+    ) -> super::ManagedWorkflow {
+        managed_workflow_with_clusters(request, node_ids, &[])
+    }
+
+    /// Workflow placed with the round-robin strategy on the given nodes,
+    /// which only support in-memory links, and peer clusters.
+    fn managed_workflow_with_clusters(
+        request: edgeless_api::workflow_instance::SpawnWorkflowRequest,
+        node_ids: &[edgeless_api::function_instance::NodeId],
+        clusters: &[(edgeless_api::function_instance::NodeId, &str)],
+    ) -> super::ManagedWorkflow {
+        managed_workflow_with_nodes(request, test_nodes(node_ids), clusters)
+    }
+
+    /// Nodes that only support in-memory links.
+    fn test_nodes(
+        node_ids: &[edgeless_api::function_instance::NodeId],
+    ) -> std::sync::Arc<tokio::sync::Mutex<std::collections::HashMap<edgeless_api::function_instance::NodeId, crate::controller::server::WorkerNode>>>
+    {
+        let memory = edgeless_api::link::LinkType(edgeless_link_memory::common::LINK_TYPE.to_string());
+        std::sync::Arc::new(tokio::sync::Mutex::new(
+            node_ids
+                .iter()
+                .map(|node_id| {
+                    let mut node = crate::controller::server::WorkerNode::new_for_test(edgeless_api::node_registration::NodeCapabilities::minimum());
+                    node.supported_link_types
+                        .insert(memory.clone(), edgeless_api::link::LinkProviderId(uuid::Uuid::new_v4()));
+                    (*node_id, node)
+                })
+                .collect(),
+        ))
+    }
+
+    /// Workflow placed with the round-robin strategy on the given nodes,
+    /// which are shared with the caller, and peer clusters.
+    fn managed_workflow_with_nodes(
+        request: edgeless_api::workflow_instance::SpawnWorkflowRequest,
+        nodes: std::sync::Arc<
+            tokio::sync::Mutex<std::collections::HashMap<edgeless_api::function_instance::NodeId, crate::controller::server::WorkerNode>>,
+        >,
+        clusters: &[(edgeless_api::function_instance::NodeId, &str)],
+    ) -> super::ManagedWorkflow {
+        let memory = edgeless_api::link::LinkType(edgeless_link_memory::common::LINK_TYPE.to_string());
+        super::ManagedWorkflow::new(
+            request,
+            edgeless_api::workflow_instance::WorkflowId {
+                workflow_id: uuid::Uuid::new_v4(),
+            },
+            std::sync::Arc::new(tokio::sync::Mutex::new(crate::orchestration_logic::OrchestrationLogic::new(
+                crate::orchestration_utils::OrchestrationStrategy::RoundRobin,
+            ))),
+            nodes,
+            std::sync::Arc::new(tokio::sync::Mutex::new(
+                clusters
+                    .iter()
+                    .map(|(cluster_id, domain_id)| (*cluster_id, crate::controller::server::PeerCluster::new_for_test(domain_id)))
+                    .collect(),
+            )),
+            std::sync::Arc::new(tokio::sync::Mutex::new(std::collections::HashMap::from([(
+                memory,
+                Box::new(edgeless_link_memory::controller::MemoryController::new()) as Box<dyn edgeless_api::link::LinkController>,
+            )]))),
+        )
+    }
+
+    fn add_function_instance(
+        wf: &mut super::ManagedWorkflow,
+        name: &str,
+        node_id: uuid::Uuid,
+        materialized: bool,
+    ) -> edgeless_api::function_instance::InstanceId {
+        let id = edgeless_api::function_instance::InstanceId::new(node_id);
+        wf.wf.functions[name]
+            .borrow_mut()
+            .instances
+            .push(std::cell::RefCell::new(crate::ir::actor::PhysicalActor {
+                id,
+                image: None,
+                desired_mapping: crate::ir::PhysicalPorts::default(),
+                materialized: materialized.then(|| crate::ir::actor::MaterializedActor {
+                    ports: crate::ir::PhysicalPorts::default(),
+                    statistics: crate::ir::statistics::RuntimeStatistics::new(None, None),
+                }),
+                initial_state: None,
+            }));
+        id
+    }
+
+    fn add_resource_instance(
+        wf: &mut super::ManagedWorkflow,
+        name: &str,
+        node_id: uuid::Uuid,
+        materialized: bool,
+    ) -> edgeless_api::function_instance::InstanceId {
+        let id = edgeless_api::function_instance::InstanceId::new(node_id);
+        wf.wf.resources[name]
+            .borrow_mut()
+            .instances
+            .push(std::cell::RefCell::new(crate::ir::resource::PhysicalResource {
+                id,
+                desired_mapping: crate::ir::PhysicalPorts::default(),
+                materialized: materialized.then(crate::ir::PhysicalPorts::default),
+            }));
+        id
+    }
+
+    #[test]
+    fn stop_tears_down_materialized_components_then_links() {
+        let node_1 = uuid::Uuid::new_v4();
+        let node_2 = uuid::Uuid::new_v4();
+        let cluster = uuid::Uuid::new_v4();
+        let mut wf = managed_workflow(request(vec![function("f1")], vec![resource("r1")]), &[]);
+
+        let f1_running = add_function_instance(&mut wf, "f1", node_1, true);
+        let f1_pending = add_function_instance(&mut wf, "f1", node_2, false);
+        let r1_running = add_resource_instance(&mut wf, "r1", node_2, true);
+
+        let subflow_id = edgeless_api::function_instance::InstanceId::new(cluster);
+        wf.wf.subflows.insert(
+            "s1".to_string(),
+            std::cell::RefCell::new(crate::ir::subflow::LogicalSubFlow {
+                functions: std::collections::HashMap::new(),
+                resources: std::collections::HashMap::new(),
+                logical_ports: crate::ir::LogicalPorts::default(),
+                internal_ports: crate::ir::InternalPorts {
+                    internal_input_mapping: std::collections::HashMap::new(),
+                    internal_output_mapping: std::collections::HashMap::new(),
+                },
+                instances: vec![std::cell::RefCell::new(crate::ir::subflow::PhysicalSubFlow {
+                    id: subflow_id,
+                    desired_mapping: crate::ir::PhysicalPorts::default(),
+                    materialized: Some(crate::ir::PhysicalPorts::default()),
+                })],
+                annotations: std::collections::HashMap::new(),
+            }),
+        );
+
+        let proxy_id = edgeless_api::function_instance::InstanceId::new(node_1);
+        wf.wf
+            .proxy
+            .borrow_mut()
+            .instances
+            .push(std::cell::RefCell::new(crate::ir::proxy::PhyiscalProxy {
+                id: proxy_id,
+                desired_mapping: crate::ir::PhysicalPorts::default(),
+                materialized: Some(crate::ir::PhysicalPorts::default()),
+            }));
+
+        let provider_id = edgeless_api::link::LinkProviderId(uuid::Uuid::new_v4());
+        let link_type = edgeless_api::link::LinkType("MULTICAST".to_string());
+        let materialized_link = edgeless_api::link::LinkInstanceId(uuid::Uuid::new_v4());
+        wf.wf.links.insert(
+            materialized_link.clone(),
+            crate::ir::link::WorkflowLink {
+                id: materialized_link.clone(),
+                class: link_type.clone(),
+                materialized: true,
+                nodes: vec![(node_1, provider_id.clone(), vec![], true), (node_2, provider_id.clone(), vec![], false)],
+            },
+        );
+        let pending_link = edgeless_api::link::LinkInstanceId(uuid::Uuid::new_v4());
+        wf.wf.links.insert(
+            pending_link.clone(),
+            crate::ir::link::WorkflowLink {
+                id: pending_link.clone(),
+                class: link_type.clone(),
+                materialized: false,
+                nodes: vec![(node_1, provider_id.clone(), vec![], false)],
+            },
+        );
+
+        let changes = wf.stop();
+        assert_eq!(changes.len(), 6, "{:?}", changes);
+
+        // Only the components that were started are stopped.
+        let stopped: std::collections::HashSet<_> = changes[..4]
+            .iter()
+            .map(|change| match change {
+                crate::ir::RequiredChange::StopFunction { function_id, function_name } => {
+                    assert_eq!(function_name, "f1");
+                    *function_id
+                }
+                crate::ir::RequiredChange::StopResource { resource_id, resource_name } => {
+                    assert_eq!(resource_name, "r1");
+                    *resource_id
+                }
+                crate::ir::RequiredChange::StopSubflow { subflow_id } => *subflow_id,
+                crate::ir::RequiredChange::StopProxy { proxy_id } => *proxy_id,
+                _ => panic!("unexpected change before the components are stopped: {:?}", change),
+            })
+            .collect();
+        assert_eq!(stopped, std::collections::HashSet::from([f1_running, r1_running, subflow_id, proxy_id]));
+        assert!(!stopped.contains(&f1_pending));
+
+        // The links are removed after the components, first from the nodes
+        // on which they were created, then from the control plane.
+        match &changes[4] {
+            crate::ir::RequiredChange::RemoveLinkFromNode { link_id, node_id } => {
+                assert_eq!(link_id, &materialized_link);
+                assert_eq!(node_id, &node_1);
+            }
+            change => panic!("unexpected change: {:?}", change),
+        }
+        match &changes[5] {
+            crate::ir::RequiredChange::RemoveLinkControlPlane { link_id, class } => {
+                assert_eq!(link_id, &materialized_link);
+                assert_eq!(class, &link_type);
+            }
+            change => panic!("unexpected change: {:?}", change),
+        }
+
+        assert!(wf.wf.functions["f1"].borrow().instances.is_empty());
+        assert!(wf.wf.resources["r1"].borrow().instances.is_empty());
+        assert!(wf.wf.subflows["s1"].borrow().instances.is_empty());
+        assert!(wf.wf.proxy.borrow().instances.is_empty());
+        assert!(wf.wf.links.is_empty());
+
+        // Nothing is left to tear down.
+        assert!(wf.stop().is_empty());
+    }
+
+    #[test]
+    fn stop_unplaced_workflow() {
+        let mut wf = managed_workflow(request(vec![function("f1"), function("f2")], vec![resource("r1")]), &[]);
+
+        // No nodes are available, hence nothing is started.
+        assert!(wf.initial_spawn().is_empty());
+        assert!(wf.stop().is_empty());
+    }
+
+    /// Reports the cumulative counters of an instance of a function as if
+    /// they had been received at the given time.
+    fn report(wf: &super::ManagedWorkflow, name: &str, instance: usize, at: std::time::Instant, invocations: u64, total_duration_ms: u64) {
+        report_dropped(wf, name, instance, at, invocations, total_duration_ms, 0);
+    }
+
+    /// Same as report(), with the number of events discarded by the
+    /// instance because its input queue was full.
+    fn report_dropped(
+        wf: &super::ManagedWorkflow,
+        name: &str,
+        instance: usize,
+        at: std::time::Instant,
+        invocations: u64,
+        total_duration_ms: u64,
+        dropped_events: u64,
+    ) {
+        report_queued(wf, name, instance, at, invocations, total_duration_ms, 0, dropped_events);
+    }
+
+    /// Same as report_dropped(), with the number of events waiting in the
+    /// input queue of the instance.
+    #[allow(clippy::too_many_arguments)]
+    fn report_queued(
+        wf: &super::ManagedWorkflow,
+        name: &str,
+        instance: usize,
+        at: std::time::Instant,
+        invocations: u64,
+        total_duration_ms: u64,
+        queue_depth: u64,
+        dropped_events: u64,
+    ) {
+        let function = wf.wf.functions[name].borrow();
+        let mut current = function.instances[instance].borrow_mut();
+        let function_id = current.id.function_id;
+        current.materialized.as_mut().unwrap().statistics.record_at(
+            &edgeless_api::node_management::InstanceStatistics {
+                function_id,
+                invocations,
+                total_duration_us: total_duration_ms * 1000,
+                invocations_by_port: std::collections::HashMap::new(),
+                queue_depth,
+                dropped_events,
+            },
+            at,
+        );
+    }
+
+    fn num_instances(wf: &super::ManagedWorkflow, name: &str) -> usize {
+        wf.wf.functions[name].borrow().instances.len()
+    }
+
+    fn started(changes: &[crate::ir::RequiredChange]) -> usize {
+        changes
+            .iter()
+            .filter(|change| matches!(change, crate::ir::RequiredChange::StartFunction { .. }))
+            .count()
+    }
+
+    #[test]
+    fn autoscale_first_decision_after_window() {
+        let mut wf = managed_workflow(
+            request(vec![with_annotations(function("f1"), &[("scale_out_rate", "1")])], vec![]),
+            &[uuid::Uuid::new_v4()],
+        );
+        assert_eq!(started(&wf.initial_spawn()), 1);
+
+        let now = std::time::Instant::now();
+        report(&wf, "f1", 0, now - std::time::Duration::from_secs(60), 0, 0);
+        report(&wf, "f1", 0, now, 6000, 0);
+
+        // The observation window starts with the first evaluation.
+        assert!(wf.autoscale().is_empty());
+        assert!(wf.wf.functions["f1"].borrow().last_scaling.is_some());
+        assert_eq!(num_instances(&wf, "f1"), 1);
+    }
+
+    #[test]
+    fn autoscale_disabled() {
+        let mut wf = managed_workflow(request(vec![function("f1")], vec![]), &[uuid::Uuid::new_v4()]);
+        wf.initial_spawn();
+
+        let now = std::time::Instant::now();
+        wf.wf.functions["f1"].borrow_mut().last_scaling = Some(now - std::time::Duration::from_secs(60));
+        report(&wf, "f1", 0, now - std::time::Duration::from_secs(60), 0, 0);
+        report(&wf, "f1", 0, now, 6000, 600000);
+
+        assert!(wf.autoscale().is_empty());
+        assert_eq!(num_instances(&wf, "f1"), 1);
+    }
+
+    #[test]
+    fn autoscale_out_up_to_max_instances() {
+        let mut wf = managed_workflow(
+            request(
+                vec![with_annotations(function("f1"), &[("scale_out_rate", "50"), ("max_instances", "2")])],
+                vec![],
+            ),
+            &[uuid::Uuid::new_v4(), uuid::Uuid::new_v4()],
+        );
+        assert_eq!(started(&wf.initial_spawn()), 1);
+
+        // 100 invocations/s over the last 40 s.
+        let now = std::time::Instant::now();
+        wf.wf.functions["f1"].borrow_mut().last_scaling = Some(now - std::time::Duration::from_secs(60));
+        report(&wf, "f1", 0, now - std::time::Duration::from_secs(40), 0, 0);
+        report(&wf, "f1", 0, now, 4000, 0);
+
+        let changes = wf.autoscale();
+        assert_eq!(started(&changes), 1, "{:?}", changes);
+        assert_eq!(num_instances(&wf, "f1"), 2);
+        assert_eq!(wf.wf.functions["f1"].borrow().desired_instances, Some(2));
+
+        // Both instances are overloaded, but max_instances is reached.
+        wf.wf.functions["f1"].borrow_mut().last_scaling = Some(now - std::time::Duration::from_secs(60));
+        report(&wf, "f1", 1, now - std::time::Duration::from_secs(40), 0, 0);
+        report(&wf, "f1", 1, now, 4000, 0);
+        assert!(wf.autoscale().is_empty());
+        assert_eq!(num_instances(&wf, "f1"), 2);
+    }
+
+    #[test]
+    fn autoscale_in_down_to_min_instances() {
+        let mut wf = managed_workflow(
+            request(
+                vec![with_annotations(function("f1"), &[("scale_in_rate", "10"), ("min_instances", "1")])],
+                vec![],
+            ),
+            &[uuid::Uuid::new_v4(), uuid::Uuid::new_v4()],
+        );
+        wf.wf.functions["f1"].borrow_mut().desired_instances = Some(2);
+        assert_eq!(started(&wf.initial_spawn()), 2);
+
+        // 1 invocation/s over the last 40 s.
+        let now = std::time::Instant::now();
+        wf.wf.functions["f1"].borrow_mut().last_scaling = Some(now - std::time::Duration::from_secs(60));
+        for instance in 0..2 {
+            report(&wf, "f1", instance, now - std::time::Duration::from_secs(40), 0, 0);
+            report(&wf, "f1", instance, now, 40, 0);
+        }
+        let removed = wf.wf.functions["f1"].borrow().instances[1].borrow().id;
+
+        // The instance removed is stopped last.
+        let changes = wf.autoscale();
+        match changes.last() {
+            Some(crate::ir::RequiredChange::StopFunction { function_id, function_name }) => {
+                assert_eq!(function_id, &removed);
+                assert_eq!(function_name, "f1");
+            }
+            _ => panic!("unexpected changes: {:?}", changes),
+        }
+        assert_eq!(started(&changes), 0);
+        assert_eq!(num_instances(&wf, "f1"), 1);
+        assert_eq!(wf.wf.functions["f1"].borrow().desired_instances, Some(1));
+
+        // The minimum number of instances is reached.
+        wf.wf.functions["f1"].borrow_mut().last_scaling = Some(now - std::time::Duration::from_secs(60));
+        assert!(wf.autoscale().is_empty());
+        assert_eq!(num_instances(&wf, "f1"), 1);
+    }
+
+    #[test]
+    fn autoscale_hard_limit_while_cooling_down() {
+        for (annotations, expected_instances) in [
+            (vec![("scale_out_rate", "1")], 1),
+            (vec![("scale_out_rate", "1"), ("hard_limit_ms", "1")], 2),
+        ] {
+            let mut wf = managed_workflow(
+                request(vec![with_annotations(function("f1"), &annotations)], vec![]),
+                &[uuid::Uuid::new_v4(), uuid::Uuid::new_v4()],
+            );
+            wf.initial_spawn();
+
+            // 5 invocations/s taking 2 ms each, 10 s after the last scaling
+            // action, with a window of 30 s.
+            let now = std::time::Instant::now();
+            wf.wf.functions["f1"].borrow_mut().last_scaling = Some(now - std::time::Duration::from_secs(10));
+            report(&wf, "f1", 0, now - std::time::Duration::from_secs(20), 0, 0);
+            report(&wf, "f1", 0, now, 100, 200);
+
+            wf.autoscale();
+            assert_eq!(num_instances(&wf, "f1"), expected_instances, "{:?}", annotations);
+        }
+    }
+
+    #[test]
+    fn autoscale_saturated_while_cooling_down() {
+        for (dropped_events, expected_instances) in [(0, 1), (3, 2)] {
+            let mut wf = managed_workflow(
+                request(
+                    vec![with_annotations(function("f1"), &[("scale_out_rate", "100"), ("scale_in_rate", "10")])],
+                    vec![],
+                ),
+                &[uuid::Uuid::new_v4(), uuid::Uuid::new_v4()],
+            );
+            wf.initial_spawn();
+
+            // 5 invocations/s, below the scale-in rate, 10 s after the last
+            // scaling action, with a window of 30 s.
+            let now = std::time::Instant::now();
+            wf.wf.functions["f1"].borrow_mut().last_scaling = Some(now - std::time::Duration::from_secs(10));
+            report_dropped(&wf, "f1", 0, now - std::time::Duration::from_secs(20), 0, 0, 0);
+            report_dropped(&wf, "f1", 0, now, 100, 200, dropped_events);
+
+            wf.autoscale();
+            assert_eq!(num_instances(&wf, "f1"), expected_instances, "{} dropped events", dropped_events);
+        }
+    }
+
+    #[test]
+    fn autoscale_in_not_while_queued() {
+        for (queue_depth, expected_instances) in [(0, 1), (5, 2)] {
+            let mut wf = managed_workflow(
+                request(vec![with_annotations(function("f1"), &[("scale_in_rate", "10")])], vec![]),
+                &[uuid::Uuid::new_v4(), uuid::Uuid::new_v4()],
+            );
+            wf.wf.functions["f1"].borrow_mut().desired_instances = Some(2);
+            wf.initial_spawn();
+
+            // 1 invocation/s over the last 40 s, with events still queued
+            // at the second instance.
+            let now = std::time::Instant::now();
+            wf.wf.functions["f1"].borrow_mut().last_scaling = Some(now - std::time::Duration::from_secs(60));
+            report(&wf, "f1", 0, now - std::time::Duration::from_secs(40), 0, 0);
+            report(&wf, "f1", 0, now, 40, 0);
+            report(&wf, "f1", 1, now - std::time::Duration::from_secs(40), 0, 0);
+            report_queued(&wf, "f1", 1, now, 40, 0, queue_depth, 0);
+
+            wf.autoscale();
+            assert_eq!(num_instances(&wf, "f1"), expected_instances, "{} queued events", queue_depth);
+        }
+    }
+
+    #[test]
+    fn restore_keeps_links_to_remove() {
+        let node_id = uuid::Uuid::new_v4();
+        let mut wf = managed_workflow(request(vec![function("f1")], vec![]), &[node_id]);
+        wf.initial_spawn();
+        let link_id = edgeless_api::link::LinkInstanceId(uuid::Uuid::new_v4());
+        wf.wf.links.insert(
+            link_id.clone(),
+            crate::ir::link::WorkflowLink {
+                id: link_id.clone(),
+                class: edgeless_api::link::LinkType("MULTICAST".to_string()),
+                materialized: true,
+                nodes: vec![(node_id, edgeless_api::link::LinkProviderId(uuid::Uuid::new_v4()), vec![], true)],
+            },
+        );
+        let record = wf.record();
+        assert_eq!(record.links.len(), 1);
+
+        let mut restored = managed_workflow(record.spawn_request.clone(), &[node_id]);
+        restored.restore(&record);
+        assert_eq!(restored.record().functions, record.functions);
+        assert_eq!(restored.record().links, record.links);
+
+        let changes = restored.stop();
+        assert!(changes
+            .iter()
+            .any(|change| matches!(change, crate::ir::RequiredChange::RemoveLinkFromNode { link_id: removed, node_id: node } if removed == &link_id && node == &node_id)));
+        assert!(changes
+            .iter()
+            .any(|change| matches!(change, crate::ir::RequiredChange::RemoveLinkControlPlane { link_id: removed, .. } if removed == &link_id)));
+    }
+
+    fn function_instances(wf: &super::ManagedWorkflow, name: &str) -> Vec<edgeless_api::function_instance::InstanceId> {
+        wf.wf.functions[name].borrow().instances.iter().map(|i| i.borrow().id).collect()
+    }
+
+    #[test]
+    fn update_replaces_changed_functions_only() {
+        let mut wf = managed_workflow(
+            request(vec![function("f1"), function("f2"), function("f3")], vec![]),
+            &[uuid::Uuid::new_v4()],
+        );
+        assert_eq!(started(&wf.initial_spawn()), 3);
+        let f1 = function_instances(&wf, "f1");
+        let f2 = function_instances(&wf, "f2");
+
+        // f1 is unchanged, f2 has a new version, f3 is removed, f4 is added.
+        let mut f2_changed = function("f2");
+        f2_changed.function_class_specification.function_class_version = "0.2".to_string();
+        let changes = wf.update(request(vec![function("f1"), f2_changed, function("f4")], vec![]));
+
+        assert_eq!(function_instances(&wf, "f1"), f1);
+        assert_eq!(function_instances(&wf, "f2").len(), 1);
+        assert_ne!(function_instances(&wf, "f2"), f2);
+        assert_eq!(function_instances(&wf, "f4").len(), 1);
+        assert!(!wf.wf.functions.contains_key("f3"));
+
+        let started: std::collections::HashSet<_> = changes
+            .iter()
+            .filter_map(|change| match change {
+                crate::ir::RequiredChange::StartFunction { function_name, .. } => Some(function_name.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(started, std::collections::HashSet::from(["f2", "f4"]));
+
+        // The replaced and removed instances are stopped after the new
+        // instances are started.
+        let first_stop = changes
+            .iter()
+            .position(|change| matches!(change, crate::ir::RequiredChange::StopFunction { .. }))
+            .unwrap();
+        assert!(changes[..first_stop]
+            .iter()
+            .all(|change| !matches!(change, crate::ir::RequiredChange::StopFunction { .. })));
+        let stopped: std::collections::HashSet<_> = changes[first_stop..]
+            .iter()
+            .map(|change| match change {
+                crate::ir::RequiredChange::StopFunction { function_name, .. } => function_name.as_str(),
+                _ => panic!("unexpected change after the stops: {:?}", change),
+            })
+            .collect();
+        assert_eq!(stopped, std::collections::HashSet::from(["f2", "f3"]));
+    }
+
+    #[test]
+    fn update_replaces_changed_subflows_only() {
+        let cluster_1 = uuid::Uuid::new_v4();
+        let cluster_2 = uuid::Uuid::new_v4();
+        let functions = |f3_version: &str| {
+            let mut f3 = with_ports(with_annotations(function("f3"), &[("cluster", "domain-2")]), &[], &["in"]);
+            f3.function_class_specification.function_class_version = f3_version.to_string();
+            vec![
+                with_ports(
+                    function("f1"),
+                    &[
+                        (
+                            "out_2",
+                            edgeless_api::workflow_instance::PortMapping::DirectTarget(
+                                "f2".to_string(),
+                                edgeless_api::function_instance::PortId("in".to_string()),
+                            ),
+                        ),
+                        (
+                            "out_3",
+                            edgeless_api::workflow_instance::PortMapping::DirectTarget(
+                                "f3".to_string(),
+                                edgeless_api::function_instance::PortId("in".to_string()),
+                            ),
+                        ),
+                    ],
+                    &[],
+                ),
+                with_ports(with_annotations(function("f2"), &[("cluster", "domain-1")]), &[], &["in"]),
+                f3,
+            ]
+        };
+        let mut wf = managed_workflow_with_clusters(
+            request(functions("0.1"), vec![]),
+            &[uuid::Uuid::new_v4()],
+            &[(cluster_1, "domain-1"), (cluster_2, "domain-2")],
+        );
+        let changes = wf.initial_spawn();
+        let created = changes
+            .iter()
+            .filter(|change| matches!(change, crate::ir::RequiredChange::CreateSubflow { .. }))
+            .count();
+        assert_eq!(created, 2, "{:?}", changes);
+        let subflow_instance = |wf: &super::ManagedWorkflow, name: &str| wf.wf.subflows[name].borrow().instances[0].borrow().id;
+        let subflow_1 = subflow_instance(&wf, "__subflow_domain-1");
+        let subflow_2 = subflow_instance(&wf, "__subflow_domain-2");
+        assert_eq!(subflow_1.node_id, cluster_1);
+        assert_eq!(subflow_2.node_id, cluster_2);
+
+        // Only the subflow with the function changed is spawned again.
+        let changes = wf.update(request(functions("0.2"), vec![]));
+        assert_eq!(subflow_instance(&wf, "__subflow_domain-1"), subflow_1);
+        let new_subflow_2 = subflow_instance(&wf, "__subflow_domain-2");
+        assert_ne!(new_subflow_2, subflow_2);
+        let subflow_changes: Vec<_> = changes
+            .iter()
+            .filter_map(|change| match change {
+                crate::ir::RequiredChange::CreateSubflow { subflow_id, .. } => Some(("create", *subflow_id)),
+                crate::ir::RequiredChange::StopSubflow { subflow_id } => Some(("stop", *subflow_id)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(subflow_changes, vec![("create", new_subflow_2), ("stop", subflow_2)]);
+
+        // The same description changes nothing.
+        let changes = wf.update(request(functions("0.2"), vec![]));
+        assert!(changes.is_empty(), "{:?}", changes);
+    }
+
+    #[test]
+    fn update_removes_unused_links() {
+        let node_id = uuid::Uuid::new_v4();
+        let functions = |f1_version: &str| {
+            let mut f1 = with_ports(
+                function("f1"),
+                &[(
+                    "out",
+                    edgeless_api::workflow_instance::PortMapping::AllOfTargets(vec![target("f2", "in"), target("f3", "in")]),
+                )],
+                &[],
+            );
+            f1.function_class_specification.function_class_version = f1_version.to_string();
+            vec![f1, with_ports(function("f2"), &[], &["in"]), with_ports(function("f3"), &[], &["in"])]
+        };
+        let mut wf = managed_workflow(request(functions("0.1"), vec![]), &[node_id]);
+        let changes = wf.initial_spawn();
+        assert_eq!(wf.wf.links.len(), 1, "{:?}", changes);
+        let old_link = wf.wf.links.keys().next().unwrap().clone();
+
+        // The new instance of f1 writes to a new link, which the instances
+        // of f2 and f3 read from after being patched, hence the old link
+        // is removed after the patches and the stop of the old f1.
+        let changes = wf.update(request(functions("0.2"), vec![]));
+        assert_eq!(wf.wf.links.len(), 1);
+        assert!(!wf.wf.links.contains_key(&old_link));
+
+        let position = |f: &dyn Fn(&crate::ir::RequiredChange) -> bool| changes.iter().rposition(f).unwrap();
+        let last_patch = position(&|change| matches!(change, crate::ir::RequiredChange::PatchFunction { .. }));
+        let stop = position(&|change| matches!(change, crate::ir::RequiredChange::StopFunction { .. }));
+        let removed_from_node = position(
+            &|change| matches!(change, crate::ir::RequiredChange::RemoveLinkFromNode { link_id, node_id: node } if link_id == &old_link && node == &node_id),
+        );
+        let removed = position(&|change| matches!(change, crate::ir::RequiredChange::RemoveLinkControlPlane { link_id, .. } if link_id == &old_link));
+        assert!(last_patch < stop, "{:?}", changes);
+        assert!(stop < removed_from_node, "{:?}", changes);
+        assert!(removed_from_node < removed, "{:?}", changes);
+        assert_eq!(removed, changes.len() - 1);
+    }
+
+    #[test]
+    fn drain_removes_unused_links() {
+        let node_1 = uuid::Uuid::new_v4();
+        let node_2 = uuid::Uuid::new_v4();
+        let nodes = test_nodes(&[node_1, node_2]);
+        nodes.blocking_lock().get_mut(&node_2).unwrap().cordoned = true;
+        let functions = vec![
+            with_ports(
+                function("f1"),
+                &[(
+                    "out",
+                    edgeless_api::workflow_instance::PortMapping::AllOfTargets(vec![target("f2", "in"), target("f3", "in")]),
+                )],
+                &[],
+            ),
+            with_ports(function("f2"), &[], &["in"]),
+            with_ports(function("f3"), &[], &["in"]),
+        ];
+        let mut wf = managed_workflow_with_nodes(request(functions, vec![]), nodes.clone(), &[]);
+        wf.initial_spawn();
+        assert_eq!(wf.wf.links.len(), 1);
+        let old_link = wf.wf.links.keys().next().unwrap().clone();
+
+        // All the instances move from node_1 to node_2, where they use a
+        // new link, hence the old one is removed after they are stopped.
+        {
+            let mut nodes = nodes.blocking_lock();
+            nodes.get_mut(&node_1).unwrap().cordoned = true;
+            nodes.get_mut(&node_2).unwrap().cordoned = false;
+        }
+        let changes = wf.node_drain(&std::collections::HashSet::from([node_1]));
+        assert_eq!(wf.wf.links.len(), 1);
+        assert!(!wf.wf.links.contains_key(&old_link));
+
+        let position = |f: &dyn Fn(&crate::ir::RequiredChange) -> bool| changes.iter().rposition(f).unwrap();
+        let stop = position(&|change| matches!(change, crate::ir::RequiredChange::StopFunction { .. }));
+        let removed_from_node = position(
+            &|change| matches!(change, crate::ir::RequiredChange::RemoveLinkFromNode { link_id, node_id } if link_id == &old_link && node_id == &node_1),
+        );
+        let removed = position(&|change| matches!(change, crate::ir::RequiredChange::RemoveLinkControlPlane { link_id, .. } if link_id == &old_link));
+        assert!(stop < removed_from_node, "{:?}", changes);
+        assert!(removed_from_node < removed, "{:?}", changes);
+        assert_eq!(removed, changes.len() - 1);
+    }
+
+    #[test]
+    fn drain_restarts_instances_elsewhere_then_stops_them() {
+        let node_1 = uuid::Uuid::new_v4();
+        let node_2 = uuid::Uuid::new_v4();
+        let node_3 = uuid::Uuid::new_v4();
+        let nodes = test_nodes(&[node_1, node_2, node_3]);
+        nodes.blocking_lock().get_mut(&node_3).unwrap().cordoned = true;
+
+        // f1 -> f2, with f1 pinned to node_1 and f2 on node_2 or node_3.
+        let mut wf = managed_workflow_with_nodes(
+            request(
+                vec![
+                    with_annotations(
+                        with_ports(
+                            function("f1"),
+                            &[(
+                                "out",
+                                edgeless_api::workflow_instance::PortMapping::AnyOfTargets(vec![target("f2", "in")]),
+                            )],
+                            &[],
+                        ),
+                        &[("node_id_match_any", &node_1.to_string())],
+                    ),
+                    with_annotations(
+                        with_ports(function("f2"), &[], &["in"]),
+                        &[("node_id_match_any", &format!("{},{}", node_2, node_3))],
+                    ),
+                ],
+                vec![],
+            ),
+            nodes.clone(),
+            &[],
+        );
+        assert_eq!(started(&wf.initial_spawn()), 2);
+        let f1 = function_instances(&wf, "f1");
+        let f2 = function_instances(&wf, "f2");
+        assert_eq!(f2.len(), 1);
+        assert_eq!(f2[0].node_id, node_2);
+
+        // node_2 is drained, which cordons it, and node_3 is available.
+        {
+            let mut nodes = nodes.blocking_lock();
+            nodes.get_mut(&node_2).unwrap().cordoned = true;
+            nodes.get_mut(&node_3).unwrap().cordoned = false;
+        }
+        let changes = wf.node_drain(&std::collections::HashSet::from([node_2]));
+
+        assert_eq!(function_instances(&wf, "f1"), f1);
+        let new_f2 = function_instances(&wf, "f2");
+        assert_eq!(new_f2.len(), 1);
+        assert_eq!(new_f2[0].node_id, node_3);
+
+        // The new instance is started, then f1 is patched to use it, and
+        // finally the old instance is stopped.
+        let position = |predicate: &dyn Fn(&crate::ir::RequiredChange) -> bool| changes.iter().position(predicate).unwrap();
+        let start = position(&|change| matches!(change, crate::ir::RequiredChange::StartFunction { function_id, .. } if *function_id == new_f2[0]));
+        let patch = position(&|change| matches!(change, crate::ir::RequiredChange::PatchFunction { function_id, .. } if *function_id == f1[0]));
+        let stop = position(&|change| matches!(change, crate::ir::RequiredChange::StopFunction { function_id, .. } if *function_id == f2[0]));
+        assert!(start < patch);
+        assert!(patch < stop);
+        assert_eq!(stop, changes.len() - 1);
+    }
+}
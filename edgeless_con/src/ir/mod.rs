@@ -8,6 +8,7 @@ pub mod link;
 pub mod managed_worflow;
 pub mod proxy;
 pub mod resource;
+pub mod statistics;
 pub mod subflow;
 pub mod transformations;
 pub mod workflow;
@@ -21,7 +22,9 @@ pub trait LogicalComponent {
 
 pub trait PhysicalComponent {
     fn physical_ports(&mut self) -> &mut PhysicalPorts;
-    fn materialized_state(&mut self) -> &mut dyn MaterializedComponent;
+    /// None if the component has not been materialized yet or if it does
+    /// not keep track of its runtime state.
+    fn materialized_state(&mut self) -> Option<&mut dyn MaterializedComponent>;
 }
 
 pub trait MaterializedComponent {
@@ -31,6 +34,10 @@ pub trait MaterializedComponent {
 
 pub trait ComponentStatistics {
     fn processing_duration(&self) -> &dyn ProcessingMetric;
+    /// Number of events waiting in the input queue, as last reported.
+    fn queue_depth(&self) -> u64;
+    /// Number of events discarded because the input queue was full.
+    fn dropped_events(&self, period: Option<std::time::Duration>) -> u64;
 }
 
 pub trait ProcessingMetric {
@@ -66,6 +73,7 @@ pub struct ExternalPorts {
     pub external_output_mapping: std::collections::HashMap<edgeless_api::function_instance::PortId, PhysicalOutput>,
 }
 
+#[derive(PartialEq)]
 pub struct InternalPorts {
     pub internal_input_mapping: std::collections::HashMap<edgeless_api::function_instance::PortId, LogicalOutput>,
     pub internal_output_mapping: std::collections::HashMap<edgeless_api::function_instance::PortId, LogicalInput>,
@@ -80,6 +88,7 @@ pub enum RequiredChange {
         input_mapping: std::collections::HashMap<edgeless_api::function_instance::PortId, PhysicalInput>,
         output_mapping: std::collections::HashMap<edgeless_api::function_instance::PortId, PhysicalOutput>,
         annotations: std::collections::HashMap<String, String>,
+        initial_state: Option<String>,
     },
     StartResource {
         resource_id: edgeless_api::function_instance::InstanceId,
@@ -138,9 +147,33 @@ pub enum RequiredChange {
         external_inputs: std::collections::HashMap<edgeless_api::function_instance::PortId, PhysicalInput>,
         external_outputs: std::collections::HashMap<edgeless_api::function_instance::PortId, PhysicalOutput>,
     },
+    /// Stops a function instance that is not used anymore by the other
+    /// components once it has processed the events already sent to it.
+    DrainFunction {
+        function_id: edgeless_api::function_instance::InstanceId,
+        function_name: String,
+    },
+    StopFunction {
+        function_id: edgeless_api::function_instance::InstanceId,
+        function_name: String,
+    },
+    StopResource {
+        resource_id: edgeless_api::function_instance::InstanceId,
+        resource_name: String,
+    },
+    RemoveLinkControlPlane {
+        link_id: edgeless_api::link::LinkInstanceId,
+        class: edgeless_api::link::LinkType,
+    },
+    StopSubflow {
+        subflow_id: edgeless_api::function_instance::InstanceId,
+    },
+    StopProxy {
+        proxy_id: edgeless_api::function_instance::InstanceId,
+    },
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum LogicalInput {
     Direct(Vec<(String, edgeless_api::function_instance::PortId)>),
     Topic(String),
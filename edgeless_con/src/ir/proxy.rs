@@ -49,8 +49,8 @@ impl super::PhysicalComponent for PhyiscalProxy {
         &mut self.desired_mapping
     }
 
-    fn materialized_state(&mut self) -> &mut dyn super::MaterializedComponent {
-        todo!()
+    fn materialized_state(&mut self) -> Option<&mut dyn super::MaterializedComponent> {
+        None
     }
 }
 
@@ -67,19 +67,29 @@ impl LogicalProxy {
                     .collect(),
                 logical_input_mapping: egress_proxies
                     .iter()
-                    .filter_map(|e| match &e.inner_input {
-                        edgeless_api::workflow_instance::PortMapping::Topic(t) => Some((
+                    .map(|e| {
+                        (
                             edgeless_api::function_instance::PortId(e.id.clone()),
-                            super::LogicalInput::Topic(t.clone()),
-                        )),
-                        _ => None,
+                            match &e.inner_input {
+                                edgeless_api::workflow_instance::PortMapping::DirectTarget(source_id, source_port) => {
+                                    super::LogicalInput::Direct(vec![(source_id.clone(), source_port.clone())])
+                                }
+                                edgeless_api::workflow_instance::PortMapping::AnyOfTargets(sources)
+                                | edgeless_api::workflow_instance::PortMapping::AllOfTargets(sources) => super::LogicalInput::Direct(sources.clone()),
+                                edgeless_api::workflow_instance::PortMapping::Topic(t) => super::LogicalInput::Topic(t.clone()),
+                            },
+                        )
                     })
                     .collect(),
             },
             external_ports: super::ExternalPorts {
                 external_input_mapping: ingress_proxies
                     .iter()
-                    .map(|i| (edgeless_api::function_instance::PortId(i.id.clone()), i.external_input.clone()))
+                    .filter_map(|i| {
+                        i.external_input
+                            .clone()
+                            .map(|external_input| (edgeless_api::function_instance::PortId(i.id.clone()), external_input))
+                    })
                     .collect(),
                 external_output_mapping: egress_proxies
                     .iter()
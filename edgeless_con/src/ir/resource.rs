@@ -50,8 +50,8 @@ impl super::PhysicalComponent for PhysicalResource {
         &mut self.desired_mapping
     }
 
-    fn materialized_state(&mut self) -> &mut dyn super::MaterializedComponent {
-        todo!()
+    fn materialized_state(&mut self) -> Option<&mut dyn super::MaterializedComponent> {
+        None
     }
 }
 
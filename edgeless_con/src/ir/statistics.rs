@@ -0,0 +1,261 @@
+// SPDX-FileCopyrightText: © 2024 Technical University of Munich, Chair of Connected Mobility
+// SPDX-License-Identifier: MIT
+
+/// Samples older than this are discarded, the latest one excepted.
+const RETENTION: std::time::Duration = std::time::Duration::from_secs(300);
+
+struct Sample {
+    at: std::time::Instant,
+    invocations: u64,
+    total_duration: std::time::Duration,
+    invocations_by_port: std::collections::HashMap<edgeless_api::function_instance::PortId, u64>,
+    queue_depth: u64,
+    dropped_events: u64,
+}
+
+/// Runtime statistics of a materialized component, built from the cumulative
+/// counters periodically reported by the node hosting it.
+pub struct RuntimeStatistics {
+    samples: std::collections::VecDeque<Sample>,
+    soft_limit_ms: Option<f64>,
+    hard_limit_ms: Option<f64>,
+}
+
+impl RuntimeStatistics {
+    /// The soft and hard limits, if any, are the processing times in ms
+    /// against which the limit scores are computed.
+    pub fn new(soft_limit_ms: Option<f64>, hard_limit_ms: Option<f64>) -> Self {
+        Self {
+            samples: std::collections::VecDeque::new(),
+            soft_limit_ms,
+            hard_limit_ms,
+        }
+    }
+
+    /// Add the latest counters reported by the node.
+    pub fn record(&mut self, statistics: &edgeless_api::node_management::InstanceStatistics) {
+        self.record_at(statistics, std::time::Instant::now());
+    }
+
+    /// Add the counters reported by the node at the given time.
+    pub(crate) fn record_at(&mut self, statistics: &edgeless_api::node_management::InstanceStatistics, at: std::time::Instant) {
+        // Counters going backwards mean that the instance has been restarted.
+        if let Some(last) = self.samples.back() {
+            if statistics.invocations < last.invocations {
+                self.samples.clear();
+            }
+        }
+        self.samples.push_back(Sample {
+            at,
+            invocations: statistics.invocations,
+            total_duration: std::time::Duration::from_micros(statistics.total_duration_us),
+            invocations_by_port: statistics.invocations_by_port.clone(),
+            queue_depth: statistics.queue_depth,
+            dropped_events: statistics.dropped_events,
+        });
+        while self.samples.len() > 1 && self.samples.front().is_some_and(|s| at.duration_since(s.at) > RETENTION) {
+            self.samples.pop_front();
+        }
+    }
+
+    /// Time covered by the samples within the given period, or by all the samples if no period is given.
+    pub fn observed(&self, period: Option<std::time::Duration>) -> std::time::Duration {
+        let first = match period {
+            Some(_) => self.baseline(period),
+            None => self.samples.front(),
+        };
+        match (first, self.samples.back()) {
+            (Some(first), Some(last)) => last.at.duration_since(first.at),
+            _ => std::time::Duration::ZERO,
+        }
+    }
+
+    /// Sample against which the differences over the given period are computed.
+    /// None means that the cumulative counters must be used as they are.
+    fn baseline(&self, period: Option<std::time::Duration>) -> Option<&Sample> {
+        let period = period?;
+        let last = self.samples.back()?;
+        self.samples
+            .iter()
+            .rev()
+            .find(|s| last.at.duration_since(s.at) >= period)
+            .or(self.samples.front())
+    }
+
+    fn delta(&self, period: Option<std::time::Duration>) -> (u64, std::time::Duration) {
+        match (self.baseline(period), self.samples.back()) {
+            (Some(first), Some(last)) => (
+                last.invocations.saturating_sub(first.invocations),
+                last.total_duration.saturating_sub(first.total_duration),
+            ),
+            (None, Some(last)) => (last.invocations, last.total_duration),
+            _ => (0, std::time::Duration::ZERO),
+        }
+    }
+
+    fn limit_score(&self, limit_ms: Option<f64>, period: Option<std::time::Duration>) -> f64 {
+        match limit_ms {
+            Some(limit_ms) if limit_ms > 0.0 => super::ProcessingMetric::mean_duration_ms(self, period) / limit_ms,
+            _ => 0.0,
+        }
+    }
+}
+
+impl super::ComponentStatistics for RuntimeStatistics {
+    fn processing_duration(&self) -> &dyn super::ProcessingMetric {
+        self
+    }
+
+    fn queue_depth(&self) -> u64 {
+        self.samples.back().map_or(0, |s| s.queue_depth)
+    }
+
+    fn dropped_events(&self, period: Option<std::time::Duration>) -> u64 {
+        match (self.baseline(period), self.samples.back()) {
+            (Some(first), Some(last)) => last.dropped_events.saturating_sub(first.dropped_events),
+            (None, Some(last)) => last.dropped_events,
+            _ => 0,
+        }
+    }
+}
+
+impl super::ProcessingMetric for RuntimeStatistics {
+    fn invocations(&self, period: Option<std::time::Duration>) -> u64 {
+        self.delta(period).0
+    }
+
+    fn invocations_by_port(&self, period: Option<std::time::Duration>) -> Vec<(edgeless_api::function_instance::PortId, u64)> {
+        let last = match self.samples.back() {
+            Some(last) => last,
+            None => return vec![],
+        };
+        let first = self.baseline(period);
+        last.invocations_by_port
+            .iter()
+            .map(|(port, invocations)| {
+                let before = first.and_then(|s| s.invocations_by_port.get(port)).copied().unwrap_or_default();
+                (port.clone(), invocations.saturating_sub(before))
+            })
+            .collect()
+    }
+
+    fn mean_duration_ms(&self, period: Option<std::time::Duration>) -> f64 {
+        let (invocations, duration) = self.delta(period);
+        if invocations == 0 {
+            0.0
+        } else {
+            duration.as_secs_f64() * 1000.0 / invocations as f64
+        }
+    }
+
+    /// Ratio between the mean processing time and the soft limit, 0 if there is no soft limit.
+    fn soft_limit_score(&self, period: Option<std::time::Duration>) -> f64 {
+        self.limit_score(self.soft_limit_ms, period)
+    }
+
+    /// Ratio between the mean processing time and the hard limit, 0 if there is no hard limit.
+    fn hard_limit_score(&self, period: Option<std::time::Duration>) -> f64 {
+        self.limit_score(self.hard_limit_ms, period)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ir::{ComponentStatistics, ProcessingMetric};
+
+    fn counters(invocations: u64, total_duration_ms: u64, dropped_events: u64) -> edgeless_api::node_management::InstanceStatistics {
+        edgeless_api::node_management::InstanceStatistics {
+            function_id: uuid::Uuid::new_v4(),
+            invocations,
+            total_duration_us: total_duration_ms * 1000,
+            invocations_by_port: std::collections::HashMap::from([(edgeless_api::function_instance::PortId("in".to_string()), invocations)]),
+            queue_depth: invocations / 10,
+            dropped_events,
+        }
+    }
+
+    #[test]
+    fn empty_statistics() {
+        let statistics = super::RuntimeStatistics::new(Some(10.0), Some(100.0));
+        assert_eq!(statistics.invocations(None), 0);
+        assert!(statistics.invocations_by_port(None).is_empty());
+        assert_eq!(statistics.mean_duration_ms(None), 0.0);
+        assert_eq!(statistics.soft_limit_score(None), 0.0);
+        assert_eq!(statistics.queue_depth(), 0);
+        assert_eq!(statistics.dropped_events(None), 0);
+        assert!(statistics.observed(None).is_zero());
+    }
+
+    #[test]
+    fn differences_over_period() {
+        let start = std::time::Instant::now();
+        let mut statistics = super::RuntimeStatistics::new(Some(10.0), Some(40.0));
+        statistics.record_at(&counters(100, 1000, 0), start);
+        statistics.record_at(&counters(200, 3000, 5), start + std::time::Duration::from_secs(10));
+        statistics.record_at(&counters(300, 6000, 5), start + std::time::Duration::from_secs(20));
+
+        // Cumulative counters.
+        assert_eq!(statistics.invocations(None), 300);
+        assert_eq!(statistics.mean_duration_ms(None), 20.0);
+        assert_eq!(statistics.soft_limit_score(None), 2.0);
+        assert_eq!(statistics.hard_limit_score(None), 0.5);
+        assert_eq!(statistics.dropped_events(None), 5);
+        assert_eq!(statistics.queue_depth(), 30);
+        assert_eq!(statistics.observed(None), std::time::Duration::from_secs(20));
+
+        // Last 10 s.
+        let period = Some(std::time::Duration::from_secs(10));
+        assert_eq!(statistics.invocations(period), 100);
+        assert_eq!(
+            statistics.invocations_by_port(period),
+            vec![(edgeless_api::function_instance::PortId("in".to_string()), 100)]
+        );
+        assert_eq!(statistics.mean_duration_ms(period), 30.0);
+        assert_eq!(statistics.dropped_events(period), 0);
+        assert_eq!(statistics.observed(period), std::time::Duration::from_secs(10));
+
+        // A period longer than the samples covers all of them.
+        let period = Some(std::time::Duration::from_secs(60));
+        assert_eq!(statistics.invocations(period), 200);
+        assert_eq!(statistics.mean_duration_ms(period), 25.0);
+        assert_eq!(statistics.dropped_events(period), 5);
+        assert_eq!(statistics.observed(period), std::time::Duration::from_secs(20));
+    }
+
+    #[test]
+    fn no_limits() {
+        let start = std::time::Instant::now();
+        let mut statistics = super::RuntimeStatistics::new(None, None);
+        statistics.record_at(&counters(10, 1000, 0), start);
+        assert_eq!(statistics.mean_duration_ms(None), 100.0);
+        assert_eq!(statistics.soft_limit_score(None), 0.0);
+        assert_eq!(statistics.hard_limit_score(None), 0.0);
+    }
+
+    #[test]
+    fn restarted_instance() {
+        let start = std::time::Instant::now();
+        let mut statistics = super::RuntimeStatistics::new(None, None);
+        statistics.record_at(&counters(100, 1000, 0), start);
+        statistics.record_at(&counters(200, 2000, 0), start + std::time::Duration::from_secs(10));
+        statistics.record_at(&counters(50, 1000, 0), start + std::time::Duration::from_secs(20));
+
+        // The samples before the restart are discarded.
+        assert_eq!(statistics.invocations(None), 50);
+        assert_eq!(statistics.invocations(Some(std::time::Duration::from_secs(10))), 0);
+        assert!(statistics.observed(None).is_zero());
+    }
+
+    #[test]
+    fn old_samples_discarded() {
+        let start = std::time::Instant::now();
+        let mut statistics = super::RuntimeStatistics::new(None, None);
+        statistics.record_at(&counters(100, 1000, 0), start);
+        statistics.record_at(&counters(200, 2000, 0), start + super::RETENTION);
+        assert_eq!(statistics.observed(None), super::RETENTION);
+
+        statistics.record_at(&counters(300, 3000, 0), start + super::RETENTION + std::time::Duration::from_secs(1));
+        assert_eq!(statistics.observed(None), std::time::Duration::from_secs(1));
+        assert_eq!(statistics.invocations(Some(super::RETENTION)), 100);
+    }
+}
@@ -54,11 +54,15 @@ impl super::PhysicalComponent for PhysicalSubFlow {
         &mut self.desired_mapping
     }
 
-    fn materialized_state(&mut self) -> &mut dyn super::MaterializedComponent {
-        todo!()
+    fn materialized_state(&mut self) -> Option<&mut dyn super::MaterializedComponent> {
+        None
     }
 }
 
-pub struct SubFlowFunction {}
+/// Function of the workflow delegated to the peer cluster, as it is
+/// requested to the controller of that cluster.
+pub struct SubFlowFunction {
+    pub(crate) spec: edgeless_api::workflow_instance::WorkflowFunction,
+}
 
 pub struct SubFlowResource {}
@@ -53,6 +53,14 @@ impl TransformationPipeline {
         }
     }
 
+    /// Applies only the transformations of the logical description, e.g.,
+    /// to find the subflows before placing the instances.
+    pub fn apply_logical(&mut self, workflow: &mut super::workflow::ActiveWorkflow) {
+        for t in &mut self.logical_pipeline {
+            t.apply(workflow);
+        }
+    }
+
     pub fn apply_all(&mut self, workflow: &mut super::workflow::ActiveWorkflow) {
         for t in &mut self.logical_pipeline {
             t.apply(workflow);
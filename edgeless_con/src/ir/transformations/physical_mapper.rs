@@ -27,14 +27,20 @@ impl super::Transformation for PhysicalConnectionMapper {
             for (output_id, output) in &logical_ports.logical_output_mapping {
                 match output {
                     LogicalOutput::DirectTarget(target_component, target_port_id) => {
-                        let mut instances = components.get(target_component).unwrap().clone();
-                        if let Some(id) = instances.pop() {
+                        let instances = components.get(target_component).unwrap();
+                        // Replicated targets are load-balanced.
+                        let physical_output = if instances.len() > 1 {
+                            Some(PhysicalOutput::Any(instances.iter().map(|id| (*id, target_port_id.clone())).collect()))
+                        } else {
+                            instances.last().map(|id| PhysicalOutput::Single(*id, target_port_id.clone()))
+                        };
+                        if let Some(physical_output) = physical_output {
                             for c_instance in &physical_instances {
                                 c_instance
                                     .borrow_mut()
                                     .physical_ports()
                                     .physical_output_mapping
-                                    .insert(output_id.clone(), PhysicalOutput::Single(id, target_port_id.clone()));
+                                    .insert(output_id.clone(), physical_output.clone());
                             }
                         }
                     }
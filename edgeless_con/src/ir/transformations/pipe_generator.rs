@@ -5,6 +5,10 @@
 
 use super::super::*;
 
+/// Largest number of target nodes reached through a TCP link, which needs
+/// a connection for each of them, if multicast links are available.
+const MAX_TCP_FAN_OUT: usize = 4;
+
 pub struct PipeGenerator {
     nodes:
         std::sync::Arc<tokio::sync::Mutex<std::collections::HashMap<edgeless_api::function_instance::NodeId, crate::controller::server::WorkerNode>>>,
@@ -23,31 +27,78 @@ impl PipeGenerator {
     ) -> Self {
         Self { nodes, link_controllers }
     }
+
+    /// Type of link connecting the given nodes, chosen by topology among
+    /// the types supported by all of them: in-memory when all the nodes are
+    /// the same, TCP for a small fan-out, and multicast for the rest, if
+    /// possible. None if the nodes have no type of link in common.
+    async fn link_type(
+        &self,
+        source_node: &edgeless_api::function_instance::NodeId,
+        target_nodes: &std::collections::HashSet<edgeless_api::function_instance::NodeId>,
+    ) -> Option<edgeless_api::link::LinkType> {
+        let memory = edgeless_api::link::LinkType(edgeless_link_memory::common::LINK_TYPE.to_string());
+        let tcp = edgeless_api::link::LinkType(edgeless_link_tcp::common::LINK_TYPE.to_string());
+        let mcast = edgeless_api::link::LinkType(edgeless_link_multicast::common::LINK_TYPE.to_string());
+
+        let mut candidates = Vec::new();
+        if target_nodes.len() == 1 && target_nodes.contains(source_node) {
+            candidates.push(memory);
+        }
+        if target_nodes.len() <= MAX_TCP_FAN_OUT {
+            candidates.push(tcp.clone());
+        }
+        candidates.push(mcast);
+        // Many TCP connections are still better than no link at all.
+        candidates.push(tcp);
+
+        let nodes = self.nodes.lock().await;
+        let link_controllers = self.link_controllers.lock().await;
+        candidates.into_iter().find(|class| {
+            link_controllers.contains_key(class)
+                && std::iter::once(source_node)
+                    .chain(target_nodes.iter())
+                    .all(|n| nodes.get(n).is_some_and(|node| node.supported_link_types.contains_key(class)))
+        })
+    }
 }
 
 impl super::Transformation for PipeGenerator {
     fn apply(&mut self, workflow: &mut workflow::ActiveWorkflow) {
-        let mcast = edgeless_api::link::LinkType("MULTICAST".to_string());
-
         let mut new_links = Vec::<(edgeless_api::link::LinkInstanceId, link::WorkflowLink)>::new();
 
         for (c_id, c) in workflow.components() {
             let mut current = c.borrow_mut();
+            let instance_ids = current.instance_ids();
             let (logical_ports, physical_instances) = current.split_view();
-            for i in &physical_instances {
+            for (i, instance_id) in physical_instances.iter().zip(instance_ids) {
                 for (out_id, out) in &mut i.borrow_mut().physical_ports().physical_output_mapping {
                     if let edgeless_api::common::Output::All(targets) = out {
                         if targets.len() >= 2 {
                             let target_nodes: std::collections::HashSet<_> = targets.iter().map(|(t_id, _)| t_id.node_id).collect();
-                            let new_link = self
+                            let class = match futures::executor::block_on(self.link_type(&instance_id.node_id, &target_nodes)) {
+                                Some(class) => class,
+                                None => continue,
+                            };
+
+                            // The source node writes to the link, thus it needs an instance of it, too.
+                            let mut link_nodes = target_nodes.clone();
+                            link_nodes.insert(instance_id.node_id);
+                            let new_link = match self
                                 .link_controllers
                                 .blocking_lock()
-                                .get_mut(&mcast)
+                                .get_mut(&class)
                                 .unwrap()
-                                .new_link(target_nodes.clone().into_iter().collect())
-                                .unwrap();
+                                .new_link(link_nodes.clone().into_iter().collect())
+                            {
+                                Ok(new_link) => new_link,
+                                Err(err) => {
+                                    log::warn!("Could not create a {} link for {}.{}: {}", class.0, c_id, out_id.0, err);
+                                    continue;
+                                }
+                            };
 
-                            let node_links: Vec<_> = target_nodes
+                            let node_links: Vec<_> = link_nodes
                                 .iter()
                                 .map(|n| {
                                     (
@@ -57,12 +108,12 @@ impl super::Transformation for PipeGenerator {
                                             .get(n)
                                             .unwrap()
                                             .supported_link_types
-                                            .get(&mcast)
+                                            .get(&class)
                                             .unwrap()
                                             .clone(),
                                         self.link_controllers
                                             .blocking_lock()
-                                            .get(&mcast)
+                                            .get(&class)
                                             .unwrap()
                                             .config_for(new_link.clone(), *n)
                                             .unwrap(),
@@ -75,7 +126,7 @@ impl super::Transformation for PipeGenerator {
                                 new_link.clone(),
                                 link::WorkflowLink {
                                     id: new_link.clone(),
-                                    class: mcast.clone(),
+                                    class: class.clone(),
                                     materialized: false,
                                     nodes: node_links,
                                 },
@@ -34,23 +34,44 @@ impl DefaultPlacement {
 
 impl super::Transformation for DefaultPlacement {
     fn apply(&mut self, slf: &mut workflow::ActiveWorkflow) {
+        let strategy = slf
+            .original_request
+            .annotations
+            .get("placement_strategy")
+            .and_then(|val| crate::orchestration_utils::OrchestrationStrategy::from_string(val));
+
+        let mut workflow_nodes: Vec<edgeless_api::function_instance::NodeId> = slf
+            .components()
+            .into_iter()
+            .flat_map(|(_, c)| c.borrow_mut().instance_ids().into_iter().map(|i| i.node_id).collect::<Vec<_>>())
+            .collect();
+
         for (f_id, function) in &mut slf.functions {
             let mut function = function.borrow_mut();
-            if function.instances.is_empty() {
-                let dst = self
-                    .orchestration_logic
-                    .blocking_lock()
-                    .next(&self.nodes.blocking_lock(), &function.image.format, &function.annotations);
+            let target_instances = function
+                .desired_instances
+                .unwrap_or_else(|| crate::orchestration_utils::DeploymentRequirements::from_annotations(&function.annotations).target_instances());
+            while function.instances.len() < target_instances {
+                let dst = self.orchestration_logic.blocking_lock().next(
+                    &self.nodes.blocking_lock(),
+                    &function.image.format,
+                    &function.annotations,
+                    strategy.as_ref(),
+                    &workflow_nodes,
+                );
 
                 if let Some(dst) = dst {
+                    workflow_nodes.push(dst);
                     function.instances.push(std::cell::RefCell::new(actor::PhysicalActor {
                         id: edgeless_api::function_instance::InstanceId::new(dst),
                         desired_mapping: PhysicalPorts::default(),
                         image: None,
                         materialized: None,
+                        initial_state: None,
                     }))
                 } else {
                     log::info!("Found no viable node for {} in {}", &f_id, slf.id.workflow_id);
+                    break;
                 }
             }
         }
@@ -71,7 +92,7 @@ impl super::Transformation for DefaultPlacement {
 
         for (_, subflow) in &mut slf.subflows {
             let mut subflow = subflow.borrow_mut();
-            if subflow.instances.is_empty() && subflow.instances.is_empty() {
+            if subflow.instances.is_empty() {
                 let dst = select_cluster_for_subflow(&subflow, &self.peer_clusters.blocking_lock());
                 if let Some(dst) = dst {
                     subflow.instances.push(std::cell::RefCell::new(subflow::PhysicalSubFlow {
@@ -107,7 +128,7 @@ fn select_node_for_resource(
 ) -> Option<edgeless_api::function_instance::NodeId> {
     if let Some((id, _)) = nodes
         .iter()
-        .find(|(_, n)| n.resource_providers.iter().any(|(_, r)| r.class_type == resource.class))
+        .find(|(_, n)| !n.cordoned && n.resource_providers.iter().any(|(_, r)| r.class_type == resource.class))
     {
         Some(*id)
     } else {
@@ -120,7 +141,7 @@ fn select_node_for_proxy(
     nodes: &std::collections::HashMap<edgeless_api::function_instance::NodeId, crate::controller::server::WorkerNode>,
 ) -> Option<edgeless_api::function_instance::NodeId> {
     for (node_id, node) in nodes {
-        if node.is_proxy {
+        if node.is_proxy && !node.cordoned {
             return Some(*node_id);
         }
     }
@@ -131,9 +152,149 @@ fn select_cluster_for_subflow(
     subflow: &subflow::LogicalSubFlow,
     clusters: &std::collections::HashMap<edgeless_api::function_instance::NodeId, crate::controller::server::PeerCluster>,
 ) -> Option<edgeless_api::function_instance::NodeId> {
-    // for (cluster_id, cluster) in clusters {
-    //     // TODO Proper Selection
-    //     return Some(*cluster_id);
-    // }
-    None
+    let cluster = subflow.annotations.get(super::workflow_spitter::CLUSTER_ANNOTATION)?;
+    super::workflow_spitter::WorkflowSplitter::peer_cluster(cluster, clusters)
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::Transformation;
+
+    /// Nodes that provide file-log resources and act as proxies, the first
+    /// `cordoned` of them being cordoned.
+    fn nodes(
+        num_nodes: usize,
+        cordoned: usize,
+    ) -> std::collections::HashMap<edgeless_api::function_instance::NodeId, crate::controller::server::WorkerNode> {
+        (0..num_nodes)
+            .map(|i| {
+                let mut node = crate::controller::server::WorkerNode::new_for_test(edgeless_api::node_registration::NodeCapabilities::minimum());
+                node.resource_providers.insert(
+                    "file-log-1".to_string(),
+                    crate::controller::server::ResourceProvider {
+                        class_type: "file-log".to_string(),
+                        outputs: vec![],
+                    },
+                );
+                node.is_proxy = true;
+                node.cordoned = i < cordoned;
+                (uuid::Uuid::new_v4(), node)
+            })
+            .collect()
+    }
+
+    fn uncordoned(
+        nodes: &std::collections::HashMap<edgeless_api::function_instance::NodeId, crate::controller::server::WorkerNode>,
+    ) -> std::collections::HashSet<edgeless_api::function_instance::NodeId> {
+        nodes.iter().filter(|(_, node)| !node.cordoned).map(|(node_id, _)| *node_id).collect()
+    }
+
+    fn function(name: &str) -> edgeless_api::workflow_instance::WorkflowFunction {
+        edgeless_api::workflow_instance::WorkflowFunction {
+            name: name.to_string(),
+            function_class_specification: edgeless_api::function_instance::FunctionClassSpecification {
+                function_class_id: name.to_string(),
+                function_class_type: "RUST_WASM".to_string(),
+                function_class_version: "0.1".to_string(),
+                ..Default::default()
+            },
+            output_mapping: std::collections::HashMap::new(),
+            input_mapping: std::collections::HashMap::new(),
+            annotations: std::collections::HashMap::new(),
+        }
+    }
+
+    fn resource(name: &str) -> edgeless_api::workflow_instance::WorkflowResource {
+        edgeless_api::workflow_instance::WorkflowResource {
+            name: name.to_string(),
+            class_type: "file-log".to_string(),
+            output_mapping: std::collections::HashMap::new(),
+            input_mapping: std::collections::HashMap::new(),
+            configurations: std::collections::HashMap::new(),
+        }
+    }
+
+    fn placement(
+        nodes: std::collections::HashMap<edgeless_api::function_instance::NodeId, crate::controller::server::WorkerNode>,
+    ) -> super::DefaultPlacement {
+        super::DefaultPlacement::new(
+            std::sync::Arc::new(tokio::sync::Mutex::new(crate::orchestration_logic::OrchestrationLogic::new(
+                crate::orchestration_utils::OrchestrationStrategy::RoundRobin,
+            ))),
+            std::sync::Arc::new(tokio::sync::Mutex::new(nodes)),
+            std::sync::Arc::new(tokio::sync::Mutex::new(std::collections::HashMap::new())),
+        )
+    }
+
+    #[test]
+    fn placement_skips_cordoned_nodes() {
+        let nodes = nodes(4, 3);
+        let allowed = uncordoned(&nodes);
+        let mut workflow = crate::ir::workflow::ActiveWorkflow::new(
+            edgeless_api::workflow_instance::SpawnWorkflowRequest {
+                workflow_functions: (0..8).map(|i| function(&format!("f{}", i))).collect(),
+                workflow_resources: vec![resource("r1"), resource("r2")],
+                workflow_ingress_proxies: vec![],
+                workflow_egress_proxies: vec![],
+                annotations: std::collections::HashMap::new(),
+            },
+            edgeless_api::workflow_instance::WorkflowId {
+                workflow_id: uuid::Uuid::new_v4(),
+            },
+        );
+        placement(nodes).apply(&mut workflow);
+
+        for (name, component) in workflow.components().into_iter().filter(|(name, _)| *name != "__proxy") {
+            let node_ids: Vec<_> = component.borrow_mut().instance_ids().into_iter().map(|id| id.node_id).collect();
+            assert_eq!(node_ids.len(), 1, "{}", name);
+            assert!(allowed.contains(&node_ids[0]), "{} placed on a cordoned node", name);
+        }
+    }
+
+    #[test]
+    fn placement_with_all_nodes_cordoned() {
+        let mut workflow = crate::ir::workflow::ActiveWorkflow::new(
+            edgeless_api::workflow_instance::SpawnWorkflowRequest {
+                workflow_functions: vec![function("f1")],
+                workflow_resources: vec![resource("r1")],
+                workflow_ingress_proxies: vec![],
+                workflow_egress_proxies: vec![],
+                annotations: std::collections::HashMap::new(),
+            },
+            edgeless_api::workflow_instance::WorkflowId {
+                workflow_id: uuid::Uuid::new_v4(),
+            },
+        );
+        placement(nodes(2, 2)).apply(&mut workflow);
+
+        for (name, component) in workflow.components() {
+            assert!(component.borrow_mut().instance_ids().is_empty(), "{} placed on a cordoned node", name);
+        }
+    }
+
+    #[test]
+    fn select_node_for_resource_skips_cordoned_nodes() {
+        let resource = crate::ir::resource::LogicalResource::from(resource("r1"));
+
+        let nodes = nodes(5, 4);
+        let allowed = uncordoned(&nodes);
+        assert!(allowed.contains(&super::select_node_for_resource(&resource, &nodes).unwrap()));
+
+        assert!(super::select_node_for_resource(&resource, &super::test::nodes(3, 3)).is_none());
+    }
+
+    #[test]
+    fn select_node_for_proxy_skips_cordoned_nodes() {
+        let proxy = crate::ir::proxy::LogicalProxy {
+            logical_ports: crate::ir::LogicalPorts::default(),
+            external_ports: crate::ir::ExternalPorts::default(),
+            instances: vec![],
+        };
+
+        let nodes = nodes(5, 4);
+        let allowed = uncordoned(&nodes);
+        assert!(allowed.contains(&super::select_node_for_proxy(&proxy, &nodes).unwrap()));
+
+        assert!(super::select_node_for_proxy(&proxy, &super::test::nodes(3, 3)).is_none());
+    }
 }
@@ -3,16 +3,379 @@
 // SPDX-FileCopyrightText: © 2023 Siemens AG
 // SPDX-License-Identifier: MIT
 
+use super::super::*;
+
+/// Function annotation with the peer cluster that must run the function.
+pub const CLUSTER_ANNOTATION: &str = "cluster";
+
+/// Moves the functions annotated with a peer cluster into one subflow per
+/// cluster, which is then spawned as a separate workflow on that cluster.
+///
+/// The ports of a subflow are named `<function>.<port>` after the port of
+/// the member function they are connected to. Inside the subflow, these
+/// ports are served by the proxy of the workflow spawned on the peer
+/// cluster, i.e., the member functions are connected to `__proxy` whenever
+/// they are connected to a component outside of the subflow.
 pub struct WorkflowSplitter {}
 
 impl WorkflowSplitter {
     pub fn new() -> Self {
         Self {}
     }
+
+    /// Peer cluster referred to by a cluster annotation, either by its
+    /// identifier or by its domain name.
+    pub fn peer_cluster(
+        cluster: &str,
+        clusters: &std::collections::HashMap<edgeless_api::function_instance::NodeId, crate::controller::server::PeerCluster>,
+    ) -> Option<edgeless_api::function_instance::NodeId> {
+        clusters
+            .iter()
+            .find(|(cluster_id, peer)| cluster_id.to_string() == cluster || (!peer.domain_id.is_empty() && peer.domain_id == cluster))
+            .map(|(cluster_id, _)| *cluster_id)
+    }
+
+    /// Checks that the cluster annotations of the functions of a workflow
+    /// refer to known peer clusters, since their subflows could not be
+    /// placed otherwise.
+    pub fn check_clusters(
+        request: &edgeless_api::workflow_instance::SpawnWorkflowRequest,
+        clusters: &std::collections::HashMap<edgeless_api::function_instance::NodeId, crate::controller::server::PeerCluster>,
+    ) -> anyhow::Result<()> {
+        for function in &request.workflow_functions {
+            if let Some(cluster) = function.annotations.get(CLUSTER_ANNOTATION) {
+                if Self::peer_cluster(cluster, clusters).is_none() {
+                    anyhow::bail!("function {} is annotated with an unknown peer cluster: {}", function.name, cluster);
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
 impl super::Transformation for WorkflowSplitter {
     fn apply(&mut self, workflow: &mut crate::ir::workflow::ActiveWorkflow) {
-        // TODO
+        let mut clusters = std::collections::BTreeMap::<String, Vec<String>>::new();
+        for (f_name, function) in &workflow.functions {
+            if let Some(cluster) = function.borrow().annotations.get(CLUSTER_ANNOTATION) {
+                clusters.entry(cluster.clone()).or_default().push(f_name.clone());
+            }
+        }
+
+        // Name of the subflow that each split function belongs to.
+        let mut membership = std::collections::HashMap::<String, String>::new();
+
+        for (cluster, members) in clusters {
+            let subflow_name = format!("__subflow_{}", cluster);
+            let mut subflow = subflow::LogicalSubFlow {
+                functions: std::collections::HashMap::new(),
+                resources: std::collections::HashMap::new(),
+                logical_ports: LogicalPorts::default(),
+                internal_ports: InternalPorts {
+                    internal_input_mapping: std::collections::HashMap::new(),
+                    internal_output_mapping: std::collections::HashMap::new(),
+                },
+                instances: Vec::new(),
+                annotations: std::collections::HashMap::from([(CLUSTER_ANNOTATION.to_string(), cluster.clone())]),
+            };
+
+            for member in &members {
+                let spec = workflow.original_request.workflow_functions.iter().find(|f| &f.name == member).cloned();
+                let mut spec = match spec {
+                    Some(spec) => spec,
+                    None => {
+                        log::warn!("Cannot move {} to cluster {}: function not found in the request", member, cluster);
+                        continue;
+                    }
+                };
+                let function = match workflow.functions.remove(member) {
+                    Some(function) => function.into_inner(),
+                    None => continue,
+                };
+
+                spec.annotations.remove(CLUSTER_ANNOTATION);
+                // The inputs are derived from the outputs by the peer cluster.
+                spec.input_mapping = std::collections::HashMap::new();
+                spec.output_mapping = std::collections::HashMap::new();
+
+                for (port_id, output) in &function.logical_ports.logical_output_mapping {
+                    let sub_port = Self::subflow_port(member, port_id);
+                    let (internal, external): (Vec<_>, Vec<_>) = Self::targets(output).into_iter().partition(|(t_id, _)| members.contains(t_id));
+                    let mut inner_targets = internal;
+                    if !external.is_empty() {
+                        inner_targets.push(("__proxy".to_string(), sub_port.clone()));
+                        subflow
+                            .logical_ports
+                            .logical_output_mapping
+                            .insert(sub_port.clone(), Self::with_targets(output, external));
+                        subflow
+                            .internal_ports
+                            .internal_output_mapping
+                            .insert(sub_port, LogicalInput::Direct(vec![(member.clone(), port_id.clone())]));
+                    }
+                    if !inner_targets.is_empty() {
+                        spec.output_mapping.insert(port_id.clone(), Self::with_targets(output, inner_targets));
+                    }
+                }
+
+                for (port_id, input) in &function.logical_ports.logical_input_mapping {
+                    if let LogicalInput::Direct(sources) = input {
+                        let external: Vec<_> = sources.iter().filter(|(s_id, _)| !members.contains(s_id)).cloned().collect();
+                        if !external.is_empty() {
+                            let sub_port = Self::subflow_port(member, port_id);
+                            subflow
+                                .logical_ports
+                                .logical_input_mapping
+                                .insert(sub_port.clone(), LogicalInput::Direct(external));
+                            subflow
+                                .internal_ports
+                                .internal_input_mapping
+                                .insert(sub_port, LogicalOutput::DirectTarget(member.clone(), port_id.clone()));
+                        }
+                    }
+                }
+
+                membership.insert(member.clone(), subflow_name.clone());
+                subflow.functions.insert(member.clone(), subflow::SubFlowFunction { spec });
+            }
+
+            if !subflow.functions.is_empty() {
+                workflow.subflows.insert(subflow_name, std::cell::RefCell::new(subflow));
+            }
+        }
+
+        if membership.is_empty() {
+            return;
+        }
+
+        // Connect the remaining components to the subflows instead of the
+        // functions that have been moved.
+        let retarget = |(c_id, p_id): &(String, edgeless_api::function_instance::PortId)| match membership.get(c_id) {
+            Some(subflow_name) => (subflow_name.clone(), Self::subflow_port(c_id, p_id)),
+            None => (c_id.clone(), p_id.clone()),
+        };
+        for (_, component) in workflow.components() {
+            let mut component = component.borrow_mut();
+            let ports = component.logical_ports();
+            for output in ports.logical_output_mapping.values_mut() {
+                *output = match output {
+                    LogicalOutput::DirectTarget(t_id, t_port) => {
+                        let (t_id, t_port) = retarget(&(t_id.clone(), t_port.clone()));
+                        LogicalOutput::DirectTarget(t_id, t_port)
+                    }
+                    LogicalOutput::AnyOfTargets(targets) => LogicalOutput::AnyOfTargets(targets.iter().map(retarget).collect()),
+                    LogicalOutput::AllOfTargets(targets) => LogicalOutput::AllOfTargets(targets.iter().map(retarget).collect()),
+                    LogicalOutput::Topic(topic) => LogicalOutput::Topic(topic.clone()),
+                };
+            }
+            for input in ports.logical_input_mapping.values_mut() {
+                if let LogicalInput::Direct(sources) = input {
+                    *sources = sources.iter().map(retarget).collect();
+                }
+            }
+        }
+    }
+}
+
+impl WorkflowSplitter {
+    fn subflow_port(component: &str, port_id: &edgeless_api::function_instance::PortId) -> edgeless_api::function_instance::PortId {
+        edgeless_api::function_instance::PortId(format!("{}.{}", component, port_id.0))
+    }
+
+    fn targets(output: &LogicalOutput) -> Vec<(String, edgeless_api::function_instance::PortId)> {
+        match output {
+            LogicalOutput::DirectTarget(t_id, t_port) => vec![(t_id.clone(), t_port.clone())],
+            LogicalOutput::AnyOfTargets(targets) | LogicalOutput::AllOfTargets(targets) => targets.clone(),
+            LogicalOutput::Topic(_) => vec![],
+        }
+    }
+
+    /// Same kind of output as the given one, but with other targets.
+    fn with_targets(output: &LogicalOutput, mut targets: Vec<(String, edgeless_api::function_instance::PortId)>) -> LogicalOutput {
+        match output {
+            LogicalOutput::DirectTarget(_, _) if targets.len() == 1 => {
+                let (t_id, t_port) = targets.remove(0);
+                LogicalOutput::DirectTarget(t_id, t_port)
+            }
+            LogicalOutput::AllOfTargets(_) => LogicalOutput::AllOfTargets(targets),
+            _ => LogicalOutput::AnyOfTargets(targets),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::Transformation;
+
+    fn port(name: &str) -> edgeless_api::function_instance::PortId {
+        edgeless_api::function_instance::PortId(name.to_string())
+    }
+
+    fn function(name: &str, cluster: Option<&str>, outputs: &[(&str, &str, &str)]) -> edgeless_api::workflow_instance::WorkflowFunction {
+        edgeless_api::workflow_instance::WorkflowFunction {
+            name: name.to_string(),
+            function_class_specification: edgeless_api::function_instance::FunctionClassSpecification {
+                function_class_id: name.to_string(),
+                function_class_type: "RUST_WASM".to_string(),
+                function_class_version: "0.1".to_string(),
+                ..Default::default()
+            },
+            output_mapping: outputs
+                .iter()
+                .map(|(out, target, target_port)| {
+                    (
+                        port(out),
+                        edgeless_api::workflow_instance::PortMapping::DirectTarget(target.to_string(), port(target_port)),
+                    )
+                })
+                .collect(),
+            input_mapping: std::collections::HashMap::new(),
+            annotations: cluster
+                .map(|cluster| std::collections::HashMap::from([(super::CLUSTER_ANNOTATION.to_string(), cluster.to_string())]))
+                .unwrap_or_default(),
+        }
+    }
+
+    fn request(workflow_functions: Vec<edgeless_api::workflow_instance::WorkflowFunction>) -> edgeless_api::workflow_instance::SpawnWorkflowRequest {
+        edgeless_api::workflow_instance::SpawnWorkflowRequest {
+            workflow_functions,
+            workflow_resources: vec![],
+            workflow_ingress_proxies: vec![],
+            workflow_egress_proxies: vec![],
+            annotations: std::collections::HashMap::new(),
+        }
+    }
+
+    /// src -> a -> b -> sink and src -> c, with a and b in domain-1 and c
+    /// in domain-2.
+    fn split_workflow() -> crate::ir::workflow::ActiveWorkflow {
+        let mut workflow = crate::ir::workflow::ActiveWorkflow::new(
+            request(vec![
+                function("src", None, &[("out", "a", "in"), ("out_c", "c", "in")]),
+                function("a", Some("domain-1"), &[("out", "b", "in")]),
+                function("b", Some("domain-1"), &[("out", "sink", "in")]),
+                function("c", Some("domain-2"), &[]),
+                function("sink", None, &[]),
+            ]),
+            edgeless_api::workflow_instance::WorkflowId {
+                workflow_id: uuid::Uuid::new_v4(),
+            },
+        );
+        super::super::input_linker::InputLinker::new().apply(&mut workflow);
+        super::WorkflowSplitter::new().apply(&mut workflow);
+        workflow
+    }
+
+    #[test]
+    fn split_by_cluster() {
+        let workflow = split_workflow();
+
+        let mut remaining: Vec<_> = workflow.functions.keys().cloned().collect();
+        remaining.sort();
+        assert_eq!(remaining, vec!["sink".to_string(), "src".to_string()]);
+        let mut subflows: Vec<_> = workflow.subflows.keys().cloned().collect();
+        subflows.sort();
+        assert_eq!(subflows, vec!["__subflow_domain-1".to_string(), "__subflow_domain-2".to_string()]);
+
+        let subflow = workflow.subflows["__subflow_domain-1"].borrow();
+        assert_eq!(
+            subflow.annotations.get(super::CLUSTER_ANNOTATION).map(|cluster| cluster.as_str()),
+            Some("domain-1")
+        );
+
+        // The connection between a and b stays inside the subflow, whereas
+        // the others go through the proxy of the peer cluster.
+        let a = &subflow.functions["a"].spec;
+        assert!(!a.annotations.contains_key(super::CLUSTER_ANNOTATION));
+        assert_eq!(
+            a.output_mapping.get(&port("out")),
+            Some(&edgeless_api::workflow_instance::PortMapping::DirectTarget("b".to_string(), port("in")))
+        );
+        let b = &subflow.functions["b"].spec;
+        assert_eq!(
+            b.output_mapping.get(&port("out")),
+            Some(&edgeless_api::workflow_instance::PortMapping::DirectTarget(
+                "__proxy".to_string(),
+                port("b.out")
+            ))
+        );
+
+        assert_eq!(
+            subflow.logical_ports.logical_input_mapping.get(&port("a.in")),
+            Some(&crate::ir::LogicalInput::Direct(vec![("src".to_string(), port("out"))]))
+        );
+        assert_eq!(
+            subflow.internal_ports.internal_input_mapping.get(&port("a.in")),
+            Some(&crate::ir::LogicalOutput::DirectTarget("a".to_string(), port("in")))
+        );
+        assert_eq!(
+            subflow.logical_ports.logical_output_mapping.get(&port("b.out")),
+            Some(&crate::ir::LogicalOutput::DirectTarget("sink".to_string(), port("in")))
+        );
+        assert_eq!(
+            subflow.internal_ports.internal_output_mapping.get(&port("b.out")),
+            Some(&crate::ir::LogicalInput::Direct(vec![("b".to_string(), port("out"))]))
+        );
+        assert_eq!(subflow.logical_ports.logical_input_mapping.len(), 1);
+        assert_eq!(subflow.logical_ports.logical_output_mapping.len(), 1);
+
+        // The remaining functions are connected to the subflows.
+        let src = workflow.functions["src"].borrow();
+        let src_outputs = &src.logical_ports.logical_output_mapping;
+        assert_eq!(
+            src_outputs.get(&port("out")),
+            Some(&crate::ir::LogicalOutput::DirectTarget("__subflow_domain-1".to_string(), port("a.in")))
+        );
+        assert_eq!(
+            src_outputs.get(&port("out_c")),
+            Some(&crate::ir::LogicalOutput::DirectTarget("__subflow_domain-2".to_string(), port("c.in")))
+        );
+        let sink = workflow.functions["sink"].borrow();
+        assert_eq!(
+            sink.logical_ports.logical_input_mapping.get(&port("in")),
+            Some(&crate::ir::LogicalInput::Direct(vec![("__subflow_domain-1".to_string(), port("b.out"))]))
+        );
+    }
+
+    #[test]
+    fn split_only_once() {
+        let mut workflow = split_workflow();
+        super::WorkflowSplitter::new().apply(&mut workflow);
+
+        assert_eq!(workflow.subflows.len(), 2);
+        assert_eq!(workflow.subflows["__subflow_domain-1"].borrow().functions.len(), 2);
+        assert_eq!(workflow.subflows["__subflow_domain-2"].borrow().functions.len(), 1);
+    }
+
+    #[test]
+    fn no_cluster_annotations() {
+        let mut workflow = crate::ir::workflow::ActiveWorkflow::new(
+            request(vec![function("src", None, &[("out", "sink", "in")]), function("sink", None, &[])]),
+            edgeless_api::workflow_instance::WorkflowId {
+                workflow_id: uuid::Uuid::new_v4(),
+            },
+        );
+        super::WorkflowSplitter::new().apply(&mut workflow);
+
+        assert!(workflow.subflows.is_empty());
+        assert_eq!(workflow.functions.len(), 2);
+    }
+
+    #[test]
+    fn check_clusters_by_id_or_name() {
+        let cluster_id = uuid::Uuid::new_v4();
+        let clusters = std::collections::HashMap::from([(cluster_id, crate::controller::server::PeerCluster::new_for_test("domain-1"))]);
+
+        let by_name = request(vec![function("a", Some("domain-1"), &[]), function("b", None, &[])]);
+        assert!(super::WorkflowSplitter::check_clusters(&by_name, &clusters).is_ok());
+        assert_eq!(super::WorkflowSplitter::peer_cluster("domain-1", &clusters), Some(cluster_id));
+
+        let by_id = request(vec![function("a", Some(&cluster_id.to_string()), &[])]);
+        assert!(super::WorkflowSplitter::check_clusters(&by_id, &clusters).is_ok());
+
+        let unknown = request(vec![function("a", Some("domain-1"), &[]), function("b", Some("domain-2"), &[])]);
+        let err = super::WorkflowSplitter::check_clusters(&unknown, &clusters).unwrap_err();
+        assert!(err.to_string().contains("domain-2"), "{}", err);
+        assert!(super::WorkflowSplitter::check_clusters(&unknown, &std::collections::HashMap::new()).is_err());
     }
 }
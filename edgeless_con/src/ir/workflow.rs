@@ -20,8 +20,9 @@ pub struct ActiveWorkflow {
 
 impl ActiveWorkflow {
     pub fn new(request: edgeless_api::workflow_instance::SpawnWorkflowRequest, id: edgeless_api::workflow_instance::WorkflowId) -> Self {
-        if !request.annotations.is_empty() {
-            log::warn!("Workflow annotations ({}) are currently ignored", request.annotations.len());
+        let ignored_annotations = request.annotations.keys().filter(|k| k.as_str() != "placement_strategy").count();
+        if ignored_annotations > 0 {
+            log::warn!("Workflow annotations ({}) are currently ignored", ignored_annotations);
         }
 
         ActiveWorkflow {
@@ -76,6 +77,8 @@ impl ActiveWorkflow {
             Some(component as &std::cell::RefCell<dyn LogicalComponent>)
         } else if let Some(compoenent) = self.resources.get(component_name) {
             return Some(compoenent as &std::cell::RefCell<dyn LogicalComponent>);
+        } else if let Some(component) = self.subflows.get(component_name) {
+            Some(component as &std::cell::RefCell<dyn LogicalComponent>)
         } else if component_name == "__proxy" {
             return Some(&self.proxy as &std::cell::RefCell<dyn LogicalComponent>);
         } else {
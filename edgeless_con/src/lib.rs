@@ -5,38 +5,78 @@
 mod controller;
 mod ir;
 mod orchestration_logic;
+mod orchestration_strategies;
 mod orchestration_utils;
+mod persistence;
 
+pub use orchestration_utils::OrchestrationStrategy;
+
+/// Peer cluster whose controller this controller announces itself to.
 #[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
 pub struct EdgelessConOrcConfig {
+    /// Name of the peer cluster, only used for logging.
     pub domain_id: String,
+    /// URL of the controller of the peer cluster.
     pub orchestrator_url: String,
 }
 
 #[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
 pub struct EdgelessConSettings {
     pub controller_url: String,
+    /// Name of this cluster announced to the peer clusters, which can be
+    /// used in the `cluster` annotation of the functions.
+    #[serde(default)]
+    pub domain_id: String,
     pub orchestrators: Vec<EdgelessConOrcConfig>,
+    /// Placement strategy used for workflows that do not specify one
+    /// through the `placement_strategy` annotation.
+    #[serde(default)]
+    pub orchestration_strategy: OrchestrationStrategy,
+    /// Where to keep the state that must survive a restart of the controller.
+    /// If not specified, the state is only kept in memory.
+    #[serde(default)]
+    pub persistence: Option<EdgelessConPersistenceSettings>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct EdgelessConPersistenceSettings {
+    /// Type of store: "file" to use a journal on the local filesystem,
+    /// or "none".
+    pub backend: String,
+    /// Path of the journal, with the file backend.
+    pub path: String,
 }
 
 pub async fn edgeless_con_main(settings: EdgelessConSettings) {
+    edgeless_con_main_until(settings, futures::future::pending()).await
+}
+
+/// Runs the controller until `shutdown` completes, then deregisters this
+/// cluster from its peer clusters.
+pub async fn edgeless_con_main_until(settings: EdgelessConSettings, shutdown: impl futures::Future<Output = ()> + Send + 'static) {
     log::info!("Starting Edgeless Controller at {}", settings.controller_url);
     log::debug!("Settings: {:?}", settings);
 
-    let (mut controller, controller_task) = controller::Controller::new_from_config(settings.clone()).await;
+    let (mut controller, controller_task) = controller::Controller::new_from_config(settings.clone(), shutdown).await;
 
     let server_task =
         edgeless_api::grpc_impl::controller::WorkflowInstanceAPIServer::run(controller.get_api_client(), settings.controller_url.clone());
 
-    futures::join!(controller_task, server_task);
+    // The controller task only completes when shutting down.
+    tokio::select! {
+        _ = controller_task => {}
+        _ = server_task => {}
+    }
 }
 
 pub fn edgeless_con_default_conf() -> String {
     String::from(
         r##"controller_url = "http://127.0.0.1:7001"
-orchestrators = [
-    { domain_id = "domain-1", orchestrator_url="http://127.0.0.1:7011" }
-]
+domain_id = "domain-1"
+orchestrators = []
+orchestration_strategy = "Random"
+# Set the backend to "file" to keep the state across restarts.
+persistence = { backend = "none", path = "controller.journal" }
 "##,
     )
 }
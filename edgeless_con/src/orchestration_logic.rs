@@ -5,34 +5,32 @@
 
 // Originally copied over from edgeless_orc.
 
-use rand::distributions::Distribution;
-use rand::SeedableRng;
-
 /// Keeps all the necessary state that is needed to make simple orchestration
 /// decisions. Provides convenience methods that can be used by the
 /// orchestrator.
 pub struct OrchestrationLogic {
-    /// Orchestration strategy.
+    /// Orchestration strategy used unless a workflow asks for a different one.
     orchestration_strategy: crate::orchestration_utils::OrchestrationStrategy,
-    /// Random-number generator.
-    rng: rand::rngs::StdRng,
+    /// Instantiated placement strategies, created on first use.
+    strategies:
+        std::collections::HashMap<crate::orchestration_utils::OrchestrationStrategy, Box<dyn crate::orchestration_strategies::PlacementStrategy>>,
 }
 
 impl OrchestrationLogic {
     pub fn new(orchestration_strategy: crate::orchestration_utils::OrchestrationStrategy) -> Self {
-        match orchestration_strategy {
-            crate::orchestration_utils::OrchestrationStrategy::Random => log::info!("Orchestration logic strategy: random"),
-        };
+        log::info!("Orchestration logic strategy: {}", orchestration_strategy);
 
         Self {
             orchestration_strategy,
-            rng: rand::rngs::StdRng::from_entropy(),
+            strategies: std::collections::HashMap::new(),
         }
     }
 
     /// Return true if it is possible to assign a function requesting a given
     /// run-time and with given deployment requirements to a node with
     /// given UUID and capabilities.
+    /// The maximum number of instances is not considered here, since it
+    /// is enforced by the placement when creating the instances.
     pub fn is_node_feasible(
         runtime: &str,
         reqs: &crate::orchestration_utils::DeploymentRequirements,
@@ -43,6 +41,9 @@ impl OrchestrationLogic {
         if !Self::runtime_supported(runtime, &capabilities.runtimes[..]) {
             return false;
         }
+        if !reqs.node_id_match_any.is_empty() && !reqs.node_id_match_any.contains(node_id) {
+            return false;
+        }
         for label in reqs.label_match_all.iter() {
             if !capabilities.labels.contains(label) {
                 return false;
@@ -73,46 +74,46 @@ impl OrchestrationLogic {
     }
 
     /// Select the next node on which a function instance should be spawned,
-    /// based on a general orchestration strategy as defined in the settings.
+    /// based on the given orchestration strategy or, if none is given, on the
+    /// general orchestration strategy as defined in the settings.
     /// Always match the deployment requirements specified with the nodes'
     /// capabilities.
+    /// `workflow_nodes` contains the nodes already hosting instances of the
+    /// same workflow.
     pub fn next(
         &mut self,
         node_pool: &std::collections::HashMap<edgeless_api::function_instance::NodeId, crate::controller::server::WorkerNode>,
         compute_type: &str,
         annotations: &std::collections::HashMap<String, String>,
+        strategy: Option<&crate::orchestration_utils::OrchestrationStrategy>,
+        workflow_nodes: &[edgeless_api::function_instance::NodeId],
     ) -> Option<uuid::Uuid> {
         if node_pool.is_empty() {
             log::info!("No nodes");
             return None;
         }
         let reqs = crate::orchestration_utils::DeploymentRequirements::from_annotations(annotations);
-        match self.orchestration_strategy {
-            crate::orchestration_utils::OrchestrationStrategy::Random => {
-                // Select only the nodes that are feasible.
-                let mut candidates = vec![];
-                let mut high: f32 = 0.0;
-                for (node_id, node_desc) in node_pool {
-                    if Self::is_node_feasible(compute_type, &reqs, node_id, &node_desc.capabilities, &node_desc.resource_providers) {
-                        candidates.push((*node_id, node_desc.weight));
-                        high += &node_desc.weight;
-                    }
-                }
-                if high > 0.0 {
-                    let rv = rand::distributions::Uniform::new(0.0, high);
-                    let rnd = rv.sample(&mut self.rng);
-                    let mut sum = 0.0_f32;
-                    for (node_id, node_weight) in candidates {
-                        sum += node_weight;
-                        if sum >= rnd {
-                            return Some(node_id);
-                        }
-                    }
-                }
-                log::info!("Random no High");
-                None
-            }
+
+        // Select only the nodes that are feasible and not cordoned.
+        let mut candidates: Vec<_> = node_pool
+            .iter()
+            .filter(|(node_id, node_desc)| {
+                !node_desc.cordoned && Self::is_node_feasible(compute_type, &reqs, node_id, &node_desc.capabilities, &node_desc.resource_providers)
+            })
+            .map(|(node_id, node_desc)| (*node_id, node_desc))
+            .collect();
+        if candidates.is_empty() {
+            log::info!("No feasible nodes");
+            return None;
         }
+        // Stateful strategies rely on a stable order of the candidates.
+        candidates.sort_by_key(|(node_id, _)| *node_id);
+
+        let strategy = strategy.unwrap_or(&self.orchestration_strategy).clone();
+        self.strategies
+            .entry(strategy.clone())
+            .or_insert_with(|| crate::orchestration_strategies::new_strategy(&strategy))
+            .select(&candidates, annotations, workflow_nodes)
     }
 
     fn runtime_supported(requested_runtime: &str, available_runtimes: &[String]) -> bool {
@@ -0,0 +1,306 @@
+// SPDX-FileCopyrightText: © 2024 Technical University of Munich, Chair of Connected Mobility
+// SPDX-License-Identifier: MIT
+
+use rand::distributions::Distribution;
+use rand::SeedableRng;
+
+/// Strategy used by the `OrchestrationLogic` to pick one node among the
+/// nodes that are feasible for a given function instance.
+/// Implementations may keep state across invocations (e.g., round-robin).
+pub trait PlacementStrategy: Send + Sync {
+    /// Select one of the `candidates`, which are guaranteed to be non-empty,
+    /// feasible and sorted by node identifier.
+    /// `workflow_nodes` contains the nodes already hosting instances of the
+    /// workflow that is being placed (one entry per instance).
+    fn select(
+        &mut self,
+        candidates: &[(edgeless_api::function_instance::NodeId, &crate::controller::server::WorkerNode)],
+        annotations: &std::collections::HashMap<String, String>,
+        workflow_nodes: &[edgeless_api::function_instance::NodeId],
+    ) -> Option<edgeless_api::function_instance::NodeId>;
+}
+
+/// Create a new instance of the placement strategy.
+pub fn new_strategy(strategy: &crate::orchestration_utils::OrchestrationStrategy) -> Box<dyn PlacementStrategy> {
+    match strategy {
+        crate::orchestration_utils::OrchestrationStrategy::Random => Box::new(RandomPlacement::new()),
+        crate::orchestration_utils::OrchestrationStrategy::RoundRobin => Box::new(RoundRobinPlacement::new()),
+        crate::orchestration_utils::OrchestrationStrategy::LeastLoaded => Box::new(LeastLoadedPlacement {}),
+        crate::orchestration_utils::OrchestrationStrategy::BinPacking => Box::new(BinPackingPlacement {}),
+        crate::orchestration_utils::OrchestrationStrategy::Spread => Box::new(SpreadPlacement {}),
+    }
+}
+
+/// Random selection, where each node is weighted by its capabilities.
+pub struct RandomPlacement {
+    rng: rand::rngs::StdRng,
+}
+
+impl RandomPlacement {
+    pub fn new() -> Self {
+        Self {
+            rng: rand::rngs::StdRng::from_entropy(),
+        }
+    }
+}
+
+impl PlacementStrategy for RandomPlacement {
+    fn select(
+        &mut self,
+        candidates: &[(edgeless_api::function_instance::NodeId, &crate::controller::server::WorkerNode)],
+        _annotations: &std::collections::HashMap<String, String>,
+        _workflow_nodes: &[edgeless_api::function_instance::NodeId],
+    ) -> Option<edgeless_api::function_instance::NodeId> {
+        let high: f32 = candidates.iter().map(|(_, node_desc)| node_desc.weight).sum();
+        if high > 0.0 {
+            let rv = rand::distributions::Uniform::new(0.0, high);
+            let rnd = rv.sample(&mut self.rng);
+            let mut sum = 0.0_f32;
+            for (node_id, node_desc) in candidates {
+                sum += node_desc.weight;
+                if sum >= rnd {
+                    return Some(*node_id);
+                }
+            }
+        }
+        log::info!("Random no High");
+        None
+    }
+}
+
+/// Cycle through the candidates.
+pub struct RoundRobinPlacement {
+    next: usize,
+}
+
+impl RoundRobinPlacement {
+    pub fn new() -> Self {
+        Self { next: 0 }
+    }
+}
+
+impl PlacementStrategy for RoundRobinPlacement {
+    fn select(
+        &mut self,
+        candidates: &[(edgeless_api::function_instance::NodeId, &crate::controller::server::WorkerNode)],
+        _annotations: &std::collections::HashMap<String, String>,
+        _workflow_nodes: &[edgeless_api::function_instance::NodeId],
+    ) -> Option<edgeless_api::function_instance::NodeId> {
+        let (node_id, _) = candidates.get(self.next % candidates.len())?;
+        self.next = self.next.wrapping_add(1);
+        Some(*node_id)
+    }
+}
+
+/// Select the node with the lowest CPU usage, breaking ties with the
+/// amount of memory available.
+pub struct LeastLoadedPlacement {}
+
+impl PlacementStrategy for LeastLoadedPlacement {
+    fn select(
+        &mut self,
+        candidates: &[(edgeless_api::function_instance::NodeId, &crate::controller::server::WorkerNode)],
+        _annotations: &std::collections::HashMap<String, String>,
+        _workflow_nodes: &[edgeless_api::function_instance::NodeId],
+    ) -> Option<edgeless_api::function_instance::NodeId> {
+        candidates
+            .iter()
+            .min_by_key(|(_, node_desc)| {
+                (
+                    node_desc.health_status.cpu_usage.max(0),
+                    std::cmp::Reverse(node_desc.health_status.mem_available),
+                )
+            })
+            .map(|(node_id, _)| *node_id)
+    }
+}
+
+/// Best-fit selection based on the memory left on the nodes.
+/// The memory demand of the function instance, in MiB, is read from the
+/// `mem_size` annotation, if present.
+pub struct BinPackingPlacement {}
+
+impl BinPackingPlacement {
+    /// Memory that is still available on the node, in MiB.
+    /// Falls back to the declared memory size until the node reports its health status.
+    fn free_memory(node_desc: &crate::controller::server::WorkerNode) -> u32 {
+        if node_desc.health_status.mem_available > 0 {
+            node_desc.health_status.mem_available as u32 / 1024
+        } else {
+            node_desc.capabilities.mem_size
+        }
+    }
+}
+
+impl PlacementStrategy for BinPackingPlacement {
+    fn select(
+        &mut self,
+        candidates: &[(edgeless_api::function_instance::NodeId, &crate::controller::server::WorkerNode)],
+        annotations: &std::collections::HashMap<String, String>,
+        _workflow_nodes: &[edgeless_api::function_instance::NodeId],
+    ) -> Option<edgeless_api::function_instance::NodeId> {
+        let demand = annotations.get("mem_size").and_then(|val| val.parse::<u32>().ok()).unwrap_or_default();
+        candidates
+            .iter()
+            .map(|(node_id, node_desc)| (*node_id, Self::free_memory(node_desc)))
+            .filter(|(_, free)| *free >= demand)
+            .min_by_key(|(_, free)| *free)
+            .map(|(node_id, _)| node_id)
+    }
+}
+
+/// Select the node hosting the fewest instances of the same workflow,
+/// preferring nodes with a higher weight on ties.
+pub struct SpreadPlacement {}
+
+impl PlacementStrategy for SpreadPlacement {
+    fn select(
+        &mut self,
+        candidates: &[(edgeless_api::function_instance::NodeId, &crate::controller::server::WorkerNode)],
+        _annotations: &std::collections::HashMap<String, String>,
+        workflow_nodes: &[edgeless_api::function_instance::NodeId],
+    ) -> Option<edgeless_api::function_instance::NodeId> {
+        let mut best: Option<(edgeless_api::function_instance::NodeId, usize, f32)> = None;
+        for (node_id, node_desc) in candidates {
+            let hosted = workflow_nodes.iter().filter(|n| *n == node_id).count();
+            match best {
+                Some((_, best_hosted, best_weight)) if hosted > best_hosted || (hosted == best_hosted && node_desc.weight <= best_weight) => {}
+                _ => best = Some((*node_id, hosted, node_desc.weight)),
+            }
+        }
+        best.map(|(node_id, _, _)| node_id)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::PlacementStrategy;
+
+    fn nodes(num: usize) -> Vec<(edgeless_api::function_instance::NodeId, crate::controller::server::WorkerNode)> {
+        let mut nodes: Vec<_> = (0..num)
+            .map(|_| {
+                (
+                    uuid::Uuid::new_v4(),
+                    crate::controller::server::WorkerNode::new_for_test(edgeless_api::node_registration::NodeCapabilities::minimum()),
+                )
+            })
+            .collect();
+        nodes.sort_by_key(|(node_id, _)| *node_id);
+        nodes
+    }
+
+    fn candidates(
+        nodes: &[(edgeless_api::function_instance::NodeId, crate::controller::server::WorkerNode)],
+    ) -> Vec<(edgeless_api::function_instance::NodeId, &crate::controller::server::WorkerNode)> {
+        nodes.iter().map(|(node_id, node_desc)| (*node_id, node_desc)).collect()
+    }
+
+    fn mem_size(size: u32) -> std::collections::HashMap<String, String> {
+        std::collections::HashMap::from([("mem_size".to_string(), size.to_string())])
+    }
+
+    #[test]
+    fn random_placement_by_weight() {
+        let mut nodes = nodes(3);
+        nodes[0].1.weight = 0.0;
+        nodes[1].1.weight = 3.0;
+        nodes[2].1.weight = 1.0;
+        let mut strategy = super::RandomPlacement::new();
+
+        let mut selected = std::collections::HashMap::new();
+        for _ in 0..1000 {
+            let node_id = strategy.select(&candidates(&nodes), &std::collections::HashMap::new(), &[]).unwrap();
+            *selected.entry(node_id).or_insert(0) += 1;
+        }
+        assert!(selected.get(&nodes[0].0).copied().unwrap_or_default() <= 1);
+        assert!(selected[&nodes[1].0] > selected[&nodes[2].0]);
+
+        for (_, node_desc) in &mut nodes {
+            node_desc.weight = 0.0;
+        }
+        assert!(strategy.select(&candidates(&nodes), &std::collections::HashMap::new(), &[]).is_none());
+    }
+
+    #[test]
+    fn round_robin_placement_cycles() {
+        let nodes = nodes(3);
+        let mut strategy = super::RoundRobinPlacement::new();
+
+        let selected: Vec<_> = (0..6)
+            .map(|_| strategy.select(&candidates(&nodes), &std::collections::HashMap::new(), &[]).unwrap())
+            .collect();
+        let expected: Vec<_> = nodes.iter().chain(nodes.iter()).map(|(node_id, _)| *node_id).collect();
+        assert_eq!(selected, expected);
+
+        // The cycle continues with fewer candidates.
+        assert_eq!(
+            strategy.select(&candidates(&nodes[..2]), &std::collections::HashMap::new(), &[]),
+            Some(nodes[0].0)
+        );
+    }
+
+    #[test]
+    fn least_loaded_placement() {
+        let mut nodes = nodes(3);
+        nodes[0].1.health_status.cpu_usage = 50;
+        nodes[1].1.health_status.cpu_usage = 10;
+        nodes[1].1.health_status.mem_available = 1024;
+        nodes[2].1.health_status.cpu_usage = 10;
+        nodes[2].1.health_status.mem_available = 2048;
+        let mut strategy = super::LeastLoadedPlacement {};
+        assert_eq!(
+            strategy.select(&candidates(&nodes), &std::collections::HashMap::new(), &[]),
+            Some(nodes[2].0)
+        );
+
+        // An invalid CPU usage, i.e., not reported yet, counts as idle.
+        nodes[0].1.health_status.cpu_usage = -1;
+        assert_eq!(
+            strategy.select(&candidates(&nodes), &std::collections::HashMap::new(), &[]),
+            Some(nodes[0].0)
+        );
+    }
+
+    #[test]
+    fn bin_packing_placement_memory_units() {
+        let mut nodes = nodes(3);
+        // The nodes report the available memory in KiB, while the demand of
+        // the function and the declared memory size of the nodes are in MiB.
+        nodes[0].1.health_status.mem_available = 2048 * 1024;
+        nodes[1].1.health_status.mem_available = 1024 * 1024;
+        nodes[2].1.capabilities.mem_size = 4096;
+        let mut strategy = super::BinPackingPlacement {};
+
+        assert_eq!(strategy.select(&candidates(&nodes), &mem_size(512), &[]), Some(nodes[1].0));
+        assert_eq!(strategy.select(&candidates(&nodes), &mem_size(1024), &[]), Some(nodes[1].0));
+        assert_eq!(strategy.select(&candidates(&nodes), &mem_size(1500), &[]), Some(nodes[0].0));
+        assert_eq!(strategy.select(&candidates(&nodes), &mem_size(3000), &[]), Some(nodes[2].0));
+        assert_eq!(strategy.select(&candidates(&nodes), &mem_size(8192), &[]), None);
+
+        // Without a demand, the fullest node is selected.
+        assert_eq!(
+            strategy.select(&candidates(&nodes), &std::collections::HashMap::new(), &[]),
+            Some(nodes[1].0)
+        );
+    }
+
+    #[test]
+    fn spread_placement() {
+        let mut nodes = nodes(3);
+        nodes[1].1.weight = 2.0;
+        let mut strategy = super::SpreadPlacement {};
+        let annotations = std::collections::HashMap::new();
+
+        // On ties, the node with the highest weight is selected.
+        assert_eq!(strategy.select(&candidates(&nodes), &annotations, &[]), Some(nodes[1].0));
+        assert_eq!(strategy.select(&candidates(&nodes), &annotations, &[nodes[1].0]), Some(nodes[0].0));
+        assert_eq!(
+            strategy.select(&candidates(&nodes), &annotations, &[nodes[1].0, nodes[0].0]),
+            Some(nodes[2].0)
+        );
+        assert_eq!(
+            strategy.select(&candidates(&nodes), &annotations, &[nodes[1].0, nodes[0].0, nodes[2].0, nodes[0].0]),
+            Some(nodes[1].0)
+        );
+    }
+}
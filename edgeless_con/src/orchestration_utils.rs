@@ -17,6 +17,9 @@ pub struct DeploymentRequirements {
     /// Maximum number of function instances in this orchestration domain.
     /// 0 means unlimited.
     pub max_instances: usize,
+    /// Minimum number of function instances (replicas) to be spawned in this
+    /// orchestration domain. 0 and 1 both mean a single instance.
+    pub min_instances: usize,
     /// The function instance must be created on a node matching one
     /// of the given UUIDs, if any is given.
     pub node_id_match_any: Vec<uuid::Uuid>,
@@ -59,8 +62,9 @@ impl std::fmt::Display for DeploymentRequirements {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(
             f,
-            "max_instances {}, node_id_match_any {}, label_match_all {}, resource_match_all {}, tee {}, tpm {}",
+            "max_instances {}, min_instances {}, node_id_match_any {}, label_match_all {}, resource_match_all {}, tee {}, tpm {}",
             self.max_instances,
+            self.min_instances,
             self.node_id_match_any.iter().map(|x| x.to_string()).collect::<Vec<String>>().join(","),
             self.label_match_all.join(","),
             self.resource_match_all.join(","),
@@ -76,6 +80,7 @@ impl DeploymentRequirements {
     pub fn none() -> Self {
         Self {
             max_instances: 0,
+            min_instances: 0,
             node_id_match_any: vec![],
             label_match_all: vec![],
             resource_match_all: vec![],
@@ -83,6 +88,18 @@ impl DeploymentRequirements {
             tpm: AffinityLevel::NotRequired,
         }
     }
+
+    /// Number of function instances that should be running, i.e., the
+    /// requested replicas capped by the maximum number of instances.
+    pub fn target_instances(&self) -> usize {
+        let target = std::cmp::max(self.min_instances, 1);
+        if self.max_instances > 0 {
+            std::cmp::min(target, self.max_instances)
+        } else {
+            target
+        }
+    }
+
     /// Deployment requirements from the annotations in the function's spawn request.
     pub fn from_annotations(annotations: &std::collections::HashMap<String, String>) -> Self {
         let mut max_instances = 0;
@@ -90,6 +107,11 @@ impl DeploymentRequirements {
             max_instances = val.parse::<usize>().unwrap_or_default();
         }
 
+        let mut min_instances = 0;
+        if let Some(val) = annotations.get("min_instances").or(annotations.get("replicas")) {
+            min_instances = val.parse::<usize>().unwrap_or_default();
+        }
+
         let mut node_id_match_any = vec![];
         if let Some(val) = annotations.get("node_id_match_any") {
             node_id_match_any = val.split(",").filter_map(|x| uuid::Uuid::parse_str(x).ok()).collect();
@@ -117,6 +139,7 @@ impl DeploymentRequirements {
 
         Self {
             max_instances,
+            min_instances,
             node_id_match_any,
             label_match_all,
             resource_match_all,
@@ -126,9 +149,181 @@ impl DeploymentRequirements {
     }
 }
 
-#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+#[derive(PartialEq, Debug, Clone)]
+/// Autoscaling policy for functions, as specified by annotations.
+/// The number of instances is kept between the target number of instances
+/// of the deployment requirements and max_instances (if not 0).
+pub struct AutoscalingPolicy {
+    /// Add an instance when the mean invocation rate per instance, in
+    /// invocations/s, is above this value.
+    pub scale_out_rate: Option<f64>,
+    /// Remove an instance when the mean invocation rate per instance, in
+    /// invocations/s, is below this value.
+    pub scale_in_rate: Option<f64>,
+    /// Add an instance when the mean processing time of any instance, in ms,
+    /// is above this value.
+    pub soft_limit_ms: Option<f64>,
+    /// Like soft_limit_ms, but the instance is added without waiting for
+    /// the window to elapse since the last scaling action.
+    pub hard_limit_ms: Option<f64>,
+    /// Period over which the metrics are evaluated, which is also the
+    /// minimum time between two scaling actions.
+    pub window: std::time::Duration,
+}
+
+impl std::fmt::Display for AutoscalingPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let fmt_opt = |val: &Option<f64>| match val {
+            Some(val) => val.to_string(),
+            None => "-".to_string(),
+        };
+        write!(
+            f,
+            "scale_out_rate {}, scale_in_rate {}, soft_limit_ms {}, hard_limit_ms {}, window {} s",
+            fmt_opt(&self.scale_out_rate),
+            fmt_opt(&self.scale_in_rate),
+            fmt_opt(&self.soft_limit_ms),
+            fmt_opt(&self.hard_limit_ms),
+            self.window.as_secs()
+        )
+    }
+}
+
+impl AutoscalingPolicy {
+    /// Autoscaling policy from the annotations in the function's spawn request.
+    pub fn from_annotations(annotations: &std::collections::HashMap<String, String>) -> Self {
+        let parse = |key: &str| annotations.get(key).and_then(|val| val.parse::<f64>().ok()).filter(|val| *val > 0.0);
+
+        let mut window = std::time::Duration::from_secs(30);
+        if let Some(val) = annotations.get("autoscale_window") {
+            if let Ok(val) = val.parse::<u64>() {
+                window = std::time::Duration::from_secs(std::cmp::max(val, 1));
+            }
+        }
+
+        Self {
+            scale_out_rate: parse("scale_out_rate"),
+            scale_in_rate: parse("scale_in_rate"),
+            soft_limit_ms: parse("soft_limit_ms"),
+            hard_limit_ms: parse("hard_limit_ms"),
+            window,
+        }
+    }
+
+    /// True if at least one threshold has been specified.
+    pub fn is_enabled(&self) -> bool {
+        self.scale_out_rate.is_some() || self.scale_in_rate.is_some() || self.soft_limit_ms.is_some() || self.hard_limit_ms.is_some()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default, serde::Deserialize, serde::Serialize)]
 pub enum OrchestrationStrategy {
     /// Random strategy utilizes a random number generator to select the worker
     /// node where a function instance is started. It is the default strategy.
+    #[default]
     Random,
+    /// RoundRobin strategy cycles through the feasible worker nodes, in order
+    /// of their identifiers.
+    RoundRobin,
+    /// LeastLoaded strategy selects the feasible worker node with the lowest
+    /// CPU usage, as reported in the last keep-alive.
+    LeastLoaded,
+    /// BinPacking strategy selects the feasible worker node with the least
+    /// memory left that can still host the function instance.
+    BinPacking,
+    /// Spread strategy selects the feasible worker node that hosts the fewest
+    /// instances of the same workflow (anti-affinity).
+    Spread,
+}
+
+impl std::fmt::Display for OrchestrationStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                OrchestrationStrategy::Random => "random",
+                OrchestrationStrategy::RoundRobin => "round-robin",
+                OrchestrationStrategy::LeastLoaded => "least-loaded",
+                OrchestrationStrategy::BinPacking => "bin-packing",
+                OrchestrationStrategy::Spread => "spread",
+            }
+        )
+    }
+}
+
+impl OrchestrationStrategy {
+    /// Parse the strategy from the value of a `placement_strategy` annotation.
+    pub fn from_string(val: &str) -> Option<Self> {
+        match val.to_lowercase().replace(['-', '_'], "").as_str() {
+            "random" => Some(OrchestrationStrategy::Random),
+            "roundrobin" => Some(OrchestrationStrategy::RoundRobin),
+            "leastloaded" => Some(OrchestrationStrategy::LeastLoaded),
+            "binpacking" => Some(OrchestrationStrategy::BinPacking),
+            "spread" | "antiaffinity" => Some(OrchestrationStrategy::Spread),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    fn annotations(values: &[(&str, &str)]) -> std::collections::HashMap<String, String> {
+        values.iter().map(|(key, val)| (key.to_string(), val.to_string())).collect()
+    }
+
+    #[test]
+    fn autoscaling_policy_from_annotations() {
+        let policy = super::AutoscalingPolicy::from_annotations(&annotations(&[]));
+        assert!(!policy.is_enabled());
+        assert_eq!(policy.window, std::time::Duration::from_secs(30));
+
+        let policy = super::AutoscalingPolicy::from_annotations(&annotations(&[
+            ("scale_out_rate", "100"),
+            ("scale_in_rate", "10.5"),
+            ("soft_limit_ms", "20"),
+            ("hard_limit_ms", "50"),
+            ("autoscale_window", "60"),
+        ]));
+        assert!(policy.is_enabled());
+        assert_eq!(
+            policy,
+            super::AutoscalingPolicy {
+                scale_out_rate: Some(100.0),
+                scale_in_rate: Some(10.5),
+                soft_limit_ms: Some(20.0),
+                hard_limit_ms: Some(50.0),
+                window: std::time::Duration::from_secs(60),
+            }
+        );
+
+        for enabling in ["scale_out_rate", "scale_in_rate", "soft_limit_ms", "hard_limit_ms"] {
+            assert!(super::AutoscalingPolicy::from_annotations(&annotations(&[(enabling, "1")])).is_enabled());
+        }
+    }
+
+    #[test]
+    fn autoscaling_policy_invalid_values() {
+        let policy = super::AutoscalingPolicy::from_annotations(&annotations(&[
+            ("scale_out_rate", "0"),
+            ("scale_in_rate", "-1"),
+            ("soft_limit_ms", "fast"),
+            ("autoscale_window", "0"),
+        ]));
+        assert!(!policy.is_enabled());
+        assert_eq!(policy.window, std::time::Duration::from_secs(1));
+
+        let policy = super::AutoscalingPolicy::from_annotations(&annotations(&[("autoscale_window", "-5")]));
+        assert_eq!(policy.window, std::time::Duration::from_secs(30));
+    }
+
+    #[test]
+    fn target_instances_within_bounds() {
+        let reqs = super::DeploymentRequirements::from_annotations(&annotations(&[]));
+        assert_eq!(reqs.target_instances(), 1);
+        let reqs = super::DeploymentRequirements::from_annotations(&annotations(&[("min_instances", "3")]));
+        assert_eq!(reqs.target_instances(), 3);
+        let reqs = super::DeploymentRequirements::from_annotations(&annotations(&[("replicas", "3"), ("max_instances", "2")]));
+        assert_eq!(reqs.target_instances(), 2);
+    }
 }
@@ -0,0 +1,488 @@
+// SPDX-FileCopyrightText: © 2024 Technical University of Munich, Chair of Connected Mobility
+// SPDX-License-Identifier: MIT
+
+use std::io::Write;
+
+/// Registration of a node, as needed to connect to it again.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct NodeRecord {
+    pub node_id: edgeless_api::function_instance::NodeId,
+    pub agent_url: String,
+    pub invocation_url: String,
+    #[serde(default)]
+    pub invocation_url_quic: Option<String>,
+    /// (provider_id, class_type, outputs)
+    pub resource_providers: Vec<(String, String, Vec<String>)>,
+    pub capabilities: edgeless_api::node_registration::NodeCapabilities,
+    /// (provider_id, class)
+    pub link_providers: Vec<(uuid::Uuid, String)>,
+    /// True if no new instances can be placed on the node.
+    #[serde(default)]
+    pub cordoned: bool,
+}
+
+impl NodeRecord {
+    pub fn resource_providers(&self) -> Vec<edgeless_api::node_registration::ResourceProviderSpecification> {
+        self.resource_providers
+            .iter()
+            .map(
+                |(provider_id, class_type, outputs)| edgeless_api::node_registration::ResourceProviderSpecification {
+                    provider_id: provider_id.clone(),
+                    class_type: class_type.clone(),
+                    outputs: outputs.clone(),
+                },
+            )
+            .collect()
+    }
+
+    pub fn link_providers(&self) -> Vec<edgeless_api::node_registration::LinkProviderSpecification> {
+        self.link_providers
+            .iter()
+            .map(|(provider_id, class)| edgeless_api::node_registration::LinkProviderSpecification {
+                provider_id: edgeless_api::link::LinkProviderId(*provider_id),
+                class: edgeless_api::link::LinkType(class.clone()),
+            })
+            .collect()
+    }
+}
+
+/// Placement of a function or resource instance of a workflow.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct InstanceRecord {
+    /// Name of the function or resource in the workflow.
+    pub component: String,
+    pub node_id: edgeless_api::function_instance::NodeId,
+    pub function_id: edgeless_api::function_instance::ComponentId,
+    /// True if the instance has been started on the node.
+    pub materialized: bool,
+}
+
+/// Link created for a workflow, as needed to remove it.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct LinkRecord {
+    pub link_id: uuid::Uuid,
+    pub class: String,
+    /// True if the link has been instantiated on the control plane.
+    pub materialized: bool,
+    /// (node_id, provider_id, config, materialized)
+    pub nodes: Vec<(edgeless_api::function_instance::NodeId, uuid::Uuid, Vec<u8>, bool)>,
+}
+
+/// Spawn request of a workflow with the placement of its instances.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct WorkflowRecord {
+    pub workflow_id: uuid::Uuid,
+    pub spawn_request: edgeless_api::workflow_instance::SpawnWorkflowRequest,
+    pub functions: Vec<InstanceRecord>,
+    pub resources: Vec<InstanceRecord>,
+    /// Subflow instances, whose node is the peer cluster hosting them.
+    #[serde(default)]
+    pub subflows: Vec<InstanceRecord>,
+    /// (subflow instance, workflow spawned for it on the peer cluster)
+    #[serde(default)]
+    pub subflow_workflows: Vec<(edgeless_api::function_instance::InstanceId, uuid::Uuid)>,
+    #[serde(default)]
+    pub links: Vec<LinkRecord>,
+}
+
+/// Controller state recovered from a store.
+#[derive(Debug, Default)]
+pub struct ControllerSnapshot {
+    /// Identifier of the cluster, which must not change across restarts
+    /// since the peer clusters know the cluster by it.
+    pub cluster_id: Option<uuid::Uuid>,
+    pub nodes: std::collections::HashMap<edgeless_api::function_instance::NodeId, NodeRecord>,
+    pub workflows: std::collections::HashMap<uuid::Uuid, WorkflowRecord>,
+}
+
+/// Storage for the controller state that must survive a restart.
+/// Every put replaces the previous record with the same identifier.
+pub trait ControllerStore: Send {
+    fn load(&mut self) -> anyhow::Result<ControllerSnapshot>;
+    fn put_cluster_id(&mut self, cluster_id: &uuid::Uuid) -> anyhow::Result<()>;
+    fn put_node(&mut self, record: NodeRecord) -> anyhow::Result<()>;
+    fn remove_node(&mut self, node_id: &edgeless_api::function_instance::NodeId) -> anyhow::Result<()>;
+    fn put_workflow(&mut self, record: WorkflowRecord) -> anyhow::Result<()>;
+    fn remove_workflow(&mut self, workflow_id: &uuid::Uuid) -> anyhow::Result<()>;
+}
+
+/// Create the store from the controller settings.
+pub fn new_store(settings: &Option<crate::EdgelessConPersistenceSettings>) -> anyhow::Result<Box<dyn ControllerStore>> {
+    match settings {
+        None => Ok(Box::new(NoStore {})),
+        Some(settings) => match settings.backend.to_lowercase().as_str() {
+            "none" => Ok(Box::new(NoStore {})),
+            "file" => Ok(Box::new(FileJournal::new(&settings.path)?)),
+            backend => Err(anyhow::anyhow!("unknown persistence backend: {}", backend)),
+        },
+    }
+}
+
+/// Store that does not keep anything.
+pub struct NoStore {}
+
+impl ControllerStore for NoStore {
+    fn load(&mut self) -> anyhow::Result<ControllerSnapshot> {
+        Ok(ControllerSnapshot::default())
+    }
+
+    fn put_cluster_id(&mut self, _cluster_id: &uuid::Uuid) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn put_node(&mut self, _record: NodeRecord) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn remove_node(&mut self, _node_id: &edgeless_api::function_instance::NodeId) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn put_workflow(&mut self, _record: WorkflowRecord) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn remove_workflow(&mut self, _workflow_id: &uuid::Uuid) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+enum JournalEntry {
+    PutClusterId(uuid::Uuid),
+    PutNode(NodeRecord),
+    RemoveNode(edgeless_api::function_instance::NodeId),
+    PutWorkflow(WorkflowRecord),
+    RemoveWorkflow(uuid::Uuid),
+}
+
+enum JournalCommand {
+    Append(String),
+    /// Open the journal again, after it has been replaced.
+    Reopen,
+}
+
+/// Store that appends every change as a JSON line to a local file.
+/// The journal is compacted when loaded, so that it only contains the
+/// records that are still valid.
+/// The lines are written and synced to disk by a dedicated thread, so that
+/// the controller is not blocked by the disk, hence the last changes are
+/// lost if the controller crashes before they are synced.
+pub struct FileJournal {
+    path: std::path::PathBuf,
+    sender: Option<std::sync::mpsc::Sender<JournalCommand>>,
+    writer: Option<std::thread::JoinHandle<()>>,
+}
+
+impl FileJournal {
+    pub fn new(path: &str) -> anyhow::Result<Self> {
+        let path = std::path::PathBuf::from(path);
+        let mut file = Self::open(&path)?;
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let writer_path = path.clone();
+        let writer = std::thread::Builder::new().name("controller-journal".to_string()).spawn(move || {
+            for command in receiver {
+                let res = match command {
+                    JournalCommand::Append(line) => file.write_all(line.as_bytes()).and_then(|_| file.sync_data()),
+                    JournalCommand::Reopen => Self::open(&writer_path).map(|reopened| file = reopened),
+                };
+                if let Err(err) = res {
+                    log::error!("could not write to {}: {}", writer_path.display(), err);
+                }
+            }
+        })?;
+        Ok(Self {
+            path,
+            sender: Some(sender),
+            writer: Some(writer),
+        })
+    }
+
+    fn open(path: &std::path::Path) -> std::io::Result<std::fs::File> {
+        std::fs::OpenOptions::new().create(true).append(true).open(path)
+    }
+
+    fn send(&mut self, command: JournalCommand) -> anyhow::Result<()> {
+        match &self.sender {
+            Some(sender) => sender
+                .send(command)
+                .map_err(|_| anyhow::anyhow!("the writer of {} is gone", self.path.display())),
+            None => Err(anyhow::anyhow!("the writer of {} is gone", self.path.display())),
+        }
+    }
+
+    fn append(&mut self, entry: &JournalEntry) -> anyhow::Result<()> {
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+        self.send(JournalCommand::Append(line))
+    }
+}
+
+impl Drop for FileJournal {
+    /// Waits for the pending changes to be written.
+    fn drop(&mut self) {
+        self.sender.take();
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+    }
+}
+
+impl ControllerStore for FileJournal {
+    fn load(&mut self) -> anyhow::Result<ControllerSnapshot> {
+        let mut snapshot = ControllerSnapshot::default();
+        for (line_number, line) in std::fs::read_to_string(&self.path)?.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<JournalEntry>(line) {
+                Ok(JournalEntry::PutClusterId(cluster_id)) => {
+                    snapshot.cluster_id = Some(cluster_id);
+                }
+                Ok(JournalEntry::PutNode(record)) => {
+                    snapshot.nodes.insert(record.node_id, record);
+                }
+                Ok(JournalEntry::RemoveNode(node_id)) => {
+                    snapshot.nodes.remove(&node_id);
+                }
+                Ok(JournalEntry::PutWorkflow(record)) => {
+                    snapshot.workflows.insert(record.workflow_id, record);
+                }
+                Ok(JournalEntry::RemoveWorkflow(workflow_id)) => {
+                    snapshot.workflows.remove(&workflow_id);
+                }
+                Err(err) => {
+                    // The last line may have been truncated by a crash.
+                    log::warn!("skipping invalid entry at line {} of {}: {}", line_number + 1, self.path.display(), err);
+                }
+            }
+        }
+
+        // Rewrite the journal with the current records only, then swap it.
+        let tmp_path = self.path.with_extension("tmp");
+        {
+            let mut tmp_file = std::fs::File::create(&tmp_path)?;
+            if let Some(cluster_id) = snapshot.cluster_id {
+                writeln!(tmp_file, "{}", serde_json::to_string(&JournalEntry::PutClusterId(cluster_id))?)?;
+            }
+            for record in snapshot.nodes.values() {
+                writeln!(tmp_file, "{}", serde_json::to_string(&JournalEntry::PutNode(record.clone()))?)?;
+            }
+            for record in snapshot.workflows.values() {
+                writeln!(tmp_file, "{}", serde_json::to_string(&JournalEntry::PutWorkflow(record.clone()))?)?;
+            }
+            tmp_file.sync_all()?;
+        }
+        std::fs::rename(&tmp_path, &self.path)?;
+        self.send(JournalCommand::Reopen)?;
+
+        Ok(snapshot)
+    }
+
+    fn put_cluster_id(&mut self, cluster_id: &uuid::Uuid) -> anyhow::Result<()> {
+        self.append(&JournalEntry::PutClusterId(*cluster_id))
+    }
+
+    fn put_node(&mut self, record: NodeRecord) -> anyhow::Result<()> {
+        self.append(&JournalEntry::PutNode(record))
+    }
+
+    fn remove_node(&mut self, node_id: &edgeless_api::function_instance::NodeId) -> anyhow::Result<()> {
+        self.append(&JournalEntry::RemoveNode(*node_id))
+    }
+
+    fn put_workflow(&mut self, record: WorkflowRecord) -> anyhow::Result<()> {
+        self.append(&JournalEntry::PutWorkflow(record))
+    }
+
+    fn remove_workflow(&mut self, workflow_id: &uuid::Uuid) -> anyhow::Result<()> {
+        self.append(&JournalEntry::RemoveWorkflow(*workflow_id))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ControllerStore;
+
+    fn journal_path() -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("edgeless-con-journal-{}", uuid::Uuid::new_v4()))
+    }
+
+    fn node(node_id: edgeless_api::function_instance::NodeId) -> super::NodeRecord {
+        super::NodeRecord {
+            node_id,
+            agent_url: "http://127.0.0.1:7021".to_string(),
+            invocation_url: "http://127.0.0.1:7002".to_string(),
+            invocation_url_quic: None,
+            resource_providers: vec![("file-log-1".to_string(), "file-log".to_string(), vec![])],
+            capabilities: edgeless_api::node_registration::NodeCapabilities::minimum(),
+            link_providers: vec![(uuid::Uuid::new_v4(), "MULTICAST".to_string())],
+            cordoned: false,
+        }
+    }
+
+    fn workflow(workflow_id: uuid::Uuid, component: &str) -> super::WorkflowRecord {
+        let node_id = uuid::Uuid::new_v4();
+        let cluster_id = uuid::Uuid::new_v4();
+        let subflow_id = edgeless_api::function_instance::InstanceId::new(cluster_id);
+        super::WorkflowRecord {
+            workflow_id,
+            spawn_request: edgeless_api::workflow_instance::SpawnWorkflowRequest {
+                workflow_functions: vec![],
+                workflow_resources: vec![],
+                workflow_ingress_proxies: vec![edgeless_api::workflow_instance::WorkflowIngressProxy {
+                    id: "in".to_string(),
+                    inner_output: edgeless_api::workflow_instance::PortMapping::DirectTarget(
+                        component.to_string(),
+                        edgeless_api::function_instance::PortId("in".to_string()),
+                    ),
+                    external_input: Some(edgeless_api::common::Input::Link(
+                        edgeless_api::link::LinkInstanceId(uuid::Uuid::new_v4()),
+                    )),
+                }],
+                workflow_egress_proxies: vec![edgeless_api::workflow_instance::WorkflowEgressProxy {
+                    id: "out".to_string(),
+                    inner_input: edgeless_api::workflow_instance::PortMapping::DirectTarget(
+                        component.to_string(),
+                        edgeless_api::function_instance::PortId("out".to_string()),
+                    ),
+                    external_output: edgeless_api::common::Output::Single(
+                        edgeless_api::function_instance::InstanceId::new(node_id),
+                        edgeless_api::function_instance::PortId("in".to_string()),
+                    ),
+                }],
+                annotations: std::collections::HashMap::new(),
+            },
+            functions: vec![super::InstanceRecord {
+                component: component.to_string(),
+                node_id,
+                function_id: uuid::Uuid::new_v4(),
+                materialized: true,
+            }],
+            resources: vec![],
+            subflows: vec![super::InstanceRecord {
+                component: "__subflow_domain-2".to_string(),
+                node_id: subflow_id.node_id,
+                function_id: subflow_id.function_id,
+                materialized: true,
+            }],
+            subflow_workflows: vec![(subflow_id, uuid::Uuid::new_v4())],
+            links: vec![super::LinkRecord {
+                link_id: uuid::Uuid::new_v4(),
+                class: "MULTICAST".to_string(),
+                materialized: true,
+                nodes: vec![(node_id, uuid::Uuid::new_v4(), vec![1, 2, 3], true)],
+            }],
+        }
+    }
+
+    fn num_lines(path: &std::path::Path) -> usize {
+        std::fs::read_to_string(path).unwrap().lines().count()
+    }
+
+    #[test]
+    fn load_saved_records() {
+        let path = journal_path();
+        let node_record = node(uuid::Uuid::new_v4());
+        let workflow_record = workflow(uuid::Uuid::new_v4(), "f1");
+        let cluster_id = uuid::Uuid::new_v4();
+        {
+            let mut journal = super::FileJournal::new(path.to_str().unwrap()).unwrap();
+            let snapshot = journal.load().unwrap();
+            assert!(snapshot.cluster_id.is_none());
+            assert!(snapshot.nodes.is_empty());
+            journal.put_cluster_id(&cluster_id).unwrap();
+            journal.put_node(node_record.clone()).unwrap();
+            journal.put_workflow(workflow_record.clone()).unwrap();
+        }
+
+        let mut journal = super::FileJournal::new(path.to_str().unwrap()).unwrap();
+        let snapshot = journal.load().unwrap();
+        assert_eq!(snapshot.cluster_id, Some(cluster_id));
+        assert_eq!(snapshot.nodes, std::collections::HashMap::from([(node_record.node_id, node_record)]));
+        assert_eq!(
+            snapshot.workflows,
+            std::collections::HashMap::from([(workflow_record.workflow_id, workflow_record)])
+        );
+
+        drop(journal);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn load_compacts_journal() {
+        let path = journal_path();
+        let node_1 = node(uuid::Uuid::new_v4());
+        let node_2 = node(uuid::Uuid::new_v4());
+        let workflow_id = uuid::Uuid::new_v4();
+        let workflow_v2 = workflow(workflow_id, "f2");
+        let cluster_id = uuid::Uuid::new_v4();
+        {
+            let mut journal = super::FileJournal::new(path.to_str().unwrap()).unwrap();
+            journal.put_cluster_id(&cluster_id).unwrap();
+            journal.put_node(node_1.clone()).unwrap();
+            journal.put_node(node_2.clone()).unwrap();
+            journal.remove_node(&node_1.node_id).unwrap();
+            journal.put_workflow(workflow(workflow_id, "f1")).unwrap();
+            journal.put_workflow(workflow_v2.clone()).unwrap();
+            journal.put_workflow(workflow(uuid::Uuid::new_v4(), "f1")).unwrap();
+            journal.remove_workflow(&workflow_v2.workflow_id).unwrap();
+            journal.put_workflow(workflow_v2.clone()).unwrap();
+        }
+        assert_eq!(num_lines(&path), 9);
+
+        let node_3 = node(uuid::Uuid::new_v4());
+        {
+            let mut journal = super::FileJournal::new(path.to_str().unwrap()).unwrap();
+            let snapshot = journal.load().unwrap();
+            assert_eq!(snapshot.cluster_id, Some(cluster_id));
+            assert_eq!(snapshot.nodes.len(), 1);
+            assert_eq!(snapshot.nodes[&node_2.node_id], node_2);
+            assert_eq!(snapshot.workflows.len(), 2);
+            assert_eq!(snapshot.workflows[&workflow_id], workflow_v2);
+
+            // The changes after the compaction are appended to the new journal.
+            journal.put_node(node_3.clone()).unwrap();
+        }
+        assert_eq!(num_lines(&path), 5);
+
+        let mut journal = super::FileJournal::new(path.to_str().unwrap()).unwrap();
+        let snapshot = journal.load().unwrap();
+        assert_eq!(snapshot.cluster_id, Some(cluster_id));
+        assert_eq!(snapshot.nodes.len(), 2);
+        assert_eq!(snapshot.nodes[&node_3.node_id], node_3);
+        assert_eq!(snapshot.workflows.len(), 2);
+
+        drop(journal);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn load_truncated_journal() {
+        let path = journal_path();
+        let node_1 = node(uuid::Uuid::new_v4());
+        let node_2 = node(uuid::Uuid::new_v4());
+        {
+            let mut journal = super::FileJournal::new(path.to_str().unwrap()).unwrap();
+            journal.put_node(node_1.clone()).unwrap();
+            journal.put_node(node_2.clone()).unwrap();
+        }
+
+        // Crash while the last line is being written.
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, &content[..content.len() - 20]).unwrap();
+
+        let mut journal = super::FileJournal::new(path.to_str().unwrap()).unwrap();
+        let snapshot = journal.load().unwrap();
+        assert_eq!(snapshot.nodes, std::collections::HashMap::from([(node_1.node_id, node_1.clone())]));
+        drop(journal);
+
+        // The invalid line is dropped by the compaction.
+        assert_eq!(num_lines(&path), 1);
+        let mut journal = super::FileJournal::new(path.to_str().unwrap()).unwrap();
+        assert_eq!(journal.load().unwrap().nodes.len(), 1);
+
+        drop(journal);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
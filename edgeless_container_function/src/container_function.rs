@@ -112,6 +112,7 @@ impl ContainerFunction {
                                     originator: instance_id.unwrap(),
                                     alias: "output".to_string(),
                                     msg: event.msg,
+                                    partition_key: None,
                                 })
                                 .await
                             {
@@ -22,19 +22,47 @@ pub trait DataPlaneLink: Send + Sync {
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum CallRet {
     NoReply,
-    Reply(String),
+    Reply(Vec<u8>),
     Err,
+    /// No reply has been received before the deadline of the call.
+    Timeout,
 }
 
+/// Outcome of a call on an output mapped to all of multiple instances.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GatherRet {
+    /// Outcome of the calls to the targets that have answered, in order of
+    /// arrival, followed by the calls still waiting for a reply at the
+    /// deadline, as `CallRet::Timeout`. The calls still waiting for a reply
+    /// when the quorum is reached are abandoned and not listed.
+    pub replies: Vec<(edgeless_api::function_instance::InstanceId, CallRet)>,
+    /// True if the quorum of the targets has answered without an error.
+    pub quorum_reached: bool,
+}
+
+/// Message exchanged over the dataplane.
+/// Payloads are opaque bytes: components exchanging text can use
+/// `payload_str()` to read them and pass `String`s or `&str`s when sending.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Message {
-    Cast(String),
-    Call(String),
-    CallRet(String),
+    Cast(Vec<u8>),
+    Call(Vec<u8>),
+    CallRet(Vec<u8>),
     CallNoRet,
     Err,
 }
 
+impl Message {
+    /// Payload of the message as a string.
+    /// None if the message has no payload or the payload is not valid UTF-8.
+    pub fn payload_str(&self) -> Option<&str> {
+        match self {
+            Message::Cast(data) | Message::Call(data) | Message::CallRet(data) => std::str::from_utf8(data).ok(),
+            Message::CallNoRet | Message::Err => None,
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct DataplaneEvent {
     pub source_id: edgeless_api::function_instance::InstanceId,
@@ -48,4 +76,199 @@ pub struct DataplaneEvent {
 pub struct EdgelessDataplanePeerSettings {
     pub node_id: uuid::Uuid,
     pub invocation_url: String,
+    /// URL of the QUIC invocation server of the peer, if any.
+    #[serde(default)]
+    pub invocation_url_quic: Option<String>,
+}
+
+/// Settings of the dataplane of a node.
+#[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize)]
+pub struct EdgelessDataplaneSettings {
+    /// Maximum number of casts and calls waiting to be processed by each
+    /// function or resource instance, 0 for no limit.
+    #[serde(default)]
+    pub queue_capacity: usize,
+    /// What happens to the events targeting an instance whose queue is full.
+    #[serde(default)]
+    pub overflow_policy: crate::queue::OverflowPolicy,
+    /// Deadline of the calls, in ms, unless overridden by the annotations
+    /// of the function. 0 for no deadline.
+    #[serde(default)]
+    pub call_timeout_ms: u64,
+    /// Do not offer multicast links, e.g., because the network of the node
+    /// does not deliver multicast traffic.
+    #[serde(default)]
+    pub disable_multicast_links: bool,
+}
+
+/// Annotation with the deadline of all the calls of a function, in ms.
+/// The deadline of the calls on a given output can be set with the
+/// annotation followed by a dot and the output name, e.g.,
+/// `call_timeout_ms.my_output`. A value of 0 means no deadline.
+pub const CALL_TIMEOUT_ANNOTATION: &str = "call_timeout_ms";
+
+/// Deadlines of the calls of a component.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CallTimeouts {
+    /// Deadline of the calls on outputs without a deadline of their own
+    /// and of the calls directly addressed to an instance.
+    pub default: Option<std::time::Duration>,
+    /// Deadline of the calls on a given output.
+    pub by_output: std::collections::HashMap<String, Option<std::time::Duration>>,
+}
+
+impl CallTimeouts {
+    /// Deadlines set by the annotations of a function, falling back
+    /// to the given default.
+    pub fn from_annotations(annotations: &std::collections::HashMap<String, String>, default: Option<std::time::Duration>) -> Self {
+        let parse = |key: &str, val: &str| match val.parse::<u64>() {
+            Ok(0) => Some(None),
+            Ok(ms) => Some(Some(std::time::Duration::from_millis(ms))),
+            Err(_) => {
+                log::warn!("invalid value of annotation {}: {}", key, val);
+                None
+            }
+        };
+
+        let mut timeouts = Self { default, ..Default::default() };
+        for (key, val) in annotations {
+            if key == CALL_TIMEOUT_ANNOTATION {
+                if let Some(timeout) = parse(key, val) {
+                    timeouts.default = timeout;
+                }
+            } else if let Some(output) = key.strip_prefix(CALL_TIMEOUT_ANNOTATION).and_then(|rest| rest.strip_prefix('.')) {
+                if let Some(timeout) = parse(key, val) {
+                    timeouts.by_output.insert(output.to_string(), timeout);
+                }
+            }
+        }
+        timeouts
+    }
+
+    /// Deadline of the calls on the given output.
+    pub fn for_output(&self, output: &str) -> Option<std::time::Duration> {
+        self.by_output.get(output).copied().unwrap_or(self.default)
+    }
+}
+
+/// Annotation with the quorum of all the calls of a function on outputs
+/// mapped to all of multiple instances. As for the deadline, the quorum of
+/// the calls on a given output can be set with, e.g., `call_quorum.my_output`.
+pub const CALL_QUORUM_ANNOTATION: &str = "call_quorum";
+
+/// Number of targets that must answer without an error a call on an output
+/// mapped to all of multiple instances.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Quorum {
+    #[default]
+    All,
+    Majority,
+    AtLeast(usize),
+}
+
+impl std::str::FromStr for Quorum {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "all" => Ok(Self::All),
+            "majority" => Ok(Self::Majority),
+            _ => match s.parse::<usize>() {
+                Ok(0) | Err(_) => Err(anyhow::anyhow!("invalid quorum: {}", s)),
+                Ok(n) => Ok(Self::AtLeast(n)),
+            },
+        }
+    }
+}
+
+impl Quorum {
+    /// Number of answers needed out of the given number of targets.
+    pub fn answers(&self, targets: usize) -> usize {
+        match self {
+            Self::All => targets,
+            Self::Majority => targets / 2 + 1,
+            Self::AtLeast(n) => std::cmp::min(*n, targets),
+        }
+    }
+}
+
+/// Quorums of the calls of a component.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CallQuorums {
+    pub default: Quorum,
+    pub by_output: std::collections::HashMap<String, Quorum>,
+}
+
+impl CallQuorums {
+    /// Quorums set by the annotations of a function.
+    pub fn from_annotations(annotations: &std::collections::HashMap<String, String>) -> Self {
+        let parse = |key: &str, val: &str| match val.parse::<Quorum>() {
+            Ok(quorum) => Some(quorum),
+            Err(err) => {
+                log::warn!("invalid value of annotation {}: {}", key, err);
+                None
+            }
+        };
+
+        let mut quorums = Self::default();
+        for (key, val) in annotations {
+            if key == CALL_QUORUM_ANNOTATION {
+                if let Some(quorum) = parse(key, val) {
+                    quorums.default = quorum;
+                }
+            } else if let Some(output) = key.strip_prefix(CALL_QUORUM_ANNOTATION).and_then(|rest| rest.strip_prefix('.')) {
+                if let Some(quorum) = parse(key, val) {
+                    quorums.by_output.insert(output.to_string(), quorum);
+                }
+            }
+        }
+        quorums
+    }
+
+    /// Quorum of the calls on the given output.
+    pub fn for_output(&self, output: &str) -> Quorum {
+        self.by_output.get(output).copied().unwrap_or(self.default)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn call_timeouts_from_annotations() {
+        let default = Some(std::time::Duration::from_secs(10));
+        let timeouts = CallTimeouts::from_annotations(&std::collections::HashMap::new(), default);
+        assert_eq!(timeouts.for_output("out"), default);
+
+        let annotations = std::collections::HashMap::from([
+            ("call_timeout_ms".to_string(), "500".to_string()),
+            ("call_timeout_ms.slow".to_string(), "0".to_string()),
+            ("call_timeout_ms.fast".to_string(), "20".to_string()),
+            ("call_timeout_ms.bad".to_string(), "x".to_string()),
+            ("init-payload".to_string(), "42".to_string()),
+        ]);
+        let timeouts = CallTimeouts::from_annotations(&annotations, default);
+        assert_eq!(timeouts.for_output("out"), Some(std::time::Duration::from_millis(500)));
+        assert_eq!(timeouts.for_output("slow"), None);
+        assert_eq!(timeouts.for_output("fast"), Some(std::time::Duration::from_millis(20)));
+        assert_eq!(timeouts.for_output("bad"), Some(std::time::Duration::from_millis(500)));
+    }
+
+    #[test]
+    fn call_quorums_from_annotations() {
+        let annotations = std::collections::HashMap::from([
+            ("call_quorum".to_string(), "majority".to_string()),
+            ("call_quorum.one".to_string(), "1".to_string()),
+            ("call_quorum.many".to_string(), "10".to_string()),
+            ("call_quorum.bad".to_string(), "0".to_string()),
+        ]);
+        let quorums = CallQuorums::from_annotations(&annotations);
+        assert_eq!(quorums.for_output("out").answers(4), 3);
+        assert_eq!(quorums.for_output("out").answers(5), 3);
+        assert_eq!(quorums.for_output("one").answers(4), 1);
+        assert_eq!(quorums.for_output("many").answers(4), 4);
+        assert_eq!(quorums.for_output("bad"), Quorum::Majority);
+        assert_eq!(CallQuorums::from_annotations(&std::collections::HashMap::new()).for_output("out").answers(4), 4);
+    }
 }
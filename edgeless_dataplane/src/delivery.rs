@@ -0,0 +1,198 @@
+// SPDX-FileCopyrightText: © 2024 Technical University of Munich, Chair of Connected Mobility
+// SPDX-License-Identifier: MIT
+
+/// Annotation selecting the delivery guarantee of the casts of a function,
+/// as `delivery` for all the outputs or as `delivery.<output>` for a single one.
+pub const DELIVERY_ANNOTATION: &str = "delivery";
+/// Annotation with the number of times an undelivered cast is sent again.
+pub const DELIVERY_RETRIES_ANNOTATION: &str = "delivery_retries";
+/// Annotation with the time, in ms, before sending an undelivered cast again
+/// for the first time. The time doubles at each retry.
+pub const DELIVERY_BACKOFF_ANNOTATION: &str = "delivery_backoff_ms";
+/// Annotation with the output receiving the casts that could not be delivered.
+pub const DEAD_LETTER_ANNOTATION: &str = "dead_letter_output";
+
+const DEFAULT_RETRIES: u32 = 5;
+const DEFAULT_BACKOFF: std::time::Duration = std::time::Duration::from_millis(100);
+const MAX_BACKOFF: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Number of sequence ids remembered for each source to detect duplicates.
+const DEDUPLICATION_WINDOW: usize = 4096;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DeliveryGuarantee {
+    /// A cast that cannot be delivered is lost.
+    #[default]
+    AtMostOnce,
+    /// A cast that cannot be delivered to a remote node is sent again until
+    /// acknowledged by the node, then passed to the dead-letter output, if any.
+    /// The receiving node discards the copies of a cast it has already seen.
+    AtLeastOnce,
+}
+
+impl std::str::FromStr for DeliveryGuarantee {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "at-most-once" => Ok(Self::AtMostOnce),
+            "at-least-once" => Ok(Self::AtLeastOnce),
+            _ => Err(anyhow::anyhow!("unknown delivery guarantee: {}", s)),
+        }
+    }
+}
+
+/// Delivery of the casts of a component.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeliverySettings {
+    /// Guarantee of the outputs without a guarantee of their own.
+    pub default: DeliveryGuarantee,
+    /// Guarantee of a given output.
+    pub by_output: std::collections::HashMap<String, DeliveryGuarantee>,
+    pub retries: u32,
+    pub backoff: std::time::Duration,
+    pub dead_letter_output: Option<String>,
+}
+
+impl Default for DeliverySettings {
+    fn default() -> Self {
+        Self {
+            default: DeliveryGuarantee::default(),
+            by_output: std::collections::HashMap::new(),
+            retries: DEFAULT_RETRIES,
+            backoff: DEFAULT_BACKOFF,
+            dead_letter_output: None,
+        }
+    }
+}
+
+impl DeliverySettings {
+    /// Settings from the annotations of a function.
+    pub fn from_annotations(annotations: &std::collections::HashMap<String, String>) -> Self {
+        let mut settings = Self::default();
+        for (key, val) in annotations {
+            if key == DELIVERY_ANNOTATION {
+                match val.parse() {
+                    Ok(guarantee) => settings.default = guarantee,
+                    Err(err) => log::warn!("invalid value of annotation {}: {}", key, err),
+                }
+            } else if let Some(output) = key.strip_prefix(DELIVERY_ANNOTATION).and_then(|rest| rest.strip_prefix('.')) {
+                match val.parse() {
+                    Ok(guarantee) => {
+                        settings.by_output.insert(output.to_string(), guarantee);
+                    }
+                    Err(err) => log::warn!("invalid value of annotation {}: {}", key, err),
+                }
+            } else if key == DELIVERY_RETRIES_ANNOTATION {
+                match val.parse() {
+                    Ok(retries) => settings.retries = retries,
+                    Err(_) => log::warn!("invalid value of annotation {}: {}", key, val),
+                }
+            } else if key == DELIVERY_BACKOFF_ANNOTATION {
+                match val.parse() {
+                    Ok(ms) => settings.backoff = std::time::Duration::from_millis(ms),
+                    Err(_) => log::warn!("invalid value of annotation {}: {}", key, val),
+                }
+            } else if key == DEAD_LETTER_ANNOTATION && !val.is_empty() {
+                settings.dead_letter_output = Some(val.clone());
+            }
+        }
+        settings
+    }
+
+    /// True if the casts on the given output must be delivered at least once.
+    /// The casts on the dead-letter output are never retried.
+    pub fn is_reliable(&self, output: &str) -> bool {
+        self.by_output.get(output).copied().unwrap_or(self.default) == DeliveryGuarantee::AtLeastOnce
+            && self.dead_letter_output.as_deref() != Some(output)
+    }
+
+    /// Time to wait before the given retry, starting from 1.
+    pub fn backoff(&self, retry: u32) -> std::time::Duration {
+        let factor = 1_u32.checked_shl(retry.saturating_sub(1)).unwrap_or(u32::MAX);
+        std::cmp::min(self.backoff.saturating_mul(factor), MAX_BACKOFF)
+    }
+}
+
+/// Sequence ids of the casts recently received from each source,
+/// used to discard the copies of the casts delivered at least once.
+#[derive(Default)]
+pub struct Deduplicator {
+    seen: std::collections::HashMap<edgeless_api::function_instance::InstanceId, (std::collections::VecDeque<u64>, std::collections::HashSet<u64>)>,
+}
+
+impl Deduplicator {
+    /// Return false if the cast with the given sequence id has already been received from the source.
+    pub fn first_seen(&mut self, source: &edgeless_api::function_instance::InstanceId, sequence_id: u64) -> bool {
+        let (order, ids) = self.seen.entry(*source).or_default();
+        if !ids.insert(sequence_id) {
+            return false;
+        }
+        order.push_back(sequence_id);
+        if order.len() > DEDUPLICATION_WINDOW {
+            if let Some(oldest) = order.pop_front() {
+                ids.remove(&oldest);
+            }
+        }
+        true
+    }
+
+    /// Forget a cast, e.g., because it could not be delivered after all.
+    pub fn forget(&mut self, source: &edgeless_api::function_instance::InstanceId, sequence_id: u64) {
+        if let Some((order, ids)) = self.seen.get_mut(source) {
+            if ids.remove(&sequence_id) {
+                order.retain(|id| *id != sequence_id);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn delivery_settings_from_annotations() {
+        let settings = DeliverySettings::from_annotations(&std::collections::HashMap::new());
+        assert!(!settings.is_reliable("out"));
+
+        let annotations = std::collections::HashMap::from([
+            ("delivery".to_string(), "at-least-once".to_string()),
+            ("delivery.metrics".to_string(), "at-most-once".to_string()),
+            ("delivery.bad".to_string(), "exactly-once".to_string()),
+            ("delivery_retries".to_string(), "3".to_string()),
+            ("delivery_backoff_ms".to_string(), "10".to_string()),
+            ("dead_letter_output".to_string(), "failed".to_string()),
+        ]);
+        let settings = DeliverySettings::from_annotations(&annotations);
+        assert!(settings.is_reliable("out"));
+        assert!(settings.is_reliable("bad"));
+        assert!(!settings.is_reliable("metrics"));
+        assert!(!settings.is_reliable("failed"));
+        assert_eq!(settings.retries, 3);
+        assert_eq!(settings.backoff(1), std::time::Duration::from_millis(10));
+        assert_eq!(settings.backoff(3), std::time::Duration::from_millis(40));
+        assert_eq!(settings.backoff(100), MAX_BACKOFF);
+    }
+
+    #[test]
+    fn deduplicator() {
+        let source_1 = edgeless_api::function_instance::InstanceId::new(uuid::Uuid::new_v4());
+        let source_2 = edgeless_api::function_instance::InstanceId::new(uuid::Uuid::new_v4());
+        let mut deduplicator = Deduplicator::default();
+
+        assert!(deduplicator.first_seen(&source_1, 1));
+        assert!(!deduplicator.first_seen(&source_1, 1));
+        assert!(deduplicator.first_seen(&source_2, 1));
+
+        deduplicator.forget(&source_1, 1);
+        assert!(deduplicator.first_seen(&source_1, 1));
+
+        for sequence_id in 2..=(DEDUPLICATION_WINDOW as u64 + 1) {
+            assert!(deduplicator.first_seen(&source_1, sequence_id));
+        }
+        // The oldest sequence id has left the window.
+        assert!(deduplicator.first_seen(&source_1, 1));
+        assert!(!deduplicator.first_seen(&source_1, DEDUPLICATION_WINDOW as u64));
+    }
+}
@@ -1,18 +1,16 @@
 // SPDX-FileCopyrightText: © 2023 Technical University of Munich, Chair of Connected Mobility
 // SPDX-FileCopyrightText: © 2023 Claudio Cicconetti <c.cicconetti@iit.cnr.it>
 // SPDX-License-Identifier: MIT
-use futures::{SinkExt, StreamExt};
 use opentelemetry::trace::TraceContextExt;
 use opentelemetry::trace::Tracer;
 
 use crate::core::*;
 use crate::node_local::*;
 use crate::remote_node::*;
-use rand::seq::SliceRandom;
 
 #[derive(Clone)]
 struct IncommingLink {
-    sender: futures::channel::mpsc::UnboundedSender<DataplaneEvent>,
+    sender: crate::queue::InputQueueSender,
     target_id: edgeless_api::function_instance::InstanceId,
     target_port: edgeless_api::function_instance::PortId,
 }
@@ -20,19 +18,22 @@ struct IncommingLink {
 #[async_trait::async_trait]
 impl edgeless_api::link::LinkWriter for IncommingLink {
     async fn handle(&mut self, msg: Vec<u8>) {
-        self.sender
-            .send(DataplaneEvent {
+        if let Err(err) = self
+            .sender
+            .push(DataplaneEvent {
                 source_id: edgeless_api::function_instance::InstanceId {
                     node_id: edgeless_api::function_instance::NODE_ID_NONE,
                     function_id: edgeless_api::function_instance::FUNCTION_ID_NONE,
                 },
                 target_port: self.target_port.clone(),
                 channel_id: 0,
-                message: crate::core::Message::Cast(String::from_utf8(msg).unwrap()),
+                message: crate::core::Message::Cast(msg),
                 context: opentelemetry::trace::SpanContext::empty_context(),
             })
             .await
-            .unwrap();
+        {
+            log::warn!("Could not deliver link message to {:?}: {}", self.target_id, err);
+        }
     }
 }
 
@@ -43,13 +44,16 @@ pub struct DataplaneHandle {
     alias_mapping: crate::alias_mapping::AliasMapping,
     slf: edgeless_api::function_instance::InstanceId,
     incomming_links: std::sync::Arc<tokio::sync::Mutex<std::collections::HashMap<edgeless_api::link::LinkInstanceId, Box<IncommingLink>>>>,
-    sender: futures::channel::mpsc::UnboundedSender<DataplaneEvent>,
-    receiver: std::sync::Arc<tokio::sync::Mutex<futures::channel::mpsc::UnboundedReceiver<DataplaneEvent>>>,
+    sender: crate::queue::InputQueueSender,
+    receiver: crate::queue::InputQueueReceiver,
     link_manager: Box<dyn edgeless_api::link::LinkManager>,
     links: std::collections::HashMap<edgeless_api::link::LinkInstanceId, std::sync::Arc<tokio::sync::Mutex<Box<dyn edgeless_api::link::LinkWriter>>>>,
     output_chain: std::sync::Arc<tokio::sync::Mutex<Vec<Box<dyn DataPlaneLink>>>>,
-    receiver_overwrites: std::sync::Arc<tokio::sync::Mutex<TemporaryReceivers>>,
-    next_id: u64,
+    call_timeouts: CallTimeouts,
+    call_quorums: CallQuorums,
+    delivery: crate::delivery::DeliverySettings,
+    next_sequence_id: std::sync::Arc<std::sync::atomic::AtomicU64>,
+    load_balancer: crate::load_balancing::LoadBalancer,
     tracer: Option<opentelemetry_sdk::trace::Tracer>,
 }
 
@@ -58,53 +62,34 @@ impl DataplaneHandle {
         receiver_id: edgeless_api::function_instance::InstanceId,
         link_manager: Box<dyn edgeless_api::link::LinkManager>,
         output_chain: Vec<Box<dyn DataPlaneLink>>,
-        receiver: futures::channel::mpsc::UnboundedReceiver<DataplaneEvent>,
+        sender: crate::queue::InputQueueSender,
+        receiver: crate::queue::InputQueueReceiver,
+        rejected: tokio::sync::mpsc::UnboundedReceiver<DataplaneEvent>,
+        call_timeouts: CallTimeouts,
     ) -> Self {
-        let (main_sender, main_receiver) = futures::channel::mpsc::unbounded::<DataplaneEvent>();
-        let receiver_overwrites = std::sync::Arc::new(tokio::sync::Mutex::new(TemporaryReceivers {
-            temporary_receivers: std::collections::HashMap::new(),
-        }));
-
-        let clone_overwrites = receiver_overwrites.clone();
-        // This task intercepts the messages received and routes responses towards temporary receivers while routing other events towards the main receiver used in `receive_next`.
-
-        let mut cloned_sender = main_sender.clone();
+        let output_chain = std::sync::Arc::new(tokio::sync::Mutex::new(output_chain));
 
+        // This task answers with an error the calls that have been discarded because the input queue was full,
+        // so that the callers do not wait for a reply that will never come.
+        let cloned_chain = output_chain.clone();
         tokio::spawn(async move {
-            let mut receiver = receiver;
-            loop {
-                if let Some(DataplaneEvent {
-                    source_id,
-                    channel_id,
-                    message,
-                    target_port,
-                    context
-                }) = receiver.next().await
-                {
-                    if let Some(sender) = clone_overwrites.lock().await.temporary_receivers.remove(&channel_id) {
-                        match sender.send((source_id, message.clone())) {
-                            Ok(_) => {
-                                continue;
-                            }
-                            Err(_) => {
-                                log::error!("Tried to use expired overwrite send handle.");
-                            }
-                        }
-                    }
-                    match cloned_sender
-                        .send(DataplaneEvent {
-                            source_id,
-                            channel_id,
-                            message,
-                            target_port,
-                            context
-                        })
+            let mut rejected = rejected;
+            while let Some(event) = rejected.recv().await {
+                let mut lck = cloned_chain.lock().await;
+                for link in &mut lck.iter_mut() {
+                    if link
+                        .handle_send(
+                            &event.source_id,
+                            Message::Err,
+                            &receiver_id,
+                            event.channel_id,
+                            edgeless_api::function_instance::PortId("reply".to_string()),
+                            opentelemetry::trace::SpanContext::empty_context(),
+                        )
                         .await
+                        == LinkProcessingResult::FINAL
                     {
-                        Ok(_) => {}
-                        Err(_) => {
-                            break;
-                        }
+                        break;
                     }
                 }
             }
@@ -113,14 +98,17 @@ impl DataplaneHandle {
         DataplaneHandle {
             alias_mapping: crate::alias_mapping::AliasMapping::new(),
             slf: receiver_id,
-            sender: main_sender.clone(),
+            sender,
             incomming_links: std::sync::Arc::new(tokio::sync::Mutex::new(std::collections::HashMap::new())),
-            receiver: std::sync::Arc::new(tokio::sync::Mutex::new(main_receiver)),
-            output_chain: std::sync::Arc::new(tokio::sync::Mutex::new(output_chain)),
+            receiver,
+            output_chain,
             link_manager,
             links: std::collections::HashMap::new(),
-            receiver_overwrites,
-            next_id: 1,
+            call_timeouts,
+            call_quorums: CallQuorums::default(),
+            delivery: crate::delivery::DeliverySettings::default(),
+            next_sequence_id: std::sync::Arc::new(std::sync::atomic::AtomicU64::new(1)),
+            load_balancer: crate::load_balancing::LoadBalancer::default(),
             tracer: None
         }
     }
@@ -128,29 +116,12 @@ impl DataplaneHandle {
     /// Main receive function for receiving the next cast or call event.
     /// This is NOT used for processing replies to return values.
     pub async fn receive_next(&mut self) -> DataplaneEvent {
-        loop {
-            if let Some(DataplaneEvent {
-                source_id,
-                channel_id,
-                message,
-                target_port: target_channel,
-                context
-            }) = self.receiver.lock().await.next().await
-            {
-                if std::mem::discriminant(&message) == std::mem::discriminant(&Message::Cast("".to_string()))
-                    || std::mem::discriminant(&message) == std::mem::discriminant(&Message::Call("".to_string()))
-                {
-                    return DataplaneEvent {
-                        source_id,
-                        channel_id,
-                        message,
-                        target_port: target_channel,
-                        context
-                    };
-                }
-                log::error!("Unprocesses other message {:?}", message);
-            }
-        }
+        self.receiver.pop().await
+    }
+
+    /// Current state of the input queue of this element.
+    pub fn queue_statistics(&self) -> crate::queue::QueueStatistics {
+        self.receiver.statistics()
     }
 
     pub async fn update_mapping(
@@ -198,7 +169,29 @@ impl DataplaneHandle {
         self.tracer = Some(tracer);
     }
 
-    pub async fn send_alias(&mut self, target: String, msg: String, context: opentelemetry::Context) -> anyhow::Result<()> {
+    pub async fn send_alias(&mut self, target: String, msg: impl Into<Vec<u8>>, context: opentelemetry::Context) -> anyhow::Result<()> {
+        self.send_alias_inner(target, msg.into(), None, context).await
+    }
+
+    /// Send a `cast` event on an output, using the given key to pick the target
+    /// if the output is mapped to multiple instances with consistent hashing.
+    pub async fn send_alias_with_key(
+        &mut self,
+        target: String,
+        partition_key: &[u8],
+        msg: impl Into<Vec<u8>>,
+        context: opentelemetry::Context,
+    ) -> anyhow::Result<()> {
+        self.send_alias_inner(target, msg.into(), Some(partition_key), context).await
+    }
+
+    async fn send_alias_inner(
+        &mut self,
+        target: String,
+        msg: Vec<u8>,
+        partition_key: Option<&[u8]>,
+        context: opentelemetry::Context,
+    ) -> anyhow::Result<()> {
         let context = if let Some(tracer) = &self.tracer {
             let call_handler_span = tracer.start_with_context(format!("send_{}", target), &context);
             opentelemetry::Context::current_with_span(call_handler_span)
@@ -207,29 +200,29 @@ impl DataplaneHandle {
             context
         };
         if target == "self" {
-            self.send_inner(self.slf, Message::Cast(msg.to_string()), edgeless_api::function_instance::PortId("INTERNAL".to_string()), 0, context)
+            self.send_inner(self.slf, Message::Cast(msg.clone()), edgeless_api::function_instance::PortId("INTERNAL".to_string()), 0, context)
                 .await;
             Ok(())
-        } else if let Some(target) = self.alias_mapping.get_mapping(&target).await {
-            match target {
+        } else if let Some(mapping) = self.alias_mapping.get_mapping(&target).await {
+            match mapping {
                 edgeless_api::common::Output::Single(instance_id, port_id) => {
-                    self.send_inner(instance_id,Message::Cast(msg.to_string()),  port_id.clone(), 0, context.clone()).await;
+                    self.send_cast(&target, instance_id, port_id, msg, context).await;
                 }
                 edgeless_api::common::Output::Any(ids) => {
-                    let id = ids.choose(&mut rand::thread_rng());
+                    let id = self.load_balancer.select(&target, &ids, &self.slf.node_id, &msg, partition_key).cloned();
                     if let Some((instance_id, port_id)) = id {
-                        self.send_inner(*instance_id, Message::Cast(msg.to_string()), port_id.clone(),0, context.clone()).await;
+                        self.send_cast(&target, instance_id, port_id, msg, context).await;
                     } else {
                         return Err(anyhow::anyhow!("Unknown Alias"));
                     }
                 }
                 edgeless_api::common::Output::All(ids) => {
                     for (instance_id, port_id) in ids {
-                        self.send_inner(instance_id, Message::Cast(msg.to_string()), port_id.clone(), 0, context.clone()).await;
+                        self.send_cast(&target, instance_id, port_id, msg.clone(), context.clone()).await;
                     }
                 }
                 edgeless_api::common::Output::Link(link_id) => {
-                    self.send_to_link(&link_id, msg.to_string().into_bytes()).await;
+                    self.send_to_link(&link_id, msg).await;
                 }
             }
             Ok(())
@@ -238,15 +231,88 @@ impl DataplaneHandle {
         }
     }
 
-    pub async fn call_alias(&mut self, alias: String, msg: String, context: opentelemetry::Context) -> CallRet {
+    /// Send a `cast` event on an output to one of its targets. If the output requires at-least-once
+    /// delivery, the event is sent again in the background until the target node acknowledges it.
+    async fn send_cast(
+        &mut self,
+        output: &str,
+        target: edgeless_api::function_instance::InstanceId,
+        target_port: edgeless_api::function_instance::PortId,
+        msg: Vec<u8>,
+        context: opentelemetry::Context,
+    ) {
+        if !self.delivery.is_reliable(output) {
+            self.send_inner(target, Message::Cast(msg), target_port, 0, context).await;
+            return;
+        }
+
+        // The sequence id lets the target node discard the copies it has already received.
+        let sequence_id = self.next_sequence_id.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        if self
+            .send_inner(target, Message::Cast(msg.clone()), target_port.clone(), sequence_id, context.clone())
+            .await
+            == LinkProcessingResult::FINAL
+        {
+            return;
+        }
+
+        let retry = CastRetry {
+            slf: self.slf,
+            output_chain: self.output_chain.clone(),
+            alias_mapping: self.alias_mapping.clone(),
+            load_balancer: self.load_balancer.clone(),
+            links: self.links.clone(),
+            delivery: self.delivery.clone(),
+        };
+        let output = output.to_string();
+        tokio::spawn(async move {
+            for attempt in 1..=retry.delivery.retries {
+                tokio::time::sleep(retry.delivery.backoff(attempt)).await;
+                if retry
+                    .send(target, Message::Cast(msg.clone()), target_port.clone(), sequence_id, context.clone())
+                    .await
+                    == LinkProcessingResult::FINAL
+                {
+                    return;
+                }
+            }
+            log::warn!(
+                "Cast from {:?} to {:?} on {} not delivered after {} retries",
+                retry.slf,
+                target,
+                output,
+                retry.delivery.retries
+            );
+            retry.send_dead_letter(msg, context).await;
+        });
+    }
+
+    pub async fn call_alias(&mut self, alias: String, msg: impl Into<Vec<u8>>, context: opentelemetry::Context) -> CallRet {
+        self.call_alias_inner(alias, msg.into(), None, context).await
+    }
+
+    /// Send a `call` event on an output, using the given key to pick the target
+    /// if the output is mapped to multiple instances with consistent hashing.
+    pub async fn call_alias_with_key(
+        &mut self,
+        alias: String,
+        partition_key: &[u8],
+        msg: impl Into<Vec<u8>>,
+        context: opentelemetry::Context,
+    ) -> CallRet {
+        self.call_alias_inner(alias, msg.into(), Some(partition_key), context).await
+    }
+
+    async fn call_alias_inner(&mut self, alias: String, msg: Vec<u8>, partition_key: Option<&[u8]>, context: opentelemetry::Context) -> CallRet {
         let context = if let Some(tracer) = &self.tracer {
             let call_handler_span = tracer.start_with_context(format!("call_{}", alias), &context);
             opentelemetry::Context::current_with_span(call_handler_span)
         } else {
             context
         };
+        let timeout = self.call_timeouts.for_output(&alias);
         if alias == "self" {
-            self.call_raw(self.slf, edgeless_api::function_instance::PortId("INTERNAL".to_string()), msg, context)
+            self.call_inner(self.slf, edgeless_api::function_instance::PortId("INTERNAL".to_string()), msg, context, timeout)
                 .await
             // return Ok(self.data_plane.call(self.instance_id.clone(), msg.to_string()).await);
         } else if let Some(target) = self.alias_mapping.get_mapping(&alias).await {
@@ -254,21 +320,20 @@ impl DataplaneHandle {
             match target {
                 edgeless_api::common::Output::Single(instance_id, port_id) => {
                     // self.data_plane.send(id, msg.to_string()).await;
-                    return self.call_raw(instance_id, port_id, msg, context).await;
+                    return self.call_inner(instance_id, port_id, msg, context, timeout).await;
                 }
                 edgeless_api::common::Output::Any(ids) => {
-                    let id = ids.choose(&mut rand::thread_rng());
+                    let id = self.load_balancer.select(&alias, &ids, &self.slf.node_id, &msg, partition_key).cloned();
                     if let Some((instance_id, port_id)) = id {
                         // self.data_plane.send(id.clone(), msg.to_string()).await;
-                        return self.call_raw(*instance_id, port_id.clone(), msg, context).await;
+                        return self.call_inner(instance_id, port_id, msg, context, timeout).await;
                     } else {
                         // return Err(GuestAPIError::UnknownAlias);
                         return CallRet::Err;
                     }
                 }
                 edgeless_api::common::Output::All(_ids) => {
-                    // TODO(raphaelhetzel) introduce new error for this
-                    // return Err(GuestAPIError::UnknownAlias);
+                    log::warn!("Call on output {} mapped to all of its targets, use call_all_alias instead.", alias);
                     return CallRet::Err;
                 }
                 edgeless_api::common::Output::Link(link_id) => {
@@ -282,6 +347,75 @@ impl DataplaneHandle {
         }
     }
 
+    /// Send a `call` event to all the targets of an output and gather their replies
+    /// until the quorum of the output is reached or its deadline elapses.
+    pub async fn call_all_alias(&mut self, alias: String, msg: impl Into<Vec<u8>>, context: opentelemetry::Context) -> anyhow::Result<GatherRet> {
+        let msg: Vec<u8> = msg.into();
+        let context = if let Some(tracer) = &self.tracer {
+            let call_handler_span = tracer.start_with_context(format!("call_all_{}", alias), &context);
+            opentelemetry::Context::current_with_span(call_handler_span)
+        } else {
+            context
+        };
+        let targets = match self.alias_mapping.get_mapping(&alias).await {
+            Some(edgeless_api::common::Output::Single(instance_id, port_id)) => vec![(instance_id, port_id)],
+            Some(edgeless_api::common::Output::All(ids)) => ids,
+            Some(_) => return Err(anyhow::anyhow!("Output {} is not mapped to all of its targets", alias)),
+            None => return Err(anyhow::anyhow!("Unknown Alias")),
+        };
+        let quorum = self.call_quorums.for_output(&alias).answers(targets.len());
+        let deadline = self.call_timeouts.for_output(&alias).map(|timeout| tokio::time::Instant::now() + timeout);
+
+        // All the calls are sent before waiting for any reply, so that the targets process them in parallel.
+        let mut waiting = Vec::new();
+        let mut pending = futures::stream::FuturesUnordered::new();
+        for (target, port) in targets {
+            let outstanding = self.load_balancer.track_call(target);
+            let reply = self.receiver.expect_reply();
+            let channel_id = reply.channel_id();
+            self.send_inner(target, Message::Call(msg.clone()), port, channel_id, context.clone()).await;
+            waiting.push(target);
+            pending.push(async move {
+                let res = reply.await;
+                drop(outstanding);
+                (target, Self::call_ret(res))
+            });
+        }
+
+        let mut replies = Vec::new();
+        let mut answers = 0;
+        while answers < quorum {
+            let next = match deadline {
+                Some(deadline) => match tokio::time::timeout_at(deadline, futures::StreamExt::next(&mut pending)).await {
+                    Ok(next) => next,
+                    Err(_) => {
+                        log::warn!("Call from {:?} on {} timed out with {} of {} answers", self.slf, alias, answers, quorum);
+                        replies.extend(waiting.drain(..).map(|target| (target, CallRet::Timeout)));
+                        break;
+                    }
+                },
+                None => futures::StreamExt::next(&mut pending).await,
+            };
+            match next {
+                Some((target, ret)) => {
+                    if let Some(pos) = waiting.iter().position(|id| *id == target) {
+                        waiting.swap_remove(pos);
+                    }
+                    if matches!(ret, CallRet::Reply(_) | CallRet::NoReply) {
+                        answers += 1;
+                    }
+                    replies.push((target, ret));
+                }
+                None => break,
+            }
+        }
+
+        Ok(GatherRet {
+            replies,
+            quorum_reached: answers >= quorum,
+        })
+    }
+
     pub async fn send_to_link(&mut self, link_id: &edgeless_api::link::LinkInstanceId, msg: Vec<u8>) {
         if let Some(link) = self.links.get(link_id) {
             link.lock().await.handle(msg).await;
@@ -295,7 +429,7 @@ impl DataplaneHandle {
         &mut self,
         target: edgeless_api::function_instance::InstanceId,
         target_port: edgeless_api::function_instance::PortId,
-        msg: String,
+        msg: impl Into<Vec<u8>>,
         context: opentelemetry::Context
     ) {
         let context = if let Some(tracer) = &self.tracer {
@@ -304,16 +438,16 @@ impl DataplaneHandle {
         } else {
             context
         };
-        self.send_inner(target, Message::Cast(msg), target_port, 0,context).await;
+        self.send_inner(target, Message::Cast(msg.into()), target_port, 0,context).await;
     }
 
     // Send a `call` event and wait for the return event.
-    // Internally, this sets up a receiver override to handle the message before it would be sent to the `receive_next` function.
+    // Internally, this registers the call with the input queue, which hands the reply over instead of queueing it for the `receive_next` function.
     pub async fn call(
         &mut self,
         target: edgeless_api::function_instance::InstanceId,
         target_port: edgeless_api::function_instance::PortId,
-        msg: String,
+        msg: impl Into<Vec<u8>>,
         context: opentelemetry::Context
     ) -> CallRet {
         let context = if let Some(tracer) = &self.tracer {
@@ -330,16 +464,81 @@ impl DataplaneHandle {
         &mut self,
         target: edgeless_api::function_instance::InstanceId,
         target_port: edgeless_api::function_instance::PortId,
-        msg: String,
+        msg: impl Into<Vec<u8>>,
         context: opentelemetry::Context
     ) -> CallRet {
-        let (sender, receiver) = futures::channel::oneshot::channel::<(edgeless_api::function_instance::InstanceId, Message)>();
-        let channel_id = self.next_id;
-        self.next_id += 1;
-        // Potential Leak: This is only received if a message is received (or the handle is dropped)
-        self.receiver_overwrites.lock().await.temporary_receivers.insert(channel_id, sender);
-        self.send_inner(target, Message::Call(msg), target_port, channel_id, context.clone()).await;
-        match receiver.await {
+        let timeout = self.call_timeouts.default;
+        self.call_inner(target, target_port, msg.into(), context, timeout).await
+    }
+
+    pub fn call_timeouts(&self) -> &CallTimeouts {
+        &self.call_timeouts
+    }
+
+    /// Set the deadlines of the calls made through this handle.
+    pub fn set_call_timeouts(&mut self, call_timeouts: CallTimeouts) {
+        self.call_timeouts = call_timeouts;
+    }
+
+    pub fn call_quorums(&self) -> &CallQuorums {
+        &self.call_quorums
+    }
+
+    /// Set the quorums of the calls made through this handle on outputs mapped to all of multiple instances.
+    pub fn set_call_quorums(&mut self, call_quorums: CallQuorums) {
+        self.call_quorums = call_quorums;
+    }
+
+    pub fn delivery(&self) -> &crate::delivery::DeliverySettings {
+        &self.delivery
+    }
+
+    /// Set how the casts made through this handle are delivered.
+    pub fn set_delivery(&mut self, delivery: crate::delivery::DeliverySettings) {
+        self.delivery = delivery;
+    }
+
+    pub fn load_balancing(&self) -> &crate::load_balancing::LoadBalancingPolicies {
+        self.load_balancer.policies()
+    }
+
+    /// Set the policies used to pick the target of the outputs mapped to multiple instances.
+    pub fn set_load_balancing(&mut self, policies: crate::load_balancing::LoadBalancingPolicies) {
+        self.load_balancer.set_policies(policies);
+    }
+
+    async fn call_inner(
+        &mut self,
+        target: edgeless_api::function_instance::InstanceId,
+        target_port: edgeless_api::function_instance::PortId,
+        msg: Vec<u8>,
+        context: opentelemetry::Context,
+        timeout: Option<std::time::Duration>,
+    ) -> CallRet {
+        let slf = self.slf;
+        let _outstanding = self.load_balancer.track_call(target);
+        // The reply is purged from the input queue if it has not arrived when this returns.
+        let reply = self.receiver.expect_reply();
+        let channel_id = reply.channel_id();
+        let call = async {
+            self.send_inner(target, Message::Call(msg), target_port, channel_id, context).await;
+            reply.await
+        };
+        let res = match timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, call).await {
+                Ok(res) => res,
+                Err(_) => {
+                    log::warn!("Call from {:?} to {:?} timed out after {:?}", slf, target, timeout);
+                    return CallRet::Timeout;
+                }
+            },
+            None => call.await,
+        };
+        Self::call_ret(res)
+    }
+
+    fn call_ret(res: Result<(edgeless_api::function_instance::InstanceId, Message), futures::channel::oneshot::Canceled>) -> CallRet {
+        match res {
             Ok((_src, msg)) => match msg {
                 Message::CallRet(ret) => CallRet::Reply(ret),
                 Message::CallNoRet => CallRet::NoReply,
@@ -356,7 +555,8 @@ impl DataplaneHandle {
             match msg {
                 CallRet::Reply(msg) => Message::CallRet(msg),
                 CallRet::NoReply => Message::CallNoRet,
-                CallRet::Err => Message::Err,
+                // The caller sees a timeout further down the chain as an error.
+                CallRet::Err | CallRet::Timeout => Message::Err,
             },
             edgeless_api::function_instance::PortId("reply".to_string()),
             channel_id,
@@ -372,19 +572,90 @@ impl DataplaneHandle {
         target_port: edgeless_api::function_instance::PortId,
         channel_id: u64,
         context: opentelemetry::Context
-    ) {
-        let mut lck = self.output_chain.lock().await;
-        for link in &mut lck.iter_mut() {
-            if link.handle_send(&target, msg.clone(), &self.slf, channel_id, target_port.clone(), context.span().span_context().clone()).await == LinkProcessingResult::FINAL {
-                return;
-            }
+    ) -> LinkProcessingResult {
+        send_on_chain(&self.output_chain, &self.slf, target, msg, target_port, channel_id, context).await
+    }
+}
+
+/// Send an event through the first link of the chain that can deliver it.
+async fn send_on_chain(
+    output_chain: &tokio::sync::Mutex<Vec<Box<dyn DataPlaneLink>>>,
+    slf: &edgeless_api::function_instance::InstanceId,
+    target: edgeless_api::function_instance::InstanceId,
+    msg: Message,
+    target_port: edgeless_api::function_instance::PortId,
+    channel_id: u64,
+    context: opentelemetry::Context,
+) -> LinkProcessingResult {
+    let mut lck = output_chain.lock().await;
+    for link in &mut lck.iter_mut() {
+        match link
+            .handle_send(&target, msg.clone(), slf, channel_id, target_port.clone(), context.span().span_context().clone())
+            .await
+        {
+            LinkProcessingResult::FINAL => return LinkProcessingResult::FINAL,
+            LinkProcessingResult::ERROR => return LinkProcessingResult::ERROR,
+            _ => {}
         }
-        log::info!("Unprocessed Message: {:?} {:?}->{:?}", msg, self.slf, target);
     }
+    log::info!("Unprocessed Message: {:?} {:?}->{:?}", msg, slf, target);
+    LinkProcessingResult::PASSED
 }
 
-struct TemporaryReceivers {
-    temporary_receivers: std::collections::HashMap<u64, futures::channel::oneshot::Sender<(edgeless_api::function_instance::InstanceId, Message)>>,
+/// Sending side and routing state of a handle, used to retry a cast in the
+/// background and to send it to the dead-letter output if it is never
+/// delivered. Unlike a clone of the handle, it does not keep the input queue
+/// of the component alive.
+struct CastRetry {
+    slf: edgeless_api::function_instance::InstanceId,
+    output_chain: std::sync::Arc<tokio::sync::Mutex<Vec<Box<dyn DataPlaneLink>>>>,
+    alias_mapping: crate::alias_mapping::AliasMapping,
+    load_balancer: crate::load_balancing::LoadBalancer,
+    links: std::collections::HashMap<edgeless_api::link::LinkInstanceId, std::sync::Arc<tokio::sync::Mutex<Box<dyn edgeless_api::link::LinkWriter>>>>,
+    delivery: crate::delivery::DeliverySettings,
+}
+
+impl CastRetry {
+    async fn send(
+        &self,
+        target: edgeless_api::function_instance::InstanceId,
+        msg: Message,
+        target_port: edgeless_api::function_instance::PortId,
+        channel_id: u64,
+        context: opentelemetry::Context,
+    ) -> LinkProcessingResult {
+        send_on_chain(&self.output_chain, &self.slf, target, msg, target_port, channel_id, context).await
+    }
+
+    /// Send an undeliverable event to the dead-letter output, if any, without retrying.
+    async fn send_dead_letter(&self, msg: Vec<u8>, context: opentelemetry::Context) {
+        let dead_letter_output = match &self.delivery.dead_letter_output {
+            Some(dead_letter_output) => dead_letter_output.clone(),
+            None => return,
+        };
+        match self.alias_mapping.get_mapping(&dead_letter_output).await {
+            Some(edgeless_api::common::Output::Single(instance_id, port_id)) => {
+                self.send(instance_id, Message::Cast(msg), port_id, 0, context).await;
+            }
+            Some(edgeless_api::common::Output::Any(ids)) => {
+                if let Some((instance_id, port_id)) = self.load_balancer.select(&dead_letter_output, &ids, &self.slf.node_id, &msg, None).cloned() {
+                    self.send(instance_id, Message::Cast(msg), port_id, 0, context).await;
+                }
+            }
+            Some(edgeless_api::common::Output::All(ids)) => {
+                for (instance_id, port_id) in ids {
+                    self.send(instance_id, Message::Cast(msg.clone()), port_id, 0, context.clone()).await;
+                }
+            }
+            Some(edgeless_api::common::Output::Link(link_id)) => match self.links.get(&link_id) {
+                Some(link) => link.lock().await.handle(msg).await,
+                None => log::info!("Link not found"),
+            },
+            None => {
+                log::warn!("Dead-letter output {} is not mapped, event dropped", dead_letter_output);
+            }
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -392,6 +663,10 @@ pub struct DataplaneProvider {
     local_provider: std::sync::Arc<tokio::sync::Mutex<NodeLocalLinkProvider>>,
     remote_provider: std::sync::Arc<tokio::sync::Mutex<RemoteLinkProvider>>,
     link_manager: std::sync::Arc<tokio::sync::Mutex<LinkManager>>,
+    settings: EdgelessDataplaneSettings,
+    /// Endpoint of the QUIC clients, if this node has a QUIC invocation server.
+    #[cfg(feature = "quic")]
+    quic_endpoint: Option<edgeless_api::quic_impl::invocation::QuicClientEndpoint>,
 }
 
 struct LinkManager {
@@ -414,6 +689,16 @@ impl LinkManager {
 
 impl DataplaneProvider {
     pub async fn new(node_id: uuid::Uuid, invocation_url: String, invocation_url_coap: Option<String>) -> Self {
+        Self::with_settings(node_id, invocation_url, invocation_url_coap, None, EdgelessDataplaneSettings::default()).await
+    }
+
+    pub async fn with_settings(
+        node_id: uuid::Uuid,
+        invocation_url: String,
+        invocation_url_coap: Option<String>,
+        invocation_url_quic: Option<String>,
+        settings: EdgelessDataplaneSettings,
+    ) -> Self {
         let remote_provider = std::sync::Arc::new(tokio::sync::Mutex::new(RemoteLinkProvider::new(node_id).await));
 
         let (_, _, port) = edgeless_api::util::parse_http_host(&invocation_url.clone()).unwrap();
@@ -434,26 +719,83 @@ impl DataplaneProvider {
             ));
         }
 
+        #[cfg(feature = "quic")]
+        let mut quic_endpoint = None;
+        #[cfg(feature = "quic")]
+        if let Some(invocation_url_quic) = invocation_url_quic.filter(|url| !url.is_empty()) {
+            let _quic_server = tokio::spawn(edgeless_api::quic_impl::invocation::QuicInvocationAPIServer::run(
+                clone_provider.lock().await.incomming_api().await,
+                invocation_url_quic,
+            ));
+            match edgeless_api::quic_impl::invocation::QuicClientEndpoint::new() {
+                Ok(endpoint) => quic_endpoint = Some(endpoint),
+                Err(err) => log::error!("Could not create the QUIC client endpoint: {}", err),
+            }
+        }
+        #[cfg(not(feature = "quic"))]
+        if invocation_url_quic.is_some_and(|url| !url.is_empty()) {
+            log::warn!("QUIC invocation URL ignored, since the quic feature is disabled");
+        }
+
         let mut lm = LinkManager::new();
+        if !settings.disable_multicast_links {
+            lm.link_providers.insert(
+                edgeless_api::link::LinkProviderId(uuid::Uuid::new_v4()),
+                Box::new(edgeless_link_multicast::link::MulticastProvider::new()),
+            );
+        }
+        lm.link_providers.insert(
+            edgeless_api::link::LinkProviderId(uuid::Uuid::new_v4()),
+            Box::new(edgeless_link_tcp::link::TcpProvider::new()),
+        );
         lm.link_providers.insert(
             edgeless_api::link::LinkProviderId(uuid::Uuid::new_v4()),
-            Box::new(edgeless_link_multicast::link::MulticastProvider::new()),
+            Box::new(edgeless_link_memory::link::MemoryProvider::new()),
         );
 
         Self {
             local_provider: std::sync::Arc::new(tokio::sync::Mutex::new(NodeLocalLinkProvider::new())),
             remote_provider,
             link_manager: std::sync::Arc::new(tokio::sync::Mutex::new(lm)),
+            settings,
+            #[cfg(feature = "quic")]
+            quic_endpoint,
         }
     }
 
     pub async fn get_handle_for(&mut self, target: edgeless_api::function_instance::InstanceId) -> DataplaneHandle {
-        let (sender, receiver) = futures::channel::mpsc::unbounded::<DataplaneEvent>();
+        let (sender, receiver, rejected) = crate::queue::input_queue(&self.settings);
         let output_chain = vec![
             self.local_provider.lock().await.new_link(target, sender.clone()).await,
             self.remote_provider.lock().await.new_link(target, sender.clone()).await,
         ];
-        DataplaneHandle::new(target, edgeless_api::link::LinkManagerClone::clone_box(self), output_chain, receiver).await
+        DataplaneHandle::new(
+            target,
+            edgeless_api::link::LinkManagerClone::clone_box(self),
+            output_chain,
+            sender,
+            receiver,
+            rejected,
+            CallTimeouts {
+                default: self.default_call_timeout(),
+                ..Default::default()
+            },
+        )
+        .await
+    }
+
+    /// Current state of the input queues of the components on this node,
+    /// by function identifier.
+    pub async fn queue_statistics(&self) -> std::collections::HashMap<uuid::Uuid, crate::queue::QueueStatistics> {
+        self.local_provider.lock().await.queue_statistics().await
+    }
+
+    /// Deadline of the calls of the components that do not set their own.
+    pub fn default_call_timeout(&self) -> Option<std::time::Duration> {
+        match self.settings.call_timeout_ms {
+            0 => None,
+            ms => Some(std::time::Duration::from_millis(ms)),
+        }
     }
 
     pub async fn add_peer(&mut self, peer: EdgelessDataplanePeerSettings) {
@@ -461,7 +803,7 @@ impl DataplaneProvider {
         self.remote_provider
             .lock()
             .await
-            .add_peer(peer.node_id, Self::connect_peer(&peer).await)
+            .add_peer(peer.node_id, self.connect_peer(&peer).await)
             .await;
     }
 
@@ -470,7 +812,16 @@ impl DataplaneProvider {
         self.remote_provider.lock().await.del_peer(node_id).await;
     }
 
-    async fn connect_peer(target: &EdgelessDataplanePeerSettings) -> Box<dyn edgeless_api::invocation::InvocationAPI> {
+    async fn connect_peer(&self, target: &EdgelessDataplanePeerSettings) -> Box<dyn edgeless_api::invocation::InvocationAPI> {
+        // QUIC is only used if both this node and the peer support it.
+        #[cfg(feature = "quic")]
+        if let (Some(endpoint), Some(invocation_url_quic)) = (&self.quic_endpoint, &target.invocation_url_quic) {
+            match edgeless_api::quic_impl::invocation::QuicInvocationAPIClient::new(endpoint.clone(), invocation_url_quic).await {
+                Ok(client) => return Box::new(client),
+                Err(err) => log::warn!("Could not reach peer {} with QUIC, falling back to gRPC: {}", target.node_id, err),
+            }
+        }
+
         let (proto, url, port) = edgeless_api::util::parse_http_host(&target.invocation_url).unwrap();
         match proto {
             edgeless_api::util::Proto::COAP => {
@@ -527,7 +878,18 @@ impl edgeless_api::link::LinkInstanceAPI for DataplaneProvider {
         let mut lm = self.link_manager.lock().await;
 
         lm.links.remove(&id);
-        Ok(())
+        // Only the provider that created the link knows it, but all of them
+        // are tried even if one fails, so that the link is not left behind.
+        let mut errors = Vec::new();
+        for (provider_id, provider) in lm.link_providers.iter_mut() {
+            if let Err(err) = provider.remove(id.clone()).await {
+                errors.push(format!("provider {:?}: {}", provider_id, err));
+            }
+        }
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(anyhow::anyhow!("Could not remove link {:?}: {}", id, errors.join(", "))),
+        }
     }
 }
 
@@ -547,13 +909,18 @@ mod test {
         let mut handle_2 = provider.get_handle_for(fid_2).await;
 
         handle_1
-            .send(fid_2, edgeless_api::function_instance::PortId("test".to_string()), "Test".to_string())
+            .send(
+                fid_2,
+                edgeless_api::function_instance::PortId("test".to_string()),
+                "Test".to_string(),
+                opentelemetry::Context::new(),
+            )
             .await;
 
         let res = handle_2.receive_next().await;
         assert_eq!(
             std::mem::discriminant(&res.message),
-            std::mem::discriminant(&crate::core::Message::Cast("".to_string()))
+            std::mem::discriminant(&crate::core::Message::Cast(vec![]))
         );
     }
 
@@ -570,14 +937,19 @@ mod test {
 
         let return_handle = tokio::spawn(async move {
             handle_1
-                .call(fid_2, edgeless_api::function_instance::PortId("test".to_string()), "Test".to_string())
+                .call(
+                    fid_2,
+                    edgeless_api::function_instance::PortId("test".to_string()),
+                    "Test".to_string(),
+                    opentelemetry::Context::new(),
+                )
                 .await
         });
 
         let req = handle_2.receive_next().await;
         assert_eq!(
             std::mem::discriminant(&req.message),
-            std::mem::discriminant(&crate::core::Message::Call("".to_string()))
+            std::mem::discriminant(&crate::core::Message::Call(vec![]))
         );
 
         handle_2.reply(req.source_id, req.channel_id, CallRet::NoReply).await;
@@ -599,6 +971,7 @@ mod test {
                 .add_peer(EdgelessDataplanePeerSettings {
                     node_id: node_id_2,
                     invocation_url: "http://127.0.0.1:7098".to_string(),
+                    invocation_url_quic: None,
                 })
                 .await;
             dataplane
@@ -610,6 +983,7 @@ mod test {
                 .add_peer(EdgelessDataplanePeerSettings {
                     node_id,
                     invocation_url: "http://127.0.0.1:7099".to_string(),
+                    invocation_url_quic: None,
                 })
                 .await;
             dataplane
@@ -628,12 +1002,17 @@ mod test {
         let mut handle_2 = provider_2.get_handle_for(fid_2).await;
 
         handle_1
-            .send(fid_2, edgeless_api::function_instance::PortId("test".to_string()), "Test".to_string())
+            .send(
+                fid_2,
+                edgeless_api::function_instance::PortId("test".to_string()),
+                "Test".to_string(),
+                opentelemetry::Context::new(),
+            )
             .await;
         let cast_req = handle_2.receive_next().await;
         assert_eq!(
             std::mem::discriminant(&cast_req.message),
-            std::mem::discriminant(&crate::core::Message::Cast("".to_string()))
+            std::mem::discriminant(&crate::core::Message::Cast(vec![]))
         );
 
         let cloned_id_1 = fid_1;
@@ -645,6 +1024,7 @@ mod test {
                     cloned_id_1,
                     edgeless_api::function_instance::PortId("test".to_string()),
                     "Test".to_string(),
+                    opentelemetry::Context::new(),
                 )
                 .await
         });
@@ -652,11 +1032,261 @@ mod test {
         let call_req = handle_1.receive_next().await;
         assert_eq!(
             std::mem::discriminant(&call_req.message),
-            std::mem::discriminant(&crate::core::Message::Call("".to_string()))
+            std::mem::discriminant(&crate::core::Message::Call(vec![]))
         );
         handle_1.reply(call_req.source_id, call_req.channel_id, CallRet::NoReply).await;
 
         let repl = return_handle.await.unwrap();
         assert_eq!(std::mem::discriminant(&CallRet::NoReply), std::mem::discriminant(&repl));
     }
+
+    #[tokio::test]
+    async fn local_call_timeout() {
+        let node_id = uuid::Uuid::new_v4();
+        let fid_1 = edgeless_api::function_instance::InstanceId::new(node_id);
+        let fid_2 = edgeless_api::function_instance::InstanceId::new(node_id);
+
+        let mut provider = DataplaneProvider::new(node_id, "http://127.0.0.1:7095".to_string(), None).await;
+
+        let mut handle_1 = provider.get_handle_for(fid_1).await;
+        let mut handle_2 = provider.get_handle_for(fid_2).await;
+        handle_1.set_call_timeouts(CallTimeouts {
+            default: Some(std::time::Duration::from_millis(50)),
+            by_output: std::collections::HashMap::from([("out".to_string(), Some(std::time::Duration::from_millis(100)))]),
+        });
+        handle_1
+            .update_mapping(
+                std::collections::HashMap::new(),
+                std::collections::HashMap::from([(
+                    edgeless_api::function_instance::PortId("out".to_string()),
+                    edgeless_api::common::Output::Single(fid_2, edgeless_api::function_instance::PortId("test".to_string())),
+                )]),
+            )
+            .await;
+
+        // The callee never replies.
+        let start = tokio::time::Instant::now();
+        let ret = handle_1
+            .call(
+                fid_2,
+                edgeless_api::function_instance::PortId("test".to_string()),
+                "Test".to_string(),
+                opentelemetry::Context::new(),
+            )
+            .await;
+        assert_eq!(ret, CallRet::Timeout);
+        assert!(start.elapsed() >= std::time::Duration::from_millis(50));
+        assert_eq!(handle_1.receiver.pending_replies(), 0);
+        let call = handle_2.receive_next().await;
+
+        let start = tokio::time::Instant::now();
+        let ret = handle_1.call_alias("out".to_string(), "Test", opentelemetry::Context::new()).await;
+        assert_eq!(ret, CallRet::Timeout);
+        assert!(start.elapsed() >= std::time::Duration::from_millis(100));
+        assert_eq!(handle_1.receiver.pending_replies(), 0);
+        handle_2.receive_next().await;
+
+        // A late reply is discarded instead of being queued.
+        handle_2
+            .reply(call.source_id, call.channel_id, CallRet::Reply(b"Late".to_vec()))
+            .await;
+        assert_eq!(handle_1.queue_statistics().depth, 0);
+        assert_eq!(handle_1.receiver.pending_replies(), 0);
+    }
+
+    /// Answer the calls received by a handle after the given delay, or never.
+    fn spawn_callee(mut handle: DataplaneHandle, delay: Option<std::time::Duration>) {
+        tokio::spawn(async move {
+            loop {
+                let call = handle.receive_next().await;
+                if let Some(delay) = delay {
+                    let mut handle = handle.clone();
+                    tokio::spawn(async move {
+                        tokio::time::sleep(delay).await;
+                        handle.reply(call.source_id, call.channel_id, CallRet::Reply(b"Ret".to_vec())).await;
+                    });
+                }
+            }
+        });
+    }
+
+    #[tokio::test]
+    async fn local_call_all() {
+        let node_id = uuid::Uuid::new_v4();
+        let fid_caller = edgeless_api::function_instance::InstanceId::new(node_id);
+
+        let mut provider = DataplaneProvider::new(node_id, "http://127.0.0.1:7094".to_string(), None).await;
+        let mut caller = provider.get_handle_for(fid_caller).await;
+
+        // Two targets reply at once, one after 300 ms, one never.
+        let mut targets = vec![];
+        for delay in [Some(0), Some(0), Some(300), None] {
+            let fid = edgeless_api::function_instance::InstanceId::new(node_id);
+            spawn_callee(provider.get_handle_for(fid).await, delay.map(std::time::Duration::from_millis));
+            targets.push((fid, edgeless_api::function_instance::PortId("test".to_string())));
+        }
+        let late = targets[2].0;
+        caller
+            .update_mapping(
+                std::collections::HashMap::new(),
+                std::collections::HashMap::from([(
+                    edgeless_api::function_instance::PortId("out".to_string()),
+                    edgeless_api::common::Output::All(targets),
+                )]),
+            )
+            .await;
+
+        let annotations = |quorum: &str, timeout_ms: &str| {
+            std::collections::HashMap::from([
+                ("call_quorum.out".to_string(), quorum.to_string()),
+                ("call_timeout_ms.out".to_string(), timeout_ms.to_string()),
+            ])
+        };
+        let answered = |ret: &GatherRet| ret.replies.iter().filter(|(_, ret)| matches!(ret, CallRet::Reply(_))).count();
+        let timed_out = |ret: &GatherRet| ret.replies.iter().filter(|(_, ret)| *ret == CallRet::Timeout).count();
+
+        // All: the deadline elapses before the late and silent targets answer.
+        caller.set_call_quorums(CallQuorums::from_annotations(&annotations("all", "150")));
+        caller.set_call_timeouts(CallTimeouts::from_annotations(&annotations("all", "150"), None));
+        let start = tokio::time::Instant::now();
+        let ret = caller.call_all_alias("out".to_string(), "Test", opentelemetry::Context::new()).await.unwrap();
+        assert!(start.elapsed() >= std::time::Duration::from_millis(150));
+        assert!(!ret.quorum_reached);
+        assert_eq!((answered(&ret), timed_out(&ret)), (2, 2));
+        assert_eq!(caller.receiver.pending_replies(), 0);
+
+        // Majority: the late target completes the quorum, the silent one is abandoned.
+        caller.set_call_quorums(CallQuorums::from_annotations(&annotations("majority", "1000")));
+        caller.set_call_timeouts(CallTimeouts::from_annotations(&annotations("majority", "1000"), None));
+        let start = tokio::time::Instant::now();
+        let ret = caller.call_all_alias("out".to_string(), "Test", opentelemetry::Context::new()).await.unwrap();
+        assert!(start.elapsed() < std::time::Duration::from_millis(1000));
+        assert!(ret.quorum_reached);
+        assert_eq!((answered(&ret), timed_out(&ret)), (3, 0));
+        assert_eq!(ret.replies.last().unwrap().0, late);
+        assert_eq!(caller.receiver.pending_replies(), 0);
+
+        // At least 2: the late and silent targets are abandoned.
+        caller.set_call_quorums(CallQuorums::from_annotations(&annotations("2", "1000")));
+        caller.set_call_timeouts(CallTimeouts::from_annotations(&annotations("2", "1000"), None));
+        let start = tokio::time::Instant::now();
+        let ret = caller.call_all_alias("out".to_string(), "Test", opentelemetry::Context::new()).await.unwrap();
+        assert!(start.elapsed() < std::time::Duration::from_millis(300));
+        assert!(ret.quorum_reached);
+        assert_eq!((answered(&ret), timed_out(&ret)), (2, 0));
+        assert!(ret.replies.iter().all(|(target, _)| *target != late));
+        assert_eq!(caller.receiver.pending_replies(), 0);
+
+        // The replies to the abandoned calls are discarded when they arrive.
+        tokio::time::sleep(std::time::Duration::from_millis(400)).await;
+        assert_eq!(caller.queue_statistics().depth, 0);
+        assert_eq!(caller.receiver.pending_replies(), 0);
+    }
+
+    #[tokio::test]
+    async fn undelivered_cast_to_dead_letter_output() {
+        let node_id = uuid::Uuid::new_v4();
+        let fid_1 = edgeless_api::function_instance::InstanceId::new(node_id);
+        let fid_dead_letter = edgeless_api::function_instance::InstanceId::new(node_id);
+        // The node of the target is not a peer, hence the cast is never delivered.
+        let fid_unreachable = edgeless_api::function_instance::InstanceId::new(uuid::Uuid::new_v4());
+
+        let mut provider = DataplaneProvider::new(node_id, "http://127.0.0.1:7093".to_string(), None).await;
+
+        let mut handle_1 = provider.get_handle_for(fid_1).await;
+        let mut handle_dead_letter = provider.get_handle_for(fid_dead_letter).await;
+        handle_1.set_delivery(crate::delivery::DeliverySettings::from_annotations(&std::collections::HashMap::from([
+            ("delivery.out".to_string(), "at-least-once".to_string()),
+            ("delivery_retries".to_string(), "3".to_string()),
+            ("delivery_backoff_ms".to_string(), "10".to_string()),
+            ("dead_letter_output".to_string(), "dead".to_string()),
+        ])));
+        handle_1
+            .update_mapping(
+                std::collections::HashMap::new(),
+                std::collections::HashMap::from([
+                    (
+                        edgeless_api::function_instance::PortId("out".to_string()),
+                        edgeless_api::common::Output::Single(fid_unreachable, edgeless_api::function_instance::PortId("in".to_string())),
+                    ),
+                    (
+                        edgeless_api::function_instance::PortId("dead".to_string()),
+                        edgeless_api::common::Output::Single(fid_dead_letter, edgeless_api::function_instance::PortId("dead_in".to_string())),
+                    ),
+                ]),
+            )
+            .await;
+
+        handle_1.send_alias("out".to_string(), "Test", opentelemetry::Context::new()).await.unwrap();
+        // The retries do not need the handle.
+        drop(handle_1);
+
+        // 10 + 20 + 40 ms of backoff.
+        let start = tokio::time::Instant::now();
+        let event = tokio::time::timeout(std::time::Duration::from_secs(5), handle_dead_letter.receive_next())
+            .await
+            .unwrap();
+        assert!(start.elapsed() >= std::time::Duration::from_millis(70));
+        assert_eq!(event.message, Message::Cast(b"Test".to_vec()));
+        assert_eq!(event.source_id, fid_1);
+        assert_eq!(event.target_port, edgeless_api::function_instance::PortId("dead_in".to_string()));
+    }
+
+    /// Link provider counting the links it is asked to remove, failing if `fail` is set.
+    #[derive(Clone)]
+    struct CountingLinkProvider {
+        removed: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+        fail: bool,
+    }
+
+    #[async_trait::async_trait]
+    impl edgeless_api::link::LinkProvider for CountingLinkProvider {
+        fn class(&self) -> edgeless_api::link::LinkType {
+            edgeless_api::link::LinkType("COUNTING".to_string())
+        }
+        async fn create(&mut self, _req: edgeless_api::link::CreateLinkRequest) -> anyhow::Result<Box<dyn edgeless_api::link::LinkInstance>> {
+            anyhow::bail!("Not supported")
+        }
+        async fn remove(&mut self, _id: edgeless_api::link::LinkInstanceId) -> anyhow::Result<()> {
+            self.removed.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            match self.fail {
+                true => anyhow::bail!("Removal failed"),
+                false => Ok(()),
+            }
+        }
+        async fn register_reader(&mut self, _link_id: &edgeless_api::link::LinkInstanceId, _reader: Box<dyn edgeless_api::link::LinkWriter>) {}
+        async fn get_writer(&mut self, _link_id: &edgeless_api::link::LinkInstanceId) -> Option<Box<dyn edgeless_api::link::LinkWriter>> {
+            None
+        }
+    }
+
+    #[tokio::test]
+    async fn remove_link_from_all_providers() {
+        use edgeless_api::link::LinkInstanceAPI;
+
+        let node_id = uuid::Uuid::new_v4();
+        let mut provider = DataplaneProvider::new(node_id, "http://127.0.0.1:7092".to_string(), None).await;
+
+        let removed = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        {
+            let mut lm = provider.link_manager.lock().await;
+            for fail in [true, false, true] {
+                lm.link_providers.insert(
+                    edgeless_api::link::LinkProviderId(uuid::Uuid::new_v4()),
+                    Box::new(CountingLinkProvider {
+                        removed: removed.clone(),
+                        fail,
+                    }),
+                );
+            }
+        }
+
+        // The failures do not stop the other providers from removing the link.
+        let err = provider
+            .remove(edgeless_api::link::LinkInstanceId(uuid::Uuid::new_v4()))
+            .await
+            .unwrap_err();
+        assert_eq!(removed.load(std::sync::atomic::Ordering::SeqCst), 3);
+        assert_eq!(err.to_string().matches("Removal failed").count(), 2);
+    }
 }
@@ -2,6 +2,9 @@
 // SPDX-License-Identifier: MIT
 pub mod alias_mapping;
 pub mod core;
+pub mod delivery;
 pub mod handle;
+pub mod load_balancing;
 pub mod node_local;
+pub mod queue;
 pub mod remote_node;
@@ -0,0 +1,351 @@
+// SPDX-FileCopyrightText: © 2024 Technical University of Munich, Chair of Connected Mobility
+// SPDX-License-Identifier: MIT
+use rand::seq::SliceRandom;
+
+/// Annotation of a function selecting the load-balancing policy of its
+/// outputs, as `load_balancing` for all the outputs or as
+/// `load_balancing.<output>` for a single one.
+pub const LOAD_BALANCING_ANNOTATION: &str = "load_balancing";
+
+/// Policy used to pick the target of an event sent on an output
+/// mapped to any of multiple instances.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum LoadBalancingPolicy {
+    /// Pick a target uniformly at random.
+    #[default]
+    Random,
+    /// Pick the targets in turn.
+    RoundRobin,
+    /// Pick the target with the fewest calls from this instance still
+    /// waiting for a reply. Casts are not counted.
+    LeastOutstanding,
+    /// Pick a random target on the same node, if any, otherwise
+    /// a random target on any node.
+    LocalFirst,
+    /// Always pick the same target for the same key, as long as it is
+    /// available, so that stateful replicas see all the events of a
+    /// partition. The key is the partition key given by the caller, if any,
+    /// otherwise the value at the given JSON pointer in the payload, if any,
+    /// otherwise the whole payload.
+    ConsistentHash { key_pointer: Option<String> },
+}
+
+impl std::str::FromStr for LoadBalancingPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            Some(("consistent-hash", pointer)) if pointer.is_empty() || pointer.starts_with('/') => Ok(Self::ConsistentHash {
+                key_pointer: Some(pointer.to_string()),
+            }),
+            Some(_) => Err(anyhow::anyhow!("invalid load-balancing policy: {}", s)),
+            None => match s {
+                "random" => Ok(Self::Random),
+                "round-robin" => Ok(Self::RoundRobin),
+                "least-outstanding" => Ok(Self::LeastOutstanding),
+                "local-first" => Ok(Self::LocalFirst),
+                "consistent-hash" => Ok(Self::ConsistentHash { key_pointer: None }),
+                _ => Err(anyhow::anyhow!("unknown load-balancing policy: {}", s)),
+            },
+        }
+    }
+}
+
+/// Load-balancing policies of the outputs of a component.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LoadBalancingPolicies {
+    /// Policy of the outputs without a policy of their own.
+    pub default: LoadBalancingPolicy,
+    /// Policy of a given output.
+    pub by_output: std::collections::HashMap<String, LoadBalancingPolicy>,
+}
+
+impl LoadBalancingPolicies {
+    /// Policies set by the annotations of a function.
+    pub fn from_annotations(annotations: &std::collections::HashMap<String, String>) -> Self {
+        let parse = |key: &str, val: &str| match val.parse::<LoadBalancingPolicy>() {
+            Ok(policy) => Some(policy),
+            Err(err) => {
+                log::warn!("invalid value of annotation {}: {}", key, err);
+                None
+            }
+        };
+
+        let mut policies = Self::default();
+        for (key, val) in annotations {
+            if key == LOAD_BALANCING_ANNOTATION {
+                if let Some(policy) = parse(key, val) {
+                    policies.default = policy;
+                }
+            } else if let Some(output) = key.strip_prefix(LOAD_BALANCING_ANNOTATION).and_then(|rest| rest.strip_prefix('.')) {
+                if let Some(policy) = parse(key, val) {
+                    policies.by_output.insert(output.to_string(), policy);
+                }
+            }
+        }
+        policies
+    }
+
+    /// Policy of the given output.
+    pub fn for_output(&self, output: &str) -> &LoadBalancingPolicy {
+        self.by_output.get(output).unwrap_or(&self.default)
+    }
+}
+
+#[derive(Default)]
+struct BalancerState {
+    /// Next round-robin position, by output.
+    next: std::collections::HashMap<String, usize>,
+    /// Number of calls waiting for a reply, by target.
+    outstanding: std::collections::HashMap<edgeless_api::function_instance::InstanceId, usize>,
+}
+
+/// Picks the targets of the outputs of a component. The clones of a
+/// balancer share the round-robin positions and the outstanding calls.
+#[derive(Clone, Default)]
+pub struct LoadBalancer {
+    policies: LoadBalancingPolicies,
+    state: std::sync::Arc<std::sync::Mutex<BalancerState>>,
+}
+
+impl LoadBalancer {
+    pub fn policies(&self) -> &LoadBalancingPolicies {
+        &self.policies
+    }
+
+    pub fn set_policies(&mut self, policies: LoadBalancingPolicies) {
+        self.policies = policies;
+    }
+
+    /// Pick the target of an event with the given payload sent on an output.
+    /// Return None only if there are no targets.
+    pub fn select<'a>(
+        &self,
+        output: &str,
+        targets: &'a [(edgeless_api::function_instance::InstanceId, edgeless_api::function_instance::PortId)],
+        local_node: &edgeless_api::function_instance::NodeId,
+        payload: &[u8],
+        partition_key: Option<&[u8]>,
+    ) -> Option<&'a (edgeless_api::function_instance::InstanceId, edgeless_api::function_instance::PortId)> {
+        if targets.len() <= 1 {
+            return targets.first();
+        }
+        match self.policies.for_output(output) {
+            LoadBalancingPolicy::Random => targets.choose(&mut rand::thread_rng()),
+            LoadBalancingPolicy::RoundRobin => {
+                let mut state = self.state.lock().unwrap();
+                let next = state.next.entry(output.to_string()).or_default();
+                let pos = *next % targets.len();
+                *next = next.wrapping_add(1);
+                targets.get(pos)
+            }
+            LoadBalancingPolicy::LeastOutstanding => {
+                let state = self.state.lock().unwrap();
+                let outstanding = |id: &edgeless_api::function_instance::InstanceId| state.outstanding.get(id).copied().unwrap_or_default();
+                let min = targets.iter().map(|(id, _)| outstanding(id)).min().unwrap_or_default();
+                let least_loaded: Vec<_> = targets.iter().filter(|(id, _)| outstanding(id) == min).collect();
+                least_loaded.choose(&mut rand::thread_rng()).copied()
+            }
+            LoadBalancingPolicy::LocalFirst => {
+                let local: Vec<_> = targets.iter().filter(|(id, _)| id.node_id == *local_node).collect();
+                match local.choose(&mut rand::thread_rng()) {
+                    Some(target) => Some(*target),
+                    None => targets.choose(&mut rand::thread_rng()),
+                }
+            }
+            LoadBalancingPolicy::ConsistentHash { key_pointer } => {
+                let key = match partition_key {
+                    Some(key) => key.to_vec(),
+                    None => payload_key(key_pointer.as_deref(), payload),
+                };
+                // Rendezvous hashing: when a target goes away only its keys move elsewhere.
+                let key_hash = fnv1a(FNV_OFFSET_BASIS, &key);
+                targets.iter().max_by_key(|(id, port)| {
+                    let hash = fnv1a(key_hash, id.node_id.as_bytes());
+                    let hash = fnv1a(hash, id.function_id.as_bytes());
+                    mix(fnv1a(hash, port.0.as_bytes()))
+                })
+            }
+        }
+    }
+
+    /// Count a call to the given target as outstanding until the returned
+    /// guard is dropped.
+    pub fn track_call(&self, target: edgeless_api::function_instance::InstanceId) -> OutstandingCall {
+        *self.state.lock().unwrap().outstanding.entry(target).or_default() += 1;
+        OutstandingCall {
+            state: self.state.clone(),
+            target,
+        }
+    }
+}
+
+/// A call that is waiting for a reply, see `LoadBalancer::track_call`.
+pub struct OutstandingCall {
+    state: std::sync::Arc<std::sync::Mutex<BalancerState>>,
+    target: edgeless_api::function_instance::InstanceId,
+}
+
+impl Drop for OutstandingCall {
+    fn drop(&mut self) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(count) = state.outstanding.get_mut(&self.target) {
+            *count -= 1;
+            if *count == 0 {
+                state.outstanding.remove(&self.target);
+            }
+        }
+    }
+}
+
+/// Key of a payload: the value at the given JSON pointer, if the payload
+/// is a JSON document that has it, otherwise the whole payload.
+fn payload_key(key_pointer: Option<&str>, payload: &[u8]) -> Vec<u8> {
+    if let Some(key_pointer) = key_pointer {
+        if let Ok(document) = serde_json::from_slice::<serde_json::Value>(payload) {
+            match document.pointer(key_pointer) {
+                Some(serde_json::Value::String(val)) => return val.as_bytes().to_vec(),
+                Some(val) => return val.to_string().into_bytes(),
+                None => {}
+            }
+        }
+    }
+    payload.to_vec()
+}
+
+// The hash must be the same on all the nodes, hence the standard library
+// hasher, whose algorithm may change between releases, is not used.
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+
+fn fnv1a(mut hash: u64, data: &[u8]) -> u64 {
+    for byte in data {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+fn mix(mut hash: u64) -> u64 {
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xff51afd7ed558ccd);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xc4ceb9fe1a85ec53);
+    hash ^ (hash >> 33)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn targets(node_ids: &[uuid::Uuid]) -> Vec<(edgeless_api::function_instance::InstanceId, edgeless_api::function_instance::PortId)> {
+        node_ids
+            .iter()
+            .map(|node_id| {
+                (
+                    edgeless_api::function_instance::InstanceId::new(*node_id),
+                    edgeless_api::function_instance::PortId("in".to_string()),
+                )
+            })
+            .collect()
+    }
+
+    fn balancer(policy: &str) -> LoadBalancer {
+        let mut balancer = LoadBalancer::default();
+        balancer.set_policies(LoadBalancingPolicies::from_annotations(&std::collections::HashMap::from([(
+            "load_balancing.out".to_string(),
+            policy.to_string(),
+        )])));
+        balancer
+    }
+
+    #[test]
+    fn policies_from_annotations() {
+        let annotations = std::collections::HashMap::from([
+            ("load_balancing".to_string(), "round-robin".to_string()),
+            ("load_balancing.cart".to_string(), "consistent-hash:/user".to_string()),
+            ("load_balancing.bad".to_string(), "fastest".to_string()),
+            ("call_timeout_ms".to_string(), "100".to_string()),
+        ]);
+        let policies = LoadBalancingPolicies::from_annotations(&annotations);
+        assert_eq!(policies.for_output("out"), &LoadBalancingPolicy::RoundRobin);
+        assert_eq!(policies.for_output("bad"), &LoadBalancingPolicy::RoundRobin);
+        assert_eq!(
+            policies.for_output("cart"),
+            &LoadBalancingPolicy::ConsistentHash {
+                key_pointer: Some("/user".to_string())
+            }
+        );
+        assert_eq!(
+            LoadBalancingPolicies::from_annotations(&std::collections::HashMap::new()).for_output("out"),
+            &LoadBalancingPolicy::Random
+        );
+    }
+
+    #[test]
+    fn round_robin() {
+        let node_id = uuid::Uuid::new_v4();
+        let targets = targets(&[node_id, node_id, node_id]);
+        let balancer = balancer("round-robin");
+        let cloned = balancer.clone();
+        for i in 0..6 {
+            let balancer = if i % 2 == 0 { &balancer } else { &cloned };
+            assert_eq!(balancer.select("out", &targets, &node_id, &[], None), targets.get(i % 3));
+        }
+    }
+
+    #[test]
+    fn least_outstanding() {
+        let node_id = uuid::Uuid::new_v4();
+        let targets = targets(&[node_id, node_id]);
+        let balancer = balancer("least-outstanding");
+        let _busy = balancer.track_call(targets[0].0);
+        for _ in 0..10 {
+            assert_eq!(balancer.select("out", &targets, &node_id, &[], None), Some(&targets[1]));
+        }
+        {
+            let _busier = (balancer.track_call(targets[1].0), balancer.track_call(targets[1].0));
+            assert_eq!(balancer.select("out", &targets, &node_id, &[], None), Some(&targets[0]));
+        }
+        assert_eq!(balancer.select("out", &targets, &node_id, &[], None), Some(&targets[1]));
+    }
+
+    #[test]
+    fn local_first() {
+        let local_node = uuid::Uuid::new_v4();
+        let remote_node = uuid::Uuid::new_v4();
+        let balancer = balancer("local-first");
+        let mixed = targets(&[remote_node, local_node, remote_node]);
+        for _ in 0..10 {
+            assert_eq!(balancer.select("out", &mixed, &local_node, &[], None), Some(&mixed[1]));
+        }
+        let remote_only = targets(&[remote_node, remote_node]);
+        assert!(balancer.select("out", &remote_only, &local_node, &[], None).is_some());
+    }
+
+    #[test]
+    fn consistent_hash() {
+        let node_id = uuid::Uuid::new_v4();
+        let targets = targets(&[node_id, node_id, node_id, node_id]);
+        let balancer = balancer("consistent-hash:/user");
+
+        // Same key, same target, regardless of the rest of the payload.
+        let first = balancer.select("out", &targets, &node_id, br#"{"user":"alice","item":1}"#, None);
+        let second = balancer.select("out", &targets, &node_id, br#"{"user":"alice","item":2}"#, None);
+        assert_eq!(first, second);
+
+        // The partition key of the caller takes precedence over the payload.
+        assert_eq!(balancer.select("out", &targets, &node_id, b"not json", Some(b"alice")), first);
+
+        // Removing another target does not move the key.
+        let first_pos = targets.iter().position(|target| Some(target) == first).unwrap();
+        let mut remaining = targets.clone();
+        remaining.remove((first_pos + 1) % targets.len());
+        assert_eq!(balancer.select("out", &remaining, &node_id, b"", Some(b"alice")), first);
+
+        // The keys are spread over the targets.
+        let picked: std::collections::HashSet<_> = (0..100)
+            .filter_map(|i| balancer.select("out", &targets, &node_id, b"", Some(format!("key-{}", i).as_bytes())))
+            .collect();
+        assert_eq!(picked.len(), targets.len());
+    }
+}
@@ -2,8 +2,6 @@
 // SPDX-FileCopyrightText: © 2023 Claudio Cicconetti <c.cicconetti@iit.cnr.it>
 // SPDX-License-Identifier: MIT
 use crate::core::*;
-use edgeless_api::invocation::InvocationAPI;
-use futures::SinkExt;
 
 // Link representing a component on the local node.
 // Internally uses a table if link instances (NodeLocalRouter) that enqueues events based on the targeted function_id.
@@ -24,26 +22,18 @@ impl DataPlaneLink for NodeLocalLink {
         context: opentelemetry::trace::SpanContext
     ) -> LinkProcessingResult {
         if target.node_id == self.node_id {
-            return self
-                .router
-                .lock()
-                .await
-                .handle(edgeless_api::invocation::Event {
-                    target: *target,
-                    source: *src,
-                    stream_id,
-                    data: match msg {
-                        Message::Call(data) => edgeless_api::invocation::EventData::Call(data),
-                        Message::Cast(data) => edgeless_api::invocation::EventData::Cast(data),
-                        Message::CallRet(data) => edgeless_api::invocation::EventData::CallRet(data),
-                        Message::CallNoRet => edgeless_api::invocation::EventData::CallNoRet,
-                        Message::Err => edgeless_api::invocation::EventData::Err,
-                    },
+            return NodeLocalRouter::deliver(
+                &self.router,
+                &target.function_id,
+                DataplaneEvent {
+                    source_id: *src,
+                    channel_id: stream_id,
+                    message: msg,
                     target_port,
-                    context
-                })
-                .await
-                .unwrap();
+                    context,
+                },
+            )
+            .await;
         } else {
             return LinkProcessingResult::PASSED;
         }
@@ -51,40 +41,56 @@ impl DataPlaneLink for NodeLocalLink {
 }
 
 pub struct NodeLocalRouter {
-    pub receivers: std::collections::HashMap<uuid::Uuid, futures::channel::mpsc::UnboundedSender<DataplaneEvent>>,
+    pub receivers: std::collections::HashMap<uuid::Uuid, crate::queue::InputQueueSender>,
 }
 
-// This is used by the remote node that is currently borrowing the `NodeLocalRouter`
-#[async_trait::async_trait]
-impl edgeless_api::invocation::InvocationAPI for NodeLocalRouter {
-    async fn handle(&mut self, event: edgeless_api::invocation::Event) -> anyhow::Result<edgeless_api::invocation::LinkProcessingResult> {
-        if let Some(sender) = self.receivers.get_mut(&event.target.function_id) {
-            let msg = match event.data {
-                edgeless_api::invocation::EventData::Call(data) => Message::Call(data),
-                edgeless_api::invocation::EventData::Cast(data) => Message::Cast(data),
-                edgeless_api::invocation::EventData::CallRet(data) => Message::CallRet(data),
-                edgeless_api::invocation::EventData::CallNoRet => Message::CallNoRet,
-                edgeless_api::invocation::EventData::Err => Message::Err,
-            };
-            match sender
-                .send(DataplaneEvent {
-                    source_id: event.source,
-                    channel_id: event.stream_id,
-                    message: msg,
-                    target_port: event.target_port.clone(),
-                    context: event.context.clone()
-                })
-                .await
-            {
-                Ok(_) => {}
-                Err(_) => {
+impl NodeLocalRouter {
+    /// Deliver an event to a component on the local node.
+    /// The router is only locked to look up the input queue of the component,
+    /// so that a sender waiting for room in a full queue does not hold up the
+    /// events targeting the other components.
+    pub async fn deliver(
+        router: &std::sync::Arc<tokio::sync::Mutex<NodeLocalRouter>>,
+        component_id: &uuid::Uuid,
+        event: DataplaneEvent,
+    ) -> LinkProcessingResult {
+        let sender = router.lock().await.receivers.get(component_id).cloned();
+        match sender {
+            Some(sender) => {
+                if sender.push(event).await.is_err() {
                     log::debug!("Remove old receiver.");
-                    self.receivers.remove(&event.target.function_id);
+                    router.lock().await.receivers.remove(component_id);
                 }
+                LinkProcessingResult::FINAL
             }
-            return Ok(LinkProcessingResult::FINAL);
+            None => LinkProcessingResult::PASSED,
         }
-        Ok(LinkProcessingResult::PASSED)
+    }
+
+    // This is used by the remote node to deliver the events received from other nodes.
+    pub async fn handle_event(
+        router: &std::sync::Arc<tokio::sync::Mutex<NodeLocalRouter>>,
+        event: edgeless_api::invocation::Event,
+    ) -> anyhow::Result<LinkProcessingResult> {
+        let message = match event.data {
+            edgeless_api::invocation::EventData::Call(data) => Message::Call(data),
+            edgeless_api::invocation::EventData::Cast(data) => Message::Cast(data),
+            edgeless_api::invocation::EventData::CallRet(data) => Message::CallRet(data),
+            edgeless_api::invocation::EventData::CallNoRet => Message::CallNoRet,
+            edgeless_api::invocation::EventData::Err => Message::Err,
+        };
+        Ok(Self::deliver(
+            router,
+            &event.target.function_id,
+            DataplaneEvent {
+                source_id: event.source,
+                channel_id: event.stream_id,
+                message,
+                target_port: event.target_port,
+                context: event.context,
+            },
+        )
+        .await)
     }
 }
 
@@ -102,7 +108,7 @@ impl NodeLocalLinkProvider {
     pub fn new() -> Self {
         Self {
             router: std::sync::Arc::new(tokio::sync::Mutex::new(NodeLocalRouter {
-                receivers: std::collections::HashMap::<uuid::Uuid, futures::channel::mpsc::UnboundedSender<DataplaneEvent>>::new(),
+                receivers: std::collections::HashMap::<uuid::Uuid, crate::queue::InputQueueSender>::new(),
             })),
         }
     }
@@ -110,7 +116,7 @@ impl NodeLocalLinkProvider {
     pub async fn new_link(
         &self,
         target: edgeless_api::function_instance::InstanceId,
-        sender: futures::channel::mpsc::UnboundedSender<DataplaneEvent>,
+        sender: crate::queue::InputQueueSender,
     ) -> Box<dyn DataPlaneLink> {
         self.router.lock().await.receivers.insert(target.function_id, sender);
         Box::new(NodeLocalLink {
@@ -118,6 +124,18 @@ impl NodeLocalLinkProvider {
             router: self.router.clone(),
         })
     }
+
+    /// Current state of the input queues of the components on this node,
+    /// by function identifier.
+    pub async fn queue_statistics(&self) -> std::collections::HashMap<uuid::Uuid, crate::queue::QueueStatistics> {
+        self.router
+            .lock()
+            .await
+            .receivers
+            .iter()
+            .filter_map(|(component_id, sender)| Some((*component_id, sender.statistics()?)))
+            .collect()
+    }
 }
 
 #[cfg(test)]
@@ -132,44 +150,53 @@ mod test {
         let fid_3 = edgeless_api::function_instance::InstanceId::new(node_id);
 
         let provider = NodeLocalLinkProvider::new();
+        let settings = crate::core::EdgelessDataplaneSettings::default();
 
-        let (sender_1, mut receiver_1) = futures::channel::mpsc::unbounded::<crate::core::DataplaneEvent>();
+        let (sender_1, receiver_1, _) = crate::queue::input_queue(&settings);
         let mut handle_1 = provider.new_link(fid_1, sender_1).await;
 
-        let (sender_2, mut receiver_2) = futures::channel::mpsc::unbounded::<crate::core::DataplaneEvent>();
+        let (sender_2, receiver_2, _) = crate::queue::input_queue(&settings);
         let _handle_2 = provider.new_link(fid_2, sender_2).await;
 
-        assert!(receiver_1.try_next().is_err());
-        assert!(receiver_2.try_next().is_err());
+        assert_eq!(receiver_1.statistics().depth, 0);
+        assert_eq!(receiver_2.statistics().depth, 0);
 
         let ret_1 = handle_1
             .handle_send(
                 &fid_3,
-                crate::core::Message::Cast("".to_string()),
+                crate::core::Message::Cast(vec![]),
                 &fid_1,
                 0,
                 edgeless_api::function_instance::PortId("test".to_string()),
+                opentelemetry::trace::SpanContext::empty_context(),
             )
             .as_mut()
             .await;
 
         assert_eq!(ret_1, crate::core::LinkProcessingResult::PASSED);
-        assert!(receiver_1.try_next().is_err());
-        assert!(receiver_2.try_next().is_err());
+        assert_eq!(receiver_1.statistics().depth, 0);
+        assert_eq!(receiver_2.statistics().depth, 0);
 
         let ret_2 = handle_1
             .handle_send(
                 &fid_2,
-                crate::core::Message::Cast("".to_string()),
+                crate::core::Message::Cast(vec![]),
                 &fid_1,
                 0,
                 edgeless_api::function_instance::PortId("test".to_string()),
+                opentelemetry::trace::SpanContext::empty_context(),
             )
             .as_mut()
             .await;
 
         assert_eq!(ret_2, crate::core::LinkProcessingResult::FINAL);
-        assert!(receiver_1.try_next().is_err());
-        assert!(receiver_2.try_next().unwrap().is_some());
+        assert_eq!(receiver_1.statistics().depth, 0);
+        assert_eq!(receiver_2.statistics().depth, 1);
+
+        // The queues of the components that have gone are not reported.
+        drop(receiver_1);
+        let statistics = provider.queue_statistics().await;
+        assert_eq!(statistics.len(), 1);
+        assert_eq!(statistics[&fid_2.function_id].depth, 1);
     }
 }
@@ -0,0 +1,388 @@
+// SPDX-FileCopyrightText: © 2024 Technical University of Munich, Chair of Connected Mobility
+// SPDX-License-Identifier: MIT
+use crate::core::*;
+
+/// Behavior of an input queue that is full when a new cast or call arrives.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OverflowPolicy {
+    /// The sender waits until there is room in the queue.
+    /// Components sending events to each other in a cycle deadlock when all
+    /// their queues are full.
+    #[default]
+    Block,
+    /// The oldest event in the queue is discarded to make room for the new one.
+    DropOldest,
+    /// The new event is discarded.
+    DropNewest,
+    /// Calls are answered with an error, casts wait as with `Block`.
+    RejectCalls,
+}
+
+/// Current state of an input queue.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueueStatistics {
+    /// Number of events waiting to be processed.
+    pub depth: usize,
+    /// Number of events discarded or rejected since the queue was created.
+    pub dropped: u64,
+}
+
+type ReplySender = futures::channel::oneshot::Sender<(edgeless_api::function_instance::InstanceId, Message)>;
+
+struct QueueState {
+    events: std::collections::VecDeque<DataplaneEvent>,
+    // Calls waiting for a reply, by channel_id.
+    replies: std::collections::HashMap<u64, ReplySender>,
+    next_channel_id: u64,
+    // Calls that have been discarded and must be answered with an error.
+    // None once the receiving side has gone.
+    rejected: Option<tokio::sync::mpsc::UnboundedSender<DataplaneEvent>>,
+    dropped: u64,
+    closed: bool,
+}
+
+struct SharedQueue {
+    state: std::sync::Mutex<QueueState>,
+    capacity: usize,
+    overflow_policy: OverflowPolicy,
+    readable: tokio::sync::Notify,
+    writable: tokio::sync::Notify,
+}
+
+impl SharedQueue {
+    fn state(&self) -> std::sync::MutexGuard<'_, QueueState> {
+        // The state is consistent after every critical section, hence a poisoned lock can be reused.
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Sending side of the input queue of a component, used by the routers
+/// delivering the events targeting the component.
+/// Replies (return values and errors) are not queued: they are handed over
+/// to the call waiting for them as soon as they arrive, so that a component
+/// blocked on a call is never waiting for a reply stuck behind its own
+/// full queue.
+#[derive(Clone)]
+pub struct InputQueueSender {
+    shared: std::sync::Arc<SharedQueue>,
+}
+
+/// Receiving side of the input queue of a component.
+/// The queue is closed when the last clone is dropped.
+#[derive(Clone)]
+pub struct InputQueueReceiver {
+    shared: std::sync::Arc<SharedQueue>,
+    _guard: std::sync::Arc<CloseOnDrop>,
+}
+
+struct CloseOnDrop {
+    shared: std::sync::Arc<SharedQueue>,
+}
+
+impl Drop for CloseOnDrop {
+    fn drop(&mut self) {
+        {
+            let mut state = self.shared.state();
+            state.closed = true;
+            state.events.clear();
+            state.replies.clear();
+            state.rejected = None;
+        }
+        self.shared.writable.notify_waiters();
+    }
+}
+
+/// Create the input queue of a component.
+/// Calls discarded because of the overflow policy are returned through the
+/// last receiver, so that the owner of the queue can answer them with an error.
+pub fn input_queue(
+    settings: &EdgelessDataplaneSettings,
+) -> (InputQueueSender, InputQueueReceiver, tokio::sync::mpsc::UnboundedReceiver<DataplaneEvent>) {
+    let (rejected_sender, rejected_receiver) = tokio::sync::mpsc::unbounded_channel();
+    let shared = std::sync::Arc::new(SharedQueue {
+        state: std::sync::Mutex::new(QueueState {
+            events: std::collections::VecDeque::new(),
+            replies: std::collections::HashMap::new(),
+            next_channel_id: 1,
+            rejected: Some(rejected_sender),
+            dropped: 0,
+            closed: false,
+        }),
+        capacity: settings.queue_capacity,
+        overflow_policy: settings.overflow_policy,
+        readable: tokio::sync::Notify::new(),
+        writable: tokio::sync::Notify::new(),
+    });
+    (
+        InputQueueSender { shared: shared.clone() },
+        InputQueueReceiver {
+            shared: shared.clone(),
+            _guard: std::sync::Arc::new(CloseOnDrop { shared }),
+        },
+        rejected_receiver,
+    )
+}
+
+impl InputQueueSender {
+    /// Deliver an event to the component, applying the overflow policy
+    /// if the queue is full.
+    /// Fails only if the component does not exist anymore.
+    pub async fn push(&self, event: DataplaneEvent) -> anyhow::Result<()> {
+        if !matches!(event.message, Message::Cast(_) | Message::Call(_)) {
+            return self.push_reply(event);
+        }
+
+        loop {
+            // Register as a waiter before checking, so that no wake-up is missed.
+            let writable = self.shared.writable.notified();
+            tokio::pin!(writable);
+            writable.as_mut().enable();
+
+            {
+                let mut state = self.shared.state();
+                if state.closed {
+                    return Err(anyhow::anyhow!("input queue closed"));
+                }
+                if self.shared.capacity == 0 || state.events.len() < self.shared.capacity {
+                    state.events.push_back(event);
+                    drop(state);
+                    self.shared.readable.notify_one();
+                    return Ok(());
+                }
+
+                let wait = match self.shared.overflow_policy {
+                    OverflowPolicy::Block => true,
+                    OverflowPolicy::RejectCalls => !matches!(event.message, Message::Call(_)),
+                    OverflowPolicy::DropOldest | OverflowPolicy::DropNewest => false,
+                };
+                if !wait {
+                    state.dropped += 1;
+                    if self.shared.overflow_policy == OverflowPolicy::DropOldest {
+                        if let Some(oldest) = state.events.pop_front() {
+                            Self::reject(&mut state, oldest);
+                        }
+                        state.events.push_back(event);
+                        drop(state);
+                        self.shared.readable.notify_one();
+                    } else {
+                        Self::reject(&mut state, event);
+                    }
+                    return Ok(());
+                }
+            }
+
+            writable.await;
+        }
+    }
+
+    /// Current state of the queue, None if the receiving side has gone.
+    pub fn statistics(&self) -> Option<QueueStatistics> {
+        let state = self.shared.state();
+        if state.closed {
+            return None;
+        }
+        Some(QueueStatistics {
+            depth: state.events.len(),
+            dropped: state.dropped,
+        })
+    }
+
+    fn push_reply(&self, event: DataplaneEvent) -> anyhow::Result<()> {
+        let mut state = self.shared.state();
+        if state.closed {
+            return Err(anyhow::anyhow!("input queue closed"));
+        }
+        let delivered = match state.replies.remove(&event.channel_id) {
+            Some(sender) => sender.send((event.source_id, event.message)).is_ok(),
+            None => false,
+        };
+        if !delivered {
+            // E.g., the call has timed out in the meantime.
+            log::debug!("No call waiting for the reply on channel {} from {:?}", event.channel_id, event.source_id);
+        }
+        Ok(())
+    }
+
+    fn reject(state: &mut QueueState, event: DataplaneEvent) {
+        log::debug!("Input queue full, discarding event from {:?}", event.source_id);
+        if let Message::Call(_) = event.message {
+            if let Some(rejected) = &state.rejected {
+                let _ = rejected.send(event);
+            }
+        }
+    }
+}
+
+impl InputQueueReceiver {
+    /// Wait for the next cast or call event.
+    pub async fn pop(&self) -> DataplaneEvent {
+        loop {
+            let readable = self.shared.readable.notified();
+            tokio::pin!(readable);
+            readable.as_mut().enable();
+
+            if let Some(event) = self.shared.state().events.pop_front() {
+                self.shared.writable.notify_one();
+                return event;
+            }
+
+            readable.await;
+        }
+    }
+
+    /// Register a new call waiting for a reply on a channel of its own.
+    /// The call stops waiting when the returned future is dropped, e.g.,
+    /// because of a timeout.
+    pub fn expect_reply(&self) -> PendingReply {
+        let (sender, receiver) = futures::channel::oneshot::channel();
+        let mut state = self.shared.state();
+        let channel_id = state.next_channel_id;
+        state.next_channel_id += 1;
+        state.replies.insert(channel_id, sender);
+        PendingReply {
+            shared: self.shared.clone(),
+            channel_id,
+            receiver,
+        }
+    }
+
+    pub fn statistics(&self) -> QueueStatistics {
+        let state = self.shared.state();
+        QueueStatistics {
+            depth: state.events.len(),
+            dropped: state.dropped,
+        }
+    }
+
+    /// Number of calls waiting for a reply.
+    #[cfg(test)]
+    pub(crate) fn pending_replies(&self) -> usize {
+        self.shared.state().replies.len()
+    }
+}
+
+/// Reply to a call, resolved when the reply is received.
+pub struct PendingReply {
+    shared: std::sync::Arc<SharedQueue>,
+    channel_id: u64,
+    receiver: futures::channel::oneshot::Receiver<(edgeless_api::function_instance::InstanceId, Message)>,
+}
+
+impl PendingReply {
+    /// Channel on which the reply is expected, to be sent with the call.
+    pub fn channel_id(&self) -> u64 {
+        self.channel_id
+    }
+}
+
+impl std::future::Future for PendingReply {
+    type Output = Result<(edgeless_api::function_instance::InstanceId, Message), futures::channel::oneshot::Canceled>;
+
+    fn poll(mut self: std::pin::Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> std::task::Poll<Self::Output> {
+        std::future::Future::poll(std::pin::Pin::new(&mut self.receiver), cx)
+    }
+}
+
+impl Drop for PendingReply {
+    fn drop(&mut self) {
+        self.shared.state().replies.remove(&self.channel_id);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn event(message: Message, channel_id: u64) -> DataplaneEvent {
+        DataplaneEvent {
+            source_id: edgeless_api::function_instance::InstanceId::new(uuid::Uuid::new_v4()),
+            channel_id,
+            message,
+            target_port: edgeless_api::function_instance::PortId("test".to_string()),
+            context: opentelemetry::trace::SpanContext::empty_context(),
+        }
+    }
+
+    fn settings(queue_capacity: usize, overflow_policy: OverflowPolicy) -> EdgelessDataplaneSettings {
+        EdgelessDataplaneSettings {
+            queue_capacity,
+            overflow_policy,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn drop_oldest_and_newest() {
+        let (sender, receiver, mut rejected) = input_queue(&settings(2, OverflowPolicy::DropOldest));
+        for i in 1..=3 {
+            sender.push(event(Message::Cast(vec![i]), 0)).await.unwrap();
+        }
+        assert_eq!(receiver.statistics(), QueueStatistics { depth: 2, dropped: 1 });
+        assert_eq!(receiver.pop().await.message, Message::Cast(vec![2]));
+        assert_eq!(receiver.pop().await.message, Message::Cast(vec![3]));
+        assert!(rejected.try_recv().is_err());
+
+        let (sender, receiver, _rejected) = input_queue(&settings(2, OverflowPolicy::DropNewest));
+        for i in 1..=3 {
+            sender.push(event(Message::Cast(vec![i]), 0)).await.unwrap();
+        }
+        assert_eq!(receiver.statistics(), QueueStatistics { depth: 2, dropped: 1 });
+        assert_eq!(receiver.pop().await.message, Message::Cast(vec![1]));
+        assert_eq!(receiver.pop().await.message, Message::Cast(vec![2]));
+    }
+
+    #[tokio::test]
+    async fn reject_calls() {
+        let (sender, receiver, mut rejected) = input_queue(&settings(1, OverflowPolicy::RejectCalls));
+        sender.push(event(Message::Cast(vec![1]), 0)).await.unwrap();
+        sender.push(event(Message::Call(vec![2]), 7)).await.unwrap();
+        assert_eq!(rejected.try_recv().unwrap().channel_id, 7);
+
+        // Casts wait for room in the queue.
+        let blocked_sender = sender.clone();
+        let blocked = tokio::spawn(async move { blocked_sender.push(event(Message::Cast(vec![3]), 0)).await });
+        tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
+        assert!(!blocked.is_finished());
+        assert_eq!(receiver.pop().await.message, Message::Cast(vec![1]));
+        blocked.await.unwrap().unwrap();
+        assert_eq!(receiver.pop().await.message, Message::Cast(vec![3]));
+        assert_eq!(receiver.statistics(), QueueStatistics { depth: 0, dropped: 1 });
+    }
+
+    #[tokio::test]
+    async fn replies_bypass_full_queue() {
+        let (sender, receiver, _rejected) = input_queue(&settings(1, OverflowPolicy::Block));
+        sender.push(event(Message::Cast(vec![1]), 0)).await.unwrap();
+        let reply = receiver.expect_reply();
+        sender.push(event(Message::CallRet(vec![2]), reply.channel_id())).await.unwrap();
+        assert_eq!(reply.await.unwrap().1, Message::CallRet(vec![2]));
+        assert_eq!(receiver.statistics().depth, 1);
+    }
+
+    #[tokio::test]
+    async fn dropped_reply_is_purged() {
+        let (sender, receiver, _rejected) = input_queue(&settings(0, OverflowPolicy::Block));
+        let reply = receiver.expect_reply();
+        let channel_id = reply.channel_id();
+        assert!(tokio::time::timeout(tokio::time::Duration::from_millis(10), reply).await.is_err());
+        assert!(receiver.shared.state().replies.is_empty());
+
+        // A late reply is discarded.
+        sender.push(event(Message::CallRet(vec![]), channel_id)).await.unwrap();
+        assert_eq!(receiver.expect_reply().channel_id(), channel_id + 1);
+        assert_eq!(receiver.statistics().depth, 0);
+    }
+
+    #[tokio::test]
+    async fn closed_when_receiver_dropped() {
+        let (sender, receiver, _rejected) = input_queue(&settings(1, OverflowPolicy::Block));
+        sender.push(event(Message::Cast(vec![1]), 0)).await.unwrap();
+        let blocked_sender = sender.clone();
+        let blocked = tokio::spawn(async move { blocked_sender.push(event(Message::Cast(vec![2]), 0)).await });
+        tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
+        drop(receiver);
+        assert!(blocked.await.unwrap().is_err());
+        assert!(sender.push(event(Message::Cast(vec![3]), 0)).await.is_err());
+    }
+}
@@ -22,11 +22,18 @@ impl DataPlaneLink for RemoteLink {
         target_port: edgeless_api::function_instance::PortId,
         context: opentelemetry::trace::SpanContext
     ) -> LinkProcessingResult {
-        return self
-            .remotes
-            .lock()
-            .await
-            .handle(edgeless_api::invocation::Event {
+        // The casts delivered at least once carry a sequence id in place of the
+        // stream id: only for them the sender needs to know if they were handled.
+        let fire_and_forget = matches!(msg, Message::Cast(_)) && stream_id == 0;
+
+        // The lock is only held to hand the event over, so that the events sent
+        // by the other components to remote nodes can be pipelined with this one.
+        let mut remotes = self.remotes.lock().await;
+        if !remotes.receivers.contains_key(&target.node_id) {
+            return LinkProcessingResult::PASSED;
+        }
+        let pending = remotes
+            .handle_pipelined(edgeless_api::invocation::Event {
                 target: *target,
                 source: *src,
                 stream_id,
@@ -40,8 +47,25 @@ impl DataPlaneLink for RemoteLink {
                 target_port,
                 context
             })
-            .await
-            .unwrap();
+            .await;
+        drop(remotes);
+
+        if fire_and_forget {
+            let target = *target;
+            tokio::spawn(async move {
+                if let Err(err) = pending.await {
+                    log::warn!("Error in handling event for {:?}: {}", target, err);
+                }
+            });
+            return LinkProcessingResult::FINAL;
+        }
+        match pending.await {
+            Ok(res) => res,
+            Err(err) => {
+                log::warn!("Error in handling event for {:?}: {}", target, err);
+                LinkProcessingResult::ERROR
+            }
+        }
     }
 }
 
@@ -53,18 +77,38 @@ pub struct RemoteLinkProvider {
     own_node_id: edgeless_api::function_instance::NodeId,
     remotes: std::sync::Arc<tokio::sync::Mutex<RemoteRouter>>,
     locals: std::sync::Arc<tokio::sync::Mutex<NodeLocalRouter>>,
+    deduplicator: std::sync::Arc<std::sync::Mutex<crate::delivery::Deduplicator>>,
 }
 
 struct InvocationEventHandler {
     node_id: edgeless_api::function_instance::NodeId,
     locals: std::sync::Arc<tokio::sync::Mutex<NodeLocalRouter>>,
+    deduplicator: std::sync::Arc<std::sync::Mutex<crate::delivery::Deduplicator>>,
 }
 
 #[async_trait::async_trait]
 impl edgeless_api::invocation::InvocationAPI for InvocationEventHandler {
     async fn handle(&mut self, event: edgeless_api::invocation::Event) -> anyhow::Result<edgeless_api::invocation::LinkProcessingResult> {
         if event.target.node_id == self.node_id {
-            self.locals.lock().await.handle(event).await
+            // The casts delivered at least once carry a sequence id in place of the stream id.
+            let sequence_id = match event.data {
+                edgeless_api::invocation::EventData::Cast(_) if event.stream_id != 0 => Some(event.stream_id),
+                _ => None,
+            };
+            let source = event.source;
+            if let Some(sequence_id) = sequence_id {
+                if !self.deduplicator.lock().unwrap().first_seen(&source, sequence_id) {
+                    log::debug!("Discard duplicate cast {} from {:?}", sequence_id, source);
+                    return Ok(edgeless_api::invocation::LinkProcessingResult::FINAL);
+                }
+            }
+            let res = NodeLocalRouter::handle_event(&self.locals, event).await;
+            if let Some(sequence_id) = sequence_id {
+                if !matches!(res, Ok(edgeless_api::invocation::LinkProcessingResult::FINAL)) {
+                    self.deduplicator.lock().unwrap().forget(&source, sequence_id);
+                }
+            }
+            res
         } else {
             Err(anyhow::anyhow!("Wrong Node ID"))
         }
@@ -74,13 +118,18 @@ impl edgeless_api::invocation::InvocationAPI for InvocationEventHandler {
 #[async_trait::async_trait]
 impl edgeless_api::invocation::InvocationAPI for RemoteRouter {
     async fn handle(&mut self, event: edgeless_api::invocation::Event) -> anyhow::Result<edgeless_api::invocation::LinkProcessingResult> {
+        self.handle_pipelined(event).await.await
+    }
+
+    async fn handle_pipelined(&mut self, event: edgeless_api::invocation::Event) -> edgeless_api::invocation::PendingResult {
         if let Some(node_client) = self.receivers.get_mut(&event.target.node_id) {
-            if let Err(err) = node_client.handle(event).await {
-                log::warn!("Error in handling event: {}", err);
-            }
-            Ok(edgeless_api::invocation::LinkProcessingResult::FINAL)
+            let pending = node_client.handle_pipelined(event).await;
+            Box::pin(async move {
+                pending.await?;
+                Ok(edgeless_api::invocation::LinkProcessingResult::FINAL)
+            })
         } else {
-            Ok(edgeless_api::invocation::LinkProcessingResult::PASSED)
+            Box::pin(async { Ok(edgeless_api::invocation::LinkProcessingResult::PASSED) })
         }
     }
 }
@@ -88,7 +137,7 @@ impl edgeless_api::invocation::InvocationAPI for RemoteRouter {
 impl RemoteLinkProvider {
     pub async fn new(own_node_id: edgeless_api::function_instance::NodeId) -> Self {
         let locals = std::sync::Arc::new(tokio::sync::Mutex::new(NodeLocalRouter {
-            receivers: std::collections::HashMap::<ComponentId, futures::channel::mpsc::UnboundedSender<DataplaneEvent>>::new(),
+            receivers: std::collections::HashMap::<ComponentId, crate::queue::InputQueueSender>::new(),
         }));
 
         let remotes = std::sync::Arc::new(tokio::sync::Mutex::new(RemoteRouter {
@@ -99,13 +148,14 @@ impl RemoteLinkProvider {
             own_node_id,
             remotes,
             locals,
+            deduplicator: std::sync::Arc::new(std::sync::Mutex::new(crate::delivery::Deduplicator::default())),
         }
     }
 
     pub async fn new_link(
         &self,
         target: edgeless_api::function_instance::InstanceId,
-        sender: futures::channel::mpsc::UnboundedSender<DataplaneEvent>,
+        sender: crate::queue::InputQueueSender,
     ) -> Box<dyn DataPlaneLink> {
         self.locals.lock().await.receivers.insert(target.function_id, sender);
         Box::new(RemoteLink {
@@ -117,6 +167,7 @@ impl RemoteLinkProvider {
         Box::new(InvocationEventHandler {
             node_id: self.own_node_id,
             locals: self.locals.clone(),
+            deduplicator: self.deduplicator.clone(),
         })
     }
 
@@ -151,45 +202,76 @@ mod test {
         let mut provider = RemoteLinkProvider::new(node_id).await;
         let mut api = provider.incomming_api().await;
 
-        let (sender_1, mut receiver_1) = futures::channel::mpsc::unbounded::<crate::core::DataplaneEvent>();
+        let (sender_1, receiver_1, _) = crate::queue::input_queue(&crate::core::EdgelessDataplaneSettings::default());
         provider.new_link(fid_target, sender_1).await;
 
         api.handle(edgeless_api::invocation::Event {
             target: fid_wrong_component_id,
             source: fid_source,
             stream_id: 0,
-            data: edgeless_api::invocation::EventData::Cast("Test".to_string()),
+            data: edgeless_api::invocation::EventData::Cast(b"Test".to_vec()),
             target_port: edgeless_api::function_instance::PortId("test".to_string()),
+            context: opentelemetry::trace::SpanContext::empty_context(),
         })
         .await
         .unwrap();
 
-        assert!(receiver_1.try_next().is_err());
+        assert_eq!(receiver_1.statistics().depth, 0);
 
         assert!(api
             .handle(edgeless_api::invocation::Event {
                 target: fid_wrong_node_id,
                 source: fid_source,
                 stream_id: 0,
-                data: edgeless_api::invocation::EventData::Cast("Test".to_string()),
-                target_port: edgeless_api::function_instance::PortId("test".to_string())
+                data: edgeless_api::invocation::EventData::Cast(b"Test".to_vec()),
+                target_port: edgeless_api::function_instance::PortId("test".to_string()),
+                context: opentelemetry::trace::SpanContext::empty_context(),
             })
             .await
             .is_err());
 
-        assert!(receiver_1.try_next().is_err());
+        assert_eq!(receiver_1.statistics().depth, 0);
 
         api.handle(edgeless_api::invocation::Event {
             target: fid_target,
             source: fid_source,
             stream_id: 0,
-            data: edgeless_api::invocation::EventData::Cast("Test".to_string()),
+            data: edgeless_api::invocation::EventData::Cast(b"Test".to_vec()),
             target_port: edgeless_api::function_instance::PortId("test".to_string()),
+            context: opentelemetry::trace::SpanContext::empty_context(),
         })
         .await
         .unwrap();
 
-        assert!(receiver_1.try_next().unwrap().is_some());
+        assert_eq!(receiver_1.statistics().depth, 1);
+    }
+
+    #[tokio::test]
+    async fn duplicate_cast_discarded() {
+        let node_id = uuid::Uuid::new_v4();
+        let fid_target = edgeless_api::function_instance::InstanceId::new(node_id);
+        let fid_source = edgeless_api::function_instance::InstanceId::new(uuid::Uuid::new_v4());
+
+        let mut provider = RemoteLinkProvider::new(node_id).await;
+        let mut api = provider.incomming_api().await;
+
+        let (sender_1, receiver_1, _) = crate::queue::input_queue(&crate::core::EdgelessDataplaneSettings::default());
+        provider.new_link(fid_target, sender_1).await;
+
+        let event = |stream_id| edgeless_api::invocation::Event {
+            target: fid_target,
+            source: fid_source,
+            stream_id,
+            data: edgeless_api::invocation::EventData::Cast(b"Test".to_vec()),
+            target_port: edgeless_api::function_instance::PortId("test".to_string()),
+            context: opentelemetry::trace::SpanContext::empty_context(),
+        };
+
+        // Casts without a sequence id are never discarded.
+        for stream_id in [0, 0, 1, 1, 2] {
+            assert_eq!(api.handle(event(stream_id)).await.unwrap(), LinkProcessingResult::FINAL);
+        }
+        assert_eq!(receiver_1.statistics().depth, 4);
     }
 
     struct MockInvocationAPI {
@@ -233,16 +315,17 @@ mod test {
         provider.add_peer(node_id_2, node_2_api).await;
         // let mut api = provider.incomming_api().await;
 
-        let (sender_1, _receiver_1) = futures::channel::mpsc::unbounded::<crate::core::DataplaneEvent>();
+        let (sender_1, _receiver_1, _) = crate::queue::input_queue(&crate::core::EdgelessDataplaneSettings::default());
         let mut link = provider.new_link(fid_source, sender_1).await;
 
         let res = link
             .handle_send(
                 &fid_target,
-                Message::Cast("Test".to_string()),
+                Message::Cast(b"Test".to_vec()),
                 &fid_source,
                 0,
                 edgeless_api::function_instance::PortId("test".to_string()),
+                opentelemetry::trace::SpanContext::empty_context(),
             )
             .await;
         assert_eq!(res, LinkProcessingResult::FINAL);
@@ -251,10 +334,11 @@ mod test {
         let res = link
             .handle_send(
                 &fid_wrong_component_id,
-                Message::Cast("Test".to_string()),
+                Message::Cast(b"Test".to_vec()),
                 &fid_source,
                 0,
                 edgeless_api::function_instance::PortId("test".to_string()),
+                opentelemetry::trace::SpanContext::empty_context(),
             )
             .await;
         assert_eq!(res, LinkProcessingResult::FINAL);
@@ -263,10 +347,11 @@ mod test {
         let res = link
             .handle_send(
                 &fid_wrong_node_id,
-                Message::Cast("Test".to_string()),
+                Message::Cast(b"Test".to_vec()),
                 &fid_source,
                 0,
                 edgeless_api::function_instance::PortId("test".to_string()),
+                opentelemetry::trace::SpanContext::empty_context(),
             )
             .await;
         assert_eq!(res, LinkProcessingResult::PASSED);
@@ -275,13 +360,83 @@ mod test {
         let res = link
             .handle_send(
                 &fid_target,
-                Message::Cast("Test".to_string()),
+                Message::Cast(b"Test".to_vec()),
                 &fid_source,
                 0,
                 edgeless_api::function_instance::PortId("test".to_string()),
+                opentelemetry::trace::SpanContext::empty_context(),
             )
             .await;
         assert_eq!(res, LinkProcessingResult::FINAL);
         assert!(api_receiver_node_2.try_next().unwrap().is_some());
     }
+
+    /// Peer that queues the events but does not handle them until released.
+    struct SlowInvocationAPI {
+        release: std::sync::Arc<tokio::sync::Notify>,
+    }
+
+    #[async_trait::async_trait]
+    impl edgeless_api::invocation::InvocationAPI for SlowInvocationAPI {
+        async fn handle(&mut self, event: edgeless_api::invocation::Event) -> anyhow::Result<LinkProcessingResult> {
+            self.handle_pipelined(event).await.await
+        }
+
+        async fn handle_pipelined(&mut self, _event: edgeless_api::invocation::Event) -> edgeless_api::invocation::PendingResult {
+            let release = self.release.clone();
+            Box::pin(async move {
+                release.notified().await;
+                Ok(LinkProcessingResult::FINAL)
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn only_reliable_casts_wait_for_outcome() {
+        let node_id = uuid::Uuid::new_v4();
+        let node_id_2 = uuid::Uuid::new_v4();
+        let fid_source = edgeless_api::function_instance::InstanceId::new(node_id);
+        let fid_target = edgeless_api::function_instance::InstanceId::new(node_id_2);
+
+        let release = std::sync::Arc::new(tokio::sync::Notify::new());
+        let mut provider = RemoteLinkProvider::new(node_id).await;
+        provider
+            .add_peer(node_id_2, Box::new(SlowInvocationAPI { release: release.clone() }))
+            .await;
+
+        let (sender_1, _receiver_1, _) = crate::queue::input_queue(&crate::core::EdgelessDataplaneSettings::default());
+        let mut link = provider.new_link(fid_source, sender_1).await;
+
+        // A cast without a sequence id returns once queued.
+        let res = tokio::time::timeout(
+            std::time::Duration::from_secs(1),
+            link.handle_send(
+                &fid_target,
+                Message::Cast(b"Test".to_vec()),
+                &fid_source,
+                0,
+                edgeless_api::function_instance::PortId("test".to_string()),
+                opentelemetry::trace::SpanContext::empty_context(),
+            ),
+        )
+        .await;
+        assert_eq!(res.unwrap(), LinkProcessingResult::FINAL);
+
+        // A cast delivered at least once waits until the peer has handled it.
+        let mut reliable = tokio::spawn(async move {
+            link.handle_send(
+                &fid_target,
+                Message::Cast(b"Test".to_vec()),
+                &fid_source,
+                1,
+                edgeless_api::function_instance::PortId("test".to_string()),
+                opentelemetry::trace::SpanContext::empty_context(),
+            )
+            .await
+        });
+        assert!(tokio::time::timeout(std::time::Duration::from_millis(100), &mut reliable).await.is_err());
+        release.notify_waiters();
+        let res = tokio::time::timeout(std::time::Duration::from_secs(1), reliable).await;
+        assert_eq!(res.unwrap().unwrap(), LinkProcessingResult::FINAL);
+    }
 }
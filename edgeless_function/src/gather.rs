@@ -0,0 +1,82 @@
+// SPDX-FileCopyrightText: © 2024 Technical University of Munich, Chair of Connected Mobility
+// SPDX-License-Identifier: MIT
+
+/// Return value of `call_all`.
+pub enum GatherRet {
+    /// The quorum of the targets answered without an error.
+    Quorum(GatheredReplies),
+    /// Too many targets answered with an error or did not answer before the deadline.
+    NoQuorum(GatheredReplies),
+    /// The output is unknown or not mapped to all of its targets.
+    Err,
+}
+
+/// Answers of the targets of a `call_all`, in the order in which they arrived,
+/// followed by the targets that did not answer before the deadline.
+pub struct GatheredReplies {
+    pub(crate) data: Option<crate::owned_data::OwnedByteBuff>,
+}
+
+impl GatheredReplies {
+    pub fn iter(&self) -> GatheredRepliesIter<'_> {
+        GatheredRepliesIter {
+            data: self.data.as_deref().unwrap_or_default(),
+        }
+    }
+}
+
+/// Answer of a single target of a `call_all`.
+pub enum GatheredReply<'a> {
+    NoReply,
+    Reply(&'a [u8]),
+    Err,
+    Timeout,
+}
+
+/// Iterator over the answers encoded by the host, each one as: the node and
+/// component identifiers of the target (16 bytes each), the type of answer
+/// (1 byte, with the same values as the return value of `call_asm`),
+/// the length of the reply (4 bytes, little endian), the reply.
+pub struct GatheredRepliesIter<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> GatheredRepliesIter<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+}
+
+impl<'a> Iterator for GatheredRepliesIter<'a> {
+    type Item = (crate::InstanceId, GatheredReply<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        const HEADER_LEN: usize = 16 + 16 + 1 + 4;
+        if self.data.len() < HEADER_LEN {
+            return None;
+        }
+        let (header, rest) = self.data.split_at(HEADER_LEN);
+        let mut id = crate::InstanceId {
+            node_id: [0; 16],
+            component_id: [0; 16],
+        };
+        id.node_id.copy_from_slice(&header[0..16]);
+        id.component_id.copy_from_slice(&header[16..32]);
+        let mut len = [0; 4];
+        len.copy_from_slice(&header[33..37]);
+        let len = u32::from_le_bytes(len) as usize;
+        if rest.len() < len {
+            return None;
+        }
+        let (reply, rest) = rest.split_at(len);
+        self.data = rest;
+
+        let reply = match header[32] {
+            0 => GatheredReply::NoReply,
+            1 => GatheredReply::Reply(reply),
+            3 => GatheredReply::Timeout,
+            _ => GatheredReply::Err,
+        };
+        Some((id, reply))
+    }
+}
@@ -12,6 +12,14 @@ extern "C" {
         payload_len: usize,
     );
     pub(crate) fn cast_asm(target_ptr: *const u8, target_len: usize, payload_ptr: *const u8, payload_len: usize);
+    pub(crate) fn cast_key_asm(
+        target_ptr: *const u8,
+        target_len: usize,
+        key_ptr: *const u8,
+        key_len: usize,
+        payload_ptr: *const u8,
+        payload_len: usize,
+    );
     pub(crate) fn call_raw_asm(
         instance_node_id_ptr: *const u8,
         instance_component_id_ptr: *const u8,
@@ -30,6 +38,24 @@ extern "C" {
         out_ptr_ptr: *mut *mut u8,
         out_len_ptr: *mut usize,
     ) -> i32;
+    pub(crate) fn call_key_asm(
+        target_ptr: *const u8,
+        target_len: usize,
+        key_ptr: *const u8,
+        key_len: usize,
+        payload_ptr: *const u8,
+        payload_len: usize,
+        out_ptr_ptr: *mut *mut u8,
+        out_len_ptr: *mut usize,
+    ) -> i32;
+    pub(crate) fn call_all_asm(
+        target_ptr: *const u8,
+        target_len: usize,
+        payload_ptr: *const u8,
+        payload_len: usize,
+        out_ptr_ptr: *mut *mut u8,
+        out_len_ptr: *mut usize,
+    ) -> i32;
     pub(crate) fn telemetry_log_asm(level: usize, target_ptr: *const u8, target_len: usize, msg_ptr: *const u8, msg_len: usize);
     pub(crate) fn slf_asm(out_node_id_ptr: *mut u8, out_component_id_ptr: *mut u8);
     pub(crate) fn delayed_cast_asm(delay_ms: u64, target_ptr: *const u8, target_len: usize, payload_ptr: *const u8, payload_len: usize);
@@ -30,10 +30,16 @@ pub mod memory;
 pub mod output_api;
 pub use output_api::*;
 
+/// Provides the return value of the calls on outputs mapped to all of multiple instances.
+pub mod gather;
+pub use gather::*;
+
 pub enum CallRet {
     NoReply,
     Reply(owned_data::OwnedByteBuff),
     Err,
+    /// The callee did not reply before the deadline of the call.
+    Timeout,
 }
 
 #[derive(Clone, Copy)]
@@ -94,4 +100,33 @@ mod test {
 
         assert!(parse_init_payload(",,,a,s,s,,42,").is_empty());
     }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_gathered_replies_iter() {
+        let mut data = Vec::new();
+        for (id, kind, reply) in [(1_u8, 1_u8, &b"hello"[..]), (2, 0, &[]), (3, 3, &[])] {
+            data.extend_from_slice(&[id; 16]);
+            data.extend_from_slice(&[id + 10; 16]);
+            data.push(kind);
+            data.extend_from_slice(&(reply.len() as u32).to_le_bytes());
+            data.extend_from_slice(reply);
+        }
+
+        let mut iter = GatheredRepliesIter::new(&data);
+        match iter.next() {
+            Some((id, GatheredReply::Reply(reply))) => {
+                assert_eq!(id.node_id, [1; 16]);
+                assert_eq!(id.component_id, [11; 16]);
+                assert_eq!(reply, b"hello");
+            }
+            _ => panic!("expected a reply"),
+        }
+        assert!(matches!(iter.next(), Some((_, GatheredReply::NoReply))));
+        assert!(matches!(iter.next(), Some((_, GatheredReply::Timeout))));
+        assert!(iter.next().is_none());
+
+        // A truncated entry is ignored.
+        assert!(GatheredRepliesIter::new(&data[..40]).next().is_none());
+    }
 }
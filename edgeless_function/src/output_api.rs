@@ -20,6 +20,14 @@ pub fn cast(name: &str, msg: &[u8]) {
     }
 }
 
+/// Like `cast`, but if the output is mapped to multiple instances load-balanced
+/// with consistent hashing, all the events with the same key go to the same instance.
+pub fn cast_with_key(name: &str, key: &[u8], msg: &[u8]) {
+    unsafe {
+        crate::imports::cast_key_asm(name.as_bytes().as_ptr(), name.len(), key.as_ptr(), key.len(), msg.as_ptr(), msg.len());
+    }
+}
+
 pub fn delayed_cast(delay_ms: u64, name: &str, msg: &[u8]) {
     unsafe {
         crate::imports::delayed_cast_asm(delay_ms, name.as_bytes().as_ptr(), name.as_bytes().len(), msg.as_ptr(), msg.len());
@@ -45,6 +53,7 @@ pub fn call_raw(target: crate::InstanceId, port: &str, msg: &[u8]) -> crate::Cal
         match call_ret_type {
             0 => crate::CallRet::NoReply,
             1 => crate::CallRet::Reply(crate::owned_data::OwnedByteBuff::new(out_ptr_ptr, out_len_ptr)),
+            3 => crate::CallRet::Timeout,
             _ => crate::CallRet::Err,
         }
     }
@@ -66,11 +75,68 @@ pub fn call(name: &str, msg: &[u8]) -> crate::CallRet {
         match call_ret_type {
             0 => crate::CallRet::NoReply,
             1 => crate::CallRet::Reply(crate::owned_data::OwnedByteBuff::new(out_ptr_ptr, out_len_ptr)),
+            3 => crate::CallRet::Timeout,
+            _ => crate::CallRet::Err,
+        }
+    }
+}
+
+/// Like `call`, but if the output is mapped to multiple instances load-balanced
+/// with consistent hashing, all the calls with the same key go to the same instance.
+pub fn call_with_key(name: &str, key: &[u8], msg: &[u8]) -> crate::CallRet {
+    unsafe {
+        let mut out_ptr_ptr: *mut u8 = core::ptr::null_mut();
+        let mut out_len_ptr: usize = 0;
+        let call_ret_type = crate::imports::call_key_asm(
+            name.as_bytes().as_ptr(),
+            name.len(),
+            key.as_ptr(),
+            key.len(),
+            msg.as_ptr(),
+            msg.len(),
+            &mut out_ptr_ptr as *mut *mut u8,
+            &mut out_len_ptr as *mut usize,
+        );
+
+        match call_ret_type {
+            0 => crate::CallRet::NoReply,
+            1 => crate::CallRet::Reply(crate::owned_data::OwnedByteBuff::new(out_ptr_ptr, out_len_ptr)),
+            3 => crate::CallRet::Timeout,
             _ => crate::CallRet::Err,
         }
     }
 }
 
+/// Call all the targets of an output in parallel and gather their answers, until
+/// the quorum of the output is reached or its deadline elapses.
+pub fn call_all(name: &str, msg: &[u8]) -> crate::GatherRet {
+    unsafe {
+        let mut out_ptr_ptr: *mut u8 = core::ptr::null_mut();
+        let mut out_len_ptr: usize = 0;
+        let call_ret_type = crate::imports::call_all_asm(
+            name.as_bytes().as_ptr(),
+            name.len(),
+            msg.as_ptr(),
+            msg.len(),
+            &mut out_ptr_ptr as *mut *mut u8,
+            &mut out_len_ptr as *mut usize,
+        );
+
+        let replies = || crate::GatheredReplies {
+            data: if out_ptr_ptr.is_null() {
+                None
+            } else {
+                Some(crate::owned_data::OwnedByteBuff::new(out_ptr_ptr, out_len_ptr))
+            },
+        };
+        match call_ret_type {
+            0 => crate::GatherRet::NoQuorum(replies()),
+            1 => crate::GatherRet::Quorum(replies()),
+            _ => crate::GatherRet::Err,
+        }
+    }
+}
+
 pub fn telemetry_log(level: usize, target: &str, msg: &str) {
     unsafe {
         crate::imports::telemetry_log_asm(
@@ -139,7 +139,7 @@ pub fn generate(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
                     }
                 }
                 PortMethod::CALL => {
-                    let (return_type_ident, return_statement) = if let Some(rdt) = output_spec.return_data_type.as_ref() {
+                    let (return_type_ident, return_statement, gathered_reply) = if let Some(rdt) = output_spec.return_data_type.as_ref() {
                         let return_type_name = rdt.replace('.', "_").to_uppercase();
                         let return_type_ident = quote::format_ident!("{}", return_type_name);
                         types
@@ -155,17 +155,32 @@ pub fn generate(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
                             }
                         };
 
-                        (Some(return_type_ident), return_statement)
+                        let gathered_reply = quote! {
+                            if let edgeless_function::GatheredReply::Reply(val) = reply {
+                                Ok(<<#parsed_ident as #trait_name>::#return_type_ident as edgeless_function_core::Deserialize>::deserialize(val))
+                            } else {
+                                Err(())
+                            }
+                        };
+
+                        (Some(return_type_ident), return_statement, gathered_reply)
                     } else {
                         (
                             None,
                             quote! {
                                 return Ok(());
                             },
+                            quote! {
+                                match reply {
+                                    edgeless_function::GatheredReply::NoReply | edgeless_function::GatheredReply::Reply(_) => Ok(()),
+                                    _ => Err(()),
+                                }
+                            },
                         )
                     };
 
                     let handler_ident = quote::format_ident!("call_{}", output_id);
+                    let call_all_ident = quote::format_ident!("call_all_{}", output_id);
                     let rt = if let Some(return_type_ident) = return_type_ident  {
                         quote!{
                             Result<<#parsed_ident as #trait_name>::#return_type_ident, ()>
@@ -188,6 +203,29 @@ pub fn generate(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
                                 return Err(())
                             }
                         }
+
+                        /// Calls all the targets of the output, passing each answer to `handle_reply`.
+                        /// Returns Ok(true) if the quorum of the output has been reached.
+                        #[allow(dead_code)]
+                        fn #call_all_ident(payload: &<#parsed_ident as #trait_name>::#type_ident, mut handle_reply: impl FnMut(edgeless_function::InstanceId, #rt)) -> Result<bool, ()> {
+                            #[cfg(feature = #feature)]
+                            {
+                                let serialized = <<#parsed_ident as #trait_name>::#type_ident as edgeless_function_core::Serialize>::serialize(payload);
+                                let (quorum_reached, replies) = match call_all(#output_id, &serialized) {
+                                    edgeless_function::GatherRet::Quorum(replies) => (true, replies),
+                                    edgeless_function::GatherRet::NoQuorum(replies) => (false, replies),
+                                    edgeless_function::GatherRet::Err => return Err(()),
+                                };
+                                for (id, reply) in replies.iter() {
+                                    handle_reply(id, #gathered_reply);
+                                }
+                                return Ok(quorum_reached);
+                            }
+                            #[cfg(not(feature = #feature))]
+                            {
+                                return Err(())
+                            }
+                        }
                     }
                 }
             }
@@ -279,7 +317,8 @@ pub fn generate(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
             let (ret, output_params) = match ret {
                 CallRet::NoReply => (0, None),
                 CallRet::Reply(reply) => (1, Some(reply.consume())),
-                CallRet::Err => (2, None),
+                // A timeout of a call made by the function is an error for its own caller.
+                CallRet::Err | CallRet::Timeout => (2, None),
             };
             if let (Some((output_ptr, output_len))) = output_params {
                 *out_ptr_ptr = output_ptr;
@@ -99,7 +99,10 @@ fn generate_configs(number_of_nodes: i32) -> Result<InABoxConfig, String> {
     let con_conf = edgeless_con::EdgelessConSettings {
         controller_url,
         // for now only one orchestrator
+        domain_id: String::new(),
         orchestrators: vec![],
+        orchestration_strategy: edgeless_con::OrchestrationStrategy::default(),
+        persistence: None,
     };
 
     // Nodes
@@ -116,11 +119,20 @@ fn generate_configs(number_of_nodes: i32) -> Result<InABoxConfig, String> {
                 invocation_url_announced: "".to_string(),
                 invocation_url_coap: Some(node_coap_invocation_urls.get(node_id).expect("").clone()), // we are sure that it is there
                 invocation_url_announced_coap: Some("".to_string()),
+                invocation_url_quic: None,
+                invocation_url_announced_quic: None,
                 metrics_url: next_url(),
                 controller_url: con_conf.controller_url.clone(),
             },
-            wasm_runtime: Some(edgeless_node::EdgelessNodeWasmRuntimeSettings { enabled: true }),
+            wasm_runtime: Some(edgeless_node::EdgelessNodeWasmRuntimeSettings {
+                enabled: true,
+                module_cache_dir: String::new(),
+                pooling_instances: 0,
+                wasi_dirs_root: String::new(),
+                limits: edgeless_node::base_runtime::limits::FunctionLimits::default(),
+            }),
             container_runtime: None,
+            native_runtime: None,
             resources: Some(EdgelessNodeResourceSettings {
                 http_ingress_url: match first_node {
                     true => Some(next_url()),
@@ -153,6 +165,7 @@ fn generate_configs(number_of_nodes: i32) -> Result<InABoxConfig, String> {
                 ollama_provider: None,
             }),
             user_node_capabilities: None,
+            dataplane: None,
         });
         first_node = false;
     }
@@ -0,0 +1,4 @@
+// SPDX-FileCopyrightText: © 2024 Technical University of Munich, Chair of Connected Mobility
+// SPDX-License-Identifier: MIT
+
+pub const LINK_TYPE: &str = "MEMORY";
@@ -0,0 +1,49 @@
+// SPDX-FileCopyrightText: © 2024 Technical University of Munich, Chair of Connected Mobility
+// SPDX-License-Identifier: MIT
+
+/// Controller of the links whose writers and readers are all on one node.
+pub struct MemoryController {
+    active: std::collections::HashMap<edgeless_api::link::LinkInstanceId, edgeless_api::function_instance::NodeId>,
+}
+
+impl Default for MemoryController {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MemoryController {
+    pub fn new() -> MemoryController {
+        MemoryController {
+            active: std::collections::HashMap::new(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl edgeless_api::link::LinkController for MemoryController {
+    fn new_link(&mut self, nodes: Vec<edgeless_api::function_instance::NodeId>) -> anyhow::Result<edgeless_api::link::LinkInstanceId> {
+        let node = match nodes.first() {
+            Some(node) if nodes.iter().all(|n| n == node) => *node,
+            _ => return Err(anyhow::anyhow!("In-memory links must be on a single node")),
+        };
+        let id = edgeless_api::link::LinkInstanceId(uuid::Uuid::new_v4());
+        self.active.insert(id.clone(), node);
+        Ok(id)
+    }
+
+    fn config_for(&self, link: edgeless_api::link::LinkInstanceId, node: edgeless_api::function_instance::NodeId) -> Option<Vec<u8>> {
+        match self.active.get(&link) {
+            Some(active_node) if *active_node == node => Some(Vec::new()),
+            _ => None,
+        }
+    }
+
+    fn remove_link(&mut self, id: edgeless_api::link::LinkInstanceId) {
+        self.active.remove(&id);
+    }
+
+    async fn instantiate_control_plane(&mut self, _id: edgeless_api::link::LinkInstanceId) {
+        // NOOP
+    }
+}
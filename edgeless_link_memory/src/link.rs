@@ -0,0 +1,99 @@
+// SPDX-FileCopyrightText: © 2024 Technical University of Munich, Chair of Connected Mobility
+// SPDX-License-Identifier: MIT
+
+type Readers = std::sync::Arc<tokio::sync::Mutex<Vec<Box<dyn edgeless_api::link::LinkWriter>>>>;
+
+/// Messages written to the link are passed directly to its readers,
+/// which must be on the same node as the writers.
+#[derive(Clone)]
+struct MemoryWriter {
+    reader: Readers,
+}
+
+#[derive(Clone)]
+pub struct MemoryLink {
+    reader: Readers,
+    writer: Box<MemoryWriter>,
+}
+
+#[derive(Clone)]
+pub struct MemoryProvider {
+    links: std::sync::Arc<tokio::sync::Mutex<std::collections::HashMap<edgeless_api::link::LinkInstanceId, Box<MemoryLink>>>>,
+}
+
+impl Default for MemoryLink {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MemoryLink {
+    pub fn new() -> Self {
+        let reader: Readers = std::sync::Arc::new(tokio::sync::Mutex::new(Vec::new()));
+        MemoryLink {
+            reader: reader.clone(),
+            writer: Box::new(MemoryWriter { reader }),
+        }
+    }
+}
+
+impl Default for MemoryProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MemoryProvider {
+    pub fn new() -> Self {
+        MemoryProvider {
+            links: std::sync::Arc::new(tokio::sync::Mutex::new(std::collections::HashMap::new())),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl edgeless_api::link::LinkProvider for MemoryProvider {
+    fn class(&self) -> edgeless_api::link::LinkType {
+        edgeless_api::link::LinkType(crate::common::LINK_TYPE.to_string())
+    }
+
+    async fn create(&mut self, req: edgeless_api::link::CreateLinkRequest) -> anyhow::Result<Box<dyn edgeless_api::link::LinkInstance>> {
+        let link = Box::new(MemoryLink::new());
+
+        self.links.lock().await.insert(req.id, link.clone());
+
+        Ok(link)
+    }
+    async fn remove(&mut self, id: edgeless_api::link::LinkInstanceId) -> anyhow::Result<()> {
+        self.links.lock().await.remove(&id);
+        Ok(())
+    }
+    async fn register_reader(&mut self, link_id: &edgeless_api::link::LinkInstanceId, reader: Box<dyn edgeless_api::link::LinkWriter>) {
+        if let Some(link) = self.links.lock().await.get_mut(link_id) {
+            link.reader.lock().await.push(reader);
+        }
+    }
+    async fn get_writer(&mut self, link_id: &edgeless_api::link::LinkInstanceId) -> Option<Box<dyn edgeless_api::link::LinkWriter>> {
+        Some(self.links.lock().await.get(link_id)?.writer.clone())
+    }
+}
+
+#[async_trait::async_trait]
+impl edgeless_api::link::LinkInstance for MemoryLink {
+    async fn register_reader(&mut self, reader: Box<dyn edgeless_api::link::LinkWriter>) -> anyhow::Result<()> {
+        self.reader.lock().await.push(reader);
+        Ok(())
+    }
+    async fn get_writer(&mut self) -> Option<Box<dyn edgeless_api::link::LinkWriter>> {
+        Some(self.writer.clone())
+    }
+}
+
+#[async_trait::async_trait]
+impl edgeless_api::link::LinkWriter for MemoryWriter {
+    async fn handle(&mut self, msg: Vec<u8>) {
+        for r in self.reader.lock().await.iter_mut() {
+            r.handle(msg.clone()).await;
+        }
+    }
+}
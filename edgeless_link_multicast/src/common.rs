@@ -1,6 +1,8 @@
 // SPDX-FileCopyrightText: © 2024 Technical University of Munich, Chair of Connected Mobility
 // SPDX-License-Identifier: MIT
 
+pub const LINK_TYPE: &str = "MULTICAST";
+
 #[derive(serde::Serialize, serde::Deserialize)]
 pub struct MulticastConfig {
     pub ip: std::net::Ipv4Addr,
@@ -4,3 +4,4 @@
 pub mod common;
 pub mod controller;
 pub mod link;
+pub mod reliability;
@@ -1,18 +1,44 @@
 // SPDX-FileCopyrightText: © 2024 Technical University of Munich, Chair of Connected Mobility
 // SPDX-License-Identifier: MIT
 
-use futures::FutureExt;
+/// Time between two checks for missing fragments.
+const TICK_INTERVAL: std::time::Duration = std::time::Duration::from_millis(10);
+
+/// Time between two heartbeats of a writer that has sent new messages.
+const HEARTBEAT_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// Size of the socket receive buffer, which absorbs the bursts of fragments.
+const RECV_BUFFER_SIZE: usize = 4 * 1024 * 1024;
+
+/// Counters of a multicast link on this node.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MulticastStatistics {
+    pub messages_sent: u64,
+    pub messages_received: u64,
+    /// Messages given up on after asking for them again too many times.
+    pub messages_lost: u64,
+    pub datagrams_sent: u64,
+    pub datagrams_received: u64,
+    pub datagrams_retransmitted: u64,
+    pub nacks_sent: u64,
+    /// Failures to send or receive a datagram, and malformed datagrams.
+    pub errors: u64,
+}
 
 #[derive(Clone)]
 struct MulticastWriter {
     sender: tokio::sync::mpsc::UnboundedSender<Vec<u8>>,
 }
 
+/// Aborts the task of a link once all the handles to the link are gone.
+struct Task(tokio::task::JoinHandle<()>);
+
 #[derive(Clone)]
 pub struct MulticastLink {
     reader: std::sync::Arc<tokio::sync::Mutex<Vec<Box<dyn edgeless_api::link::LinkWriter>>>>,
     writer: Box<MulticastWriter>,
-    task: std::sync::Arc<tokio::sync::Mutex<tokio::task::JoinHandle<()>>>,
+    statistics: std::sync::Arc<std::sync::Mutex<MulticastStatistics>>,
+    _task: std::sync::Arc<Task>,
 }
 
 #[derive(Clone)]
@@ -21,51 +47,149 @@ pub struct MulticastProvider {
 }
 
 impl MulticastLink {
-    pub fn new(addr: std::net::Ipv4Addr, port: u16) -> Self {
+    pub async fn new(addr: std::net::Ipv4Addr, port: u16) -> anyhow::Result<Self> {
+        if !addr.is_multicast() {
+            return Err(anyhow::anyhow!("Not a multicast address: {}", addr));
+        }
+        let sock_addr = std::net::SocketAddrV4::new(addr, port);
+        let sock = socket2::Socket::new(socket2::Domain::IPV4, socket2::Type::DGRAM, Some(socket2::Protocol::UDP))?;
+        // The nodes running on the same host share the address of the link.
+        sock.set_reuse_address(true)?;
+        sock.set_nonblocking(true)?;
+        if let Err(err) = sock.set_recv_buffer_size(RECV_BUFFER_SIZE) {
+            log::warn!("Cannot enlarge the receive buffer of multicast link {}: {}", sock_addr, err);
+        }
+        sock.bind(&std::net::SocketAddr::from(sock_addr).into())?;
+        let sock = tokio::net::UdpSocket::from_std(sock.into())?;
+        sock.join_multicast_v4(addr, std::net::Ipv4Addr::UNSPECIFIED)?;
+
         let (sender, receiver) = tokio::sync::mpsc::unbounded_channel::<Vec<u8>>();
         let reader: std::sync::Arc<tokio::sync::Mutex<Vec<Box<dyn edgeless_api::link::LinkWriter>>>> =
             std::sync::Arc::new(tokio::sync::Mutex::new(Vec::new()));
+        let statistics = std::sync::Arc::new(std::sync::Mutex::new(MulticastStatistics::default()));
 
-        let reader_clone = reader.clone();
-        let task = tokio::task::spawn(async move {
-            let addr = addr;
-            let sock_addr = std::net::SocketAddrV4::new(addr, port);
-            let mut receiver = receiver;
+        let task = tokio::task::spawn(Self::run(sock, sock_addr, receiver, reader.clone(), statistics.clone()));
 
-            assert!(addr.is_multicast());
+        Ok(MulticastLink {
+            reader,
+            writer: Box::new(MulticastWriter { sender }),
+            statistics,
+            _task: std::sync::Arc::new(Task(task)),
+        })
+    }
+
+    pub fn statistics(&self) -> MulticastStatistics {
+        self.statistics.lock().unwrap().clone()
+    }
 
-            let sock = tokio::net::UdpSocket::bind(sock_addr).await.unwrap();
-            sock.join_multicast_v4(addr, "0.0.0.0".parse().unwrap()).unwrap();
-            let mut buffer = vec![0_u8; 5000];
+    async fn run(
+        sock: tokio::net::UdpSocket,
+        sock_addr: std::net::SocketAddrV4,
+        mut receiver: tokio::sync::mpsc::UnboundedReceiver<Vec<u8>>,
+        reader: std::sync::Arc<tokio::sync::Mutex<Vec<Box<dyn edgeless_api::link::LinkWriter>>>>,
+        statistics: std::sync::Arc<std::sync::Mutex<MulticastStatistics>>,
+    ) {
+        let mut transmitter = crate::reliability::Transmitter::new(uuid::Uuid::new_v4().as_u64_pair().0);
+        let mut reassembler = crate::reliability::Reassembler::default();
+        let mut buffer = vec![0_u8; 65536];
+        let mut tick = tokio::time::interval(TICK_INTERVAL);
+        let mut last_heartbeat = tokio::time::Instant::now();
 
-            loop {
-                tokio::select! {
-                    outgoing = Box::pin(receiver.recv()).fuse() => {
-                        if let Some(outgoing) = outgoing {
-                            sock.send_to(&outgoing[..], sock_addr).await.unwrap();
+        // Send the datagrams, counting the failures.
+        let send = |datagrams: Vec<Vec<u8>>| {
+            let sock = &sock;
+            let statistics = &statistics;
+            async move {
+                for datagram in datagrams {
+                    match sock.send_to(&datagram[..], sock_addr).await {
+                        Ok(_) => {
+                            statistics.lock().unwrap().datagrams_sent += 1;
                         }
-                    },
-                    incomming = Box::pin(sock.recv_from(&mut buffer[..])).fuse() => {
-                        match incomming {
-                            Ok((data_size, _sender)) => {
-                                for r in reader_clone.lock().await.iter_mut() {
-                                    let data = Vec::from(&buffer[0..data_size]);
-                                    r.handle(data).await;
-                                }
-                            },
-                            Err(err) => {
-                                log::error!("{}", err);
-                            },
+                        Err(err) => {
+                            log::warn!("Cannot send to multicast link {}: {}", sock_addr, err);
+                            statistics.lock().unwrap().errors += 1;
                         }
                     }
                 }
             }
-        });
+        };
 
-        MulticastLink {
-            reader,
-            writer: Box::new(MulticastWriter { sender }),
-            task: std::sync::Arc::new(tokio::sync::Mutex::new(task)),
+        loop {
+            let delivered = tokio::select! {
+                outgoing = receiver.recv() => {
+                    let outgoing = match outgoing {
+                        Some(outgoing) => outgoing,
+                        None => break,
+                    };
+                    match transmitter.send(&outgoing) {
+                        Ok(datagrams) => {
+                            statistics.lock().unwrap().messages_sent += 1;
+                            send(datagrams).await;
+                        }
+                        Err(err) => {
+                            log::warn!("Cannot send to multicast link {}: {}", sock_addr, err);
+                            statistics.lock().unwrap().errors += 1;
+                        }
+                    }
+                    vec![]
+                },
+                incomming = sock.recv_from(&mut buffer[..]) => {
+                    let data_size = match incomming {
+                        Ok((data_size, _sender)) => data_size,
+                        Err(err) => {
+                            log::warn!("Cannot receive from multicast link {}: {}", sock_addr, err);
+                            statistics.lock().unwrap().errors += 1;
+                            continue;
+                        }
+                    };
+                    statistics.lock().unwrap().datagrams_received += 1;
+                    match crate::reliability::Packet::decode(&buffer[0..data_size]) {
+                        Ok(crate::reliability::Packet::Data { sender, seq, index, count, payload }) => {
+                            reassembler.handle_data(sender, seq, index, count, payload, std::time::Instant::now())
+                        }
+                        Ok(crate::reliability::Packet::Nack { sender, seq, fragments }) => {
+                            if sender == transmitter.id() {
+                                let datagrams = transmitter.retransmit(seq, &fragments);
+                                statistics.lock().unwrap().datagrams_retransmitted += datagrams.len() as u64;
+                                send(datagrams).await;
+                            }
+                            vec![]
+                        }
+                        Ok(crate::reliability::Packet::Heartbeat { sender, last_seq }) => {
+                            reassembler.handle_heartbeat(sender, last_seq, std::time::Instant::now());
+                            vec![]
+                        }
+                        Err(err) => {
+                            log::debug!("Discarding datagram on multicast link {}: {}", sock_addr, err);
+                            statistics.lock().unwrap().errors += 1;
+                            vec![]
+                        }
+                    }
+                },
+                _ = tick.tick() => {
+                    let (nacks, delivered) = reassembler.poll(std::time::Instant::now());
+                    statistics.lock().unwrap().nacks_sent += nacks.len() as u64;
+                    send(nacks).await;
+                    if last_heartbeat.elapsed() >= HEARTBEAT_INTERVAL {
+                        last_heartbeat = tokio::time::Instant::now();
+                        if let Some(heartbeat) = transmitter.heartbeat() {
+                            send(vec![heartbeat]).await;
+                        }
+                    }
+                    delivered
+                }
+            };
+
+            {
+                let mut statistics = statistics.lock().unwrap();
+                statistics.messages_received += delivered.len() as u64;
+                statistics.messages_lost = reassembler.lost();
+            }
+            for msg in delivered {
+                for r in reader.lock().await.iter_mut() {
+                    r.handle(msg.clone()).await;
+                }
+            }
         }
     }
 }
@@ -84,45 +208,47 @@ impl MulticastProvider {
     }
 }
 
-impl Drop for MulticastLink {
+impl MulticastProvider {
+    /// Counters of a link on this node, if it exists.
+    pub async fn statistics(&self, link_id: &edgeless_api::link::LinkInstanceId) -> Option<MulticastStatistics> {
+        Some(self.links.lock().await.get(link_id)?.statistics())
+    }
+}
+
+impl Drop for Task {
     fn drop(&mut self) {
-        self.task.blocking_lock().abort();
+        self.0.abort();
     }
 }
 
 #[async_trait::async_trait]
 impl edgeless_api::link::LinkProvider for MulticastProvider {
     fn class(&self) -> edgeless_api::link::LinkType {
-        edgeless_api::link::LinkType("MULTICAST".to_string())
+        edgeless_api::link::LinkType(crate::common::LINK_TYPE.to_string())
     }
 
     async fn create(&mut self, req: edgeless_api::link::CreateLinkRequest) -> anyhow::Result<Box<dyn edgeless_api::link::LinkInstance>> {
-        let cfg: crate::common::MulticastConfig = serde_json::from_slice(&req.config).unwrap();
+        let cfg: crate::common::MulticastConfig = serde_json::from_slice(&req.config)?;
 
-        let link = Box::new(MulticastLink::new(cfg.ip, cfg.port));
+        let link = Box::new(MulticastLink::new(cfg.ip, cfg.port).await?);
 
         self.links.lock().await.insert(req.id, link.clone());
 
-        return Ok(link);
+        Ok(link)
     }
     async fn remove(&mut self, id: edgeless_api::link::LinkInstanceId) -> anyhow::Result<()> {
-        self.links.lock().await.remove(&id);
+        if let Some(link) = self.links.lock().await.remove(&id) {
+            log::debug!("Removed multicast link {:?}: {:?}", id, link.statistics());
+        }
         Ok(())
     }
     async fn register_reader(&mut self, link_id: &edgeless_api::link::LinkInstanceId, reader: Box<dyn edgeless_api::link::LinkWriter>) {
-        self.links
-            .lock()
-            .await
-            .get_mut(link_id)
-            .unwrap()
-            .as_mut()
-            .reader
-            .lock()
-            .await
-            .push(reader);
+        if let Some(link) = self.links.lock().await.get_mut(link_id) {
+            link.reader.lock().await.push(reader);
+        }
     }
     async fn get_writer(&mut self, link_id: &edgeless_api::link::LinkInstanceId) -> Option<Box<dyn edgeless_api::link::LinkWriter>> {
-        Some(self.links.lock().await.get_mut(link_id).unwrap().as_mut().writer.clone())
+        Some(self.links.lock().await.get(link_id)?.writer.clone())
     }
 }
 
@@ -140,6 +266,8 @@ impl edgeless_api::link::LinkInstance for MulticastLink {
 #[async_trait::async_trait]
 impl edgeless_api::link::LinkWriter for MulticastWriter {
     async fn handle(&mut self, msg: Vec<u8>) {
-        self.sender.send(msg).unwrap();
+        if self.sender.send(msg).is_err() {
+            log::warn!("Multicast link closed, message discarded");
+        }
     }
 }
@@ -0,0 +1,493 @@
+// SPDX-FileCopyrightText: © 2024 Technical University of Munich, Chair of Connected Mobility
+// SPDX-License-Identifier: MIT
+
+//! Fragmentation and NACK-based retransmission of the messages of a
+//! multicast link. Every writer numbers its messages and splits them into
+//! datagrams that fit in a packet; the readers reassemble the messages,
+//! deliver them in order, and ask the writer again for the missing
+//! fragments until they give up on a message.
+
+/// Largest payload of a datagram, which fits in an Ethernet frame with the headers.
+pub const MAX_FRAGMENT_PAYLOAD: usize = 1200;
+
+/// Time without progress on a message before its missing fragments are requested.
+pub const NACK_INTERVAL: std::time::Duration = std::time::Duration::from_millis(50);
+
+/// Number of requests for the missing fragments of a message before it is considered lost.
+pub const MAX_NACKS: u32 = 8;
+
+/// Number of messages, and of their bytes, that a writer keeps to send them again.
+const HISTORY_MESSAGES: usize = 1024;
+const HISTORY_BYTES: usize = 16 * 1024 * 1024;
+
+/// Messages that a reader waits for at most, beyond which the oldest ones are lost.
+const MAX_PENDING: u64 = 4096;
+
+/// Largest number of fragments requested by a single NACK, so that it fits in a datagram.
+const MAX_NACKED_FRAGMENTS: usize = 512;
+
+const KIND_DATA: u8 = 0;
+const KIND_NACK: u8 = 1;
+const KIND_HEARTBEAT: u8 = 2;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Packet<'a> {
+    /// Fragment of message `seq` of the writer `sender`.
+    Data {
+        sender: u64,
+        seq: u64,
+        index: u16,
+        count: u16,
+        payload: &'a [u8],
+    },
+    /// Request for fragments of message `seq` of the writer `sender`,
+    /// or for all of them if none is given.
+    Nack { sender: u64, seq: u64, fragments: Vec<u16> },
+    /// Last message of the writer `sender`, which lets the readers detect
+    /// that the messages at the end of a burst were lost.
+    Heartbeat { sender: u64, last_seq: u64 },
+}
+
+impl<'a> Packet<'a> {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        match self {
+            Packet::Data {
+                sender,
+                seq,
+                index,
+                count,
+                payload,
+            } => {
+                buf.reserve(21 + payload.len());
+                buf.push(KIND_DATA);
+                buf.extend_from_slice(&sender.to_be_bytes());
+                buf.extend_from_slice(&seq.to_be_bytes());
+                buf.extend_from_slice(&index.to_be_bytes());
+                buf.extend_from_slice(&count.to_be_bytes());
+                buf.extend_from_slice(payload);
+            }
+            Packet::Nack { sender, seq, fragments } => {
+                buf.push(KIND_NACK);
+                buf.extend_from_slice(&sender.to_be_bytes());
+                buf.extend_from_slice(&seq.to_be_bytes());
+                buf.extend_from_slice(&(fragments.len() as u16).to_be_bytes());
+                for fragment in fragments {
+                    buf.extend_from_slice(&fragment.to_be_bytes());
+                }
+            }
+            Packet::Heartbeat { sender, last_seq } => {
+                buf.push(KIND_HEARTBEAT);
+                buf.extend_from_slice(&sender.to_be_bytes());
+                buf.extend_from_slice(&last_seq.to_be_bytes());
+            }
+        }
+        buf
+    }
+
+    pub fn decode(data: &'a [u8]) -> anyhow::Result<Self> {
+        let u16_at = |pos: usize| data.get(pos..pos + 2).map(|b| u16::from_be_bytes([b[0], b[1]]));
+        let u64_at = |pos: usize| data.get(pos..pos + 8).map(|b| u64::from_be_bytes(b.try_into().unwrap()));
+        let malformed = || anyhow::anyhow!("Malformed multicast datagram of {} bytes", data.len());
+
+        let sender = u64_at(1).ok_or_else(malformed)?;
+        match data[0] {
+            KIND_DATA => Ok(Packet::Data {
+                sender,
+                seq: u64_at(9).ok_or_else(malformed)?,
+                index: u16_at(17).ok_or_else(malformed)?,
+                count: u16_at(19).ok_or_else(malformed)?,
+                payload: &data[21..],
+            }),
+            KIND_NACK => {
+                let seq = u64_at(9).ok_or_else(malformed)?;
+                let len = u16_at(17).ok_or_else(malformed)? as usize;
+                let fragments = (0..len).map(|i| u16_at(19 + 2 * i)).collect::<Option<Vec<_>>>().ok_or_else(malformed)?;
+                Ok(Packet::Nack { sender, seq, fragments })
+            }
+            KIND_HEARTBEAT => Ok(Packet::Heartbeat {
+                sender,
+                last_seq: u64_at(9).ok_or_else(malformed)?,
+            }),
+            kind => Err(anyhow::anyhow!("Unknown multicast datagram kind {}", kind)),
+        }
+    }
+}
+
+/// Writer side: numbers and fragments the messages, keeping the most
+/// recent ones to send them again.
+pub struct Transmitter {
+    id: u64,
+    next_seq: u64,
+    heartbeat_seq: u64,
+    history: std::collections::VecDeque<(u64, Vec<Vec<u8>>)>,
+    history_bytes: usize,
+}
+
+impl Transmitter {
+    pub fn new(id: u64) -> Self {
+        Self {
+            id,
+            next_seq: 1,
+            heartbeat_seq: 0,
+            history: std::collections::VecDeque::new(),
+            history_bytes: 0,
+        }
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Datagrams carrying a new message.
+    pub fn send(&mut self, msg: &[u8]) -> anyhow::Result<Vec<Vec<u8>>> {
+        let count = std::cmp::max(1, msg.len().div_ceil(MAX_FRAGMENT_PAYLOAD));
+        let count: u16 = count
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("Message too large for a multicast link: {} bytes", msg.len()))?;
+
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        let datagrams: Vec<_> = (0..count)
+            .map(|index| {
+                let start = index as usize * MAX_FRAGMENT_PAYLOAD;
+                let end = std::cmp::min(start + MAX_FRAGMENT_PAYLOAD, msg.len());
+                Packet::Data {
+                    sender: self.id,
+                    seq,
+                    index,
+                    count,
+                    payload: &msg[start..end],
+                }
+                .encode()
+            })
+            .collect();
+
+        self.history_bytes += datagrams.iter().map(|d| d.len()).sum::<usize>();
+        self.history.push_back((seq, datagrams.clone()));
+        while self.history.len() > HISTORY_MESSAGES || (self.history_bytes > HISTORY_BYTES && self.history.len() > 1) {
+            if let Some((_, old)) = self.history.pop_front() {
+                self.history_bytes -= old.iter().map(|d| d.len()).sum::<usize>();
+            }
+        }
+
+        Ok(datagrams)
+    }
+
+    /// Datagrams requested by a NACK, if the message is still in the history.
+    pub fn retransmit(&self, seq: u64, fragments: &[u16]) -> Vec<Vec<u8>> {
+        let first = match self.history.front() {
+            Some((first, _)) if seq >= *first => *first,
+            _ => return vec![],
+        };
+        match self.history.get((seq - first) as usize) {
+            Some((_, datagrams)) if fragments.is_empty() => datagrams.clone(),
+            Some((_, datagrams)) => fragments.iter().filter_map(|f| datagrams.get(*f as usize)).cloned().collect(),
+            None => vec![],
+        }
+    }
+
+    /// Heartbeat to send, if messages were sent after the previous one.
+    pub fn heartbeat(&mut self) -> Option<Vec<u8>> {
+        let last_seq = self.next_seq - 1;
+        if last_seq == self.heartbeat_seq {
+            return None;
+        }
+        self.heartbeat_seq = last_seq;
+        Some(Packet::Heartbeat { sender: self.id, last_seq }.encode())
+    }
+}
+
+/// Message of which some fragments are still missing.
+struct Partial {
+    /// Empty until the first fragment tells the number of fragments.
+    fragments: Vec<Option<Vec<u8>>>,
+    received: usize,
+    last_progress: std::time::Instant,
+    nacks: u32,
+}
+
+impl Partial {
+    fn new(now: std::time::Instant) -> Self {
+        Self {
+            fragments: Vec::new(),
+            received: 0,
+            last_progress: now,
+            nacks: 0,
+        }
+    }
+
+    fn is_complete(&self) -> bool {
+        !self.fragments.is_empty() && self.received == self.fragments.len()
+    }
+}
+
+/// Messages of a writer not delivered yet.
+struct Stream {
+    /// Next message to deliver.
+    next_seq: u64,
+    /// First message whose existence is not known yet.
+    known_until: u64,
+    pending: std::collections::BTreeMap<u64, Partial>,
+}
+
+impl Stream {
+    /// Learn that the messages before `end` exist, returning the number
+    /// of messages lost because too many would be waiting.
+    fn learn_until(&mut self, end: u64, now: std::time::Instant) -> u64 {
+        let mut lost = 0;
+        if end > self.known_until {
+            let start = std::cmp::max(self.known_until, end.saturating_sub(MAX_PENDING));
+            lost += start - self.known_until;
+            for seq in start..end {
+                self.pending.insert(seq, Partial::new(now));
+            }
+            self.known_until = end;
+        }
+        if self.known_until - self.next_seq > MAX_PENDING {
+            self.next_seq = self.known_until - MAX_PENDING;
+            let kept = self.pending.split_off(&self.next_seq);
+            lost += self.pending.len() as u64;
+            self.pending = kept;
+        }
+        lost
+    }
+
+    /// Complete messages that can be delivered in order, skipping the lost ones.
+    fn deliver(&mut self) -> Vec<Vec<u8>> {
+        let mut delivered = Vec::new();
+        while self.next_seq < self.known_until {
+            match self.pending.get(&self.next_seq) {
+                Some(partial) if !partial.is_complete() => break,
+                Some(_) => {
+                    let partial = self.pending.remove(&self.next_seq).unwrap();
+                    delivered.push(partial.fragments.into_iter().flatten().flatten().collect());
+                }
+                None => {}
+            }
+            self.next_seq += 1;
+        }
+        delivered
+    }
+}
+
+/// Reader side: reassembles the messages of all the writers of the link.
+#[derive(Default)]
+pub struct Reassembler {
+    streams: std::collections::HashMap<u64, Stream>,
+    lost: u64,
+}
+
+impl Reassembler {
+    /// Add a fragment, returning the messages that can now be delivered.
+    pub fn handle_data(&mut self, sender: u64, seq: u64, index: u16, count: u16, payload: &[u8], now: std::time::Instant) -> Vec<Vec<u8>> {
+        // The readers joining late do not ask for the previous messages.
+        let stream = self.streams.entry(sender).or_insert_with(|| Stream {
+            next_seq: seq,
+            known_until: seq,
+            pending: std::collections::BTreeMap::new(),
+        });
+        if seq < stream.next_seq || index >= count {
+            return vec![];
+        }
+
+        self.lost += stream.learn_until(seq + 1, now);
+        let partial = stream.pending.get_mut(&seq).unwrap();
+        if partial.fragments.is_empty() {
+            partial.fragments = vec![None; count as usize];
+        }
+        if partial.fragments.len() == count as usize && partial.fragments[index as usize].is_none() {
+            partial.fragments[index as usize] = Some(payload.to_vec());
+            partial.received += 1;
+            partial.last_progress = now;
+        }
+
+        stream.deliver()
+    }
+
+    pub fn handle_heartbeat(&mut self, sender: u64, last_seq: u64, now: std::time::Instant) {
+        let stream = self.streams.entry(sender).or_insert_with(|| Stream {
+            next_seq: last_seq + 1,
+            known_until: last_seq + 1,
+            pending: std::collections::BTreeMap::new(),
+        });
+        self.lost += stream.learn_until(last_seq + 1, now);
+    }
+
+    /// NACKs for the messages without progress for a while, and the
+    /// messages that can be delivered after giving up on the lost ones.
+    pub fn poll(&mut self, now: std::time::Instant) -> (Vec<Vec<u8>>, Vec<Vec<u8>>) {
+        let mut nacks = Vec::new();
+        let mut delivered = Vec::new();
+        for (sender, stream) in &mut self.streams {
+            let mut gave_up = false;
+            stream.pending.retain(|seq, partial| {
+                if partial.is_complete() || now.duration_since(partial.last_progress) < NACK_INTERVAL {
+                    return true;
+                }
+                if partial.nacks >= MAX_NACKS {
+                    gave_up = true;
+                    self.lost += 1;
+                    return false;
+                }
+                let fragments = partial
+                    .fragments
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, f)| f.is_none())
+                    .map(|(i, _)| i as u16)
+                    .take(MAX_NACKED_FRAGMENTS)
+                    .collect();
+                nacks.push(
+                    Packet::Nack {
+                        sender: *sender,
+                        seq: *seq,
+                        fragments,
+                    }
+                    .encode(),
+                );
+                partial.nacks += 1;
+                partial.last_progress = now;
+                true
+            });
+            if gave_up {
+                delivered.append(&mut stream.deliver());
+            }
+        }
+        (nacks, delivered)
+    }
+
+    /// Number of messages given up on.
+    pub fn lost(&self) -> u64 {
+        self.lost
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn receive_all(reassembler: &mut Reassembler, datagrams: &[Vec<u8>], now: std::time::Instant) -> Vec<Vec<u8>> {
+        let mut delivered = Vec::new();
+        for datagram in datagrams {
+            if let Packet::Data {
+                sender,
+                seq,
+                index,
+                count,
+                payload,
+            } = Packet::decode(datagram).unwrap()
+            {
+                delivered.append(&mut reassembler.handle_data(sender, seq, index, count, payload, now));
+            }
+        }
+        delivered
+    }
+
+    #[test]
+    fn encode_decode() {
+        let packets = vec![
+            Packet::Data {
+                sender: 1,
+                seq: 2,
+                index: 3,
+                count: 4,
+                payload: b"abc",
+            },
+            Packet::Nack {
+                sender: 5,
+                seq: 6,
+                fragments: vec![7, 8],
+            },
+            Packet::Heartbeat { sender: 9, last_seq: 10 },
+        ];
+        for packet in packets {
+            assert_eq!(Packet::decode(&packet.encode()).unwrap(), packet);
+        }
+        assert!(Packet::decode(&[KIND_DATA, 0, 1]).is_err());
+        assert!(Packet::decode(&[]).is_err());
+    }
+
+    #[test]
+    fn fragments_reassembled_in_order() {
+        let now = std::time::Instant::now();
+        let mut transmitter = Transmitter::new(42);
+        let large: Vec<u8> = (0..3 * MAX_FRAGMENT_PAYLOAD + 10).map(|i| i as u8).collect();
+        let first = transmitter.send(&large).unwrap();
+        let second = transmitter.send(b"small").unwrap();
+        let empty = transmitter.send(b"").unwrap();
+        assert_eq!(first.len(), 4);
+        assert_eq!(second.len(), 1);
+        assert_eq!(empty.len(), 1);
+
+        let mut reassembler = Reassembler::default();
+        assert!(receive_all(&mut reassembler, &first[..1], now).is_empty());
+        // The second message waits for the first one.
+        assert!(receive_all(&mut reassembler, &second, now).is_empty());
+        let mut reversed = first[1..].to_vec();
+        reversed.reverse();
+        assert_eq!(receive_all(&mut reassembler, &reversed, now), vec![large, b"small".to_vec()]);
+        assert_eq!(receive_all(&mut reassembler, &empty, now), vec![Vec::<u8>::new()]);
+        // Duplicates are discarded.
+        assert!(receive_all(&mut reassembler, &second, now).is_empty());
+    }
+
+    #[test]
+    fn lost_fragments_retransmitted() {
+        let now = std::time::Instant::now();
+        let mut transmitter = Transmitter::new(42);
+        let large = vec![7_u8; 2 * MAX_FRAGMENT_PAYLOAD];
+        let first = transmitter.send(b"first").unwrap();
+        let second = transmitter.send(&large).unwrap();
+        let third = transmitter.send(b"third").unwrap();
+
+        let mut reassembler = Reassembler::default();
+        assert_eq!(receive_all(&mut reassembler, &first, now), vec![b"first".to_vec()]);
+        // The second fragment of the second message and the third message are lost.
+        assert!(receive_all(&mut reassembler, &second[..1], now).is_empty());
+        reassembler.handle_heartbeat(42, 3, now);
+
+        let (nacks, delivered) = reassembler.poll(now);
+        assert!(nacks.is_empty());
+        assert!(delivered.is_empty());
+
+        let later = now + NACK_INTERVAL;
+        let (nacks, _) = reassembler.poll(later);
+        let mut retransmitted = Vec::new();
+        for nack in &nacks {
+            if let Packet::Nack { sender, seq, fragments } = Packet::decode(nack).unwrap() {
+                assert_eq!(sender, 42);
+                retransmitted.append(&mut transmitter.retransmit(seq, &fragments));
+            }
+        }
+        assert_eq!(retransmitted, vec![second[1].clone(), third[0].clone()]);
+        assert_eq!(receive_all(&mut reassembler, &retransmitted, later), vec![large, b"third".to_vec()]);
+        assert_eq!(reassembler.lost(), 0);
+        assert!(transmitter.heartbeat().is_some());
+        assert!(transmitter.heartbeat().is_none());
+    }
+
+    #[test]
+    fn message_lost_after_max_nacks() {
+        let mut now = std::time::Instant::now();
+        let mut transmitter = Transmitter::new(1);
+        let first = transmitter.send(b"first").unwrap();
+        let _lost = transmitter.send(b"lost").unwrap();
+        let third = transmitter.send(b"third").unwrap();
+
+        let mut reassembler = Reassembler::default();
+        receive_all(&mut reassembler, &first, now);
+        assert!(receive_all(&mut reassembler, &third, now).is_empty());
+        for _ in 0..MAX_NACKS {
+            now += NACK_INTERVAL;
+            let (nacks, delivered) = reassembler.poll(now);
+            assert_eq!(nacks.len(), 1);
+            assert!(delivered.is_empty());
+        }
+        now += NACK_INTERVAL;
+        let (nacks, delivered) = reassembler.poll(now);
+        assert!(nacks.is_empty());
+        assert_eq!(delivered, vec![b"third".to_vec()]);
+        assert_eq!(reassembler.lost(), 1);
+    }
+}
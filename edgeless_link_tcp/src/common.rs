@@ -0,0 +1,14 @@
+// SPDX-FileCopyrightText: © 2024 Technical University of Munich, Chair of Connected Mobility
+// SPDX-License-Identifier: MIT
+
+pub const LINK_TYPE: &str = "TCP";
+
+/// Configuration of a node of a TCP link. Each pair of nodes of the link
+/// shares a single connection, opened by the node that comes first.
+#[derive(Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct TcpConfig {
+    /// Port on which the node accepts the connections of the other nodes, if any.
+    pub listen_port: Option<u16>,
+    /// Host and port of the nodes to which this node connects.
+    pub peers: Vec<(String, u16)>,
+}
@@ -0,0 +1,125 @@
+// SPDX-FileCopyrightText: © 2024 Technical University of Munich, Chair of Connected Mobility
+// SPDX-License-Identifier: MIT
+
+struct ActiveTcpLink {
+    /// Nodes of the link, each one connecting to the nodes that follow it.
+    nodes: Vec<edgeless_api::function_instance::NodeId>,
+    /// Port on which each node, except the first one, accepts connections.
+    ports: std::collections::HashMap<edgeless_api::function_instance::NodeId, u16>,
+}
+
+pub struct TcpController {
+    pool_free: Vec<u16>,
+    hosts: std::collections::HashMap<edgeless_api::function_instance::NodeId, String>,
+    active: std::collections::HashMap<edgeless_api::link::LinkInstanceId, ActiveTcpLink>,
+}
+
+impl Default for TcpController {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TcpController {
+    pub fn new() -> TcpController {
+        TcpController {
+            pool_free: std::ops::Range { start: 9100, end: 9400 }.rev().collect(),
+            hosts: std::collections::HashMap::new(),
+            active: std::collections::HashMap::new(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl edgeless_api::link::LinkController for TcpController {
+    fn new_link(&mut self, nodes: Vec<edgeless_api::function_instance::NodeId>) -> anyhow::Result<edgeless_api::link::LinkInstanceId> {
+        let mut nodes = nodes;
+        nodes.sort();
+        nodes.dedup();
+
+        if let Some(unknown) = nodes.iter().find(|n| !self.hosts.contains_key(*n)) {
+            return Err(anyhow::anyhow!("Unknown address of node {}", unknown));
+        }
+        if self.pool_free.len() < nodes.len().saturating_sub(1) {
+            return Err(anyhow::anyhow!("No Capacity"));
+        }
+
+        let ports = nodes.iter().skip(1).map(|n| (*n, self.pool_free.pop().unwrap())).collect();
+        let id = edgeless_api::link::LinkInstanceId(uuid::Uuid::new_v4());
+        self.active.insert(id.clone(), ActiveTcpLink { nodes, ports });
+        Ok(id)
+    }
+
+    fn config_for(&self, link: edgeless_api::link::LinkInstanceId, node: edgeless_api::function_instance::NodeId) -> Option<Vec<u8>> {
+        let active_link = self.active.get(&link)?;
+        let pos = active_link.nodes.iter().position(|n| *n == node)?;
+        let cfg = crate::common::TcpConfig {
+            listen_port: active_link.ports.get(&node).copied(),
+            peers: active_link.nodes[pos + 1..]
+                .iter()
+                .filter_map(|n| Some((self.hosts.get(n)?.clone(), *active_link.ports.get(n)?)))
+                .collect(),
+        };
+        Some(serde_json::to_string(&cfg).unwrap().into_bytes())
+    }
+
+    fn remove_link(&mut self, id: edgeless_api::link::LinkInstanceId) {
+        if let Some(active) = self.active.remove(&id) {
+            self.pool_free.extend(active.ports.into_values());
+        }
+    }
+
+    async fn instantiate_control_plane(&mut self, _id: edgeless_api::link::LinkInstanceId) {
+        // NOOP
+    }
+
+    fn add_node(&mut self, node: edgeless_api::function_instance::NodeId, invocation_url: &str) {
+        match edgeless_api::util::parse_http_host(invocation_url) {
+            Ok((_, host, _)) => {
+                self.hosts.insert(node, host);
+            }
+            Err(err) => log::warn!("Cannot use TCP links with node {}: {}", node, err),
+        }
+    }
+
+    fn remove_node(&mut self, node: edgeless_api::function_instance::NodeId) {
+        self.hosts.remove(&node);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use edgeless_api::link::LinkController;
+
+    #[test]
+    fn config_of_each_node() {
+        let mut nodes: Vec<_> = (0..3).map(|_| uuid::Uuid::new_v4()).collect();
+        nodes.sort();
+
+        let mut controller = super::TcpController::new();
+        assert!(controller.new_link(nodes.clone()).is_err());
+        for (i, node) in nodes.iter().enumerate() {
+            controller.add_node(*node, &format!("http://10.0.0.{}:7002", i));
+        }
+
+        let link = controller.new_link(vec![nodes[2], nodes[0], nodes[1], nodes[0]]).unwrap();
+        let configs: Vec<crate::common::TcpConfig> = nodes
+            .iter()
+            .map(|n| serde_json::from_slice(&controller.config_for(link.clone(), *n).unwrap()).unwrap())
+            .collect();
+        assert_eq!(configs[0].listen_port, None);
+        assert!(configs[2].peers.is_empty());
+
+        let port_1 = configs[1].listen_port.unwrap();
+        let port_2 = configs[2].listen_port.unwrap();
+        assert_ne!(port_1, port_2);
+        assert_eq!(configs[0].peers, vec![("10.0.0.1".to_string(), port_1), ("10.0.0.2".to_string(), port_2)]);
+        assert_eq!(configs[1].peers, vec![("10.0.0.2".to_string(), port_2)]);
+        assert!(controller.config_for(link.clone(), uuid::Uuid::new_v4()).is_none());
+
+        let free = controller.pool_free.len();
+        controller.remove_link(link.clone());
+        assert_eq!(controller.pool_free.len(), free + 2);
+        assert!(controller.config_for(link, nodes[0]).is_none());
+    }
+}
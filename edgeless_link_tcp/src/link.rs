@@ -0,0 +1,282 @@
+// SPDX-FileCopyrightText: © 2024 Technical University of Munich, Chair of Connected Mobility
+// SPDX-License-Identifier: MIT
+
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncWriteExt;
+
+/// Time between the attempts to (re-)connect to a peer of the link.
+const RECONNECT_INTERVAL: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// Largest message accepted from a peer.
+const MAX_FRAME_SIZE: usize = 16 * 1024 * 1024;
+
+type Readers = std::sync::Arc<tokio::sync::Mutex<Vec<Box<dyn edgeless_api::link::LinkWriter>>>>;
+
+/// Senders of the messages to the peers currently connected.
+#[derive(Default)]
+struct Connections {
+    next_id: u64,
+    senders: std::collections::HashMap<u64, tokio::sync::mpsc::UnboundedSender<Vec<u8>>>,
+}
+
+/// Messages written to the link are delivered to the local readers and
+/// to the peers currently connected. Messages to disconnected peers are lost.
+#[derive(Clone)]
+struct TcpWriter {
+    reader: Readers,
+    connections: std::sync::Arc<tokio::sync::Mutex<Connections>>,
+}
+
+/// Aborts the tasks of a link once all the handles to the link are gone.
+struct Tasks(Vec<tokio::task::JoinHandle<()>>);
+
+#[derive(Clone)]
+pub struct TcpLink {
+    reader: Readers,
+    writer: Box<TcpWriter>,
+    _tasks: std::sync::Arc<Tasks>,
+}
+
+#[derive(Clone)]
+pub struct TcpProvider {
+    links: std::sync::Arc<tokio::sync::Mutex<std::collections::HashMap<edgeless_api::link::LinkInstanceId, Box<TcpLink>>>>,
+}
+
+impl TcpLink {
+    pub fn new(config: crate::common::TcpConfig) -> Self {
+        let reader: Readers = std::sync::Arc::new(tokio::sync::Mutex::new(Vec::new()));
+        let connections = std::sync::Arc::new(tokio::sync::Mutex::new(Connections::default()));
+        let mut tasks = Vec::new();
+
+        if let Some(port) = config.listen_port {
+            let reader = reader.clone();
+            let connections = connections.clone();
+            tasks.push(tokio::spawn(async move {
+                let listener = match tokio::net::TcpListener::bind(("0.0.0.0", port)).await {
+                    Ok(listener) => listener,
+                    Err(err) => {
+                        log::error!("Cannot listen for TCP link connections on port {}: {}", port, err);
+                        return;
+                    }
+                };
+                // Dropped, and thus aborted, together with this task.
+                let mut handlers = tokio::task::JoinSet::new();
+                loop {
+                    match listener.accept().await {
+                        Ok((stream, _)) => {
+                            handlers.spawn(run_connection(stream, reader.clone(), connections.clone()));
+                        }
+                        Err(err) => log::warn!("Cannot accept TCP link connection: {}", err),
+                    }
+                }
+            }));
+        }
+
+        for (host, port) in config.peers {
+            let reader = reader.clone();
+            let connections = connections.clone();
+            tasks.push(tokio::spawn(async move {
+                loop {
+                    match tokio::net::TcpStream::connect((host.as_str(), port)).await {
+                        Ok(stream) => run_connection(stream, reader.clone(), connections.clone()).await,
+                        Err(err) => log::debug!("Cannot connect TCP link to {}:{}: {}", host, port, err),
+                    }
+                    tokio::time::sleep(RECONNECT_INTERVAL).await;
+                }
+            }));
+        }
+
+        TcpLink {
+            reader: reader.clone(),
+            writer: Box::new(TcpWriter { reader, connections }),
+            _tasks: std::sync::Arc::new(Tasks(tasks)),
+        }
+    }
+}
+
+impl Drop for Tasks {
+    fn drop(&mut self) {
+        for task in &self.0 {
+            task.abort();
+        }
+    }
+}
+
+/// Exchange length-prefixed messages with a peer until the connection fails.
+async fn run_connection(stream: tokio::net::TcpStream, reader: Readers, connections: std::sync::Arc<tokio::sync::Mutex<Connections>>) {
+    if let Err(err) = stream.set_nodelay(true) {
+        log::warn!("Cannot disable Nagle's algorithm on TCP link: {}", err);
+    }
+    let (mut read_half, mut write_half) = stream.into_split();
+
+    let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel::<Vec<u8>>();
+    let id = {
+        let mut connections = connections.lock().await;
+        let id = connections.next_id;
+        connections.next_id += 1;
+        connections.senders.insert(id, sender);
+        id
+    };
+
+    let outgoing = async move {
+        while let Some(msg) = receiver.recv().await {
+            let mut frame = Vec::with_capacity(4 + msg.len());
+            frame.extend_from_slice(&(msg.len() as u32).to_be_bytes());
+            frame.extend_from_slice(&msg);
+            if let Err(err) = write_half.write_all(&frame).await {
+                log::debug!("TCP link connection closed: {}", err);
+                break;
+            }
+        }
+    };
+    let incoming = async move {
+        loop {
+            match read_frame(&mut read_half).await {
+                Ok(msg) => {
+                    for r in reader.lock().await.iter_mut() {
+                        r.handle(msg.clone()).await;
+                    }
+                }
+                Err(err) => {
+                    log::debug!("TCP link connection closed: {}", err);
+                    break;
+                }
+            }
+        }
+    };
+    tokio::select! {
+        _ = outgoing => {},
+        _ = incoming => {},
+    }
+
+    connections.lock().await.senders.remove(&id);
+}
+
+async fn read_frame(stream: &mut (impl tokio::io::AsyncRead + Unpin)) -> anyhow::Result<Vec<u8>> {
+    let mut len = [0_u8; 4];
+    stream.read_exact(&mut len).await?;
+    let len = u32::from_be_bytes(len) as usize;
+    if len > MAX_FRAME_SIZE {
+        return Err(anyhow::anyhow!("Message too large: {} bytes", len));
+    }
+    let mut msg = vec![0_u8; len];
+    stream.read_exact(&mut msg).await?;
+    Ok(msg)
+}
+
+impl Default for TcpProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TcpProvider {
+    pub fn new() -> Self {
+        TcpProvider {
+            links: std::sync::Arc::new(tokio::sync::Mutex::new(std::collections::HashMap::new())),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl edgeless_api::link::LinkProvider for TcpProvider {
+    fn class(&self) -> edgeless_api::link::LinkType {
+        edgeless_api::link::LinkType(crate::common::LINK_TYPE.to_string())
+    }
+
+    async fn create(&mut self, req: edgeless_api::link::CreateLinkRequest) -> anyhow::Result<Box<dyn edgeless_api::link::LinkInstance>> {
+        let cfg: crate::common::TcpConfig = serde_json::from_slice(&req.config)?;
+
+        let link = Box::new(TcpLink::new(cfg));
+
+        self.links.lock().await.insert(req.id, link.clone());
+
+        Ok(link)
+    }
+    async fn remove(&mut self, id: edgeless_api::link::LinkInstanceId) -> anyhow::Result<()> {
+        self.links.lock().await.remove(&id);
+        Ok(())
+    }
+    async fn register_reader(&mut self, link_id: &edgeless_api::link::LinkInstanceId, reader: Box<dyn edgeless_api::link::LinkWriter>) {
+        if let Some(link) = self.links.lock().await.get_mut(link_id) {
+            link.reader.lock().await.push(reader);
+        }
+    }
+    async fn get_writer(&mut self, link_id: &edgeless_api::link::LinkInstanceId) -> Option<Box<dyn edgeless_api::link::LinkWriter>> {
+        Some(self.links.lock().await.get(link_id)?.writer.clone())
+    }
+}
+
+#[async_trait::async_trait]
+impl edgeless_api::link::LinkInstance for TcpLink {
+    async fn register_reader(&mut self, reader: Box<dyn edgeless_api::link::LinkWriter>) -> anyhow::Result<()> {
+        self.reader.lock().await.push(reader);
+        Ok(())
+    }
+    async fn get_writer(&mut self) -> Option<Box<dyn edgeless_api::link::LinkWriter>> {
+        Some(self.writer.clone())
+    }
+}
+
+#[async_trait::async_trait]
+impl edgeless_api::link::LinkWriter for TcpWriter {
+    async fn handle(&mut self, msg: Vec<u8>) {
+        for r in self.reader.lock().await.iter_mut() {
+            r.handle(msg.clone()).await;
+        }
+        for sender in self.connections.lock().await.senders.values() {
+            // The connection is being closed if the receiver is gone.
+            let _ = sender.send(msg.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use edgeless_api::link::LinkInstance;
+
+    #[derive(Clone)]
+    struct Collector(tokio::sync::mpsc::UnboundedSender<Vec<u8>>);
+
+    #[async_trait::async_trait]
+    impl edgeless_api::link::LinkWriter for Collector {
+        async fn handle(&mut self, msg: Vec<u8>) {
+            self.0.send(msg).unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn messages_in_both_directions() {
+        let mut connecting = super::TcpLink::new(crate::common::TcpConfig {
+            listen_port: None,
+            peers: vec![("127.0.0.1".to_string(), 7106)],
+        });
+        let mut listening = super::TcpLink::new(crate::common::TcpConfig {
+            listen_port: Some(7106),
+            peers: vec![],
+        });
+
+        let (sender, mut received_connecting) = tokio::sync::mpsc::unbounded_channel();
+        connecting.register_reader(Box::new(Collector(sender))).await.unwrap();
+        let (sender, mut received_listening) = tokio::sync::mpsc::unbounded_channel();
+        listening.register_reader(Box::new(Collector(sender))).await.unwrap();
+
+        // Wait for the connection, since the messages written before are lost.
+        while connecting.writer.connections.lock().await.senders.is_empty() || listening.writer.connections.lock().await.senders.is_empty() {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+
+        let mut writer = connecting.get_writer().await.unwrap();
+        writer.handle(b"first".to_vec()).await;
+        writer.handle(b"second".to_vec()).await;
+        // The local readers get the messages, too.
+        assert_eq!(received_connecting.recv().await.unwrap(), b"first");
+        assert_eq!(received_connecting.recv().await.unwrap(), b"second");
+        assert_eq!(received_listening.recv().await.unwrap(), b"first");
+        assert_eq!(received_listening.recv().await.unwrap(), b"second");
+
+        listening.get_writer().await.unwrap().handle(b"back".to_vec()).await;
+        assert_eq!(received_connecting.recv().await.unwrap(), b"back");
+        assert_eq!(received_listening.recv().await.unwrap(), b"back");
+    }
+}
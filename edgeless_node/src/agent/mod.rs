@@ -20,6 +20,10 @@ enum AgentRequest {
         futures::channel::oneshot::Sender<anyhow::Result<()>>,
     ),
     Patch(edgeless_api::common::PatchRequest),
+    GetState(
+        edgeless_api::function_instance::InstanceId,
+        futures::channel::oneshot::Sender<anyhow::Result<Option<String>>>,
+    ),
     PatchResource(edgeless_api::common::PatchRequest, futures::channel::oneshot::Sender<anyhow::Result<()>>),
     UpdatePeers(edgeless_api::node_management::UpdatePeersRequest),
     HealthStatus(futures::channel::oneshot::Sender<anyhow::Result<edgeless_api::node_management::HealthStatus>>),
@@ -47,6 +51,7 @@ impl Agent {
         node_id: uuid::Uuid,
         data_plane_provider: edgeless_dataplane::handle::DataplaneProvider,
         proxy: Box<dyn ProxyInstanceAPI>,
+        instance_statistics: edgeless_telemetry::instance_statistics::InstanceStatisticsHandle,
     ) -> (Self, std::pin::Pin<Box<dyn Future<Output = ()> + Send>>) {
         let (sender, receiver) = futures::channel::mpsc::unbounded();
 
@@ -55,7 +60,7 @@ impl Agent {
         }
 
         let main_task = Box::pin(async move {
-            Self::main_task(receiver, runners, resources, data_plane_provider, proxy).await;
+            Self::main_task(receiver, runners, resources, data_plane_provider, proxy, instance_statistics).await;
         });
 
         (Agent { sender, node_id }, main_task)
@@ -69,6 +74,7 @@ impl Agent {
         resources: std::collections::HashMap<String, ResourceDesc>,
         data_plane_provider: edgeless_dataplane::handle::DataplaneProvider,
         mut proxy: Box<dyn ProxyInstanceAPI>,
+        instance_statistics: edgeless_telemetry::instance_statistics::InstanceStatisticsHandle,
     ) {
         let mut receiver = std::pin::pin!(receiver);
         let mut data_plane_provider = data_plane_provider;
@@ -187,12 +193,25 @@ impl Agent {
                         }
                     }
                 }
+                AgentRequest::GetState(instance_id, responder) => {
+                    log::debug!("Agent GetState {:?}", instance_id);
+
+                    let res = match component_id_to_class_map.get(&instance_id).and_then(|class| runners.get_mut(class)) {
+                        Some(r) => r.get_state(instance_id).await,
+                        None => Err(anyhow::anyhow!("Could not find the runner of function instance {}", instance_id)),
+                    };
+                    responder.send(res).unwrap_or_else(|_| log::warn!("Responder Send Error"));
+                }
                 AgentRequest::UpdatePeers(request) => {
                     log::debug!("Agent UpdatePeers {:?}", request);
                     match request {
-                        UpdatePeersRequest::Add(node_id, invocation_url) => {
+                        UpdatePeersRequest::Add(node_id, invocation_url, invocation_url_quic) => {
                             data_plane_provider
-                                .add_peer(EdgelessDataplanePeerSettings { node_id, invocation_url })
+                                .add_peer(EdgelessDataplanePeerSettings {
+                                    node_id,
+                                    invocation_url,
+                                    invocation_url_quic,
+                                })
                                 .await
                         }
                         UpdatePeersRequest::Del(node_id) => data_plane_provider.del_peer(node_id).await,
@@ -301,6 +320,18 @@ impl Agent {
                     sys.refresh_memory();
                     sys.refresh_process(my_pid);
 
+                    // The input queues are sampled now, rather than at the
+                    // end of the last invocation, which may never come for
+                    // an instance that is stuck.
+                    let queues = data_plane_provider.queue_statistics().await;
+                    let mut statistics = instance_statistics.snapshot();
+                    for instance in &mut statistics {
+                        if let Some(queue) = queues.get(&instance.function_id) {
+                            instance.queue_depth = queue.depth as u64;
+                            instance.dropped_events = queue.dropped;
+                        }
+                    }
+
                     let to_kb = |x| (x / 1024) as i32;
                     let proc = sys.process(my_pid).unwrap();
                     let health_status = edgeless_api::node_management::HealthStatus {
@@ -313,6 +344,7 @@ impl Agent {
                         proc_cpu_usage: proc.cpu_usage() as i32,
                         proc_memory: to_kb(proc.memory()),
                         proc_vmemory: to_kb(proc.virtual_memory()),
+                        instance_statistics: statistics,
                     };
                     responder.send(Ok(health_status)).unwrap_or_else(|_| log::warn!("Responder Send Error"));
                 }
@@ -431,6 +463,18 @@ impl edgeless_api::function_instance::FunctionInstanceAPI<edgeless_api::function
             )),
         }
     }
+
+    async fn get_state(&mut self, id: edgeless_api::function_instance::InstanceId) -> anyhow::Result<Option<String>> {
+        let (rsp_sender, rsp_receiver) = futures::channel::oneshot::channel::<anyhow::Result<Option<String>>>();
+        let _ = self
+            .sender
+            .send(AgentRequest::GetState(id, rsp_sender))
+            .await
+            .map_err(|err| anyhow::anyhow!("Agent channel error when getting the state of a function instance: {}", err))?;
+        rsp_receiver
+            .await
+            .map_err(|err| anyhow::anyhow!("Agent channel error when getting the state of a function instance: {}", err))?
+    }
 }
 
 #[async_trait::async_trait]
@@ -37,6 +37,7 @@ struct FunctionInstanceTask<FunctionInstanceType: FunctionInstance> {
     instance_id: edgeless_api::function_instance::InstanceId,
     tracer_provider: opentelemetry_sdk::trace::TracerProvider,
     tracing_context: std::sync::Arc<tokio::sync::Mutex<TracingContext>>,
+    annotations: std::collections::HashMap<String, String>,
 }
 pub struct TracingContext {
     pub tracer: opentelemetry_sdk::trace::Tracer,
@@ -91,6 +92,11 @@ impl<FunctionInstanceType: FunctionInstance> FunctionInstanceRunner<FunctionInst
         ));
 
         data_plane.set_tracer(tracer);
+        let call_timeouts = edgeless_dataplane::core::CallTimeouts::from_annotations(&spawn_req.annotations, data_plane.call_timeouts().default);
+        data_plane.set_call_timeouts(call_timeouts);
+        data_plane.set_call_quorums(edgeless_dataplane::core::CallQuorums::from_annotations(&spawn_req.annotations));
+        data_plane.set_load_balancing(edgeless_dataplane::load_balancing::LoadBalancingPolicies::from_annotations(&spawn_req.annotations));
+        data_plane.set_delivery(edgeless_dataplane::delivery::DeliverySettings::from_annotations(&spawn_req.annotations));
 
         let guest_api_host = crate::base_runtime::guest_api::GuestAPIHost {
             instance_id,
@@ -98,7 +104,8 @@ impl<FunctionInstanceType: FunctionInstance> FunctionInstanceRunner<FunctionInst
             state_handle,
             telemetry_handle: telemetry_handle.fork(std::collections::BTreeMap::new()),
             poison_pill_receiver: poison_pill_sender.subscribe(),
-            tracing_context: tracing_context.clone()
+            tracing_context: tracing_context.clone(),
+            waiting_time: std::time::Duration::ZERO,
         };
 
         let task = Box::new(
@@ -114,7 +121,8 @@ impl<FunctionInstanceType: FunctionInstance> FunctionInstanceRunner<FunctionInst
                 runtime_api,
                 instance_id,
                 tracer_provider,
-                tracing_context
+                tracing_context,
+                spawn_req.annotations.clone(),
             )
             .await,
         );
@@ -161,6 +169,7 @@ impl<FunctionInstanceType: FunctionInstance> FunctionInstanceTask<FunctionInstan
         instance_id: edgeless_api::function_instance::InstanceId,
         tracer_provider: opentelemetry_sdk::trace::TracerProvider,
         tracing_context: std::sync::Arc<tokio::sync::Mutex<TracingContext>>,
+        annotations: std::collections::HashMap<String, String>,
     ) -> Self {
         Self {
             poison_pill_receiver,
@@ -176,6 +185,7 @@ impl<FunctionInstanceType: FunctionInstance> FunctionInstanceTask<FunctionInstan
             instance_id,
             tracer_provider,
             tracing_context,
+            annotations,
         }
     }
 
@@ -200,7 +210,7 @@ impl<FunctionInstanceType: FunctionInstance> FunctionInstanceTask<FunctionInstan
         let start = tokio::time::Instant::now();
         let mut span = self.tracing_context.lock().await.tracer.start("instantiate");
 
-        let runtime_configuration;
+        let mut runtime_configuration;
         {
             // Register this function instance, if needed by the runtime.
             let mut register = self.guest_api_host_register.lock().await;
@@ -209,9 +219,13 @@ impl<FunctionInstanceType: FunctionInstance> FunctionInstanceTask<FunctionInstan
             }
             runtime_configuration = register.configuration();
         }
+        // The annotations of the function can only make the limits of the runtime stricter.
+        let limits = super::limits::FunctionLimits::from_annotations(&runtime_configuration)
+            .restricted_by(&super::limits::FunctionLimits::from_annotations(&self.annotations));
+        runtime_configuration.extend(limits.to_configuration());
 
         self.function_instance =
-            Some(FunctionInstanceType::instantiate(&self.instance_id, runtime_configuration, &mut self.guest_api_host.take(), &self.code).await?);
+            Some(FunctionInstanceType::instantiate(&self.instance_id, runtime_configuration, &self.annotations, &mut self.guest_api_host.take(), &self.code).await?);
 
         span.end();
 
@@ -291,7 +305,7 @@ impl<FunctionInstanceType: FunctionInstance> FunctionInstanceTask<FunctionInstan
         &mut self,
         source_id: edgeless_api::function_instance::InstanceId,
         target_port: edgeless_api::function_instance::PortId,
-        payload: String,
+        payload: Vec<u8>,
         span_context: opentelemetry::trace::SpanContext
     ) -> Result<(), super::FunctionInstanceError> {
         
@@ -308,9 +322,15 @@ impl<FunctionInstanceType: FunctionInstance> FunctionInstanceTask<FunctionInstan
 
         // span.end();
         self.tracing_context.lock().await.parent_context = opentelemetry::Context::new();
+        let queue = self.data_plane.queue_statistics();
         self.telemetry_handle.observe(
             edgeless_telemetry::telemetry_events::TelemetryEvent::FunctionInvocationCompleted(start.elapsed()),
-            std::collections::BTreeMap::from([("EVENT_TYPE".to_string(), "CAST".to_string())]),
+            std::collections::BTreeMap::from([
+                ("EVENT_TYPE".to_string(), "CAST".to_string()),
+                ("PORT".to_string(), target_port.0.clone()),
+                ("QUEUE_DEPTH".to_string(), queue.depth.to_string()),
+                ("QUEUE_DROPPED".to_string(), queue.dropped.to_string()),
+            ]),
         );
         Ok(())
     }
@@ -319,7 +339,7 @@ impl<FunctionInstanceType: FunctionInstance> FunctionInstanceTask<FunctionInstan
         &mut self,
         source_id: edgeless_api::function_instance::InstanceId,
         target_port: edgeless_api::function_instance::PortId,
-        payload: String,
+        payload: Vec<u8>,
         channel_id: u64,
         span_context: opentelemetry::trace::SpanContext
     ) -> Result<(), super::FunctionInstanceError> {
@@ -336,9 +356,15 @@ impl<FunctionInstanceType: FunctionInstance> FunctionInstanceTask<FunctionInstan
             .await?;
 
         self.tracing_context.lock().await.parent_context = opentelemetry::Context::new();
+        let queue = self.data_plane.queue_statistics();
         self.telemetry_handle.observe(
             edgeless_telemetry::telemetry_events::TelemetryEvent::FunctionInvocationCompleted(start.elapsed()),
-            std::collections::BTreeMap::from([("EVENT_TYPE".to_string(), "CALL".to_string())]),
+            std::collections::BTreeMap::from([
+                ("EVENT_TYPE".to_string(), "CALL".to_string()),
+                ("PORT".to_string(), target_port.0.clone()),
+                ("QUEUE_DEPTH".to_string(), queue.depth.to_string()),
+                ("QUEUE_DROPPED".to_string(), queue.dropped.to_string()),
+            ]),
         );
 
         let mut wh = self.data_plane.clone();
@@ -375,6 +401,7 @@ impl<FunctionInstanceType: FunctionInstance> FunctionInstanceTask<FunctionInstan
                 Ok(_) => edgeless_telemetry::telemetry_events::FunctionExitStatus::Ok,
                 Err(exit_err) => match exit_err {
                     FunctionInstanceError::BadCode => edgeless_telemetry::telemetry_events::FunctionExitStatus::CodeError,
+                    FunctionInstanceError::ResourceLimitExceeded => edgeless_telemetry::telemetry_events::FunctionExitStatus::ResourceLimitExceeded,
                     _ => edgeless_telemetry::telemetry_events::FunctionExitStatus::InternalError,
                 },
             }),
@@ -397,7 +424,6 @@ impl<FunctionInstanceType: FunctionInstance> FunctionInstanceTask<FunctionInstan
             context.span().end();
             tracer.start_with_context(span_id, &context)
         } else {
-            assert!(false);
             tracer.start(span_id)
         };
         span.add_event("test", vec![]);
@@ -13,7 +13,10 @@ pub struct GuestAPIHost {
     pub state_handle: Box<dyn crate::state_management::StateHandleAPI>,
     pub telemetry_handle: Box<dyn edgeless_telemetry::telemetry_events::TelemetryHandleAPI>,
     pub poison_pill_receiver: tokio::sync::broadcast::Receiver<()>,
-    pub tracing_context: std::sync::Arc<tokio::sync::Mutex<super::function_instance_runner::TracingContext>>
+    pub tracing_context: std::sync::Arc<tokio::sync::Mutex<super::function_instance_runner::TracingContext>>,
+    /// Total time spent waiting in the host calls, e.g., for the replies to
+    /// calls, which does not count towards the execution time of the invocations.
+    pub waiting_time: std::time::Duration,
 }
 
 /// Errors to be reported by the host side of the guest binding.
@@ -24,48 +27,92 @@ pub enum GuestAPIError {
 }
 
 impl GuestAPIHost {
-    pub async fn cast_alias(&mut self, alias: &str, msg: &str) -> Result<(), GuestAPIError> {
-        self.data_plane
-            .send_alias(alias.to_string(), msg.to_string(), self.tracing_context.lock().await.parent_context.clone())
-            .await
-            .map_err(|_e| GuestAPIError::UnknownAlias)
+    /// The partition key, if any, picks the target of outputs load-balanced with consistent hashing.
+    pub async fn cast_alias(&mut self, alias: &str, partition_key: Option<&[u8]>, msg: &[u8]) -> Result<(), GuestAPIError> {
+        let start = tokio::time::Instant::now();
+        let context = self.tracing_context.lock().await.parent_context.clone();
+        // The cast may wait for room in the input queue of the target.
+        let res = match partition_key {
+            Some(partition_key) => self.data_plane.send_alias_with_key(alias.to_string(), partition_key, msg, context).await,
+            None => self.data_plane.send_alias(alias.to_string(), msg, context).await,
+        }
+        .map_err(|_e| GuestAPIError::UnknownAlias);
+        self.waiting_time += start.elapsed();
+        res
     }
 
     pub async fn cast_raw(
         &mut self,
         target: edgeless_api::function_instance::InstanceId,
         target_port: edgeless_api::function_instance::PortId,
-        msg: &str,
+        msg: &[u8],
     ) -> Result<(), GuestAPIError> {
-        self.data_plane.send(target, target_port, msg.to_string(), self.tracing_context.lock().await.parent_context.clone()).await;
+        let start = tokio::time::Instant::now();
+        self.data_plane.send(target, target_port, msg, self.tracing_context.lock().await.parent_context.clone()).await;
+        self.waiting_time += start.elapsed();
         Ok(())
     }
 
-    pub async fn call_alias(&mut self, alias: &str, msg: &str) -> Result<edgeless_dataplane::core::CallRet, GuestAPIError> {
-        futures::select! {
+    /// Like `cast_alias`, but waits for the reply.
+    pub async fn call_alias(
+        &mut self,
+        alias: &str,
+        partition_key: Option<&[u8]>,
+        msg: &[u8],
+    ) -> Result<edgeless_dataplane::core::CallRet, GuestAPIError> {
+        let start = tokio::time::Instant::now();
+        let context = self.tracing_context.lock().await.parent_context.clone();
+        let data_plane = &mut self.data_plane;
+        let call = async move {
+            match partition_key {
+                Some(partition_key) => data_plane.call_alias_with_key(alias.to_string(), partition_key, msg, context).await,
+                None => data_plane.call_alias(alias.to_string(), msg, context).await,
+            }
+        };
+        let res = futures::select! {
             _ = Box::pin(self.poison_pill_receiver.recv()).fuse() => {
                 Ok(edgeless_dataplane::core::CallRet::Err)
             },
-            call_res = Box::pin(self.data_plane.call_alias(alias.to_string(), msg.to_string(), self.tracing_context.lock().await.parent_context.clone()).fuse()) => {
+            call_res = Box::pin(call.fuse()) => {
                 Ok(call_res)
             }
-        }
+        };
+        self.waiting_time += start.elapsed();
+        res
+    }
+
+    pub async fn call_all_alias(&mut self, alias: &str, msg: &[u8]) -> Result<edgeless_dataplane::core::GatherRet, GuestAPIError> {
+        let start = tokio::time::Instant::now();
+        let context = self.tracing_context.lock().await.parent_context.clone();
+        let res = futures::select! {
+            _ = Box::pin(self.poison_pill_receiver.recv()).fuse() => {
+                Ok(edgeless_dataplane::core::GatherRet { replies: vec![], quorum_reached: false })
+            },
+            call_res = Box::pin(self.data_plane.call_all_alias(alias.to_string(), msg, context)).fuse() => {
+                call_res.map_err(|_e| GuestAPIError::UnknownAlias)
+            }
+        };
+        self.waiting_time += start.elapsed();
+        res
     }
 
     pub async fn call_raw(
         &mut self,
         target: edgeless_api::function_instance::InstanceId,
         target_port: edgeless_api::function_instance::PortId,
-        msg: &str,
+        msg: &[u8],
     ) -> Result<edgeless_dataplane::core::CallRet, GuestAPIError> {
-        futures::select! {
+        let start = tokio::time::Instant::now();
+        let res = futures::select! {
             _ = Box::pin(self.poison_pill_receiver.recv()).fuse() => {
                 Ok(edgeless_dataplane::core::CallRet::Err)
             },
-            call_res = Box::pin(self.data_plane.call(target, target_port, msg.to_string(), self.tracing_context.lock().await.parent_context.clone())).fuse() => {
+            call_res = Box::pin(self.data_plane.call(target, target_port, msg, self.tracing_context.lock().await.parent_context.clone())).fuse() => {
                 Ok(call_res)
             }
-        }
+        };
+        self.waiting_time += start.elapsed();
+        res
     }
 
     pub async fn telemetry_log(&mut self, lvl: edgeless_telemetry::telemetry_events::TelemetryLogLevel, target: &str, msg: &str) {
@@ -79,9 +126,9 @@ impl GuestAPIHost {
         self.instance_id
     }
 
-    pub async fn delayed_cast(&mut self, delay: u64, target_alias: &str, payload: &str) -> Result<(), GuestAPIError> {
+    pub async fn delayed_cast(&mut self, delay: u64, target_alias: &str, payload: &[u8]) -> Result<(), GuestAPIError> {
         let mut cloned_plane = self.data_plane.clone();
-        let cloned_msg = payload.to_string();
+        let cloned_msg = payload.to_vec();
         let cloned_alias = target_alias.to_string();
 
         // let cloned_context = self.tracing_context.lock().await.parent_context.clone();
@@ -97,8 +144,32 @@ impl GuestAPIHost {
     }
 
     pub async fn sync(&mut self, serialized_state: &str) -> Result<(), GuestAPIError> {
+        let start = tokio::time::Instant::now();
         self.state_handle.set(serialized_state.to_string()).await;
+        self.waiting_time += start.elapsed();
         log::info!("Function State Sync: {}", serialized_state);
         Ok(())
     }
 }
+
+/// Encode the answers gathered by `call_all_alias` in the format expected by
+/// the guest: for each answer, the node and component identifiers of the
+/// target, the type of answer as returned by a call, the length of the reply
+/// (4 bytes, little endian), and the reply.
+pub fn encode_gathered_replies(replies: &[(edgeless_api::function_instance::InstanceId, edgeless_dataplane::core::CallRet)]) -> Vec<u8> {
+    let mut data = Vec::new();
+    for (target, ret) in replies {
+        data.extend_from_slice(target.node_id.as_bytes());
+        data.extend_from_slice(target.function_id.as_bytes());
+        let (kind, reply): (u8, &[u8]) = match ret {
+            edgeless_dataplane::core::CallRet::NoReply => (0, &[]),
+            edgeless_dataplane::core::CallRet::Reply(reply) => (1, reply),
+            edgeless_dataplane::core::CallRet::Err => (2, &[]),
+            edgeless_dataplane::core::CallRet::Timeout => (3, &[]),
+        };
+        data.push(kind);
+        data.extend_from_slice(&(reply.len() as u32).to_le_bytes());
+        data.extend_from_slice(reply);
+    }
+    data
+}
@@ -0,0 +1,248 @@
+// SPDX-FileCopyrightText: © 2024 Technical University of Munich, Chair of Connected Mobility
+// SPDX-License-Identifier: MIT
+
+/// Largest linear memory of the instance, in pages of 64 KiB.
+pub const MAX_MEMORY_PAGES_ANNOTATION: &str = "max_memory_pages";
+
+/// Longest execution of an invocation of the instance, in ms.
+pub const MAX_EXECUTION_TIME_ANNOTATION: &str = "max_execution_time_ms";
+
+/// Largest table of the instance, in elements.
+pub const MAX_TABLE_ELEMENTS_ANNOTATION: &str = "max_table_elements";
+
+/// Largest number of WASM module instances created by the function instance.
+pub const MAX_WASM_INSTANCES_ANNOTATION: &str = "max_wasm_instances";
+
+/// Size of a page of WASM linear memory.
+pub const WASM_PAGE_SIZE: u64 = 64 * 1024;
+
+/// Number of tables, memories, and module instances allowed without a limit,
+/// which is the same default as wasmtime.
+const DEFAULT_COUNT_LIMIT: usize = 10_000;
+
+/// Resource limits of a function instance, where 0 means no limit.
+///
+/// The limits are set by the node for all the instances of a runtime and by
+/// the annotations of the function, with the same keys. The annotations can
+/// only make the limits of the node stricter.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct FunctionLimits {
+    #[serde(default)]
+    pub max_memory_pages: u64,
+    #[serde(default)]
+    pub max_execution_time_ms: u64,
+    #[serde(default)]
+    pub max_table_elements: u64,
+    #[serde(default)]
+    pub max_wasm_instances: u64,
+}
+
+impl FunctionLimits {
+    pub fn from_annotations(annotations: &std::collections::HashMap<String, String>) -> Self {
+        let mut limits = Self::default();
+        for (key, val) in annotations {
+            let limit = match key.as_str() {
+                MAX_MEMORY_PAGES_ANNOTATION => &mut limits.max_memory_pages,
+                MAX_EXECUTION_TIME_ANNOTATION => &mut limits.max_execution_time_ms,
+                MAX_TABLE_ELEMENTS_ANNOTATION => &mut limits.max_table_elements,
+                MAX_WASM_INSTANCES_ANNOTATION => &mut limits.max_wasm_instances,
+                _ => continue,
+            };
+            match val.parse::<u64>() {
+                Ok(val) => *limit = val,
+                Err(_) => log::warn!("invalid value of annotation {}: {}", key, val),
+            }
+        }
+        limits
+    }
+
+    /// Entries of the runtime configuration with the limits that are set.
+    pub fn to_configuration(&self) -> std::collections::HashMap<String, String> {
+        [
+            (MAX_MEMORY_PAGES_ANNOTATION, self.max_memory_pages),
+            (MAX_EXECUTION_TIME_ANNOTATION, self.max_execution_time_ms),
+            (MAX_TABLE_ELEMENTS_ANNOTATION, self.max_table_elements),
+            (MAX_WASM_INSTANCES_ANNOTATION, self.max_wasm_instances),
+        ]
+        .into_iter()
+        .filter(|(_, limit)| *limit > 0)
+        .map(|(key, limit)| (key.to_string(), limit.to_string()))
+        .collect()
+    }
+
+    /// Strictest of each limit of `self` and `other`.
+    pub fn restricted_by(&self, other: &FunctionLimits) -> FunctionLimits {
+        let min = |a: u64, b: u64| match (a, b) {
+            (0, b) => b,
+            (a, 0) => a,
+            (a, b) => a.min(b),
+        };
+        FunctionLimits {
+            max_memory_pages: min(self.max_memory_pages, other.max_memory_pages),
+            max_execution_time_ms: min(self.max_execution_time_ms, other.max_execution_time_ms),
+            max_table_elements: min(self.max_table_elements, other.max_table_elements),
+            max_wasm_instances: min(self.max_wasm_instances, other.max_wasm_instances),
+        }
+    }
+
+    pub fn max_execution_time(&self) -> Option<std::time::Duration> {
+        match self.max_execution_time_ms {
+            0 => None,
+            ms => Some(std::time::Duration::from_millis(ms)),
+        }
+    }
+}
+
+/// Decides whether the memories and tables of a WASM instance can grow,
+/// to be wrapped by the resource limiter of each WASM engine.
+/// Remembers if a limit was hit, so that the failure of the instance can be
+/// reported as such, whatever the WASM code does when it is denied memory.
+pub struct WasmLimiter {
+    limits: FunctionLimits,
+    pub exceeded: bool,
+    /// Start of the current invocation, or of the instantiation.
+    invocation_start: std::time::Instant,
+    /// Time waited by the host calls before the current invocation.
+    waiting_time_at_start: std::time::Duration,
+}
+
+impl WasmLimiter {
+    pub fn new(limits: FunctionLimits) -> Self {
+        Self {
+            limits,
+            exceeded: false,
+            invocation_start: std::time::Instant::now(),
+            waiting_time_at_start: std::time::Duration::ZERO,
+        }
+    }
+
+    /// Marks the start of an invocation, given the total time waited by the
+    /// host calls so far.
+    pub fn start_invocation(&mut self, waiting_time: std::time::Duration) {
+        self.invocation_start = std::time::Instant::now();
+        self.waiting_time_at_start = waiting_time;
+    }
+
+    /// Execution time left to the current invocation, given the total time
+    /// waited by the host calls so far, which is not counted. None if the
+    /// execution time is not limited.
+    pub fn remaining_execution_time(&self, waiting_time: std::time::Duration) -> Option<std::time::Duration> {
+        let waited = waiting_time.saturating_sub(self.waiting_time_at_start);
+        let executed = self.invocation_start.elapsed().saturating_sub(waited);
+        Some(self.limits.max_execution_time()?.saturating_sub(executed))
+    }
+
+    pub fn allow_memory(&mut self, desired_bytes: usize) -> bool {
+        self.allow(self.limits.max_memory_pages.saturating_mul(WASM_PAGE_SIZE), desired_bytes as u64)
+    }
+
+    pub fn allow_table(&mut self, desired_elements: u64) -> bool {
+        self.allow(self.limits.max_table_elements, desired_elements)
+    }
+
+    pub fn max_instances(&self) -> usize {
+        match self.limits.max_wasm_instances {
+            0 => DEFAULT_COUNT_LIMIT,
+            max => max as usize,
+        }
+    }
+
+    pub fn max_tables(&self) -> usize {
+        DEFAULT_COUNT_LIMIT
+    }
+
+    pub fn max_memories(&self) -> usize {
+        DEFAULT_COUNT_LIMIT
+    }
+
+    /// Reports the failure of an instance that hit one of its limits as
+    /// `ResourceLimitExceeded`.
+    pub fn check<T>(&self, res: Result<T, super::FunctionInstanceError>) -> Result<T, super::FunctionInstanceError> {
+        match res {
+            Err(_) if self.exceeded => Err(super::FunctionInstanceError::ResourceLimitExceeded),
+            res => res,
+        }
+    }
+
+    fn allow(&mut self, limit: u64, desired: u64) -> bool {
+        if limit > 0 && desired > limit {
+            self.exceeded = true;
+            return false;
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn annotations_restrict_node_limits() {
+        let node = super::FunctionLimits::from_annotations(&std::collections::HashMap::from([
+            ("max_memory_pages".to_string(), "160".to_string()),
+            ("max_execution_time_ms".to_string(), "1000".to_string()),
+            ("init-payload".to_string(), "ignored".to_string()),
+        ]));
+        let function = super::FunctionLimits::from_annotations(&std::collections::HashMap::from([
+            ("max_memory_pages".to_string(), "320".to_string()),
+            ("max_execution_time_ms".to_string(), "100".to_string()),
+            ("max_table_elements".to_string(), "1024".to_string()),
+            ("max_wasm_instances".to_string(), "many".to_string()),
+        ]));
+
+        let limits = node.restricted_by(&function);
+        assert_eq!(
+            limits,
+            super::FunctionLimits {
+                max_memory_pages: 160,
+                max_execution_time_ms: 100,
+                max_table_elements: 1024,
+                max_wasm_instances: 0,
+            }
+        );
+        assert_eq!(super::FunctionLimits::from_annotations(&limits.to_configuration()), limits);
+        assert_eq!(limits.max_execution_time(), Some(std::time::Duration::from_millis(100)));
+    }
+
+    #[test]
+    fn limiter_remembers_exceeded_limits() {
+        let mut limiter = super::WasmLimiter::new(super::FunctionLimits {
+            max_memory_pages: 2,
+            ..Default::default()
+        });
+        assert!(limiter.allow_memory(2 * 64 * 1024));
+        assert!(limiter.allow_table(1_000_000));
+        assert!(limiter.check(Err::<(), _>(crate::base_runtime::FunctionInstanceError::BadCode)).is_err());
+        assert!(!limiter.exceeded);
+
+        assert!(!limiter.allow_memory(3 * 64 * 1024));
+        assert!(limiter.exceeded);
+        assert!(matches!(
+            limiter.check(Err::<(), _>(crate::base_runtime::FunctionInstanceError::BadCode)),
+            Err(crate::base_runtime::FunctionInstanceError::ResourceLimitExceeded)
+        ));
+        assert!(limiter.check(Ok(())).is_ok());
+    }
+
+    #[test]
+    fn waiting_time_not_executed() {
+        let mut limiter = super::WasmLimiter::new(super::FunctionLimits {
+            max_execution_time_ms: 100,
+            ..Default::default()
+        });
+        assert!(super::WasmLimiter::new(super::FunctionLimits::default())
+            .remaining_execution_time(std::time::Duration::ZERO)
+            .is_none());
+
+        limiter.start_invocation(std::time::Duration::from_secs(10));
+        std::thread::sleep(std::time::Duration::from_millis(60));
+        let remaining = limiter.remaining_execution_time(std::time::Duration::from_secs(10)).unwrap();
+        assert!(remaining <= std::time::Duration::from_millis(40));
+
+        // The invocation waited for most of the time in the host calls.
+        let remaining = limiter.remaining_execution_time(std::time::Duration::from_millis(10_050)).unwrap();
+        assert!(remaining >= std::time::Duration::from_millis(40) && remaining <= std::time::Duration::from_millis(90));
+
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        assert!(limiter.remaining_execution_time(std::time::Duration::from_secs(10)).unwrap().is_zero());
+    }
+}
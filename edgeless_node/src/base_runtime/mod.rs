@@ -3,6 +3,7 @@
 pub mod alias_mapping;
 pub mod function_instance_runner;
 pub mod guest_api;
+pub mod limits;
 pub mod runtime;
 
 /// (Deprecated) Trait to be implemented by each runtime.
@@ -12,6 +13,7 @@ pub trait RuntimeAPI {
     async fn start(&mut self, request: edgeless_api::function_instance::SpawnFunctionRequest) -> anyhow::Result<()>;
     async fn stop(&mut self, instance_id: edgeless_api::function_instance::InstanceId) -> anyhow::Result<()>;
     async fn patch(&mut self, update: edgeless_api::common::PatchRequest) -> anyhow::Result<()>;
+    async fn get_state(&mut self, instance_id: edgeless_api::function_instance::InstanceId) -> anyhow::Result<Option<String>>;
 }
 
 /// This must be implemented for each virtualization technology.
@@ -21,16 +23,17 @@ pub trait FunctionInstance: Send + 'static {
     async fn instantiate(
         instance_id: &edgeless_api::function_instance::InstanceId,
         runtime_configuration: std::collections::HashMap<String, String>,
+        annotations: &std::collections::HashMap<String, String>,
         guest_api_host: &mut Option<crate::base_runtime::guest_api::GuestAPIHost>,
         code: &[u8],
     ) -> Result<Box<Self>, FunctionInstanceError>;
     async fn init(&mut self, init_payload: Option<&str>, serialized_state: Option<&str>) -> Result<(), FunctionInstanceError>;
-    async fn cast(&mut self, src: &edgeless_api::function_instance::InstanceId, port: &str, msg: &str) -> Result<(), FunctionInstanceError>;
+    async fn cast(&mut self, src: &edgeless_api::function_instance::InstanceId, port: &str, msg: &[u8]) -> Result<(), FunctionInstanceError>;
     async fn call(
         &mut self,
         src: &edgeless_api::function_instance::InstanceId,
         port: &str,
-        msg: &str,
+        msg: &[u8],
     ) -> Result<edgeless_dataplane::core::CallRet, FunctionInstanceError>;
     async fn stop(&mut self) -> Result<(), FunctionInstanceError>;
 }
@@ -39,4 +42,7 @@ pub trait FunctionInstance: Send + 'static {
 pub enum FunctionInstanceError {
     BadCode,
     InternalError,
+    /// The instance hit one of its `limits::FunctionLimits`, or the node has
+    /// no room for it, e.g., its WASM instance pool is full.
+    ResourceLimitExceeded,
 }
@@ -39,12 +39,18 @@ pub struct RuntimeTask<FunctionInstanceType: super::FunctionInstance> {
         edgeless_api::function_instance::InstanceId,
         super::function_instance_runner::FunctionInstanceRunner<FunctionInstanceType>,
     >,
+    // Handles to the state of the running functions, to hand it off on migration.
+    states: std::collections::HashMap<edgeless_api::function_instance::InstanceId, Box<dyn crate::state_management::StateHandleAPI>>,
 }
 
 pub enum RuntimeRequest {
     Start(edgeless_api::function_instance::SpawnFunctionRequest),
     Stop(edgeless_api::function_instance::InstanceId),
     Patch(edgeless_api::common::PatchRequest),
+    GetState(
+        edgeless_api::function_instance::InstanceId,
+        futures::channel::oneshot::Sender<anyhow::Result<Option<String>>>,
+    ),
     FunctionExit(edgeless_api::function_instance::InstanceId, Result<(), super::FunctionInstanceError>),
 }
 
@@ -87,6 +93,7 @@ impl<FunctionInstanceType: super::FunctionInstance> RuntimeTask<FunctionInstance
             guest_api_host_register,
             slf_channel,
             functions: std::collections::HashMap::new(),
+            states: std::collections::HashMap::new(),
         }
     }
 
@@ -103,6 +110,13 @@ impl<FunctionInstanceType: super::FunctionInstance> RuntimeTask<FunctionInstance
                 RuntimeRequest::Patch(update_request) => {
                     self.patch_function_links(update_request).await;
                 }
+                RuntimeRequest::GetState(instance_id, responder) => {
+                    let res = match self.states.get_mut(&instance_id) {
+                        Some(state) => Ok(state.get().await),
+                        None => Err(anyhow::anyhow!("Function instance not found: {}", instance_id)),
+                    };
+                    responder.send(res).unwrap_or_else(|_| log::warn!("Responder Send Error"));
+                }
                 RuntimeRequest::FunctionExit(id, status) => {
                     self.function_exit(id, status).await;
                 }
@@ -116,12 +130,22 @@ impl<FunctionInstanceType: super::FunctionInstance> RuntimeTask<FunctionInstance
         let cloned_req = spawn_request.clone();
         let mut data_plane = self.data_plane_provider.get_handle_for(instance_id).await;
         data_plane.update_mapping(spawn_request.input_mapping, spawn_request.output_mapping).await;
+        let state_specification = spawn_request.state_specification;
+        let mut state = self
+            .state_manager
+            .get_handle(state_specification.state_policy.clone(), state_specification.state_id)
+            .await;
+        if let Some(initial_state) = state_specification.initial_state {
+            // Must be stored before the runner reads it to initialize the function.
+            state.set(initial_state).await;
+        }
+        self.states.insert(instance_id, state);
         let instance = super::function_instance_runner::FunctionInstanceRunner::new(
             cloned_req,
             data_plane,
             self.slf_channel.clone(),
             self.state_manager
-                .get_handle(spawn_request.state_specification.state_policy, spawn_request.state_specification.state_id)
+                .get_handle(state_specification.state_policy, state_specification.state_id)
                 .await,
             self.telemetry_handle.fork(std::collections::BTreeMap::from([(
                 "FUNCTION_ID".to_string(),
@@ -150,6 +174,7 @@ impl<FunctionInstanceType: super::FunctionInstance> RuntimeTask<FunctionInstance
     async fn function_exit(&mut self, instance_id: edgeless_api::function_instance::InstanceId, status: Result<(), super::FunctionInstanceError>) {
         log::info!("Function Exit Event: {:?} {:?}", instance_id, status);
         self.functions.remove(&instance_id);
+        self.states.remove(&instance_id);
     }
 }
 
@@ -183,4 +208,12 @@ impl super::RuntimeAPI for RuntimeClient {
             Err(_) => Err(anyhow::anyhow!("Runner Channel Error")),
         }
     }
+
+    async fn get_state(&mut self, instance_id: edgeless_api::function_instance::InstanceId) -> anyhow::Result<Option<String>> {
+        let (rsp_sender, rsp_receiver) = futures::channel::oneshot::channel::<anyhow::Result<Option<String>>>();
+        if self.sender.send(RuntimeRequest::GetState(instance_id, rsp_sender)).await.is_err() {
+            return Err(anyhow::anyhow!("Runner Channel Error"));
+        }
+        rsp_receiver.await.map_err(|_| anyhow::anyhow!("Runner Channel Error"))?
+    }
 }
@@ -100,7 +100,11 @@ impl ContainerRuntime {
                 ContainerRuntimeRequest::CAST(event) => {
                     log::debug!("cast, alias {}, msg {} bytes", event.alias, event.msg.len());
                     if let Some(runtime) = container_runtime.lock().await.guest_api_host(&event.originator) {
-                        if let Err(_) = runtime.cast_alias(&event.alias, String::from_utf8(event.msg).unwrap().as_str()).await {
+                        if runtime
+                            .cast_alias(&event.alias, event.partition_key.as_deref(), &event.msg)
+                            .await
+                            .is_err()
+                        {
                             log::error!("error occurred when casting an event towards alias {}: dropped", event.alias);
                         }
                     } else {
@@ -115,11 +119,7 @@ impl ContainerRuntime {
                     log::debug!("cast-raw, dst {}, msg {} bytes", event.dst, event.msg.len());
                     if let Some(runtime) = container_runtime.lock().await.guest_api_host(&event.originator) {
                         if let Err(_) = runtime
-                            .cast_raw(
-                                event.dst,
-                                edgeless_api::function_instance::PortId("direct".to_string()),
-                                String::from_utf8(event.msg).unwrap().as_str(),
-                            )
+                            .cast_raw(event.dst, edgeless_api::function_instance::PortId("direct".to_string()), &event.msg)
                             .await
                         {
                             log::error!("error occurred when raw-casting an event towards {}", event.dst);
@@ -136,14 +136,13 @@ impl ContainerRuntime {
                     log::debug!("call, alias {}, msg {} bytes", event.alias, event.msg.len());
                     let mut res = edgeless_api::guest_api_function::CallReturn::Err;
                     if let Some(runtime) = container_runtime.lock().await.guest_api_host(&event.originator) {
-                        match runtime.call_alias(&event.alias, String::from_utf8(event.msg).unwrap().as_str()).await {
+                        match runtime.call_alias(&event.alias, event.partition_key.as_deref(), &event.msg).await {
                             Ok(ret) => {
                                 res = match ret {
                                     edgeless_dataplane::core::CallRet::NoReply => edgeless_api::guest_api_function::CallReturn::NoRet,
-                                    edgeless_dataplane::core::CallRet::Reply(msg) => {
-                                        edgeless_api::guest_api_function::CallReturn::Reply(msg.as_bytes().to_vec())
-                                    }
+                                    edgeless_dataplane::core::CallRet::Reply(msg) => edgeless_api::guest_api_function::CallReturn::Reply(msg),
                                     edgeless_dataplane::core::CallRet::Err => edgeless_api::guest_api_function::CallReturn::Err,
+                                    edgeless_dataplane::core::CallRet::Timeout => edgeless_api::guest_api_function::CallReturn::Timeout,
                                 }
                             }
                             Err(_) => {
@@ -169,20 +168,15 @@ impl ContainerRuntime {
                     let mut res = edgeless_api::guest_api_function::CallReturn::Err;
                     if let Some(runtime) = container_runtime.lock().await.guest_api_host(&event.originator) {
                         match runtime
-                            .call_raw(
-                                event.dst,
-                                edgeless_api::function_instance::PortId("TODO".to_string()),
-                                String::from_utf8(event.msg).unwrap().as_str(),
-                            )
+                            .call_raw(event.dst, edgeless_api::function_instance::PortId("TODO".to_string()), &event.msg)
                             .await
                         {
                             Ok(ret) => {
                                 res = match ret {
                                     edgeless_dataplane::core::CallRet::NoReply => edgeless_api::guest_api_function::CallReturn::NoRet,
-                                    edgeless_dataplane::core::CallRet::Reply(msg) => {
-                                        edgeless_api::guest_api_function::CallReturn::Reply(msg.as_bytes().to_vec())
-                                    }
+                                    edgeless_dataplane::core::CallRet::Reply(msg) => edgeless_api::guest_api_function::CallReturn::Reply(msg),
                                     edgeless_dataplane::core::CallRet::Err => edgeless_api::guest_api_function::CallReturn::Err,
+                                    edgeless_dataplane::core::CallRet::Timeout => edgeless_api::guest_api_function::CallReturn::Timeout,
                                 }
                             }
                             Err(_) => {
@@ -263,10 +257,7 @@ impl ContainerRuntime {
                         event.msg.len()
                     );
                     if let Some(runtime) = container_runtime.lock().await.guest_api_host(&event.originator) {
-                        if let Err(_) = runtime
-                            .delayed_cast(event.delay, &event.alias, String::from_utf8(event.msg).unwrap().as_str())
-                            .await
-                        {
+                        if runtime.delayed_cast(event.delay, &event.alias, &event.msg).await.is_err() {
                             log::error!(
                                 "error occurred when casting an event with delay {} towards alias {}: dropped",
                                 event.delay,
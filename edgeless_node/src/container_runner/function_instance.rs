@@ -20,6 +20,7 @@ impl crate::base_runtime::FunctionInstance for ContainerFunctionInstance {
     async fn instantiate(
         instance_id: &edgeless_api::function_instance::InstanceId,
         runtime_configuration: std::collections::HashMap<String, String>,
+        _annotations: &std::collections::HashMap<String, String>,
         _guest_api_host: &mut Option<crate::base_runtime::guest_api::GuestAPIHost>,
         code: &[u8],
     ) -> Result<Box<Self>, crate::base_runtime::FunctionInstanceError> {
@@ -59,54 +60,7 @@ impl crate::base_runtime::FunctionInstance for ContainerFunctionInstance {
                 id = Some(fun_id);
             }
 
-            // TODO(ccicconetti) timeout is hard-coded to 30 seconds, which might
-            // not be enough with big containers
-            match edgeless_api::grpc_impl::container_function::ContainerFunctionAPIClient::new(&grpc_address, std::time::Duration::from_secs(30))
-                .await
-            {
-                Ok(mut _function_client) => {
-                    let mut function_client_api = _function_client.guest_api_function();
-
-                    match runtime_configuration.get("guest_api_host_url") {
-                        Some(url) => {
-                            let ts = std::time::Instant::now();
-                            loop {
-                                match function_client_api
-                                    .boot(edgeless_api::guest_api_function::BootData {
-                                        guest_api_host_endpoint: url.clone(),
-                                        instance_id: *instance_id,
-                                    })
-                                    .await
-                                {
-                                    Ok(_) => {
-                                        return Ok(Box::new(Self {
-                                            _function_client,
-                                            function_client_api,
-                                            id,
-                                        }))
-                                    }
-                                    Err(err) => {
-                                        if ts.elapsed() >= std::time::Duration::from_secs(30) {
-                                            log::error!("could not boot the container function instance: {}", err);
-                                            return Err(crate::base_runtime::FunctionInstanceError::InternalError);
-                                        } else {
-                                            let _ = tokio::time::sleep(std::time::Duration::from_millis(10)).await;
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                        None => {
-                            log::error!("invalid or missing guest_api_host_url");
-                            Err(crate::base_runtime::FunctionInstanceError::InternalError)
-                        }
-                    }
-                }
-                Err(err) => {
-                    log::error!("could not connect to the function instance at {}: {}", grpc_address, err);
-                    Err(crate::base_runtime::FunctionInstanceError::InternalError)
-                }
-            }
+            Self::connect(instance_id, &runtime_configuration, &grpc_address, id).await
         } else {
             log::error!("invalid container function specifier: {}", fun_spec);
             Err(crate::base_runtime::FunctionInstanceError::BadCode)
@@ -132,11 +86,14 @@ impl crate::base_runtime::FunctionInstance for ContainerFunctionInstance {
         &mut self,
         src: &edgeless_api::function_instance::InstanceId,
         port: &str,
-        msg: &str,
+        msg: &[u8],
     ) -> Result<(), crate::base_runtime::FunctionInstanceError> {
         log::debug!("container run-time: cast, src {}, msg {} bytes", src, msg.len());
         self.function_client_api
-            .cast(edgeless_api::guest_api_function::InputEventData { src: *src, msg: msg.into() })
+            .cast(edgeless_api::guest_api_function::InputEventData {
+                src: *src,
+                msg: msg.to_vec(),
+            })
             .await
             .or(Err(crate::base_runtime::FunctionInstanceError::InternalError))
     }
@@ -145,20 +102,22 @@ impl crate::base_runtime::FunctionInstance for ContainerFunctionInstance {
         &mut self,
         src: &edgeless_api::function_instance::InstanceId,
         port: &str,
-        msg: &str,
+        msg: &[u8],
     ) -> Result<edgeless_dataplane::core::CallRet, crate::base_runtime::FunctionInstanceError> {
         log::debug!("container run-time: call, src {}, msg {} bytes", src, msg.len());
         match self
             .function_client_api
-            .call(edgeless_api::guest_api_function::InputEventData { src: *src, msg: msg.into() })
+            .call(edgeless_api::guest_api_function::InputEventData {
+                src: *src,
+                msg: msg.to_vec(),
+            })
             .await
         {
             Ok(ret) => match ret {
                 edgeless_api::guest_api_function::CallReturn::NoRet => Ok(edgeless_dataplane::core::CallRet::NoReply),
-                edgeless_api::guest_api_function::CallReturn::Reply(msg) => {
-                    Ok(edgeless_dataplane::core::CallRet::Reply(String::from_utf8(msg).unwrap_or_default()))
-                }
+                edgeless_api::guest_api_function::CallReturn::Reply(msg) => Ok(edgeless_dataplane::core::CallRet::Reply(msg)),
                 edgeless_api::guest_api_function::CallReturn::Err => Ok(edgeless_dataplane::core::CallRet::Err),
+                edgeless_api::guest_api_function::CallReturn::Timeout => Ok(edgeless_dataplane::core::CallRet::Timeout),
             },
             Err(_) => Err(crate::base_runtime::FunctionInstanceError::InternalError),
         }
@@ -190,3 +149,62 @@ impl crate::base_runtime::FunctionInstance for ContainerFunctionInstance {
         Ok(())
     }
 }
+
+impl ContainerFunctionInstance {
+    /// Connects to the GuestAPIFunction server of a function instance at
+    /// `grpc_address` and boots it, for the containers started by this
+    /// run-time (`id`) or the processes started by the native run-time.
+    pub(crate) async fn connect(
+        instance_id: &edgeless_api::function_instance::InstanceId,
+        runtime_configuration: &std::collections::HashMap<String, String>,
+        grpc_address: &str,
+        id: Option<String>,
+    ) -> Result<Box<Self>, crate::base_runtime::FunctionInstanceError> {
+        // TODO(ccicconetti) timeout is hard-coded to 30 seconds, which might
+        // not be enough with big containers
+        match edgeless_api::grpc_impl::container_function::ContainerFunctionAPIClient::new(grpc_address, std::time::Duration::from_secs(30)).await {
+            Ok(mut _function_client) => {
+                let mut function_client_api = _function_client.guest_api_function();
+
+                match runtime_configuration.get("guest_api_host_url") {
+                    Some(url) => {
+                        let ts = std::time::Instant::now();
+                        loop {
+                            match function_client_api
+                                .boot(edgeless_api::guest_api_function::BootData {
+                                    guest_api_host_endpoint: url.clone(),
+                                    instance_id: *instance_id,
+                                })
+                                .await
+                            {
+                                Ok(_) => {
+                                    return Ok(Box::new(Self {
+                                        _function_client,
+                                        function_client_api,
+                                        id,
+                                    }))
+                                }
+                                Err(err) => {
+                                    if ts.elapsed() >= std::time::Duration::from_secs(30) {
+                                        log::error!("could not boot the container function instance: {}", err);
+                                        return Err(crate::base_runtime::FunctionInstanceError::InternalError);
+                                    } else {
+                                        let _ = tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    None => {
+                        log::error!("invalid or missing guest_api_host_url");
+                        Err(crate::base_runtime::FunctionInstanceError::InternalError)
+                    }
+                }
+            }
+            Err(err) => {
+                log::error!("could not connect to the function instance at {}: {}", grpc_address, err);
+                Err(crate::base_runtime::FunctionInstanceError::InternalError)
+            }
+        }
+    }
+}
@@ -9,6 +9,7 @@ use opentelemetry_otlp::WithExportConfig;
 pub mod agent;
 pub mod base_runtime;
 pub mod container_runner;
+pub mod process_runner;
 pub mod proxy;
 pub mod resources;
 pub mod state_management;
@@ -25,16 +26,38 @@ pub struct EdgelessNodeSettings {
     pub wasm_runtime: Option<EdgelessNodeWasmRuntimeSettings>,
     /// Container run-time settings.  Disabled if not present.
     pub container_runtime: Option<EdgelessNodeContainerRuntimeSettings>,
+    /// Native process run-time settings. Disabled if not present.
+    pub native_runtime: Option<EdgelessNodeNativeRuntimeSettings>,
     /// Resource settings.
     pub resources: Option<EdgelessNodeResourceSettings>,
     /// User-specific capabilities.
     pub user_node_capabilities: Option<NodeCapabilitiesUser>,
+    /// Dataplane settings. Input queues are unbounded if not present.
+    pub dataplane: Option<edgeless_dataplane::core::EdgelessDataplaneSettings>,
 }
 
 #[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
 pub struct EdgelessNodeWasmRuntimeSettings {
     /// True if WASM is enabled.
     pub enabled: bool,
+    /// Directory where the compiled modules are stored, to be reused after
+    /// a restart of the node. Modules are only cached in memory if empty.
+    /// It must be writable only by the node, since the modules in it are
+    /// loaded as native code.
+    #[serde(default)]
+    pub module_cache_dir: String,
+    /// Largest number of function instances that can exist at the same time,
+    /// whose memory is reserved in advance, which makes creating them faster.
+    /// Memory is reserved on demand, without a cap, if 0.
+    #[serde(default)]
+    pub pooling_instances: u32,
+    /// Directory below which the WASM function instances can get directories
+    /// through WASI. Instances cannot get directories if empty.
+    #[serde(default)]
+    pub wasi_dirs_root: String,
+    /// Default resource limits of the WASM function instances.
+    #[serde(flatten)]
+    pub limits: crate::base_runtime::limits::FunctionLimits,
 }
 
 #[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
@@ -45,6 +68,18 @@ pub struct EdgelessNodeContainerRuntimeSettings {
     pub guest_api_host_url: String,
 }
 
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct EdgelessNodeNativeRuntimeSettings {
+    /// True if the native process run-time is enabled.
+    pub enabled: bool,
+    /// End-point of the gRPC server to use for the GuestAPIHost interface.
+    pub guest_api_host_url: String,
+    /// Directory with the executables that can be run as functions, which
+    /// are trusted since they run with the same privileges as the node.
+    /// Must be an absolute path, otherwise the run-time is not enabled.
+    pub executables_dir: String,
+}
+
 #[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
 pub struct EdgelessNodeGeneralSettings {
     /// The UUID of this node.
@@ -65,6 +100,12 @@ pub struct EdgelessNodeGeneralSettings {
     /// The COAP invocation URL announced by the node.
     /// It can be different from `agent_url`, e.g., for NAT traversal.
     pub invocation_url_announced_coap: Option<String>,
+    /// The QUIC URL of the dataplane of this node, used for event dispatching
+    /// with the peers that have one too.
+    pub invocation_url_quic: Option<String>,
+    /// The QUIC invocation URL announced by the node.
+    /// It can be different from `invocation_url_quic`, e.g., for NAT traversal.
+    pub invocation_url_announced_quic: Option<String>,
     /// The URL exposed by this node to publish telemetry metrics collected.
     pub metrics_url: String,
     /// The URL of the controller to which this node registers.
@@ -151,13 +192,23 @@ impl EdgelessNodeSettings {
                 invocation_url_announced: invocation_url,
                 invocation_url_coap: invocation_url_coap.clone(),
                 invocation_url_announced_coap: invocation_url_coap,
+                invocation_url_quic: None,
+                invocation_url_announced_quic: None,
                 metrics_url: format!("http://{}:{}", node_address, metrics_port),
                 controller_url: controller_url.to_string(),
             },
-            wasm_runtime: Some(EdgelessNodeWasmRuntimeSettings { enabled: true }),
+            wasm_runtime: Some(EdgelessNodeWasmRuntimeSettings {
+                enabled: true,
+                module_cache_dir: String::new(),
+                pooling_instances: 0,
+                wasi_dirs_root: String::new(),
+                limits: crate::base_runtime::limits::FunctionLimits::default(),
+            }),
             container_runtime: None,
+            native_runtime: None,
             resources: None,
             user_node_capabilities: None,
+            dataplane: None,
         }
     }
 }
@@ -227,6 +278,13 @@ pub async fn register_node(
             resource_provider_specifications,
             capabilities,
             link_provider_specifications,
+            // The peers cannot use QUIC if the data plane does not support it.
+            settings
+                .invocation_url_announced_quic
+                .clone()
+                .filter(|url| !url.is_empty())
+                .or_else(|| settings.invocation_url_quic.clone().filter(|url| !url.is_empty()))
+                .filter(|_| cfg!(feature = "quic")),
         ))
         .await
     {
@@ -420,10 +478,12 @@ pub async fn edgeless_node_main(settings: EdgelessNodeSettings) {
     let state_manager = Box::new(state_management::StateManager::new().await);
 
     // Create the data plane.
-    let data_plane = edgeless_dataplane::handle::DataplaneProvider::new(
+    let data_plane = edgeless_dataplane::handle::DataplaneProvider::with_settings(
         settings.general.node_id,
         settings.general.invocation_url.clone(),
         settings.general.invocation_url_coap.clone(),
+        settings.general.invocation_url_quic.clone(),
+        settings.dataplane.clone().unwrap_or_default(),
     )
     .await;
 
@@ -461,8 +521,24 @@ pub async fn edgeless_node_main(settings: EdgelessNodeSettings) {
                     #[allow(unused_variables)]
                     #[cfg(feature = "wasmtime")]
                     {
+                        crate::wasm_runner::engine::SharedEngine::configure(crate::wasm_runner::engine::EngineSettings {
+                            module_cache_dir: match wasm_runtime_settings.module_cache_dir.is_empty() {
+                                true => None,
+                                false => Some(std::path::PathBuf::from(&wasm_runtime_settings.module_cache_dir)),
+                            },
+                            pooling_instances: wasm_runtime_settings.pooling_instances,
+                            limits: wasm_runtime_settings.limits,
+                        })
+                        .unwrap_or_else(|err| panic!("could not create the WASM engine: {}", err));
+                        let mut configuration = wasm_runtime_settings.limits.to_configuration();
+                        if !wasm_runtime_settings.wasi_dirs_root.is_empty() {
+                            configuration.insert(
+                                crate::wasm_runner::wasi::WASI_DIRS_ROOT_CONFIGURATION.to_string(),
+                                wasm_runtime_settings.wasi_dirs_root.clone(),
+                            );
+                        }
                         let (wasmtime_runtime_client, mut wasmtime_runtime_task_s) =
-                            base_runtime::runtime::create::<wasm_runner::function_instance::WASMFunctionInstance>(
+                            base_runtime::runtime::create::<wasm_runner::any_instance::AnyWASMFunctionInstance>(
                                 data_plane.clone(),
                                 state_manager.clone(),
                                 Box::new(telemetry_provider.get_handle(std::collections::BTreeMap::from([
@@ -470,7 +546,7 @@ pub async fn edgeless_node_main(settings: EdgelessNodeSettings) {
                                     ("WASM_RUNTIME".to_string(), "wasmtime".to_string()),
                                     ("NODE_ID".to_string(), settings.general.node_id.to_string()),
                                 ]))),
-                                std::sync::Arc::new(tokio::sync::Mutex::new(Box::new(crate::wasm_runner::runtime::WasmRuntime::new()))),
+                                std::sync::Arc::new(tokio::sync::Mutex::new(Box::new(crate::wasm_runner::runtime::WasmRuntime::with_configuration(configuration)))),
                             );
                         runners.insert("RUST_WASM".to_string(), Box::new(wasmtime_runtime_client.clone()));
                         tokio::spawn(async move {
@@ -490,7 +566,9 @@ pub async fn edgeless_node_main(settings: EdgelessNodeSettings) {
                                 ("WASM_RUNTIME".to_string(), "wasmi".to_string()),
                                 ("NODE_ID".to_string(), settings.general.node_id.to_string()),
                             ]))),
-                            std::sync::Arc::new(tokio::sync::Mutex::new(Box::new(crate::wasmi_runner::runtime::WasmiRuntime::new()))),
+                            std::sync::Arc::new(tokio::sync::Mutex::new(Box::new(crate::wasmi_runner::runtime::WasmiRuntime::with_configuration(
+                                wasm_runtime_settings.limits.to_configuration(),
+                            )))),
                         );
                         runners.insert("RUST_WASM".to_string(), Box::new(wasmi_runtime_client.clone()));
                         tokio::spawn(async move {
@@ -536,6 +614,50 @@ pub async fn edgeless_node_main(settings: EdgelessNodeSettings) {
         None => tokio::spawn(async {}),
     };
 
+    // Create the native process run-time, if needed.
+    let native_runtime_task = match settings.native_runtime {
+        Some(native_runtime_settings) => match native_runtime_settings.enabled {
+            // Never enabled with an invalid executables directory.
+            true => match process_runner::function_instance::executables_dir(&native_runtime_settings.executables_dir) {
+                Ok(executables_dir) => {
+                    let (native_runtime, native_runtime_task, native_runtime_api) =
+                        container_runner::container_runtime::ContainerRuntime::new(std::collections::HashMap::from([
+                            ("guest_api_host_url".to_string(), native_runtime_settings.guest_api_host_url.clone()),
+                            (
+                                process_runner::function_instance::EXECUTABLES_DIR_CONFIGURATION.to_string(),
+                                executables_dir.to_string_lossy().to_string(),
+                            ),
+                        ]));
+                    let server_task = edgeless_api::grpc_impl::container_runtime::GuestAPIHostServer::run(
+                        native_runtime_api,
+                        native_runtime_settings.guest_api_host_url,
+                    );
+
+                    let (native_runtime_client, mut native_runtime_task_s) =
+                        base_runtime::runtime::create::<process_runner::function_instance::ProcessFunctionInstance>(
+                            data_plane.clone(),
+                            state_manager.clone(),
+                            Box::new(telemetry_provider.get_handle(std::collections::BTreeMap::from([
+                                ("FUNCTION_TYPE".to_string(), "NATIVE_PROCESS".to_string()),
+                                ("NODE_ID".to_string(), settings.general.node_id.to_string()),
+                            ]))),
+                            native_runtime.clone(),
+                        );
+                    runners.insert("NATIVE_PROCESS".to_string(), Box::new(native_runtime_client.clone()));
+                    tokio::spawn(async move {
+                        futures::join!(native_runtime_task_s.run(), native_runtime_task, server_task);
+                    })
+                }
+                Err(err) => {
+                    log::error!("the native process run-time is not enabled, invalid executables_dir: {}", err);
+                    tokio::spawn(async {})
+                }
+            },
+            false => tokio::spawn(async {}),
+        },
+        None => tokio::spawn(async {}),
+    };
+
     // Create the resources.
     let mut resource_provider_specifications = vec![];
     let resources = fill_resources(
@@ -550,13 +672,21 @@ pub async fn edgeless_node_main(settings: EdgelessNodeSettings) {
 
     // Create the agent.
     let runtimes = runners.keys().map(|x| x.to_string()).collect::<Vec<String>>();
-    let (mut agent, agent_task) = agent::Agent::new(runners, resources, settings.general.node_id, data_plane.clone(), proxy_manager);
+    let (mut agent, agent_task) = agent::Agent::new(
+        runners,
+        resources,
+        settings.general.node_id,
+        data_plane.clone(),
+        proxy_manager,
+        telemetry_provider.instance_statistics(),
+    );
     let agent_api_server = edgeless_api::grpc_impl::agent::AgentAPIServer::run(agent.get_api_client(), settings.general.agent_url.clone());
 
     // Wait for all the tasks to complete.
     let _ = futures::join!(
         rust_runtime_task,
         container_runtime_task,
+        native_runtime_task,
         agent_task,
         agent_api_server,
         register_node(
@@ -586,16 +716,30 @@ invocation_url = "http://127.0.0.1:7002"
 invocation_url_announced = ""
 invocation_url_coap = "coap://127.0.0.1:7002"
 invocation_url_announced_coap = ""
+invocation_url_quic = ""
+invocation_url_announced_quic = ""
 metrics_url = "http://127.0.0.1:7003"
 controller_url = "http://127.0.0.1:7001"
 
 [wasm_runtime]
 enabled = true
+module_cache_dir = ""
+pooling_instances = 0
+wasi_dirs_root = ""
+max_memory_pages = 0
+max_execution_time_ms = 0
+max_table_elements = 0
+max_wasm_instances = 0
 
 [container_runtime]
 enabled = false
 guest_api_host_url = "http://127.0.0.1:7100"
 
+[native_runtime]
+enabled = false
+guest_api_host_url = "http://127.0.0.1:7110"
+executables_dir = ""
+
 [resources]
 http_ingress_url = "http://127.0.0.1:7035"
 http_ingress_provider = "http-ingress-1"
@@ -611,6 +755,12 @@ port = 11434
 messages_number_limit = 30
 provider = "ollama-1"
 
+[dataplane]
+queue_capacity = 1024
+overflow_policy = "drop-oldest"
+call_timeout_ms = 30000
+disable_multicast_links = false
+
 [user_node_capabilities]
 "##,
         caps.num_cpus,
@@ -0,0 +1,410 @@
+// SPDX-FileCopyrightText: © 2024 Technical University of Munich, Chair of Connected Mobility
+// SPDX-License-Identifier: MIT
+
+/// Key of the runtime configuration with the directory that contains the
+/// executables that can be run as function instances.
+pub const EXECUTABLES_DIR_CONFIGURATION: &str = "executables_dir";
+
+/// Number of times that the process of a function instance is started, with
+/// a new port each time, if its port is taken by another process first.
+const MAX_START_ATTEMPTS: u32 = 3;
+
+/// FunctionInstance implementation allowing to execute functions defined as
+/// local executables, e.g., built from the `edgeless_container_function`
+/// template, without a container engine.
+///
+/// The code of the function is the path of the executable, relative to the
+/// executables directory of the node, optionally followed by arguments
+/// separated by whitespace. The executable is run with these arguments and
+/// `--endpoint <URL>`, where it must serve the `GuestAPIFunction` API.
+pub struct ProcessFunctionInstance {
+    /// Interaction with the process, which is the same as with a container.
+    function: Box<crate::container_runner::function_instance::ContainerFunctionInstance>,
+    /// Killed when the function instance is stopped or dropped.
+    child: tokio::process::Child,
+}
+
+#[async_trait::async_trait]
+impl crate::base_runtime::FunctionInstance for ProcessFunctionInstance {
+    async fn instantiate(
+        instance_id: &edgeless_api::function_instance::InstanceId,
+        runtime_configuration: std::collections::HashMap<String, String>,
+        _annotations: &std::collections::HashMap<String, String>,
+        _guest_api_host: &mut Option<crate::base_runtime::guest_api::GuestAPIHost>,
+        code: &[u8],
+    ) -> Result<Box<Self>, crate::base_runtime::FunctionInstanceError> {
+        let fun_spec = String::from_utf8(code.to_vec()).unwrap_or_default();
+        log::info!("native run-time: instantiate {}", fun_spec);
+
+        let executables_dir = match runtime_configuration.get(EXECUTABLES_DIR_CONFIGURATION).map(|dir| executables_dir(dir)) {
+            Some(Ok(dir)) => dir,
+            Some(Err(err)) => {
+                log::error!("invalid {}: {}", EXECUTABLES_DIR_CONFIGURATION, err);
+                return Err(crate::base_runtime::FunctionInstanceError::InternalError);
+            }
+            None => {
+                log::error!("missing {}", EXECUTABLES_DIR_CONFIGURATION);
+                return Err(crate::base_runtime::FunctionInstanceError::InternalError);
+            }
+        };
+        let mut tokens = fun_spec.split_whitespace();
+        let executable = match tokens.next().and_then(|name| executable_path(&executables_dir, name)) {
+            Some(executable) => executable,
+            None => {
+                log::error!("invalid native function specifier: {}", fun_spec);
+                return Err(crate::base_runtime::FunctionInstanceError::BadCode);
+            }
+        };
+        let args: Vec<&str> = tokens.collect();
+
+        let mut attempt = 1;
+        loop {
+            let port = match std::net::TcpListener::bind("127.0.0.1:0").and_then(|listener| listener.local_addr()) {
+                Ok(addr) => addr.port(),
+                Err(err) => {
+                    log::error!("could not find a free port for the native function: {}", err);
+                    return Err(crate::base_runtime::FunctionInstanceError::InternalError);
+                }
+            };
+            let grpc_address = format!("http://127.0.0.1:{}/", port);
+
+            let mut child = match tokio::process::Command::new(&executable)
+                .args(&args)
+                .arg("--endpoint")
+                .arg(&grpc_address)
+                .kill_on_drop(true)
+                .spawn()
+            {
+                Ok(child) => child,
+                Err(err) => {
+                    log::error!("could not run {}: {}", executable.display(), err);
+                    return Err(crate::base_runtime::FunctionInstanceError::BadCode);
+                }
+            };
+            log::info!(
+                "started {} PID {:?} GuestAPIFunction URL {}",
+                executable.display(),
+                child.id(),
+                grpc_address
+            );
+
+            // Not waiting for the connection to time out if the process fails.
+            tokio::select! {
+                function = crate::container_runner::function_instance::ContainerFunctionInstance::connect(
+                    instance_id,
+                    &runtime_configuration,
+                    &grpc_address,
+                    None,
+                ) => return Ok(Box::new(Self { function: function?, child })),
+                status = child.wait() => {
+                    // The port was free when found, but another process may
+                    // have taken it before the function could.
+                    if attempt < MAX_START_ATTEMPTS && std::net::TcpListener::bind(("127.0.0.1", port)).is_err() {
+                        log::warn!("{} could not use port {}, starting it again", executable.display(), port);
+                        attempt += 1;
+                        continue;
+                    }
+                    log::error!("{} exited before booting: {:?}", executable.display(), status);
+                    return Err(crate::base_runtime::FunctionInstanceError::BadCode);
+                }
+            }
+        }
+    }
+
+    async fn init(&mut self, init_payload: Option<&str>, serialized_state: Option<&str>) -> Result<(), crate::base_runtime::FunctionInstanceError> {
+        self.function.init(init_payload, serialized_state).await
+    }
+
+    async fn cast(
+        &mut self,
+        src: &edgeless_api::function_instance::InstanceId,
+        port: &str,
+        msg: &[u8],
+    ) -> Result<(), crate::base_runtime::FunctionInstanceError> {
+        self.function.cast(src, port, msg).await
+    }
+
+    async fn call(
+        &mut self,
+        src: &edgeless_api::function_instance::InstanceId,
+        port: &str,
+        msg: &[u8],
+    ) -> Result<edgeless_dataplane::core::CallRet, crate::base_runtime::FunctionInstanceError> {
+        self.function.call(src, port, msg).await
+    }
+
+    async fn stop(&mut self) -> Result<(), crate::base_runtime::FunctionInstanceError> {
+        let res = self.function.stop().await;
+        if let Err(err) = self.child.kill().await {
+            log::error!("could not kill the native function process: {}", err);
+            return Err(crate::base_runtime::FunctionInstanceError::InternalError);
+        }
+        res
+    }
+}
+
+/// Executables directory of the node, which must be the absolute path of a
+/// directory, in canonical form.
+pub fn executables_dir(dir: &str) -> anyhow::Result<std::path::PathBuf> {
+    let path = std::path::Path::new(dir);
+    if !path.is_absolute() {
+        anyhow::bail!("not an absolute path: '{}'", dir);
+    }
+    let path = std::fs::canonicalize(path)?;
+    match path.is_dir() {
+        true => Ok(path),
+        false => Err(anyhow::anyhow!("not a directory: {}", dir)),
+    }
+}
+
+/// Path of the executable with the given name, which cannot be outside of
+/// the executables directory, not even through symbolic links.
+fn executable_path(executables_dir: &std::path::Path, name: &str) -> Option<std::path::PathBuf> {
+    let name = std::path::Path::new(name);
+    let relative = name.components().all(|component| matches!(component, std::path::Component::Normal(_)));
+    if name.as_os_str().is_empty() || !relative {
+        return None;
+    }
+    let path = std::fs::canonicalize(executables_dir.join(name)).ok()?;
+    match path.starts_with(executables_dir) {
+        true => Some(path),
+        false => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::base_runtime::FunctionInstance;
+
+    /// Environment variable with the endpoint at which native_function_fixture()
+    /// serves the GuestAPIFunction API, when run by the script of
+    /// container_function_lifecycle().
+    const FIXTURE_ENDPOINT_ENV: &str = "EDGELESS_NATIVE_FUNCTION_FIXTURE_ENDPOINT";
+
+    /// Writes an executable shell script with the given body.
+    #[cfg(unix)]
+    fn write_script(path: &std::path::Path, body: &str) {
+        std::fs::write(path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+        let mut permissions = std::fs::metadata(path).unwrap().permissions();
+        std::os::unix::fs::PermissionsExt::set_mode(&mut permissions, 0o755);
+        std::fs::set_permissions(path, permissions).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn executables_stay_in_dir() {
+        let dir = std::env::temp_dir().join(format!("edgeless-native-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("analytics")).unwrap();
+        std::fs::write(dir.join("analytics/run.py"), "").unwrap();
+        std::os::unix::fs::symlink("/bin/sh", dir.join("sh")).unwrap();
+        let dir = super::executables_dir(dir.to_str().unwrap()).unwrap();
+
+        assert_eq!(super::executable_path(&dir, "analytics/run.py"), Some(dir.join("analytics/run.py")));
+        assert!(super::executable_path(&dir, "../../../bin/sh").is_none());
+        assert!(super::executable_path(&dir, "/bin/sh").is_none());
+        assert!(super::executable_path(&dir, "").is_none());
+        assert!(super::executable_path(&dir, "sh").is_none());
+        assert!(super::executable_path(&dir, "missing.py").is_none());
+
+        assert!(super::executables_dir("").is_err());
+        assert!(super::executables_dir("bin").is_err());
+        assert!(super::executables_dir(dir.join("analytics/run.py").to_str().unwrap()).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn process_exiting_before_boot() {
+        let dir = std::env::temp_dir().join(format!("edgeless-native-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        write_script(&dir.join("exit.sh"), "exit 1");
+
+        let instance_id = edgeless_api::function_instance::InstanceId::new(uuid::Uuid::new_v4());
+        let configuration = std::collections::HashMap::from([
+            (super::EXECUTABLES_DIR_CONFIGURATION.to_string(), dir.to_string_lossy().to_string()),
+            ("guest_api_host_url".to_string(), "http://127.0.0.1:7100".to_string()),
+        ]);
+        let annotations = std::collections::HashMap::new();
+
+        let start = std::time::Instant::now();
+        for code in ["exit.sh", "missing.sh", "../exit.sh"] {
+            let res =
+                super::ProcessFunctionInstance::instantiate(&instance_id, configuration.clone(), &annotations, &mut None, code.as_bytes()).await;
+            assert!(matches!(res, Err(crate::base_runtime::FunctionInstanceError::BadCode)));
+        }
+        assert!(start.elapsed() < std::time::Duration::from_secs(10));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[derive(Clone)]
+    struct MockGuestAPIHost {
+        sender: tokio::sync::mpsc::UnboundedSender<edgeless_api::guest_api_host::OutputEventData>,
+    }
+
+    #[cfg(unix)]
+    impl edgeless_api::container_runtime::ContainerRuntimeAPI for MockGuestAPIHost {
+        fn guest_api_host(&mut self) -> Box<dyn edgeless_api::guest_api_host::GuestAPIHost> {
+            Box::new(self.clone())
+        }
+    }
+
+    #[cfg(unix)]
+    #[async_trait::async_trait]
+    impl edgeless_api::guest_api_host::GuestAPIHost for MockGuestAPIHost {
+        async fn cast(&mut self, event: edgeless_api::guest_api_host::OutputEventData) -> anyhow::Result<()> {
+            Ok(self.sender.send(event)?)
+        }
+        async fn cast_raw(&mut self, _event: edgeless_api::guest_api_host::OutputEventDataRaw) -> anyhow::Result<()> {
+            Ok(())
+        }
+        async fn call(
+            &mut self,
+            _event: edgeless_api::guest_api_host::OutputEventData,
+        ) -> anyhow::Result<edgeless_api::guest_api_function::CallReturn> {
+            Ok(edgeless_api::guest_api_function::CallReturn::NoRet)
+        }
+        async fn call_raw(
+            &mut self,
+            _event: edgeless_api::guest_api_host::OutputEventDataRaw,
+        ) -> anyhow::Result<edgeless_api::guest_api_function::CallReturn> {
+            Ok(edgeless_api::guest_api_function::CallReturn::NoRet)
+        }
+        async fn telemetry_log(&mut self, _event: edgeless_api::guest_api_host::TelemetryLogEvent) -> anyhow::Result<()> {
+            Ok(())
+        }
+        async fn slf(&mut self) -> anyhow::Result<edgeless_api::function_instance::InstanceId> {
+            Ok(edgeless_api::function_instance::InstanceId::none())
+        }
+        async fn delayed_cast(&mut self, _event: edgeless_api::guest_api_host::DelayedEventData) -> anyhow::Result<()> {
+            Ok(())
+        }
+        async fn sync(&mut self, _event: edgeless_api::guest_api_host::SyncData) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Function that forwards the events cast to it to its output, like the
+    /// one built from the `edgeless_container_function` template.
+    #[derive(Clone, Default)]
+    struct ForwardingFunction {
+        booted: std::sync::Arc<tokio::sync::Mutex<Option<BootedFunction>>>,
+    }
+
+    struct BootedFunction {
+        instance_id: edgeless_api::function_instance::InstanceId,
+        host: Box<dyn edgeless_api::guest_api_host::GuestAPIHost>,
+    }
+
+    impl edgeless_api::container_function::ContainerFunctionAPI for ForwardingFunction {
+        fn guest_api_function(&mut self) -> Box<dyn edgeless_api::guest_api_function::GuestAPIFunction> {
+            Box::new(self.clone())
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl edgeless_api::guest_api_function::GuestAPIFunction for ForwardingFunction {
+        async fn boot(&mut self, boot_data: edgeless_api::guest_api_function::BootData) -> anyhow::Result<()> {
+            let mut client =
+                edgeless_api::grpc_impl::container_runtime::ContainerRuntimeAPIClient::new(&boot_data.guest_api_host_endpoint, None).await?;
+            *self.booted.lock().await = Some(BootedFunction {
+                instance_id: boot_data.instance_id,
+                host: edgeless_api::container_runtime::ContainerRuntimeAPI::guest_api_host(&mut client),
+            });
+            Ok(())
+        }
+        async fn init(&mut self, _init_data: edgeless_api::guest_api_function::FunctionInstanceInit) -> anyhow::Result<()> {
+            Ok(())
+        }
+        async fn cast(&mut self, event: edgeless_api::guest_api_function::InputEventData) -> anyhow::Result<()> {
+            match &mut *self.booted.lock().await {
+                Some(booted) => {
+                    booted
+                        .host
+                        .cast(edgeless_api::guest_api_host::OutputEventData {
+                            originator: booted.instance_id,
+                            alias: "output".to_string(),
+                            msg: event.msg,
+                            partition_key: None,
+                        })
+                        .await
+                }
+                None => Err(anyhow::anyhow!("cast before boot")),
+            }
+        }
+        async fn call(
+            &mut self,
+            _event: edgeless_api::guest_api_function::InputEventData,
+        ) -> anyhow::Result<edgeless_api::guest_api_function::CallReturn> {
+            Ok(edgeless_api::guest_api_function::CallReturn::NoRet)
+        }
+        async fn stop(&mut self) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    // Not a test on its own: the process of a native function in
+    // container_function_lifecycle(), which runs the test binary again with
+    // only this test and the endpoint of the function.
+    #[tokio::test]
+    async fn native_function_fixture() {
+        if let Ok(endpoint) = std::env::var(FIXTURE_ENDPOINT_ENV) {
+            edgeless_api::grpc_impl::container_function::GuestAPIFunctionServer::run(Box::new(ForwardingFunction::default()), endpoint).await;
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn container_function_lifecycle() {
+        // The native function is a script running native_function_fixture()
+        // with the endpoint passed by the run-time in --endpoint <URL>.
+        let dir = std::env::temp_dir().join(format!("edgeless-native-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let fixture = concat!(module_path!(), "::native_function_fixture").split_once("::").unwrap().1;
+        write_script(
+            &dir.join("forward.sh"),
+            &format!(
+                "exec env {}=\"$2\" '{}' --exact {} --nocapture > /dev/null",
+                FIXTURE_ENDPOINT_ENV,
+                std::env::current_exe().unwrap().display(),
+                fixture
+            ),
+        );
+
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let guest_api_host_url = format!("http://127.0.0.1:{}", port);
+        tokio::spawn(edgeless_api::grpc_impl::container_runtime::GuestAPIHostServer::run(
+            Box::new(MockGuestAPIHost { sender }),
+            guest_api_host_url.clone(),
+        ));
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+        let instance_id = edgeless_api::function_instance::InstanceId::new(uuid::Uuid::new_v4());
+        let configuration = std::collections::HashMap::from([
+            (super::EXECUTABLES_DIR_CONFIGURATION.to_string(), dir.to_string_lossy().to_string()),
+            ("guest_api_host_url".to_string(), guest_api_host_url),
+        ]);
+        let mut instance =
+            super::ProcessFunctionInstance::instantiate(&instance_id, configuration, &std::collections::HashMap::new(), &mut None, b"forward.sh")
+                .await
+                .unwrap();
+
+        instance.init(Some("payload"), None).await.unwrap();
+        instance.cast(&instance_id, "input", b"hello").await.unwrap();
+        let event = tokio::time::timeout(std::time::Duration::from_secs(10), receiver.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(event.originator, instance_id);
+        assert_eq!(event.alias, "output");
+        assert_eq!(event.msg, b"hello");
+
+        instance.stop().await.unwrap();
+        assert!(instance.child.try_wait().unwrap().is_some());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,6 @@
+// SPDX-FileCopyrightText: © 2024 Technical University of Munich, Chair of Connected Mobility
+// SPDX-License-Identifier: MIT
+
+/// Function instances run as child processes of the node, which interact
+/// with it through the same gRPC API as the container function instances.
+pub mod function_instance;
@@ -191,7 +191,7 @@ impl DDAResource {
                     }
                 };
 
-                let dpmd: Result<DataplanePubMessage, Error> = serde_json::from_str(&message_data);
+                let dpmd: Result<DataplanePubMessage, Error> = serde_json::from_slice(&message_data);
 
                 let msg_obj = match dpmd {
                     Ok(msg) => msg,
@@ -204,7 +204,7 @@ impl DDAResource {
                 if let Some(dda_pub_task) = dda_pub_array.iter().find(|&p| p.pubid == msg_obj.pubid) {
                     log::info!(
                         "Dataplane message is {} and corresponding DDA pubid is {}",
-                        message_data,
+                        String::from_utf8_lossy(&message_data),
                         dda_pub_task.pubid
                     );
 
@@ -225,7 +225,7 @@ impl DDAResource {
                                         // we need a reply in case of a call from the dataplane
                                         if need_reply {
                                             dataplane_handle
-                                                .reply(source_id, channel_id, edgeless_dataplane::core::CallRet::Reply("".to_string()))
+                                                .reply(source_id, channel_id, edgeless_dataplane::core::CallRet::Reply(vec![]))
                                                 .await;
                                         }
                                     }
@@ -62,9 +62,10 @@ impl FileLogResource {
                     continue;
                 }
 
+                let message_data = String::from_utf8_lossy(&message_data);
                 let line = match add_timestamp {
                     true => format!("{} {}", chrono::Utc::now().to_rfc3339(), message_data),
-                    false => message_data,
+                    false => message_data.to_string(),
                 };
 
                 log::debug!("{}", line);
@@ -74,7 +75,7 @@ impl FileLogResource {
 
                 if need_reply {
                     dataplane_handle
-                        .reply(source_id, channel_id, edgeless_dataplane::core::CallRet::Reply("".to_string()))
+                        .reply(source_id, channel_id, edgeless_dataplane::core::CallRet::Reply(vec![]))
                         .await;
                 }
             }
@@ -49,7 +49,7 @@ impl EgressResource {
                     continue;
                 }
 
-                let req = match edgeless_http::request_from_string(&message_data) {
+                let req = match serde_json::from_slice::<edgeless_http::EdgelessHTTPRequest>(&message_data) {
                     Ok(val) => val,
                     Err(_) => {
                         dataplane_handle
@@ -64,7 +64,7 @@ impl EgressResource {
                         Ok(resp) => {
                             let serialized_resp = edgeless_http::response_to_string(&resp);
                             cloned_dataplane
-                                .reply(source_id, channel_id, edgeless_dataplane::core::CallRet::Reply(serialized_resp))
+                                .reply(source_id, channel_id, edgeless_dataplane::core::CallRet::Reply(serialized_resp.into_bytes()))
                                 .await;
                         }
                         Err(_) => {
@@ -80,7 +80,7 @@ impl hyper::service::Service<hyper::Request<hyper::body::Incoming>> for IngressS
                 let serialized_msg = serde_json::to_string(&msg)?;
                 let res = lck.dataplane.call(target, target_port, serialized_msg, request_context.clone()).await;
                 if let edgeless_dataplane::core::CallRet::Reply(data) = res {
-                    let processor_response: edgeless_http::EdgelessHTTPResponse = serde_json::from_str(&data)?;
+                    let processor_response: edgeless_http::EdgelessHTTPResponse = serde_json::from_slice(&data)?;
                     let mut response_builder = hyper::Response::new(http_body_util::Full::new(hyper::body::Bytes::from(
                         processor_response.body.unwrap_or_default(),
                     )));
@@ -97,6 +97,10 @@ impl hyper::service::Service<hyper::Request<hyper::body::Incoming>> for IngressS
                         }
                     }
                     return Ok(response_builder);
+                } else if let edgeless_dataplane::core::CallRet::Timeout = res {
+                    let mut timeout = hyper::Response::new(http_body_util::Full::new(hyper::body::Bytes::from("Gateway Timeout")));
+                    *timeout.status_mut() = hyper::StatusCode::GATEWAY_TIMEOUT;
+                    return Ok(timeout);
                 }
             }
 
@@ -86,7 +86,13 @@ impl OllamaResource {
 
                 // Ignore any non-cast messages.
                 let prompt = match message {
-                    edgeless_dataplane::core::Message::Cast(data) => data,
+                    edgeless_dataplane::core::Message::Cast(data) => match String::from_utf8(data) {
+                        Ok(prompt) => prompt,
+                        Err(_) => {
+                            log::warn!("Ignoring a prompt that is not valid UTF-8");
+                            continue;
+                        }
+                    },
                     _ => {
                         continue;
                     }
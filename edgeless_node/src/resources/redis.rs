@@ -57,13 +57,13 @@ impl RedisResource {
                     }
                 };
 
-                if let Err(e) = connection.set::<&str, &str, std::string::String>(&redis_key, &message_data) {
-                    log::error!("Could not set key '{}' to value '{}': {}", redis_key, &message_data, e);
+                if let Err(e) = connection.set::<&str, &[u8], std::string::String>(&redis_key, &message_data) {
+                    log::error!("Could not set key '{}' to a value of {} bytes: {}", redis_key, message_data.len(), e);
                 }
 
                 if need_reply {
                     dataplane_handle
-                        .reply(source_id, channel_id, edgeless_dataplane::core::CallRet::Reply("".to_string()))
+                        .reply(source_id, channel_id, edgeless_dataplane::core::CallRet::Reply(vec![]))
                         .await;
                 }
             }
@@ -0,0 +1,71 @@
+// SPDX-FileCopyrightText: © 2024 Technical University of Munich, Chair of Connected Mobility
+// SPDX-License-Identifier: MIT
+use crate::base_runtime::FunctionInstance;
+
+/// FunctionInstance implementation for both WASM core modules, with the
+/// guest API of `edgeless_function`, and WASM components, chosen by the
+/// format of the code.
+pub enum AnyWASMFunctionInstance {
+    Module(Box<super::function_instance::WASMFunctionInstance>),
+    Component(Box<super::component::ComponentFunctionInstance>),
+}
+
+#[async_trait::async_trait]
+impl crate::base_runtime::FunctionInstance for AnyWASMFunctionInstance {
+    async fn instantiate(
+        instance_id: &edgeless_api::function_instance::InstanceId,
+        runtime_configuration: std::collections::HashMap<String, String>,
+        annotations: &std::collections::HashMap<String, String>,
+        guest_api_host: &mut Option<crate::base_runtime::guest_api::GuestAPIHost>,
+        code: &[u8],
+    ) -> Result<Box<Self>, crate::base_runtime::FunctionInstanceError> {
+        Ok(Box::new(match super::component::is_component(code) {
+            true => Self::Component(
+                super::component::ComponentFunctionInstance::instantiate(instance_id, runtime_configuration, annotations, guest_api_host, code)
+                    .await?,
+            ),
+            false => Self::Module(
+                super::function_instance::WASMFunctionInstance::instantiate(instance_id, runtime_configuration, annotations, guest_api_host, code)
+                    .await?,
+            ),
+        }))
+    }
+
+    async fn init(&mut self, init_payload: Option<&str>, serialized_state: Option<&str>) -> Result<(), crate::base_runtime::FunctionInstanceError> {
+        match self {
+            Self::Module(instance) => instance.init(init_payload, serialized_state).await,
+            Self::Component(instance) => instance.init(init_payload, serialized_state).await,
+        }
+    }
+
+    async fn cast(
+        &mut self,
+        src: &edgeless_api::function_instance::InstanceId,
+        port: &str,
+        msg: &[u8],
+    ) -> Result<(), crate::base_runtime::FunctionInstanceError> {
+        match self {
+            Self::Module(instance) => instance.cast(src, port, msg).await,
+            Self::Component(instance) => instance.cast(src, port, msg).await,
+        }
+    }
+
+    async fn call(
+        &mut self,
+        src: &edgeless_api::function_instance::InstanceId,
+        port: &str,
+        msg: &[u8],
+    ) -> Result<edgeless_dataplane::core::CallRet, crate::base_runtime::FunctionInstanceError> {
+        match self {
+            Self::Module(instance) => instance.call(src, port, msg).await,
+            Self::Component(instance) => instance.call(src, port, msg).await,
+        }
+    }
+
+    async fn stop(&mut self) -> Result<(), crate::base_runtime::FunctionInstanceError> {
+        match self {
+            Self::Module(instance) => instance.stop().await,
+            Self::Component(instance) => instance.stop().await,
+        }
+    }
+}
@@ -0,0 +1,267 @@
+// SPDX-FileCopyrightText: © 2024 Technical University of Munich, Chair of Connected Mobility
+// SPDX-License-Identifier: MIT
+
+wasmtime::component::bindgen!({
+    path: "../edgeless_function/wit",
+    world: "edgeless-function",
+    async: true,
+});
+
+use edgeless::function::types;
+
+/// True if the code is a WASM component, rather than a core module, which
+/// differ by the layer in the header of the binary.
+pub fn is_component(code: &[u8]) -> bool {
+    code.len() >= 8 && code[0..4] == *b"\0asm" && code[6..8] == [1, 0]
+}
+
+/// FunctionInstance implementation for WASM components of the
+/// `edgeless-function` world, which is defined in WIT by `edgeless_function`.
+pub struct ComponentFunctionInstance {
+    bindings: EdgelessFunction,
+    store: wasmtime::Store<super::guest_api_binding::GuestAPI>,
+    /// Epochs after which an invocation is interrupted, if limited.
+    epoch_deadline: Option<u64>,
+}
+
+#[async_trait::async_trait]
+impl crate::base_runtime::FunctionInstance for ComponentFunctionInstance {
+    async fn instantiate(
+        _instance_id: &edgeless_api::function_instance::InstanceId,
+        runtime_configuration: std::collections::HashMap<String, String>,
+        annotations: &std::collections::HashMap<String, String>,
+        guest_api_host: &mut Option<crate::base_runtime::guest_api::GuestAPIHost>,
+        code: &[u8],
+    ) -> Result<Box<Self>, crate::base_runtime::FunctionInstanceError> {
+        let engine = super::engine::SharedEngine::get().map_err(|_| crate::base_runtime::FunctionInstanceError::InternalError)?;
+        let instance_pre = engine
+            .component_instance_pre(code)
+            .map_err(|_| crate::base_runtime::FunctionInstanceError::BadCode)?;
+        let (mut store, epoch_deadline) = super::function_instance::new_store(&engine, &runtime_configuration, annotations, guest_api_host)?;
+
+        let bindings = EdgelessFunction::instantiate_pre(&mut store, &instance_pre)
+            .await
+            .map_err(super::function_instance::instantiation_error);
+        let (bindings, _instance) = store.data().limiter.check(bindings)?;
+
+        Ok(Box::new(Self {
+            bindings,
+            store,
+            epoch_deadline,
+        }))
+    }
+
+    async fn init(&mut self, init_payload: Option<&str>, serialized_state: Option<&str>) -> Result<(), crate::base_runtime::FunctionInstanceError> {
+        self.start_invocation();
+        let res = self
+            .bindings
+            .call_handle_init(&mut self.store, init_payload.map(|payload| payload.as_bytes()), serialized_state)
+            .await
+            .map_err(|_| crate::base_runtime::FunctionInstanceError::InternalError);
+        self.store.data().limiter.check(res)
+    }
+
+    async fn cast(
+        &mut self,
+        src: &edgeless_api::function_instance::InstanceId,
+        port: &str,
+        msg: &[u8],
+    ) -> Result<(), crate::base_runtime::FunctionInstanceError> {
+        self.start_invocation();
+        let res = self
+            .bindings
+            .call_handle_cast(&mut self.store, &to_wit_instance_id(src), port, msg)
+            .await
+            .map_err(|_| crate::base_runtime::FunctionInstanceError::BadCode);
+        self.store.data().limiter.check(res)
+    }
+
+    async fn call(
+        &mut self,
+        src: &edgeless_api::function_instance::InstanceId,
+        port: &str,
+        msg: &[u8],
+    ) -> Result<edgeless_dataplane::core::CallRet, crate::base_runtime::FunctionInstanceError> {
+        self.start_invocation();
+        let res = self
+            .bindings
+            .call_handle_call(&mut self.store, &to_wit_instance_id(src), port, msg)
+            .await
+            .map(from_wit_call_ret)
+            .map_err(|_| crate::base_runtime::FunctionInstanceError::BadCode);
+        self.store.data().limiter.check(res)
+    }
+
+    async fn stop(&mut self) -> Result<(), crate::base_runtime::FunctionInstanceError> {
+        self.start_invocation();
+        let res = self
+            .bindings
+            .call_handle_stop(&mut self.store)
+            .await
+            .map_err(|_| crate::base_runtime::FunctionInstanceError::BadCode);
+        self.store.data().limiter.check(res)
+    }
+}
+
+impl ComponentFunctionInstance {
+    /// Gives the next invocation its whole execution time.
+    fn start_invocation(&mut self) {
+        if let Some(deadline) = self.epoch_deadline {
+            self.store.set_epoch_deadline(deadline);
+            let guest_api = self.store.data_mut();
+            guest_api.limiter.start_invocation(guest_api.host.waiting_time);
+        }
+    }
+}
+
+impl wasmtime_wasi::preview2::WasiView for super::guest_api_binding::GuestAPI {
+    fn table(&mut self) -> &mut wasmtime::component::ResourceTable {
+        &mut self.resource_table
+    }
+
+    fn ctx(&mut self) -> &mut wasmtime_wasi::preview2::WasiCtx {
+        &mut self.wasi_preview2
+    }
+}
+
+#[async_trait::async_trait]
+impl edgeless::function::host::Host for super::guest_api_binding::GuestAPI {
+    async fn cast_raw(&mut self, target: types::InstanceId, port: String, payload: Vec<u8>) -> wasmtime::Result<()> {
+        self.host
+            .cast_raw(from_wit_instance_id(&target)?, edgeless_api::function_instance::PortId(port), &payload)
+            .await
+            .map_err(|_| wasmtime::Error::msg("cast error"))
+    }
+
+    async fn cast(&mut self, target: String, payload: Vec<u8>) -> wasmtime::Result<()> {
+        if self.host.cast_alias(&target, None, &payload).await.is_err() {
+            // We ignore casts to unknown targets.
+            log::warn!("Cast to unknown target: {}", target);
+        }
+        Ok(())
+    }
+
+    async fn cast_with_key(&mut self, target: String, key: Vec<u8>, payload: Vec<u8>) -> wasmtime::Result<()> {
+        if self.host.cast_alias(&target, Some(&key), &payload).await.is_err() {
+            log::warn!("Cast to unknown target: {}", target);
+        }
+        Ok(())
+    }
+
+    async fn delayed_cast(&mut self, delay_ms: u64, target: String, payload: Vec<u8>) -> wasmtime::Result<()> {
+        self.host
+            .delayed_cast(delay_ms, &target, &payload)
+            .await
+            .map_err(|_| wasmtime::Error::msg("call error"))
+    }
+
+    async fn call_raw(&mut self, target: types::InstanceId, port: String, payload: Vec<u8>) -> wasmtime::Result<types::CallRet> {
+        self.host
+            .call_raw(from_wit_instance_id(&target)?, edgeless_api::function_instance::PortId(port), &payload)
+            .await
+            .map(to_wit_call_ret)
+            .map_err(|_| wasmtime::Error::msg("call error"))
+    }
+
+    async fn call(&mut self, target: String, payload: Vec<u8>) -> wasmtime::Result<types::CallRet> {
+        self.host
+            .call_alias(&target, None, &payload)
+            .await
+            .map(to_wit_call_ret)
+            .map_err(|_| wasmtime::Error::msg("call error"))
+    }
+
+    async fn call_with_key(&mut self, target: String, key: Vec<u8>, payload: Vec<u8>) -> wasmtime::Result<types::CallRet> {
+        self.host
+            .call_alias(&target, Some(&key), &payload)
+            .await
+            .map(to_wit_call_ret)
+            .map_err(|_| wasmtime::Error::msg("call error"))
+    }
+
+    async fn call_all(&mut self, target: String, payload: Vec<u8>) -> wasmtime::Result<Result<types::GatherRet, ()>> {
+        let gather_ret = match self.host.call_all_alias(&target, &payload).await {
+            Ok(gather_ret) => gather_ret,
+            Err(_) => {
+                log::warn!("Call on unknown target: {}", target);
+                return Ok(Err(()));
+            }
+        };
+        Ok(Ok(types::GatherRet {
+            quorum_reached: gather_ret.quorum_reached,
+            replies: gather_ret
+                .replies
+                .into_iter()
+                .map(|(src, reply)| types::GatheredReply {
+                    src: to_wit_instance_id(&src),
+                    reply: to_wit_call_ret(reply),
+                })
+                .collect(),
+        }))
+    }
+
+    async fn telemetry_log(&mut self, level: types::LogLevel, target: String, msg: String) -> wasmtime::Result<()> {
+        let level = match level {
+            types::LogLevel::Error => edgeless_telemetry::telemetry_events::TelemetryLogLevel::Error,
+            types::LogLevel::Warn => edgeless_telemetry::telemetry_events::TelemetryLogLevel::Warn,
+            types::LogLevel::Info => edgeless_telemetry::telemetry_events::TelemetryLogLevel::Info,
+            types::LogLevel::Debug => edgeless_telemetry::telemetry_events::TelemetryLogLevel::Debug,
+            types::LogLevel::Trace => edgeless_telemetry::telemetry_events::TelemetryLogLevel::Trace,
+        };
+        self.host.telemetry_log(level, &target, &msg).await;
+        Ok(())
+    }
+
+    async fn slf(&mut self) -> wasmtime::Result<types::InstanceId> {
+        Ok(to_wit_instance_id(&self.host.slf().await))
+    }
+
+    async fn sync(&mut self, serialized_state: String) -> wasmtime::Result<()> {
+        self.host.sync(&serialized_state).await.map_err(|_| wasmtime::Error::msg("sync error"))
+    }
+}
+
+impl types::Host for super::guest_api_binding::GuestAPI {}
+
+fn to_wit_instance_id(id: &edgeless_api::function_instance::InstanceId) -> types::InstanceId {
+    types::InstanceId {
+        node_id: id.node_id.to_string(),
+        component_id: id.function_id.to_string(),
+    }
+}
+
+fn from_wit_instance_id(id: &types::InstanceId) -> wasmtime::Result<edgeless_api::function_instance::InstanceId> {
+    Ok(edgeless_api::function_instance::InstanceId {
+        node_id: uuid::Uuid::parse_str(&id.node_id).map_err(|_| wasmtime::Error::msg("uuid error"))?,
+        function_id: uuid::Uuid::parse_str(&id.component_id).map_err(|_| wasmtime::Error::msg("uuid error"))?,
+    })
+}
+
+fn to_wit_call_ret(call_ret: edgeless_dataplane::core::CallRet) -> types::CallRet {
+    match call_ret {
+        edgeless_dataplane::core::CallRet::NoReply => types::CallRet::NoReply,
+        edgeless_dataplane::core::CallRet::Reply(data) => types::CallRet::Reply(data),
+        edgeless_dataplane::core::CallRet::Err => types::CallRet::Err,
+        edgeless_dataplane::core::CallRet::Timeout => types::CallRet::Timeout,
+    }
+}
+
+fn from_wit_call_ret(call_ret: types::CallRet) -> edgeless_dataplane::core::CallRet {
+    match call_ret {
+        types::CallRet::NoReply => edgeless_dataplane::core::CallRet::NoReply,
+        types::CallRet::Reply(data) => edgeless_dataplane::core::CallRet::Reply(data),
+        // A timeout of a call made by the function is an error for its own caller.
+        types::CallRet::Err | types::CallRet::Timeout => edgeless_dataplane::core::CallRet::Err,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn component_detected_by_header() {
+        assert!(!super::is_component(include_bytes!("test/fixtures/messaging_test.wasm")));
+        assert!(super::is_component(b"\0asm\x0d\0\x01\0"));
+        assert!(!super::is_component(b"\0asm"));
+        assert!(!super::is_component(b"not wasm"));
+    }
+}
@@ -0,0 +1,533 @@
+// SPDX-FileCopyrightText: © 2024 Technical University of Munich, Chair of Connected Mobility
+// SPDX-License-Identifier: MIT
+
+/// Period of the epoch of the engine, which is the granularity of the limit
+/// on the execution time of the invocations.
+pub const EPOCH_TICK: std::time::Duration = std::time::Duration::from_millis(10);
+
+/// Epoch deadline of the instances without a limit on the execution time,
+/// which is never reached.
+pub const NO_EPOCH_DEADLINE: u64 = u64::MAX / 2;
+
+/// Number of epochs that cover the given time, at least one.
+pub fn epochs(time: std::time::Duration) -> u64 {
+    (time.as_millis() as u64).div_ceil(EPOCH_TICK.as_millis() as u64).max(1)
+}
+
+/// Number of function classes of each kind, i.e., modules and components,
+/// whose compiled code is kept in memory, the least recently used being
+/// dropped first.
+const MAX_CACHED_CLASSES: usize = 64;
+
+/// Number of compiled modules and components kept in the module cache
+/// directory, the least recently used being removed first.
+const MAX_CACHED_FILES: usize = 256;
+
+/// Engine shared by the WASM function instances of all the nodes in this process.
+static SHARED_ENGINE: std::sync::Mutex<Option<std::sync::Arc<SharedEngine>>> = std::sync::Mutex::new(None);
+
+#[derive(Debug, Clone, Default)]
+pub struct EngineSettings {
+    /// Directory where the compiled modules are stored, to be loaded instead
+    /// of compiled again, e.g., after a restart of the node. It must be
+    /// writable only by the node, since the modules in it are loaded as
+    /// native code without being validated.
+    pub module_cache_dir: Option<std::path::PathBuf>,
+    /// Largest number of instances that can exist at the same time, whose
+    /// memory is reserved in advance by the pooling allocator: creating more
+    /// fails with `FunctionInstanceError::ResourceLimitExceeded`. If 0, the
+    /// memory of each instance is allocated on creation, without a cap.
+    pub pooling_instances: u32,
+    /// Resource limits of the node, which size the slots of the pool.
+    pub limits: crate::base_runtime::limits::FunctionLimits,
+}
+
+/// wasmtime engine with the modules and components of the recently used
+/// function classes already compiled and linked with the guest API, by hash
+/// of their code, so that a function instance of a known class is created
+/// without compiling its code again.
+pub struct SharedEngine {
+    engine: wasmtime::Engine,
+    linker: wasmtime::Linker<super::guest_api_binding::GuestAPI>,
+    component_linker: wasmtime::component::Linker<super::guest_api_binding::GuestAPI>,
+    modules: std::sync::Mutex<ClassCache<wasmtime::InstancePre<super::guest_api_binding::GuestAPI>>>,
+    components: std::sync::Mutex<ClassCache<wasmtime::component::InstancePre<super::guest_api_binding::GuestAPI>>>,
+    module_cache_dir: Option<std::path::PathBuf>,
+}
+
+/// Function classes compiled by the engine, by hash of their code, which
+/// keeps only the `capacity` most recently used.
+struct ClassCache<T> {
+    entries: std::collections::HashMap<[u8; 32], (T, u64)>,
+    capacity: usize,
+    /// Number of uses so far, which orders the entries by their last use.
+    uses: u64,
+}
+
+impl<T: Clone> ClassCache<T> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            entries: std::collections::HashMap::new(),
+            capacity,
+            uses: 0,
+        }
+    }
+
+    fn get(&mut self, hash: &[u8; 32]) -> Option<T> {
+        self.uses += 1;
+        let (value, last_use) = self.entries.get_mut(hash)?;
+        *last_use = self.uses;
+        Some(value.clone())
+    }
+
+    fn insert(&mut self, hash: [u8; 32], value: T) {
+        self.uses += 1;
+        self.entries.insert(hash, (value, self.uses));
+        if self.entries.len() > self.capacity {
+            // The instances already created keep their code.
+            if let Some(least_recent) = self.entries.iter().min_by_key(|(_, (_, last_use))| *last_use).map(|(hash, _)| *hash) {
+                self.entries.remove(&least_recent);
+            }
+        }
+    }
+}
+
+impl SharedEngine {
+    /// Creates the shared engine with the given settings. If there is one
+    /// already, e.g., created by another node in this process, it is kept.
+    pub fn configure(settings: EngineSettings) -> anyhow::Result<()> {
+        let mut shared = SHARED_ENGINE.lock().unwrap();
+        match shared.as_ref() {
+            Some(_) => log::info!("Reusing the WASM engine of this process, with its settings"),
+            None => *shared = Some(std::sync::Arc::new(Self::new(settings)?)),
+        }
+        Ok(())
+    }
+
+    /// Shared engine, created with the default settings if not configured.
+    pub fn get() -> anyhow::Result<std::sync::Arc<SharedEngine>> {
+        let mut shared = SHARED_ENGINE.lock().unwrap();
+        if shared.is_none() {
+            *shared = Some(std::sync::Arc::new(Self::new(EngineSettings::default())?));
+        }
+        Ok(shared.as_ref().unwrap().clone())
+    }
+
+    fn new(settings: EngineSettings) -> anyhow::Result<Self> {
+        let mut config = wasmtime::Config::new();
+        config.async_support(true);
+        // Always enabled, since the engine is shared with the instances that
+        // have a limit on the execution time.
+        config.epoch_interruption(true);
+        config.wasm_component_model(true);
+        if settings.pooling_instances > 0 {
+            let mut pooling = wasmtime::PoolingAllocationConfig::default();
+            pooling
+                .total_core_instances(settings.pooling_instances)
+                .total_memories(settings.pooling_instances)
+                .total_tables(settings.pooling_instances);
+            if settings.limits.max_memory_pages > 0 {
+                pooling.memory_pages(settings.limits.max_memory_pages);
+            }
+            config.allocation_strategy(wasmtime::InstanceAllocationStrategy::Pooling(pooling));
+        }
+        let engine = wasmtime::Engine::new(&config)?;
+
+        if let Some(dir) = &settings.module_cache_dir {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        let ticker = engine.clone();
+        std::thread::spawn(move || loop {
+            std::thread::sleep(EPOCH_TICK);
+            ticker.increment_epoch();
+        });
+
+        Ok(Self {
+            linker: linker(&engine)?,
+            component_linker: component_linker(&engine)?,
+            engine,
+            modules: std::sync::Mutex::new(ClassCache::new(MAX_CACHED_CLASSES)),
+            components: std::sync::Mutex::new(ClassCache::new(MAX_CACHED_CLASSES)),
+            module_cache_dir: settings.module_cache_dir,
+        })
+    }
+
+    pub fn engine(&self) -> &wasmtime::Engine {
+        &self.engine
+    }
+
+    /// Module with the given code, linked and ready to be instantiated.
+    /// It is compiled, or loaded from the module cache directory, only if it
+    /// has not been used recently.
+    pub fn instance_pre(&self, code: &[u8]) -> anyhow::Result<wasmtime::InstancePre<super::guest_api_binding::GuestAPI>> {
+        let hash = openssl::sha::sha256(code);
+        if let Some(instance_pre) = self.modules.lock().unwrap().get(&hash) {
+            return Ok(instance_pre);
+        }
+
+        // Not holding the lock, since compiling can take long.
+        let module = self.compiled::<wasmtime::Module>(&hash, code)?;
+        let instance_pre = self.linker.instantiate_pre(&module)?;
+        self.modules.lock().unwrap().insert(hash, instance_pre.clone());
+        Ok(instance_pre)
+    }
+
+    /// Like `instance_pre`, for a component of the `edgeless-function` world.
+    pub fn component_instance_pre(&self, code: &[u8]) -> anyhow::Result<wasmtime::component::InstancePre<super::guest_api_binding::GuestAPI>> {
+        let hash = openssl::sha::sha256(code);
+        if let Some(instance_pre) = self.components.lock().unwrap().get(&hash) {
+            return Ok(instance_pre);
+        }
+
+        let component = self.compiled::<wasmtime::component::Component>(&hash, code)?;
+        let instance_pre = self.component_linker.instantiate_pre(&component)?;
+        self.components.lock().unwrap().insert(hash, instance_pre.clone());
+        Ok(instance_pre)
+    }
+
+    fn compiled<T: Compiled>(&self, hash: &[u8; 32], code: &[u8]) -> anyhow::Result<T> {
+        let dir = match &self.module_cache_dir {
+            Some(dir) => dir,
+            None => return T::compile(&self.engine, code),
+        };
+        let name = hash.iter().map(|byte| format!("{:02x}", byte)).collect::<String>();
+        let path = dir.join(format!("{}.cwasm", name));
+
+        if path.exists() {
+            // SAFETY: the files in the directory are only written below,
+            // with modules compiled by wasmtime, as long as the directory is
+            // writable only by the node.
+            match unsafe { T::deserialize_file(&self.engine, &path) } {
+                Ok(module) => {
+                    // Marked as recently used, so that it is not pruned.
+                    if let Err(err) = std::fs::File::options()
+                        .write(true)
+                        .open(&path)
+                        .and_then(|file| file.set_modified(std::time::SystemTime::now()))
+                    {
+                        log::debug!("Cannot update the compiled module {}: {}", path.display(), err);
+                    }
+                    return Ok(module);
+                }
+                // E.g., compiled by another version of wasmtime.
+                Err(err) => log::info!("Cannot load the compiled module {}: {}", path.display(), err),
+            }
+        }
+
+        let compiled = T::compile(&self.engine, code)?;
+        // Renamed once complete, so that a partial file is never loaded.
+        let tmp_path = dir.join(format!("{}.{}.tmp", name, std::process::id()));
+        if let Err(err) = compiled
+            .serialize()
+            .and_then(|compiled| Ok(std::fs::write(&tmp_path, compiled)?))
+            .and_then(|_| Ok(std::fs::rename(&tmp_path, &path)?))
+        {
+            log::warn!("Cannot store the compiled module {}: {}", path.display(), err);
+        }
+        if let Err(err) = prune_cache_dir(dir, MAX_CACHED_FILES) {
+            log::warn!("Cannot remove the old compiled modules from {}: {}", dir.display(), err);
+        }
+        Ok(compiled)
+    }
+}
+
+/// Removes the least recently used compiled modules from the module cache
+/// directory, so that at most `max_files` are kept.
+fn prune_cache_dir(dir: &std::path::Path, max_files: usize) -> anyhow::Result<()> {
+    let mut files = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "cwasm"))
+        .filter_map(|path| Some((path.metadata().ok()?.modified().ok()?, path)))
+        .collect::<Vec<_>>();
+    if files.len() <= max_files {
+        return Ok(());
+    }
+    files.sort();
+    for (_, path) in &files[..files.len() - max_files] {
+        std::fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+/// Code compiled by the engine, which can be stored in the module cache directory.
+trait Compiled: Sized {
+    fn compile(engine: &wasmtime::Engine, code: &[u8]) -> anyhow::Result<Self>;
+    fn serialize(&self) -> anyhow::Result<Vec<u8>>;
+    /// # Safety
+    /// The file must have been written by `serialize`.
+    unsafe fn deserialize_file(engine: &wasmtime::Engine, path: &std::path::Path) -> anyhow::Result<Self>;
+}
+
+impl Compiled for wasmtime::Module {
+    fn compile(engine: &wasmtime::Engine, code: &[u8]) -> anyhow::Result<Self> {
+        wasmtime::Module::from_binary(engine, code)
+    }
+
+    fn serialize(&self) -> anyhow::Result<Vec<u8>> {
+        wasmtime::Module::serialize(self)
+    }
+
+    unsafe fn deserialize_file(engine: &wasmtime::Engine, path: &std::path::Path) -> anyhow::Result<Self> {
+        wasmtime::Module::deserialize_file(engine, path)
+    }
+}
+
+impl Compiled for wasmtime::component::Component {
+    fn compile(engine: &wasmtime::Engine, code: &[u8]) -> anyhow::Result<Self> {
+        wasmtime::component::Component::from_binary(engine, code)
+    }
+
+    fn serialize(&self) -> anyhow::Result<Vec<u8>> {
+        wasmtime::component::Component::serialize(self)
+    }
+
+    unsafe fn deserialize_file(engine: &wasmtime::Engine, path: &std::path::Path) -> anyhow::Result<Self> {
+        wasmtime::component::Component::deserialize_file(engine, path)
+    }
+}
+
+/// Linker of the imports of the WASM modules with the guest API.
+fn linker(engine: &wasmtime::Engine) -> anyhow::Result<wasmtime::Linker<super::guest_api_binding::GuestAPI>> {
+    let mut linker = wasmtime::Linker::new(engine);
+
+    linker.func_wrap6_async(
+        "env",
+        "cast_raw_asm",
+        |store, instance_node_id_ptr, instance_component_id_ptr, port_ptr, port_len, payload_ptr, payload_len| {
+            Box::new(super::guest_api_binding::cast_raw(
+                store,
+                instance_node_id_ptr,
+                instance_component_id_ptr,
+                port_ptr,
+                port_len,
+                payload_ptr,
+                payload_len,
+            ))
+        },
+    )?;
+    linker.func_wrap4_async("env", "cast_asm", |store, target_ptr, target_len, payload_ptr, payload_len| {
+        Box::new(super::guest_api_binding::cast(store, target_ptr, target_len, payload_ptr, payload_len))
+    })?;
+    linker.func_wrap8_async(
+        "env",
+        "call_raw_asm",
+        |store, instance_node_id_ptr, instance_component_id_ptr, port_ptr, port_len, payload_ptr, payload_len, out_ptr_ptr, out_len_ptr| {
+            Box::new(super::guest_api_binding::call_raw(
+                store,
+                instance_node_id_ptr,
+                instance_component_id_ptr,
+                port_ptr,
+                port_len,
+                payload_ptr,
+                payload_len,
+                out_ptr_ptr,
+                out_len_ptr,
+            ))
+        },
+    )?;
+    linker.func_wrap6_async(
+        "env",
+        "call_asm",
+        |store, target_ptr, target_len, payload_ptr, payload_len, out_ptr_ptr, out_len_ptr| {
+            Box::new(super::guest_api_binding::call(
+                store,
+                target_ptr,
+                target_len,
+                payload_ptr,
+                payload_len,
+                out_ptr_ptr,
+                out_len_ptr,
+            ))
+        },
+    )?;
+    linker.func_wrap6_async(
+        "env",
+        "cast_key_asm",
+        |store, target_ptr, target_len, key_ptr, key_len, payload_ptr, payload_len| {
+            Box::new(super::guest_api_binding::cast_with_key(
+                store,
+                target_ptr,
+                target_len,
+                key_ptr,
+                key_len,
+                payload_ptr,
+                payload_len,
+            ))
+        },
+    )?;
+    linker.func_wrap8_async(
+        "env",
+        "call_key_asm",
+        |store, target_ptr, target_len, key_ptr, key_len, payload_ptr, payload_len, out_ptr_ptr, out_len_ptr| {
+            Box::new(super::guest_api_binding::call_with_key(
+                store,
+                target_ptr,
+                target_len,
+                key_ptr,
+                key_len,
+                payload_ptr,
+                payload_len,
+                out_ptr_ptr,
+                out_len_ptr,
+            ))
+        },
+    )?;
+    linker.func_wrap6_async(
+        "env",
+        "call_all_asm",
+        |store, target_ptr, target_len, payload_ptr, payload_len, out_ptr_ptr, out_len_ptr| {
+            Box::new(super::guest_api_binding::call_all(
+                store,
+                target_ptr,
+                target_len,
+                payload_ptr,
+                payload_len,
+                out_ptr_ptr,
+                out_len_ptr,
+            ))
+        },
+    )?;
+    linker.func_wrap5_async("env", "telemetry_log_asm", |store, level, target_ptr, target_len, msg_ptr, msg_len| {
+        Box::new(super::guest_api_binding::telemetry_log(
+            store, level, target_ptr, target_len, msg_ptr, msg_len,
+        ))
+    })?;
+    linker.func_wrap2_async("env", "slf_asm", |store, out_node_id_ptr, out_component_id_ptr| {
+        Box::new(super::guest_api_binding::slf(store, out_node_id_ptr, out_component_id_ptr))
+    })?;
+    linker.func_wrap5_async(
+        "env",
+        "delayed_cast_asm",
+        |store, delay_ms, target_ptr, target_len, payload_ptr, payload_len| {
+            Box::new(super::guest_api_binding::delayed_cast(
+                store,
+                delay_ms,
+                target_ptr,
+                target_len,
+                payload_ptr,
+                payload_len,
+            ))
+        },
+    )?;
+    linker.func_wrap2_async("env", "sync_asm", |store, state_ptr, state_len| {
+        Box::new(super::guest_api_binding::sync(store, state_ptr, state_len))
+    })?;
+
+    // The WASI imports, which can only use the capabilities of the WASI context of each instance.
+    // They are async, so that accessing the files does not block the runtime.
+    wasmtime_wasi::tokio::add_to_linker(&mut linker, |guest_api: &mut super::guest_api_binding::GuestAPI| &mut guest_api.wasi)?;
+
+    Ok(linker)
+}
+
+/// Linker of the imports of the components with the guest API.
+fn component_linker(engine: &wasmtime::Engine) -> anyhow::Result<wasmtime::component::Linker<super::guest_api_binding::GuestAPI>> {
+    let mut linker = wasmtime::component::Linker::new(engine);
+    super::component::EdgelessFunction::add_to_linker(&mut linker, |guest_api: &mut super::guest_api_binding::GuestAPI| guest_api)?;
+    // Same capabilities as the modules, given by the WASI policy of the instance.
+    wasmtime_wasi::preview2::command::add_to_linker(&mut linker)?;
+    add_random_to_linker(&mut linker)?;
+    Ok(linker)
+}
+
+/// Replaces the random numbers of WASI preview 2, which cannot fail, with
+/// ones that trap for the instances that cannot get random numbers.
+/// Shadowing replaces each interface as a whole, so all its functions are
+/// defined again.
+fn add_random_to_linker(linker: &mut wasmtime::component::Linker<super::guest_api_binding::GuestAPI>) -> anyhow::Result<()> {
+    use wasmtime_wasi::preview2::bindings::random::{insecure, insecure_seed, random};
+    type Store<'a> = wasmtime::StoreContextMut<'a, super::guest_api_binding::GuestAPI>;
+
+    fn allowed(guest_api: &mut super::guest_api_binding::GuestAPI) -> anyhow::Result<&mut super::guest_api_binding::GuestAPI> {
+        match guest_api.wasi_random {
+            true => Ok(guest_api),
+            false => Err(anyhow::anyhow!("random numbers not allowed")),
+        }
+    }
+
+    linker.allow_shadowing(true);
+    let mut instance = linker.instance("wasi:random/random@0.2.0")?;
+    instance.func_wrap("get-random-bytes", |mut store: Store, (len,): (u64,)| {
+        Ok((random::Host::get_random_bytes(allowed(store.data_mut())?, len)?,))
+    })?;
+    instance.func_wrap("get-random-u64", |mut store: Store, (): ()| {
+        Ok((random::Host::get_random_u64(allowed(store.data_mut())?)?,))
+    })?;
+    let mut instance = linker.instance("wasi:random/insecure@0.2.0")?;
+    instance.func_wrap("get-insecure-random-bytes", |mut store: Store, (len,): (u64,)| {
+        Ok((insecure::Host::get_insecure_random_bytes(allowed(store.data_mut())?, len)?,))
+    })?;
+    instance.func_wrap("get-insecure-random-u64", |mut store: Store, (): ()| {
+        Ok((insecure::Host::get_insecure_random_u64(allowed(store.data_mut())?)?,))
+    })?;
+    let mut instance = linker.instance("wasi:random/insecure-seed@0.2.0")?;
+    instance.func_wrap("insecure-seed", |mut store: Store, (): ()| {
+        Ok((insecure_seed::Host::insecure_seed(allowed(store.data_mut())?)?,))
+    })?;
+    linker.allow_shadowing(false);
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn modules_compiled_once() {
+        let dir = std::env::temp_dir().join(format!("edgeless_module_cache_{}", uuid::Uuid::new_v4()));
+        let settings = super::EngineSettings {
+            module_cache_dir: Some(dir.clone()),
+            ..Default::default()
+        };
+        let code = include_bytes!("test/fixtures/messaging_test.wasm");
+
+        let engine = super::SharedEngine::new(settings.clone()).unwrap();
+        engine.instance_pre(code).unwrap();
+        engine.instance_pre(code).unwrap();
+        assert_eq!(engine.modules.lock().unwrap().entries.len(), 1);
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        assert!(engine.instance_pre(b"not a module").is_err());
+        assert!(engine.component_instance_pre(code).is_err());
+
+        // E.g., after a restart of the node.
+        let engine = super::SharedEngine::new(settings).unwrap();
+        engine.instance_pre(code).unwrap();
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn least_recently_used_classes_dropped() {
+        let mut cache = super::ClassCache::new(2);
+        cache.insert([1; 32], 1);
+        cache.insert([2; 32], 2);
+        assert_eq!(cache.get(&[1; 32]), Some(1));
+        cache.insert([3; 32], 3);
+        assert_eq!(cache.entries.len(), 2);
+        assert_eq!(cache.get(&[2; 32]), None);
+        assert_eq!(cache.get(&[1; 32]), Some(1));
+        assert_eq!(cache.get(&[3; 32]), Some(3));
+    }
+
+    #[test]
+    fn cache_dir_pruned() {
+        let dir = std::env::temp_dir().join(format!("edgeless_module_cache_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let now = std::time::SystemTime::now();
+        for (name, age_s) in [("a.cwasm", 10), ("b.cwasm", 30), ("c.cwasm", 20), ("d.tmp", 40)] {
+            let file = std::fs::File::create(dir.join(name)).unwrap();
+            file.set_modified(now - std::time::Duration::from_secs(age_s)).unwrap();
+        }
+
+        super::prune_cache_dir(&dir, 2).unwrap();
+        let mut names = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, vec!["a.cwasm", "c.cwasm", "d.tmp"]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
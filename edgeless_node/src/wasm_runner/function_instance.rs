@@ -44,122 +44,27 @@ pub struct WASMFunctionInstance {
     edgefunctione_handle_stop: wasmtime::TypedFunc<(), ()>,
     memory: wasmtime::Memory,
     store: wasmtime::Store<super::guest_api_binding::GuestAPI>,
+    /// Epochs after which an invocation is interrupted, if limited.
+    epoch_deadline: Option<u64>,
 }
 
 #[async_trait::async_trait]
 impl crate::base_runtime::FunctionInstance for WASMFunctionInstance {
     async fn instantiate(
         _instance_id: &edgeless_api::function_instance::InstanceId,
-        _runtime_configuration: std::collections::HashMap<String, String>,
+        runtime_configuration: std::collections::HashMap<String, String>,
+        annotations: &std::collections::HashMap<String, String>,
         guest_api_host: &mut Option<crate::base_runtime::guest_api::GuestAPIHost>,
         code: &[u8],
     ) -> Result<Box<Self>, crate::base_runtime::FunctionInstanceError> {
-        let mut config = wasmtime::Config::new();
-        config.async_support(true);
-        let engine = wasmtime::Engine::new(&config).map_err(|_err| crate::base_runtime::FunctionInstanceError::InternalError)?;
-        let module = wasmtime::Module::from_binary(&engine, code).map_err(|_err| crate::base_runtime::FunctionInstanceError::BadCode)?;
-        let mut linker = wasmtime::Linker::new(&engine);
-
-        let mut store: wasmtime::Store<super::guest_api_binding::GuestAPI> = wasmtime::Store::new(
-            &engine,
-            super::guest_api_binding::GuestAPI {
-                host: guest_api_host.take().expect("the impossible happened: no GuestAPIHost"),
-            },
-        );
-
-        linker
-            .func_wrap6_async(
-                "env",
-                "cast_raw_asm",
-                |store, instance_node_id_ptr, instance_component_id_ptr, port_ptr, port_len, payload_ptr, payload_len| {
-                    Box::new(super::guest_api_binding::cast_raw(
-                        store,
-                        instance_node_id_ptr,
-                        instance_component_id_ptr,
-                        port_ptr,
-                        port_len,
-                        payload_ptr,
-                        payload_len,
-                    ))
-                },
-            )
-            .map_err(|_| crate::base_runtime::FunctionInstanceError::InternalError)?;
-        linker
-            .func_wrap4_async("env", "cast_asm", |store, target_ptr, target_len, payload_ptr, payload_len| {
-                Box::new(super::guest_api_binding::cast(store, target_ptr, target_len, payload_ptr, payload_len))
-            })
-            .map_err(|_| crate::base_runtime::FunctionInstanceError::InternalError)?;
-        linker
-            .func_wrap8_async(
-                "env",
-                "call_raw_asm",
-                |store, instance_node_id_ptr, instance_component_id_ptr, port_ptr, port_len, payload_ptr, payload_len, out_ptr_ptr, out_len_ptr| {
-                    Box::new(super::guest_api_binding::call_raw(
-                        store,
-                        instance_node_id_ptr,
-                        instance_component_id_ptr,
-                        port_ptr,
-                        port_len,
-                        payload_ptr,
-                        payload_len,
-                        out_ptr_ptr,
-                        out_len_ptr,
-                    ))
-                },
-            )
-            .map_err(|_| crate::base_runtime::FunctionInstanceError::InternalError)?;
-        linker
-            .func_wrap6_async(
-                "env",
-                "call_asm",
-                |store, target_ptr, target_len, payload_ptr, payload_len, out_ptr_ptr, out_len_ptr| {
-                    Box::new(super::guest_api_binding::call(
-                        store,
-                        target_ptr,
-                        target_len,
-                        payload_ptr,
-                        payload_len,
-                        out_ptr_ptr,
-                        out_len_ptr,
-                    ))
-                },
-            )
-            .map_err(|_| crate::base_runtime::FunctionInstanceError::InternalError)?;
-        linker
-            .func_wrap5_async("env", "telemetry_log_asm", |store, level, target_ptr, target_len, msg_ptr, msg_len| {
-                Box::new(super::guest_api_binding::telemetry_log(
-                    store, level, target_ptr, target_len, msg_ptr, msg_len,
-                ))
-            })
-            .map_err(|_| crate::base_runtime::FunctionInstanceError::InternalError)?;
-        linker
-            .func_wrap2_async("env", "slf_asm", |store, out_node_id_ptr, out_component_id_ptr| {
-                Box::new(super::guest_api_binding::slf(store, out_node_id_ptr, out_component_id_ptr))
-            })
-            .map_err(|_| crate::base_runtime::FunctionInstanceError::InternalError)?;
-        linker
-            .func_wrap5_async(
-                "env",
-                "delayed_cast_asm",
-                |store, delay_ms, target_ptr, target_len, payload_ptr, payload_len| {
-                    Box::new(super::guest_api_binding::delayed_cast(
-                        store,
-                        delay_ms,
-                        target_ptr,
-                        target_len,
-                        payload_ptr,
-                        payload_len,
-                    ))
-                },
-            )
-            .map_err(|_| crate::base_runtime::FunctionInstanceError::InternalError)?;
-        linker
-            .func_wrap2_async("env", "sync_asm", |store, state_ptr, state_len| {
-                Box::new(super::guest_api_binding::sync(store, state_ptr, state_len))
-            })
-            .map_err(|_| crate::base_runtime::FunctionInstanceError::InternalError)?;
+        let engine = super::engine::SharedEngine::get().map_err(|_| crate::base_runtime::FunctionInstanceError::InternalError)?;
+        let instance_pre = engine
+            .instance_pre(code)
+            .map_err(|_| crate::base_runtime::FunctionInstanceError::BadCode)?;
+        let (mut store, epoch_deadline) = new_store(&engine, &runtime_configuration, annotations, guest_api_host)?;
 
-        let instance = linker.instantiate_async(&mut store, &module).await.unwrap();
+        let instance = instance_pre.instantiate_async(&mut store).await.map_err(instantiation_error);
+        let instance = store.data().limiter.check(instance)?;
 
         Ok(Box::new(Self {
             edgeless_mem_alloc: instance
@@ -187,10 +92,60 @@ impl crate::base_runtime::FunctionInstance for WASMFunctionInstance {
                 .get_memory(&mut store, "memory")
                 .ok_or(crate::base_runtime::FunctionInstanceError::BadCode)?,
             store,
+            epoch_deadline,
         }))
     }
 
     async fn init(&mut self, init_payload: Option<&str>, serialized_state: Option<&str>) -> Result<(), crate::base_runtime::FunctionInstanceError> {
+        self.start_invocation();
+        let res = self.invoke_init(init_payload, serialized_state).await;
+        self.store.data().limiter.check(res)
+    }
+
+    async fn cast(
+        &mut self,
+        src: &edgeless_api::function_instance::InstanceId,
+        port: &str,
+        msg: &[u8],
+    ) -> Result<(), crate::base_runtime::FunctionInstanceError> {
+        self.start_invocation();
+        let res = self.invoke_cast(src, port, msg).await;
+        self.store.data().limiter.check(res)
+    }
+
+    async fn call(
+        &mut self,
+        src: &edgeless_api::function_instance::InstanceId,
+        port: &str,
+        msg: &[u8],
+    ) -> Result<edgeless_dataplane::core::CallRet, crate::base_runtime::FunctionInstanceError> {
+        self.start_invocation();
+        let res = self.invoke_call(src, port, msg).await;
+        self.store.data().limiter.check(res)
+    }
+
+    async fn stop(&mut self) -> Result<(), crate::base_runtime::FunctionInstanceError> {
+        self.start_invocation();
+        let res = self.invoke_stop().await;
+        self.store.data().limiter.check(res)
+    }
+}
+
+impl WASMFunctionInstance {
+    /// Gives the next invocation its whole execution time.
+    fn start_invocation(&mut self) {
+        if let Some(deadline) = self.epoch_deadline {
+            self.store.set_epoch_deadline(deadline);
+            let guest_api = self.store.data_mut();
+            guest_api.limiter.start_invocation(guest_api.host.waiting_time);
+        }
+    }
+
+    async fn invoke_init(
+        &mut self,
+        init_payload: Option<&str>,
+        serialized_state: Option<&str>,
+    ) -> Result<(), crate::base_runtime::FunctionInstanceError> {
         let (init_payload_ptr, init_payload_len) = match init_payload {
             Some(payload) => {
                 let len = payload.len();
@@ -246,11 +201,11 @@ impl crate::base_runtime::FunctionInstance for WASMFunctionInstance {
         ret
     }
 
-    async fn cast(
+    async fn invoke_cast(
         &mut self,
         src: &edgeless_api::function_instance::InstanceId,
         port: &str,
-        msg: &str,
+        msg: &[u8],
     ) -> Result<(), crate::base_runtime::FunctionInstanceError> {
         // Depending on the Function, we might employ a basic arena/bump allocator that we must reset at the end of a transaction.
         // This might be a noop if the function defines a working version of `edgeless_mem_free`.
@@ -276,8 +231,8 @@ impl crate::base_runtime::FunctionInstance for WASMFunctionInstance {
         .await
         .map_err(|_| crate::base_runtime::FunctionInstanceError::BadCode)?;
 
-        let payload_len = msg.as_bytes().len();
-        let payload_ptr = super::helpers::copy_to_vm(&mut self.store.as_context_mut(), &self.memory, &self.edgeless_mem_alloc, msg.as_bytes())
+        let payload_len = msg.len();
+        let payload_ptr = super::helpers::copy_to_vm(&mut self.store.as_context_mut(), &self.memory, &self.edgeless_mem_alloc, msg)
             .await
             .map_err(|_| crate::base_runtime::FunctionInstanceError::BadCode)?;
 
@@ -320,11 +275,11 @@ impl crate::base_runtime::FunctionInstance for WASMFunctionInstance {
         ret
     }
 
-    async fn call(
+    async fn invoke_call(
         &mut self,
         src: &edgeless_api::function_instance::InstanceId,
         port: &str,
-        msg: &str,
+        msg: &[u8],
     ) -> Result<edgeless_dataplane::core::CallRet, crate::base_runtime::FunctionInstanceError> {
         self.edgeless_mem_clear
             .call_async(&mut self.store, ())
@@ -349,8 +304,8 @@ impl crate::base_runtime::FunctionInstance for WASMFunctionInstance {
         .await
         .map_err(|_| crate::base_runtime::FunctionInstanceError::BadCode)?;
 
-        let payload_len = msg.as_bytes().len();
-        let payload_ptr = super::helpers::copy_to_vm(&mut self.store.as_context_mut(), &self.memory, &self.edgeless_mem_alloc, msg.as_bytes())
+        let payload_len = msg.len();
+        let payload_ptr = super::helpers::copy_to_vm(&mut self.store.as_context_mut(), &self.memory, &self.edgeless_mem_alloc, msg)
             .await
             .map_err(|_| crate::base_runtime::FunctionInstanceError::BadCode)?;
 
@@ -406,9 +361,7 @@ impl crate::base_runtime::FunctionInstance for WASMFunctionInstance {
 
                 // load the atual output param
                 let out_raw = self.memory.data_mut(&mut self.store)[out_ptr as usize..(out_ptr as usize) + out_len as usize].to_vec();
-                // TODO(raphaelhetzel) This unwrap can be removed after we migrate the dataplane to use string slices.
-                let out = std::string::String::from_utf8(out_raw).unwrap();
-                Ok(edgeless_dataplane::core::CallRet::Reply(out))
+                Ok(edgeless_dataplane::core::CallRet::Reply(out_raw))
             }
             _ => Ok(edgeless_dataplane::core::CallRet::Err),
         };
@@ -449,7 +402,7 @@ impl crate::base_runtime::FunctionInstance for WASMFunctionInstance {
         ret
     }
 
-    async fn stop(&mut self) -> Result<(), crate::base_runtime::FunctionInstanceError> {
+    async fn invoke_stop(&mut self) -> Result<(), crate::base_runtime::FunctionInstanceError> {
         self.edgeless_mem_clear
             .call_async(&mut self.store, ())
             .await
@@ -460,3 +413,94 @@ impl crate::base_runtime::FunctionInstance for WASMFunctionInstance {
             .map_err(|_| crate::base_runtime::FunctionInstanceError::BadCode)
     }
 }
+
+/// Error of the instantiation of a function instance, which is a resource
+/// error if all the slots of the pooling allocator are in use.
+pub(crate) fn instantiation_error(err: anyhow::Error) -> crate::base_runtime::FunctionInstanceError {
+    match err.chain().any(|cause| cause.is::<wasmtime::PoolConcurrencyLimitError>()) {
+        true => crate::base_runtime::FunctionInstanceError::ResourceLimitExceeded,
+        false => crate::base_runtime::FunctionInstanceError::BadCode,
+    }
+}
+
+/// Store of a new function instance, with its resource limits and WASI
+/// context, and the epoch deadline of its invocations, if limited.
+pub(crate) fn new_store(
+    engine: &super::engine::SharedEngine,
+    runtime_configuration: &std::collections::HashMap<String, String>,
+    annotations: &std::collections::HashMap<String, String>,
+    guest_api_host: &mut Option<crate::base_runtime::guest_api::GuestAPIHost>,
+) -> Result<(wasmtime::Store<super::guest_api_binding::GuestAPI>, Option<u64>), crate::base_runtime::FunctionInstanceError> {
+    let limits = crate::base_runtime::limits::FunctionLimits::from_annotations(runtime_configuration);
+    let epoch_deadline = limits.max_execution_time().map(super::engine::epochs);
+
+    let host = guest_api_host.take().expect("the impossible happened: no GuestAPIHost");
+    // The directories of each function are below its own, named by its id.
+    let wasi_policy = super::wasi::WasiPolicy::from_annotations(annotations);
+    let dirs_root = runtime_configuration
+        .get(super::wasi::WASI_DIRS_ROOT_CONFIGURATION)
+        .map(std::path::Path::new);
+    let instance_dir = host.instance_id.function_id.to_string();
+    let (wasi, wasi_preview2) = wasi_policy
+        .context(dirs_root, &instance_dir)
+        .and_then(|wasi| Ok((wasi, wasi_policy.component_context(dirs_root, &instance_dir)?)))
+        .map_err(|err| {
+            log::warn!("cannot give the WASI capabilities of the function: {}", err);
+            crate::base_runtime::FunctionInstanceError::BadCode
+        })?;
+
+    let mut store: wasmtime::Store<super::guest_api_binding::GuestAPI> = wasmtime::Store::new(
+        engine.engine(),
+        super::guest_api_binding::GuestAPI {
+            host,
+            limiter: crate::base_runtime::limits::WasmLimiter::new(limits),
+            wasi,
+            wasi_preview2,
+            resource_table: wasmtime::component::ResourceTable::new(),
+            wasi_random: wasi_policy.random,
+        },
+    );
+    store.limiter(|guest_api| &mut guest_api.limiter);
+    match epoch_deadline {
+        Some(deadline) => {
+            // Also limits the start function run by the instantiation.
+            store.set_epoch_deadline(deadline);
+            // The deadline is also reached while an invocation waits in a
+            // host call, e.g., for the reply to a call, which is not counted.
+            store.epoch_deadline_callback(|mut store| {
+                let guest_api = store.data_mut();
+                match guest_api.limiter.remaining_execution_time(guest_api.host.waiting_time) {
+                    Some(remaining) if !remaining.is_zero() => Ok(wasmtime::UpdateDeadline::Continue(super::engine::epochs(remaining))),
+                    _ => {
+                        guest_api.limiter.exceeded = true;
+                        Err(anyhow::anyhow!("execution time limit exceeded"))
+                    }
+                }
+            });
+        }
+        None => store.set_epoch_deadline(super::engine::NO_EPOCH_DEADLINE),
+    }
+    Ok((store, epoch_deadline))
+}
+
+impl wasmtime::ResourceLimiter for crate::base_runtime::limits::WasmLimiter {
+    fn memory_growing(&mut self, _current: usize, desired: usize, _maximum: Option<usize>) -> anyhow::Result<bool> {
+        Ok(self.allow_memory(desired))
+    }
+
+    fn table_growing(&mut self, _current: u32, desired: u32, _maximum: Option<u32>) -> anyhow::Result<bool> {
+        Ok(self.allow_table(desired as u64))
+    }
+
+    fn instances(&self) -> usize {
+        self.max_instances()
+    }
+
+    fn tables(&self) -> usize {
+        self.max_tables()
+    }
+
+    fn memories(&self) -> usize {
+        self.max_memories()
+    }
+}
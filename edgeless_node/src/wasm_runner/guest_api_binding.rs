@@ -6,6 +6,13 @@ use wasmtime::AsContextMut;
 /// Binds the WASM component's imports to the function's GuestAPIHost.
 pub struct GuestAPI {
     pub host: crate::base_runtime::guest_api::GuestAPIHost,
+    pub limiter: crate::base_runtime::limits::WasmLimiter,
+    pub wasi: wasmtime_wasi::WasiCtx,
+    /// WASI preview 2 context and resources, used by the components.
+    pub wasi_preview2: wasmtime_wasi::preview2::WasiCtx,
+    pub resource_table: wasmtime::component::ResourceTable,
+    /// Whether the instance can get random numbers.
+    pub wasi_random: bool,
 }
 
 pub async fn telemetry_log(
@@ -46,7 +53,7 @@ pub async fn cast_raw(
     };
 
     let port = super::helpers::load_string_from_vm(&mut caller.as_context_mut(), &mem, port_ptr, port_len)?;
-    let payload = super::helpers::load_string_from_vm(&mut caller.as_context_mut(), &mem, payload_ptr, payload_len)?;
+    let payload = super::helpers::load_bytes_from_vm(&mut caller.as_context_mut(), &mem, payload_ptr, payload_len)?;
 
     caller
         .data_mut()
@@ -78,7 +85,7 @@ pub async fn call_raw(
     };
 
     let port = super::helpers::load_string_from_vm(&mut caller.as_context_mut(), &mem, port_ptr, port_len)?;
-    let payload = super::helpers::load_string_from_vm(&mut caller.as_context_mut(), &mem, payload_ptr, payload_len)?;
+    let payload = super::helpers::load_bytes_from_vm(&mut caller.as_context_mut(), &mem, payload_ptr, payload_len)?;
 
     let call_ret = caller
         .data_mut()
@@ -89,31 +96,59 @@ pub async fn call_raw(
     match call_ret {
         edgeless_dataplane::core::CallRet::NoReply => Ok(0),
         edgeless_dataplane::core::CallRet::Reply(data) => {
-            let len = data.as_bytes().len();
+            let len = data.len();
 
-            let data_ptr = super::helpers::copy_to_vm(&mut caller.as_context_mut(), &mem, &alloc, data.as_bytes()).await?;
+            let data_ptr = super::helpers::copy_to_vm(&mut caller.as_context_mut(), &mem, &alloc, &data).await?;
             super::helpers::copy_to_vm_ptr(&mut caller.as_context_mut(), &mem, out_ptr_ptr, &data_ptr.to_le_bytes())?;
             super::helpers::copy_to_vm_ptr(&mut caller.as_context_mut(), &mem, out_len_ptr, &len.to_le_bytes())?;
 
             Ok(1)
         }
         edgeless_dataplane::core::CallRet::Err => Ok(2),
+        edgeless_dataplane::core::CallRet::Timeout => Ok(3),
     }
 }
 
 pub async fn cast(
+    caller: wasmtime::Caller<'_, GuestAPI>,
+    target_ptr: i32,
+    target_len: i32,
+    payload_ptr: i32,
+    payload_len: i32,
+) -> wasmtime::Result<()> {
+    cast_inner(caller, target_ptr, target_len, None, payload_ptr, payload_len).await
+}
+
+pub async fn cast_with_key(
+    caller: wasmtime::Caller<'_, GuestAPI>,
+    target_ptr: i32,
+    target_len: i32,
+    key_ptr: i32,
+    key_len: i32,
+    payload_ptr: i32,
+    payload_len: i32,
+) -> wasmtime::Result<()> {
+    cast_inner(caller, target_ptr, target_len, Some((key_ptr, key_len)), payload_ptr, payload_len).await
+}
+
+async fn cast_inner(
     mut caller: wasmtime::Caller<'_, GuestAPI>,
     target_ptr: i32,
     target_len: i32,
+    key: Option<(i32, i32)>,
     payload_ptr: i32,
     payload_len: i32,
 ) -> wasmtime::Result<()> {
     let mem = get_memory(&mut caller)?;
 
     let target = super::helpers::load_string_from_vm(&mut caller.as_context_mut(), &mem, target_ptr, target_len)?;
-    let payload = super::helpers::load_string_from_vm(&mut caller.as_context_mut(), &mem, payload_ptr, payload_len)?;
+    let key = match key {
+        Some((key_ptr, key_len)) => Some(super::helpers::load_bytes_from_vm(&mut caller.as_context_mut(), &mem, key_ptr, key_len)?),
+        None => None,
+    };
+    let payload = super::helpers::load_bytes_from_vm(&mut caller.as_context_mut(), &mem, payload_ptr, payload_len)?;
 
-    match caller.data_mut().host.cast_alias(&target, &payload).await {
+    match caller.data_mut().host.cast_alias(&target, key.as_deref(), &payload).await {
         Ok(_) => {}
         Err(_) => {
             // We ignore casts to unknown targets.
@@ -125,9 +160,48 @@ pub async fn cast(
 }
 
 pub async fn call(
+    caller: wasmtime::Caller<'_, GuestAPI>,
+    target_ptr: i32,
+    target_len: i32,
+    payload_ptr: i32,
+    payload_len: i32,
+    out_ptr_ptr: i32,
+    out_len_ptr: i32,
+) -> wasmtime::Result<i32> {
+    call_inner(caller, target_ptr, target_len, None, payload_ptr, payload_len, out_ptr_ptr, out_len_ptr).await
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn call_with_key(
+    caller: wasmtime::Caller<'_, GuestAPI>,
+    target_ptr: i32,
+    target_len: i32,
+    key_ptr: i32,
+    key_len: i32,
+    payload_ptr: i32,
+    payload_len: i32,
+    out_ptr_ptr: i32,
+    out_len_ptr: i32,
+) -> wasmtime::Result<i32> {
+    call_inner(
+        caller,
+        target_ptr,
+        target_len,
+        Some((key_ptr, key_len)),
+        payload_ptr,
+        payload_len,
+        out_ptr_ptr,
+        out_len_ptr,
+    )
+    .await
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn call_inner(
     mut caller: wasmtime::Caller<'_, GuestAPI>,
     target_ptr: i32,
     target_len: i32,
+    key: Option<(i32, i32)>,
     payload_ptr: i32,
     payload_len: i32,
     out_ptr_ptr: i32,
@@ -137,27 +211,64 @@ pub async fn call(
     let alloc = get_alloc(&mut caller)?;
 
     let target = super::helpers::load_string_from_vm(&mut caller.as_context_mut(), &mem, target_ptr, target_len)?;
-    let payload = super::helpers::load_string_from_vm(&mut caller.as_context_mut(), &mem, payload_ptr, payload_len)?;
+    let key = match key {
+        Some((key_ptr, key_len)) => Some(super::helpers::load_bytes_from_vm(&mut caller.as_context_mut(), &mem, key_ptr, key_len)?),
+        None => None,
+    };
+    let payload = super::helpers::load_bytes_from_vm(&mut caller.as_context_mut(), &mem, payload_ptr, payload_len)?;
 
     let call_ret = caller
         .data_mut()
         .host
-        .call_alias(&target, &payload)
+        .call_alias(&target, key.as_deref(), &payload)
         .await
         .map_err(|_| wasmtime::Error::msg("call error"))?;
     match call_ret {
         edgeless_dataplane::core::CallRet::NoReply => Ok(0),
         edgeless_dataplane::core::CallRet::Reply(data) => {
-            let len = data.as_bytes().len();
+            let len = data.len();
 
-            let data_ptr = super::helpers::copy_to_vm(&mut caller.as_context_mut(), &mem, &alloc, data.as_bytes()).await?;
+            let data_ptr = super::helpers::copy_to_vm(&mut caller.as_context_mut(), &mem, &alloc, &data).await?;
             super::helpers::copy_to_vm_ptr(&mut caller.as_context_mut(), &mem, out_ptr_ptr, &data_ptr.to_le_bytes())?;
             super::helpers::copy_to_vm_ptr(&mut caller.as_context_mut(), &mem, out_len_ptr, &len.to_le_bytes())?;
 
             Ok(1)
         }
         edgeless_dataplane::core::CallRet::Err => Ok(2),
+        edgeless_dataplane::core::CallRet::Timeout => Ok(3),
+    }
+}
+
+pub async fn call_all(
+    mut caller: wasmtime::Caller<'_, GuestAPI>,
+    target_ptr: i32,
+    target_len: i32,
+    payload_ptr: i32,
+    payload_len: i32,
+    out_ptr_ptr: i32,
+    out_len_ptr: i32,
+) -> wasmtime::Result<i32> {
+    let mem = get_memory(&mut caller)?;
+    let alloc = get_alloc(&mut caller)?;
+
+    let target = super::helpers::load_string_from_vm(&mut caller.as_context_mut(), &mem, target_ptr, target_len)?;
+    let payload = super::helpers::load_bytes_from_vm(&mut caller.as_context_mut(), &mem, payload_ptr, payload_len)?;
+
+    let gather_ret = match caller.data_mut().host.call_all_alias(&target, &payload).await {
+        Ok(gather_ret) => gather_ret,
+        Err(_) => {
+            log::warn!("Call on unknown target: {}", target);
+            return Ok(2);
+        }
+    };
+    let data = crate::base_runtime::guest_api::encode_gathered_replies(&gather_ret.replies);
+    if !data.is_empty() {
+        let len = data.len();
+        let data_ptr = super::helpers::copy_to_vm(&mut caller.as_context_mut(), &mem, &alloc, &data).await?;
+        super::helpers::copy_to_vm_ptr(&mut caller.as_context_mut(), &mem, out_ptr_ptr, &data_ptr.to_le_bytes())?;
+        super::helpers::copy_to_vm_ptr(&mut caller.as_context_mut(), &mem, out_len_ptr, &len.to_le_bytes())?;
     }
+    Ok(if gather_ret.quorum_reached { 1 } else { 0 })
 }
 
 pub async fn delayed_cast(
@@ -170,7 +281,7 @@ pub async fn delayed_cast(
 ) -> wasmtime::Result<()> {
     let mem = get_memory(&mut caller)?;
     let target = super::helpers::load_string_from_vm(&mut caller.as_context_mut(), &mem, target_ptr, target_len)?;
-    let payload = super::helpers::load_string_from_vm(&mut caller.as_context_mut(), &mem, payload_ptr, payload_len)?;
+    let payload = super::helpers::load_bytes_from_vm(&mut caller.as_context_mut(), &mem, payload_ptr, payload_len)?;
 
     caller
         .data_mut()
@@ -28,6 +28,19 @@ pub(crate) fn copy_to_vm_ptr(
     Ok(())
 }
 
+pub(crate) fn load_bytes_from_vm(
+    ctx: &mut wasmtime::StoreContextMut<'_, super::guest_api_binding::GuestAPI>,
+    memory: &wasmtime::Memory,
+    data_ptr: i32,
+    data_len: i32,
+) -> wasmtime::Result<Vec<u8>> {
+    memory
+        .data_mut(ctx)
+        .get(data_ptr as usize..(data_ptr as usize) + data_len as usize)
+        .map(|data| data.to_vec())
+        .ok_or(wasmtime::Error::msg("memory error"))
+}
+
 pub(crate) fn load_string_from_vm(
     ctx: &mut wasmtime::StoreContextMut<'_, super::guest_api_binding::GuestAPI>,
     memory: &wasmtime::Memory,
@@ -5,9 +5,22 @@
 /// Note that this module only contains the WASM specifics, the generic parts are implemented in the base_runtime.
 pub mod function_instance;
 
+/// Implementation of a function instance for WASM components of the WIT world
+/// defined in `edgeless_function/wit`.
+pub mod component;
+
+/// Function instance that is either of the above, depending on the code.
+pub mod any_instance;
+
+/// wasmtime engine and compiled modules shared by the function instances.
+pub mod engine;
+
 /// Bridge between the guest_api_host and the interface defined in the wit binding
 pub mod guest_api_binding;
 
+/// WASI capabilities given to the function instances by their annotations.
+pub mod wasi;
+
 pub mod runtime;
 
 mod helpers;
@@ -17,6 +17,14 @@ impl WasmRuntime {
             _configuration: std::collections::HashMap::new(),
         }
     }
+
+    /// Runtime whose function instances get the given configuration, e.g.,
+    /// the resource limits of the node.
+    pub fn with_configuration(configuration: std::collections::HashMap<String, String>) -> Self {
+        Self {
+            _configuration: configuration,
+        }
+    }
 }
 
 impl crate::base_runtime::runtime::GuestAPIHostRegister for WasmRuntime {
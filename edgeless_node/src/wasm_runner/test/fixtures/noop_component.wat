@@ -0,0 +1,48 @@
+;; Core module of the noop_component.wasm fixture, which is made a component
+;; of the edgeless-function world of edgeless_function/wit by wit-component.
+;; Like functions/noop_component, it logs each invocation and its calls have
+;; no reply, but with constant messages.
+(module
+  (import "edgeless:function/host@0.1.0" "telemetry-log" (func $telemetry_log (param i32 i32 i32 i32 i32)))
+  (memory (export "memory") 1)
+  ;; Start of the free memory, which is never released.
+  (global $heap (mut i32) (i32.const 1024))
+  (data (i32.const 16) "noop_component")
+  (data (i32.const 32) "Noop initialized")
+  (data (i32.const 64) "Noop casted")
+  (data (i32.const 96) "Noop called")
+  (data (i32.const 128) "Noop stopped")
+  ;; call-ret returned by handle-call: no-reply.
+  (data (i32.const 160) "\00\00\00\00\00\00\00\00\00\00\00\00")
+
+  (func $log (param $msg i32) (param $len i32)
+    ;; log-level info.
+    (call $telemetry_log (i32.const 2) (i32.const 16) (i32.const 14) (local.get $msg) (local.get $len)))
+
+  (func (export "cabi_realloc") (param $old_ptr i32) (param $old_size i32) (param $align i32) (param $new_size i32) (result i32)
+    (local $ptr i32)
+    (local.set $ptr
+      (i32.and
+        (i32.add (global.get $heap) (i32.sub (local.get $align) (i32.const 1)))
+        (i32.sub (i32.const 0) (local.get $align))))
+    (global.set $heap (i32.add (local.get $ptr) (local.get $new_size)))
+    (if (i32.gt_u (global.get $heap) (i32.mul (memory.size) (i32.const 65536)))
+      (then
+        (if (i32.eq
+              (memory.grow (i32.add (i32.div_u (local.get $new_size) (i32.const 65536)) (i32.const 1)))
+              (i32.const -1))
+          (then unreachable))))
+    (local.get $ptr))
+
+  (func (export "handle-cast") (param i32 i32 i32 i32 i32 i32 i32 i32)
+    (call $log (i32.const 64) (i32.const 11)))
+
+  (func (export "handle-call") (param i32 i32 i32 i32 i32 i32 i32 i32) (result i32)
+    (call $log (i32.const 96) (i32.const 11))
+    (i32.const 160))
+
+  (func (export "handle-init") (param i32 i32 i32 i32 i32 i32)
+    (call $log (i32.const 32) (i32.const 16)))
+
+  (func (export "handle-stop")
+    (call $log (i32.const 128) (i32.const 12))))
@@ -0,0 +1,66 @@
+;; SPDX-FileCopyrightText: © 2024 Technical University of Munich, Chair of Connected Mobility
+;; SPDX-License-Identifier: MIT
+
+;; Source of wasi_test.wasm: a cast writes "hello" to the file "out" of the
+;; first directory preopened with WASI, and traps if it cannot.
+(module
+  (import "wasi_snapshot_preview1" "path_open"
+    (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "fd_close" (func $fd_close (param i32) (result i32)))
+
+  (memory (export "memory") 1)
+  (global $next (mut i32) (i32.const 1024))
+
+  ;; Path of the file.
+  (data (i32.const 16) "out")
+  ;; I/O vector with the content of the file.
+  (data (i32.const 32) "\30\00\00\00\05\00\00\00")
+  (data (i32.const 48) "hello")
+
+  (func (export "edgeless_mem_alloc") (param $size i32) (result i32) (local $ptr i32)
+    global.get $next
+    local.set $ptr
+    global.get $next
+    local.get $size
+    i32.add
+    global.set $next
+    local.get $ptr)
+  (func (export "edgeless_mem_free") (param i32 i32))
+  (func (export "edgeless_mem_clear")
+    i32.const 1024
+    global.set $next)
+
+  (func (export "handle_init_asm") (param i32 i32 i32 i32))
+  (func (export "handle_stop_asm"))
+  (func (export "handle_call_asm") (param i32 i32 i32 i32 i32 i32 i32 i32) (result i32)
+    i32.const 0)
+  (func (export "handle_cast_asm") (param i32 i32 i32 i32 i32 i32)
+    ;; Opens "out" in the first preopened directory, i.e., fd 3, with the
+    ;; O_CREAT and O_TRUNC flags and the right to write, and stores its fd at 64.
+    i32.const 3
+    i32.const 0
+    i32.const 16
+    i32.const 3
+    i32.const 9
+    i64.const 64
+    i64.const 0
+    i32.const 0
+    i32.const 64
+    call $path_open
+    if
+      unreachable
+    end
+    i32.const 64
+    i32.load
+    i32.const 32
+    i32.const 1
+    i32.const 72
+    call $fd_write
+    if
+      unreachable
+    end
+    i32.const 64
+    i32.load
+    call $fd_close
+    drop))
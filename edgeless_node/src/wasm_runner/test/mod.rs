@@ -67,6 +67,19 @@ fn mock_runtime() -> std::sync::Arc<tokio::sync::Mutex<Box<dyn crate::base_runti
     std::sync::Arc::new(tokio::sync::Mutex::new(Box::new(super::runtime::WasmRuntime::new())))
 }
 
+/// Context of the events sent by the tests, with the valid and sampled span
+/// context expected by the function instance runner.
+fn test_context() -> opentelemetry::Context {
+    use opentelemetry::trace::TraceContextExt;
+    opentelemetry::Context::new().with_remote_span_context(opentelemetry::trace::SpanContext::new(
+        opentelemetry::trace::TraceId::from_bytes(1u128.to_be_bytes()),
+        opentelemetry::trace::SpanId::from_bytes(1u64.to_be_bytes()),
+        opentelemetry::trace::TraceFlags::SAMPLED,
+        true,
+        opentelemetry::trace::TraceState::default(),
+    ))
+}
+
 #[tokio::test]
 async fn basic_lifecycle() {
     let node_id = uuid::Uuid::new_v4();
@@ -109,6 +122,7 @@ async fn basic_lifecycle() {
         state_specification: edgeless_api::function_instance::StateSpecification {
             state_id: instance_id.function_id,
             state_policy: edgeless_api::function_instance::StatePolicy::Transient,
+            initial_state: None,
         },
     };
 
@@ -264,6 +278,7 @@ async fn messaging_test_setup() -> (
         state_specification: edgeless_api::function_instance::StateSpecification {
             state_id: instance_id.function_id,
             state_policy: edgeless_api::function_instance::StatePolicy::Transient,
+            initial_state: None,
         },
     };
 
@@ -300,6 +315,7 @@ async fn messaging_cast_raw_input() {
             instance_id,
             edgeless_api::function_instance::PortId("test_cast_input".to_string()),
             "some_message".to_string(),
+            test_context(),
         )
         .await;
     tokio::time::sleep(Duration::from_millis(50)).await;
@@ -319,6 +335,7 @@ async fn messaging_cast_raw_output() {
             instance_id,
             edgeless_api::function_instance::PortId("test_cast_input".to_string()),
             "test_cast_raw_output".to_string(),
+            test_context(),
         )
         .await;
     tokio::time::sleep(Duration::from_millis(50)).await;
@@ -334,10 +351,7 @@ async fn messaging_cast_raw_output() {
 
     let test_message = test_peer_handle.receive_next().await;
     assert_eq!(test_message.source_id, instance_id);
-    assert_eq!(
-        test_message.message,
-        edgeless_dataplane::core::Message::Cast("cast_raw_output".to_string())
-    );
+    assert_eq!(test_message.message, edgeless_dataplane::core::Message::Cast(b"cast_raw_output".to_vec()));
 }
 
 // test output: call
@@ -350,6 +364,7 @@ async fn messaging_call_raw_output() {
             instance_id,
             edgeless_api::function_instance::PortId("test_cast_input".to_string()),
             "test_call_raw_output".to_string(),
+            test_context(),
         )
         .await;
     tokio::time::sleep(Duration::from_millis(50)).await;
@@ -359,10 +374,7 @@ async fn messaging_call_raw_output() {
 
     let test_message = test_peer_handle.receive_next().await;
     assert_eq!(test_message.source_id, instance_id);
-    assert_eq!(
-        test_message.message,
-        edgeless_dataplane::core::Message::Call("call_raw_output".to_string())
-    );
+    assert_eq!(test_message.message, edgeless_dataplane::core::Message::Call(b"call_raw_output".to_vec()));
 
     test_peer_handle
         .reply(test_message.source_id, test_message.channel_id, CallRet::NoReply)
@@ -389,6 +401,7 @@ async fn messaging_delayed_cast_output() {
             instance_id,
             edgeless_api::function_instance::PortId("test_cast_input".to_string()),
             "test_delayed_cast_output".to_string(),
+            test_context(),
         )
         .await;
     let start = tokio::time::Instant::now();
@@ -399,7 +412,7 @@ async fn messaging_delayed_cast_output() {
     assert_eq!(test_message.source_id, instance_id);
     assert_eq!(
         test_message.message,
-        edgeless_dataplane::core::Message::Cast("delayed_cast_output".to_string())
+        edgeless_dataplane::core::Message::Cast(b"delayed_cast_output".to_vec())
     );
 
     tokio::time::sleep(Duration::from_millis(50)).await;
@@ -424,6 +437,7 @@ async fn messaging_cast_output() {
             instance_id,
             edgeless_api::function_instance::PortId("test_cast_input".to_string()),
             "test_cast_output".to_string(),
+            test_context(),
         )
         .await;
     tokio::time::sleep(Duration::from_millis(50)).await;
@@ -439,7 +453,7 @@ async fn messaging_cast_output() {
 
     let test_message = next_handle.receive_next().await;
     assert_eq!(test_message.source_id, instance_id);
-    assert_eq!(test_message.message, edgeless_dataplane::core::Message::Cast("cast_output".to_string()));
+    assert_eq!(test_message.message, edgeless_dataplane::core::Message::Cast(b"cast_output".to_vec()));
 }
 
 // test output: call
@@ -452,6 +466,7 @@ async fn messaging_call_output() {
             instance_id,
             edgeless_api::function_instance::PortId("test_cast_input".to_string()),
             "test_call_output".to_string(),
+            test_context(),
         )
         .await;
     tokio::time::sleep(Duration::from_millis(50)).await;
@@ -462,7 +477,7 @@ async fn messaging_call_output() {
 
     let test_message = next_handle.receive_next().await;
     assert_eq!(test_message.source_id, instance_id);
-    assert_eq!(test_message.message, edgeless_dataplane::core::Message::Call("call_output".to_string()));
+    assert_eq!(test_message.message, edgeless_dataplane::core::Message::Call(b"call_output".to_vec()));
 
     next_handle.reply(test_message.source_id, test_message.channel_id, CallRet::NoReply).await;
     tokio::time::sleep(Duration::from_millis(50)).await;
@@ -488,6 +503,7 @@ async fn function_in_call_can_be_stopped() {
             instance_id,
             edgeless_api::function_instance::PortId("test_cast_input".to_string()),
             "test_call_output".to_string(),
+            test_context(),
         )
         .await;
     tokio::time::sleep(Duration::from_millis(50)).await;
@@ -497,7 +513,7 @@ async fn function_in_call_can_be_stopped() {
 
     let test_message = next_handle.receive_next().await;
     assert_eq!(test_message.source_id, instance_id);
-    assert_eq!(test_message.message, edgeless_dataplane::core::Message::Call("call_output".to_string()));
+    assert_eq!(test_message.message, edgeless_dataplane::core::Message::Call(b"call_output".to_vec()));
 
     assert!(telemetry_mock_receiver.try_recv().is_err());
 
@@ -518,6 +534,7 @@ async fn messaging_call_raw_input_noreply() {
             instance_id,
             edgeless_api::function_instance::PortId("test_input_noreply".to_string()),
             "some_cast".to_string(),
+            test_context(),
         )
         .await;
     assert_eq!(ret, CallRet::NoReply);
@@ -542,9 +559,10 @@ async fn messaging_call_raw_input_reply() {
             instance_id,
             edgeless_api::function_instance::PortId("test_input_reply".to_string()),
             "test_ret".to_string(),
+            test_context(),
         )
         .await;
-    assert_eq!(ret, CallRet::Reply("test_reply".to_string()));
+    assert_eq!(ret, CallRet::Reply(b"test_reply".to_vec()));
 
     let telemetry_event = telemetry_mock_receiver.try_recv();
     assert!(telemetry_event.is_ok());
@@ -634,6 +652,7 @@ async fn state_management() {
         state_specification: edgeless_api::function_instance::StateSpecification {
             state_id: instance_id.function_id,
             state_policy: edgeless_api::function_instance::StatePolicy::Transient,
+            initial_state: None,
         },
     };
 
@@ -665,6 +684,7 @@ async fn state_management() {
             instance_id,
             edgeless_api::function_instance::PortId("test_cast_input".to_string()),
             "test_cast_raw_output".to_string(),
+            test_context(),
         )
         .await;
     tokio::time::sleep(Duration::from_millis(100)).await;
@@ -712,3 +732,229 @@ async fn state_management() {
     assert!(telemetry_mock_receiver.try_recv().is_ok());
     assert!(telemetry_mock_receiver.try_recv().is_err());
 }
+
+/// Starts an instance with the given code, runtime configuration, and
+/// annotations, and casts an event to the given port, e.g., of
+/// `limits_test.wasm`, which does not return or grows the memory.
+/// The instance runs as long as the returned client is not dropped.
+async fn fixture_cast(
+    code: &[u8],
+    configuration: std::collections::HashMap<String, String>,
+    annotations: std::collections::HashMap<String, String>,
+    port: &str,
+) -> (
+    crate::base_runtime::runtime::RuntimeClient,
+    InstanceId,
+    std::sync::mpsc::Receiver<(
+        edgeless_telemetry::telemetry_events::TelemetryEvent,
+        std::collections::BTreeMap<String, String>,
+    )>,
+) {
+    let node_id = uuid::Uuid::new_v4();
+    let instance_id = edgeless_api::function_instance::InstanceId::new(node_id);
+
+    let state_manager = Box::new(crate::state_management::StateManager::new().await);
+    let mut dataplane_provider = edgeless_dataplane::handle::DataplaneProvider::new(node_id, "http://127.0.0.1:7002".to_string(), None).await;
+    let mut test_peer_handle = dataplane_provider
+        .get_handle_for(edgeless_api::function_instance::InstanceId::new(node_id))
+        .await;
+
+    let (telemetry_mock_sender, telemetry_mock_receiver) = std::sync::mpsc::channel::<(
+        edgeless_telemetry::telemetry_events::TelemetryEvent,
+        std::collections::BTreeMap<String, String>,
+    )>();
+    let telemetry_handle = Box::new(MockTelemetryHandle {
+        sender: telemetry_mock_sender,
+    });
+
+    let (mut client, mut rt_task) = crate::base_runtime::runtime::create::<super::function_instance::WASMFunctionInstance>(
+        dataplane_provider,
+        state_manager,
+        telemetry_handle,
+        std::sync::Arc::new(tokio::sync::Mutex::new(Box::new(super::runtime::WasmRuntime::with_configuration(
+            configuration,
+        )))),
+    );
+
+    tokio::spawn(async move { rt_task.run().await });
+
+    let spawn_req = edgeless_api::function_instance::SpawnFunctionRequest {
+        instance_id,
+        code: edgeless_api::function_instance::FunctionClassSpecification {
+            function_class_id: "EXAMPLE_1".to_string(),
+            function_class_type: "RUST_WASM".to_string(),
+            function_class_version: "0.1".to_string(),
+            function_class_code: code.to_vec(),
+            function_class_outputs: std::collections::HashMap::new(),
+            function_class_inputs: std::collections::HashMap::new(),
+            function_class_inner_structure: std::collections::HashMap::new(),
+        },
+        input_mapping: std::collections::HashMap::new(),
+        output_mapping: std::collections::HashMap::new(),
+        annotations,
+        state_specification: edgeless_api::function_instance::StateSpecification {
+            state_id: instance_id.function_id,
+            state_policy: edgeless_api::function_instance::StatePolicy::Transient,
+            initial_state: None,
+        },
+    };
+    assert!(client.start(spawn_req).await.is_ok());
+
+    // Wait for the initialization of the instance, before which the events are lost.
+    loop {
+        match telemetry_mock_receiver.recv_timeout(Duration::from_secs(5)) {
+            Ok((TelemetryEvent::FunctionInit(_), _tags)) => break,
+            Ok(_) => continue,
+            Err(err) => panic!("the instance was not initialized: {}", err),
+        }
+    }
+
+    test_peer_handle
+        .send(
+            instance_id,
+            edgeless_api::function_instance::PortId(port.to_string()),
+            "fixture_cast".to_string(),
+            test_context(),
+        )
+        .await;
+
+    (client, instance_id, telemetry_mock_receiver)
+}
+
+/// Waits for the exit of the instance, returning its status.
+fn exit_status(
+    telemetry_mock_receiver: &std::sync::mpsc::Receiver<(
+        edgeless_telemetry::telemetry_events::TelemetryEvent,
+        std::collections::BTreeMap<String, String>,
+    )>,
+) -> edgeless_telemetry::telemetry_events::FunctionExitStatus {
+    loop {
+        match telemetry_mock_receiver.recv_timeout(Duration::from_secs(5)) {
+            Ok((TelemetryEvent::FunctionExit(status), _tags)) => return status,
+            Ok(_) => continue,
+            Err(err) => panic!("the instance did not exit: {}", err),
+        }
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn execution_time_limit() {
+    let (_client, _instance_id, telemetry_mock_receiver) = fixture_cast(
+        include_bytes!("fixtures/limits_test.wasm"),
+        std::collections::HashMap::new(),
+        std::collections::HashMap::from([(crate::base_runtime::limits::MAX_EXECUTION_TIME_ANNOTATION.to_string(), "100".to_string())]),
+        "loop",
+    )
+    .await;
+
+    assert_eq!(
+        exit_status(&telemetry_mock_receiver),
+        edgeless_telemetry::telemetry_events::FunctionExitStatus::ResourceLimitExceeded
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn memory_limit() {
+    let (_client, _instance_id, telemetry_mock_receiver) = fixture_cast(
+        include_bytes!("fixtures/limits_test.wasm"),
+        std::collections::HashMap::new(),
+        std::collections::HashMap::from([(crate::base_runtime::limits::MAX_MEMORY_PAGES_ANNOTATION.to_string(), "16".to_string())]),
+        "grow_memory",
+    )
+    .await;
+
+    assert_eq!(
+        exit_status(&telemetry_mock_receiver),
+        edgeless_telemetry::telemetry_events::FunctionExitStatus::ResourceLimitExceeded
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn wasi_dir_of_function() {
+    let root = std::env::temp_dir().join(format!("edgeless-wasi-test-{}", uuid::Uuid::new_v4()));
+    let (_client, instance_id, telemetry_mock_receiver) = fixture_cast(
+        include_bytes!("fixtures/wasi_test.wasm"),
+        std::collections::HashMap::from([(super::wasi::WASI_DIRS_ROOT_CONFIGURATION.to_string(), root.to_str().unwrap().to_string())]),
+        std::collections::HashMap::from([(format!("{}/data", super::wasi::WASI_DIR_ANNOTATION_PREFIX), "my_data".to_string())]),
+        "test",
+    )
+    .await;
+
+    loop {
+        match telemetry_mock_receiver.recv_timeout(Duration::from_secs(5)) {
+            Ok((TelemetryEvent::FunctionInvocationCompleted(_), _tags)) => break,
+            Ok((TelemetryEvent::FunctionExit(status), _tags)) => panic!("the instance exited: {:?}", status),
+            Ok(_) => continue,
+            Err(err) => panic!("the cast was not processed: {}", err),
+        }
+    }
+    let path = root.join(instance_id.function_id.to_string()).join("my_data").join("out");
+    assert_eq!(std::fs::read_to_string(path).unwrap(), "hello");
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+// The instance is driven directly, rather than by a function instance runner,
+// to also stop it.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn component_lifecycle() {
+    use crate::base_runtime::FunctionInstance;
+    use crate::state_management::StateManagerAPI;
+    use opentelemetry::trace::TracerProvider;
+
+    let node_id = uuid::Uuid::new_v4();
+    let instance_id = InstanceId::new(node_id);
+    let mut dataplane_provider = edgeless_dataplane::handle::DataplaneProvider::new(node_id, "http://127.0.0.1:7002".to_string(), None).await;
+    let (telemetry_mock_sender, telemetry_mock_receiver) = std::sync::mpsc::channel();
+    let (_poison_pill_sender, poison_pill_receiver) = tokio::sync::broadcast::channel(1);
+    let mut guest_api_host = Some(crate::base_runtime::guest_api::GuestAPIHost {
+        instance_id,
+        data_plane: dataplane_provider.get_handle_for(instance_id).await,
+        state_handle: crate::state_management::StateManager::new()
+            .await
+            .get_handle(edgeless_api::function_instance::StatePolicy::Transient, instance_id.function_id)
+            .await,
+        telemetry_handle: Box::new(MockTelemetryHandle {
+            sender: telemetry_mock_sender,
+        }),
+        poison_pill_receiver,
+        tracing_context: std::sync::Arc::new(tokio::sync::Mutex::new(crate::base_runtime::function_instance_runner::TracingContext {
+            tracer: opentelemetry_sdk::trace::TracerProvider::builder().build().tracer("test"),
+            parent_context: test_context(),
+        })),
+        waiting_time: Duration::ZERO,
+    });
+
+    let mut instance = super::any_instance::AnyWASMFunctionInstance::instantiate(
+        &instance_id,
+        std::collections::HashMap::new(),
+        &std::collections::HashMap::new(),
+        &mut guest_api_host,
+        include_bytes!("fixtures/noop_component.wasm"),
+    )
+    .await
+    .unwrap();
+    assert!(matches!(*instance, super::any_instance::AnyWASMFunctionInstance::Component(_)));
+
+    instance.init(Some("payload"), Some("state")).await.unwrap();
+    instance.cast(&instance_id, "test", b"cast message").await.unwrap();
+    assert_eq!(instance.call(&instance_id, "test", b"call message").await.unwrap(), CallRet::NoReply);
+    instance.stop().await.unwrap();
+
+    let logs: Vec<String> = telemetry_mock_receiver
+        .try_iter()
+        .filter_map(|(event, _tags)| match event {
+            TelemetryEvent::FunctionLogEntry(_level, target, msg) => Some(format!("{}: {}", target, msg)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        logs,
+        vec![
+            "noop_component: Noop initialized",
+            "noop_component: Noop casted",
+            "noop_component: Noop called",
+            "noop_component: Noop stopped"
+        ]
+    );
+}
@@ -0,0 +1,229 @@
+// SPDX-FileCopyrightText: © 2024 Technical University of Munich, Chair of Connected Mobility
+// SPDX-License-Identifier: MIT
+
+/// Environment variable of the instance, e.g., `wasi_env.RUST_LOG=debug`.
+pub const WASI_ENV_ANNOTATION_PREFIX: &str = "wasi_env.";
+
+/// Directory preopened at the given guest path, e.g., `wasi_dir./data=my_data`,
+/// which is a sub-directory of the directory of the function instance below
+/// the WASI root directory of the node.
+pub const WASI_DIR_ANNOTATION_PREFIX: &str = "wasi_dir.";
+
+/// Whether the instance can read the wall and monotonic clocks.
+pub const WASI_CLOCKS_ANNOTATION: &str = "wasi_clocks";
+
+/// Whether the instance can get random numbers.
+pub const WASI_RANDOM_ANNOTATION: &str = "wasi_random";
+
+/// Key of the runtime configuration with the directory of the node that
+/// contains the directories that can be preopened.
+pub const WASI_DIRS_ROOT_CONFIGURATION: &str = "wasi_dirs_root";
+
+/// WASI capabilities of a function instance, set by its annotations.
+///
+/// An instance gets no capability by default: no environment variable, no
+/// directory, and the clocks and random numbers return an error.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WasiPolicy {
+    /// Name and value of the environment variables.
+    pub env: Vec<(String, String)>,
+    /// Guest path and directory relative to the directory of the instance.
+    pub dirs: Vec<(String, String)>,
+    pub clocks: bool,
+    pub random: bool,
+}
+
+impl WasiPolicy {
+    pub fn from_annotations(annotations: &std::collections::HashMap<String, String>) -> Self {
+        let mut policy = Self::default();
+        for (key, val) in annotations {
+            if let Some(name) = key.strip_prefix(WASI_ENV_ANNOTATION_PREFIX) {
+                policy.env.push((name.to_string(), val.clone()));
+                continue;
+            }
+            if let Some(guest_path) = key.strip_prefix(WASI_DIR_ANNOTATION_PREFIX) {
+                policy.dirs.push((guest_path.to_string(), val.clone()));
+                continue;
+            }
+            let capability = match key.as_str() {
+                WASI_CLOCKS_ANNOTATION => &mut policy.clocks,
+                WASI_RANDOM_ANNOTATION => &mut policy.random,
+                _ => continue,
+            };
+            match val.parse::<bool>() {
+                Ok(val) => *capability = val,
+                Err(_) => log::warn!("invalid value of annotation {}: {}", key, val),
+            }
+        }
+        policy.env.sort();
+        policy.dirs.sort();
+        policy
+    }
+
+    /// WASI context of an instance, with the directories opened below the
+    /// directory of the instance `instance_dir` in `dirs_root`, which they
+    /// cannot escape, so that the instances of different functions never
+    /// share files. The directories are created if missing.
+    pub fn context(&self, dirs_root: Option<&std::path::Path>, instance_dir: &str) -> anyhow::Result<wasmtime_wasi::WasiCtx> {
+        let random: Box<dyn rand_core::RngCore + Send + Sync> = match self.random {
+            true => wasmtime_wasi::sync::random_ctx(),
+            false => Box::new(NoRandom {}),
+        };
+        let clocks = match self.clocks {
+            true => wasmtime_wasi::sync::clocks_ctx(),
+            false => wasi_common::WasiClocks::new(),
+        };
+        // The scheduler and the files of tokio do not block the runtime.
+        let mut ctx = wasmtime_wasi::WasiCtx::new(random, clocks, wasmtime_wasi::tokio::sched_ctx(), wasi_common::Table::new());
+
+        for (name, value) in &self.env {
+            ctx.push_env(name, value)?;
+        }
+        for (guest_path, dir) in self.open_dirs(dirs_root, instance_dir)? {
+            ctx.push_preopened_dir(Box::new(wasmtime_wasi::tokio::Dir::from_cap_std(dir)), guest_path)?;
+        }
+        Ok(ctx)
+    }
+
+    /// WASI preview 2 context of a component instance, like `context`.
+    /// Since the clocks of WASI preview 2 cannot fail, they are stopped at
+    /// zero for the instances that cannot read them, while getting random
+    /// numbers traps (see `engine::add_random_to_linker`).
+    pub fn component_context(&self, dirs_root: Option<&std::path::Path>, instance_dir: &str) -> anyhow::Result<wasmtime_wasi::preview2::WasiCtx> {
+        let mut builder = wasmtime_wasi::preview2::WasiCtxBuilder::new();
+        for (name, value) in &self.env {
+            builder.env(name, value);
+        }
+        for (guest_path, dir) in self.open_dirs(dirs_root, instance_dir)? {
+            builder.preopened_dir(
+                dir,
+                wasmtime_wasi::preview2::DirPerms::all(),
+                wasmtime_wasi::preview2::FilePerms::all(),
+                guest_path,
+            );
+        }
+        if !self.clocks {
+            builder.wall_clock(StoppedClock {}).monotonic_clock(StoppedClock {});
+        }
+        if !self.random {
+            builder.secure_random(NoRandom {}).insecure_random(NoRandom {}).insecure_random_seed(0);
+        }
+        Ok(builder.build())
+    }
+
+    /// Guest paths and directories, opened below the directory of the instance.
+    fn open_dirs(&self, dirs_root: Option<&std::path::Path>, instance_dir: &str) -> anyhow::Result<Vec<(&str, wasmtime_wasi::sync::Dir)>> {
+        if self.dirs.is_empty() {
+            return Ok(vec![]);
+        }
+        let dirs_root = dirs_root.ok_or(anyhow::anyhow!("the node does not allow WASI directories"))?;
+        let root = wasmtime_wasi::sync::Dir::open_ambient_dir(dirs_root, wasmtime_wasi::sync::ambient_authority())?;
+        root.create_dir_all(instance_dir)?;
+        let root = root.open_dir(instance_dir)?;
+        let mut dirs = vec![];
+        for (guest_path, host_dir) in &self.dirs {
+            root.create_dir_all(host_dir)?;
+            dirs.push((guest_path.as_str(), root.open_dir(host_dir)?));
+        }
+        Ok(dirs)
+    }
+}
+
+/// Clocks of the component instances that cannot read the clocks.
+struct StoppedClock {}
+
+impl wasmtime_wasi::preview2::HostWallClock for StoppedClock {
+    fn resolution(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(1)
+    }
+
+    fn now(&self) -> std::time::Duration {
+        std::time::Duration::ZERO
+    }
+}
+
+impl wasmtime_wasi::preview2::HostMonotonicClock for StoppedClock {
+    fn resolution(&self) -> u64 {
+        1_000_000_000
+    }
+
+    fn now(&self) -> u64 {
+        0
+    }
+}
+
+/// Random number generator of the instances that cannot get random numbers,
+/// for which the WASI calls fail.
+struct NoRandom {}
+
+impl rand_core::RngCore for NoRandom {
+    fn next_u32(&mut self) -> u32 {
+        0
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        0
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        dest.fill(0);
+    }
+
+    fn try_fill_bytes(&mut self, _dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        Err(rand_core::Error::new(std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            "random numbers not allowed",
+        )))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn policy_from_annotations() {
+        let policy = super::WasiPolicy::from_annotations(&std::collections::HashMap::from([
+            ("wasi_env.RUST_LOG".to_string(), "debug".to_string()),
+            ("wasi_env.MODE".to_string(), "a=b".to_string()),
+            ("wasi_dir./data".to_string(), "my_function".to_string()),
+            ("wasi_clocks".to_string(), "true".to_string()),
+            ("wasi_random".to_string(), "maybe".to_string()),
+            ("max_memory_pages".to_string(), "10".to_string()),
+        ]));
+        assert_eq!(
+            policy,
+            super::WasiPolicy {
+                env: vec![("MODE".to_string(), "a=b".to_string()), ("RUST_LOG".to_string(), "debug".to_string())],
+                dirs: vec![("/data".to_string(), "my_function".to_string())],
+                clocks: true,
+                random: false,
+            }
+        );
+    }
+
+    #[test]
+    fn dirs_stay_below_root() {
+        let root = std::env::temp_dir().join(format!("edgeless-wasi-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&root).unwrap();
+
+        let policy = super::WasiPolicy {
+            dirs: vec![("/data".to_string(), "my_data".to_string())],
+            ..Default::default()
+        };
+        assert!(policy.context(None, "function_1").is_err());
+        assert!(policy.context(Some(&root), "function_1").is_ok());
+        assert!(policy.component_context(Some(&root), "function_1").is_ok());
+        assert!(root.join("function_1").join("my_data").is_dir());
+
+        // Not even into the directory of another function.
+        let escaping = super::WasiPolicy {
+            dirs: vec![("/data".to_string(), "../function_2".to_string())],
+            ..Default::default()
+        };
+        assert!(escaping.context(Some(&root), "function_1").is_err());
+        assert!(escaping.component_context(Some(&root), "function_1").is_err());
+        assert!(!root.join("function_2").exists());
+        assert!(super::WasiPolicy::default().context(None, "function_1").is_ok());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
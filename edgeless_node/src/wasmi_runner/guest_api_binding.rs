@@ -6,6 +6,7 @@ use wasmi::AsContextMut;
 use super::helpers::*;
 pub struct GuestAPI {
     pub host: crate::base_runtime::guest_api::GuestAPIHost,
+    pub limiter: crate::base_runtime::limits::WasmLimiter,
 }
 
 pub fn telemetry_log(
@@ -43,7 +44,7 @@ pub fn cast_raw(
 
     let port = load_string_from_vm(&mut caller.as_context_mut(), &mem, port_ptr, port_len)?;
 
-    let payload = load_string_from_vm(&mut caller.as_context_mut(), &mem, payload_ptr, payload_len)?;
+    let payload = load_bytes_from_vm(&mut caller.as_context_mut(), &mem, payload_ptr, payload_len)?;
 
     tokio::runtime::Handle::current()
         .block_on(
@@ -77,7 +78,7 @@ pub fn call_raw(
     };
 
     let port = load_string_from_vm(&mut caller.as_context_mut(), &mem, port_ptr, port_len)?;
-    let payload = load_string_from_vm(&mut caller.as_context_mut(), &mem, payload_ptr, payload_len)?;
+    let payload = load_bytes_from_vm(&mut caller.as_context_mut(), &mem, payload_ptr, payload_len)?;
 
     let call_ret = tokio::runtime::Handle::current()
         .block_on(
@@ -90,31 +91,59 @@ pub fn call_raw(
     match call_ret {
         edgeless_dataplane::core::CallRet::NoReply => Ok(0),
         edgeless_dataplane::core::CallRet::Reply(data) => {
-            let len = data.as_bytes().len();
+            let len = data.len();
 
-            let data_ptr = copy_to_vm(&mut caller.as_context_mut(), &mem, &alloc, data.as_bytes())?;
+            let data_ptr = copy_to_vm(&mut caller.as_context_mut(), &mem, &alloc, &data)?;
             copy_to_vm_ptr(&mut caller.as_context_mut(), &mem, out_ptr_ptr, &data_ptr.to_le_bytes())?;
             copy_to_vm_ptr(&mut caller.as_context_mut(), &mem, out_len_ptr, &len.to_le_bytes())?;
 
             Ok(1)
         }
         edgeless_dataplane::core::CallRet::Err => Ok(2),
+        edgeless_dataplane::core::CallRet::Timeout => Ok(3),
     }
 }
 
 pub fn cast(
+    caller: wasmi::Caller<'_, GuestAPI>,
+    target_ptr: i32,
+    target_len: i32,
+    payload_ptr: i32,
+    payload_len: i32,
+) -> Result<(), wasmi::core::Trap> {
+    cast_inner(caller, target_ptr, target_len, None, payload_ptr, payload_len)
+}
+
+pub fn cast_with_key(
+    caller: wasmi::Caller<'_, GuestAPI>,
+    target_ptr: i32,
+    target_len: i32,
+    key_ptr: i32,
+    key_len: i32,
+    payload_ptr: i32,
+    payload_len: i32,
+) -> Result<(), wasmi::core::Trap> {
+    cast_inner(caller, target_ptr, target_len, Some((key_ptr, key_len)), payload_ptr, payload_len)
+}
+
+fn cast_inner(
     mut caller: wasmi::Caller<'_, GuestAPI>,
     target_ptr: i32,
     target_len: i32,
+    key: Option<(i32, i32)>,
     payload_ptr: i32,
     payload_len: i32,
 ) -> Result<(), wasmi::core::Trap> {
     let mem = get_memory(&mut caller)?;
 
     let target = load_string_from_vm(&mut caller.as_context_mut(), &mem, target_ptr, target_len)?;
-    let payload = load_string_from_vm(&mut caller.as_context_mut(), &mem, payload_ptr, payload_len)?;
+    let key = match key {
+        Some((key_ptr, key_len)) => Some(load_bytes_from_vm(&mut caller.as_context_mut(), &mem, key_ptr, key_len)?),
+        None => None,
+    };
+    let payload = load_bytes_from_vm(&mut caller.as_context_mut(), &mem, payload_ptr, payload_len)?;
 
-    match tokio::runtime::Handle::current().block_on(caller.data_mut().host.cast_alias(&target, &payload)) {
+    match tokio::runtime::Handle::current().block_on(caller.data_mut().host.cast_alias(&target, key.as_deref(), &payload)) {
         Ok(_) => {}
         Err(_) => {
             // We ignore casts to unknown targets.
@@ -126,9 +155,47 @@ pub fn cast(
 }
 
 pub fn call(
+    caller: wasmi::Caller<'_, GuestAPI>,
+    target_ptr: i32,
+    target_len: i32,
+    payload_ptr: i32,
+    payload_len: i32,
+    out_ptr_ptr: i32,
+    out_len_ptr: i32,
+) -> Result<i32, wasmi::core::Trap> {
+    call_inner(caller, target_ptr, target_len, None, payload_ptr, payload_len, out_ptr_ptr, out_len_ptr)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn call_with_key(
+    caller: wasmi::Caller<'_, GuestAPI>,
+    target_ptr: i32,
+    target_len: i32,
+    key_ptr: i32,
+    key_len: i32,
+    payload_ptr: i32,
+    payload_len: i32,
+    out_ptr_ptr: i32,
+    out_len_ptr: i32,
+) -> Result<i32, wasmi::core::Trap> {
+    call_inner(
+        caller,
+        target_ptr,
+        target_len,
+        Some((key_ptr, key_len)),
+        payload_ptr,
+        payload_len,
+        out_ptr_ptr,
+        out_len_ptr,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn call_inner(
     mut caller: wasmi::Caller<'_, GuestAPI>,
     target_ptr: i32,
     target_len: i32,
+    key: Option<(i32, i32)>,
     payload_ptr: i32,
     payload_len: i32,
     out_ptr_ptr: i32,
@@ -138,24 +205,61 @@ pub fn call(
     let alloc = get_alloc(&mut caller)?;
 
     let target = load_string_from_vm(&mut caller.as_context_mut(), &mem, target_ptr, target_len)?;
-    let payload = load_string_from_vm(&mut caller.as_context_mut(), &mem, payload_ptr, payload_len)?;
+    let key = match key {
+        Some((key_ptr, key_len)) => Some(load_bytes_from_vm(&mut caller.as_context_mut(), &mem, key_ptr, key_len)?),
+        None => None,
+    };
+    let payload = load_bytes_from_vm(&mut caller.as_context_mut(), &mem, payload_ptr, payload_len)?;
 
     let call_ret = tokio::runtime::Handle::current()
-        .block_on(caller.data_mut().host.call_alias(&target, &payload))
+        .block_on(caller.data_mut().host.call_alias(&target, key.as_deref(), &payload))
         .map_err(|_| wasmi::core::Trap::new("call error"))?;
     match call_ret {
         edgeless_dataplane::core::CallRet::NoReply => Ok(0),
         edgeless_dataplane::core::CallRet::Reply(data) => {
-            let len = data.as_bytes().len();
+            let len = data.len();
 
-            let data_ptr = copy_to_vm(&mut caller.as_context_mut(), &mem, &alloc, data.as_bytes())?;
+            let data_ptr = copy_to_vm(&mut caller.as_context_mut(), &mem, &alloc, &data)?;
             copy_to_vm_ptr(&mut caller.as_context_mut(), &mem, out_ptr_ptr, &data_ptr.to_le_bytes())?;
             copy_to_vm_ptr(&mut caller.as_context_mut(), &mem, out_len_ptr, &len.to_le_bytes())?;
 
             Ok(1)
         }
         edgeless_dataplane::core::CallRet::Err => Ok(2),
+        edgeless_dataplane::core::CallRet::Timeout => Ok(3),
+    }
+}
+
+pub fn call_all(
+    mut caller: wasmi::Caller<'_, GuestAPI>,
+    target_ptr: i32,
+    target_len: i32,
+    payload_ptr: i32,
+    payload_len: i32,
+    out_ptr_ptr: i32,
+    out_len_ptr: i32,
+) -> Result<i32, wasmi::core::Trap> {
+    let mem = get_memory(&mut caller)?;
+    let alloc = get_alloc(&mut caller)?;
+
+    let target = load_string_from_vm(&mut caller.as_context_mut(), &mem, target_ptr, target_len)?;
+    let payload = load_bytes_from_vm(&mut caller.as_context_mut(), &mem, payload_ptr, payload_len)?;
+
+    let gather_ret = match tokio::runtime::Handle::current().block_on(caller.data_mut().host.call_all_alias(&target, &payload)) {
+        Ok(gather_ret) => gather_ret,
+        Err(_) => {
+            log::warn!("Call on unknown target");
+            return Ok(2);
+        }
+    };
+    let data = crate::base_runtime::guest_api::encode_gathered_replies(&gather_ret.replies);
+    if !data.is_empty() {
+        let len = data.len();
+        let data_ptr = copy_to_vm(&mut caller.as_context_mut(), &mem, &alloc, &data)?;
+        copy_to_vm_ptr(&mut caller.as_context_mut(), &mem, out_ptr_ptr, &data_ptr.to_le_bytes())?;
+        copy_to_vm_ptr(&mut caller.as_context_mut(), &mem, out_len_ptr, &len.to_le_bytes())?;
     }
+    Ok(if gather_ret.quorum_reached { 1 } else { 0 })
 }
 
 pub fn delayed_cast(
@@ -168,7 +272,7 @@ pub fn delayed_cast(
 ) -> Result<(), wasmi::core::Trap> {
     let mem = get_memory(&mut caller)?;
     let target = load_string_from_vm(&mut caller.as_context_mut(), &mem, target_ptr, target_len)?;
-    let payload = load_string_from_vm(&mut caller.as_context_mut(), &mem, payload_ptr, payload_len)?;
+    let payload = load_bytes_from_vm(&mut caller.as_context_mut(), &mem, payload_ptr, payload_len)?;
 
     tokio::runtime::Handle::current()
         .block_on(caller.data_mut().host.delayed_cast(delay_ms as u64, &target, &payload))
@@ -9,6 +9,14 @@ pub mod runtime;
 
 #[cfg(test)]
 pub mod test;
+
+/// Fuel of an invocation for each ms of its execution time limit, which is
+/// roughly the number of instructions run by wasmi in 1 ms. The limit is
+/// enforced with fuel, since the interpreter cannot be interrupted, so the
+/// time waited by the host calls, e.g., for the replies to calls, is not
+/// counted as with wasmtime.
+const FUEL_PER_MILLISECOND: u64 = 100_000;
+
 pub struct WASMIFunctionInstance {
     edgeless_mem_alloc: wasmi::TypedFunc<i32, i32>,
     edgeless_mem_free: wasmi::TypedFunc<(i32, i32), ()>,
@@ -49,26 +57,46 @@ pub struct WASMIFunctionInstance {
     edgefunctione_handle_stop: wasmi::TypedFunc<(), ()>,
     memory: wasmi::Memory,
     store: wasmi::Store<guest_api_binding::GuestAPI>,
+    /// Fuel of an invocation, if limited.
+    fuel_per_invocation: Option<u64>,
+    /// Total fuel added to the store.
+    fuel_added: u64,
 }
 
 #[async_trait::async_trait]
 impl crate::base_runtime::FunctionInstance for WASMIFunctionInstance {
     async fn instantiate(
         _instance_id: &edgeless_api::function_instance::InstanceId,
-        _runtime_configuration: std::collections::HashMap<String, String>,
+        runtime_configuration: std::collections::HashMap<String, String>,
+        _annotations: &std::collections::HashMap<String, String>,
         guest_api_host: &mut Option<crate::base_runtime::guest_api::GuestAPIHost>,
         code: &[u8],
     ) -> Result<Box<Self>, crate::base_runtime::FunctionInstanceError> {
-        let _comfig = wasmi::Config::default();
+        let limits = crate::base_runtime::limits::FunctionLimits::from_annotations(&runtime_configuration);
+        let fuel_per_invocation = limits
+            .max_execution_time()
+            .map(|max| (max.as_millis() as u64).saturating_mul(FUEL_PER_MILLISECOND));
 
-        let engine = wasmi::Engine::default();
+        let mut config = wasmi::Config::default();
+        config.consume_fuel(fuel_per_invocation.is_some());
+
+        let engine = wasmi::Engine::new(&config);
         let module = wasmi::Module::new(&engine, &code[..]).map_err(|_| crate::base_runtime::FunctionInstanceError::InternalError)?;
         let mut store = wasmi::Store::new(
             &engine,
             guest_api_binding::GuestAPI {
                 host: guest_api_host.take().expect("the impossible happened: no GuestAPIHost"),
+                limiter: crate::base_runtime::limits::WasmLimiter::new(limits),
             },
         );
+        store.limiter(|guest_api| &mut guest_api.limiter);
+        // Also limits the start function run by the instantiation.
+        let fuel_added = fuel_per_invocation.unwrap_or(0);
+        if fuel_added > 0 {
+            store
+                .add_fuel(fuel_added)
+                .map_err(|_| crate::base_runtime::FunctionInstanceError::InternalError)?;
+        }
         let mut linker = wasmi::Linker::<guest_api_binding::GuestAPI>::new(&engine);
 
         linker
@@ -77,12 +105,21 @@ impl crate::base_runtime::FunctionInstance for WASMIFunctionInstance {
         linker
             .define("env", "cast_asm", wasmi::Func::wrap(&mut store, guest_api_binding::cast))
             .map_err(|_| crate::base_runtime::FunctionInstanceError::InternalError)?;
+        linker
+            .define("env", "cast_key_asm", wasmi::Func::wrap(&mut store, guest_api_binding::cast_with_key))
+            .map_err(|_| crate::base_runtime::FunctionInstanceError::InternalError)?;
         linker
             .define("env", "call_raw_asm", wasmi::Func::wrap(&mut store, guest_api_binding::call_raw))
             .map_err(|_| crate::base_runtime::FunctionInstanceError::InternalError)?;
         linker
             .define("env", "call_asm", wasmi::Func::wrap(&mut store, guest_api_binding::call))
             .map_err(|_| crate::base_runtime::FunctionInstanceError::InternalError)?;
+        linker
+            .define("env", "call_key_asm", wasmi::Func::wrap(&mut store, guest_api_binding::call_with_key))
+            .map_err(|_| crate::base_runtime::FunctionInstanceError::InternalError)?;
+        linker
+            .define("env", "call_all_asm", wasmi::Func::wrap(&mut store, guest_api_binding::call_all))
+            .map_err(|_| crate::base_runtime::FunctionInstanceError::InternalError)?;
         linker
             .define(
                 "env",
@@ -102,9 +139,13 @@ impl crate::base_runtime::FunctionInstance for WASMIFunctionInstance {
 
         let instance = linker
             .instantiate(&mut store, &module)
-            .map_err(|_| crate::base_runtime::FunctionInstanceError::InternalError)?
-            .start(&mut store)
-            .map_err(|_| crate::base_runtime::FunctionInstanceError::InternalError)?;
+            .map_err(|_| crate::base_runtime::FunctionInstanceError::InternalError)
+            .and_then(|instance| {
+                instance
+                    .start(&mut store)
+                    .map_err(|err| code_error(&mut store, err, crate::base_runtime::FunctionInstanceError::InternalError))
+            });
+        let instance = store.data().limiter.check(instance)?;
 
         Ok(Box::new(Self {
             edgeless_mem_alloc: instance
@@ -132,10 +173,66 @@ impl crate::base_runtime::FunctionInstance for WASMIFunctionInstance {
                 .get_memory(&mut store, "memory")
                 .ok_or_else(|| (crate::base_runtime::FunctionInstanceError::BadCode))?,
             store: store,
+            fuel_per_invocation,
+            fuel_added,
         }))
     }
 
     async fn init(&mut self, init_payload: Option<&str>, serialized_state: Option<&str>) -> Result<(), crate::base_runtime::FunctionInstanceError> {
+        self.start_invocation()?;
+        let res = self.invoke_init(init_payload, serialized_state).await;
+        self.store.data().limiter.check(res)
+    }
+
+    async fn cast(
+        &mut self,
+        src: &edgeless_api::function_instance::InstanceId,
+        port: &str,
+        msg: &[u8],
+    ) -> Result<(), crate::base_runtime::FunctionInstanceError> {
+        self.start_invocation()?;
+        let res = self.invoke_cast(src, port, msg).await;
+        self.store.data().limiter.check(res)
+    }
+
+    async fn call(
+        &mut self,
+        src: &edgeless_api::function_instance::InstanceId,
+        port: &str,
+        msg: &[u8],
+    ) -> Result<edgeless_dataplane::core::CallRet, crate::base_runtime::FunctionInstanceError> {
+        self.start_invocation()?;
+        let res = self.invoke_call(src, port, msg).await;
+        self.store.data().limiter.check(res)
+    }
+
+    async fn stop(&mut self) -> Result<(), crate::base_runtime::FunctionInstanceError> {
+        self.start_invocation()?;
+        let res = self.invoke_stop().await;
+        self.store.data().limiter.check(res)
+    }
+}
+
+impl WASMIFunctionInstance {
+    /// Refills the fuel, if limited, so that the next invocation has its whole execution time.
+    fn start_invocation(&mut self) -> Result<(), crate::base_runtime::FunctionInstanceError> {
+        if let Some(fuel) = self.fuel_per_invocation {
+            let remaining = self.fuel_added.saturating_sub(self.store.fuel_consumed().unwrap_or(0));
+            if remaining < fuel {
+                self.store
+                    .add_fuel(fuel - remaining)
+                    .map_err(|_| crate::base_runtime::FunctionInstanceError::InternalError)?;
+                self.fuel_added += fuel - remaining;
+            }
+        }
+        Ok(())
+    }
+
+    async fn invoke_init(
+        &mut self,
+        init_payload: Option<&str>,
+        serialized_state: Option<&str>,
+    ) -> Result<(), crate::base_runtime::FunctionInstanceError> {
         let (init_payload_ptr, init_payload_len) = match init_payload {
             Some(payload) => {
                 let len = payload.len();
@@ -167,7 +264,7 @@ impl crate::base_runtime::FunctionInstance for WASMIFunctionInstance {
                     &mut self.store,
                     (init_payload_ptr, init_payload_len, serialized_state_ptr, serialized_state_len),
                 )
-                .map_err(|_| crate::base_runtime::FunctionInstanceError::InternalError)?;
+                .map_err(|err| code_error(&mut self.store, err, crate::base_runtime::FunctionInstanceError::InternalError))?;
             Ok(())
         });
 
@@ -186,11 +283,11 @@ impl crate::base_runtime::FunctionInstance for WASMIFunctionInstance {
         ret
     }
 
-    async fn cast(
+    async fn invoke_cast(
         &mut self,
         src: &edgeless_api::function_instance::InstanceId,
         port: &str,
-        msg: &str,
+        msg: &[u8],
     ) -> Result<(), crate::base_runtime::FunctionInstanceError> {
         // Depending on the Function, we might employ a basic arena/bump allocator that we must reset at the end of a transaction.
         // This might be a noop if the function defines a working version of `edgeless_mem_free`.
@@ -217,8 +314,8 @@ impl crate::base_runtime::FunctionInstance for WASMIFunctionInstance {
         let port_ptr = helpers::copy_to_vm(&mut self.store.as_context_mut(), &self.memory, &self.edgeless_mem_alloc, port.as_bytes())
             .map_err(|_| crate::base_runtime::FunctionInstanceError::BadCode)?;
 
-        let payload_len = msg.as_bytes().len();
-        let payload_ptr = helpers::copy_to_vm(&mut self.store.as_context_mut(), &self.memory, &self.edgeless_mem_alloc, msg.as_bytes())
+        let payload_len = msg.len();
+        let payload_ptr = helpers::copy_to_vm(&mut self.store.as_context_mut(), &self.memory, &self.edgeless_mem_alloc, msg)
             .map_err(|_| crate::base_runtime::FunctionInstanceError::BadCode)?;
 
         let ret = tokio::task::block_in_place(|| {
@@ -227,7 +324,7 @@ impl crate::base_runtime::FunctionInstance for WASMIFunctionInstance {
                     &mut self.store,
                     (node_id_ptr, component_id_ptr, port_ptr, port_len as i32, payload_ptr, payload_len as i32),
                 )
-                .map_err(|_| crate::base_runtime::FunctionInstanceError::BadCode)?;
+                .map_err(|err| code_error(&mut self.store, err, crate::base_runtime::FunctionInstanceError::BadCode))?;
             Ok(())
         });
 
@@ -245,11 +342,11 @@ impl crate::base_runtime::FunctionInstance for WASMIFunctionInstance {
         ret
     }
 
-    async fn call(
+    async fn invoke_call(
         &mut self,
         src: &edgeless_api::function_instance::InstanceId,
         port: &str,
-        msg: &str,
+        msg: &[u8],
     ) -> Result<edgeless_dataplane::core::CallRet, crate::base_runtime::FunctionInstanceError> {
         self.edgeless_mem_clear
             .call(&mut self.store, ())
@@ -275,8 +372,8 @@ impl crate::base_runtime::FunctionInstance for WASMIFunctionInstance {
         let port_ptr = helpers::copy_to_vm(&mut self.store.as_context_mut(), &self.memory, &self.edgeless_mem_alloc, port.as_bytes())
             .map_err(|_| crate::base_runtime::FunctionInstanceError::BadCode)?;
 
-        let payload_len = msg.as_bytes().len();
-        let payload_ptr = helpers::copy_to_vm(&mut self.store.as_context_mut(), &self.memory, &self.edgeless_mem_alloc, msg.as_bytes())
+        let payload_len = msg.len();
+        let payload_ptr = helpers::copy_to_vm(&mut self.store.as_context_mut(), &self.memory, &self.edgeless_mem_alloc, msg)
             .map_err(|_| crate::base_runtime::FunctionInstanceError::BadCode)?;
 
         let out_ptr_ptr = self
@@ -304,7 +401,7 @@ impl crate::base_runtime::FunctionInstance for WASMIFunctionInstance {
                         out_len_ptr,
                     ),
                 )
-                .map_err(|_| crate::base_runtime::FunctionInstanceError::BadCode)
+                .map_err(|err| code_error(&mut self.store, err, crate::base_runtime::FunctionInstanceError::BadCode))
         })?;
 
         let ret = match callret_type {
@@ -324,9 +421,7 @@ impl crate::base_runtime::FunctionInstance for WASMIFunctionInstance {
 
                 // load the atual output param
                 let out_raw = self.memory.data_mut(&mut self.store)[out_ptr as usize..(out_ptr as usize) + out_len as usize].to_vec();
-                // TODO(raphaelhetzel) This unwrap can be removed after we migrate the dataplane to use string slices.
-                let out = std::string::String::from_utf8(out_raw).unwrap();
-                Ok(edgeless_dataplane::core::CallRet::Reply(out))
+                Ok(edgeless_dataplane::core::CallRet::Reply(out_raw))
             }
             _ => Ok(edgeless_dataplane::core::CallRet::Err),
         };
@@ -355,14 +450,48 @@ impl crate::base_runtime::FunctionInstance for WASMIFunctionInstance {
         ret
     }
 
-    async fn stop(&mut self) -> Result<(), crate::base_runtime::FunctionInstanceError> {
+    async fn invoke_stop(&mut self) -> Result<(), crate::base_runtime::FunctionInstanceError> {
         self.edgeless_mem_clear
             .call(&mut self.store, ())
             .map_err(|_| crate::base_runtime::FunctionInstanceError::BadCode)?;
         tokio::task::block_in_place(|| {
             self.edgefunctione_handle_stop
                 .call(&mut self.store, ())
-                .map_err(|_| crate::base_runtime::FunctionInstanceError::BadCode)
+                .map_err(|err| code_error(&mut self.store, err, crate::base_runtime::FunctionInstanceError::BadCode))
         })
     }
 }
+
+/// Error of the WASM code, remembering if it ran out of the fuel of the invocation.
+fn code_error(
+    store: &mut wasmi::Store<guest_api_binding::GuestAPI>,
+    err: impl Into<wasmi::Error>,
+    error: crate::base_runtime::FunctionInstanceError,
+) -> crate::base_runtime::FunctionInstanceError {
+    if matches!(err.into(), wasmi::Error::Trap(trap) if matches!(trap.trap_code(), Some(wasmi::core::TrapCode::OutOfFuel))) {
+        store.data_mut().limiter.exceeded = true;
+    }
+    error
+}
+
+impl wasmi::ResourceLimiter for crate::base_runtime::limits::WasmLimiter {
+    fn memory_growing(&mut self, _current: usize, desired: usize, _maximum: Option<usize>) -> Result<bool, wasmi::errors::MemoryError> {
+        Ok(self.allow_memory(desired))
+    }
+
+    fn table_growing(&mut self, _current: u32, desired: u32, _maximum: Option<u32>) -> Result<bool, wasmi::errors::TableError> {
+        Ok(self.allow_table(desired as u64))
+    }
+
+    fn instances(&self) -> usize {
+        self.max_instances()
+    }
+
+    fn tables(&self) -> usize {
+        self.max_tables()
+    }
+
+    fn memories(&self) -> usize {
+        self.max_memories()
+    }
+}
@@ -0,0 +1,43 @@
+;; SPDX-FileCopyrightText: © 2024 Technical University of Munich, Chair of Connected Mobility
+;; SPDX-License-Identifier: MIT
+
+;; Source of limits_test.wasm: a cast on a port with a 4-byte name, e.g.,
+;; "loop", never returns, while any other cast grows the memory by 64 MiB
+;; and traps if it is denied.
+(module
+  (memory (export "memory") 1)
+  (global $next (mut i32) (i32.const 1024))
+
+  (func (export "edgeless_mem_alloc") (param $size i32) (result i32) (local $ptr i32)
+    global.get $next
+    local.set $ptr
+    global.get $next
+    local.get $size
+    i32.add
+    global.set $next
+    local.get $ptr)
+  (func (export "edgeless_mem_free") (param i32 i32))
+  (func (export "edgeless_mem_clear")
+    i32.const 1024
+    global.set $next)
+
+  (func (export "handle_init_asm") (param i32 i32 i32 i32))
+  (func (export "handle_stop_asm"))
+  (func (export "handle_call_asm") (param i32 i32 i32 i32 i32 i32 i32 i32) (result i32)
+    i32.const 0)
+  (func (export "handle_cast_asm") (param $node_id_ptr i32) (param $component_id_ptr i32) (param $port_ptr i32) (param $port_len i32) (param $payload_ptr i32) (param $payload_len i32)
+    local.get $port_len
+    i32.const 4
+    i32.eq
+    if
+      loop
+        br 0
+      end
+    end
+    i32.const 1024
+    memory.grow
+    i32.const -1
+    i32.eq
+    if
+      unreachable
+    end))
@@ -68,7 +68,20 @@ fn mock_runtime() -> std::sync::Arc<tokio::sync::Mutex<Box<dyn crate::base_runti
     std::sync::Arc::new(tokio::sync::Mutex::new(Box::new(super::runtime::WasmiRuntime::new())))
 }
 
-#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+/// Context of the events sent by the tests, with the valid and sampled span
+/// context expected by the function instance runner.
+fn test_context() -> opentelemetry::Context {
+    use opentelemetry::trace::TraceContextExt;
+    opentelemetry::Context::new().with_remote_span_context(opentelemetry::trace::SpanContext::new(
+        opentelemetry::trace::TraceId::from_bytes(1u128.to_be_bytes()),
+        opentelemetry::trace::SpanId::from_bytes(1u64.to_be_bytes()),
+        opentelemetry::trace::TraceFlags::SAMPLED,
+        true,
+        opentelemetry::trace::TraceState::default(),
+    ))
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn basic_lifecycle() {
     let node_id = uuid::Uuid::new_v4();
     let instance_id = edgeless_api::function_instance::InstanceId::new(node_id);
@@ -90,7 +103,7 @@ async fn basic_lifecycle() {
     tokio::spawn(async move { rt_task.run().await });
 
     let spawn_req = edgeless_api::function_instance::SpawnFunctionRequest {
-        instance_id: Some(instance_id.clone()),
+        instance_id,
         code: edgeless_api::function_instance::FunctionClassSpecification {
             function_class_id: "EXAMPLE_1".to_string(),
             function_class_type: "RUST_WASM".to_string(),
@@ -100,10 +113,13 @@ async fn basic_lifecycle() {
             function_class_inputs: std::collections::HashMap::new(),
             function_class_inner_structure: std::collections::HashMap::new(),
         },
+        input_mapping: std::collections::HashMap::new(),
+        output_mapping: std::collections::HashMap::new(),
         annotations: std::collections::HashMap::new(),
         state_specification: edgeless_api::function_instance::StateSpecification {
             state_id: instance_id.function_id.clone(),
             state_policy: edgeless_api::function_instance::StatePolicy::Transient,
+            initial_state: None,
         },
     };
 
@@ -227,7 +243,7 @@ async fn messaging_test_setup() -> (
     tokio::spawn(async move { rt_task.run().await });
 
     let spawn_req = edgeless_api::function_instance::SpawnFunctionRequest {
-        instance_id: Some(instance_id.clone()),
+        instance_id,
         code: edgeless_api::function_instance::FunctionClassSpecification {
             function_class_id: "EXAMPLE_1".to_string(),
             function_class_type: "RUST_WASM".to_string(),
@@ -238,10 +254,13 @@ async fn messaging_test_setup() -> (
             function_class_inner_structure: std::collections::HashMap::new(),
             // outputs: vec!["test".to_string()],
         },
+        input_mapping: std::collections::HashMap::new(),
+        output_mapping: std::collections::HashMap::new(),
         annotations: std::collections::HashMap::new(),
         state_specification: edgeless_api::function_instance::StateSpecification {
             state_id: instance_id.function_id.clone(),
             state_policy: edgeless_api::function_instance::StatePolicy::Transient,
+            initial_state: None,
         },
     };
 
@@ -252,11 +271,12 @@ async fn messaging_test_setup() -> (
 
     let res = client
         .patch(PatchRequest {
-            function_id: instance_id.function_id.clone(),
+            function_id: instance_id,
             output_mapping: std::collections::HashMap::from([(
-                "test".to_string(),
+                edgeless_api::function_instance::PortId("test".to_string()),
                 edgeless_api::common::Output::Single(next_fid.clone(), edgeless_api::function_instance::PortId("test".to_string())),
             )]),
+            input_mapping: std::collections::HashMap::new(),
         })
         .await;
 
@@ -281,7 +301,7 @@ async fn messaging_test_setup() -> (
 
 // test input (host-> function): cast
 // We assume this works after this test and trigger the different outputs using casts.
-#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn messaging_cast_raw_input() {
     let (instance_id, mut test_peer_handle, _test_peer_fid, _next_handle, _next_fid, telemetry_mock_receiver) = messaging_test_setup().await;
     test_peer_handle
@@ -289,6 +309,7 @@ async fn messaging_cast_raw_input() {
             instance_id.clone(),
             edgeless_api::function_instance::PortId("test".to_string()),
             "some_message".to_string(),
+            test_context(),
         )
         .await;
     tokio::time::sleep(Duration::from_millis(50)).await;
@@ -310,6 +331,7 @@ async fn messaging_cast_raw_output() {
             instance_id.clone(),
             edgeless_api::function_instance::PortId("test".to_string()),
             "test_cast_raw_output".to_string(),
+            test_context(),
         )
         .await;
     tokio::time::sleep(Duration::from_millis(50)).await;
@@ -326,14 +348,11 @@ async fn messaging_cast_raw_output() {
     println!("Wait");
     let test_message = test_peer_handle.receive_next().await;
     assert_eq!(test_message.source_id, instance_id);
-    assert_eq!(
-        test_message.message,
-        edgeless_dataplane::core::Message::Cast("cast_raw_output".to_string())
-    );
+    assert_eq!(test_message.message, edgeless_dataplane::core::Message::Cast(b"cast_raw_output".to_vec()));
 }
 
 // test output: call
-#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn messaging_call_raw_output() {
     let (instance_id, mut test_peer_handle, _test_peer_fid, _next_handle, _next_fid, telemetry_mock_receiver) = messaging_test_setup().await;
 
@@ -342,6 +361,7 @@ async fn messaging_call_raw_output() {
             instance_id.clone(),
             edgeless_api::function_instance::PortId("test".to_string()),
             "test_call_raw_output".to_string(),
+            test_context(),
         )
         .await;
     tokio::time::sleep(Duration::from_millis(50)).await;
@@ -351,10 +371,7 @@ async fn messaging_call_raw_output() {
 
     let test_message = test_peer_handle.receive_next().await;
     assert_eq!(test_message.source_id, instance_id);
-    assert_eq!(
-        test_message.message,
-        edgeless_dataplane::core::Message::Call("call_raw_output".to_string())
-    );
+    assert_eq!(test_message.message, edgeless_dataplane::core::Message::Call(b"call_raw_output".to_vec()));
 
     test_peer_handle
         .reply(test_message.source_id, test_message.channel_id, CallRet::NoReply)
@@ -372,7 +389,7 @@ async fn messaging_call_raw_output() {
 }
 
 // test output: delayed_cast
-#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn messaging_delayed_cast_output() {
     let (instance_id, mut test_peer_handle, _test_peer_fid, mut next_handle, _next_fid, telemetry_mock_receiver) = messaging_test_setup().await;
 
@@ -381,6 +398,7 @@ async fn messaging_delayed_cast_output() {
             instance_id.clone(),
             edgeless_api::function_instance::PortId("test".to_string()),
             "test_delayed_cast_output".to_string(),
+            test_context(),
         )
         .await;
     let start = tokio::time::Instant::now();
@@ -391,7 +409,7 @@ async fn messaging_delayed_cast_output() {
     assert_eq!(test_message.source_id, instance_id);
     assert_eq!(
         test_message.message,
-        edgeless_dataplane::core::Message::Cast("delayed_cast_output".to_string())
+        edgeless_dataplane::core::Message::Cast(b"delayed_cast_output".to_vec())
     );
 
     tokio::time::sleep(Duration::from_millis(50)).await;
@@ -407,7 +425,7 @@ async fn messaging_delayed_cast_output() {
 }
 
 // test output: cast
-#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn messaging_cast_output() {
     let (instance_id, mut test_peer_handle, _test_peer_fid, mut next_handle, _next_fid, telemetry_mock_receiver) = messaging_test_setup().await;
 
@@ -416,6 +434,7 @@ async fn messaging_cast_output() {
             instance_id.clone(),
             edgeless_api::function_instance::PortId("test".to_string()),
             "test_cast_output".to_string(),
+            test_context(),
         )
         .await;
     tokio::time::sleep(Duration::from_millis(50)).await;
@@ -431,11 +450,11 @@ async fn messaging_cast_output() {
 
     let test_message = next_handle.receive_next().await;
     assert_eq!(test_message.source_id, instance_id);
-    assert_eq!(test_message.message, edgeless_dataplane::core::Message::Cast("cast_output".to_string()));
+    assert_eq!(test_message.message, edgeless_dataplane::core::Message::Cast(b"cast_output".to_vec()));
 }
 
 // test output: call
-#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn messaging_call_output() {
     let (instance_id, mut test_peer_handle, _test_peer_fid, mut next_handle, _next_fid, telemetry_mock_receiver) = messaging_test_setup().await;
 
@@ -444,6 +463,7 @@ async fn messaging_call_output() {
             instance_id.clone(),
             edgeless_api::function_instance::PortId("test".to_string()),
             "test_call_output".to_string(),
+            test_context(),
         )
         .await;
     tokio::time::sleep(Duration::from_millis(50)).await;
@@ -453,7 +473,7 @@ async fn messaging_call_output() {
 
     let test_message = next_handle.receive_next().await;
     assert_eq!(test_message.source_id, instance_id);
-    assert_eq!(test_message.message, edgeless_dataplane::core::Message::Call("call_output".to_string()));
+    assert_eq!(test_message.message, edgeless_dataplane::core::Message::Call(b"call_output".to_vec()));
 
     next_handle.reply(test_message.source_id, test_message.channel_id, CallRet::NoReply).await;
     tokio::time::sleep(Duration::from_millis(50)).await;
@@ -469,7 +489,7 @@ async fn messaging_call_output() {
 }
 
 // test call-interaction: Noreply
-#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn messaging_call_raw_input_noreply() {
     let (instance_id, mut test_peer_handle, _test_peer_fid, _next_handle, _next_fid, telemetry_mock_receiver) = messaging_test_setup().await;
 
@@ -478,6 +498,7 @@ async fn messaging_call_raw_input_noreply() {
             instance_id.clone(),
             edgeless_api::function_instance::PortId("test".to_string()),
             "some_cast".to_string(),
+            test_context(),
         )
         .await;
     assert_eq!(ret, CallRet::NoReply);
@@ -493,7 +514,7 @@ async fn messaging_call_raw_input_noreply() {
 }
 
 // test call-interaction: Reply
-#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn messaging_call_raw_input_reply() {
     let (instance_id, mut test_peer_handle, _test_peer_fid, _next_handle, _next_fid, telemetry_mock_receiver) = messaging_test_setup().await;
 
@@ -502,9 +523,10 @@ async fn messaging_call_raw_input_reply() {
             instance_id.clone(),
             edgeless_api::function_instance::PortId("test".to_string()),
             "test_ret".to_string(),
+            test_context(),
         )
         .await;
-    assert_eq!(ret, CallRet::Reply("test_reply".to_string()));
+    assert_eq!(ret, CallRet::Reply(b"test_reply".to_vec()));
 
     let telemetry_event = telemetry_mock_receiver.try_recv();
     assert!(telemetry_event.is_ok());
@@ -517,7 +539,7 @@ async fn messaging_call_raw_input_reply() {
 }
 
 // test call-interaction: Error
-#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn messaging_call_raw_input_err() {
     let (instance_id, mut test_peer_handle, _test_peer_fid, _next_handle, _next_fid, telemetry_mock_receiver) = messaging_test_setup().await;
 
@@ -526,6 +548,7 @@ async fn messaging_call_raw_input_err() {
             instance_id.clone(),
             edgeless_api::function_instance::PortId("test".to_string()),
             "test_err".to_string(),
+            test_context(),
         )
         .await;
     assert_eq!(ret, CallRet::Err);
@@ -540,7 +563,7 @@ async fn messaging_call_raw_input_err() {
     assert!(telemetry_mock_receiver.try_recv().is_err());
 }
 
-#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn state_management() {
     let node_id = uuid::Uuid::new_v4();
     let instance_id = edgeless_api::function_instance::InstanceId::new(node_id);
@@ -576,7 +599,7 @@ async fn state_management() {
     tokio::spawn(async move { rt_task.run().await });
 
     let mut spawn_req = edgeless_api::function_instance::SpawnFunctionRequest {
-        instance_id: Some(instance_id.clone()),
+        instance_id,
         code: edgeless_api::function_instance::FunctionClassSpecification {
             function_class_id: "EXAMPLE_1".to_string(),
             function_class_type: "RUST_WASM".to_string(),
@@ -586,10 +609,13 @@ async fn state_management() {
             function_class_inputs: std::collections::HashMap::new(),
             function_class_inner_structure: std::collections::HashMap::new(),
         },
+        input_mapping: std::collections::HashMap::new(),
+        output_mapping: std::collections::HashMap::new(),
         annotations: std::collections::HashMap::new(),
         state_specification: edgeless_api::function_instance::StateSpecification {
             state_id: instance_id.function_id.clone(),
             state_policy: edgeless_api::function_instance::StatePolicy::Transient,
+            initial_state: None,
         },
     };
 
@@ -621,6 +647,7 @@ async fn state_management() {
             instance_id.clone(),
             edgeless_api::function_instance::PortId("test".to_string()),
             "test_cast_raw_output".to_string(),
+            test_context(),
         )
         .await;
     tokio::time::sleep(Duration::from_millis(100)).await;
@@ -649,7 +676,7 @@ async fn state_management() {
         .insert(instance_id.function_id.clone(), "existing_state".to_string());
 
     // TODO(raphaelhetzel) InstanceId reuse leads to problems that need to be fixed.
-    spawn_req.instance_id = Some(fid2);
+    spawn_req.instance_id = fid2;
 
     let res2 = client.start(spawn_req).await;
     assert!(res2.is_ok());
@@ -671,3 +698,125 @@ async fn state_management() {
     assert!(telemetry_mock_receiver.try_recv().is_ok());
     assert!(telemetry_mock_receiver.try_recv().is_err());
 }
+
+/// Starts an instance of `limits_test.wasm` with the given annotations and
+/// casts an event to the given port, which does not return or grows the memory.
+/// The instance runs as long as the returned client is not dropped.
+async fn limits_test_cast(
+    annotations: std::collections::HashMap<String, String>,
+    port: &str,
+) -> (
+    crate::base_runtime::runtime::RuntimeClient,
+    std::sync::mpsc::Receiver<(
+        edgeless_telemetry::telemetry_events::TelemetryEvent,
+        std::collections::BTreeMap<String, String>,
+    )>,
+) {
+    let node_id = uuid::Uuid::new_v4();
+    let instance_id = edgeless_api::function_instance::InstanceId::new(node_id);
+
+    let state_manager = Box::new(crate::state_management::StateManager::new().await);
+    let mut dataplane_provider = edgeless_dataplane::handle::DataplaneProvider::new(node_id, "http://127.0.0.1:7002".to_string(), None).await;
+    let mut test_peer_handle = dataplane_provider
+        .get_handle_for(edgeless_api::function_instance::InstanceId::new(node_id))
+        .await;
+
+    let (telemetry_mock_sender, telemetry_mock_receiver) = std::sync::mpsc::channel::<(
+        edgeless_telemetry::telemetry_events::TelemetryEvent,
+        std::collections::BTreeMap<String, String>,
+    )>();
+    let telemetry_handle = Box::new(MockTelemetryHandle {
+        sender: telemetry_mock_sender,
+    });
+
+    let (mut client, mut rt_task) =
+        crate::base_runtime::runtime::create::<super::WASMIFunctionInstance>(dataplane_provider, state_manager, telemetry_handle, mock_runtime());
+
+    tokio::spawn(async move { rt_task.run().await });
+
+    let spawn_req = edgeless_api::function_instance::SpawnFunctionRequest {
+        instance_id,
+        code: edgeless_api::function_instance::FunctionClassSpecification {
+            function_class_id: "EXAMPLE_1".to_string(),
+            function_class_type: "RUST_WASM".to_string(),
+            function_class_version: "0.1".to_string(),
+            function_class_code: include_bytes!("fixtures/limits_test.wasm").to_vec(),
+            function_class_outputs: std::collections::HashMap::new(),
+            function_class_inputs: std::collections::HashMap::new(),
+            function_class_inner_structure: std::collections::HashMap::new(),
+        },
+        input_mapping: std::collections::HashMap::new(),
+        output_mapping: std::collections::HashMap::new(),
+        annotations,
+        state_specification: edgeless_api::function_instance::StateSpecification {
+            state_id: instance_id.function_id,
+            state_policy: edgeless_api::function_instance::StatePolicy::Transient,
+            initial_state: None,
+        },
+    };
+    assert!(client.start(spawn_req).await.is_ok());
+
+    // Wait for the initialization of the instance, before which the events are lost.
+    loop {
+        match telemetry_mock_receiver.recv_timeout(Duration::from_secs(5)) {
+            Ok((TelemetryEvent::FunctionInit(_), _tags)) => break,
+            Ok(_) => continue,
+            Err(err) => panic!("the instance was not initialized: {}", err),
+        }
+    }
+
+    test_peer_handle
+        .send(
+            instance_id,
+            edgeless_api::function_instance::PortId(port.to_string()),
+            "limits_test".to_string(),
+            test_context(),
+        )
+        .await;
+
+    (client, telemetry_mock_receiver)
+}
+
+/// Waits for the exit of the instance, returning its status.
+fn exit_status(
+    telemetry_mock_receiver: &std::sync::mpsc::Receiver<(
+        edgeless_telemetry::telemetry_events::TelemetryEvent,
+        std::collections::BTreeMap<String, String>,
+    )>,
+) -> edgeless_telemetry::telemetry_events::FunctionExitStatus {
+    loop {
+        match telemetry_mock_receiver.recv_timeout(Duration::from_secs(5)) {
+            Ok((TelemetryEvent::FunctionExit(status), _tags)) => return status,
+            Ok(_) => continue,
+            Err(err) => panic!("the instance did not exit: {}", err),
+        }
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn execution_time_limit() {
+    let (_client, telemetry_mock_receiver) = limits_test_cast(
+        std::collections::HashMap::from([(crate::base_runtime::limits::MAX_EXECUTION_TIME_ANNOTATION.to_string(), "100".to_string())]),
+        "loop",
+    )
+    .await;
+
+    assert_eq!(
+        exit_status(&telemetry_mock_receiver),
+        edgeless_telemetry::telemetry_events::FunctionExitStatus::ResourceLimitExceeded
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn memory_limit() {
+    let (_client, telemetry_mock_receiver) = limits_test_cast(
+        std::collections::HashMap::from([(crate::base_runtime::limits::MAX_MEMORY_PAGES_ANNOTATION.to_string(), "16".to_string())]),
+        "grow_memory",
+    )
+    .await;
+
+    assert_eq!(
+        exit_status(&telemetry_mock_receiver),
+        edgeless_telemetry::telemetry_events::FunctionExitStatus::ResourceLimitExceeded
+    );
+}
@@ -30,7 +30,10 @@ mod tests {
 
         let (task, handle) = futures::future::abortable(edgeless_con::edgeless_con_main(edgeless_con::EdgelessConSettings {
             controller_url: controller_url.clone(),
+            domain_id: String::new(),
             orchestrators: vec![],
+            orchestration_strategy: edgeless_con::OrchestrationStrategy::default(),
+            persistence: None,
         }));
         tokio::spawn(task);
         handles.push(handle);
@@ -49,11 +52,20 @@ mod tests {
                         invocation_url_announced: "".to_string(),
                         invocation_url_coap: None,
                         invocation_url_announced_coap: None,
+                        invocation_url_quic: None,
+                        invocation_url_announced_quic: None,
                         metrics_url: format!("http://{}:{}", address, next_port()),
                         controller_url: controller_url.to_string(),
                     },
-                    wasm_runtime: Some(edgeless_node::EdgelessNodeWasmRuntimeSettings { enabled: true }),
+                    wasm_runtime: Some(edgeless_node::EdgelessNodeWasmRuntimeSettings {
+                        enabled: true,
+                        module_cache_dir: String::new(),
+                        pooling_instances: 0,
+                        wasi_dirs_root: String::new(),
+                        limits: edgeless_node::base_runtime::limits::FunctionLimits::default(),
+                    }),
                     container_runtime: None,
+                    native_runtime: None,
                     resources: Some(edgeless_node::EdgelessNodeResourceSettings {
                         http_ingress_url: None,
                         http_ingress_provider: None,
@@ -65,6 +77,7 @@ mod tests {
                         ollama_provider: None,
                     }),
                     user_node_capabilities: None,
+                    dataplane: None,
                 },
                 _ => edgeless_node::EdgelessNodeSettings::new_without_resources(&controller_url, address, next_port(), next_port(), next_port()),
             }));
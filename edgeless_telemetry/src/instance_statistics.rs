@@ -0,0 +1,92 @@
+// SPDX-FileCopyrightText: © 2024 Technical University of Munich, Chair of Connected Mobility
+// SPDX-License-Identifier: MIT
+
+/// Shared view on the runtime statistics collected for the function
+/// instances running on this node, keyed by function identifier.
+#[derive(Clone, Default)]
+pub struct InstanceStatisticsHandle {
+    statistics: std::sync::Arc<std::sync::Mutex<std::collections::HashMap<uuid::Uuid, edgeless_api::node_management::InstanceStatistics>>>,
+}
+
+impl InstanceStatisticsHandle {
+    /// Current cumulative statistics of all the function instances.
+    pub fn snapshot(&self) -> Vec<edgeless_api::node_management::InstanceStatistics> {
+        match self.statistics.lock() {
+            Ok(statistics) => statistics.values().cloned().collect(),
+            Err(_) => vec![],
+        }
+    }
+}
+
+/// Telemetry event processor that accumulates invocation counts and
+/// durations of function instances, identified by the FUNCTION_ID tag.
+/// The state of the input queue is taken from the QUEUE_DEPTH and
+/// QUEUE_DROPPED tags of the last completed invocation, unless it is
+/// sampled more recently by the node.
+/// An instance is tracked from its initialization until it exits, so the
+/// statistics also tell which instances are running on the node.
+pub struct InstanceStatisticsCollector {
+    handle: InstanceStatisticsHandle,
+}
+
+impl InstanceStatisticsCollector {
+    pub fn new(handle: InstanceStatisticsHandle) -> Self {
+        Self { handle }
+    }
+}
+
+impl crate::telemetry_events::EventProcessor for InstanceStatisticsCollector {
+    fn handle(
+        &mut self,
+        event: &crate::telemetry_events::TelemetryEvent,
+        event_tags: &std::collections::BTreeMap<String, String>,
+    ) -> crate::telemetry_events::TelemetryProcessingResult {
+        let function_id = match event_tags.get("FUNCTION_ID").and_then(|id| uuid::Uuid::parse_str(id).ok()) {
+            Some(function_id) => function_id,
+            None => return crate::telemetry_events::TelemetryProcessingResult::PASSED,
+        };
+        let mut statistics = match self.handle.statistics.lock() {
+            Ok(statistics) => statistics,
+            Err(_) => return crate::telemetry_events::TelemetryProcessingResult::PASSED,
+        };
+
+        let new_entry = || edgeless_api::node_management::InstanceStatistics {
+            function_id,
+            invocations: 0,
+            total_duration_us: 0,
+            invocations_by_port: std::collections::HashMap::new(),
+            queue_depth: 0,
+            dropped_events: 0,
+        };
+
+        match event {
+            crate::telemetry_events::TelemetryEvent::FunctionInit(_) => {
+                statistics.entry(function_id).or_insert_with(new_entry);
+                crate::telemetry_events::TelemetryProcessingResult::PROCESSED
+            }
+            crate::telemetry_events::TelemetryEvent::FunctionInvocationCompleted(duration) => {
+                let entry = statistics.entry(function_id).or_insert_with(new_entry);
+                entry.invocations += 1;
+                entry.total_duration_us += duration.as_micros() as u64;
+                if let Some(port) = event_tags.get("PORT") {
+                    *entry
+                        .invocations_by_port
+                        .entry(edgeless_api::function_instance::PortId(port.clone()))
+                        .or_default() += 1;
+                }
+                if let Some(queue_depth) = event_tags.get("QUEUE_DEPTH").and_then(|val| val.parse().ok()) {
+                    entry.queue_depth = queue_depth;
+                }
+                if let Some(dropped_events) = event_tags.get("QUEUE_DROPPED").and_then(|val| val.parse().ok()) {
+                    entry.dropped_events = dropped_events;
+                }
+                crate::telemetry_events::TelemetryProcessingResult::PROCESSED
+            }
+            crate::telemetry_events::TelemetryEvent::FunctionExit(_) => {
+                statistics.remove(&function_id);
+                crate::telemetry_events::TelemetryProcessingResult::PROCESSED
+            }
+            _ => crate::telemetry_events::TelemetryProcessingResult::PASSED,
+        }
+    }
+}
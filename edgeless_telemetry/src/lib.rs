@@ -1,4 +1,5 @@
 // SPDX-FileCopyrightText: © 2023 Technical University of Munich, Chair of Connected Mobility
 // SPDX-License-Identifier: MIT
+pub mod instance_statistics;
 // pub mod prometheus_target;
 pub mod telemetry_events;
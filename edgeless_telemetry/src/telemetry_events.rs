@@ -36,6 +36,7 @@ pub enum FunctionExitStatus {
     Ok,
     InternalError,
     CodeError,
+    ResourceLimitExceeded,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -132,6 +133,7 @@ impl TelemetryProcessorInner {
 
 pub struct TelemetryProcessor {
     sender: tokio::sync::mpsc::UnboundedSender<TelemetryProcessorInput>,
+    instance_statistics: crate::instance_statistics::InstanceStatisticsHandle,
 }
 
 impl TelemetryProcessor {
@@ -139,10 +141,12 @@ impl TelemetryProcessor {
         match edgeless_api::util::parse_http_host(&metrics_url) {
             Ok((_, ip, port)) => {
                 let (sender, receiver) = tokio::sync::mpsc::unbounded_channel::<TelemetryProcessorInput>();
+                let instance_statistics = crate::instance_statistics::InstanceStatisticsHandle::default();
 
                 let inner = TelemetryProcessorInner {
                     processing_chain: vec![
                         // Box::new(crate::prometheus_target::PrometheusEventTarget::new(&format!("{}:{}", &ip, port)).await),
+                        Box::new(crate::instance_statistics::InstanceStatisticsCollector::new(instance_statistics.clone())),
                         Box::new(EventLogger {}),
                     ],
                     receiver,
@@ -153,7 +157,7 @@ impl TelemetryProcessor {
                     inner.run().await;
                 });
 
-                Ok(Self { sender })
+                Ok(Self { sender, instance_statistics })
             }
             Err(err) => Err(err),
         }
@@ -165,4 +169,9 @@ impl TelemetryProcessor {
             sender: self.sender.clone(),
         }
     }
+
+    /// Runtime statistics of the function instances, as collected from the telemetry events.
+    pub fn instance_statistics(&self) -> crate::instance_statistics::InstanceStatisticsHandle {
+        self.instance_statistics.clone()
+    }
 }
@@ -0,0 +1,49 @@
+// SPDX-FileCopyrightText: © 2024 Technical University of Munich, Chair of Connected Mobility
+// SPDX-License-Identifier: MIT
+
+// Like the noop function, but as a WASM component that only depends on the
+// WIT definition of the guest API, not on the edgeless_function crate.
+wit_bindgen::generate!({
+    path: "../../edgeless_function/wit",
+    world: "edgeless-function",
+});
+
+use edgeless::function::host;
+use edgeless::function::types::LogLevel;
+
+struct NoopComponent;
+
+impl Guest for NoopComponent {
+    fn handle_cast(src: InstanceId, port: String, payload: Vec<u8>) {
+        host::telemetry_log(
+            LogLevel::Info,
+            "noop_component",
+            &format!(
+                "Noop casted, node {}, function {}, port {}, MSG: {:?}",
+                src.node_id, src.component_id, port, payload
+            ),
+        );
+    }
+
+    fn handle_call(src: InstanceId, port: String, payload: Vec<u8>) -> CallRet {
+        host::telemetry_log(
+            LogLevel::Info,
+            "noop_component",
+            &format!(
+                "Noop called, node {}, function {}, port {}, MSG: {:?}",
+                src.node_id, src.component_id, port, payload
+            ),
+        );
+        CallRet::NoReply
+    }
+
+    fn handle_init(payload: Option<Vec<u8>>, _serialized_state: Option<String>) {
+        host::telemetry_log(LogLevel::Info, "noop_component", &format!("Noop initialized, payload: {:?}", payload));
+    }
+
+    fn handle_stop() {
+        host::telemetry_log(LogLevel::Info, "noop_component", "Noop stopped");
+    }
+}
+
+export!(NoopComponent);